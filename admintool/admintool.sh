@@ -10,6 +10,7 @@ display_help()
     echo "    default value is 'admin'"
     echo
     echo "-l ip_list     list all the node's IP and port"
+    echo "    IPv6 addresses are written as [::1]:4000"
     echo "    default value is '127.0.0.1:4000,127.0.0.1:4001,127.0.0.1:4002,127.0.0.1:4003'"
     echo
     echo "-n consensus_name  name of consensus algorithm"
@@ -86,9 +87,9 @@ if [ ! -n "$IP_LIST" ]; then
     IP_LIST="127.0.0.1:4000,127.0.0.1:4001,127.0.0.1:4002,127.0.0.1:4003"
 fi
 
-#calc size of nodes
-TMP=${IP_LIST//[^\:]}
-SIZE=${#TMP}
+#calc size of nodes, count the commas as IPv6 addresses contain colons
+TMP=${IP_LIST//[^,]}
+SIZE=$((${#TMP} + 1))
 
 if [ ! -n "$CONSENSUS_NAME" ]; then
     CONSENSUS_NAME="tendermint"
@@ -137,7 +138,7 @@ for ((ID=0;ID<$SIZE;ID++))
 do
     mkdir -p $DATA_PATH/node$ID
     echo "Start generating private Key for Node" $ID "!"
-    python create_keys_addr.py $DATA_PATH $ID 
+    $DATA_PATH/bin/cita-key generate -o $DATA_PATH/node$ID/privkey -a $DATA_PATH/authorities
     echo "[PrivateKey Path] : " $DATA_PATH/node$ID
     echo "End generating private Key for Node" $ID "!"
     echo "Start creating Network Node" $ID "Configuration!"
@@ -147,7 +148,7 @@ do
 done
 echo "Step 2: ********************************************************"
 
-python create_genesis.py --authorities "$DATA_PATH/authorities"
+python create_genesis.py --authorities "$DATA_PATH/authorities" --consensus "$CONSENSUS_NAME"
 for ((ID=0;ID<$SIZE;ID++))
 do
     echo "Start creating Node " $ID " Configuration!"
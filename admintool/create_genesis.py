@@ -48,6 +48,8 @@ def main():
     parser = argparse.ArgumentParser()
     parser.add_argument(
         "--authorities", help="Authorities nodes list file.")
+    parser.add_argument(
+        "--consensus", default="tendermint", help="Consensus of the chain.")
 
     args = parser.parse_args()
     auth_path = os.path.join(args.authorities)
@@ -60,6 +62,8 @@ def main():
     timestamp = int(time.time())
     data["prevhash"] = "0x0000000000000000000000000000000000000000000000000000000000000000"
     data["timestamp"] = timestamp
+    data["authorities"] = authorities
+    data["consensus"] = args.consensus
 
     print "authorities", authorities
     #alloc = init_contracts(authorities)
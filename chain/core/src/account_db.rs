@@ -22,6 +22,10 @@ use util::*;
 
 static NULL_RLP_STATIC: [u8; 1] = [0x80; 1];
 
+// the code is kept as in the account db of an address hash no account has,
+// so that the accounts with the same code share it.
+const CODE_ADDRESS_HASH: H256 = H256([0xff; 32]);
+
 // combines a key with an address hash to ensure uniqueness.
 // leaves the first 96 bits untouched in order to support partial key lookup.
 #[inline]
@@ -70,6 +74,71 @@ impl Factory {
             Factory::Plain => Box::new(WrappingMut(db)),
         }
     }
+
+    /// Create a read-only db of the code of the account.
+    /// The code is looked up in the shared code db, then in the account db where it was kept before.
+    pub fn readonly_code<'db>(&self, db: &'db HashDB, address_hash: H256) -> Box<HashDB + 'db> {
+        match *self {
+            Factory::Mangled => Box::new(CodeDB {
+                                             db: db,
+                                             address_hash: address_hash,
+                                         }),
+            Factory::Plain => Box::new(Wrapping(db)),
+        }
+    }
+
+    /// Create a mutable db of the code shared by all the accounts.
+    pub fn create_code<'db>(&self, db: &'db mut HashDB) -> Box<HashDB + 'db> {
+        self.create(db, CODE_ADDRESS_HASH)
+    }
+
+    /// Key of the shared code in the backing db.
+    pub fn code_key(&self, code_hash: &H256) -> H256 {
+        match *self {
+            Factory::Mangled => combine_key(&CODE_ADDRESS_HASH, code_hash),
+            Factory::Plain => *code_hash,
+        }
+    }
+
+    /// Whether the code of the account is kept in its own account db, so it is not shared.
+    pub fn has_own_code(&self, db: &HashDB, address_hash: H256, code_hash: &H256) -> bool {
+        match *self {
+            Factory::Mangled => db.contains(&combine_key(&address_hash, code_hash)),
+            Factory::Plain => false,
+        }
+    }
+}
+
+/// DB backend wrapper for the code of an account.
+struct CodeDB<'db> {
+    db: &'db HashDB,
+    address_hash: H256,
+}
+
+impl<'db> HashDB for CodeDB<'db> {
+    fn keys(&self) -> HashMap<H256, i32> {
+        unimplemented!()
+    }
+
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        self.db.get(&combine_key(&CODE_ADDRESS_HASH, key)).or_else(|| self.db.get(&combine_key(&self.address_hash, key)))
+    }
+
+    fn contains(&self, key: &H256) -> bool {
+        self.db.contains(&combine_key(&CODE_ADDRESS_HASH, key)) || self.db.contains(&combine_key(&self.address_hash, key))
+    }
+
+    fn insert(&mut self, _value: &[u8]) -> H256 {
+        unimplemented!()
+    }
+
+    fn emplace(&mut self, _key: H256, _value: DBValue) {
+        unimplemented!()
+    }
+
+    fn remove(&mut self, _key: &H256) {
+        unimplemented!()
+    }
 }
 
 // TODO: introduce HashDBMut?
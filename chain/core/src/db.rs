@@ -23,6 +23,7 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::{Deref, Index};
 use util::{DBTransaction, KeyValueDB, RwLock, HeapSizeOf};
+use util::snappy;
 
 // database columns
 /// Column for State
@@ -42,6 +43,33 @@ pub const COL_NODE_INFO: Option<u32> = Some(6);
 /// Number of columns in DB
 pub const NUM_COLUMNS: Option<u32> = Some(7);
 
+/// Prefix of the compressed values, which never starts the rlp of a list.
+const COMPRESSED_PREFIX: u8 = 0;
+
+/// Compress the rlp of a value with snappy.
+pub fn compress(value: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    snappy::compress_into(value, &mut compressed);
+    let mut result = Vec::with_capacity(compressed.len() + 1);
+    result.push(COMPRESSED_PREFIX);
+    result.extend_from_slice(&compressed);
+    result
+}
+
+/// Whether the value is written by `compress`.
+pub fn is_compressed(value: &[u8]) -> bool {
+    value.first() == Some(&COMPRESSED_PREFIX)
+}
+
+/// Get back the rlp of a value, which is left as it is if written before the compression.
+pub fn decompress(value: &[u8]) -> Vec<u8> {
+    if is_compressed(value) {
+        snappy::decompress(&value[1..]).expect("Invalid compressed value.")
+    } else {
+        value.to_vec()
+    }
+}
+
 /// Contains all block receipts.
 #[derive(Clone)]
 pub struct DBList<T> {
@@ -164,6 +192,11 @@ pub trait Key<T> {
 
     /// Returns db key.
     fn key(&self) -> Self::Target;
+
+    /// Whether the value is stored compressed, only the large lists are.
+    fn compressed(&self) -> bool {
+        false
+    }
 }
 
 /// Should be used to write value into database.
@@ -433,7 +466,11 @@ impl Writable for DBTransaction {
         T: Encodable,
         R: Deref<Target = [u8]>,
     {
-        self.put(col, &key.key(), &encode(value));
+        if key.compressed() {
+            self.put(col, &key.key(), &compress(&encode(value)));
+        } else {
+            self.put(col, &key.key(), &encode(value));
+        }
     }
 
     fn append<T, R>(&mut self, col: Option<u32>, key: &Key<T, Target = R>, value: &DBList<T>)
@@ -441,7 +478,11 @@ impl Writable for DBTransaction {
         T: Encodable,
         R: Deref<Target = [u8]>,
     {
-        self.put(col, &key.key(), &encode(value));
+        if key.compressed() {
+            self.put(col, &key.key(), &compress(&encode(value)));
+        } else {
+            self.put(col, &key.key(), &encode(value));
+        }
     }
 
     fn delete<T, R>(&mut self, col: Option<u32>, key: &Key<T, Target = R>)
@@ -462,7 +503,7 @@ impl<KVDB: KeyValueDB + ?Sized> Readable for KVDB {
         let result = self.get(col, &key.key());
 
         match result {
-            Ok(option) => option.map(|v| decode(&v)),
+            Ok(option) => option.map(|v| if key.compressed() { decode(&decompress(&v)) } else { decode(&v) }),
             Err(err) => {
                 panic!("db get failed, key: {:?}, err: {:?}", &key.key() as &[u8], err);
             }
@@ -477,7 +518,7 @@ impl<KVDB: KeyValueDB + ?Sized> Readable for KVDB {
         let result = self.get(col, &key.key());
 
         match result {
-            Ok(option) => option.map(|v| decode(&v)),
+            Ok(option) => option.map(|v| if key.compressed() { decode(&decompress(&v)) } else { decode(&v) }),
             Err(err) => {
                 panic!("db get failed, key: {:?}, err: {:?}", &key.key() as &[u8], err);
             }
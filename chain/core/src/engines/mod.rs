@@ -16,6 +16,9 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use builtin::Builtin;
+use env_info::EnvInfo;
+use evm::Schedule;
+use header::BlockNumber;
 use native;
 use std::collections::{BTreeMap, HashMap};
 use util::{Address, U256, BytesRef};
@@ -47,12 +50,21 @@ pub trait Engine: Sync + Send {
     fn register(&mut self, addr: Address, contract: Box<native::Contract>);
     fn unregister(&mut self, addr: Address) -> Option<Box<native::Contract>>;
     fn get_native_contract(&self, addr: &Address) -> Option<&Box<native::Contract>>;
+
+    /// The EVM schedule of the block given by `env_info`.
+    fn schedule(&self, env_info: &EnvInfo) -> Schedule;
+
+    /// Id of the chain the transactions must be signed for.
+    fn chain_id(&self) -> u32;
 }
 
 /// An engine which does not provide any consensus mechanism and does not seal blocks.
 pub struct NullEngine {
     builtins: BTreeMap<Address, Builtin>,
     contracts: HashMap<Address, Box<native::Contract>>,
+    byzantium_height: Option<BlockNumber>,
+    create2_height: Option<BlockNumber>,
+    chain_id: u32,
 }
 
 impl NullEngine {
@@ -61,10 +73,36 @@ impl NullEngine {
         let mut engine = NullEngine {
             builtins: builtins,
             contracts: HashMap::new(),
+            byzantium_height: None,
+            create2_height: None,
+            chain_id: 0,
         };
         engine.register(Address::from(0x400), Box::new(native::NowPay::new()));
+        engine.register(native::sys_config_address(), Box::new(native::SysConfig::new()));
+        engine.register(native::node_manager_address(), Box::new(native::NodeManager::new()));
+        engine.register(native::quota_manager_address(), Box::new(native::QuotaManager::new()));
+        engine.register(native::storage_rent_address(), Box::new(native::StorageRent::new()));
+        engine.register(native::permission_manager_address(), Box::new(native::PermissionManager::new()));
         engine
     }
+
+    /// Enable the Byzantium opcodes from the block at `height`.
+    pub fn with_byzantium_height(mut self, height: Option<BlockNumber>) -> Self {
+        self.byzantium_height = height;
+        self
+    }
+
+    /// Enable the CREATE2 opcode from the block at `height`.
+    pub fn with_create2_height(mut self, height: Option<BlockNumber>) -> Self {
+        self.create2_height = height;
+        self
+    }
+
+    /// Execute only the transactions signed for the chain `chain_id`.
+    pub fn with_chain_id(mut self, chain_id: u32) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
 }
 
 impl Default for NullEngine {
@@ -93,4 +131,17 @@ impl Engine for NullEngine {
     fn get_native_contract(&self, addr: &Address) -> Option<&Box<native::Contract>> {
         self.contracts.get(addr)
     }
+
+    fn schedule(&self, env_info: &EnvInfo) -> Schedule {
+        let mut schedule = match self.byzantium_height {
+            Some(height) if env_info.number >= height => Schedule::new_byzantium(),
+            _ => Schedule::new_frontier(),
+        };
+        schedule.have_create2 = self.create2_height.map_or(false, |height| env_info.number >= height);
+        schedule
+    }
+
+    fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
 }
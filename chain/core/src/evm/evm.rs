@@ -19,7 +19,7 @@
 use action_params::ActionParams;
 use evm::Ext;
 use std::{ops, cmp, fmt};
-use util::{U128, U256, U512, trie};
+use util::{Bytes, U128, U256, U512, trie};
 
 /// Evm errors.
 #[derive(Debug, Clone, PartialEq)]
@@ -59,6 +59,20 @@ pub enum Error {
         /// What was the stack limit
         limit: usize,
     },
+    /// `MutableCallInStaticContext` is returned when a state changing instruction,
+    /// or a call transferring value, is executed in the context of `STATICCALL`
+    MutableCallInStaticContext,
+    /// `OutOfBounds` is returned when `RETURNDATACOPY` reads past the end of the return data
+    OutOfBounds,
+    /// `Reverted` is returned when the outer call/create ended with `REVERT`.
+    /// The state changes are reverted, but the gas left is refunded.
+    Reverted,
+    /// `StorageEvicted` is returned when calling a contract whose storage expired,
+    /// until the storage is restored.
+    StorageEvicted,
+    /// `Unauthorized` is returned when an account other than the admins calls a function
+    /// of a native contract changing the parameters of the chain.
+    Unauthorized,
     /// Returned on evm internal error. Should never be ignored during development.
     /// Likely to cause consensus issues.
     Internal(String),
@@ -79,6 +93,11 @@ impl fmt::Display for Error {
             BadInstruction { .. } => "Bad instruction",
             StackUnderflow { .. } => "Stack underflow",
             OutOfStack { .. } => "Out of stack",
+            MutableCallInStaticContext => "Mutable call in static context",
+            OutOfBounds => "Out of bounds",
+            Reverted => "Reverted",
+            StorageEvicted => "Storage evicted",
+            Unauthorized => "Unauthorized",
             Internal(ref msg) => msg,
         };
         message.fmt(f)
@@ -94,8 +113,26 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 pub enum GasLeft<'a> {
     /// Known gas left
     Known(U256),
-    /// Return instruction must be processed.
-    NeedsReturn(U256, &'a [u8]),
+    /// Return or Revert instruction must be processed.
+    NeedsReturn {
+        /// Amount of gas left.
+        gas_left: U256,
+        /// Return data.
+        data: &'a [u8],
+        /// Apply the state changes, false on `REVERT`.
+        apply_state: bool,
+    },
+}
+
+/// Finalization result of an execution.
+#[derive(Debug)]
+pub struct FinalizationResult {
+    /// Final amount of gas left.
+    pub gas_left: U256,
+    /// Apply the state changes, false if the execution was reverted.
+    pub apply_state: bool,
+    /// Data returned by `RETURN` or `REVERT`, the return data of the caller's last call.
+    pub return_data: Bytes,
 }
 
 /// Types that can be "finalized" using an EVM.
@@ -104,14 +141,28 @@ pub enum GasLeft<'a> {
 /// `Reult<GasLeft<'a>>`.
 pub trait Finalize {
     /// Consume the externalities, call return if necessary, and produce a final amount of gas left.
-    fn finalize<E: Ext>(self, ext: E) -> Result<U256>;
+    fn finalize<E: Ext>(self, ext: E) -> Result<FinalizationResult>;
 }
 
 impl<'a> Finalize for Result<GasLeft<'a>> {
-    fn finalize<E: Ext>(self, ext: E) -> Result<U256> {
+    fn finalize<E: Ext>(self, ext: E) -> Result<FinalizationResult> {
         match self {
-            Ok(GasLeft::Known(gas)) => Ok(gas),
-            Ok(GasLeft::NeedsReturn(gas, ret_code)) => ext.ret(&gas, ret_code),
+            Ok(GasLeft::Known(gas_left)) => {
+                Ok(FinalizationResult {
+                       gas_left: gas_left,
+                       apply_state: true,
+                       return_data: Bytes::new(),
+                   })
+            }
+            Ok(GasLeft::NeedsReturn { gas_left, data, apply_state }) => {
+                ext.ret(&gas_left, data, apply_state).map(|gas_left| {
+                    FinalizationResult {
+                        gas_left: gas_left,
+                        apply_state: apply_state,
+                        return_data: data.to_vec(),
+                    }
+                })
+            }
             Err(err) => Err(err),
         }
     }
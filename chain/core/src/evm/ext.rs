@@ -27,16 +27,31 @@ pub enum ContractCreateResult {
     /// Returned when creation was successfull.
     /// Contains an address of newly created contract and gas left.
     Created(Address, U256),
+    /// Returned when the init code ended with `REVERT`.
+    /// Contains gas left and the data returned.
+    Reverted(U256, Bytes),
     /// Returned when contract creation failed.
     /// VM doesn't have to know the reason.
     Failed,
 }
 
+/// Specifies how an address is calculated for a new contract.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CreateContractAddress {
+    /// Address is calculated from the sender and its nonce. Used by CREATE.
+    FromSenderAndNonce,
+    /// Address is calculated from the sender, the salt and the code hash. Used by CREATE2.
+    FromSenderSaltAndCodeHash(H256),
+}
+
 /// Result of externalities call function.
 pub enum MessageCallResult {
     /// Returned when message call was successfull.
-    /// Contains gas left.
-    Success(U256),
+    /// Contains gas left and the data returned.
+    Success(U256, Bytes),
+    /// Returned when message call ended with `REVERT`.
+    /// Contains gas left and the data returned.
+    Reverted(U256, Bytes),
     /// Returned when message call failed.
     /// VM doesn't have to know the reason.
     Failed,
@@ -51,6 +66,12 @@ pub trait Ext {
     /// Stores a value for given key.
     fn set_storage(&mut self, key: H256, value: H256) -> trie::Result<()>;
 
+    /// Write back the evicted storage of another contract, only used by the native contracts.
+    fn restore_storage(&mut self, address: &Address, storage: Vec<(H256, H256)>) -> trie::Result<()>;
+
+    /// Returns a value for given key in the storage of another contract, only used by the native contracts.
+    fn storage_of(&self, address: &Address, key: &H256) -> trie::Result<H256>;
+
     /// Determine whether an account exists.
     fn exists(&self, address: &Address) -> trie::Result<bool>;
 
@@ -69,7 +90,7 @@ pub trait Ext {
     /// Creates new contract.
     ///
     /// Returns gas_left and contract address if contract creation was succesfull.
-    fn create(&mut self, gas: &U256, value: &U256, code: &[u8]) -> ContractCreateResult;
+    fn create(&mut self, gas: &U256, value: &U256, code: &[u8], address: CreateContractAddress) -> ContractCreateResult;
 
     /// Message call.
     ///
@@ -88,9 +109,10 @@ pub trait Ext {
     /// Creates log entry with given topics and data
     fn log(&mut self, topics: Vec<H256>, data: &[u8]);
 
-    /// Should be called when transaction calls `RETURN` opcode.
+    /// Should be called when transaction calls `RETURN` or `REVERT` opcode.
     /// Returns gas_left if cost of returning the data is not too high.
-    fn ret(self, gas: &U256, data: &[u8]) -> evm::Result<U256>
+    /// The code of a created contract is only deployed if `apply_state` is true.
+    fn ret(self, gas: &U256, data: &[u8], apply_state: bool) -> evm::Result<U256>
     where
         Self: Sized;
 
@@ -110,6 +132,9 @@ pub trait Ext {
     /// then A depth is 0, B is 1, C is 2 and so on.
     fn depth(&self) -> usize;
 
+    /// Check if running in the context of `STATICCALL`, where the state must not change.
+    fn is_static(&self) -> bool;
+
     /// Increments sstore refunds count by 1.
     fn inc_sstore_clears(&mut self);
 
@@ -149,7 +149,7 @@ impl<Gas: CostType> Gasometer<Gas> {
             instructions::MSTORE8 => {
                 Request::GasMem(default_gas, mem_needed_const(stack.peek(0), 1)?)
             }
-            instructions::RETURN => {
+            instructions::RETURN | instructions::REVERT => {
                 Request::GasMem(default_gas, mem_needed(stack.peek(0), stack.peek(1))?)
             }
             instructions::SHA3 => {
@@ -159,7 +159,8 @@ impl<Gas: CostType> Gasometer<Gas> {
                 Request::GasMem(gas, mem_needed(stack.peek(0), stack.peek(1))?)
             }
             instructions::CALLDATACOPY |
-            instructions::CODECOPY => {
+            instructions::CODECOPY |
+            instructions::RETURNDATACOPY => {
                 Request::GasMemCopy(default_gas, mem_needed(stack.peek(0), stack.peek(2))?, Gas::from_u256(*stack.peek(2))?)
             }
             instructions::EXTCODECOPY => {
@@ -192,7 +193,7 @@ impl<Gas: CostType> Gasometer<Gas> {
 
                 Request::GasMemProvide(gas, mem, Some(requested))
             }
-            instructions::DELEGATECALL => {
+            instructions::DELEGATECALL | instructions::STATICCALL => {
                 let gas = Gas::from(schedule.call_gas);
                 let mem = cmp::max(mem_needed(stack.peek(4), stack.peek(5))?, mem_needed(stack.peek(2), stack.peek(3))?);
                 let requested = *stack.peek(0);
@@ -205,6 +206,15 @@ impl<Gas: CostType> Gasometer<Gas> {
 
                 Request::GasMemProvide(gas, mem, None)
             }
+            instructions::CREATE2 => {
+                // the init code is hashed to derive the address
+                let w = overflowing!(add_gas_usize(Gas::from_u256(*stack.peek(2))?, 31));
+                let words = w >> 5;
+                let gas = Gas::from(schedule.create_gas) + (Gas::from(schedule.sha3_word_gas) * words);
+                let mem = mem_needed(stack.peek(1), stack.peek(2))?;
+
+                Request::GasMemProvide(gas, mem, None)
+            }
             instructions::EXP => {
                 let expon = stack.peek(1);
                 let bytes = ((expon.bits() + 7) / 8) as usize;
@@ -29,7 +29,7 @@ pub use self::shared_cache::SharedCache;
 use self::stack::{Stack, VecStack};
 use action_params::{ActionParams, ActionValue};
 use bit_set::BitSet;
-use evm::{self, MessageCallResult, ContractCreateResult, GasLeft, CostType};
+use evm::{self, MessageCallResult, ContractCreateResult, CreateContractAddress, GasLeft, CostType};
 use evm::instructions::{self, Instruction, InstructionInfo};
 use executed::CallType;
 use std::cmp;
@@ -82,8 +82,8 @@ enum InstructionResult<Gas> {
     Ok,
     UnusedGas(Gas),
     JumpToPosition(U256),
-    // gas left, init_orf, init_size
-    StopExecutionNeedsReturn(Gas, U256, U256),
+    // gas left, init_orf, init_size, apply state
+    StopExecutionNeedsReturn(Gas, U256, U256, bool),
     StopExecution,
 }
 
@@ -92,12 +92,15 @@ enum InstructionResult<Gas> {
 pub struct Interpreter<Cost: CostType> {
     mem: Vec<u8>,
     cache: Arc<SharedCache>,
+    // data returned by the last call or create
+    return_data: Bytes,
     _type: PhantomData<Cost>,
 }
 
 impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
     fn exec(&mut self, params: ActionParams, ext: &mut evm::Ext) -> evm::Result<GasLeft> {
         self.mem.clear();
+        self.return_data.clear();
 
         let mut informant = informant::EvmInformant::new(ext.depth());
 
@@ -156,9 +159,13 @@ impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
                     let pos = self.verify_jump(position, &valid_jump_destinations)?;
                     reader.position = pos;
                 }
-                InstructionResult::StopExecutionNeedsReturn(gas, off, size) => {
+                InstructionResult::StopExecutionNeedsReturn(gas, off, size, apply) => {
                     informant.done();
-                    return Ok(GasLeft::NeedsReturn(gas.as_u256(), self.mem.read_slice(off, size)));
+                    return Ok(GasLeft::NeedsReturn {
+                                  gas_left: gas.as_u256(),
+                                  data: self.mem.read_slice(off, size),
+                                  apply_state: apply,
+                              });
                 }
                 InstructionResult::StopExecution => break,
                 _ => {}
@@ -175,6 +182,7 @@ impl<Cost: CostType> Interpreter<Cost> {
         Interpreter {
             mem: Vec::new(),
             cache: cache,
+            return_data: Bytes::new(),
             _type: PhantomData::default(),
         }
     }
@@ -182,10 +190,16 @@ impl<Cost: CostType> Interpreter<Cost> {
     fn verify_instruction(&self, ext: &evm::Ext, instruction: Instruction, info: &InstructionInfo, stack: &Stack<U256>) -> evm::Result<()> {
         let schedule = ext.schedule();
 
-        if !schedule.have_delegate_call && instruction == instructions::DELEGATECALL {
+        if (!schedule.have_delegate_call && instruction == instructions::DELEGATECALL) || (!schedule.have_revert && instruction == instructions::REVERT) || (!schedule.have_static_call && instruction == instructions::STATICCALL) || (!schedule.have_create2 && instruction == instructions::CREATE2) ||
+           (!schedule.have_return_data && (instruction == instructions::RETURNDATASIZE || instruction == instructions::RETURNDATACOPY))
+        {
             return Err(evm::Error::BadInstruction { instruction: instruction });
         }
 
+        if ext.is_static() && (instruction == instructions::SSTORE || instruction == instructions::CREATE || instruction == instructions::CREATE2 || instruction == instructions::SUICIDE || (instruction >= instructions::LOG0 && instruction <= instructions::LOG4)) {
+            return Err(evm::Error::MutableCallInStaticContext);
+        }
+
         if info.tier == instructions::GasPriceTier::Invalid {
             return Err(evm::Error::BadInstruction { instruction: instruction });
         }
@@ -212,10 +226,11 @@ impl<Cost: CostType> Interpreter<Cost> {
             instructions::MSTORE | instructions::MLOAD => Some((stack.peek(0).low_u64() as usize, 32)),
             instructions::MSTORE8 => Some((stack.peek(0).low_u64() as usize, 1)),
             instructions::CALLDATACOPY |
-            instructions::CODECOPY => Some((stack.peek(0).low_u64() as usize, stack.peek(2).low_u64() as usize)),
+            instructions::CODECOPY |
+            instructions::RETURNDATACOPY => Some((stack.peek(0).low_u64() as usize, stack.peek(2).low_u64() as usize)),
             instructions::EXTCODECOPY => Some((stack.peek(1).low_u64() as usize, stack.peek(3).low_u64() as usize)),
             instructions::CALL | instructions::CALLCODE => Some((stack.peek(5).low_u64() as usize, stack.peek(6).low_u64() as usize)),
-            instructions::DELEGATECALL => Some((stack.peek(4).low_u64() as usize, stack.peek(5).low_u64() as usize)),
+            instructions::DELEGATECALL | instructions::STATICCALL => Some((stack.peek(4).low_u64() as usize, stack.peek(5).low_u64() as usize)),
             _ => None,
         }
     }
@@ -244,26 +259,38 @@ impl<Cost: CostType> Interpreter<Cost> {
             instructions::JUMPDEST => {
                 // ignore
             }
-            instructions::CREATE => {
+            instructions::CREATE | instructions::CREATE2 => {
                 let endowment = stack.pop_back();
                 let init_off = stack.pop_back();
                 let init_size = stack.pop_back();
-                let create_gas = provided.expect("`provided` comes through Self::exec from `Gasometer::get_gas_cost_mem`; `gas_gas_mem_cost` guarantees `Some` when instruction is `CALL`/`CALLCODE`/`DELEGATECALL`/`CREATE`; this is `CREATE`; qed");
+                let address_scheme = match instruction {
+                    instructions::CREATE2 => CreateContractAddress::FromSenderSaltAndCodeHash(H256::from(&stack.pop_back())),
+                    _ => CreateContractAddress::FromSenderAndNonce,
+                };
+                let create_gas = provided.expect("`provided` comes through Self::exec from `Gasometer::get_gas_cost_mem`; `gas_gas_mem_cost` guarantees `Some` when instruction is `CALL`/`CALLCODE`/`DELEGATECALL`/`CREATE`/`CREATE2`; this is one of `CREATE`/`CREATE2`; qed");
 
                 let contract_code = self.mem.read_slice(init_off, init_size);
                 let can_create = ext.balance(&params.address)? >= endowment && ext.depth() < ext.schedule().max_depth;
 
+                // clear return data buffer before creating new call frame.
+                self.return_data.clear();
+
                 if !can_create {
                     stack.push(U256::zero());
                     return Ok(InstructionResult::UnusedGas(create_gas));
                 }
 
-                let create_result = ext.create(&create_gas.as_u256(), &endowment, contract_code);
+                let create_result = ext.create(&create_gas.as_u256(), &endowment, contract_code, address_scheme);
                 return match create_result {
                     ContractCreateResult::Created(address, gas_left) => {
                         stack.push(address_to_u256(address));
                         Ok(InstructionResult::UnusedGas(Cost::from_u256(gas_left).expect("Gas left cannot be greater.")))
                     }
+                    ContractCreateResult::Reverted(gas_left, return_data) => {
+                        stack.push(U256::zero());
+                        self.return_data = return_data;
+                        Ok(InstructionResult::UnusedGas(Cost::from_u256(gas_left).expect("Gas left cannot be greater.")))
+                    }
                     ContractCreateResult::Failed => {
                         stack.push(U256::zero());
                         Ok(InstructionResult::Ok)
@@ -272,14 +299,23 @@ impl<Cost: CostType> Interpreter<Cost> {
             }
             instructions::CALL |
             instructions::CALLCODE |
-            instructions::DELEGATECALL => {
+            instructions::DELEGATECALL |
+            instructions::STATICCALL => {
                 assert!(ext.schedule().call_value_transfer_gas > ext.schedule().call_stipend, "overflow possible");
                 stack.pop_back();
                 let call_gas = provided.expect("`provided` comes through Self::exec from `Gasometer::get_gas_cost_mem`; `gas_gas_mem_cost` guarantees `Some` when instruction is `CALL`/`CALLCODE`/`DELEGATECALL`/`CREATE`; this is one of `CALL`/`CALLCODE`/`DELEGATECALL`; qed");
                 let code_address = stack.pop_back();
                 let code_address = u256_to_address(&code_address);
 
-                let value = if instruction == instructions::DELEGATECALL { None } else { Some(stack.pop_back()) };
+                let value = match instruction {
+                    instructions::DELEGATECALL => None,
+                    instructions::STATICCALL => Some(U256::zero()),
+                    _ => Some(stack.pop_back()),
+                };
+
+                if ext.is_static() && value.map_or(false, |v| !v.is_zero()) {
+                    return Err(evm::Error::MutableCallInStaticContext);
+                }
 
                 let in_off = stack.pop_back();
                 let in_size = stack.pop_back();
@@ -304,9 +340,13 @@ impl<Cost: CostType> Interpreter<Cost> {
                         (&params.address, &params.address, has_balance, CallType::CallCode)
                     }
                     instructions::DELEGATECALL => (&params.sender, &params.address, true, CallType::DelegateCall),
+                    instructions::STATICCALL => (&params.address, &code_address, true, CallType::StaticCall),
                     _ => panic!(format!("Unexpected instruction {} in CALL branch.", instruction)),
                 };
 
+                // clear return data buffer before creating new call frame.
+                self.return_data.clear();
+
                 let can_call = has_balance && ext.depth() < ext.schedule().max_depth;
                 if !can_call {
                     stack.push(U256::zero());
@@ -322,8 +362,14 @@ impl<Cost: CostType> Interpreter<Cost> {
                 };
 
                 return match call_result {
-                    MessageCallResult::Success(gas_left) => {
+                    MessageCallResult::Success(gas_left, return_data) => {
                         stack.push(U256::one());
+                        self.return_data = return_data;
+                        Ok(InstructionResult::UnusedGas(Cost::from_u256(gas_left).expect("Gas left cannot be greater then current one")))
+                    }
+                    MessageCallResult::Reverted(gas_left, return_data) => {
+                        stack.push(U256::zero());
+                        self.return_data = return_data;
                         Ok(InstructionResult::UnusedGas(Cost::from_u256(gas_left).expect("Gas left cannot be greater then current one")))
                     }
                     MessageCallResult::Failed => {
@@ -336,7 +382,13 @@ impl<Cost: CostType> Interpreter<Cost> {
                 let init_off = stack.pop_back();
                 let init_size = stack.pop_back();
 
-                return Ok(InstructionResult::StopExecutionNeedsReturn(gas, init_off, init_size));
+                return Ok(InstructionResult::StopExecutionNeedsReturn(gas, init_off, init_size, true));
+            }
+            instructions::REVERT => {
+                let init_off = stack.pop_back();
+                let init_size = stack.pop_back();
+
+                return Ok(InstructionResult::StopExecutionNeedsReturn(gas, init_off, init_size, false));
             }
             instructions::STOP => {
                 return Ok(InstructionResult::StopExecution);
@@ -463,6 +515,21 @@ impl<Cost: CostType> Interpreter<Cost> {
                 let code = ext.extcode(&address)?;
                 self.copy_data_to_memory(stack, &code);
             }
+            instructions::RETURNDATASIZE => {
+                stack.push(U256::from(self.return_data.len()));
+            }
+            instructions::RETURNDATACOPY => {
+                let dest_offset = stack.pop_back();
+                let source_offset = stack.pop_back();
+                let size = stack.pop_back();
+                let (source_end, overflow) = source_offset.overflowing_add(size);
+                if overflow || source_end > U256::from(self.return_data.len()) {
+                    return Err(evm::Error::OutOfBounds);
+                }
+                let begin = source_offset.low_u64() as usize;
+                let end = source_end.low_u64() as usize;
+                self.mem.write_slice(dest_offset, &self.return_data[begin..end]);
+            }
             instructions::GASPRICE => {
                 stack.push(params.gas_price.clone());
             }
@@ -31,8 +31,8 @@ mod tests;
 #[cfg(all(feature = "benches", test))]
 mod benches;
 
-pub use self::evm::{Evm, Error, Finalize, GasLeft, Result, CostType};
-pub use self::ext::{Ext, ContractCreateResult, MessageCallResult};
+pub use self::evm::{Evm, Error, Finalize, FinalizationResult, GasLeft, Result, CostType};
+pub use self::ext::{Ext, ContractCreateResult, CreateContractAddress, MessageCallResult};
 pub use self::factory::{Factory, VMType};
 pub use self::schedule::Schedule;
 pub use executed::CallType;
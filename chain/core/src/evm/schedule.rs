@@ -22,6 +22,14 @@ pub struct Schedule {
     pub exceptional_failed_code_deposit: bool,
     /// Does it have a delegate cal
     pub have_delegate_call: bool,
+    /// Does it have a REVERT instruction
+    pub have_revert: bool,
+    /// Does it have RETURNDATASIZE and RETURNDATACOPY instructions
+    pub have_return_data: bool,
+    /// Does it have a STATICCALL instruction
+    pub have_static_call: bool,
+    /// Does it have a CREATE2 instruction
+    pub have_create2: bool,
     /// VM stack limit
     pub stack_limit: usize,
     /// Max number of nested calls/creates
@@ -117,6 +125,10 @@ impl Schedule {
         Schedule {
             exceptional_failed_code_deposit: true,
             have_delegate_call: true,
+            have_revert: false,
+            have_return_data: false,
+            have_static_call: false,
+            have_create2: false,
             stack_limit: 1024,
             max_depth: 1024,
             tier_step_gas: [0, 2, 3, 5, 8, 10, 20, 0],
@@ -158,10 +170,23 @@ impl Schedule {
         }
     }
 
+    /// Schedule for the Byzantium-era of the Ethereum main net, without the limit on the code size.
+    pub fn new_byzantium() -> Schedule {
+        let mut schedule = Self::new_post_eip150(usize::max_value(), true, false, false);
+        schedule.have_revert = true;
+        schedule.have_return_data = true;
+        schedule.have_static_call = true;
+        schedule
+    }
+
     fn new(efcd: bool, hdc: bool, tcg: usize) -> Schedule {
         Schedule {
             exceptional_failed_code_deposit: efcd,
             have_delegate_call: hdc,
+            have_revert: false,
+            have_return_data: false,
+            have_static_call: false,
+            have_create2: false,
             stack_limit: 1024,
             max_depth: 1024,
             tier_step_gas: [0, 2, 3, 5, 8, 10, 20, 0],
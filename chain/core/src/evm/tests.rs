@@ -16,7 +16,7 @@
 
 use action_params::{ActionParams, ActionValue};
 use env_info::EnvInfo;
-use evm::{self, Ext, Schedule, Factory, GasLeft, VMType, ContractCreateResult, MessageCallResult};
+use evm::{self, Ext, Schedule, Factory, GasLeft, VMType, ContractCreateResult, CreateContractAddress, MessageCallResult};
 use executed::CallType;
 use rustc_hex::FromHex;
 use std::collections::{HashMap, HashSet};
@@ -64,13 +64,16 @@ pub struct FakeExt {
     schedule: Schedule,
     balances: HashMap<Address, U256>,
     calls: HashSet<FakeCall>,
+    is_static: bool,
+    // data returned by the fake calls
+    call_output: Bytes,
 }
 
 // similar to the normal `finalize` function, but ignoring NeedsReturn.
 fn test_finalize(res: Result<GasLeft, evm::Error>) -> Result<U256, evm::Error> {
     match res {
         Ok(GasLeft::Known(gas)) => Ok(gas),
-        Ok(GasLeft::NeedsReturn(_, _)) => unimplemented!(), // since ret is unimplemented.
+        Ok(GasLeft::NeedsReturn { .. }) => unimplemented!(), // since ret is unimplemented.
         Err(e) => Err(e),
     }
 }
@@ -97,6 +100,14 @@ impl Ext for FakeExt {
         Ok(())
     }
 
+    fn restore_storage(&mut self, _address: &Address, _storage: Vec<(H256, H256)>) -> trie::Result<()> {
+        unimplemented!();
+    }
+
+    fn storage_of(&self, _address: &Address, _key: &H256) -> trie::Result<H256> {
+        unimplemented!();
+    }
+
     fn exists(&self, address: &Address) -> trie::Result<bool> {
         Ok(self.balances.contains_key(address))
     }
@@ -117,7 +128,7 @@ impl Ext for FakeExt {
         self.blockhashes.get(number).unwrap_or(&H256::new()).clone()
     }
 
-    fn create(&mut self, gas: &U256, value: &U256, code: &[u8]) -> ContractCreateResult {
+    fn create(&mut self, gas: &U256, value: &U256, code: &[u8], _address: CreateContractAddress) -> ContractCreateResult {
         self.calls.insert(FakeCall {
                               call_type: FakeCallType::Create,
                               gas: *gas,
@@ -141,7 +152,7 @@ impl Ext for FakeExt {
                               data: data.to_vec(),
                               code_address: Some(code_address.clone()),
                           });
-        MessageCallResult::Success(*gas)
+        MessageCallResult::Success(*gas, self.call_output.clone())
     }
 
     fn extcode(&self, address: &Address) -> trie::Result<Arc<Bytes>> {
@@ -159,7 +170,7 @@ impl Ext for FakeExt {
                        });
     }
 
-    fn ret(self, _gas: &U256, _data: &[u8]) -> evm::Result<U256> {
+    fn ret(self, _gas: &U256, _data: &[u8], _apply_state: bool) -> evm::Result<U256> {
         unimplemented!();
     }
 
@@ -179,6 +190,10 @@ impl Ext for FakeExt {
         self.depth
     }
 
+    fn is_static(&self) -> bool {
+        self.is_static
+    }
+
     fn inc_sstore_clears(&mut self) {
         self.sstore_clears += 1;
     }
@@ -958,6 +973,121 @@ fn test_calls(factory: super::Factory) {
     assert_eq!(ext.calls.len(), 2);
 }
 
+evm_test!{ignorejit => test_revert: test_revert_jit, test_revert_int}
+fn test_revert(factory: super::Factory) {
+    // mstore(0, 0x2a) revert(0, 32)
+    let code = "602a60005260206000fd".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+    ext.schedule = Schedule::new_byzantium();
+
+    let mut vm = factory.create(params.gas);
+    match vm.exec(params, &mut ext).unwrap() {
+        GasLeft::NeedsReturn { gas_left, data, apply_state } => {
+            assert_eq!(gas_left, U256::from(99_982));
+            assert_eq!(data, &*H256::from(0x2a));
+            assert!(!apply_state);
+        }
+        GasLeft::Known(_) => panic!("REVERT must return the data"),
+    }
+}
+
+evm_test!{ignorejit => test_revert_before_byzantium: test_revert_before_byzantium_jit, test_revert_before_byzantium_int}
+fn test_revert_before_byzantium(factory: super::Factory) {
+    let code = "602a60005260206000fd".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+
+    let err = {
+        let mut vm = factory.create(params.gas);
+        test_finalize(vm.exec(params, &mut ext)).unwrap_err()
+    };
+
+    assert_eq!(err, evm::Error::BadInstruction { instruction: 0xfd });
+}
+
+evm_test!{ignorejit => test_static_call_return_data: test_static_call_return_data_jit, test_static_call_return_data_int}
+fn test_static_call_return_data(factory: super::Factory) {
+    // staticcall(0xffff, 0x998, 0, 0, 0, 0)
+    // sstore(1, returndatasize) sstore(0, success)
+    // returndatacopy(0, 0, 32) sstore(2, mload(0))
+    let code = "600060006000600061099861fffffa3d6001556000556020600060003e600051600255".from_hex().unwrap();
+
+    let address = Address::from(0x155);
+    let code_address = Address::from(0x998);
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    params.address = address.clone();
+    let mut ext = FakeExt::new();
+    ext.schedule = Schedule::new_byzantium();
+    ext.call_output = H256::from(0x2a).to_vec();
+
+    {
+        let mut vm = factory.create(params.gas);
+        test_finalize(vm.exec(params, &mut ext)).unwrap();
+    }
+
+    assert_set_contains(&ext.calls,
+                        &FakeCall {
+                            call_type: FakeCallType::Call,
+                            gas: U256::from(0xffff),
+                            sender_address: Some(address.clone()),
+                            receive_address: Some(code_address.clone()),
+                            value: Some(U256::zero()),
+                            data: vec![],
+                            code_address: Some(code_address.clone()),
+                        });
+    assert_store(&ext, 0, "0000000000000000000000000000000000000000000000000000000000000001");
+    assert_store(&ext, 1, "0000000000000000000000000000000000000000000000000000000000000020");
+    assert_store(&ext, 2, "000000000000000000000000000000000000000000000000000000000000002a");
+}
+
+evm_test!{ignorejit => test_return_data_out_of_bounds: test_return_data_out_of_bounds_jit, test_return_data_out_of_bounds_int}
+fn test_return_data_out_of_bounds(factory: super::Factory) {
+    // returndatacopy(0, 0, 1) without a call before
+    let code = "6001600060003e".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+    ext.schedule = Schedule::new_byzantium();
+
+    let err = {
+        let mut vm = factory.create(params.gas);
+        test_finalize(vm.exec(params, &mut ext)).unwrap_err()
+    };
+
+    assert_eq!(err, evm::Error::OutOfBounds);
+}
+
+evm_test!{ignorejit => test_sstore_in_static_context: test_sstore_in_static_context_jit, test_sstore_in_static_context_int}
+fn test_sstore_in_static_context(factory: super::Factory) {
+    let code = "600160005500".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+    ext.schedule = Schedule::new_byzantium();
+    ext.is_static = true;
+
+    let err = {
+        let mut vm = factory.create(params.gas);
+        test_finalize(vm.exec(params, &mut ext)).unwrap_err()
+    };
+
+    assert_eq!(err, evm::Error::MutableCallInStaticContext);
+    assert!(ext.store.is_empty());
+}
+
 fn assert_set_contains<T: Debug + Eq + PartialEq + Hash>(set: &HashSet<T>, val: &T) {
     let contains = set.contains(val);
     if !contains {
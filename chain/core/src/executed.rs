@@ -36,6 +36,8 @@ pub enum CallType {
     CallCode,
     /// DELEGATECALL.
     DelegateCall,
+    /// STATICCALL.
+    StaticCall,
 }
 
 impl Encodable for CallType {
@@ -45,6 +47,7 @@ impl Encodable for CallType {
             CallType::Call => 1,
             CallType::CallCode => 2,
             CallType::DelegateCall => 3,
+            CallType::StaticCall => 4,
         };
         s.append_internal(&value);
     }
@@ -58,6 +61,7 @@ impl Decodable for CallType {
             1 => Ok(CallType::Call),
             2 => Ok(CallType::CallCode),
             3 => Ok(CallType::DelegateCall),
+            4 => Ok(CallType::StaticCall),
             _ => Err(DecoderError::Custom("Invalid value of CallType item")),
         }
     }
@@ -138,6 +142,16 @@ pub enum ExecutionError {
         /// Nonce found.
         got: U256,
     },
+    /// Returned when the transaction was signed for another chain,
+    /// or in a version of transaction not known.
+    InvalidChainId {
+        /// Chain id expected.
+        expected: u32,
+        /// Chain id found.
+        got: u32,
+        /// Version of the transaction.
+        version: u32,
+    },
     /// Returned when cost of transaction (value + gas_price * gas) exceeds
     /// current sender balance.
     NotEnoughCash {
@@ -170,6 +184,7 @@ impl fmt::Display for ExecutionError {
                 ref gas,
             } => format!("Block gas limit reached. The limit is {}, {} has already been used, and {} more is required", gas_limit, gas_used, gas),
             InvalidNonce { ref expected, ref got } => format!("Invalid transaction nonce: expected {}, found {}", expected, got),
+            InvalidChainId { ref expected, ref got, ref version } => format!("Invalid transaction chain id: expected {}, found {} in version {}", expected, got, version),
             NotEnoughCash { ref required, ref got } => format!("Cost of transaction exceeds sender balance. {} is required but the sender only has {}", required, got),
             Internal(ref msg) => msg.clone(),
             TransactionMalformed(ref err) => format!("Malformed transaction: {}", err),
@@ -22,10 +22,12 @@ use engines::Engine;
 use env_info::EnvInfo;
 use error::ExecutionError;
 use ethcore_io as io;
-use evm::{self, Ext, Factory, Finalize, Schedule};
+use evm::{self, Ext, Factory, Finalize, FinalizationResult};
 pub use executed::{Executed, ExecutionResult};
 use executed::CallType;
 use externalities::*;
+use libproto::TRANSACTION_VERSION;
+use native;
 use state::{State, Substate};
 use state::backend::Backend as StateBackend;
 use std::cmp;
@@ -50,6 +52,17 @@ pub fn contract_address(address: &Address, nonce: &U256) -> Address {
     From::from(stream.out().crypt_hash())
 }
 
+/// Returns new address created by CREATE2 from the sender, a salt and the init code,
+/// known before the contract is deployed.
+pub fn contract_address_from_salt(sender: &Address, salt: &H256, code: &[u8]) -> Address {
+    let mut buffer = [0u8; 1 + 20 + 32 + 32];
+    buffer[0] = 0xff;
+    buffer[1..21].copy_from_slice(&sender[..]);
+    buffer[21..53].copy_from_slice(&salt[..]);
+    buffer[53..].copy_from_slice(&code.crypt_hash()[..]);
+    From::from(buffer.crypt_hash())
+}
+
 /// Transaction execution options.
 #[derive(Default, Copy, Clone, PartialEq)]
 pub struct TransactOptions {
@@ -68,6 +81,7 @@ pub struct Executive<'a, B: 'a + StateBackend> {
     engine: &'a Engine,
     vm_factory: &'a Factory,
     depth: usize,
+    static_flag: bool,
 }
 
 impl<'a, B: 'a + StateBackend> Executive<'a, B> {
@@ -79,27 +93,32 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             engine: engine,
             vm_factory: vm_factory,
             depth: 0,
+            static_flag: false,
         }
     }
 
     /// Populates executive from parent properties. Increments executive depth.
-    pub fn from_parent(state: &'a mut State<B>, info: &'a EnvInfo, engine: &'a Engine, vm_factory: &'a Factory, parent_depth: usize) -> Self {
+    /// The state must not change if the parent runs in the context of `STATICCALL`.
+    pub fn from_parent(state: &'a mut State<B>, info: &'a EnvInfo, engine: &'a Engine, vm_factory: &'a Factory, parent_depth: usize, static_flag: bool) -> Self {
         Executive {
             state: state,
             info: info,
             engine: engine,
             vm_factory: vm_factory,
             depth: parent_depth + 1,
+            static_flag: static_flag,
         }
     }
 
     /// Creates `Externalities` from `Executive`.
-    pub fn as_externalities<'any, T, V>(&'any mut self, origin_info: OriginInfo, substate: &'any mut Substate, output: OutputPolicy<'any, 'any>, tracer: &'any mut T, vm_tracer: &'any mut V) -> Externalities<'any, T, V, B>
+    #[cfg_attr(feature = "dev", allow(too_many_arguments))]
+    pub fn as_externalities<'any, T, V>(&'any mut self, origin_info: OriginInfo, substate: &'any mut Substate, output: OutputPolicy<'any, 'any>, tracer: &'any mut T, vm_tracer: &'any mut V, static_call: bool) -> Externalities<'any, T, V, B>
     where
         T: Tracer,
         V: VMTracer,
     {
-        Externalities::new(self.state, self.info, self.engine, self.vm_factory, self.depth, origin_info, substate, output, tracer, vm_tracer)
+        let is_static = self.static_flag || static_call;
+        Externalities::new(self.state, self.info, self.engine, self.vm_factory, self.depth, origin_info, substate, output, tracer, vm_tracer, is_static)
     }
 
     /// This function should be used to execute transaction.
@@ -155,6 +174,14 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                                       got: t.gas,
                                   }));
         }
+        // validate the chain the transaction was signed for
+        if t.chain_id != self.engine.chain_id() || t.version > TRANSACTION_VERSION {
+            return Err(From::from(ExecutionError::InvalidChainId {
+                                      expected: self.engine.chain_id(),
+                                      got: t.chain_id,
+                                      version: t.version,
+                                  }));
+        }
         // validate transaction nonce
         if check_nonce && t.nonce != nonce {
             return Err(From::from(ExecutionError::InvalidNonce { expected: nonce, got: t.nonce }));
@@ -183,9 +210,14 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
          */
         let mut substate = Substate::new();
 
-        let (gas_left, output) = match t.action {
+        let (result, output) = match t.action {
             Action::Store => {
-                (Ok(t.gas), vec![])
+                let result = FinalizationResult {
+                    gas_left: t.gas,
+                    apply_state: true,
+                    return_data: vec![],
+                };
+                (Ok(result), vec![])
             }
             Action::Create => {
                 let new_address = contract_address(&sender, &nonce);
@@ -224,21 +256,22 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         };
 
         // finalize here!
-        Ok(self.finalize(t, substate, gas_left, output, tracer.traces(), vm_tracer.drain())?)
+        Ok(self.finalize(t, substate, result, output, tracer.traces(), vm_tracer.drain())?)
     }
 
-    fn exec_vm<T, V>(&mut self, params: ActionParams, unconfirmed_substate: &mut Substate, output_policy: OutputPolicy, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<U256>
+    fn exec_vm<T, V>(&mut self, params: ActionParams, unconfirmed_substate: &mut Substate, output_policy: OutputPolicy, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<FinalizationResult>
     where
         T: Tracer,
         V: VMTracer,
     {
 
         let depth_threshold = io::LOCAL_STACK_SIZE.with(|sz| sz.get() / STACK_SIZE_PER_DEPTH);
+        let static_call = params.call_type == CallType::StaticCall;
 
         // Ordinary execution - keep VM in same thread
         if (self.depth + 1) % depth_threshold != 0 {
             let vm_factory = self.vm_factory;
-            let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer);
+            let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer, static_call);
             trace!(target: "executive", "ext.schedule.have_delegate_call: {}", ext.schedule().have_delegate_call);
             return vm_factory.create(params.gas).exec(params, &mut ext).finalize(ext);
         }
@@ -248,7 +281,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         // https://github.com/aturon/crossbeam/issues/16
         crossbeam::scope(|scope| {
                              let vm_factory = self.vm_factory;
-                             let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer);
+                             let mut ext = self.as_externalities(OriginInfo::from(&params), unconfirmed_substate, output_policy, tracer, vm_tracer, static_call);
 
                              scope.spawn(move || vm_factory.create(params.gas).exec(params, &mut ext).finalize(ext))
                          })
@@ -258,8 +291,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     /// Calls contract function with given contract params.
     /// NOTE. It does not finalize the transaction (doesn't do refunds, nor suicides).
     /// Modifies the substate and the output.
-    /// Returns either gas_left or `evm::Error`.
-    pub fn call<T, V>(&mut self, params: ActionParams, substate: &mut Substate, mut output: BytesRef, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<U256>
+    /// Returns either the finalization result or `evm::Error`.
+    pub fn call<T, V>(&mut self, params: ActionParams, substate: &mut Substate, mut output: BytesRef, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<FinalizationResult>
     where
         T: Tracer,
         V: VMTracer,
@@ -268,6 +301,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         self.state.checkpoint();
 
         if let Some(contract) = self.engine.get_native_contract(&params.code_address) {
+            // all the functions of the native contracts change the state
+            if self.static_flag || params.call_type == CallType::StaticCall {
+                self.state.revert_to_checkpoint();
+                return Err(evm::Error::MutableCallInStaticContext);
+            }
 
             //let cost = self.engine.cost_of_builtin(&params.code_address, data);
             let cost = U256::from(100);
@@ -277,11 +315,17 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 let mut subvmtracer = vm_tracer.prepare_subtrace(params.code.as_ref().expect("scope is conditional on params.code.is_some(); qed"));
                 let mut trace_output = tracer.prepare_trace_output();
                 let output_policy = OutputPolicy::Return(output, trace_output.as_mut());
-                {
-                    let mut ext = self.as_externalities(OriginInfo::from(&params), &mut unconfirmed_substate, output_policy, tracer, &mut subvmtracer);
-                    contract.exec(&params, &mut ext);
-                }
-                let res = Ok(params.gas - cost);
+                let result = {
+                    let mut ext = self.as_externalities(OriginInfo::from(&params), &mut unconfirmed_substate, output_policy, tracer, &mut subvmtracer, false);
+                    contract.exec(&params, &mut ext)
+                };
+                let res = result.map(|_| {
+                                         FinalizationResult {
+                                             gas_left: params.gas - cost,
+                                             apply_state: true,
+                                             return_data: vec![],
+                                         }
+                                     });
                 self.enact_result(&res, substate, unconfirmed_substate);
                 return res;
             }
@@ -296,7 +340,9 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 
             let cost = self.engine.cost_of_builtin(&params.code_address, data);
             if cost <= params.gas {
-                self.engine.execute_builtin(&params.code_address, data, &mut output);
+                let mut builtin_output = Vec::new();
+                self.engine.execute_builtin(&params.code_address, data, &mut BytesRef::Flexible(&mut builtin_output));
+                output.write(0, &builtin_output);
                 self.state.discard_checkpoint();
 
                 // trace only top level calls to builtins to avoid DDoS attacks
@@ -309,7 +355,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     tracer.trace_call(trace_info, cost, trace_output, vec![]);
                 }
 
-                Ok(params.gas - cost)
+                Ok(FinalizationResult {
+                       gas_left: params.gas - cost,
+                       apply_state: true,
+                       return_data: builtin_output,
+                   })
             } else {
                 // just drain the whole gas
                 self.state.revert_to_checkpoint();
@@ -326,6 +376,20 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             let gas = params.gas;
 
             if params.code.is_some() {
+                // the contract cannot run on the empty storage left by the expiry
+                let evicted = match self.state.storage_at(&native::storage_rent_address(), &native::evicted_key(&params.address)) {
+                    Ok(root) => !root.is_zero(),
+                    Err(e) => {
+                        self.state.revert_to_checkpoint();
+                        return Err(e.into());
+                    }
+                };
+                if evicted {
+                    self.state.revert_to_checkpoint();
+                    tracer.trace_failed_call(trace_info, vec![], evm::Error::StorageEvicted.into());
+                    return Err(evm::Error::StorageEvicted);
+                }
+
                 // part of substate that may be reverted
                 let mut unconfirmed_substate = Substate::new();
 
@@ -342,9 +406,10 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 
                 let traces = subtracer.traces();
                 match res {
-                    Ok(ref gas_left) => {
-                        tracer.trace_call(trace_info, gas - *gas_left, trace_output, traces)
+                    Ok(ref res) if res.apply_state => {
+                        tracer.trace_call(trace_info, gas - res.gas_left, trace_output, traces)
                     }
+                    Ok(_) => tracer.trace_failed_call(trace_info, traces, evm::Error::Reverted.into()),
                     Err(ref e) => tracer.trace_failed_call(trace_info, traces, e.into()),
                 };
 
@@ -358,7 +423,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 self.state.discard_checkpoint();
 
                 tracer.trace_call(trace_info, U256::zero(), trace_output, vec![]);
-                Ok(params.gas)
+                Ok(FinalizationResult {
+                       gas_left: params.gas,
+                       apply_state: true,
+                       return_data: vec![],
+                   })
             }
         }
     }
@@ -366,7 +435,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     /// Creates contract with given contract params.
     /// NOTE. It does not finalize the transaction (doesn't do refunds, nor suicides).
     /// Modifies the substate.
-    pub fn create<T, V>(&mut self, params: ActionParams, substate: &mut Substate, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<U256>
+    pub fn create<T, V>(&mut self, params: ActionParams, substate: &mut Substate, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<FinalizationResult>
     where
         T: Tracer,
         V: VMTracer,
@@ -410,9 +479,10 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         vm_tracer.done_subtrace(subvmtracer);
 
         match res {
-            Ok(ref gas_left) => {
-                tracer.trace_create(trace_info, gas - *gas_left, trace_output, created, subtracer.traces())
+            Ok(ref res) if res.apply_state => {
+                tracer.trace_create(trace_info, gas - res.gas_left, trace_output, created, subtracer.traces())
             }
+            Ok(_) => tracer.trace_failed_create(trace_info, subtracer.traces(), evm::Error::Reverted.into()),
             Err(ref e) => tracer.trace_failed_create(trace_info, subtracer.traces(), e.into()),
         };
 
@@ -421,11 +491,8 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     }
 
     /// Finalizes the transaction (does refunds and suicides).
-    fn finalize(&mut self, t: &SignedTransaction, substate: Substate, result: evm::Result<U256>, output: Bytes, trace: Vec<FlatTrace>, vm_trace: Option<VMTrace>) -> ExecutionResult {
-        /*
+    fn finalize(&mut self, t: &SignedTransaction, substate: Substate, result: evm::Result<FinalizationResult>, output: Bytes, trace: Vec<FlatTrace>, vm_trace: Option<VMTrace>) -> ExecutionResult {
         let schedule = self.engine.schedule(self.info);
-         */
-        let schedule = Schedule::new_frontier();
         // refunds from SSTORE nonzero -> zero
         let sstore_refunds = U256::from(schedule.sstore_refund_gas) * substate.sstore_clears_count;
         // refunds from contract suicides
@@ -434,7 +501,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
 
         // real ammount to refund
         let gas_left_prerefund = match result {
-            Ok(x) => x,
+            Ok(ref x) => x.gas_left,
             _ => 0.into(),
         };
         let refunded = cmp::min(refunds_bound, (t.gas - gas_left_prerefund) >> 1);
@@ -485,9 +552,9 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                        state_diff: None,
                    })
             }
-            _ => {
+            Ok(r) => {
                 Ok(Executed {
-                       exception: None,
+                       exception: if r.apply_state { None } else { Some(evm::Error::Reverted) },
                        gas: t.gas,
                        gas_used: gas_used,
                        refunded: refunded,
@@ -503,13 +570,19 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         }
     }
 
-    fn enact_result(&mut self, result: &evm::Result<U256>, substate: &mut Substate, un_substate: Substate) {
+    fn enact_result(&mut self, result: &evm::Result<FinalizationResult>, substate: &mut Substate, un_substate: Substate) {
         match *result {
             Err(evm::Error::OutOfGas) |
             Err(evm::Error::BadJumpDestination { .. }) |
             Err(evm::Error::BadInstruction { .. }) |
             Err(evm::Error::StackUnderflow { .. }) |
-            Err(evm::Error::OutOfStack { .. }) => {
+            Err(evm::Error::OutOfStack { .. }) |
+            Err(evm::Error::MutableCallInStaticContext) |
+            Err(evm::Error::OutOfBounds) |
+            Err(evm::Error::Reverted) |
+            Err(evm::Error::StorageEvicted) |
+            Err(evm::Error::Unauthorized) |
+            Ok(FinalizationResult { apply_state: false, .. }) => {
                 self.state.revert_to_checkpoint();
             }
             Ok(_) |
@@ -590,7 +663,7 @@ mod tests {
 
         let gas_left = {
             let mut ex = Executive::new(&mut state, &info, &engine, &factory);
-            ex.create(params.clone(), &mut substate, &mut tracer, &mut vm_tracer).unwrap()
+            ex.create(params.clone(), &mut substate, &mut tracer, &mut vm_tracer).unwrap().gas_left
         };
 
         println!("{:?}", state.code(&contract_address).unwrap());
@@ -656,7 +729,7 @@ mod tests {
         let gas_left = {
             let mut ex = Executive::new(&mut state, &info, &engine, &factory);
             let mut out = vec![];
-            ex.call(params, &mut substate, BytesRef::Fixed(&mut out), &mut tracer, &mut vm_tracer).unwrap()
+            ex.call(params, &mut substate, BytesRef::Fixed(&mut out), &mut tracer, &mut vm_tracer).unwrap().gas_left
         };
         assert_eq!(gas_left, U256::from(79842));
         assert_eq!(
@@ -666,4 +739,121 @@ mod tests {
             H256::from(&U256::from(0x12345678))
         );
     }
+
+    #[test]
+    fn test_revert() {
+        let factory = Factory::new(VMType::Interpreter, 1024 * 32);
+        let mut tracer = ExecutiveTracer::default();
+        let mut vm_tracer = ExecutiveVMTracer::toplevel();
+
+        // sstore(0, 1) revert(0, 0)
+        let contract_code = "600160005560006000fd".from_hex().unwrap();
+        let contract_addr = Address::from(0x998);
+        let mut state = get_temp_state();
+        state.init_code(&contract_addr, contract_code).unwrap();
+        let mut params = ActionParams::default();
+        params.address = contract_addr.clone();
+        params.code_address = contract_addr.clone();
+        params.gas = U256::from(100_000);
+        params.code = state.code(&contract_addr).unwrap();
+        params.code_hash = state.code_hash(&contract_addr).unwrap();
+        params.call_type = CallType::Call;
+
+        let info = EnvInfo::default();
+        let engine = NullEngine::default().with_byzantium_height(Some(0));
+        let mut substate = Substate::new();
+        let result = {
+            let mut ex = Executive::new(&mut state, &info, &engine, &factory);
+            let mut out = vec![];
+            ex.call(params, &mut substate, BytesRef::Flexible(&mut out), &mut tracer, &mut vm_tracer).unwrap()
+        };
+        assert!(!result.apply_state);
+        assert_eq!(result.gas_left, U256::from(79_988));
+        assert_eq!(state.storage_at(&contract_addr, &H256::from(0)).unwrap(), H256::zero());
+    }
+
+    #[test]
+    fn test_static_call() {
+        let factory = Factory::new(VMType::Interpreter, 1024 * 32);
+        let mut tracer = ExecutiveTracer::default();
+        let mut vm_tracer = ExecutiveVMTracer::toplevel();
+
+        // sstore(0, 1)
+        let callee_code = "600160005500".from_hex().unwrap();
+        let callee_addr = Address::from(0x998);
+        // sstore(0, add(staticcall(0xffff, 0x998, 0, 0, 0, 0), 1))
+        let caller_code = "600060006000600061099861fffffa600101600055".from_hex().unwrap();
+        let caller_addr = Address::from(0x155);
+        let mut state = get_temp_state();
+        state.init_code(&callee_addr, callee_code).unwrap();
+        state.init_code(&caller_addr, caller_code).unwrap();
+        let mut params = ActionParams::default();
+        params.address = caller_addr.clone();
+        params.code_address = caller_addr.clone();
+        params.gas = U256::from(100_000);
+        params.code = state.code(&caller_addr).unwrap();
+        params.code_hash = state.code_hash(&caller_addr).unwrap();
+        params.call_type = CallType::Call;
+
+        let info = EnvInfo::default();
+        let engine = NullEngine::default().with_byzantium_height(Some(0));
+        let mut substate = Substate::new();
+        let result = {
+            let mut ex = Executive::new(&mut state, &info, &engine, &factory);
+            let mut out = vec![];
+            ex.call(params, &mut substate, BytesRef::Flexible(&mut out), &mut tracer, &mut vm_tracer).unwrap()
+        };
+        assert!(result.apply_state);
+        // the call failed, and the callee is unchanged
+        assert_eq!(state.storage_at(&caller_addr, &H256::from(0)).unwrap(), H256::from(1));
+        assert_eq!(state.storage_at(&callee_addr, &H256::from(0)).unwrap(), H256::zero());
+    }
+
+    #[test]
+    fn test_create2() {
+        let factory = Factory::new(VMType::Interpreter, 1024 * 32);
+        let mut tracer = ExecutiveTracer::default();
+        let mut vm_tracer = ExecutiveVMTracer::toplevel();
+
+        // init code returning the code 0x00, deployed twice with the same salt:
+        // mstore(0, 0x60016000f3)
+        // sstore(0, create2(0, 27, 5, 42))
+        // sstore(1, create2(0, 27, 5, 42))
+        let caller_code = "6460016000f3600052602a6005601b6000f5600055602a6005601b6000f5600155".from_hex().unwrap();
+        let caller_addr = Address::from(0x155);
+        let init_code = "60016000f3".from_hex().unwrap();
+        let expected = contract_address_from_salt(&caller_addr, &H256::from(42), &init_code);
+
+        let mut run = |engine: NullEngine| {
+            let mut state = get_temp_state();
+            state.init_code(&caller_addr, caller_code.clone()).unwrap();
+            let mut params = ActionParams::default();
+            params.address = caller_addr.clone();
+            params.code_address = caller_addr.clone();
+            params.gas = U256::from(1_000_000);
+            params.code = state.code(&caller_addr).unwrap();
+            params.code_hash = state.code_hash(&caller_addr).unwrap();
+            params.call_type = CallType::Call;
+
+            let info = EnvInfo::default();
+            let mut substate = Substate::new();
+            let result = {
+                let mut ex = Executive::new(&mut state, &info, &engine, &factory);
+                let mut out = vec![];
+                ex.call(params, &mut substate, BytesRef::Flexible(&mut out), &mut tracer, &mut vm_tracer)
+            };
+            (result.is_ok(), state)
+        };
+
+        // not before the fork height
+        let (ok, _) = run(NullEngine::default().with_create2_height(Some(1)));
+        assert!(!ok);
+
+        let (ok, state) = run(NullEngine::default().with_create2_height(Some(0)));
+        assert!(ok);
+        assert_eq!(state.storage_at(&caller_addr, &H256::from(0)).unwrap(), H256::from(expected));
+        assert_eq!(state.code(&expected).unwrap().map(|c| c.to_vec()), Some(vec![0x00]));
+        // the address is taken
+        assert_eq!(state.storage_at(&caller_addr, &H256::from(1)).unwrap(), H256::zero());
+    }
 }
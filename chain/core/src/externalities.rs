@@ -22,7 +22,7 @@
 use action_params::{ActionParams, ActionValue};
 use engines::Engine;
 use env_info::EnvInfo;
-use evm::{self, MessageCallResult, Schedule, Factory};
+use evm::{self, FinalizationResult, MessageCallResult, CreateContractAddress, Schedule, Factory};
 use executed::CallType;
 use executive::*;
 use state::State;
@@ -83,6 +83,7 @@ where
     output: OutputPolicy<'a, 'a>,
     tracer: &'a mut T,
     vm_tracer: &'a mut V,
+    static_flag: bool,
 }
 
 
@@ -94,7 +95,7 @@ where
 {
     /// Basic `Externalities` constructor.
     #[cfg_attr(feature = "dev", allow(too_many_arguments))]
-    pub fn new(state: &'a mut State<B>, env_info: &'a EnvInfo, engine: &'a Engine, vm_factory: &'a Factory, depth: usize, origin_info: OriginInfo, substate: &'a mut Substate, output: OutputPolicy<'a, 'a>, tracer: &'a mut T, vm_tracer: &'a mut V) -> Self {
+    pub fn new(state: &'a mut State<B>, env_info: &'a EnvInfo, engine: &'a Engine, vm_factory: &'a Factory, depth: usize, origin_info: OriginInfo, substate: &'a mut Substate, output: OutputPolicy<'a, 'a>, tracer: &'a mut T, vm_tracer: &'a mut V, static_flag: bool) -> Self {
         Externalities {
             state: state,
             env_info: env_info,
@@ -103,10 +104,11 @@ where
             depth: depth,
             origin_info: origin_info,
             substate: substate,
-            schedule: Schedule::new_frontier(),
+            schedule: engine.schedule(env_info),
             output: output,
             tracer: tracer,
             vm_tracer: vm_tracer,
+            static_flag: static_flag,
         }
     }
 }
@@ -125,6 +127,17 @@ where
         self.state.set_storage(&self.origin_info.address, key, value)
     }
 
+    fn restore_storage(&mut self, address: &Address, storage: Vec<(H256, H256)>) -> trie::Result<()> {
+        for (key, value) in storage {
+            self.state.set_storage(address, key, value)?;
+        }
+        Ok(())
+    }
+
+    fn storage_of(&self, address: &Address, key: &H256) -> trie::Result<H256> {
+        self.state.storage_at(address, key)
+    }
+
     fn exists(&self, address: &Address) -> trie::Result<bool> {
         self.state.exists(address)
     }
@@ -160,13 +173,29 @@ where
         }
     }
 
-    fn create(&mut self, gas: &U256, value: &U256, code: &[u8]) -> evm::ContractCreateResult {
+    fn create(&mut self, gas: &U256, value: &U256, code: &[u8], address_scheme: CreateContractAddress) -> evm::ContractCreateResult {
         // create new contract address
-        let address = match self.state.nonce(&self.origin_info.address) {
-            Ok(nonce) => contract_address(&self.origin_info.address, &nonce),
-            Err(e) => {
-                debug!(target: "ext", "Database corruption encountered: {:?}", e);
-                return evm::ContractCreateResult::Failed;
+        let address = match address_scheme {
+            CreateContractAddress::FromSenderAndNonce => {
+                match self.state.nonce(&self.origin_info.address) {
+                    Ok(nonce) => contract_address(&self.origin_info.address, &nonce),
+                    Err(e) => {
+                        debug!(target: "ext", "Database corruption encountered: {:?}", e);
+                        return evm::ContractCreateResult::Failed;
+                    }
+                }
+            }
+            CreateContractAddress::FromSenderSaltAndCodeHash(salt) => {
+                let address = contract_address_from_salt(&self.origin_info.address, &salt, code);
+                // the same code with the same salt can be deployed only once
+                match self.state.code_hash(&address) {
+                    Ok(hash) if hash == HASH_EMPTY => address,
+                    Ok(_) => return evm::ContractCreateResult::Failed,
+                    Err(e) => {
+                        debug!(target: "ext", "Database corruption encountered: {:?}", e);
+                        return evm::ContractCreateResult::Failed;
+                    }
+                }
             }
         };
 
@@ -189,14 +218,15 @@ where
             debug!(target: "ext", "Database corruption encountered: {:?}", e);
             return evm::ContractCreateResult::Failed;
         }
-        let mut ex = Executive::from_parent(self.state, self.env_info, self.engine, self.vm_factory, self.depth);
+        let mut ex = Executive::from_parent(self.state, self.env_info, self.engine, self.vm_factory, self.depth, self.static_flag);
 
         // TODO: handle internal error separately
         match ex.create(params, self.substate, self.tracer, self.vm_tracer) {
-            Ok(gas_left) => {
+            Ok(FinalizationResult { gas_left, apply_state: true, .. }) => {
                 self.substate.contracts_created.push(address.clone());
                 evm::ContractCreateResult::Created(address, gas_left)
             }
+            Ok(FinalizationResult { gas_left, apply_state: false, return_data }) => evm::ContractCreateResult::Reverted(gas_left, return_data),
             _ => evm::ContractCreateResult::Failed,
         }
     }
@@ -231,10 +261,11 @@ where
             params.value = ActionValue::Transfer(value);
         }
 
-        let mut ex = Executive::from_parent(self.state, self.env_info, self.engine, self.vm_factory, self.depth);
+        let mut ex = Executive::from_parent(self.state, self.env_info, self.engine, self.vm_factory, self.depth, self.static_flag);
 
         match ex.call(params, self.substate, BytesRef::Fixed(output), self.tracer, self.vm_tracer) {
-            Ok(gas_left) => MessageCallResult::Success(gas_left),
+            Ok(FinalizationResult { gas_left, apply_state: true, return_data }) => MessageCallResult::Success(gas_left, return_data),
+            Ok(FinalizationResult { gas_left, apply_state: false, return_data }) => MessageCallResult::Reverted(gas_left, return_data),
             _ => MessageCallResult::Failed,
         }
     }
@@ -248,7 +279,7 @@ where
     }
 
     #[cfg_attr(feature = "dev", allow(match_ref_pats))]
-    fn ret(mut self, gas: &U256, data: &[u8]) -> evm::Result<U256>
+    fn ret(mut self, gas: &U256, data: &[u8], apply_state: bool) -> evm::Result<U256>
     where
         Self: Sized,
     {
@@ -268,7 +299,7 @@ where
                 vec.extend_from_slice(data);
                 Ok(*gas)
             }
-            OutputPolicy::InitContract(ref mut copy) => {
+            OutputPolicy::InitContract(ref mut copy) if apply_state => {
                 let return_cost = U256::from(data.len()) * U256::from(self.schedule.create_data_gas);
                 if return_cost > *gas || data.len() > self.schedule.create_data_limit {
                     return match self.schedule.exceptional_failed_code_deposit {
@@ -282,6 +313,7 @@ where
                 self.state.init_code(&self.origin_info.address, data.to_vec())?;
                 Ok(*gas - return_cost)
             }
+            OutputPolicy::InitContract(_) => Ok(*gas),
         }
     }
 
@@ -329,6 +361,10 @@ where
         self.depth
     }
 
+    fn is_static(&self) -> bool {
+        self.static_flag
+    }
+
     fn inc_sstore_clears(&mut self) {
         self.substate.sstore_clears_count = self.substate.sstore_clears_count + U256::one();
     }
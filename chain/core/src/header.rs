@@ -18,7 +18,9 @@
 //! Block header.
 
 use basic_types::{LogBloom, ZERO_LOGBLOOM};
-use libproto::blockchain::{Proof, ProofType, BlockHeader};
+use libproto::blockchain::{Proof, ProofType, BlockHeader, BlockHeaderExtra};
+use protobuf::{Message, ProtobufResult};
+use protobuf::core::parse_from_bytes;
 use rlp::*;
 use std::cell::Cell;
 use std::cmp;
@@ -28,6 +30,12 @@ use time::get_time;
 pub use types::BlockNumber;
 use util::*;
 
+/// The latest version of the header. Headers of version 0 have no extra fields,
+/// so they keep the hashes of the blocks before the versions.
+pub const HEADER_VERSION: u32 = 1;
+/// Items of the RLP of a header of version 0.
+const HEADER_ITEMS_V0: usize = 11;
+
 /// A block header.
 ///
 #[derive(Debug, PartialEq, Clone, Eq)]
@@ -70,11 +78,14 @@ pub struct Header {
     hash: HashWrap,
     /// The version of the header.
     version: u32,
+    /// Protobuf encoded `BlockHeaderExtra`, kept as it is so that a node which does
+    /// not know the fields added later hashes the same bytes.
+    extra: Bytes,
 }
 
 impl PartialEq for Header {
     fn eq(&self, c: &Header) -> bool {
-        self.parent_hash == c.parent_hash && self.timestamp == c.timestamp && self.number == c.number && self.transactions_root == c.transactions_root && self.state_root == c.state_root && self.receipts_root == c.receipts_root && self.log_bloom == c.log_bloom && self.gas_used == c.gas_used && self.gas_limit == c.gas_limit && self.proof == c.proof
+        self.parent_hash == c.parent_hash && self.timestamp == c.timestamp && self.number == c.number && self.transactions_root == c.transactions_root && self.state_root == c.state_root && self.receipts_root == c.receipts_root && self.log_bloom == c.log_bloom && self.gas_used == c.gas_used && self.gas_limit == c.gas_limit && self.proof == c.proof && self.extra == c.extra
 
     }
 }
@@ -94,6 +105,7 @@ impl Default for Header {
             proof: Proof::new(),
             hash: HashWrap(Cell::new(None)),
             version: 0,
+            extra: Bytes::new(),
         }
     }
 }
@@ -112,6 +124,7 @@ impl From<BlockHeader> for Header {
             gas_limit: U256::from(u64::max_value()),
             proof: bh.get_proof().clone(),
             version: 0,
+            extra: bh.get_extra().to_vec(),
             hash: HashWrap(Cell::new(None)),
         }
     }
@@ -167,6 +180,18 @@ impl Header {
     pub fn version(&self) -> u32 {
         self.version
     }
+    /// Get the encoded extra fields of the header.
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+    /// Decode the extra fields of the header.
+    pub fn extra_fields(&self) -> ProtobufResult<BlockHeaderExtra> {
+        parse_from_bytes(&self.extra)
+    }
+    /// Whether the version is known and the header of version 0 has no extra fields.
+    pub fn is_valid_version(&self) -> bool {
+        self.version <= HEADER_VERSION && (self.version > 0 || self.extra.is_empty())
+    }
     /// Get the proof type field of the header.
     pub fn proof_type(&self) -> Option<ProofType> {
         if self.proof == Proof::new() { None } else { Some(self.proof.get_field_type()) }
@@ -232,6 +257,11 @@ impl Header {
         self.proof = a;
         self.note_dirty();
     }
+    /// Set the extra fields of the header, taken from version 1.
+    pub fn set_extra_fields(&mut self, a: &BlockHeaderExtra) {
+        self.extra = a.write_to_bytes().expect("BlockHeaderExtra always encodes.");
+        self.note_dirty();
+    }
 
     /// Get the hash of this header (sha3 of the RLP).
     pub fn hash(&self) -> H256 {
@@ -254,7 +284,11 @@ impl Header {
     // TODO: make these functions traity
     /// Place this header into an RLP stream `s`.
     pub fn stream_rlp(&self, s: &mut RlpStream) {
-        s.begin_list(11);
+        if self.version == 0 {
+            s.begin_list(HEADER_ITEMS_V0);
+        } else {
+            s.begin_list(HEADER_ITEMS_V0 + 1);
+        }
         s.append(&self.parent_hash);
         s.append(&self.state_root);
         s.append(&self.transactions_root);
@@ -266,7 +300,9 @@ impl Header {
         s.append(&self.timestamp);
         s.append(&self.version);
         s.append(&self.proof);
-
+        if self.version > 0 {
+            s.append(&self.extra);
+        }
     }
 
     /// Get the RLP of this header.
@@ -292,6 +328,19 @@ impl Header {
         bh.set_transactions_root(self.transactions_root.to_vec());
         bh.set_gas_used(u64::from(self.gas_used));
         bh.set_proof(self.proof.clone());
+        bh.set_extra(self.extra.clone());
+        bh
+    }
+
+    /// Generate the protobuf header proposed in consensus, its hash is the one signed by the validators.
+    pub fn proposal_protobuf(&self) -> BlockHeader {
+        let mut bh = BlockHeader::new();
+        bh.set_prevhash(self.parent_hash.to_vec());
+        bh.set_timestamp(self.timestamp);
+        bh.set_height(self.number);
+        bh.set_transactions_root(self.transactions_root.to_vec());
+        bh.set_proof(self.proof.clone());
+        bh.set_extra(self.extra.clone());
         bh
     }
 }
@@ -310,6 +359,7 @@ impl Decodable for Header {
             timestamp: cmp::min(r.val_at::<U256>(8)?, u64::max_value().into()).as_u64(),
             version: r.val_at(9)?,
             proof: r.val_at(10)?,
+            extra: if r.item_count()? > HEADER_ITEMS_V0 { r.val_at(11)? } else { Bytes::new() },
             hash: HashWrap(Cell::new(Some(r.as_raw().crypt_hash()))),
         };
 
@@ -332,6 +382,8 @@ impl HeapSizeOf for Header {
 #[cfg(test)]
 mod tests {
     use super::Header;
+    use libproto::blockchain::BlockHeaderExtra;
+    use protobuf::Message;
     use rlp;
 
     #[test]
@@ -344,4 +396,32 @@ mod tests {
 
         assert_eq!(header_rlp, encoded_header);
     }
+
+    #[test]
+    fn header_version() {
+        let mut header = Header::new();
+        header.set_number(42);
+        let hash = header.hash();
+        assert!(header.is_valid_version());
+        assert_eq!(rlp::UntrustedRlp::new(&header.rlp()).item_count().unwrap(), 11);
+
+        // a header of version 0 has no extra fields
+        header.set_extra_fields(&BlockHeaderExtra::new());
+        assert_eq!(header.hash(), hash);
+        header.extra = vec![0x08, 0x01];
+        assert!(!header.is_valid_version());
+
+        header.set_version(1);
+        assert!(header.is_valid_version());
+        assert_ne!(header.hash(), hash);
+        let decoded: Header = rlp::decode(&header.rlp());
+        assert_eq!(decoded, header);
+        assert_eq!(decoded.hash(), header.hash());
+        assert_eq!(decoded.extra(), &[0x08, 0x01]);
+        // the fields unknown to this version are kept
+        assert_eq!(decoded.extra_fields().unwrap().write_to_bytes().unwrap(), vec![0x08, 0x01]);
+
+        header.set_version(super::HEADER_VERSION + 1);
+        assert!(!header.is_valid_version());
+    }
 }
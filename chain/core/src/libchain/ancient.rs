@@ -0,0 +1,214 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Append-only store of the ancient blocks, moved out of the database once
+//! they are older than `ancient_blocks`, so that they may live on a cheaper disk.
+//!
+//! The bodies and the receipts are kept in two tables, each of which is a data
+//! file of the values as they were stored in the database and an index file of
+//! the end offsets, entry `n` being the block of height `n`. Blocks missing in
+//! the database, like the ones before a restored snapshot, are kept empty.
+
+use byteorder::{BigEndian, ByteOrder};
+use std::cmp;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const INDEX_ENTRY_SIZE: u64 = 8;
+
+struct Table {
+    data: File,
+    index: File,
+    len: u64,
+}
+
+impl Table {
+    fn open(dir: &Path, name: &str) -> io::Result<Self> {
+        let open = |file: String| OpenOptions::new().read(true).write(true).create(true).open(dir.join(file));
+        let mut table = Table {
+            data: open(format!("{}.dat", name))?,
+            index: open(format!("{}.idx", name))?,
+            len: 0,
+        };
+        // Drop what an interrupted append left behind.
+        let data_len = table.data.metadata()?.len();
+        let mut len = table.index.metadata()?.len() / INDEX_ENTRY_SIZE;
+        while len > 0 && table.end(len - 1)? > data_len {
+            len -= 1;
+        }
+        table.truncate(len)?;
+        Ok(table)
+    }
+
+    fn end(&mut self, n: u64) -> io::Result<u64> {
+        let mut buf = [0u8; INDEX_ENTRY_SIZE as usize];
+        self.index.seek(SeekFrom::Start(n * INDEX_ENTRY_SIZE))?;
+        self.index.read_exact(&mut buf)?;
+        Ok(BigEndian::read_u64(&buf))
+    }
+
+    fn start(&mut self, n: u64) -> io::Result<u64> {
+        if n == 0 {
+            Ok(0)
+        } else {
+            self.end(n - 1)
+        }
+    }
+
+    fn get(&mut self, n: u64) -> io::Result<Option<Vec<u8>>> {
+        if n >= self.len {
+            return Ok(None);
+        }
+        let start = self.start(n)?;
+        let end = self.end(n)?;
+        if start == end {
+            return Ok(None);
+        }
+        let mut value = vec![0u8; (end - start) as usize];
+        self.data.seek(SeekFrom::Start(start))?;
+        self.data.read_exact(&mut value)?;
+        Ok(Some(value))
+    }
+
+    fn append(&mut self, value: &[u8]) -> io::Result<()> {
+        let len = self.len;
+        let end = self.start(len)? + value.len() as u64;
+        let mut buf = [0u8; INDEX_ENTRY_SIZE as usize];
+        BigEndian::write_u64(&mut buf, end);
+        self.data.seek(SeekFrom::End(0))?;
+        self.data.write_all(value)?;
+        self.index.seek(SeekFrom::Start(len * INDEX_ENTRY_SIZE))?;
+        self.index.write_all(&buf)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        let end = self.start(len)?;
+        self.index.set_len(len * INDEX_ENTRY_SIZE)?;
+        self.data.set_len(end)?;
+        self.len = len;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.sync_data()?;
+        self.index.sync_data()
+    }
+}
+
+pub struct AncientStore {
+    bodies: Table,
+    receipts: Table,
+}
+
+impl AncientStore {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut store = AncientStore {
+            bodies: Table::open(dir, "bodies")?,
+            receipts: Table::open(dir, "receipts")?,
+        };
+        let len = cmp::min(store.bodies.len, store.receipts.len);
+        store.truncate(len)?;
+        Ok(store)
+    }
+
+    /// Blocks in the store, the next one appended is of the height.
+    pub fn len(&self) -> u64 {
+        self.bodies.len
+    }
+
+    /// Append the block of height `len()`, an empty value is kept as missing.
+    pub fn append(&mut self, body: &[u8], receipts: &[u8]) -> io::Result<()> {
+        self.bodies.append(body)?;
+        self.receipts.append(receipts)
+    }
+
+    pub fn body(&mut self, number: u64) -> io::Result<Option<Vec<u8>>> {
+        self.bodies.get(number)
+    }
+
+    pub fn receipts(&mut self, number: u64) -> io::Result<Option<Vec<u8>>> {
+        self.receipts.get(number)
+    }
+
+    /// Drop the blocks from the height `len` on.
+    pub fn truncate(&mut self, len: u64) -> io::Result<()> {
+        if len < self.bodies.len {
+            self.bodies.truncate(len)?;
+        }
+        if len < self.receipts.len {
+            self.receipts.truncate(len)?;
+        }
+        Ok(())
+    }
+
+    /// Make the appended blocks durable, before they are deleted from the database.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.bodies.flush()?;
+        self.receipts.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate mktemp;
+
+    use super::*;
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn append_and_get() {
+        let dir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let mut store = AncientStore::open(&dir).unwrap();
+        store.append(b"body0", b"receipts0").unwrap();
+        store.append(b"", b"").unwrap();
+        store.append(b"body2", b"receipts2").unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.body(0).unwrap(), Some(b"body0".to_vec()));
+        assert_eq!(store.body(1).unwrap(), None);
+        assert_eq!(store.receipts(2).unwrap(), Some(b"receipts2".to_vec()));
+        assert_eq!(store.body(3).unwrap(), None);
+
+        store.truncate(1).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.body(2).unwrap(), None);
+        store.append(b"body1", b"receipts1").unwrap();
+        assert_eq!(store.body(1).unwrap(), Some(b"body1".to_vec()));
+    }
+
+    #[test]
+    fn repair_interrupted_append() {
+        let dir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        {
+            let mut store = AncientStore::open(&dir).unwrap();
+            store.append(b"body0", b"receipts0").unwrap();
+            store.append(b"body1", b"receipts1").unwrap();
+            store.flush().unwrap();
+        }
+        // the index entry of the last body is written, but not all of its data
+        let data = OpenOptions::new().write(true).open(dir.join("bodies.dat")).unwrap();
+        data.set_len(7).unwrap();
+
+        let mut store = AncientStore::open(&dir).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.body(0).unwrap(), Some(b"body0".to_vec()));
+        assert_eq!(store.receipts(1).unwrap(), None);
+    }
+}
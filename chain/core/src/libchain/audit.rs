@@ -0,0 +1,188 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Audit of the execution, to find the transaction executed differently by two nodes.
+//!
+//! An auditing node records for each transaction the quota it used and a digest of
+//! the state it read and written. The audits are exported into a file, one block of
+//! json a line, and compared with the ones of another node block by block.
+
+use header::BlockNumber;
+use libchain::chain::Chain;
+use rlp::{Decodable, DecoderError, Encodable, RlpStream, UntrustedRlp};
+use serde_json;
+use state::AccessLog;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use util::{H256, Hashable};
+
+/// Audit of a transaction, the transactions not applied have an empty log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxAudit {
+    pub hash: H256,
+    pub quota_used: u64,
+    /// Hash of the quota used and the state accessed.
+    pub digest: H256,
+}
+
+impl TxAudit {
+    pub fn new(hash: H256, quota_used: u64, log: &AccessLog) -> Self {
+        let mut s = RlpStream::new_list(2);
+        s.append(&quota_used);
+        s.append(log);
+        TxAudit {
+            hash: hash,
+            quota_used: quota_used,
+            digest: s.out().crypt_hash(),
+        }
+    }
+}
+
+/// Audits of the transactions of a block, in the order of the block.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct BlockAudit {
+    pub height: BlockNumber,
+    pub transactions: Vec<TxAudit>,
+}
+
+impl Encodable for BlockAudit {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+        s.append(&self.height);
+        s.begin_list(self.transactions.len());
+        for tx in &self.transactions {
+            s.begin_list(3).append(&tx.hash).append(&tx.quota_used).append(&tx.digest);
+        }
+    }
+}
+
+impl Decodable for BlockAudit {
+    fn decode(r: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let mut transactions = Vec::new();
+        for tx in r.at(1)?.iter() {
+            transactions.push(TxAudit {
+                                  hash: tx.val_at(0)?,
+                                  quota_used: tx.val_at(1)?,
+                                  digest: tx.val_at(2)?,
+                              });
+        }
+        Ok(BlockAudit {
+               height: r.val_at(0)?,
+               transactions: transactions,
+           })
+    }
+}
+
+/// The first transaction whose audits differ, `None` for the one missing on a side.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub height: BlockNumber,
+    pub index: usize,
+    pub local: Option<TxAudit>,
+    pub remote: Option<TxAudit>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "block {} transaction {}", self.height, self.index)?;
+        write_side(f, "local", &self.local)?;
+        write_side(f, "remote", &self.remote)
+    }
+}
+
+fn write_side(f: &mut fmt::Formatter, side: &str, audit: &Option<TxAudit>) -> fmt::Result {
+    match *audit {
+        Some(ref tx) => write!(f, ", {} {:?} quota {} digest {:?}", side, tx.hash, tx.quota_used, tx.digest),
+        None => write!(f, ", {} missing", side),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Json(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+/// Write the audits of the blocks of the range into the writer, skipping the blocks
+/// executed while the node was not auditing. Returns the number of blocks written.
+pub fn export_audits<W: Write>(chain: &Chain, range: Range<BlockNumber>, writer: &mut W) -> Result<u64, Error> {
+    let mut count = 0;
+    for height in range {
+        if let Some(audit) = chain.block_audit(height) {
+            serde_json::to_writer(&mut *writer, &audit)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Compare the audits of the chain with the ones exported by another node,
+/// returning the first transaction which differs. The blocks audited by one side only are skipped.
+pub fn compare_audits<R: BufRead>(chain: &Chain, reader: R) -> Result<Option<Mismatch>, Error> {
+    let mut count = 0;
+    for line in reader.lines() {
+        let remote: BlockAudit = serde_json::from_str(&line?)?;
+        let local = match chain.block_audit(remote.height) {
+            Some(local) => local,
+            None => continue,
+        };
+        if let Some(mismatch) = compare_block(&local, &remote) {
+            return Ok(Some(mismatch));
+        }
+        count += 1;
+    }
+    info!("audits of {} blocks are the same", count);
+    Ok(None)
+}
+
+fn compare_block(local: &BlockAudit, remote: &BlockAudit) -> Option<Mismatch> {
+    let len = ::std::cmp::max(local.transactions.len(), remote.transactions.len());
+    (0..len)
+        .find(|&i| local.transactions.get(i) != remote.transactions.get(i))
+        .map(|i| {
+                 Mismatch {
+                     height: local.height,
+                     index: i,
+                     local: local.transactions.get(i).cloned(),
+                     remote: remote.transactions.get(i).cloned(),
+                 }
+             })
+}
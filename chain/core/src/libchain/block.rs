@@ -15,29 +15,36 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use engines::NullEngine;
 use env_info::EnvInfo;
 use env_info::LastHashes;
 use error::{Error, ExecutionError};
 use factory::Factories;
 use header::*;
+use libchain::audit::{BlockAudit, TxAudit};
 use libchain::chain::TransactionHash;
 use libchain::extras::TransactionAddress;
 
 use libproto::blockchain::{Block as ProtoBlock, BlockBody as ProtoBlockBody};
 use libproto::blockchain::SignedTransaction as ProtoSignedTransaction;
+use native;
 use protobuf::RepeatedField;
 use receipt::Receipt;
 use rlp::*;
-use state::State;
+use state::{AccessLog, State};
 use state_db::StateDB;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use trace::FlatTrace;
-use types::transaction::SignedTransaction;
-use util::{U256, H256, Address, merklehash, HeapSizeOf};
+use types::state_diff::StateDiff;
+use types::transaction::{Action, SignedTransaction};
+use util::{U256, H256, Address, merklehash, HeapSizeOf, HASH_NULL_RLP};
 use basic_types::LogBloom;
 
+/// The least quota a transaction uses, the base cost charged by the executive.
+pub const TRANSACTION_BASE_QUOTA: u64 = 100;
+
 /// Trait for a object that has a state database.
 pub trait Drain {
     /// Drop this object and return the underlieing database.
@@ -203,6 +210,10 @@ pub struct ClosedBlock {
     pub transactions_dup: HashMap<H256, TransactionAddress>,
     pub receipts: Vec<Option<Receipt>>,
     pub state: State<StateDB>,
+    /// Accounts changed by the block, if recorded.
+    pub state_diff: Option<StateDiff>,
+    /// Audit of the execution, if recorded.
+    pub audit: Option<BlockAudit>,
 }
 
 impl Drain for ClosedBlock {
@@ -233,6 +244,8 @@ pub struct ExecutedBlock {
     pub state: State<StateDB>,
     pub current_gas_used: U256,
     traces: Option<Vec<Vec<FlatTrace>>>,
+    state_diff: Option<StateDiff>,
+    audit: Option<BlockAudit>,
 }
 
 impl Deref for ExecutedBlock {
@@ -250,13 +263,23 @@ impl DerefMut for ExecutedBlock {
 }
 
 impl ExecutedBlock {
-    fn new(block: Block, state: State<StateDB>, tracing: bool) -> ExecutedBlock {
+    fn new(block: Block, state: State<StateDB>, tracing: bool, state_diffing: bool, auditing: bool) -> ExecutedBlock {
+        let audit = if auditing {
+            Some(BlockAudit {
+                     height: block.number(),
+                     transactions: Vec::new(),
+                 })
+        } else {
+            None
+        };
         ExecutedBlock {
             block: block,
             receipts: Default::default(),
             state: state,
             current_gas_used: U256::zero(),
             traces: if tracing { Some(Vec::new()) } else { None },
+            state_diff: if state_diffing { Some(StateDiff { raw: Default::default() }) } else { None },
+            audit: audit,
         }
     }
 
@@ -275,6 +298,30 @@ pub struct OpenBlock {
     exec_block: ExecutedBlock,
     last_hashes: Arc<LastHashes>,
     tx_hashes: Vec<bool>,
+    // quota the block may use, read from the quota manager at the parent state
+    quota_limit: Option<U256>,
+    // quota an account may be charged in the block, and the quota charged so far.
+    // Accounts hold no balance, so the quota is the only cost of a transaction.
+    account_quota_limit: Option<U256>,
+    account_quota_used: HashMap<Address, U256>,
+    // blocks the storage of a contract is kept without being touched, read from the storage rent contract
+    storage_expiry: Option<u64>,
+    // accounts allowed to send transactions and to deploy contracts, read from the permission manager,
+    // anyone may if empty
+    senders_permitted: HashSet<Address>,
+    creators_permitted: HashSet<Address>,
+    engine: NullEngine,
+}
+
+// Accounts in the list of the permission manager in the state.
+fn permitted_accounts(state: &State<StateDB>, list: u64) -> Result<HashSet<Address>, Error> {
+    let address = native::permission_manager_address();
+    let count = U256::from(&*state.storage_at(&address, &native::permission_key(list, 0))?).low_u64();
+    let mut accounts = HashSet::new();
+    for i in 0..count {
+        accounts.insert(Address::from(state.storage_at(&address, &native::permission_key(list, i + 1))?));
+    }
+    Ok(accounts)
 }
 
 impl Deref for OpenBlock {
@@ -292,12 +339,27 @@ impl DerefMut for OpenBlock {
 }
 
 impl OpenBlock {
-    pub fn new(factories: Factories, tracing: bool, block: Block, db: StateDB, state_root: H256, last_hashes: Arc<LastHashes>) -> Result<Self, Error> {
+    pub fn new(factories: Factories, tracing: bool, state_diffing: bool, auditing: bool, block: Block, db: StateDB, state_root: H256, last_hashes: Arc<LastHashes>, engine: NullEngine) -> Result<Self, Error> {
         let state = State::from_existing(db, state_root, U256::default(), factories)?;
+        let quota_limit = state.storage_at(&native::quota_manager_address(), &H256::from(native::BLOCK_QUOTA_LIMIT_KEY))?;
+        let account_quota_limit = state.storage_at(&native::quota_manager_address(), &H256::from(native::ACCOUNT_QUOTA_LIMIT_KEY))?;
+        let storage_expiry = U256::from(&*state.storage_at(&native::storage_rent_address(), &H256::from(native::STORAGE_EXPIRY_KEY))?).low_u64();
+        let senders_permitted = permitted_accounts(&state, native::SEND_PERMISSION)?;
+        let creators_permitted = permitted_accounts(&state, native::CREATE_PERMISSION)?;
+        if storage_expiry != 0 {
+            state.track_storage_touches();
+        }
         let r = OpenBlock {
-            exec_block: ExecutedBlock::new(block, state, tracing),
+            exec_block: ExecutedBlock::new(block, state, tracing, state_diffing, auditing),
             last_hashes: last_hashes,
             tx_hashes: Vec::new(),
+            quota_limit: if quota_limit.is_zero() { None } else { Some(U256::from(&*quota_limit)) },
+            account_quota_limit: if account_quota_limit.is_zero() { None } else { Some(U256::from(&*account_quota_limit)) },
+            account_quota_used: HashMap::new(),
+            storage_expiry: if storage_expiry == 0 { None } else { Some(storage_expiry) },
+            senders_permitted: senders_permitted,
+            creators_permitted: creators_permitted,
+            engine: engine,
         };
 
         Ok(r)
@@ -319,35 +381,175 @@ impl OpenBlock {
     ///execute transactions
     pub fn apply_transactions(&mut self) {
         for t in self.body.transactions.clone() {
-            self.apply_transaction(&t);
+            let quota_left = self.quota_left();
+            if quota_left.map_or(false, |left| left < U256::from(TRANSACTION_BASE_QUOTA)) {
+                trace!("block quota limit reached, transaction {} not applied", t.hash());
+                self.receipts.push(None);
+                self.tx_hashes.push(false);
+                self.audit_transaction(t.hash(), 0, AccessLog::default());
+                continue;
+            }
+            if !self.permitted(&t) {
+                trace!("{} is not permitted, transaction {} not applied", t.sender(), t.hash());
+                self.receipts.push(None);
+                self.tx_hashes.push(false);
+                self.audit_transaction(t.hash(), 0, AccessLog::default());
+                continue;
+            }
+            if self.account_quota_exhausted(t.quota_payer()) {
+                trace!("account quota limit of {} reached, transaction {} not applied", t.quota_payer(), t.hash());
+                self.receipts.push(None);
+                self.tx_hashes.push(false);
+                self.audit_transaction(t.hash(), 0, AccessLog::default());
+                continue;
+            }
+            self.apply_transaction_within(&t, quota_left);
+        }
+        if let Some(expiry) = self.storage_expiry {
+            self.expire_storage(expiry).expect("storage expiry trie error");
+        }
+        if self.state_diff.is_some() {
+            self.state_diff = Some(self.state.diff().expect("diff trie error"));
         }
         self.state.commit().expect("commit trie error");
         let gas_used = self.current_gas_used;
         self.set_gas_used(gas_used);
     }
 
+    /// The quota the block may still use under the limit, None if unlimited.
+    fn quota_left(&self) -> Option<U256> {
+        self.quota_limit.map(|limit| if self.current_gas_used < limit { limit - self.current_gas_used } else { U256::zero() })
+    }
+
+    /// The same for the account paying the quota, which is the sponsor of the transaction if any.
+    fn account_quota_exhausted(&self, payer: &Address) -> bool {
+        match (self.account_quota_limit, self.account_quota_used.get(payer)) {
+            (Some(limit), Some(used)) => *used >= limit,
+            _ => false,
+        }
+    }
+
+    /// Whether the sender may send the transaction, and deploy the contract if it creates one.
+    fn permitted(&self, t: &SignedTransaction) -> bool {
+        let sender = t.sender();
+        let may_send = self.senders_permitted.is_empty() || self.senders_permitted.contains(sender);
+        let may_create = t.action != Action::Create || self.creators_permitted.is_empty() || self.creators_permitted.contains(sender);
+        may_send && may_create
+    }
+
+    /// Record the height the contracts touched in the block were touched at, then evict the
+    /// storage of the contracts last touched `expiry` blocks ago.
+    fn expire_storage(&mut self, expiry: u64) -> Result<(), Error> {
+        let height = self.number();
+        let rent = native::storage_rent_address();
+        let touched: Vec<Address> = self.state.take_storage_touches().into_iter().filter(|a| self.engine.get_native_contract(a).is_none()).collect();
+        let state = &mut self.exec_block.state;
+        for (index, address) in touched.iter().enumerate() {
+            state.set_storage(&rent, native::touched_key(address), H256::from(height))?;
+            state.set_storage(&rent, native::expiry_queue_key(height, index as u64), H256::from(*address))?;
+        }
+        if !touched.is_empty() {
+            state.set_storage(&rent, native::expiry_queue_len_key(height), H256::from(touched.len() as u64))?;
+        }
+
+        if height < expiry {
+            return Ok(());
+        }
+        let expired = height - expiry;
+        let len = U256::from(&*state.storage_at(&rent, &native::expiry_queue_len_key(expired))?).low_u64();
+        for index in 0..len {
+            let key = native::expiry_queue_key(expired, index);
+            let address = Address::from(state.storage_at(&rent, &key)?);
+            state.set_storage(&rent, key, H256::default())?;
+            // touched again since, so queued at a later height
+            if U256::from(&*state.storage_at(&rent, &native::touched_key(&address))?).low_u64() != expired {
+                continue;
+            }
+            state.set_storage(&rent, native::touched_key(&address), H256::default())?;
+            if !state.exists(&address)? {
+                continue;
+            }
+            let root = state.evict_storage(&address)?;
+            if root != HASH_NULL_RLP {
+                trace!("storage of {} expired at height {}", address, height);
+                state.set_storage(&rent, native::evicted_key(&address), root)?;
+            }
+        }
+        state.set_storage(&rent, native::expiry_queue_len_key(expired), H256::default())?;
+        // the expiry reads are not touches
+        state.take_storage_touches();
+        Ok(())
+    }
+
+    /// Record the audit of the transaction, if the block is audited.
+    fn audit_transaction(&mut self, hash: H256, quota_used: u64, log: AccessLog) {
+        if let Some(ref mut audit) = self.exec_block.audit {
+            audit.transactions.push(TxAudit::new(hash, quota_used, &log));
+        }
+    }
+
     pub fn apply_transaction(&mut self, t: &SignedTransaction) {
+        self.apply_transaction_within(t, None);
+    }
+
+    /// Apply the transaction with the quota it may use capped to the quota left in the block.
+    /// A transaction running out of the capped quota would take the block over its limit,
+    /// so it is reverted and not applied, as if it came after the limit.
+    fn apply_transaction_within(&mut self, t: &SignedTransaction, quota_left: Option<U256>) {
+        let capped = match quota_left {
+            Some(left) if left < t.gas => {
+                let mut capped = t.clone();
+                capped.gas = left;
+                Some(capped)
+            }
+            _ => None,
+        };
+        let t = capped.as_ref().unwrap_or(t);
         let env_info = self.env_info();
         let has_traces = self.traces.is_some();
         info!("env_info says gas_used={}", env_info.gas_used);
-        match self.state.apply(&env_info, &t, has_traces) {
+        let gas_used = self.current_gas_used;
+        if self.audit.is_some() {
+            self.state.start_access_log();
+        }
+        self.exec_block.state.checkpoint();
+        match self.exec_block.state.apply(&env_info, &self.engine, &t, has_traces) {
+            Ok(ref outcome) if capped.is_some() && outcome.receipt.gas_used - self.current_gas_used >= t.gas => {
+                trace!("block quota limit reached by transaction {}, not applied", t.hash());
+                self.exec_block.state.revert_to_checkpoint();
+                self.receipts.push(None);
+                self.tx_hashes.push(false);
+            }
             Ok(outcome) => {
+                self.exec_block.state.discard_checkpoint();
                 let trace = outcome.trace;
                 trace!("apply signed transaction {} success", t.hash());
                 self.traces.as_mut().map(|tr| tr.push(trace));
+                {
+                    let quota_used = outcome.receipt.gas_used - self.current_gas_used;
+                    let charged = self.account_quota_used.entry(*t.quota_payer()).or_insert_with(U256::zero);
+                    *charged = *charged + quota_used;
+                }
                 self.current_gas_used = outcome.receipt.gas_used;
                 self.receipts.push(Some(outcome.receipt));
                 self.tx_hashes.push(false);
             }
             Err(Error::Execution(ExecutionError::InvalidNonce { expected: _, got: _ })) => {
+                self.exec_block.state.discard_checkpoint();
                 self.receipts.push(None);
                 self.tx_hashes.push(true);
             }
             Err(_) => {
+                self.exec_block.state.discard_checkpoint();
                 self.receipts.push(None);
                 self.tx_hashes.push(false);
             }
         }
+        if self.audit.is_some() {
+            let log = self.state.take_access_log().expect("audit trie error").unwrap_or_default();
+            let quota_used = self.current_gas_used - gas_used;
+            self.audit_transaction(t.hash(), quota_used.low_u64(), log);
+        }
     }
 
     /// Turn this into a `ClosedBlock`.
@@ -387,6 +589,8 @@ impl OpenBlock {
             transactions_dup: transactions_dup,
             receipts: self.receipts.clone(),
             state: self.state.clone(),
+            state_diff: self.state_diff.clone(),
+            audit: self.audit.clone(),
         }
     }
 }
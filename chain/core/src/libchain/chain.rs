@@ -32,29 +32,42 @@ use factory::*;
 use filters::{PollManager, PollFilter};
 use header::*;
 pub use libchain::block::*;
+use libchain::ancient::AncientStore;
+use libchain::audit::BlockAudit;
 use libchain::cache::CacheSize;
 use libchain::call_request::CallRequest;
 use libchain::extras::*;
 
+use libchain::config::{Config, NodeMode};
 use libchain::genesis::Genesis;
+use libchain::snapshot::SNAPSHOT_BLOCKS;
 pub use libchain::transaction::*;
 use libproto::blockchain::{ProofType, Status as ProtoStatus};
 use libproto::request::FullTransaction;
-use proof::TendermintProof;
+use libproto::TRANSACTION_VERSION;
+use native;
+use proof::{TendermintProof, LightClientProof};
 use receipt::{Receipt, LocalizedReceipt};
+use rlp::Decodable;
 use state::State;
 use state_db::StateDB;
 
 use std::collections::{BTreeMap, VecDeque};
 use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::path::Path;
+use std::process;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering, AtomicBool};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use types::basic_account::BasicAccount;
 use types::filter::Filter;
 use types::ids::{BlockId, TransactionId};
 use types::log_entry::{LogEntry, LocalizedLogEntry};
+use types::state_diff::StateDiff;
 use types::transaction::{SignedTransaction, Transaction, Action};
-use util::{journaldb, H256, U256, H2048, Address, Bytes};
+use util::{journaldb, JournalDB, H256, U256, H2048, Address, Bytes};
 use util::{RwLock, Mutex};
 use util::HeapSizeOf;
 use util::kvdb::*;
@@ -63,6 +76,21 @@ use util::trie::{TrieFactory, TrieSpec};
 pub const VERSION: u32 = 0;
 const LOG_BLOOMS_LEVELS: usize = 3;
 const LOG_BLOOMS_ELEMENTS_PER_INDEX: usize = 16;
+pub const JOURNAL_ALGORITHM_KEY: &'static [u8] = b"journal_algorithm";
+// Eras canonicalized in one batch when pruning the journal at startup.
+const PRUNE_BATCH_ERAS: u64 = 1000;
+pub const COMPRESSION_KEY: &'static [u8] = b"compression";
+// Values compressed in one batch when migrating an older db.
+const COMPRESS_BATCH_SIZE: usize = 1000;
+// Blocks indexed in one batch when rebuilding the log blooms.
+const BLOOMS_BATCH_SIZE: u64 = 1000;
+// Blocks moved into the ancient store at a time.
+const ANCIENT_BATCH_SIZE: u64 = 1000;
+pub const ACCOUNT_INDEX_KEY: &'static [u8] = b"account_index";
+// Blocks indexed in one batch when building the account transactions index.
+const ACCOUNT_INDEX_BATCH_SIZE: u64 = 1000;
+// Blocks whose commit rounds consensus replays to skip the proposers missing their slots.
+pub const COMMIT_ROUNDS_WINDOW: u64 = 256;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum BlockSource {
@@ -143,10 +171,14 @@ pub struct Chain {
     pub block_map: RwLock<BTreeMap<u64, (BlockSource, Block)>>,
     pub db: Arc<KeyValueDB>,
     pub sync_sender: Mutex<Sender<u64>>,
+    // heights of the blocks buffered for the committer to flush
+    commit_sender: Mutex<Sender<BlockNumber>>,
     pub state_db: StateDB,
     pub factories: Factories,
     // Hash of the given block - only works for 256 most recent blocks excluding current
     pub last_hashes: RwLock<VecDeque<H256>>,
+    // heights and rounds of the commits carried by the last COMMIT_ROUNDS_WINDOW blocks
+    commit_rounds: RwLock<VecDeque<(u64, u64)>>,
 
     // block cache
     block_headers: RwLock<HashMap<H256, Header>>,
@@ -160,12 +192,60 @@ pub struct Chain {
 
     cache_man: Mutex<CacheManager<CacheId>>,
     polls_filter: Arc<Mutex<PollManager<PollFilter>>>,
+
+    // validators signing the blocks before the key rotations recorded on chain, taken from the genesis spec
+    genesis_authorities: Vec<Address>,
+
+    // states of the recent blocks kept, None for an archive node
+    pruning_history: Option<u64>,
+    mode: NodeMode,
+    // blocks older than ancient_blocks are moved into the ancient store, 0 for none
+    ancient: Option<Mutex<AncientStore>>,
+    ancient_blocks: u64,
+    state_diffing: bool,
+    auditing: bool,
+    // height from which the EVM supports the Byzantium opcodes, taken from the genesis spec
+    byzantium_height: Option<BlockNumber>,
+    // height from which the EVM supports CREATE2, taken from the genesis spec
+    create2_height: Option<BlockNumber>,
+    // id of the chain the transactions are signed for, taken from the genesis spec
+    chain_id: u32,
+}
+
+/// Flush the blocks buffered by the import into the database, off the import thread.
+/// A flush writes the buffered blocks in one atomic batch, so a crash loses whole
+/// blocks only, which are synced again. The state is flushed every `state_flush_interval`
+/// blocks, along with its journal, so the state on the disk is always the one of a block,
+/// and the blocks after it are executed again on start. Stops when the chain is dropped,
+/// and exits the process when a flush fails, as the blocks imported after it would never
+/// reach the disk.
+fn commit_blocks(db: Arc<KeyValueDB>, receiver: Receiver<BlockNumber>, mut state_height: BlockNumber, state_flush_interval: u64) {
+    while let Ok(mut height) = receiver.recv() {
+        // blocks queued meanwhile are in the buffer already
+        while let Ok(h) = receiver.try_recv() {
+            height = h;
+        }
+        let with_state = height >= state_height + state_flush_interval;
+        let result = if with_state { db.flush() } else { db.flush_except(COL_STATE) };
+        if let Err(err) = result {
+            error!("failed to commit blocks up to {}: {}", height, err);
+            process::exit(1);
+        }
+        if with_state {
+            state_height = height;
+            trace!("committed blocks and state up to {}", height);
+        } else {
+            trace!("committed blocks up to {}", height);
+        }
+    }
 }
 
-pub fn save_genesis(db: &KeyValueDB, genesis: &Genesis) -> Result<(), String> {
+/// Save the genesis block with its state built by `Genesis::lazy_execute`.
+pub fn save_genesis(db: &KeyValueDB, genesis: &Genesis, mut state_db: StateDB) -> Result<(), String> {
     let mut batch = db.transaction();
     let hash = genesis.block.hash();
     let height: BlockNumber = 0;
+    state_db.journal_under(&mut batch, height, &hash).map_err(|e| format!("{}", e))?;
     batch.write(db::COL_HEADERS, &hash, genesis.block.header());
     batch.write(db::COL_BODIES, &hash, genesis.block.body());
     batch.write(db::COL_EXTRA, &ConstKey::CurrentHash, &hash);
@@ -174,6 +254,85 @@ pub fn save_genesis(db: &KeyValueDB, genesis: &Genesis) -> Result<(), String> {
     db.write(batch)
 }
 
+/// Check the configured journal algorithm against the one the state db was created with.
+/// Databases created before the algorithm was recorded used the default one.
+fn check_journal_algorithm(db: &KeyValueDB, algorithm: journaldb::Algorithm) {
+    let created = match db.get(db::COL_EXTRA, JOURNAL_ALGORITHM_KEY) {
+        Ok(Some(value)) => String::from_utf8_lossy(&value).parse().expect("Invalid journal algorithm in db."),
+        _ if get_chain(db).is_some() => journaldb::Algorithm::default(),
+        _ => {
+            let mut batch = db.transaction();
+            batch.put(db::COL_EXTRA, JOURNAL_ALGORITHM_KEY, algorithm.as_str().as_bytes());
+            db.write(batch).expect("DB write failed.");
+            algorithm
+        }
+    };
+    if created != algorithm {
+        panic!("state db was created with {} pruning, but {} is configured", created, algorithm);
+    }
+}
+
+/// Compress the block bodies and receipts written before they were stored compressed.
+/// Reading works either way, so an interrupted migration is simply run again.
+fn compress_db(db: &KeyValueDB) {
+    if let Ok(Some(_)) = db.get(db::COL_EXTRA, COMPRESSION_KEY) {
+        return;
+    }
+    let receipts_prefix = [ExtrasIndex::BlockReceipts as u8];
+    let receipts = db.iter_from_prefix(db::COL_EXTRA, &receipts_prefix).take_while(|&(ref key, _)| key.starts_with(&receipts_prefix));
+    let count = compress_values(db, db::COL_BODIES, db.iter(db::COL_BODIES)) + compress_values(db, db::COL_EXTRA, receipts);
+    if count > 0 {
+        info!("compressed {} block bodies and receipts", count);
+    }
+    let mut batch = db.transaction();
+    batch.put(db::COL_EXTRA, COMPRESSION_KEY, b"snappy");
+    db.write(batch).expect("DB write failed.");
+}
+
+fn compress_values<I>(db: &KeyValueDB, col: Option<u32>, values: I) -> usize
+where
+    I: Iterator<Item = (Box<[u8]>, Box<[u8]>)>,
+{
+    let mut count = 0;
+    let mut batch = db.transaction();
+    for (key, value) in values.filter(|&(_, ref value)| !db::is_compressed(value)) {
+        batch.put(col, &key, &db::compress(&value));
+        count += 1;
+        if count % COMPRESS_BATCH_SIZE == 0 {
+            db.write(mem::replace(&mut batch, DBTransaction::new())).expect("DB write failed.");
+        }
+    }
+    db.write(batch).expect("DB write failed.");
+    count
+}
+
+/// Mark the states older than `history` blocks canonical, so that the journal
+/// left by a node which has not been pruning gets pruned as well.
+fn prune_journal(db: &KeyValueDB, journal_db: &mut JournalDB, height: u64, history: u64) {
+    if height <= history {
+        return;
+    }
+    let end = height - history;
+    let mut era = match journal_db.earliest_era() {
+        Some(era) if era <= end => era,
+        _ => return,
+    };
+    info!("prune state journal from {} to {}", era, end);
+    while era <= end {
+        let mut batch = db.transaction();
+        let last = ::std::cmp::min(era + PRUNE_BATCH_ERAS, end + 1);
+        for canon_era in era..last {
+            let hash: Option<H256> = db.read(db::COL_EXTRA, &canon_era);
+            if let Some(hash) = hash {
+                journal_db.mark_canonical(&mut batch, canon_era, &hash).expect("DB commit failed");
+            }
+        }
+        db.write(batch).expect("DB write failed.");
+        journal_db.flush();
+        era = last;
+    }
+}
+
 /// Get latest status
 pub fn get_chain(db: &KeyValueDB) -> Option<(H256, u64)> {
     let current_hash = db.read(db::COL_EXTRA, &ConstKey::CurrentHash);
@@ -205,7 +364,7 @@ impl Chain {
         status
     }
 
-    pub fn init_chain(db: Arc<KeyValueDB>, mut genesis: Genesis, sync_sender: Sender<u64>) -> (Arc<Chain>, ProtoStatus) {
+    pub fn init_chain(db: Arc<KeyValueDB>, mut genesis: Genesis, config: &Config, sync_sender: Sender<u64>) -> (Arc<Chain>, ProtoStatus) {
         // 400 is the avarage size of the key
         let cache_man = CacheManager::new(1 << 14, 1 << 20, 400);
 
@@ -216,8 +375,9 @@ impl Chain {
             accountdb: Default::default(),
         };
 
-        let journal_db = journaldb::new(db.clone(), Default::default(), COL_STATE);
-        let state_db = StateDB::new(journal_db);
+        let algorithm = config.algorithm();
+        check_journal_algorithm(&*db, algorithm);
+        compress_db(&*db);
         let blooms_config = bc::Config {
             levels: LOG_BLOOMS_LEVELS,
             elements_per_index: LOG_BLOOMS_ELEMENTS_PER_INDEX,
@@ -231,18 +391,41 @@ impl Chain {
                 height = ht;
             }
             _ => {
-                let _ = genesis.lazy_execute();
-                save_genesis(&*db, &genesis).expect("Failed to save genesis.");
+                let state_db = StateDB::new(journaldb::new(db.clone(), algorithm, COL_STATE), 0);
+                let state_db = genesis.lazy_execute(state_db, &factories).expect("Failed to execute genesis.");
+                save_genesis(&*db, &genesis, state_db).expect("Failed to save genesis.");
                 info!("init genesis {:?}", genesis);
                 hash = genesis.block.hash();
                 height = 0;
             }
         }
 
+        let mut journal_db = journaldb::new(db.clone(), algorithm, COL_STATE);
+        // the state written last, the blocks after it were imported but their states not flushed
+        let state_height = journal_db.latest_era().map_or(height, |era| ::std::cmp::min(era, height));
+        let pruning_history = if journal_db.is_pruned() { Some(config.pruning_history) } else { None };
+        if let Some(history) = pruning_history {
+            prune_journal(&*db, &mut *journal_db, state_height, history);
+        }
+        let state_db = StateDB::new(journal_db, config.account_cache_size);
+
         let mut status = Status::new();
         status.set_hash(hash);
         status.set_number(height);
 
+        let ancient = match config.ancient_path {
+            Some(ref path) if config.ancient_blocks > 0 || Path::new(path).exists() => Some(Mutex::new(AncientStore::open(Path::new(path)).expect("Failed to open ancient store."))),
+            _ => None,
+        };
+
+        let (commit_sender, commit_receiver) = channel();
+        let commit_db = db.clone();
+        let state_flush_interval = config.state_flush_interval;
+        thread::Builder::new()
+            .name("chain_commit".to_string())
+            .spawn(move || commit_blocks(commit_db, commit_receiver, height, state_flush_interval))
+            .expect("Failed to spawn the committer.");
+
         let chain = Arc::new(Chain {
                                  blooms_config: blooms_config,
                                  current_hash: RwLock::new(hash),
@@ -261,11 +444,33 @@ impl Chain {
                                  state_db: state_db,
                                  factories: factories,
                                  sync_sender: Mutex::new(sync_sender),
+                                 commit_sender: Mutex::new(commit_sender),
                                  last_hashes: RwLock::new(VecDeque::new()),
+                                 commit_rounds: RwLock::new(VecDeque::new()),
                                  polls_filter: Arc::new(Mutex::new(PollManager::new())),
+                                 genesis_authorities: genesis.spec.authorities.clone(),
+                                 pruning_history: pruning_history,
+                                 mode: config.mode(),
+                                 ancient: ancient,
+                                 ancient_blocks: config.ancient_blocks,
+                                 state_diffing: config.state_diff,
+                                 auditing: config.audit,
+                                 byzantium_height: genesis.spec.byzantium_height,
+                                 create2_height: genesis.spec.create2_height,
+                                 chain_id: genesis.spec.chain_id,
                              });
 
+        if state_height < height {
+            chain.replay_states(state_height + 1, height);
+        }
         chain.build_last_hashes(Some(hash), height);
+        chain.build_commit_rounds(height);
+        if let Ok(None) = chain.db.get(db::COL_EXTRA, ACCOUNT_INDEX_KEY) {
+            chain.index_accounts(0, height);
+            let mut batch = DBTransaction::new();
+            batch.put(db::COL_EXTRA, ACCOUNT_INDEX_KEY, b"1");
+            chain.db.write(batch).expect("DB write failed.");
+        }
         (chain, status.protobuf())
     }
 
@@ -280,7 +485,71 @@ impl Chain {
     pub fn block_body_by_hash(&self, hash: H256) -> Option<BlockBody> {
         let result = self.db.read_with_cache(db::COL_BODIES, &self.block_bodies, &hash);
         self.cache_man.lock().note_used(CacheId::BlockHeaders(hash));
-        result
+        result.or_else(|| self.ancient_value(hash, |ancient, number| ancient.body(number)))
+    }
+
+    /// Read the value of the block from the ancient store, if it has been moved there.
+    fn ancient_value<T, F>(&self, hash: H256, read: F) -> Option<T>
+    where
+        T: Decodable,
+        F: FnOnce(&mut AncientStore, BlockNumber) -> ::std::io::Result<Option<Vec<u8>>>,
+    {
+        let ancient = match self.ancient {
+            Some(ref ancient) => ancient,
+            None => return None,
+        };
+        let number = match self.block_header_by_hash(hash) {
+            Some(header) => header.number(),
+            None => return None,
+        };
+        let value = read(&mut *ancient.lock(), number).expect("Failed to read ancient store.");
+        value.map(|value| ::rlp::decode(&db::decompress(&value)))
+    }
+
+    /// Move the blocks older than `ancient_blocks` out of the database into the ancient store,
+    /// returning the number of blocks moved.
+    pub fn freeze_ancient(&self) -> usize {
+        let ancient = match self.ancient {
+            Some(ref ancient) if self.ancient_blocks > 0 && self.mode != NodeMode::Light => ancient,
+            _ => return 0,
+        };
+        let mut ancient = ancient.lock();
+        let from = ancient.len();
+        let to = ::std::cmp::min(self.get_current_height().saturating_sub(self.ancient_blocks), from + ANCIENT_BATCH_SIZE);
+        if from >= to {
+            return 0;
+        }
+
+        let mut batch = DBTransaction::new();
+        let mut hashes = Vec::new();
+        for number in from..to {
+            let (body, receipts) = match self.block_hash(number) {
+                Some(hash) => {
+                    let receipts_key = <H256 as Key<BlockReceipts>>::key(&hash);
+                    let body = self.db.get(db::COL_BODIES, &hash).expect("DB read failed.");
+                    let receipts = self.db.get(db::COL_EXTRA, &receipts_key).expect("DB read failed.");
+                    batch.delete(db::COL_BODIES, &hash);
+                    batch.delete(db::COL_EXTRA, &receipts_key);
+                    hashes.push(hash);
+                    (body, receipts)
+                }
+                None => (None, None),
+            };
+            ancient.append(body.as_ref().map_or(&[][..], |v| &**v), receipts.as_ref().map_or(&[][..], |v| &**v)).expect("Failed to write ancient store.");
+        }
+        // The blocks must be durable in the ancient store before they leave the database.
+        ancient.flush().expect("Failed to write ancient store.");
+        self.db.write(batch).expect("DB write failed.");
+        {
+            let mut bodies = self.block_bodies.write();
+            let mut receipts = self.block_receipts.write();
+            for hash in &hashes {
+                bodies.remove(hash);
+                receipts.remove(hash);
+            }
+        }
+        info!("moved blocks from {} to {} into the ancient store", from, to - 1);
+        (to - from) as usize
     }
 
     // Get block by hash
@@ -306,6 +575,47 @@ impl Chain {
         self.block_hash(number).map_or(None, |h| self.block_by_hash(h))
     }
 
+    /// Get the light client proof of the block, the commit proof is taken from the next block,
+    /// so the latest block has none yet. None without the validators of the block.
+    pub fn light_client_proof(&self, number: BlockNumber) -> Option<Vec<u8>> {
+        match (self.block_header_by_height(number), self.block_header_by_height(number + 1), self.authorities_at(number)) {
+            (Some(ref header), Some(ref next), Some(ref authorities)) if next.proof_type() == Some(ProofType::Tendermint) => {
+                let proof = TendermintProof::from(next.proof().clone());
+                Some(LightClientProof::new(&header.proposal_protobuf(), authorities, proof).encode())
+            }
+            _ => None,
+        }
+    }
+
+    /// Heights and rounds the last blocks were committed at, from the oldest, for the commits
+    /// carried by the last `COMMIT_ROUNDS_WINDOW` blocks. The round of a block is in the proof
+    /// of the next one, so the latest is left out.
+    pub fn commit_rounds(&self) -> Vec<(u64, u64)> {
+        self.commit_rounds.read().iter().cloned().collect()
+    }
+
+    /// Read the commit rounds of the window ending at the block from the headers.
+    fn build_commit_rounds(&self, height: BlockNumber) {
+        let from = height.saturating_sub(COMMIT_ROUNDS_WINDOW) + 1;
+        let rounds = (from..height + 1)
+            .filter_map(|height| self.block_header_by_height(height))
+            .filter(|header| header.proof_type() == Some(ProofType::Tendermint))
+            .map(|header| TendermintProof::from(header.proof().clone()))
+            .filter(|proof| !proof.is_default())
+            .map(|proof| (proof.height as u64, proof.round as u64))
+            .collect();
+        *self.commit_rounds.write() = rounds;
+    }
+
+    /// Slide the window of commit rounds to the block, with the commit its proof carries.
+    fn update_commit_rounds(&self, height: BlockNumber, commit_round: Option<(u64, u64)>) {
+        let mut rounds = self.commit_rounds.write();
+        rounds.extend(commit_round);
+        while rounds.front().map_or(false, |&(committed, _)| committed + COMMIT_ROUNDS_WINDOW < height) {
+            rounds.pop_front();
+        }
+    }
+
     // Get block hash by height
     pub fn block_hash(&self, index: BlockNumber) -> Option<H256> {
         let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_hashes, &index);
@@ -468,7 +778,7 @@ impl Chain {
     fn execute_block(&self, block: Block) -> OpenBlock {
         let current_state_root = self.current_state_root();
         let last_hashes = self.last_hashes();
-        let mut open_block = OpenBlock::new(self.factories.clone(), false, block, self.state_db.boxed_clone(), current_state_root, last_hashes.into()).unwrap();
+        let mut open_block = OpenBlock::new(self.factories.clone(), false, self.state_diffing, self.auditing, block, self.state_db.boxed_clone_canon(&current_state_root), current_state_root, last_hashes.into(), self.engine()).unwrap();
         open_block.apply_transactions();
 
         open_block
@@ -547,7 +857,7 @@ impl Chain {
              .collect()
     }
 
-    fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
+    pub fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
         match id {
             BlockId::Number(number) => Some(number),
             BlockId::Hash(hash) => self.block_number_by_hash(hash),
@@ -576,6 +886,82 @@ impl Chain {
         self.logs(blocks, |entry| filter.matches(entry), filter.limit)
     }
 
+    /// Rebuild the log blooms index of the blocks from `from` to `to`, for the
+    /// blocks which are not committed by this node, e.g. restored from a snapshot.
+    /// Returns the number of blocks having logs.
+    pub fn rebuild_blooms(&self, from: BlockNumber, to: BlockNumber) -> usize {
+        let chain = bc::group::BloomGroupChain::new(self.blooms_config, self);
+        let mut count = 0;
+        let mut batch = DBTransaction::new();
+        for height in from..to + 1 {
+            let log_bloom = match self.block_header_by_height(height) {
+                Some(header) => *header.log_bloom(),
+                None => continue,
+            };
+            if !log_bloom.is_zero() {
+                let blooms: HashMap<LogGroupPosition, BloomGroup> = chain.insert(height as bc::Number, Bloom::from(log_bloom).into())
+                                                                         .into_iter()
+                                                                         .map(|p| (From::from(p.0), From::from(p.1)))
+                                                                         .collect();
+                for key in blooms.keys() {
+                    self.cache_man.lock().note_used(CacheId::BlocksBlooms(key.clone()));
+                }
+                // the groups are read back from the cache by the next insert
+                batch.extend_with_cache(db::COL_EXTRA, &mut *self.blocks_blooms.write(), blooms, CacheUpdatePolicy::Overwrite);
+                count += 1;
+            }
+            if (height - from + 1) % BLOOMS_BATCH_SIZE == 0 {
+                self.db.write(mem::replace(&mut batch, DBTransaction::new())).expect("DB write failed.");
+            }
+        }
+        self.db.write(batch).expect("DB write failed.");
+        if count > 0 {
+            info!("rebuilt log blooms of {} blocks from {} to {}", count, from, to);
+        }
+        count
+    }
+
+    /// Index the transactions of the blocks from `from` to `to` by their accounts,
+    /// for the blocks committed before the index existed or restored from a snapshot.
+    /// Returns the number of transactions indexed.
+    pub fn index_accounts(&self, from: BlockNumber, to: BlockNumber) -> usize {
+        let mut count = 0;
+        let mut batch = DBTransaction::new();
+        for height in from..to + 1 {
+            if let Some(body) = self.block_body_by_height(height) {
+                count += body.transactions().len();
+                for (position, tx_hash) in AccountTransactionPosition::of_block(height, &body) {
+                    batch.write(db::COL_EXTRA, &position, &tx_hash);
+                }
+            }
+            if (height - from + 1) % ACCOUNT_INDEX_BATCH_SIZE == 0 {
+                self.db.write(mem::replace(&mut batch, DBTransaction::new())).expect("DB write failed.");
+            }
+        }
+        self.db.write(batch).expect("DB write failed.");
+        if count > 0 {
+            info!("indexed {} transactions by account from {} to {}", count, from, to);
+        }
+        count
+    }
+
+    /// Hashes of the transactions sent or received by the account in the blocks
+    /// from `from` to `to`, ordered by the position in the chain, at most `limit` of them.
+    pub fn account_transactions(&self, address: &Address, from: BlockNumber, to: BlockNumber, limit: usize) -> Vec<H256> {
+        // the iteration only sees the flushed blocks, the state stays buffered for the committer
+        self.db.flush_except(COL_STATE).expect("DB write failed.");
+        let mut prefix = vec![ExtrasIndex::AccountTransaction as u8];
+        prefix.extend_from_slice(address);
+        self.db
+            .iter_from_prefix(db::COL_EXTRA, &prefix)
+            .take_while(|&(ref key, _)| key.starts_with(&prefix))
+            .skip_while(|&(ref key, _)| AccountTransactionPosition::block_number_of(key) < from)
+            .take_while(|&(ref key, _)| AccountTransactionPosition::block_number_of(key) <= to)
+            .take(limit)
+            .map(|(_, value)| ::rlp::decode(&value))
+            .collect()
+    }
+
     /// Build last 256 hashes.
     fn build_last_hashes(&self, prevhash: Option<H256>, parent_height: u64) -> Arc<LastHashes> {
         let parent_hash = prevhash.unwrap_or_else(|| self.block_hash(parent_height).expect("Block height always valid."));
@@ -667,6 +1053,15 @@ impl Chain {
             batch.extend_with_cache_append(db::COL_EXTRA, &*self.db, &mut *write_txs, block.transactions_dup.clone(), AppendPolicy::Update);
 
         }
+        if let Some(ref state_diff) = block.state_diff {
+            batch.write(db::COL_EXTRA, &hash, state_diff);
+        }
+        if let Some(ref audit) = block.audit {
+            batch.write(db::COL_EXTRA, &hash, audit);
+        }
+        for (position, tx_hash) in AccountTransactionPosition::of_block(height, block.body()) {
+            batch.write(db::COL_EXTRA, &position, &tx_hash);
+        }
 
         //note used
         self.cache_man.lock().note_used(CacheId::BlockHashes(height as BlockNumber));
@@ -687,10 +1082,135 @@ impl Chain {
         }
 
 
+        let state_root = *block.state.root();
         let mut state = block.drain();
-        // Store triedb changes in journal db
-        state.journal_under(batch, height, &hash).expect("DB commit failed");
+        self.journal_state(batch, &mut state, height, &hash);
+        state.sync_cache(&state_root);
+        if let Some(history) = self.pruning_history {
+            if self.mode == NodeMode::Light && height > history + 1 {
+                self.prune_block(batch, height - history - 1);
+            }
+        }
+
+    }
+
+    /// Store the triedb changes of the block in the journal db.
+    fn journal_state(&self, batch: &mut DBTransaction, state: &mut StateDB, height: BlockNumber, hash: &H256) {
+        state.journal_under(batch, height, hash).expect("DB commit failed");
+        // Blocks are final, the delay only keeps the recent states queryable.
+        if let Some(history) = self.pruning_history {
+            if height > history {
+                let era = height - history;
+                if let Some(canon_hash) = self.block_hash(era) {
+                    state.mark_canonical(batch, era, &canon_hash).expect("DB commit failed");
+                }
+            }
+        }
+    }
+
+    /// Execute the blocks from `from` to `to` again to rebuild their states,
+    /// which were still in memory when the node stopped.
+    fn replay_states(&self, from: BlockNumber, to: BlockNumber) {
+        info!("execute blocks from {} to {} again to rebuild the state", from, to);
+        for height in from..(to + 1) {
+            let block = self.block_by_height(height).expect("Missing block to rebuild the state.");
+            let parent_root = *self.block_header_by_height(height - 1).expect("Missing block to rebuild the state.").state_root();
+            let last_hashes = self.build_last_hashes(None, height - 1);
+            let state_root = *block.state_root();
+            let hash = block.hash();
+            let mut open_block = OpenBlock::new(self.factories.clone(), false, false, false, block, self.state_db.boxed_clone(), parent_root, last_hashes, self.engine()).unwrap();
+            open_block.apply_transactions();
+            let closed_block = open_block.close();
+            assert_eq!(*closed_block.state.root(), state_root, "state of block {} differs from the one executed before", height);
+
+            let mut state = closed_block.drain();
+            let mut batch = self.db.transaction();
+            self.journal_state(&mut batch, &mut state, height, &hash);
+            self.db.write(batch).expect("DB write failed.");
+        }
+    }
+
+    /// Drop the body, receipts and transaction index of the block, keeping its header.
+    fn prune_block(&self, batch: &mut DBTransaction, height: BlockNumber) {
+        let hash = match self.block_hash(height) {
+            Some(hash) => hash,
+            None => return,
+        };
+        if let Some(body) = self.block_body_by_hash(hash) {
+            let mut write_txs = self.transaction_addresses.write();
+            for tx_hash in body.transaction_hashes() {
+                batch.delete(db::COL_EXTRA, &<H256 as Key<TransactionAddress>>::key(&tx_hash));
+                write_txs.remove(&tx_hash);
+            }
+            for (position, _) in AccountTransactionPosition::of_block(height, &body) {
+                batch.delete(db::COL_EXTRA, &position.key());
+            }
+        }
+        batch.delete(db::COL_BODIES, &<H256 as Key<BlockBody>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<BlockReceipts>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<StateDiff>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<BlockAudit>>::key(&hash));
+        self.block_bodies.write().remove(&hash);
+        self.block_receipts.write().remove(&hash);
+    }
+
+    /// Earliest block whose state is kept.
+    pub fn earliest_state(&self) -> BlockNumber {
+        self.pruning_history.map_or(0, |history| self.get_current_height().saturating_sub(history))
+    }
+
+    /// Earliest block whose body and receipts are kept, only a light node prunes them.
+    pub fn earliest_block(&self) -> BlockNumber {
+        match self.mode {
+            NodeMode::Light => self.earliest_state(),
+            _ => 0,
+        }
+    }
+
+    /// The error for a missing state of the block, if it has been pruned.
+    pub fn state_pruned_error(&self, id: BlockId) -> Option<String> {
+        let earliest = self.earliest_state();
+        match self.block_number(id) {
+            Some(number) if number < earliest => Some(format!("state of block {} has been pruned, the states are kept from block {}", number, earliest)),
+            _ => None,
+        }
+    }
+
+    /// The error for a missing body or receipts of the block, if it has been pruned.
+    pub fn block_pruned_error(&self, id: BlockId) -> Option<String> {
+        let earliest = self.earliest_block();
+        match self.block_number(id) {
+            Some(number) if number < earliest => Some(format!("block {} has been pruned, the blocks are kept from block {}", number, earliest)),
+            _ => None,
+        }
+    }
+
+    /// Accounts and storage changed by the block, recorded if `state_diff` is configured.
+    pub fn state_diff(&self, id: BlockId) -> Option<StateDiff> {
+        self.block_number(id).and_then(|number| self.block_hash(number)).and_then(|hash| self.db.read(db::COL_EXTRA, &hash))
+    }
+
+    /// Audit of the execution of the block, if the node was auditing when executing it.
+    pub fn block_audit(&self, number: BlockNumber) -> Option<BlockAudit> {
+        self.block_hash(number).and_then(|hash| self.db.read(db::COL_EXTRA, &hash))
+    }
+
+    /// The error for a missing state diff of the block.
+    pub fn state_diff_error(&self, id: BlockId) -> Option<String> {
+        if !self.state_diffing {
+            return Some("state diffs are not recorded by the node".to_string());
+        }
+        self.block_pruned_error(id)
+    }
 
+    /// The error for a missing transaction, which may be in the blocks pruned by a light node.
+    pub fn transaction_pruned_error(&self) -> Option<String> {
+        let earliest = self.earliest_block();
+        if earliest > 0 {
+            Some(format!("transaction not found, the blocks are kept from block {}", earliest))
+        } else {
+            None
+        }
     }
 
     /// Get the address of transaction with given hash.
@@ -707,7 +1227,7 @@ impl Chain {
     pub fn block_receipts(&self, hash: H256) -> Option<BlockReceipts> {
         let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_receipts, &hash);
         self.cache_man.lock().note_used(CacheId::BlockReceipts(hash));
-        result
+        result.or_else(|| self.ancient_value(hash, |ancient, number| ancient.receipts(number)))
     }
 
     pub fn cita_call(&self, request: CallRequest, id: BlockId) -> Result<Bytes, String> {
@@ -726,10 +1246,26 @@ impl Chain {
             value: U256::zero(),
             data: request.data.map_or_else(Vec::new, |d| d.to_vec()),
             block_limit: u64::max_value(),
+            chain_id: self.chain_id,
+            version: TRANSACTION_VERSION,
+            quota_price: 0,
         }
         .fake_sign(from)
     }
 
+    /// The engine executing the blocks of the chain.
+    fn engine(&self) -> NullEngine {
+        NullEngine::default()
+            .with_byzantium_height(self.byzantium_height)
+            .with_create2_height(self.create2_height)
+            .with_chain_id(self.chain_id)
+    }
+
+    /// Id of the chain the transactions must be signed for.
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
     /// Attempt to get a copy of a specific block's final state.
     pub fn state_at(&self, id: BlockId) -> Option<State<StateDB>> {
         self.block_header(id).map_or(None, |h| self.gen_state(*h.state_root()))
@@ -737,7 +1273,7 @@ impl Chain {
 
     /// generate block's final state.
     pub fn gen_state(&self, root: H256) -> Option<State<StateDB>> {
-        let db = self.state_db.boxed_clone();
+        let db = self.state_db.boxed_clone_canon(&root);
         State::from_existing(db, root, U256::from(0), self.factories.clone()).ok()
     }
 
@@ -750,11 +1286,101 @@ impl Chain {
             .expect("State root of current block always valid.")
     }
 
+    /// Target block interval in milliseconds set in the system config contract.
+    pub fn block_interval(&self) -> Option<u64> {
+        let key = H256::from(native::BLOCK_INTERVAL_KEY);
+        match self.state().storage_at(&native::sys_config_address(), &key) {
+            Ok(value) if !value.is_zero() => Some(value.low_u64()),
+            _ => None,
+        }
+    }
+
+    /// Quota a block may use, governed by the quota manager contract.
+    pub fn block_quota_limit(&self) -> Option<u64> {
+        let key = H256::from(native::BLOCK_QUOTA_LIMIT_KEY);
+        match self.state().storage_at(&native::quota_manager_address(), &key) {
+            Ok(value) if !value.is_zero() => Some(value.low_u64()),
+            _ => None,
+        }
+    }
+
+    /// Quota an account may be charged in a block, governed by the quota manager contract.
+    /// A sponsored transaction is charged to its fee payer instead of the sender.
+    pub fn account_quota_limit(&self) -> Option<u64> {
+        let key = H256::from(native::ACCOUNT_QUOTA_LIMIT_KEY);
+        match self.state().storage_at(&native::quota_manager_address(), &key) {
+            Ok(value) if !value.is_zero() => Some(value.low_u64()),
+            _ => None,
+        }
+    }
+
+    /// Mean quota used by the transactions applied in the current block, at least the base
+    /// quota of a transaction. Consensus packages no more transactions than fit the block
+    /// quota limit at this quota each.
+    pub fn quota_per_transaction(&self) -> u64 {
+        let receipts = self.block_receipts(*self.current_hash.read()).map(|r| r.receipts).unwrap_or_default();
+        let applied: Vec<&Receipt> = receipts.iter().filter_map(|r| r.as_ref()).collect();
+        match applied.last() {
+            Some(last) => ::std::cmp::max(last.gas_used.low_u64() / applied.len() as u64, TRANSACTION_BASE_QUOTA),
+            None => TRANSACTION_BASE_QUOTA,
+        }
+    }
+
+    /// Nodes approved by the node manager contract.
+    pub fn node_list(&self) -> Vec<Address> {
+        let state = self.state();
+        let address = native::node_manager_address();
+        let count = state.storage_at(&address, &H256::from(0)).map(|v| v.low_u64()).unwrap_or(0);
+        (0..count).filter_map(|i| state.storage_at(&address, &H256::from(i + 1)).ok()).map(Address::from).collect()
+    }
+
+    /// Key rotations recorded by the node manager contract, the height each takes effect
+    /// from with the old and the new consensus key.
+    pub fn key_rotations(&self) -> Vec<(u64, Address, Address)> {
+        let state = self.state();
+        let address = native::node_manager_address();
+        let field = |i, f| state.storage_at(&address, &native::rotation_key(i, f)).unwrap_or_default();
+        let count = field(0, 0).low_u64();
+        (0..count).map(|i| (field(i + 1, 0).low_u64(), Address::from(field(i + 1, 1)), Address::from(field(i + 1, 2)))).collect()
+    }
+
+    /// Accounts in the list of the permission manager contract, anyone is permitted if it is empty.
+    pub fn permitted_accounts(&self, list: u64) -> Vec<Address> {
+        let state = self.state();
+        let address = native::permission_manager_address();
+        let count = state.storage_at(&address, &native::permission_key(list, 0)).map(|v| v.low_u64()).unwrap_or(0);
+        (0..count).filter_map(|i| state.storage_at(&address, &native::permission_key(list, i + 1)).ok()).map(Address::from).collect()
+    }
+
     //get account
     pub fn code_at(&self, address: &Address, id: BlockId) -> Option<Option<Bytes>> {
         self.state_at(id).and_then(|s| s.code(address).ok()).map(|c| c.map(|c| (&*c).clone()))
     }
 
+    /// Merkle proof of the account in the state of the block, with the account.
+    pub fn prove_account(&self, address: &Address, id: BlockId) -> Option<(Vec<Bytes>, BasicAccount)> {
+        self.state_at(id).and_then(|s| s.prove_account(address).ok())
+    }
+
+    /// Merkle proof of the storage key in the account storage of the block, with the value.
+    pub fn prove_storage(&self, address: &Address, key: &H256, id: BlockId) -> Option<(Vec<Bytes>, H256)> {
+        self.state_at(id).and_then(|s| s.prove_storage(address, key).ok())
+    }
+
+    /// Value of the storage key of the account at each block from `from` to `to`,
+    /// at most `limit` of them, skipping the blocks whose state is not kept.
+    pub fn storage_history(&self, address: &Address, key: &H256, from: BlockNumber, to: BlockNumber, limit: usize) -> Vec<(BlockNumber, H256)> {
+        let from = ::std::cmp::max(from, self.earliest_state());
+        let to = ::std::cmp::min(to, self.get_current_height());
+        if from > to {
+            return Vec::new();
+        }
+        (from..to + 1)
+            .filter_map(|number| self.state_at(BlockId::Number(number)).and_then(|s| s.storage_at(address, key).ok()).map(|value| (number, value)))
+            .take(limit)
+            .collect()
+    }
+
     //account  transaction count
     pub fn nonce(&self, address: &Address, id: BlockId) -> Option<U256> {
         self.state_at(id).and_then(|s| s.nonce(address).ok())
@@ -774,7 +1400,7 @@ impl Chain {
         };
         // that's just a copy of the state.
         let mut state = self.state_at(block_id).ok_or(CallError::StatePruned)?;
-        let engine = NullEngine::default();
+        let engine = self.engine();
 
         let options = TransactOptions {
             tracing: analytics.transaction_tracing,
@@ -786,11 +1412,99 @@ impl Chain {
         Ok(ret)
     }
 
+    /// Execute again a transaction of the chain, on the state left by the transactions before it in its block.
+    pub fn replay_transaction(&self, id: TransactionId, analytics: CallAnalytics) -> Result<Executed, CallError> {
+        let address = self.transaction_address(id).ok_or(CallError::TransactionNotFound)?;
+        let mut block = self.block_by_hash(address.block_hash).ok_or(CallError::TransactionNotFound)?;
+        let t = block.body.transactions[address.index].clone();
+        block.body.transactions.truncate(address.index);
+        self.execute_in_block(block, &t, analytics, true)
+    }
+
+    /// Execute a transaction as if it were the first one of the block, without checking its nonce.
+    pub fn simulate_transaction(&self, t: &SignedTransaction, id: BlockId, analytics: CallAnalytics) -> Result<Executed, CallError> {
+        let mut block = self.block(id).ok_or(CallError::StatePruned)?;
+        block.body.transactions.clear();
+        self.execute_in_block(block, t, analytics, false)
+    }
+
+    /// Apply the transactions of the block on the state of its parent, then the transaction with tracing.
+    fn execute_in_block(&self, block: Block, t: &SignedTransaction, analytics: CallAnalytics, check_nonce: bool) -> Result<Executed, CallError> {
+        if block.number() == 0 || block.number() <= self.earliest_state() {
+            return Err(CallError::StatePruned);
+        }
+        let parent = self.block_header_by_height(block.number() - 1).ok_or(CallError::StatePruned)?;
+        let last_hashes = self.build_last_hashes(None, block.number() - 1);
+        let mut open_block = OpenBlock::new(self.factories.clone(), false, false, false, block, self.state_db.boxed_clone_canon(parent.state_root()), *parent.state_root(), last_hashes, self.engine())
+            .map_err(|_| CallError::StatePruned)?;
+        open_block.apply_transactions();
+
+        let env_info = open_block.env_info();
+        let engine = self.engine();
+        let options = TransactOptions {
+            tracing: analytics.transaction_tracing,
+            vm_tracing: analytics.vm_tracing,
+            check_nonce: check_nonce,
+        };
+        let mut ret = Executive::new(&mut open_block.state, &env_info, &engine, &self.factories.vm).transact(t, options)?;
+        if analytics.state_diffing {
+            ret.state_diff = Some(open_block.state.diff().map_err(|_| CallError::StateCorrupt)?);
+        }
+        Ok(ret)
+    }
+
     /// Get transaction receipt.
     pub fn transaction_receipt(&self, address: &TransactionAddress) -> Option<Receipt> {
         self.block_receipts(address.block_hash.clone()).map_or(None, |r| r.receipts[address.index].clone())
     }
 
+    /// Validators signing the block of the height, None if they are not configured.
+    pub fn authorities_at(&self, height: BlockNumber) -> Option<Vec<Address>> {
+        self.authority_history().into_iter().take_while(|&(first, _)| first <= height).last().map(|(_, authorities)| authorities)
+    }
+
+    /// Validators signing from each height on, ordered by the height: those of the genesis,
+    /// with the key rotations recorded on chain applied from the heights they take effect,
+    /// the same way consensus applies them.
+    pub fn authority_history(&self) -> Vec<(u64, Vec<Address>)> {
+        if self.genesis_authorities.is_empty() {
+            return Vec::new();
+        }
+        let mut rotations = self.key_rotations();
+        rotations.sort_by_key(|&(height, _, _)| height);
+        let mut authorities = self.genesis_authorities.clone();
+        let mut history = vec![(0, authorities.clone())];
+        for (height, old, new) in rotations {
+            match authorities.iter().position(|a| *a == old) {
+                // keep the position so the proposer order does not change
+                Some(pos) if !authorities.contains(&new) => authorities[pos] = new,
+                _ => continue,
+            }
+            if history.last().map_or(false, |&(first, _)| first == height) {
+                history.pop();
+            }
+            history.push((height, authorities.clone()));
+        }
+        history
+    }
+
+    /// Pick up the chain written into the database behind us, by a snapshot restoration.
+    pub fn reload(&self) -> ProtoStatus {
+        let (hash, height) = get_chain(&*self.db).expect("chain should be restored");
+        self.block_map.write().clear();
+        *self.current_hash.write() = hash;
+        self.current_height.store(height as usize, Ordering::SeqCst);
+        self.build_last_hashes(Some(hash), height);
+        self.build_commit_rounds(height);
+        self.rebuild_blooms(height.saturating_sub(SNAPSHOT_BLOCKS), height);
+        self.index_accounts(height.saturating_sub(SNAPSHOT_BLOCKS), height);
+
+        let mut status = Status::new();
+        status.set_hash(hash);
+        status.set_number(height);
+        status.protobuf()
+    }
+
     /// Add block to chain:
     /// 1. Execute block
     /// 2. Commit block
@@ -799,22 +1513,41 @@ impl Chain {
     // TODO: move proof check to sync module
     pub fn add_block(&self, batch: &mut DBTransaction, block: Block) -> Option<H256> {
         let height = block.number();
+        let mut commit_round = None;
         match block.proof_type() {
             Some(ProofType::Tendermint) => {
                 let proof = TendermintProof::from(block.proof().clone());
-                if !proof.simple_check(height as usize - 1) {
+                let valid = match self.authorities_at(height - 1) {
+                    Some(authorities) => proof.check(height as usize - 1, &authorities),
+                    None => {
+                        // only a genesis not telling its consensus may leave out the authorities
+                        warn!("no authorities to check the proof of block {}, only its signatures are checked", height);
+                        proof.simple_check(height as usize - 1)
+                    }
+                };
+                if !valid {
+                    warn!("block {} has invalid proof", height);
                     return None;
                 }
+                if !proof.is_default() {
+                    commit_round = Some((proof.height as u64, proof.round as u64));
+                }
             }
             _ => {}
         }
 
+        if !block.is_valid_version() {
+            warn!("block {} has unknown version {}", height, block.version());
+            return None;
+        }
+
         if self.validate_hash(block.parent_hash()) {
             let mut open_block = self.execute_block(block);
             let closed_block = open_block.close();
             let hash = closed_block.hash();
             self.commit_block(batch, closed_block);
             self.update_last_hashes(&hash);
+            self.update_commit_rounds(height, commit_round);
             Some(hash)
         } else {
             None
@@ -835,7 +1568,9 @@ impl Chain {
 
                 let status = self.save_status(&mut batch);
 
-                self.db.write(batch).expect("DB write failed.");
+                // The block is readable from the buffer at once, the committer makes it durable.
+                self.db.write_buffered(batch);
+                let _ = self.commit_sender.lock().send(height);
                 info!("chain update {:?}", height);
                 Some(status.protobuf())
             } else {
@@ -847,6 +1582,12 @@ impl Chain {
         }
     }
 
+    /// Flush the blocks and the state buffered into the database, when no block is in import,
+    /// so the blocks since the last flush of the state are not executed again on start.
+    pub fn flush(&self) {
+        self.db.flush().expect("DB write failed.");
+    }
+
     pub fn compare_status(&self, st: Status) -> (u64, u64) {
         let current_height = self.current_height.load(Ordering::SeqCst) as u64;
         if st.number() > current_height {
@@ -928,17 +1669,24 @@ mod tests {
     use super::*;
     use db;
     use libchain::block::{Block, BlockBody};
-    use libchain::genesis::Spec;
+    use libchain::export;
+    use libchain::genesis::{GenesisBuilder, Spec};
+    use libchain::rollback;
+    use bincode::{serialize, Infinite};
     use libproto::blockchain;
+    use proof::{authorities_hash, Step};
     use rustc_serialize::hex::FromHex;
+    use serde_json;
+    use std::fs;
     use std::sync::Arc;
     use std::sync::mpsc::channel;
     use std::time::{UNIX_EPOCH, Instant};
     use test::{Bencher, black_box};
+    use types::account_diff::Diff;
     use types::transaction::SignedTransaction;
-    use util::{U256, H256, Address};
+    use util::{U256, H256, Address, HASH_NULL_RLP};
     use util::kvdb::{Database, DatabaseConfig};
-    use cita_ed25519::KeyPair;
+    use cita_ed25519::{KeyPair, sign};
     //use util::hashable::HASH_NAME;
 
     #[test]
@@ -962,19 +1710,28 @@ mod tests {
     }
 
     fn init_chain() -> Arc<Chain> {
+        init_chain_with(&Config::default())
+    }
+
+    fn init_chain_with(chain_config: &Config) -> Arc<Chain> {
         let _ = env_logger::init();
         let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
         let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
         let db = Database::open(&config, &tempdir.to_str().unwrap()).unwrap();
+        open_chain(Arc::new(db), chain_config)
+    }
+
+    fn open_chain(db: Arc<KeyValueDB>, chain_config: &Config) -> Arc<Chain> {
         let genesis = Genesis {
             spec: Spec {
                 prevhash: H256::from(0),
                 timestamp: 0,
+                ..Spec::default()
             },
             block: Block::default(),
         };
         let (sync_tx, _) = channel();
-        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, sync_tx);
+        let (chain, _) = Chain::init_chain(db, genesis, chain_config, sync_tx);
         chain
     }
 
@@ -989,31 +1746,36 @@ mod tests {
         let mut body = BlockBody::new();
         let mut txs = Vec::new();
         for i in nonce.0..nonce.1 {
-            let mut tx = blockchain::Transaction::new();
-            if to == Address::from(0) {
-                tx.set_to(String::from(""));
-            } else {
-                tx.set_to(to.hex());
-            }
-            tx.set_nonce(U256::from(i).to_hex());
-            tx.set_data(data.clone());
-            tx.set_valid_until_block(0);
-
-            let mut uv_tx = blockchain::UnverifiedTransaction::new();
-            uv_tx.set_transaction(tx);
-
-            let mut stx = blockchain::SignedTransaction::new();
-            stx.set_transaction_with_sig(uv_tx);
-            stx.sign(*privkey);
-            let new_tx = SignedTransaction::new(&stx).unwrap();
-            txs.push(new_tx);
-
+            txs.push(create_tx(privkey, None, to, data.clone(), i));
         }
         body.set_transactions(txs);
         block.set_body(body);
         block
     }
 
+    fn create_tx(privkey: &cita_ed25519::PrivKey, fee_payer: Option<&cita_ed25519::PrivKey>, to: Address, data: Vec<u8>, nonce: u32) -> SignedTransaction {
+        let mut tx = blockchain::Transaction::new();
+        if to == Address::from(0) {
+            tx.set_to(String::from(""));
+        } else {
+            tx.set_to(to.hex());
+        }
+        tx.set_nonce(U256::from(nonce).to_hex());
+        tx.set_data(data);
+        tx.set_valid_until_block(0);
+
+        let mut uv_tx = blockchain::UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut stx = blockchain::SignedTransaction::new();
+        stx.set_transaction_with_sig(uv_tx);
+        stx.sign(*privkey);
+        if let Some(fee_payer) = fee_payer {
+            stx.sign_fee_payer(*fee_payer);
+        }
+        SignedTransaction::new(&stx).unwrap()
+    }
+
     #[bench]
     fn bench_execute_block(b: &mut Bencher) {
         let chain = init_chain();
@@ -1048,6 +1810,125 @@ mod tests {
         b.iter(|| {});
     }
 
+    #[test]
+    fn test_pruning() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let config = Config {
+            pruning: "fast".to_string(),
+            pruning_history: 2,
+            ..Config::default()
+        };
+        let chain = init_chain_with(&config);
+        let data = "60606040523415600b57fe5b5b5b5b608e8061001c6000396000f30060606040526000357c0100000000000000000000000000000000000000000000000000000000900463ffffffff1680635524107714603a575bfe5b3415604157fe5b605560048080359060200190919050506057565b005b806000819055505b505600a165627a7a7230582079b763be08c24124c9fa25c78b9d221bdee3e981ca0b2e371628798c41e292ca0029"
+            .from_hex()
+            .unwrap();
+
+        let mut contracts = Vec::new();
+        for i in 0..4 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block.clone());
+            let txhash = block.body().transactions()[0].hash();
+            contracts.push(chain.localized_receipt(txhash).unwrap().contract_address.unwrap());
+        }
+
+        assert_eq!(chain.get_current_height(), 4);
+        assert_eq!(chain.state_db.journal_db().earliest_era(), Some(3));
+        for contract in contracts {
+            assert!(chain.code_at(&contract, BlockId::Latest).unwrap().is_some());
+        }
+        assert!(chain.state_pruned_error(BlockId::Number(1)).is_some());
+        assert!(chain.state_pruned_error(BlockId::Number(2)).is_none());
+        assert!(chain.block_pruned_error(BlockId::Number(1)).is_none());
+    }
+
+    #[test]
+    fn test_light_mode() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let config = Config {
+            mode: Some(NodeMode::Light),
+            pruning_history: 2,
+            ..Config::default()
+        };
+        let chain = init_chain_with(&config);
+        let data = vec![0x60, 0x00];
+
+        let mut txhashes = Vec::new();
+        for i in 0..4 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block.clone());
+            txhashes.push(block.body().transactions()[0].hash());
+        }
+
+        assert_eq!(chain.earliest_block(), 2);
+        assert!(chain.block_header_by_height(1).is_some());
+        assert!(chain.block_body_by_height(1).is_none());
+        assert!(chain.localized_receipt(txhashes[0]).is_none());
+        assert!(chain.block_pruned_error(BlockId::Number(1)).is_some());
+        assert!(chain.block_body_by_height(2).is_some());
+        assert!(chain.localized_receipt(txhashes[1]).is_some());
+        assert!(chain.transaction_pruned_error().is_some());
+    }
+
+    #[test]
+    fn test_compress_db() {
+        let db = in_memory(db::NUM_COLUMNS.unwrap());
+        let hash = H256::from(1);
+        let mut batch = db.transaction();
+        batch.put(db::COL_BODIES, &hash, &::rlp::encode(&BlockBody::new()));
+        db.write(batch).unwrap();
+        assert!(!db::is_compressed(&db.get(db::COL_BODIES, &hash).unwrap().unwrap()));
+
+        compress_db(&db);
+        assert!(db::is_compressed(&db.get(db::COL_BODIES, &hash).unwrap().unwrap()));
+        let body: BlockBody = db.read(db::COL_BODIES, &hash).unwrap();
+        assert!(body.transactions().is_empty());
+        assert!(db.get(db::COL_EXTRA, COMPRESSION_KEY).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_rebuild_blooms() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+        // PUSH1 0 PUSH1 0 LOG0
+        let data = vec![0x60, 0x00, 0x60, 0x00, 0xa0];
+
+        let mut contracts = Vec::new();
+        for i in 0..3 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block.clone());
+            let txhash = block.body().transactions()[0].hash();
+            contracts.push(chain.localized_receipt(txhash).unwrap().contract_address.unwrap());
+        }
+        let filter = |address: Address| Filter {
+            from_block: BlockId::Earliest,
+            to_block: BlockId::Latest,
+            address: Some(vec![address]),
+            topics: vec![None, None, None, None],
+            limit: None,
+        };
+        let logs = chain.get_logs(filter(contracts[1]));
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, 2);
+
+        // drop the index, as for the blocks restored from a snapshot
+        let mut batch = DBTransaction::new();
+        for (position, _) in chain.blocks_blooms.write().drain() {
+            batch.delete(db::COL_EXTRA, &<LogGroupPosition as Key<BloomGroup>>::key(&position));
+        }
+        chain.db.write(batch).unwrap();
+        assert!(chain.get_logs(filter(contracts[1])).is_empty());
+
+        assert_eq!(chain.rebuild_blooms(0, chain.get_current_height()), 3);
+        for (i, contract) in contracts.into_iter().enumerate() {
+            let logs = chain.get_logs(filter(contract));
+            assert_eq!(logs.len(), 1);
+            assert_eq!(logs[0].block_number, i as u64 + 1);
+        }
+    }
+
     #[test]
     fn test_code_at() {
         let keypair = cita_ed25519::KeyPair::gen_keypair();
@@ -1101,6 +1982,743 @@ mod tests {
         assert!(code.unwrap().is_some());
     }
 
+    #[test]
+    fn test_state_diff() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let config = Config { state_diff: true, ..Config::default() };
+        let chain = init_chain_with(&config);
+        // PUSH1 42 PUSH1 0 SSTORE
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+
+        let block = create_block(&chain, privkey, Address::from(0), data, (0, 1));
+        chain.set_block(block.clone());
+        let txhash = block.body().transactions()[0].hash();
+        let contract = chain.localized_receipt(txhash).unwrap().contract_address.unwrap();
+
+        let diff = chain.state_diff(BlockId::Latest).unwrap();
+        assert_eq!(diff[&contract].storage[&H256::from(0)], Diff::Born(H256::from(42)));
+        assert!(chain.state_diff_error(BlockId::Latest).is_none());
+        assert!(chain.state_diff(BlockId::Number(100)).is_none());
+
+        let chain = init_chain();
+        assert!(chain.state_diff_error(BlockId::Latest).is_some());
+    }
+
+    #[test]
+    fn test_account_transactions() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+        // PUSH1 0 PUSH1 0 RETURN
+        let data = vec![0x60, 0x00, 0x60, 0x00, 0xf3];
+
+        let block = create_block(&chain, privkey, Address::from(0), data, (0, 1));
+        chain.set_block(block.clone());
+        let create_hash = block.body().transactions()[0].hash();
+        let sender = *block.body().transactions()[0].sender();
+        let contract = chain.localized_receipt(create_hash).unwrap().contract_address.unwrap();
+
+        let block = create_block(&chain, privkey, contract, vec![], (1, 3));
+        chain.set_block(block.clone());
+        let call_hashes = block.body().transaction_hashes();
+
+        let mut hashes = vec![create_hash];
+        hashes.extend(call_hashes.clone());
+        assert_eq!(chain.account_transactions(&sender, 0, 2, 10), hashes);
+        assert_eq!(chain.account_transactions(&contract, 0, 2, 10), hashes);
+        assert_eq!(chain.account_transactions(&contract, 2, 2, 10), call_hashes);
+        assert_eq!(chain.account_transactions(&contract, 0, 2, 1), vec![create_hash]);
+        assert!(chain.account_transactions(&sender, 3, 10, 10).is_empty());
+        assert!(chain.account_transactions(&Address::from(1), 0, 2, 10).is_empty());
+    }
+
+    #[test]
+    fn test_freeze_ancient() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let ancient_path = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let config = Config {
+            ancient_blocks: 1,
+            ancient_path: Some(ancient_path.to_str().unwrap().to_string()),
+            ..Config::default()
+        };
+        let chain = init_chain_with(&config);
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+
+        let mut tx_hashes = Vec::new();
+        for i in 0..3 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block.clone());
+            tx_hashes.push(block.body().transactions()[0].hash());
+        }
+        // blocks 0 and 1 are moved, the ones before the last block
+        assert_eq!(chain.freeze_ancient(), 2);
+        assert_eq!(chain.freeze_ancient(), 0);
+        let hash = chain.block_hash(1).unwrap();
+        assert!(chain.db.get(db::COL_BODIES, &hash).unwrap().is_none());
+
+        assert_eq!(chain.block_body_by_height(1).unwrap().transactions()[0].hash(), tx_hashes[0]);
+        assert!(chain.localized_receipt(tx_hashes[0]).is_some());
+        assert!(chain.localized_receipt(tx_hashes[2]).is_some());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let config = Config::default();
+        let chain = init_chain_with(&config);
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+
+        let mut tx_hashes = Vec::new();
+        for i in 0..3 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block.clone());
+            tx_hashes.push(block.body().transactions()[0].hash());
+        }
+        let hash = chain.block_hash(1).unwrap();
+        let db = chain.db.clone();
+        drop(chain);
+        assert!(rollback::rollback(db.clone(), &config, 4).is_err());
+        assert_eq!(rollback::rollback(db.clone(), &config, 1).unwrap(), hash);
+
+        let chain = open_chain(db, &config);
+        assert_eq!(chain.get_current_height(), 1);
+        assert_eq!(*chain.current_hash.read(), hash);
+        assert!(chain.block_hash(2).is_none());
+        assert!(chain.transaction_address(tx_hashes[0]).is_some());
+        assert!(chain.transaction_address(tx_hashes[2]).is_none());
+
+        // the blocks after it are imported again
+        let block = create_block(&chain, privkey, Address::from(0), data, (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.get_current_height(), 2);
+    }
+
+    #[test]
+    fn test_rollback_clears_consensus_state() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        fs::create_dir_all(tempdir.join("wal")).unwrap();
+        fs::File::create(tempdir.join("wal").join("1.log")).unwrap();
+        fs::File::create(tempdir.join("sign_state.bin")).unwrap();
+        fs::File::create(tempdir.join("chain.toml")).unwrap();
+
+        rollback::clear_consensus_state(&tempdir).unwrap();
+        assert!(!tempdir.join("wal").exists());
+        assert!(!tempdir.join("sign_state.bin").exists());
+        assert!(tempdir.join("chain.toml").exists());
+        // nothing left to clear
+        rollback::clear_consensus_state(&tempdir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_states() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let config = Config {
+            state_flush_interval: 4,
+            ..Config::default()
+        };
+        let chain = init_chain_with(&config);
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+        let blocks: Vec<Block> = (0..3)
+            .map(|i| {
+                     let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+                     chain.set_block(block.clone());
+                     block
+                 })
+            .collect();
+        let contract = chain.localized_receipt(blocks[2].body().transactions()[0].hash()).unwrap().contract_address.unwrap();
+        chain.db.flush().unwrap();
+
+        // a node which stopped with the states of blocks 2 and 3 in memory
+        let db = Arc::new(in_memory(db::NUM_COLUMNS.unwrap()));
+        {
+            let crashed = open_chain(db.clone(), &config);
+            crashed.set_block(blocks[0].clone());
+        }
+        let mut batch = db.transaction();
+        for col in &[db::COL_HEADERS, db::COL_BODIES, db::COL_EXTRA] {
+            for (key, value) in chain.db.iter(*col) {
+                batch.put(*col, &key, &value);
+            }
+        }
+        db.write(batch).unwrap();
+        assert_eq!(journaldb::new(db.clone(), config.algorithm(), db::COL_STATE).latest_era(), Some(1));
+
+        let restarted = open_chain(db, &config);
+        assert_eq!(restarted.get_current_height(), 3);
+        assert_eq!(restarted.state_db.journal_db().latest_era(), Some(3));
+        assert_eq!(restarted.prove_storage(&contract, &H256::from(0), BlockId::Latest).unwrap().1, H256::from(42));
+    }
+
+    #[test]
+    fn test_header_version() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+
+        let mut block = create_block(&chain, privkey, Address::from(0), vec![0x60, 0x00], (0, 1));
+        block.set_version(::header::HEADER_VERSION + 1);
+        assert!(chain.set_block(block.clone()).is_none());
+
+        block.set_version(1);
+        block.set_extra_fields(&blockchain::BlockHeaderExtra::new());
+        assert!(chain.set_block(block).is_some());
+        let stored = chain.block_header_by_height(1).unwrap();
+        assert_eq!(stored.version(), 1);
+        assert_eq!(stored.hash(), *chain.current_hash.read());
+    }
+
+    #[test]
+    fn test_audit() {
+        use libchain::audit::{self, BlockAudit};
+        use std::io::BufRead;
+
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain_with(&Config { audit: true, ..Config::default() });
+
+        // PUSH1 42 PUSH1 1 SSTORE
+        let block = create_block(&chain, privkey, Address::from(0), vec![0x60, 0x2a, 0x60, 0x01, 0x55], (0, 1));
+        let tx_hash = block.body().transactions()[0].hash();
+        chain.set_block(block);
+        let block_audit = chain.block_audit(1).unwrap();
+        assert_eq!(block_audit.transactions.len(), 1);
+        assert_eq!(block_audit.transactions[0].hash, tx_hash);
+        assert!(block_audit.transactions[0].quota_used > 0);
+
+        let mut buf = Vec::new();
+        assert_eq!(audit::export_audits(&chain, 0..2, &mut buf).unwrap(), 1);
+        assert_eq!(audit::compare_audits(&chain, &buf[..]).unwrap(), None);
+
+        let mut remote: BlockAudit = serde_json::from_str(&(&buf[..]).lines().next().unwrap().unwrap()).unwrap();
+        remote.transactions[0].digest = H256::from(1);
+        let tampered = serde_json::to_string(&remote).unwrap();
+        let mismatch = audit::compare_audits(&chain, tampered.as_bytes()).unwrap().unwrap();
+        assert_eq!(mismatch.height, 1);
+        assert_eq!(mismatch.index, 0);
+        assert_eq!(mismatch.remote, Some(remote.transactions[0].clone()));
+    }
+
+    #[test]
+    fn test_replay_transaction() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+
+        // PUSH1 42 PUSH1 1 SSTORE
+        let block = create_block(&chain, privkey, Address::from(0), vec![0x60, 0x2a, 0x60, 0x01, 0x55], (0, 2));
+        chain.set_block(block.clone());
+        let tx_hash = block.body().transactions()[1].hash();
+        let receipt = chain.localized_receipt(tx_hash).unwrap();
+
+        let analytics = CallAnalytics {
+            transaction_tracing: true,
+            vm_tracing: false,
+            state_diffing: true,
+        };
+        let executed = chain.replay_transaction(tx_hash, analytics).unwrap();
+        assert!(executed.exception.is_none());
+        assert_eq!(executed.cumulative_gas_used, receipt.cumulative_gas_used);
+        assert!(!executed.trace.is_empty());
+        let contract = receipt.contract_address.unwrap();
+        assert!(executed.state_diff.unwrap().raw.contains_key(&contract));
+        // the chain is untouched
+        assert_eq!(chain.get_current_height(), 1);
+        assert_eq!(chain.state_at(BlockId::Latest).unwrap().root(), chain.block_header_by_height(1).unwrap().state_root());
+        assert!(chain.replay_transaction(H256::from(1), analytics).is_err());
+
+        // a transaction which is not in the chain, with any nonce
+        let t = create_tx(privkey, None, Address::from(0), vec![0x60, 0x2a, 0x60, 0x01, 0x55], 7);
+        let executed = chain.simulate_transaction(&t, BlockId::Number(1), analytics).unwrap();
+        assert_eq!(executed.cumulative_gas_used, executed.gas_used);
+        assert!(executed.exception.is_none());
+        assert!(chain.simulate_transaction(&t, BlockId::Number(0), analytics).is_err());
+    }
+
+    #[test]
+    fn test_genesis_alloc() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let contract = Address::from(0x100);
+        let node = Address::from(0x200);
+        // PUSH1 1 SLOAD
+        let code = vec![0x60, 0x01, 0x54];
+        let genesis = GenesisBuilder::new()
+            .contract(contract, code.clone())
+            .storage(contract, H256::from(1), H256::from(42))
+            .block_interval(3000)
+            .nodes(vec![node])
+            .build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+
+        assert!(chain.block_header_by_height(0).unwrap().state_root() != &HASH_NULL_RLP);
+        assert_eq!(chain.code_at(&contract, BlockId::Latest), Some(Some(code)));
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(1)).unwrap(), H256::from(42));
+        assert_eq!(chain.block_interval(), Some(3000));
+        assert_eq!(chain.node_list(), vec![node]);
+
+        // blocks are executed on top of it
+        let keypair = KeyPair::gen_keypair();
+        let block = create_block(&chain, keypair.privkey(), contract, vec![], (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.get_current_height(), 1);
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(1)).unwrap(), H256::from(42));
+    }
+
+    #[test]
+    fn test_block_interval() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let admin = KeyPair::gen_keypair();
+        let outsider = KeyPair::gen_keypair();
+        let genesis = GenesisBuilder::new().block_interval(3000).admins(vec![admin.address()]).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        let mut data = vec![0, 0, 0, 0];
+        data.extend_from_slice(&H256::from(1000));
+
+        let block = create_block(&chain, outsider.privkey(), native::sys_config_address(), data.clone(), (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.block_interval(), Some(3000));
+        let block = create_block(&chain, admin.privkey(), native::sys_config_address(), data, (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.block_interval(), Some(1000));
+    }
+
+    #[test]
+    fn test_node_manager() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let admin = KeyPair::gen_keypair();
+        let outsider = KeyPair::gen_keypair();
+        let node = Address::from(0x200);
+        let genesis = GenesisBuilder::new().nodes(vec![node]).admins(vec![admin.address()]).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        let call = |function: u8, node: Address| {
+            let mut data = vec![0, 0, 0, function];
+            data.extend_from_slice(&H256::from(node));
+            data
+        };
+
+        // a sender other than the admins can neither approve its node nor delete the others
+        let block = create_block(&chain, outsider.privkey(), native::node_manager_address(), call(0, outsider.address()), (0, 1));
+        chain.set_block(block);
+        let block = create_block(&chain, outsider.privkey(), native::node_manager_address(), call(1, node), (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.node_list(), vec![node]);
+
+        let block = create_block(&chain, admin.privkey(), native::node_manager_address(), call(0, outsider.address()), (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.node_list(), vec![node, outsider.address()]);
+    }
+
+    #[test]
+    fn test_commit_rounds() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let keypair = KeyPair::gen_keypair();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), GenesisBuilder::new().build(), &Config::default(), sync_tx);
+        for height in 1..5u64 {
+            let mut block = create_block(&chain, keypair.privkey(), Address::from(0), vec![], (height as u32, height as u32 + 1));
+            if height > 1 {
+                let proof = TendermintProof::new(height as usize - 1, height as usize % 2, H256::default(), HashMap::new());
+                block.set_proof(proof.into());
+            }
+            chain.set_block(block);
+        }
+        assert_eq!(chain.commit_rounds(), vec![(1, 0), (2, 1), (3, 0)]);
+
+        // the window read from the headers is the one slid by the blocks
+        chain.build_commit_rounds(4);
+        assert_eq!(chain.commit_rounds(), vec![(1, 0), (2, 1), (3, 0)]);
+        chain.update_commit_rounds(COMMIT_ROUNDS_WINDOW + 2, None);
+        assert_eq!(chain.commit_rounds(), vec![(2, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn test_light_client_proof() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let keypair = KeyPair::gen_keypair();
+        let (sync_tx, _) = channel();
+        let authorities = vec![keypair.address()];
+        let (chain, _) = Chain::init_chain(Arc::new(db), GenesisBuilder::new().authorities(authorities.clone()).build(), &Config::default(), sync_tx);
+        for height in 1..4u64 {
+            let mut block = create_block(&chain, keypair.privkey(), Address::from(0), vec![], (height as u32, height as u32 + 1));
+            if height > 1 {
+                let parent = chain.block_header_by_height(height - 1).unwrap().proposal_protobuf().crypt_hash();
+                let msg = serialize(&(height as usize - 1, 0usize, Step::Precommit, keypair.address(), Some(parent)), Infinite).unwrap();
+                let mut commits = HashMap::new();
+                commits.insert(keypair.address(), sign(keypair.privkey(), &msg.crypt_hash()).unwrap().into());
+                block.set_proof(TendermintProof::new(height as usize - 1, 0, parent, commits).into());
+            }
+            chain.set_block(block);
+        }
+        let proof = LightClientProof::decode(&chain.light_client_proof(2).unwrap()).unwrap();
+        let header = proof.header().unwrap();
+        assert_eq!(header.get_height(), 2);
+        assert_eq!(header.crypt_hash(), proof.proof.proposal);
+        assert_eq!(proof.proof.height, 2);
+        assert_eq!(proof.authorities_hash, authorities_hash(&authorities));
+        // the latest block is committed by the next one to come
+        assert_eq!(chain.light_client_proof(3), None);
+        assert_eq!(chain.light_client_proof(4), None);
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let admin = KeyPair::gen_keypair();
+        let outsider = KeyPair::gen_keypair();
+        let (old, new) = (Address::from(0x200), Address::from(0x201));
+        let genesis = GenesisBuilder::new().nodes(vec![old]).admins(vec![admin.address()]).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        let rotate = |height: u64| {
+            let mut data = vec![0, 0, 0, 2];
+            data.extend_from_slice(&H256::from(old));
+            data.extend_from_slice(&H256::from(new));
+            data.extend_from_slice(&H256::from(height));
+            data
+        };
+
+        // a sender other than the admins can not rotate the key
+        let block = create_block(&chain, outsider.privkey(), native::node_manager_address(), rotate(10), (0, 1));
+        chain.set_block(block);
+        assert!(chain.key_rotations().is_empty());
+
+        // nor the admins from the height of the block recording it, which the nodes have passed
+        let block = create_block(&chain, admin.privkey(), native::node_manager_address(), rotate(2), (0, 1));
+        chain.set_block(block);
+        assert!(chain.key_rotations().is_empty());
+
+        let block = create_block(&chain, admin.privkey(), native::node_manager_address(), rotate(10), (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.key_rotations(), vec![(10, old, new)]);
+    }
+
+    #[test]
+    fn test_block_quota_limit() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let keypair = KeyPair::gen_keypair();
+        let genesis = GenesisBuilder::new().block_quota_limit(30000).admins(vec![keypair.address()]).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        assert_eq!(chain.block_quota_limit(), Some(30000));
+        assert_eq!(chain.quota_per_transaction(), TRANSACTION_BASE_QUOTA);
+
+        // the first transaction fits the quota, the next one would take the block over
+        // the limit, so it is reverted and not applied, nor those after it
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+        let block = create_block(&chain, keypair.privkey(), Address::from(0), data, (0, 3));
+        chain.set_block(block.clone());
+        let hashes = block.body().transaction_hashes();
+        assert!(chain.localized_receipt(hashes[0]).is_some());
+        assert!(chain.localized_receipt(hashes[1]).is_none());
+        assert!(chain.localized_receipt(hashes[2]).is_none());
+        let gas_used = *chain.block_header_by_height(1).unwrap().gas_used();
+        assert!(gas_used <= U256::from(30000));
+        assert_eq!(chain.quota_per_transaction(), gas_used.low_u64());
+
+        // raised by governance, it takes effect from the next block
+        let mut data = vec![0, 0, 0, 0];
+        data.extend_from_slice(&H256::from(u64::max_value()));
+        let block = create_block(&chain, keypair.privkey(), native::quota_manager_address(), data, (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.block_quota_limit(), Some(u64::max_value()));
+
+        // a sender other than the admins cannot throttle the chain
+        let outsider = KeyPair::gen_keypair();
+        for function in 0..2 {
+            let mut data = vec![0, 0, 0, function];
+            data.extend_from_slice(&H256::from(1));
+            let block = create_block(&chain, outsider.privkey(), native::quota_manager_address(), data, (function as u32, function as u32 + 1));
+            chain.set_block(block);
+        }
+        assert_eq!(chain.block_quota_limit(), Some(u64::max_value()));
+        assert_eq!(chain.account_quota_limit(), None);
+    }
+
+    #[test]
+    fn test_account_quota_limit() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let genesis = GenesisBuilder::new().account_quota_limit(1).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        assert_eq!(chain.account_quota_limit(), Some(1));
+
+        let sponsor = KeyPair::gen_keypair();
+        let user = KeyPair::gen_keypair();
+        let other = KeyPair::gen_keypair();
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+        let txs = vec![
+            // charged to the sponsor, which uses up its quota
+            create_tx(user.privkey(), Some(sponsor.privkey()), Address::from(0), data.clone(), 0),
+            // the sponsor can pay no more in this block
+            create_tx(other.privkey(), Some(sponsor.privkey()), Address::from(0), data.clone(), 0),
+            // the sender of the first one has not been charged
+            create_tx(user.privkey(), None, Address::from(0), data.clone(), 1),
+        ];
+        assert_eq!(txs[0].fee_payer(), Some(txs[1].quota_payer()));
+        assert_eq!(txs[2].quota_payer(), txs[0].sender());
+
+        let mut block = Block::new();
+        block.set_parent_hash(chain.current_hash.read().clone());
+        block.set_timestamp(UNIX_EPOCH.elapsed().unwrap().as_secs());
+        block.set_number(chain.get_current_height() + 1);
+        let mut body = BlockBody::new();
+        body.set_transactions(txs);
+        block.set_body(body);
+        chain.set_block(block.clone());
+
+        let hashes = block.body().transaction_hashes();
+        assert!(chain.localized_receipt(hashes[0]).is_some());
+        assert!(chain.localized_receipt(hashes[1]).is_none());
+        assert!(chain.localized_receipt(hashes[2]).is_some());
+    }
+
+    #[test]
+    fn test_permissions() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let admin = KeyPair::gen_keypair();
+        let user = KeyPair::gen_keypair();
+        let outsider = KeyPair::gen_keypair();
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+        let txs = vec![
+            create_tx(admin.privkey(), None, Address::from(0), data.clone(), 0),
+            // may send but not deploy
+            create_tx(user.privkey(), None, Address::from(0), data.clone(), 0),
+            create_tx(outsider.privkey(), None, Address::from(0x100), vec![], 0),
+        ];
+        let (admin_address, user_address) = (*txs[0].sender(), *txs[1].sender());
+        let genesis = GenesisBuilder::new()
+            .senders(vec![admin_address, user_address])
+            .creators(vec![admin_address])
+            .admins(vec![admin_address])
+            .build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        assert_eq!(chain.permitted_accounts(native::CREATE_PERMISSION), vec![admin_address]);
+
+        let mut block = Block::new();
+        block.set_parent_hash(chain.current_hash.read().clone());
+        block.set_timestamp(UNIX_EPOCH.elapsed().unwrap().as_secs());
+        block.set_number(chain.get_current_height() + 1);
+        let mut body = BlockBody::new();
+        body.set_transactions(txs);
+        block.set_body(body);
+        chain.set_block(block.clone());
+
+        let hashes = block.body().transaction_hashes();
+        assert!(chain.localized_receipt(hashes[0]).is_some());
+        assert!(chain.localized_receipt(hashes[1]).is_none());
+        assert!(chain.localized_receipt(hashes[2]).is_none());
+
+        // granted by governance, it takes effect from the next block
+        let mut data = vec![0, 0, 0, 2];
+        data.extend_from_slice(&H256::from(user_address));
+        let block = create_block(&chain, admin.privkey(), native::permission_manager_address(), data, (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.permitted_accounts(native::CREATE_PERMISSION), vec![admin_address, user_address]);
+
+        // a sender other than the admins cannot change the lists
+        let mut data = vec![0, 0, 0, 3];
+        data.extend_from_slice(&H256::from(admin_address));
+        let block = create_block(&chain, user.privkey(), native::permission_manager_address(), data, (0, 1));
+        chain.set_block(block);
+        let mut data = vec![0, 0, 0, 4];
+        data.extend_from_slice(&H256::from(user_address));
+        let block = create_block(&chain, user.privkey(), native::permission_manager_address(), data, (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.permitted_accounts(native::CREATE_PERMISSION), vec![admin_address, user_address]);
+        assert_eq!(chain.permitted_accounts(native::ADMIN_PERMISSION), vec![admin_address]);
+    }
+
+    #[test]
+    fn test_chain_id() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let genesis = GenesisBuilder::new().chain_id(7).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        assert_eq!(chain.chain_id(), 7);
+
+        let keypair = KeyPair::gen_keypair();
+        let signed = |chain_id: u32, nonce: u32| {
+            let mut tx = blockchain::Transaction::new();
+            tx.set_nonce(U256::from(nonce).to_hex());
+            tx.set_data(vec![0x60, 0x2a, 0x60, 0x00, 0x55]);
+            tx.set_chain_id(chain_id);
+            tx.set_version(::libproto::TRANSACTION_VERSION);
+            let mut uv_tx = blockchain::UnverifiedTransaction::new();
+            uv_tx.set_transaction(tx);
+            let mut stx = blockchain::SignedTransaction::new();
+            stx.set_transaction_with_sig(uv_tx);
+            stx.sign(*keypair.privkey());
+            SignedTransaction::new(&stx).unwrap()
+        };
+
+        // signed for another chain, or not bound to one, the transactions are not executed
+        let mut block = create_block(&chain, keypair.privkey(), Address::from(0), vec![], (0, 0));
+        block.body.set_transactions(vec![signed(8, 0), signed(0, 0), signed(7, 0)]);
+        chain.set_block(block.clone());
+        let hashes = block.body().transaction_hashes();
+        assert!(chain.localized_receipt(hashes[0]).is_none());
+        assert!(chain.localized_receipt(hashes[1]).is_none());
+        assert!(chain.localized_receipt(hashes[2]).is_some());
+    }
+
+    #[test]
+    fn test_storage_expiry() {
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let contract = Address::from(0x100);
+        // PUSH1 42 PUSH1 1 SSTORE
+        let code = vec![0x60, 0x2a, 0x60, 0x01, 0x55];
+        let admin = KeyPair::gen_keypair();
+        let genesis = GenesisBuilder::new()
+            .contract(contract, code)
+            .storage(contract, H256::from(0), H256::from(7))
+            .storage_expiry(2)
+            .admins(vec![admin.address()])
+            .build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        let rent = native::storage_rent_address();
+
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let block = create_block(&chain, privkey, contract, vec![], (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(1)).unwrap(), H256::from(42));
+        assert_eq!(chain.state().storage_at(&rent, &native::touched_key(&contract)).unwrap(), H256::from(1));
+        let root = chain.state().storage_root(&contract).unwrap().unwrap();
+
+        // not touched for the expiry, the storage is evicted keeping its root
+        for _ in 0..2 {
+            let block = create_block(&chain, privkey, contract, vec![], (1, 1));
+            chain.set_block(block);
+        }
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(0)).unwrap(), H256::default());
+        assert_eq!(chain.state().storage_at(&rent, &native::evicted_key(&contract)).unwrap(), root);
+
+        // the contract cannot run
+        let block = create_block(&chain, privkey, contract, vec![], (1, 2));
+        chain.set_block(block);
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(1)).unwrap(), H256::default());
+
+        // restored from the entries matching the root
+        let mut data = vec![0, 0, 0, 1];
+        data.extend_from_slice(&H256::from(contract));
+        for &(key, value) in &[(0, 7), (1, 42)] {
+            data.extend_from_slice(&H256::from(key));
+            data.extend_from_slice(&H256::from(value));
+        }
+        let block = create_block(&chain, privkey, rent, data, (2, 3));
+        chain.set_block(block);
+        assert_eq!(chain.state().storage_at(&contract, &H256::from(0)).unwrap(), H256::from(7));
+        assert_eq!(chain.state().storage_at(&rent, &native::evicted_key(&contract)).unwrap(), H256::default());
+        assert_eq!(chain.state().storage_root(&contract).unwrap().unwrap(), root);
+
+        // only the admins set the expiry
+        let expiry = H256::from(native::STORAGE_EXPIRY_KEY);
+        let mut data = vec![0, 0, 0, 0];
+        data.extend_from_slice(&H256::from(1));
+        let block = create_block(&chain, privkey, rent, data.clone(), (3, 4));
+        chain.set_block(block);
+        assert_eq!(chain.state().storage_at(&rent, &expiry).unwrap(), H256::from(2));
+        let block = create_block(&chain, admin.privkey(), rent, data, (0, 1));
+        chain.set_block(block);
+        assert_eq!(chain.state().storage_at(&rent, &expiry).unwrap(), H256::from(1));
+    }
+
+    #[test]
+    fn test_export_blocks() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+        for i in 0..3 {
+            let block = create_block(&chain, privkey, Address::from(0), data.clone(), (i, i + 1));
+            chain.set_block(block);
+        }
+
+        let mut file = Vec::new();
+        assert_eq!(export::export_blocks(&chain, 0..4, &mut file).unwrap(), 4);
+        assert!(export::export_blocks(&chain, 0..5, &mut Vec::new()).is_err());
+
+        // the genesis is skipped, the node has it already
+        let imported = init_chain();
+        assert_eq!(export::import_blocks(&imported, &mut &file[..]).unwrap(), 3);
+        assert_eq!(imported.get_current_height(), 3);
+        assert_eq!(*imported.current_hash.read(), *chain.current_hash.read());
+        assert_eq!(imported.state().root(), chain.state().root());
+        assert_eq!(export::import_blocks(&imported, &mut &file[..]).unwrap(), 0);
+
+        assert!(export::import_blocks(&imported, &mut &file[1..]).is_err());
+    }
+
+    #[test]
+    fn test_prove_storage() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+        // PUSH1 42 PUSH1 0 SSTORE
+        let data = vec![0x60, 0x2a, 0x60, 0x00, 0x55];
+
+        let block = create_block(&chain, privkey, Address::from(0), data, (0, 1));
+        chain.set_block(block.clone());
+        let txhash = block.body().transactions()[0].hash();
+        let contract = chain.localized_receipt(txhash).unwrap().contract_address.unwrap();
+        let state_root = *chain.block_header(BlockId::Latest).unwrap().state_root();
+
+        let (proof, account) = chain.prove_account(&contract, BlockId::Latest).unwrap();
+        let value = ::state::check_proof(&proof, &state_root, &contract).unwrap().unwrap();
+        assert_eq!(::rlp::decode::<BasicAccount>(&value), account);
+
+        let key = H256::from(0);
+        let (proof, value) = chain.prove_storage(&contract, &key, BlockId::Latest).unwrap();
+        assert_eq!(value, H256::from(42));
+        assert!(::state::check_proof(&proof, &account.storage_root, &key).unwrap().is_some());
+
+        assert!(chain.prove_account(&contract, BlockId::Number(100)).is_none());
+    }
+
+    #[test]
+    fn test_storage_history() {
+        let keypair = KeyPair::gen_keypair();
+        let privkey = keypair.privkey();
+        let chain = init_chain();
+        // the contract stores its calldata at key 0: PUSH1 0 CALLDATALOAD PUSH1 0 SSTORE
+        let mut data = vec![0x60, 0x06, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x06, 0x60, 0x00, 0xf3];
+        data.extend_from_slice(&[0x60, 0x00, 0x35, 0x60, 0x00, 0x55]);
+
+        let block = create_block(&chain, privkey, Address::from(0), data, (0, 1));
+        chain.set_block(block.clone());
+        let txhash = block.body().transactions()[0].hash();
+        let contract = chain.localized_receipt(txhash).unwrap().contract_address.unwrap();
+        for (nonce, value) in vec![(1, 7), (2, 9)] {
+            let block = create_block(&chain, privkey, contract, H256::from(value).to_vec(), (nonce, nonce + 1));
+            chain.set_block(block);
+        }
+
+        let key = H256::from(0);
+        let history = chain.storage_history(&contract, &key, 0, 100, 10);
+        assert_eq!(history, vec![(0, H256::from(0)), (1, H256::from(0)), (2, H256::from(7)), (3, H256::from(9))]);
+        assert_eq!(chain.storage_history(&contract, &key, 2, 3, 1), vec![(2, H256::from(7))]);
+        assert!(chain.storage_history(&contract, &key, 4, 10, 10).is_empty());
+    }
+
     #[test]
     fn test_contract() {
         //let keypair = cita_ed25519::KeyPair::gen_keypair();
@@ -1177,4 +2795,30 @@ mod tests {
         assert_eq!(call_result, Ok(Bytes::from(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10])));
         println!("call_result: {:?}", call_result);
     }
+
+    #[test]
+    fn test_authorities_at() {
+        let chain = init_chain();
+        assert_eq!(chain.authorities_at(10), None);
+
+        let tempdir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let db = Database::open(&DatabaseConfig::with_columns(db::NUM_COLUMNS), &tempdir.to_str().unwrap()).unwrap();
+        let admin = KeyPair::gen_keypair();
+        let (old, new, other) = (Address::from(0x200), Address::from(0x201), Address::from(0x202));
+        let genesis = GenesisBuilder::new().nodes(vec![old, other]).authorities(vec![old, other]).admins(vec![admin.address()]).build();
+        let (sync_tx, _) = channel();
+        let (chain, _) = Chain::init_chain(Arc::new(db), genesis, &Config::default(), sync_tx);
+        let mut data = vec![0, 0, 0, 2];
+        data.extend_from_slice(&H256::from(old));
+        data.extend_from_slice(&H256::from(new));
+        data.extend_from_slice(&H256::from(10));
+        let block = create_block(&chain, admin.privkey(), native::node_manager_address(), data, (0, 1));
+        chain.set_block(block);
+
+        // the rotated key signs from the height the rotation takes effect, in place of the old one
+        assert_eq!(chain.authorities_at(9), Some(vec![old, other]));
+        assert_eq!(chain.authorities_at(10), Some(vec![new, other]));
+        assert_eq!(chain.authorities_at(100), Some(vec![new, other]));
+        assert_eq!(chain.authority_history(), vec![(0, vec![old, other]), (10, vec![new, other])]);
+    }
 }
@@ -0,0 +1,353 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use db;
+use serde_json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use util::journaldb::Algorithm;
+#[cfg(feature = "lmdb")]
+use util::kvdb::LmdbDatabase;
+use util::kvdb::{CompactionProfile, Database, DatabaseConfig, KeyValueDB};
+
+/// States of the recent blocks kept by a pruning node.
+pub const DEFAULT_PRUNING_HISTORY: u64 = 64;
+
+/// Accounts of the latest state kept in memory.
+pub const DEFAULT_ACCOUNT_CACHE_SIZE: usize = 8192;
+
+/// What a node keeps of the history of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeMode {
+    /// All the blocks and the states of them.
+    Archive,
+    /// All the blocks, and the states of the recent ones.
+    Full,
+    /// All the headers, and the bodies, receipts and states of the recent blocks.
+    Light,
+}
+
+/// Local settings of the chain, which may differ between the nodes.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Config {
+    /// Mode of the node, an archive node ignores `pruning`. Taken from `pruning` if not set.
+    #[serde(default)]
+    pub mode: Option<NodeMode>,
+    /// Journal algorithm of the state db, "archive" keeps the states of all blocks,
+    /// while "fast" prunes the ones older than `pruning_history` blocks.
+    /// A light node also prunes the blocks older than `pruning_history`.
+    #[serde(default = "default_pruning")]
+    pub pruning: String,
+    #[serde(default = "default_pruning_history")]
+    pub pruning_history: u64,
+    /// Restore a fresh node from the snapshot of a peer instead of executing all the blocks.
+    #[serde(default)]
+    pub fast_sync: bool,
+    /// Blocks between the snapshots served to the peers, 0 takes none.
+    #[serde(default)]
+    pub snapshot_interval: u64,
+    /// Record the accounts and storage changed by each block, served by `cita_getStateDiff`.
+    #[serde(default)]
+    pub state_diff: bool,
+    /// Record the quota used and a digest of the state read and written by each transaction,
+    /// compared with the ones of another node to find the transaction executed differently.
+    #[serde(default)]
+    pub audit: bool,
+    /// Blocks older than it are moved out of the database into the append-only
+    /// ancient store, 0 keeps all of them in the database. A light node ignores it.
+    #[serde(default)]
+    pub ancient_blocks: u64,
+    /// Directory of the ancient store, which may be on a cheaper disk, `$DATA_PATH/ancient` if not set.
+    #[serde(default)]
+    pub ancient_path: Option<String>,
+    /// Accounts of the latest state kept in memory along with their recently used storage,
+    /// which saves the trie lookups of the following blocks. 0 disables the cache.
+    #[serde(default = "default_account_cache_size")]
+    pub account_cache_size: usize,
+    /// Blocks between the writes of the state to the disk, the trie nodes of the blocks
+    /// in between are kept in memory, which saves the writes on a slow disk. The blocks
+    /// themselves are written at once, a crash makes the node execute them again on start.
+    #[serde(default = "default_state_flush_interval")]
+    pub state_flush_interval: u64,
+    /// Tuning of the database, the defaults suit SSDs.
+    #[serde(default)]
+    pub database: DatabaseOptions,
+}
+
+/// Settings of the chain database.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DatabaseOptions {
+    /// "rocksdb", or "lmdb" which takes none of the settings below, if built with the `lmdb` feature.
+    pub backend: String,
+    /// "ssd", "hdd", or "auto" to detect the disk of the database.
+    pub compaction_profile: String,
+    /// "universal" or "level", taken from the profile if not set.
+    pub compaction_style: Option<String>,
+    /// Block cache of each column in MiB.
+    pub cache_size: usize,
+    /// Block cache in MiB of the columns by name: "state", "headers", "bodies", "extra",
+    /// "trace", "account_bloom" and "node_info".
+    pub column_cache_sizes: HashMap<String, usize>,
+    pub max_open_files: i32,
+    /// Write ahead log of the database, the recent writes may be lost in a crash without it.
+    pub wal: bool,
+    /// Size of the write ahead log in MiB at which the columns are flushed.
+    pub max_total_wal_size: u64,
+    /// Size of the memtables of all the columns in MiB.
+    pub write_buffer_size: usize,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        DatabaseOptions {
+            backend: "rocksdb".to_string(),
+            compaction_profile: "ssd".to_string(),
+            compaction_style: None,
+            cache_size: 2,
+            column_cache_sizes: HashMap::new(),
+            max_open_files: 512,
+            wal: true,
+            max_total_wal_size: 64,
+            write_buffer_size: 2,
+        }
+    }
+}
+
+fn column(name: &str) -> Option<u32> {
+    match name {
+        "state" => db::COL_STATE,
+        "headers" => db::COL_HEADERS,
+        "bodies" => db::COL_BODIES,
+        "extra" => db::COL_EXTRA,
+        "trace" => db::COL_TRACE,
+        "account_bloom" => db::COL_ACCOUNT_BLOOM,
+        "node_info" => db::COL_NODE_INFO,
+        _ => panic!("Invalid database column {}.", name),
+    }
+}
+
+impl DatabaseOptions {
+    /// Settings of the database at the path.
+    pub fn database_config(&self, path: &Path) -> DatabaseConfig {
+        let mut compaction = match self.compaction_profile.as_str() {
+            "ssd" => CompactionProfile::ssd(),
+            "hdd" => CompactionProfile::hdd(),
+            "auto" => CompactionProfile::auto(path),
+            profile => panic!("Invalid compaction profile {}.", profile),
+        };
+        if let Some(ref style) = self.compaction_style {
+            compaction.style = style.parse().expect("Invalid compaction style.");
+        }
+
+        let mut config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
+        config.compaction = compaction;
+        config.max_open_files = self.max_open_files;
+        config.wal = self.wal;
+        config.max_total_wal_size = self.max_total_wal_size * 1024 * 1024;
+        config.write_buffer_size = self.write_buffer_size * 1024 * 1024;
+        for col in 0..db::NUM_COLUMNS.unwrap_or(0) {
+            config.set_cache(Some(col), self.cache_size);
+        }
+        for (name, size) in &self.column_cache_sizes {
+            config.set_cache(column(name), *size);
+        }
+        config
+    }
+
+    /// Open the database at the path with the configured backend.
+    pub fn open(&self, path: &str) -> Result<Arc<KeyValueDB>, String> {
+        let config = self.database_config(Path::new(path));
+        // Files left by the other backend, which would be taken as an empty database.
+        let (backend, other) = match self.backend.as_str() {
+            "rocksdb" => ("rocksdb", "data.mdb"),
+            "lmdb" => ("lmdb", "CURRENT"),
+            backend => return Err(format!("unknown database backend {}", backend)),
+        };
+        if Path::new(path).join(other).exists() {
+            return Err(format!("database at {} was not created by {}", path, backend));
+        }
+        match backend {
+            "rocksdb" => Database::open(&config, path).map(|db| Arc::new(db) as Arc<KeyValueDB>),
+            _ => open_lmdb(&config, path),
+        }
+    }
+}
+
+#[cfg(feature = "lmdb")]
+fn open_lmdb(config: &DatabaseConfig, path: &str) -> Result<Arc<KeyValueDB>, String> {
+    LmdbDatabase::open(config, path).map(|db| Arc::new(db) as Arc<KeyValueDB>)
+}
+
+#[cfg(not(feature = "lmdb"))]
+fn open_lmdb(_: &DatabaseConfig, _: &str) -> Result<Arc<KeyValueDB>, String> {
+    Err("the chain is built without the lmdb feature".to_string())
+}
+
+fn default_pruning() -> String {
+    Algorithm::default().as_str().to_string()
+}
+
+fn default_pruning_history() -> u64 {
+    DEFAULT_PRUNING_HISTORY
+}
+
+fn default_account_cache_size() -> usize {
+    DEFAULT_ACCOUNT_CACHE_SIZE
+}
+
+fn default_state_flush_interval() -> u64 {
+    1
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            mode: None,
+            pruning: default_pruning(),
+            pruning_history: default_pruning_history(),
+            fast_sync: false,
+            snapshot_interval: 0,
+            state_diff: false,
+            audit: false,
+            ancient_blocks: 0,
+            ancient_path: None,
+            account_cache_size: default_account_cache_size(),
+            state_flush_interval: default_state_flush_interval(),
+            database: DatabaseOptions::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn init(path: &str) -> Config {
+        let config_file = File::open(path).unwrap();
+        let fconfig = BufReader::new(config_file);
+        let config: Config = serde_json::from_reader(fconfig).expect("Failed to load chain config.");
+        let algorithm = config.algorithm();
+        if !algorithm.is_stable() {
+            warn!("pruning algorithm {} is experimental", algorithm);
+        }
+        assert!(config.pruning_history > 0, "pruning_history must be positive.");
+        assert!(config.state_flush_interval > 0, "state_flush_interval must be positive.");
+        if let Some(mode) = config.mode {
+            assert!(mode == NodeMode::Archive || algorithm != Algorithm::Archive, "{:?} node must prune the states.", mode);
+        }
+        config
+    }
+
+    /// Journal algorithm of the state db.
+    pub fn algorithm(&self) -> Algorithm {
+        match self.mode {
+            Some(NodeMode::Archive) => Algorithm::Archive,
+            _ => self.pruning.parse().expect("Invalid pruning algorithm."),
+        }
+    }
+
+    /// Mode of the node, a pruning one is a full node unless set.
+    pub fn mode(&self) -> NodeMode {
+        match self.mode {
+            Some(mode) => mode,
+            None if self.algorithm() == Algorithm::Archive => NodeMode::Archive,
+            None => NodeMode::Full,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate mktemp;
+
+    use super::*;
+    use serde_json;
+    use util::kvdb::CompactionStyle;
+
+    #[test]
+    fn parse_config() {
+        let config: Config = serde_json::from_str(r#"{"pruning": "archive"}"#).unwrap();
+        assert_eq!(config.algorithm(), Algorithm::Archive);
+        assert_eq!(config.pruning_history, DEFAULT_PRUNING_HISTORY);
+        assert!(!config.fast_sync);
+        assert_eq!(config.mode(), NodeMode::Archive);
+
+        let config: Config = serde_json::from_str(r#"{"mode": "archive", "pruning": "fast"}"#).unwrap();
+        assert_eq!(config.algorithm(), Algorithm::Archive);
+        let config: Config = serde_json::from_str(r#"{"mode": "light"}"#).unwrap();
+        assert_eq!(config.mode(), NodeMode::Light);
+        assert_eq!(config.algorithm(), Algorithm::OverlayRecent);
+
+        let config: Config = serde_json::from_str(r#"{"fast_sync": true, "snapshot_interval": 1000}"#).unwrap();
+        assert!(config.fast_sync);
+        assert_eq!(config.snapshot_interval, 1000);
+        assert!(!config.state_diff);
+        let config: Config = serde_json::from_str(r#"{"state_diff": true}"#).unwrap();
+        assert!(config.state_diff);
+        assert!(!config.audit);
+        let config: Config = serde_json::from_str(r#"{"audit": true}"#).unwrap();
+        assert!(config.audit);
+        let config: Config = serde_json::from_str(r#"{"ancient_blocks": 100000, "ancient_path": "/mnt/ancient"}"#).unwrap();
+        assert_eq!(config.ancient_blocks, 100000);
+        assert_eq!(config.ancient_path, Some("/mnt/ancient".to_string()));
+        assert_eq!(config.account_cache_size, DEFAULT_ACCOUNT_CACHE_SIZE);
+        let config: Config = serde_json::from_str(r#"{"account_cache_size": 0}"#).unwrap();
+        assert_eq!(config.account_cache_size, 0);
+        assert_eq!(config.state_flush_interval, 1);
+        let config: Config = serde_json::from_str(r#"{"state_flush_interval": 16}"#).unwrap();
+        assert_eq!(config.state_flush_interval, 16);
+
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.algorithm(), Algorithm::OverlayRecent);
+    }
+
+    #[test]
+    fn database_config() {
+        let config: Config = serde_json::from_str(r#"{"database": {"compaction_profile": "hdd", "cache_size": 8, "column_cache_sizes": {"state": 256}, "wal": false}}"#).unwrap();
+        let db_config = config.database.database_config(Path::new("."));
+        assert_eq!(db_config.compaction, CompactionProfile::hdd());
+        assert_eq!(db_config.cache_sizes[&db::COL_STATE], 256);
+        assert_eq!(db_config.cache_sizes[&db::COL_BODIES], 8);
+        assert!(!db_config.wal);
+        assert_eq!(db_config.max_total_wal_size, 64 * 1024 * 1024);
+
+        let config: Config = serde_json::from_str(r#"{"database": {"compaction_style": "level"}}"#).unwrap();
+        let db_config = config.database.database_config(Path::new("."));
+        assert_eq!(db_config.compaction.style, CompactionStyle::Level);
+        assert_eq!(db_config.compaction.initial_file_size, CompactionProfile::ssd().initial_file_size);
+    }
+
+    #[test]
+    #[cfg(feature = "lmdb")]
+    fn open_backend() {
+        let path = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let path = path.to_str().unwrap();
+        let config: Config = serde_json::from_str(r#"{"database": {"backend": "lmdb"}}"#).unwrap();
+        {
+            let db = config.database.open(path).unwrap();
+            let mut batch = db.transaction();
+            batch.put(db::COL_EXTRA, b"key", b"value");
+            db.write(batch).unwrap();
+        }
+        let db = config.database.open(path).unwrap();
+        assert_eq!(&*db.get(db::COL_EXTRA, b"key").unwrap().unwrap(), b"value");
+        assert!(Config::default().database.open(path).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the blocks into a file and import of them back, for seeding new
+//! nodes from files or archiving the history before a light node prunes it.
+//!
+//! The file starts with `MAGIC` and the big endian `EXPORT_VERSION`, followed by
+//! the blocks in order, each one the big endian length of its rlp and the rlp.
+//! Imported blocks are executed and their proofs checked like the synced ones.
+
+use byteorder::{BigEndian, ByteOrder};
+use header::BlockNumber;
+use libchain::block::Block;
+use libchain::chain::Chain;
+use rlp::{self, DecoderError, UntrustedRlp};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+pub const MAGIC: &'static [u8] = b"CITABLKS";
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Decoder(DecoderError),
+    Db(String),
+    /// The file is not an export of blocks.
+    InvalidFormat,
+    /// The export format is not supported.
+    Version(u32),
+    MissingBlock(BlockNumber),
+    /// The block does not follow the current block of the chain.
+    Gap { expected: BlockNumber, found: BlockNumber },
+    /// The block failed the proof check or does not link to the chain.
+    InvalidBlock(BlockNumber),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Decoder(ref err) => write!(f, "{:?}", err),
+            Error::Db(ref err) => write!(f, "{}", err),
+            Error::InvalidFormat => write!(f, "not an export of blocks"),
+            Error::Version(version) => write!(f, "unsupported export version {}", version),
+            Error::MissingBlock(height) => write!(f, "missing block {}", height),
+            Error::Gap { expected, found } => write!(f, "expected block {}, found block {}", expected, found),
+            Error::InvalidBlock(height) => write!(f, "invalid block {}", height),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<DecoderError> for Error {
+    fn from(err: DecoderError) -> Self {
+        Error::Decoder(err)
+    }
+}
+
+/// Write the blocks of the range into the writer, returns the number of blocks written.
+pub fn export_blocks<W: Write>(chain: &Chain, range: Range<BlockNumber>, writer: &mut W) -> Result<u64, Error> {
+    let mut version = [0; 4];
+    BigEndian::write_u32(&mut version, EXPORT_VERSION);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&version)?;
+
+    let mut count = 0;
+    for height in range {
+        let block = chain.block_by_height(height).ok_or(Error::MissingBlock(height))?;
+        let data = rlp::encode(&block);
+        let mut len = [0; 4];
+        BigEndian::write_u32(&mut len, data.len() as u32);
+        writer.write_all(&len)?;
+        writer.write_all(&data)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Import the blocks of the reader into the chain, skipping the ones it already has.
+/// Returns the number of blocks imported.
+pub fn import_blocks<R: Read>(chain: &Chain, reader: &mut R) -> Result<u64, Error> {
+    let mut header = [0; 12];
+    reader.read_exact(&mut header).map_err(|_| Error::InvalidFormat)?;
+    if &header[..8] != MAGIC {
+        return Err(Error::InvalidFormat);
+    }
+    let version = BigEndian::read_u32(&header[8..]);
+    if version != EXPORT_VERSION {
+        return Err(Error::Version(version));
+    }
+
+    let mut count = 0;
+    let result = import_records(chain, reader, &mut count);
+    // the blocks imported before a failure are kept
+    chain.db.flush().map_err(Error::Db)?;
+    info!("imported {} blocks, current height {}", count, chain.get_current_height());
+    result.map(|_| count)
+}
+
+fn import_records<R: Read>(chain: &Chain, reader: &mut R, count: &mut u64) -> Result<(), Error> {
+    while let Some(data) = read_record(reader)? {
+        let block: Block = UntrustedRlp::new(&data).as_val()?;
+        let height = block.number();
+        let expected = chain.get_current_height() + 1;
+        if height < expected {
+            continue;
+        }
+        if height > expected {
+            return Err(Error::Gap { expected: expected, found: height });
+        }
+        chain.set_block(block).ok_or(Error::InvalidBlock(height))?;
+        *count += 1;
+    }
+    Ok(())
+}
+
+/// Read a length prefixed record, `None` at the end of the reader.
+fn read_record<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut len = [0; 4];
+    let mut read = 0;
+    while read < len.len() {
+        match reader.read(&mut len[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(Error::Io(io::ErrorKind::UnexpectedEof.into())),
+            n => read += n,
+        }
+    }
+    let mut data = vec![0; BigEndian::read_u32(&len) as usize];
+    reader.read_exact(&mut data)?;
+    Ok(Some(data))
+}
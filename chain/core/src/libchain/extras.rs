@@ -19,12 +19,17 @@
 
 use bloomchain;
 use blooms::{GroupPosition, BloomGroup};
+use byteorder::{BigEndian, ByteOrder};
 use db::{Key, ConstKey};
+use executive::contract_address;
 use header::{BlockNumber, Header};
+use libchain::audit::BlockAudit;
 use libchain::block::BlockBody;
 use receipt::Receipt;
 use rlp::*;
 use std::ops::{Deref, Index};
+use types::state_diff::StateDiff;
+use types::transaction::Action;
 use util::*;
 
 /// Represents index of extra data in database
@@ -38,6 +43,12 @@ pub enum ExtrasIndex {
     BlocksBlooms = 2,
     /// Block hash index
     BlockHash = 3,
+    /// Block state diff index
+    StateDiff = 4,
+    /// Account transactions index
+    AccountTransaction = 5,
+    /// Block execution audit index
+    BlockAudit = 6,
 }
 
 impl Key<H256> for ConstKey {
@@ -76,6 +87,10 @@ impl Key<BlockBody> for H256 {
     fn key(&self) -> H256 {
         *self
     }
+
+    fn compressed(&self) -> bool {
+        true
+    }
 }
 
 pub struct BlockNumberKey([u8; 5]);
@@ -123,6 +138,80 @@ impl Key<BlockReceipts> for H256 {
     fn key(&self) -> H264 {
         with_index(self, ExtrasIndex::BlockReceipts)
     }
+
+    fn compressed(&self) -> bool {
+        true
+    }
+}
+
+impl Key<StateDiff> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ExtrasIndex::StateDiff)
+    }
+}
+
+impl Key<BlockAudit> for H256 {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        with_index(self, ExtrasIndex::BlockAudit)
+    }
+}
+
+/// Position of a transaction sent or received by an account, the keys of an
+/// account are ordered by the block number and the index in the block.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct AccountTransactionPosition {
+    pub address: Address,
+    pub block_number: BlockNumber,
+    pub index: u32,
+}
+
+impl AccountTransactionPosition {
+    /// Positions of the transactions of the block, under their senders and their receivers,
+    /// which for a contract creation is the created contract.
+    pub fn of_block(block_number: BlockNumber, body: &BlockBody) -> Vec<(AccountTransactionPosition, H256)> {
+        let mut positions = Vec::new();
+        for (index, tx) in body.transactions().iter().enumerate() {
+            let receiver = match *tx.action() {
+                Action::Call(ref address) => Some(*address),
+                Action::Create => Some(contract_address(tx.sender(), tx.nonce())),
+                Action::Store => None,
+            };
+            let position = |address: Address| AccountTransactionPosition {
+                address: address,
+                block_number: block_number,
+                index: index as u32,
+            };
+            positions.push((position(*tx.sender()), tx.hash()));
+            if let Some(receiver) = receiver {
+                if receiver != *tx.sender() {
+                    positions.push((position(receiver), tx.hash()));
+                }
+            }
+        }
+        positions
+    }
+
+    /// Block number of the key.
+    pub fn block_number_of(key: &[u8]) -> BlockNumber {
+        BigEndian::read_u64(&key[21..29])
+    }
+}
+
+impl Key<H256> for AccountTransactionPosition {
+    type Target = H264;
+
+    fn key(&self) -> H264 {
+        let mut result = H264::default();
+        result[0] = ExtrasIndex::AccountTransaction as u8;
+        result[1..21].copy_from_slice(&self.address);
+        BigEndian::write_u64(&mut result[21..29], self.block_number);
+        BigEndian::write_u32(&mut result[29..33], self.index);
+        result
+    }
 }
 
 pub struct LogGroupKey([u8; 6]);
@@ -15,17 +15,107 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+//! The genesis block and its state, built from a JSON spec or by `GenesisBuilder`.
+//!
+//! The spec may allocate accounts with their nonce, code and storage, e.g. the
+//! runtime bytecode of Solidity contracts deployed at block 0. Accounts have no
+//! balance in CITA, so there is none to allocate. The parameters of the system
+//! contracts are written into their storage.
+
+use factory::Factories;
+use jsonrpc_types::bytes::Bytes;
 use libchain::block::Block;
+use native;
 use serde_json;
-use util::H256;
+use state::State;
+use state_db::StateDB;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufReader;
+use util::{H256, U256, Address};
 use util::HASH_NULL_RLP;
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct Spec {
     pub prevhash: H256,
     pub timestamp: u64,
+    /// Validators signing from the first block, used to check the proofs.
+    #[serde(default)]
+    pub authorities: Vec<Address>,
+    /// Consensus of the chain, "tendermint", "raft" or "poa". The proofs of a tendermint
+    /// chain are checked against its authorities, so they must be given.
+    #[serde(default)]
+    pub consensus: Option<String>,
+    /// Accounts of the genesis state.
+    #[serde(default)]
+    pub alloc: BTreeMap<Address, AccountSpec>,
+    #[serde(default)]
+    pub params: ParamsSpec,
+    /// Height from which the EVM supports the Byzantium opcodes, REVERT, RETURNDATASIZE,
+    /// RETURNDATACOPY and STATICCALL, never if not set. It is not part of the genesis block,
+    /// so all the nodes of a running chain may set it to a future height.
+    #[serde(default)]
+    pub byzantium_height: Option<u64>,
+    /// Height from which the EVM supports CREATE2, deploying contracts at an address
+    /// derived from the sender, a salt and the init code. Never if not set.
+    #[serde(default)]
+    pub create2_height: Option<u64>,
+    /// Id of the chain, signed in the transactions so that they cannot be replayed on
+    /// another chain. Transactions of any other chain id are rejected.
+    #[serde(default)]
+    pub chain_id: u32,
+}
+
+/// An account of the genesis state.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct AccountSpec {
+    #[serde(default)]
+    pub nonce: U256,
+    /// Runtime bytecode of the contract, empty for a plain account.
+    #[serde(default)]
+    pub code: Bytes,
+    #[serde(default)]
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Parameters of the system contracts.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ParamsSpec {
+    /// Target block interval in milliseconds, kept by the system config contract.
+    #[serde(default)]
+    pub block_interval: Option<u64>,
+    /// Nodes allowed to join the network, kept by the node manager contract.
+    #[serde(default)]
+    pub nodes: Vec<Address>,
+    /// Quota a block may use, kept by the quota manager contract.
+    #[serde(default)]
+    pub block_quota_limit: Option<u64>,
+    /// Quota an account may be charged in a block, kept by the quota manager contract.
+    #[serde(default)]
+    pub account_quota_limit: Option<u64>,
+    /// Blocks the storage of a contract is kept without being touched, kept by the storage rent contract.
+    #[serde(default)]
+    pub storage_expiry: Option<u64>,
+    /// Accounts allowed to send transactions, kept by the permission manager contract, anyone may if empty.
+    #[serde(default)]
+    pub senders: Vec<Address>,
+    /// Accounts allowed to deploy contracts, kept by the permission manager contract, anyone may if empty.
+    #[serde(default)]
+    pub creators: Vec<Address>,
+    /// Accounts allowed to change the parameters of the system contracts and the lists of the
+    /// permission manager contract, kept by it, nobody may if empty.
+    #[serde(default)]
+    pub admins: Vec<Address>,
+}
+
+impl Spec {
+    /// Refuse the spec the chain can not run on.
+    pub fn check(&self) -> Result<(), String> {
+        if self.consensus.as_ref().map_or(false, |consensus| consensus == "tendermint") && self.authorities.is_empty() {
+            return Err("a tendermint genesis has no authorities to check the block proofs".to_string());
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -39,21 +129,255 @@ impl Genesis {
         let config_file = File::open(path).unwrap();
         let fconfig = BufReader::new(config_file);
         let spec: Spec = serde_json::from_reader(fconfig).expect("Failed to load genesis.");
+        if let Err(reason) = spec.check() {
+            panic!("Invalid genesis: {}", reason);
+        }
         Genesis {
             spec: spec,
             block: Block::default(),
         }
     }
 
-    pub fn lazy_execute(&mut self) -> Result<(), &str> {
+    /// Build the genesis state on the state db and fill the block,
+    /// returns the state db holding the state to be journaled.
+    pub fn lazy_execute(&mut self, state_db: StateDB, factories: &Factories) -> Result<StateDB, String> {
+        let mut state = State::from_existing(state_db, HASH_NULL_RLP, U256::zero(), factories.clone()).map_err(|e| format!("{}", e))?;
+        for (address, account) in self.spec.alloc.iter() {
+            state.new_contract(address, account.nonce);
+            if !account.code.0.is_empty() {
+                state.init_code(address, account.code.0.clone()).map_err(|e| format!("{}", e))?;
+            }
+            for (key, value) in account.storage.iter() {
+                state.set_storage(address, *key, *value).map_err(|e| format!("{}", e))?;
+            }
+        }
+        for (key, value) in self.spec.params.storage() {
+            state.set_storage(&key.0, key.1, value).map_err(|e| format!("{}", e))?;
+        }
+        state.commit().map_err(|e| format!("{}", e))?;
+        let (root, state_db) = state.drop();
+
         self.block.set_version(0);
         self.block.set_parent_hash(self.spec.prevhash);
         self.block.set_timestamp(self.spec.timestamp);
         self.block.set_number(0);
-        self.block.set_state_root(HASH_NULL_RLP);
+        self.block.set_state_root(root);
 
-        info!("genesis state {:?}", HASH_NULL_RLP);
+        info!("genesis state {:?}", root);
 
-        Ok(())
+        Ok(state_db)
+    }
+}
+
+impl ParamsSpec {
+    /// Storage of the system contracts holding the parameters.
+    fn storage(&self) -> Vec<((Address, H256), H256)> {
+        let mut storage = Vec::new();
+        if let Some(interval) = self.block_interval {
+            storage.push(((native::sys_config_address(), H256::from(native::BLOCK_INTERVAL_KEY)), H256::from(interval)));
+        }
+        if let Some(limit) = self.block_quota_limit {
+            storage.push(((native::quota_manager_address(), H256::from(native::BLOCK_QUOTA_LIMIT_KEY)), H256::from(limit)));
+        }
+        if let Some(limit) = self.account_quota_limit {
+            storage.push(((native::quota_manager_address(), H256::from(native::ACCOUNT_QUOTA_LIMIT_KEY)), H256::from(limit)));
+        }
+        if let Some(expiry) = self.storage_expiry {
+            storage.push(((native::storage_rent_address(), H256::from(native::STORAGE_EXPIRY_KEY)), H256::from(expiry)));
+        }
+        if !self.nodes.is_empty() {
+            let address = native::node_manager_address();
+            storage.push(((address, H256::from(0)), H256::from(self.nodes.len() as u64)));
+            for (i, node) in self.nodes.iter().enumerate() {
+                storage.push(((address, H256::from(i as u64 + 1)), H256::from(*node)));
+            }
+        }
+        for &(list, ref accounts) in &[(native::SEND_PERMISSION, &self.senders), (native::CREATE_PERMISSION, &self.creators), (native::ADMIN_PERMISSION, &self.admins)] {
+            if accounts.is_empty() {
+                continue;
+            }
+            let address = native::permission_manager_address();
+            storage.push(((address, native::permission_key(list, 0)), H256::from(accounts.len() as u64)));
+            for (i, account) in accounts.iter().enumerate() {
+                storage.push(((address, native::permission_key(list, i as u64 + 1)), H256::from(*account)));
+            }
+        }
+        storage
+    }
+}
+
+/// Build the genesis programmatically, the same as from a spec.
+///
+/// ```ignore
+/// let genesis = GenesisBuilder::new()
+///     .timestamp(1500000000)
+///     .authorities(authorities)
+///     .contract(address, code)
+///     .block_interval(3000)
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct GenesisBuilder {
+    spec: Spec,
+}
+
+impl GenesisBuilder {
+    pub fn new() -> Self {
+        GenesisBuilder::default()
+    }
+
+    /// Start from a spec, e.g. loaded from JSON.
+    pub fn from_spec(spec: Spec) -> Self {
+        GenesisBuilder { spec: spec }
+    }
+
+    pub fn prevhash(mut self, prevhash: H256) -> Self {
+        self.spec.prevhash = prevhash;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.spec.timestamp = timestamp;
+        self
+    }
+
+    pub fn authorities(mut self, authorities: Vec<Address>) -> Self {
+        self.spec.authorities = authorities;
+        self
+    }
+
+    /// Allocate the account, replacing the one allocated at the address before.
+    pub fn account(mut self, address: Address, account: AccountSpec) -> Self {
+        self.spec.alloc.insert(address, account);
+        self
+    }
+
+    /// Deploy the runtime bytecode of a contract at the address.
+    pub fn contract(mut self, address: Address, code: Vec<u8>) -> Self {
+        self.spec.alloc.entry(address).or_insert_with(AccountSpec::default).code = Bytes::new(code);
+        self
+    }
+
+    pub fn nonce(mut self, address: Address, nonce: U256) -> Self {
+        self.spec.alloc.entry(address).or_insert_with(AccountSpec::default).nonce = nonce;
+        self
+    }
+
+    pub fn storage(mut self, address: Address, key: H256, value: H256) -> Self {
+        self.spec.alloc.entry(address).or_insert_with(AccountSpec::default).storage.insert(key, value);
+        self
+    }
+
+    pub fn block_interval(mut self, interval: u64) -> Self {
+        self.spec.params.block_interval = Some(interval);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: Vec<Address>) -> Self {
+        self.spec.params.nodes = nodes;
+        self
+    }
+
+    pub fn block_quota_limit(mut self, limit: u64) -> Self {
+        self.spec.params.block_quota_limit = Some(limit);
+        self
+    }
+
+    pub fn account_quota_limit(mut self, limit: u64) -> Self {
+        self.spec.params.account_quota_limit = Some(limit);
+        self
+    }
+
+    pub fn storage_expiry(mut self, expiry: u64) -> Self {
+        self.spec.params.storage_expiry = Some(expiry);
+        self
+    }
+
+    pub fn senders(mut self, senders: Vec<Address>) -> Self {
+        self.spec.params.senders = senders;
+        self
+    }
+
+    pub fn creators(mut self, creators: Vec<Address>) -> Self {
+        self.spec.params.creators = creators;
+        self
+    }
+
+    pub fn admins(mut self, admins: Vec<Address>) -> Self {
+        self.spec.params.admins = admins;
+        self
+    }
+
+    pub fn byzantium_height(mut self, height: u64) -> Self {
+        self.spec.byzantium_height = Some(height);
+        self
+    }
+
+    pub fn create2_height(mut self, height: u64) -> Self {
+        self.spec.create2_height = Some(height);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: u32) -> Self {
+        self.spec.chain_id = chain_id;
+        self
+    }
+
+    /// The spec, which can be written as JSON and loaded by `Genesis::init`.
+    pub fn spec(&self) -> &Spec {
+        &self.spec
+    }
+
+    pub fn build(self) -> Genesis {
+        Genesis {
+            spec: self.spec,
+            block: Block::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_spec() {
+        let spec: Spec = serde_json::from_str(r#"{
+            "prevhash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "timestamp": 1500000000,
+            "alloc": {
+                "0x0000000000000000000000000000000000000100": {
+                    "code": "0x6000",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001": "0x000000000000000000000000000000000000000000000000000000000000002a"
+                    }
+                }
+            },
+            "params": {"block_interval": 3000},
+            "byzantium_height": 100
+        }"#)
+                .unwrap();
+        let built = GenesisBuilder::new()
+            .timestamp(1500000000)
+            .contract(Address::from(0x100), vec![0x60, 0x00])
+            .storage(Address::from(0x100), H256::from(1), H256::from(42))
+            .block_interval(3000)
+            .byzantium_height(100);
+        assert_eq!(&spec, built.spec());
+        let json = serde_json::to_string(built.spec()).unwrap();
+        assert_eq!(serde_json::from_str::<Spec>(&json).unwrap(), spec);
+    }
+
+    #[test]
+    fn check_spec() {
+        let mut spec: Spec = serde_json::from_str(r#"{"prevhash": "0x0000000000000000000000000000000000000000000000000000000000000000", "timestamp": 0, "consensus": "tendermint"}"#).unwrap();
+        assert!(spec.check().is_err());
+        spec.authorities = vec![Address::from(1)];
+        assert!(spec.check().is_ok());
+        spec.authorities.clear();
+        spec.consensus = Some("raft".to_string());
+        assert!(spec.check().is_ok());
+        spec.consensus = None;
+        assert!(spec.check().is_ok());
     }
 }
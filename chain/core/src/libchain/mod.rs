@@ -20,10 +20,17 @@ pub mod chain;
 pub mod transaction;
 pub mod block;
 mod genesis;
+mod config;
 mod extras;
 pub mod call_request;
+pub mod snapshot;
+pub mod rollback;
+pub mod ancient;
+pub mod export;
+pub mod audit;
 
-pub use self::genesis::Genesis;
+pub use self::config::{Config, DatabaseOptions, NodeMode};
+pub use self::genesis::{Genesis, GenesisBuilder, Spec, AccountSpec, ParamsSpec};
 pub use libproto::*;
 pub use log::*;
 pub use util::journaldb;
@@ -0,0 +1,173 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Offline rollback of the chain to an earlier height, for recovering a node
+//! after an emergency brake or a faulty upgrade.
+//!
+//! The blocks after the height are dropped with their receipts, state diffs and
+//! transaction indexes in one batch. The state of the height must still be kept by
+//! the journal. The journal entries of the dropped blocks are left behind, they
+//! are discarded once the blocks imported again at the same heights become
+//! canonical. So are the log blooms, which only cause false positives in the
+//! filters matching the logs themselves. Blocks moved into the ancient store
+//! after the height are dropped from it as well.
+//!
+//! Consensus keeps the votes and the signatures of the heights it is working on in
+//! the data path, which are cleared too, so it signs the dropped heights again.
+
+use db::{self, ConstKey, DBList, Key, Readable, Writable};
+use header::{BlockNumber, Header};
+use libchain::ancient::AncientStore;
+use libchain::audit::BlockAudit;
+use libchain::block::BlockBody;
+use libchain::chain::get_chain;
+use libchain::config::Config;
+use libchain::extras::{AccountTransactionPosition, BlockReceipts, TransactionAddress};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use types::state_diff::StateDiff;
+use util::{journaldb, H256, Address, KeyValueDB, HASH_NULL_RLP};
+
+// files of consensus in the data path about the heights after the last block
+const CONSENSUS_STATE: &'static [&'static str] = &["wal", "sign_state.bin", "sign_state.bin.tmp"];
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Db(String),
+    /// The database has no chain.
+    NoChain,
+    /// The height is above the current one.
+    AboveCurrent { height: BlockNumber, current: BlockNumber },
+    MissingBlock(BlockNumber),
+    /// The state of the height has been pruned.
+    StatePruned { height: BlockNumber, earliest: BlockNumber },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Db(ref err) => write!(f, "{}", err),
+            Error::NoChain => write!(f, "database has no chain"),
+            Error::AboveCurrent { height, current } => write!(f, "height {} is above the current height {}", height, current),
+            Error::MissingBlock(height) => write!(f, "missing block {}", height),
+            Error::StatePruned { height, earliest } => write!(f, "state of block {} has been pruned, the states are kept from block {}", height, earliest),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Rewind the chain to the block of the height, which becomes the current one.
+/// Must be run while the chain is not running, returns the hash of the block.
+/// Clear the state consensus kept about the dropped heights, it must neither replay
+/// their votes nor refuse to sign them again.
+pub fn clear_consensus_state(data_path: &Path) -> io::Result<()> {
+    for name in CONSENSUS_STATE {
+        let path = data_path.join(name);
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else if path.exists() {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn rollback(db: Arc<KeyValueDB>, config: &Config, height: BlockNumber) -> Result<H256, Error> {
+    let (_, current) = get_chain(&*db).ok_or(Error::NoChain)?;
+    if height > current {
+        return Err(Error::AboveCurrent { height: height, current: current });
+    }
+    let hash: H256 = db.read(db::COL_EXTRA, &height).ok_or(Error::MissingBlock(height))?;
+    let header: Header = db.read(db::COL_HEADERS, &hash).ok_or(Error::MissingBlock(height))?;
+
+    let journal_db = journaldb::new(db.clone(), config.algorithm(), db::COL_STATE);
+    if journal_db.is_pruned() {
+        let earliest = current.saturating_sub(config.pruning_history);
+        let root = *header.state_root();
+        if height < earliest || (root != HASH_NULL_RLP && !journal_db.contains(&root)) {
+            return Err(Error::StatePruned { height: height, earliest: earliest });
+        }
+    }
+
+    let mut ancient = match config.ancient_path {
+        Some(ref path) if Path::new(path).exists() => Some(AncientStore::open(Path::new(path))?),
+        _ => None,
+    };
+
+    let mut batch = db.transaction();
+    let mut dropped = HashSet::new();
+    let mut tx_hashes = HashSet::new();
+    for number in (height + 1)..(current + 1) {
+        let hash: H256 = match db.read(db::COL_EXTRA, &number) {
+            Some(hash) => hash,
+            None => continue,
+        };
+        let mut body: Option<BlockBody> = db.read(db::COL_BODIES, &hash);
+        if let Some(ref mut ancient) = ancient {
+            if body.is_none() {
+                body = ancient.body(number)?.map(|value| ::rlp::decode(&db::decompress(&value)));
+            }
+        }
+        if let Some(body) = body {
+            tx_hashes.extend(body.transaction_hashes());
+            for (position, _) in AccountTransactionPosition::of_block(number, &body) {
+                batch.delete(db::COL_EXTRA, &position.key());
+            }
+        }
+        batch.delete(db::COL_EXTRA, &<BlockNumber as Key<H256>>::key(&number));
+        batch.delete(db::COL_HEADERS, &<H256 as Key<Header>>::key(&hash));
+        batch.delete(db::COL_BODIES, &<H256 as Key<BlockBody>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<BlockReceipts>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<StateDiff>>::key(&hash));
+        batch.delete(db::COL_EXTRA, &<H256 as Key<BlockAudit>>::key(&hash));
+        dropped.insert(hash);
+    }
+
+    // A transaction may also be in a kept block, only its addresses in the dropped ones go.
+    for tx_hash in tx_hashes {
+        let addresses: DBList<TransactionAddress> = match db.read_list(db::COL_EXTRA, &tx_hash) {
+            Some(addresses) => addresses,
+            None => continue,
+        };
+        let kept: Vec<TransactionAddress> = addresses.data.into_iter().filter(|address| !dropped.contains(&address.block_hash)).collect();
+        if kept.is_empty() {
+            batch.delete(db::COL_EXTRA, &<H256 as Key<TransactionAddress>>::key(&tx_hash));
+        } else {
+            batch.append(db::COL_EXTRA, &tx_hash, &DBList { data: kept });
+        }
+    }
+
+    batch.write(db::COL_EXTRA, &ConstKey::CurrentHash, &hash);
+    batch.write(db::COL_EXTRA, &ConstKey::CurrentHeight, &height);
+    db.write(batch).map_err(Error::Db)?;
+    if let Some(ref mut ancient) = ancient {
+        ancient.truncate(height + 1)?;
+    }
+    info!("rolled back from block {} to block {} {:?}", current, height, hash);
+    Ok(hash)
+}
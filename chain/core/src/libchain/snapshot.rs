@@ -0,0 +1,710 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshots of the state and the recent blocks at a given height, which restore
+//! a fresh node without executing the whole history of the chain.
+//!
+//! A snapshot is a directory holding `manifest.json` and the chunks, each chunk is
+//! a file named by its hash. State chunks list the accounts with their code and
+//! storage, an account is never split across chunks. Block chunks list the blocks
+//! with their receipts. Log blooms of the restored blocks are rebuilt by the chain
+//! once it picks them up, see `Chain::rebuild_blooms`.
+
+use db::{self, AppendPolicy, ConstKey, DBList, Writable};
+use factory::Factories;
+use libchain::block::Block;
+use libchain::chain::{Chain, get_chain, JOURNAL_ALGORITHM_KEY};
+use libchain::extras::{BlockReceipts, TransactionAddress};
+use libproto::blockchain::ProofType;
+use proof::TendermintProof;
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+use serde_json;
+use state::backend::Backend;
+use state_db::code_refs_key;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use util::{journaldb, HashDB, H256, U256, Address, Bytes, Hashable, HASH_EMPTY, HASH_NULL_RLP};
+use util::{KeyValueDB, MemoryDB, Trie, TrieError};
+use util::kvdb::in_memory;
+use util::trie::{TrieFactory, TrieSpec};
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Blocks kept in a snapshot, enough for the `BLOCKHASH` of the blocks after it.
+pub const SNAPSHOT_BLOCKS: u64 = 256;
+/// A chunk is cut once its entries exceed the size.
+pub const PREFERRED_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+pub const MANIFEST_FILE: &'static str = "manifest.json";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Decoder(DecoderError),
+    Trie(TrieError),
+    Json(serde_json::Error),
+    Db(String),
+    /// The snapshot format is not supported.
+    Version(u32),
+    /// Restoring into a database which already has blocks after the genesis.
+    NotEmpty,
+    /// The journal algorithm does not keep the state nodes by their hashes.
+    Algorithm(journaldb::Algorithm),
+    /// The chunk is not listed in the manifest or does not match its hash.
+    InvalidChunk(H256),
+    MissingBlock(u64),
+    /// The restored blocks do not link up to the block of the manifest.
+    InvalidBlocks,
+    StateRoot { expected: H256, found: H256 },
+    /// Chunks of the manifest have not been restored.
+    Incomplete,
+    /// The block of the manifest does not carry a proof of its parent signed by the validators.
+    InvalidProof,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Decoder(ref err) => write!(f, "{:?}", err),
+            Error::Trie(ref err) => write!(f, "{}", err),
+            Error::Json(ref err) => write!(f, "{}", err),
+            Error::Db(ref err) => write!(f, "{}", err),
+            Error::Version(version) => write!(f, "unsupported snapshot version {}", version),
+            Error::NotEmpty => write!(f, "database is not empty"),
+            Error::Algorithm(algorithm) => write!(f, "can not restore the state for {} pruning", algorithm),
+            Error::InvalidChunk(ref hash) => write!(f, "invalid chunk {:?}", hash),
+            Error::MissingBlock(height) => write!(f, "missing block {}", height),
+            Error::InvalidBlocks => write!(f, "blocks do not match the manifest"),
+            Error::StateRoot { ref expected, ref found } => write!(f, "state root mismatch, expected {:?}, found {:?}", expected, found),
+            Error::Incomplete => write!(f, "snapshot is incomplete"),
+            Error::InvalidProof => write!(f, "invalid proof of the blocks"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<DecoderError> for Error {
+    fn from(err: DecoderError) -> Self {
+        Error::Decoder(err)
+    }
+}
+
+impl From<Box<TrieError>> for Error {
+    fn from(err: Box<TrieError>) -> Self {
+        Error::Trie(*err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+/// Description of a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestData {
+    pub version: u32,
+    pub height: u64,
+    pub block_hash: H256,
+    pub state_root: H256,
+    pub state_hashes: Vec<H256>,
+    pub block_hashes: Vec<H256>,
+    /// First height -> validators signing from it.
+    pub authorities: Vec<(u64, Vec<Address>)>,
+}
+
+/// Factories of the state tries, the account trie is keyed by the plain addresses.
+fn factories() -> Factories {
+    Factories {
+        vm: Default::default(),
+        trie: TrieFactory::new(TrieSpec::Generic),
+        accountdb: Default::default(),
+    }
+}
+
+/// Writes the entries into chunk files.
+struct ChunkWriter<'a> {
+    dir: &'a Path,
+    entries: Vec<Bytes>,
+    size: usize,
+    hashes: Vec<H256>,
+}
+
+impl<'a> ChunkWriter<'a> {
+    fn new(dir: &'a Path) -> Self {
+        ChunkWriter {
+            dir: dir,
+            entries: Vec::new(),
+            size: 0,
+            hashes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, entry: Bytes) -> Result<(), Error> {
+        self.size += entry.len();
+        self.entries.push(entry);
+        if self.size >= PREFERRED_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        let mut stream = RlpStream::new_list(self.entries.len());
+        for entry in self.entries.drain(..) {
+            stream.append_raw(&entry, 1);
+        }
+        let chunk = stream.out();
+        let hash = chunk.crypt_hash();
+        File::create(self.dir.join(hash.hex()))?.write_all(&chunk)?;
+        self.hashes.push(hash);
+        self.size = 0;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<H256>, Error> {
+        self.flush()?;
+        Ok(self.hashes)
+    }
+}
+
+/// Write the accounts of the state into chunks, as `[address, nonce, code, [[key, value]]]`.
+fn chunk_state(db: &HashDB, factories: &Factories, root: &H256, writer: &mut ChunkWriter) -> Result<(), Error> {
+    let account_trie = factories.trie.readonly(db, root)?;
+    for item in account_trie.iter()? {
+        let (address, value) = item?;
+        let address = Address::from_slice(&address);
+        let account = UntrustedRlp::new(&value);
+        let nonce: U256 = account.val_at(0)?;
+        let storage_root: H256 = account.val_at(1)?;
+        let code_hash: H256 = account.val_at(2)?;
+
+        let code = match code_hash == HASH_EMPTY {
+            true => Bytes::new(),
+            false => {
+                let code_db = factories.accountdb.readonly_code(db, address.crypt_hash());
+                code_db.get(&code_hash).ok_or(Error::Trie(TrieError::IncompleteDatabase(code_hash)))?.to_vec()
+            }
+        };
+        let account_db = factories.accountdb.readonly(db, address.crypt_hash());
+        let mut storage = Vec::new();
+        let storage_trie = factories.trie.readonly(account_db.as_hashdb(), &storage_root)?;
+        for item in storage_trie.iter()? {
+            storage.push(item?);
+        }
+
+        let mut stream = RlpStream::new_list(4);
+        stream.append(&address);
+        stream.append(&nonce);
+        stream.append(&code);
+        stream.begin_list(storage.len());
+        for (key, value) in storage {
+            stream.begin_list(2);
+            stream.append(&key);
+            stream.append(&value.to_vec());
+        }
+        writer.push(stream.out())?;
+    }
+    Ok(())
+}
+
+/// Write the recent blocks up to the height into chunks, as `[block, receipts]`.
+fn chunk_blocks(chain: &Chain, height: u64, writer: &mut ChunkWriter) -> Result<(), Error> {
+    let start = (height + 1).saturating_sub(SNAPSHOT_BLOCKS);
+    for number in start..height + 1 {
+        let block = chain.block_by_height(number).ok_or(Error::MissingBlock(number))?;
+        let receipts = chain.block_receipts(block.hash()).unwrap_or_else(|| BlockReceipts::new(Vec::new()));
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&block);
+        stream.append(&receipts);
+        writer.push(stream.out())?;
+    }
+    Ok(())
+}
+
+/// Take a snapshot of the current block into the directory.
+pub fn take_snapshot(chain: &Chain, dir: &Path) -> Result<ManifestData, Error> {
+    take_snapshot_at(chain, chain.get_current_height(), dir)
+}
+
+/// Take a snapshot of the block of the height into the directory,
+/// whose state must not have been pruned.
+pub fn take_snapshot_at(chain: &Chain, height: u64, dir: &Path) -> Result<ManifestData, Error> {
+    if height > chain.get_current_height() {
+        return Err(Error::MissingBlock(height));
+    }
+    let header = chain.block_header_by_height(height).ok_or(Error::MissingBlock(height))?;
+    let state_root = *header.state_root();
+    fs::create_dir_all(dir)?;
+    info!("take snapshot at height {}", height);
+
+    let state_db = chain.state_db.boxed_clone();
+    let mut writer = ChunkWriter::new(dir);
+    chunk_state(state_db.as_hashdb(), &chain.factories, &state_root, &mut writer)?;
+    let state_hashes = writer.finish()?;
+
+    let mut writer = ChunkWriter::new(dir);
+    chunk_blocks(chain, height, &mut writer)?;
+    let block_hashes = writer.finish()?;
+
+    let manifest = ManifestData {
+        version: SNAPSHOT_VERSION,
+        height: height,
+        block_hash: header.hash(),
+        state_root: state_root,
+        state_hashes: state_hashes,
+        block_hashes: block_hashes,
+        authorities: chain.authority_history().into_iter().filter(|&(first, _)| first <= height).collect(),
+    };
+    let file = File::create(dir.join(MANIFEST_FILE))?;
+    serde_json::to_writer_pretty(file, &manifest)?;
+    info!("snapshot at height {} has {} state chunks and {} block chunks",
+          height,
+          manifest.state_hashes.len(),
+          manifest.block_hashes.len());
+    Ok(manifest)
+}
+
+pub fn read_manifest(dir: &Path) -> Result<ManifestData, Error> {
+    let file = File::open(dir.join(MANIFEST_FILE))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+pub fn read_chunk(dir: &Path, hash: &H256) -> Result<Bytes, Error> {
+    let mut chunk = Vec::new();
+    File::open(dir.join(hash.hex()))?.read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Check the block of the manifest is committed, before restoring a snapshot from a peer.
+/// The proposal signed by the validators leaves out the state root, so the commit proof is
+/// checked for the next block, which links to the manifest block by the parent hash.
+/// `commit` is the block after `next`, carrying the commit proof of `next`.
+pub fn verify_manifest(manifest: &ManifestData, next: &Block, commit: &Block, authorities: Option<Vec<Address>>) -> bool {
+    let height = manifest.height + 1;
+    if next.number() != height || commit.number() != height + 1 || *next.parent_hash() != manifest.block_hash {
+        return false;
+    }
+    if commit.proof_type() != Some(ProofType::Tendermint) {
+        return false;
+    }
+    let proof = TendermintProof::from(commit.proof().clone());
+    if proof.height != height as usize || proof.commits.is_empty() || proof.proposal != next.proposal_protobuf().crypt_hash() {
+        return false;
+    }
+    match authorities {
+        Some(authorities) => proof.check(height as usize, &authorities),
+        None => proof.simple_check(height as usize),
+    }
+}
+
+/// Check the snapshot in the directory offline, by restoring it into memory: the chunks match
+/// their hashes, the accounts build the state root and the blocks link up to the block of the
+/// manifest. The block of the manifest carries the commit proof of its parent, which must be
+/// signed by the validators of the manifest.
+pub fn verify_snapshot(dir: &Path) -> Result<ManifestData, Error> {
+    let db = Arc::new(in_memory(db::NUM_COLUMNS.unwrap()));
+    let manifest = restore(db, journaldb::Algorithm::Archive, dir)?;
+    if manifest.height == 0 {
+        return Ok(manifest);
+    }
+    let (mut parent, mut block) = (None, None);
+    for hash in &manifest.block_hashes {
+        let chunk = read_chunk(dir, hash)?;
+        for entry in UntrustedRlp::new(&chunk).iter() {
+            let found: Block = entry.val_at(0)?;
+            if found.number() == manifest.height - 1 {
+                parent = Some(found);
+            } else if found.number() == manifest.height {
+                block = Some(found);
+            }
+        }
+    }
+    let (parent, block) = match (parent, block) {
+        (Some(parent), Some(block)) => (parent, block),
+        _ => return Err(Error::MissingBlock(manifest.height - 1)),
+    };
+    if block.proof_type() != Some(ProofType::Tendermint) {
+        warn!("snapshot at height {} has no tendermint proof to check", manifest.height);
+        return Ok(manifest);
+    }
+    let height = (manifest.height - 1) as usize;
+    let proof = TendermintProof::from(block.proof().clone());
+    if proof.height != height || proof.commits.is_empty() || proof.proposal != parent.proposal_protobuf().crypt_hash() {
+        return Err(Error::InvalidProof);
+    }
+    let authorities = manifest.authorities.iter().filter(|&&(first, _)| first <= height as u64).last().map(|&(_, ref authorities)| authorities.clone());
+    let signed = match authorities {
+        Some(authorities) => proof.check(height, &authorities),
+        None => proof.simple_check(height),
+    };
+    if !signed {
+        return Err(Error::InvalidProof);
+    }
+    Ok(manifest)
+}
+
+fn check_chunk(pending: &mut HashSet<H256>, hash: &H256, chunk: &[u8]) -> Result<(), Error> {
+    if chunk.crypt_hash() != *hash || !pending.remove(hash) {
+        return Err(Error::InvalidChunk(*hash));
+    }
+    Ok(())
+}
+
+/// Restores a fresh database from the chunks of a snapshot, in any order.
+pub struct Restoration {
+    manifest: ManifestData,
+    algorithm: journaldb::Algorithm,
+    db: Arc<KeyValueDB>,
+    // state nodes not yet written to the database
+    state: MemoryDB,
+    factories: Factories,
+    state_chunks: HashSet<H256>,
+    block_chunks: HashSet<H256>,
+    // address -> (nonce, storage root, code hash)
+    accounts: HashMap<Address, (U256, H256, H256)>,
+    // height -> (hash, parent hash, state root)
+    blocks: BTreeMap<u64, (H256, H256, H256)>,
+    transaction_addresses: HashMap<H256, DBList<TransactionAddress>>,
+}
+
+impl Restoration {
+    pub fn new(db: Arc<KeyValueDB>, algorithm: journaldb::Algorithm, manifest: ManifestData) -> Result<Self, Error> {
+        if manifest.version != SNAPSHOT_VERSION {
+            return Err(Error::Version(manifest.version));
+        }
+        if get_chain(&*db).map_or(false, |(_, height)| height > 0) {
+            return Err(Error::NotEmpty);
+        }
+        // the state nodes are written as the canonical ones of the algorithm
+        if !algorithm.is_stable() {
+            return Err(Error::Algorithm(algorithm));
+        }
+        Ok(Restoration {
+               state_chunks: manifest.state_hashes.iter().cloned().collect(),
+               block_chunks: manifest.block_hashes.iter().cloned().collect(),
+               manifest: manifest,
+               algorithm: algorithm,
+               db: db,
+               state: MemoryDB::new(),
+               factories: factories(),
+               accounts: HashMap::new(),
+               blocks: BTreeMap::new(),
+               transaction_addresses: HashMap::new(),
+           })
+    }
+
+    pub fn manifest(&self) -> &ManifestData {
+        &self.manifest
+    }
+
+    /// Whether all the chunks have been restored.
+    pub fn is_done(&self) -> bool {
+        self.state_chunks.is_empty() && self.block_chunks.is_empty()
+    }
+
+    /// Restore the code and storage of the accounts in the chunk.
+    pub fn feed_state(&mut self, hash: &H256, chunk: &[u8]) -> Result<(), Error> {
+        check_chunk(&mut self.state_chunks, hash, chunk)?;
+        for entry in UntrustedRlp::new(chunk).iter() {
+            let address: Address = entry.val_at(0)?;
+            let nonce: U256 = entry.val_at(1)?;
+            let code: Bytes = entry.val_at(2)?;
+            let storage = entry.at(3)?;
+            let mut storage_root = HASH_NULL_RLP;
+            let mut code_hash = HASH_EMPTY;
+            if !code.is_empty() {
+                let mut code_db = self.factories.accountdb.create_code(&mut self.state);
+                code_hash = code_db.insert(&code);
+            }
+            {
+                let mut account_db = self.factories.accountdb.create(&mut self.state, address.crypt_hash());
+                if storage.item_count()? > 0 {
+                    let mut storage_trie = self.factories.trie.create(account_db.as_hashdb_mut(), &mut storage_root);
+                    for pair in storage.iter() {
+                        let key: Bytes = pair.val_at(0)?;
+                        let value: Bytes = pair.val_at(1)?;
+                        storage_trie.insert(&key, &value)?;
+                    }
+                }
+            }
+            self.accounts.insert(address, (nonce, storage_root, code_hash));
+        }
+        self.commit_state()
+    }
+
+    /// Write the pending state nodes, a node written twice is simply overwritten.
+    fn commit_state(&mut self) -> Result<(), Error> {
+        let mut batch = self.db.transaction();
+        for (key, (value, rc)) in self.state.drain() {
+            if rc > 0 {
+                batch.put(db::COL_STATE, &key, &value);
+            }
+        }
+        self.db.write(batch).map_err(Error::Db)
+    }
+
+    /// Restore the blocks and receipts in the chunk.
+    pub fn feed_blocks(&mut self, hash: &H256, chunk: &[u8]) -> Result<(), Error> {
+        check_chunk(&mut self.block_chunks, hash, chunk)?;
+        let mut batch = self.db.transaction();
+        for entry in UntrustedRlp::new(chunk).iter() {
+            let block: Block = entry.val_at(0)?;
+            let receipts: BlockReceipts = entry.val_at(1)?;
+            let hash = block.hash();
+            let height = block.number();
+            let addresses: HashMap<H256, TransactionAddress> = block.body()
+                                                                    .transaction_hashes()
+                                                                    .into_iter()
+                                                                    .enumerate()
+                                                                    .map(|(index, tx_hash)| {
+                                                                             (tx_hash,
+                                                                              TransactionAddress {
+                                                                                  block_hash: hash,
+                                                                                  index: index,
+                                                                              })
+                                                                         })
+                                                                    .collect();
+
+            batch.write(db::COL_HEADERS, &hash, block.header());
+            batch.write(db::COL_BODIES, &hash, block.body());
+            batch.write(db::COL_EXTRA, &height, &hash);
+            batch.write(db::COL_EXTRA, &hash, &receipts);
+            batch.extend_with_cache_append(db::COL_EXTRA, &*self.db, &mut self.transaction_addresses, addresses, AppendPolicy::Update);
+            self.blocks.insert(height, (hash, *block.parent_hash(), *block.state_root()));
+        }
+        self.db.write(batch).map_err(Error::Db)
+    }
+
+    /// Build the account trie from the restored accounts, returning its root.
+    pub fn rebuild_state(&mut self) -> Result<H256, Error> {
+        let mut root = HASH_NULL_RLP;
+        {
+            let mut account_trie = self.factories.trie.create(&mut self.state, &mut root);
+            for (address, &(ref nonce, ref storage_root, ref code_hash)) in &self.accounts {
+                let mut stream = RlpStream::new_list(3);
+                stream.append(nonce);
+                stream.append(storage_root);
+                stream.append(code_hash);
+                account_trie.insert(address, &stream.out())?;
+            }
+        }
+        self.commit_state()?;
+        Ok(root)
+    }
+
+    /// Check the restored blocks link up to the block of the manifest.
+    fn check_blocks(&self) -> Result<(), Error> {
+        match self.blocks.get(&self.manifest.height) {
+            Some(&(hash, _, state_root)) if hash == self.manifest.block_hash && state_root == self.manifest.state_root => {}
+            _ => return Err(Error::InvalidBlocks),
+        }
+        let mut expected: Option<(u64, H256)> = None;
+        for (height, &(hash, parent_hash, _)) in &self.blocks {
+            if let Some((parent_height, parent)) = expected {
+                if *height != parent_height + 1 || parent_hash != parent {
+                    return Err(Error::InvalidBlocks);
+                }
+            }
+            expected = Some((*height, hash));
+        }
+        Ok(())
+    }
+
+    /// Check the restored state and blocks, then make the block of the manifest current.
+    pub fn finalize(mut self) -> Result<(), Error> {
+        if !self.is_done() {
+            return Err(Error::Incomplete);
+        }
+        self.check_blocks()?;
+        let root = self.rebuild_state()?;
+        if root != self.manifest.state_root {
+            return Err(Error::StateRoot {
+                           expected: self.manifest.state_root,
+                           found: root,
+                       });
+        }
+
+        let mut batch = self.db.transaction();
+        let mut code_refs = HashMap::new();
+        for &(_, _, code_hash) in self.accounts.values().filter(|&&(_, _, code_hash)| code_hash != HASH_EMPTY) {
+            *code_refs.entry(self.factories.accountdb.code_key(&code_hash)).or_insert(0u64) += 1;
+        }
+        for (code_key, refs) in code_refs {
+            batch.put(db::COL_STATE, &code_refs_key(&code_key), &::rlp::encode(&refs));
+        }
+        batch.put(db::COL_EXTRA, JOURNAL_ALGORITHM_KEY, self.algorithm.as_str().as_bytes());
+        batch.write(db::COL_EXTRA, &ConstKey::CurrentHash, &self.manifest.block_hash);
+        batch.write(db::COL_EXTRA, &ConstKey::CurrentHeight, &self.manifest.height);
+        self.db.write(batch).map_err(Error::Db)?;
+        info!("restored snapshot at height {}", self.manifest.height);
+        Ok(())
+    }
+}
+
+/// Restore a fresh database from the snapshot in the directory.
+pub fn restore(db: Arc<KeyValueDB>, algorithm: journaldb::Algorithm, dir: &Path) -> Result<ManifestData, Error> {
+    let manifest = read_manifest(dir)?;
+    let mut restoration = Restoration::new(db, algorithm, manifest.clone())?;
+    for hash in &manifest.state_hashes {
+        restoration.feed_state(hash, &read_chunk(dir, hash)?)?;
+    }
+    for hash in &manifest.block_hashes {
+        restoration.feed_blocks(hash, &read_chunk(dir, hash)?)?;
+    }
+    restoration.finalize()?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate mktemp;
+
+    use super::*;
+    use header::Header;
+    use state::State;
+    use state_db::StateDB;
+    use tests::helpers::get_temp_state_db;
+    use util::kvdb::in_memory;
+
+    fn manifest(root: H256, state_hashes: Vec<H256>) -> ManifestData {
+        ManifestData {
+            version: SNAPSHOT_VERSION,
+            height: 0,
+            block_hash: H256::default(),
+            state_root: root,
+            state_hashes: state_hashes,
+            block_hashes: Vec::new(),
+            authorities: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn restore_state() {
+        let contract = Address::from(1u64);
+        let account = Address::from(2u64);
+        let mut state = State::new(get_temp_state_db(), 0.into(), factories());
+        state.new_contract(&contract, 0.into());
+        state.init_code(&contract, vec![0x60, 0x00]).unwrap();
+        for i in 1..100u64 {
+            state.set_storage(&contract, H256::from(i), H256::from(i * 2)).unwrap();
+        }
+        state.inc_nonce(&account).unwrap();
+        state.commit().unwrap();
+        let (root, mut state_db) = state.drop();
+        let backing = state_db.journal_db().backing().clone();
+        let mut batch = backing.transaction();
+        state_db.journal_under(&mut batch, 0, &H256::default()).unwrap();
+        backing.write(batch).unwrap();
+
+        let dir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let mut writer = ChunkWriter::new(&dir);
+        chunk_state(state_db.as_hashdb(), &factories(), &root, &mut writer).unwrap();
+        let hashes = writer.finish().unwrap();
+
+        let db = Arc::new(in_memory(db::NUM_COLUMNS.unwrap()));
+        let mut restoration = Restoration::new(db.clone(), journaldb::Algorithm::Archive, manifest(root, hashes.clone())).unwrap();
+        for hash in &hashes {
+            let chunk = read_chunk(&dir, hash).unwrap();
+            restoration.feed_state(hash, &chunk).unwrap();
+        }
+        assert!(restoration.is_done());
+        assert_eq!(restoration.rebuild_state().unwrap(), root);
+
+        let restored = State::from_existing(StateDB::new(journaldb::new(db, journaldb::Algorithm::Archive, db::COL_STATE), 0), root, 0.into(), factories()).unwrap();
+        assert_eq!(restored.storage_at(&contract, &H256::from(7u64)).unwrap(), H256::from(14u64));
+        assert_eq!(*restored.code(&contract).unwrap().unwrap(), vec![0x60, 0x00]);
+        assert_eq!(restored.nonce(&account).unwrap(), U256::from(1));
+    }
+
+    #[test]
+    fn reject_invalid_chunk() {
+        let db = Arc::new(in_memory(db::NUM_COLUMNS.unwrap()));
+        let chunk = RlpStream::new_list(0).out();
+        let hash = chunk.crypt_hash();
+        let mut restoration = Restoration::new(db, journaldb::Algorithm::Archive, manifest(HASH_NULL_RLP, vec![hash])).unwrap();
+        let other = H256::from(1u64);
+        assert!(restoration.feed_state(&other, &chunk).is_err());
+        assert!(restoration.feed_blocks(&hash, &chunk).is_err());
+        restoration.feed_state(&hash, &chunk).unwrap();
+        assert!(restoration.feed_state(&hash, &chunk).is_err());
+        assert_eq!(restoration.rebuild_state().unwrap(), HASH_NULL_RLP);
+    }
+
+    #[test]
+    fn reject_unproven_manifest() {
+        let mut manifest = manifest(HASH_NULL_RLP, vec![]);
+        manifest.height = 10;
+        manifest.block_hash = H256::from(10u64);
+
+        let mut header = Header::new();
+        header.set_number(11);
+        header.set_parent_hash(manifest.block_hash);
+        let mut next = Block::new();
+        next.set_header(header);
+
+        let proof = TendermintProof::new(11, 0, next.proposal_protobuf().crypt_hash(), HashMap::new());
+        let mut header = Header::new();
+        header.set_number(12);
+        header.set_parent_hash(next.hash());
+        header.set_proof(proof.into());
+        let mut commit = Block::new();
+        commit.set_header(header);
+
+        // nobody signed the proposal
+        assert!(!verify_manifest(&manifest, &next, &commit, None));
+        assert!(!verify_manifest(&manifest, &next, &commit, Some(vec![Address::from(1)])));
+        // not the block after the manifest
+        manifest.block_hash = H256::from(9u64);
+        assert!(!verify_manifest(&manifest, &next, &commit, None));
+    }
+
+    #[test]
+    fn verify_genesis_snapshot() {
+        let mut block = Block::new();
+        block.set_number(0);
+        block.set_state_root(HASH_NULL_RLP);
+        let dir = mktemp::Temp::new_dir().unwrap().to_path_buf();
+        let mut writer = ChunkWriter::new(&dir);
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&block);
+        stream.append(&BlockReceipts::new(Vec::new()));
+        writer.push(stream.out()).unwrap();
+        let block_hashes = writer.finish().unwrap();
+
+        let mut manifest = manifest(HASH_NULL_RLP, Vec::new());
+        manifest.block_hash = block.hash();
+        manifest.block_hashes = block_hashes.clone();
+        serde_json::to_writer(File::create(dir.join(MANIFEST_FILE)).unwrap(), &manifest).unwrap();
+        assert_eq!(verify_snapshot(&dir).unwrap(), manifest);
+
+        File::create(dir.join(block_hashes[0].hex())).unwrap().write_all(&[0xc0]).unwrap();
+        assert!(verify_snapshot(&dir).is_err());
+    }
+}
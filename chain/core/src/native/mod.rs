@@ -22,8 +22,9 @@
 
 use action_params::ActionParams;
 use evm::{self, Ext, GasLeft};
+use rlp;
 use std::collections::HashMap;
-use util::{H256, U256};
+use util::{H256, U256, Address, Hashable, sec_trie_root};
 
 ////////////////////////////////////////////////////////////////////////////////
 pub type Signature = u32;
@@ -33,19 +34,419 @@ pub type Function = Fn(&ActionParams, &mut Ext) -> evm::Result<GasLeft<'static>>
 // Contract
 pub trait Contract: Sync + Send {
     fn get_function(&self, hash: &Signature) -> Option<&Box<Function>>;
-    fn exec(&self, params: &ActionParams, mut ext: &mut Ext) {
+    fn exec(&self, params: &ActionParams, mut ext: &mut Ext) -> evm::Result<()> {
         if let Some(data) = params.clone().data.unwrap().get(0..4) {
             let signature = data.iter().fold(0u32, |acc, &x| (acc << 8) + (x as u32));
             if let Some(exec_call) = self.get_function(&signature) {
                 //let cost = self.engine.cost_of_builtin(&params.code_address, data);
                 let cost = U256::from(100);
                 if cost <= params.gas {
-                    let _ = exec_call(params, ext);
+                    exec_call(params, ext)?;
                     //self.state.discard_checkpoint();
-                    return;
                 }
             }
         }
+        Ok(())
+    }
+}
+
+/// Refuse the call unless its sender is an admin, the functions changing the
+/// parameters of the chain are called by the admins only.
+pub fn require_admin(params: &ActionParams, ext: &Ext) -> evm::Result<()> {
+    let address = permission_manager_address();
+    let count = ext.storage_of(&address, &permission_key(ADMIN_PERMISSION, 0))?.low_u64();
+    for i in 0..count {
+        if Address::from(ext.storage_of(&address, &permission_key(ADMIN_PERMISSION, i + 1))?) == params.sender {
+            return Ok(());
+        }
+    }
+    Err(evm::Error::Unauthorized)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// SysConfig, the system parameters governed on chain
+pub const SYS_CONFIG_ADDRESS: u64 = 0x401;
+/// Storage key of the target block interval in milliseconds
+pub const BLOCK_INTERVAL_KEY: u64 = 0;
+
+pub fn sys_config_address() -> Address {
+    Address::from(SYS_CONFIG_ADDRESS)
+}
+
+pub struct SysConfig {
+    functions: HashMap<Signature, Box<Function>>,
+}
+
+impl Contract for SysConfig {
+    fn get_function(&self, hash: &Signature) -> Option<&Box<Function>> {
+        self.functions.get(hash)
+    }
+}
+
+impl SysConfig {
+    pub fn new() -> Self {
+        let mut contract = SysConfig { functions: HashMap::<Signature, Box<Function>>::new() };
+        contract.functions.insert(0, Box::new(SysConfig::set_block_interval));
+        contract
+    }
+    /// Only the admins set the interval.
+    pub fn set_block_interval(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let _ = ext.set_storage(H256::from(BLOCK_INTERVAL_KEY), H256::from(data));
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NodeManager, the nodes allowed to join the network
+pub const NODE_MANAGER_ADDRESS: u64 = 0x402;
+
+pub fn node_manager_address() -> Address {
+    Address::from(NODE_MANAGER_ADDRESS)
+}
+
+/// Storage key of the field of the ith key rotation, the index 0 holds the count
+/// and the fields of the rotation i + 1 are its height, old and new keys.
+pub fn rotation_key(index: u64, field: u64) -> H256 {
+    let mut key = b"rotation".to_vec();
+    key.extend_from_slice(&H256::from(index));
+    key.extend_from_slice(&H256::from(field));
+    key.crypt_hash()
+}
+
+pub struct NodeManager {
+    functions: HashMap<Signature, Box<Function>>,
+}
+
+impl Contract for NodeManager {
+    fn get_function(&self, hash: &Signature) -> Option<&Box<Function>> {
+        self.functions.get(hash)
+    }
+}
+
+/// Storage: key 0 is the count of nodes, key i + 1 is the ith node, the key rotations at
+/// `rotation_key`. Only the admins change the nodes and record the rotations.
+impl NodeManager {
+    pub fn new() -> Self {
+        let mut contract = NodeManager { functions: HashMap::<Signature, Box<Function>>::new() };
+        contract.functions.insert(0, Box::new(NodeManager::approve_node));
+        contract.functions.insert(1, Box::new(NodeManager::delete_node));
+        contract.functions.insert(2, Box::new(NodeManager::rotate_key));
+        contract
+    }
+
+    fn nodes(ext: &Ext) -> Vec<H256> {
+        let count = ext.storage_at(&H256::from(0)).map(|v| v.low_u64()).unwrap_or(0);
+        (0..count).map(|i| ext.storage_at(&H256::from(i + 1)).unwrap_or_default()).collect()
+    }
+
+    pub fn approve_node(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let node = H256::from(Address::from(H256::from(data)));
+                let nodes = NodeManager::nodes(ext);
+                if !nodes.contains(&node) {
+                    let count = nodes.len() as u64;
+                    let _ = ext.set_storage(H256::from(count + 1), node);
+                    let _ = ext.set_storage(H256::from(0), H256::from(count + 1));
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+
+    pub fn delete_node(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let node = H256::from(Address::from(H256::from(data)));
+                let nodes = NodeManager::nodes(ext);
+                if let Some(pos) = nodes.iter().position(|n| *n == node) {
+                    // move the last node to the hole
+                    let count = nodes.len() as u64;
+                    let _ = ext.set_storage(H256::from(pos as u64 + 1), nodes[nodes.len() - 1]);
+                    let _ = ext.set_storage(H256::from(count), H256::default());
+                    let _ = ext.set_storage(H256::from(0), H256::from(count - 1));
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+
+    /// Record the consensus key `old` replaced by `new` from the height, the arguments are
+    /// the old key, the new key and the height. The height is after the current block, so
+    /// every node applies the rotation at the same height.
+    pub fn rotate_key(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..100) {
+                let height = U256::from(&data[64..96]);
+                if height > U256::from(ext.env_info().number) {
+                    let count = ext.storage_at(&rotation_key(0, 0)).map(|v| v.low_u64()).unwrap_or(0) + 1;
+                    let _ = ext.set_storage(rotation_key(count, 0), H256::from(height));
+                    let _ = ext.set_storage(rotation_key(count, 1), H256::from(Address::from(H256::from(&data[..32]))));
+                    let _ = ext.set_storage(rotation_key(count, 2), H256::from(Address::from(H256::from(&data[32..64]))));
+                    let _ = ext.set_storage(rotation_key(0, 0), H256::from(count));
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// QuotaManager, the capacity of the blocks governed on chain
+pub const QUOTA_MANAGER_ADDRESS: u64 = 0x403;
+/// Storage key of the quota limit of a block, unlimited if zero
+pub const BLOCK_QUOTA_LIMIT_KEY: u64 = 0;
+/// Storage key of the quota an account may be charged in a block, unlimited if zero
+pub const ACCOUNT_QUOTA_LIMIT_KEY: u64 = 1;
+
+pub fn quota_manager_address() -> Address {
+    Address::from(QUOTA_MANAGER_ADDRESS)
+}
+
+pub struct QuotaManager {
+    functions: HashMap<Signature, Box<Function>>,
+}
+
+impl Contract for QuotaManager {
+    fn get_function(&self, hash: &Signature) -> Option<&Box<Function>> {
+        self.functions.get(hash)
+    }
+}
+
+/// Only the admins set the limits.
+impl QuotaManager {
+    pub fn new() -> Self {
+        let mut contract = QuotaManager { functions: HashMap::<Signature, Box<Function>>::new() };
+        contract.functions.insert(0, Box::new(QuotaManager::set_block_quota_limit));
+        contract.functions.insert(1, Box::new(QuotaManager::set_account_quota_limit));
+        contract
+    }
+    pub fn set_block_quota_limit(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let _ = ext.set_storage(H256::from(BLOCK_QUOTA_LIMIT_KEY), H256::from(data));
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+    pub fn set_account_quota_limit(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let _ = ext.set_storage(H256::from(ACCOUNT_QUOTA_LIMIT_KEY), H256::from(data));
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// StorageRent, the expiry of the storage of the contracts not touched for a while
+pub const STORAGE_RENT_ADDRESS: u64 = 0x404;
+/// Storage key of the number of blocks the storage of a contract is kept without being
+/// read or written, it is kept forever if zero
+pub const STORAGE_EXPIRY_KEY: u64 = 0;
+
+pub fn storage_rent_address() -> Address {
+    Address::from(STORAGE_RENT_ADDRESS)
+}
+
+fn rent_key(tag: &[u8], data: &[u8]) -> H256 {
+    let mut key = tag.to_vec();
+    key.extend_from_slice(data);
+    key.crypt_hash()
+}
+
+/// Storage key of the height the storage of the contract was last touched at.
+pub fn touched_key(address: &Address) -> H256 {
+    rent_key(b"touched", address)
+}
+
+/// Storage key of the storage root of the contract when it was evicted, zero if it was not.
+pub fn evicted_key(address: &Address) -> H256 {
+    rent_key(b"evicted", address)
+}
+
+/// Storage key of the number of contracts touched at the height.
+pub fn expiry_queue_len_key(height: u64) -> H256 {
+    rent_key(b"queue", &H256::from(height))
+}
+
+/// Storage key of the contract touched at the height in the position.
+pub fn expiry_queue_key(height: u64, index: u64) -> H256 {
+    let mut data = H256::from(height).to_vec();
+    data.extend_from_slice(&H256::from(index));
+    rent_key(b"queue", &data)
+}
+
+pub struct StorageRent {
+    functions: HashMap<Signature, Box<Function>>,
+}
+
+impl Contract for StorageRent {
+    fn get_function(&self, hash: &Signature) -> Option<&Box<Function>> {
+        self.functions.get(hash)
+    }
+}
+
+/// The executor records the height each contract storage is touched at, and evicts the
+/// storage of the contracts not touched for the expiry, keeping its root. The contract
+/// cannot run until anyone restores the storage, by sending the evicted entries.
+/// Only the admins set the expiry.
+impl StorageRent {
+    pub fn new() -> Self {
+        let mut contract = StorageRent { functions: HashMap::<Signature, Box<Function>>::new() };
+        contract.functions.insert(0, Box::new(StorageRent::set_storage_expiry));
+        contract.functions.insert(1, Box::new(StorageRent::restore_storage));
+        contract
+    }
+    pub fn set_storage_expiry(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let _ = ext.set_storage(H256::from(STORAGE_EXPIRY_KEY), H256::from(data));
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+    /// The address, then the key and value of each entry of the evicted storage.
+    pub fn restore_storage(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        if let Some(ref data) = params.data {
+            if let (Some(address), Some(entries)) = (data.get(4..36), data.get(36..)) {
+                let address = Address::from(H256::from(address));
+                let root = ext.storage_at(&evicted_key(&address))?;
+                if root.is_zero() || entries.len() % 64 != 0 {
+                    return Ok(GasLeft::Known(U256::from(0)));
+                }
+                let storage: Vec<(H256, H256)> = entries.chunks(64).map(|e| (H256::from(&e[..32]), H256::from(&e[32..]))).collect();
+                let trie_entries = storage.iter()
+                                          .filter(|&&(_, ref value)| !value.is_zero())
+                                          .map(|&(ref key, ref value)| (key.to_vec(), rlp::encode(&U256::from(&**value)).to_vec()))
+                                          .collect();
+                if sec_trie_root(trie_entries) == root {
+                    ext.restore_storage(&address, storage)?;
+                    ext.set_storage(evicted_key(&address), H256::default())?;
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PermissionManager, the accounts allowed to send transactions and to deploy contracts
+pub const PERMISSION_MANAGER_ADDRESS: u64 = 0x405;
+/// List of the accounts allowed to send transactions, anyone may if it is empty
+pub const SEND_PERMISSION: u64 = 0;
+/// List of the accounts allowed to deploy contracts, anyone may if it is empty
+pub const CREATE_PERMISSION: u64 = 1;
+/// List of the admins, the accounts allowed to change the parameters of the chain
+/// and these lists, nobody may if it is empty
+pub const ADMIN_PERMISSION: u64 = 2;
+
+pub fn permission_manager_address() -> Address {
+    Address::from(PERMISSION_MANAGER_ADDRESS)
+}
+
+/// Storage key of the list at the index, the index 0 holds the count and the index i + 1 the ith account.
+pub fn permission_key(list: u64, index: u64) -> H256 {
+    H256::from((list << 32) + index)
+}
+
+pub struct PermissionManager {
+    functions: HashMap<Signature, Box<Function>>,
+}
+
+impl Contract for PermissionManager {
+    fn get_function(&self, hash: &Signature) -> Option<&Box<Function>> {
+        self.functions.get(hash)
+    }
+}
+
+/// The executor does not apply the transactions of the accounts not allowed,
+/// and auth refuses them before they enter the tx pool. Only the admins change the lists.
+impl PermissionManager {
+    pub fn new() -> Self {
+        let mut contract = PermissionManager { functions: HashMap::<Signature, Box<Function>>::new() };
+        contract.functions.insert(0, Box::new(PermissionManager::grant_send));
+        contract.functions.insert(1, Box::new(PermissionManager::revoke_send));
+        contract.functions.insert(2, Box::new(PermissionManager::grant_create));
+        contract.functions.insert(3, Box::new(PermissionManager::revoke_create));
+        contract.functions.insert(4, Box::new(PermissionManager::grant_admin));
+        contract.functions.insert(5, Box::new(PermissionManager::revoke_admin));
+        contract
+    }
+
+    pub fn grant_send(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::grant(SEND_PERMISSION, params, ext)
+    }
+
+    pub fn revoke_send(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::revoke(SEND_PERMISSION, params, ext)
+    }
+
+    pub fn grant_create(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::grant(CREATE_PERMISSION, params, ext)
+    }
+
+    pub fn revoke_create(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::revoke(CREATE_PERMISSION, params, ext)
+    }
+
+    pub fn grant_admin(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::grant(ADMIN_PERMISSION, params, ext)
+    }
+
+    pub fn revoke_admin(params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        PermissionManager::revoke(ADMIN_PERMISSION, params, ext)
+    }
+
+    fn accounts(list: u64, ext: &Ext) -> Vec<H256> {
+        let count = ext.storage_at(&permission_key(list, 0)).map(|v| v.low_u64()).unwrap_or(0);
+        (0..count).map(|i| ext.storage_at(&permission_key(list, i + 1)).unwrap_or_default()).collect()
+    }
+
+    fn grant(list: u64, params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let account = H256::from(Address::from(H256::from(data)));
+                let accounts = PermissionManager::accounts(list, ext);
+                if !accounts.contains(&account) {
+                    let count = accounts.len() as u64;
+                    ext.set_storage(permission_key(list, count + 1), account)?;
+                    ext.set_storage(permission_key(list, 0), H256::from(count + 1))?;
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
+    }
+
+    fn revoke(list: u64, params: &ActionParams, ext: &mut Ext) -> evm::Result<GasLeft<'static>> {
+        require_admin(params, ext)?;
+        if let Some(ref data) = params.data {
+            if let Some(data) = data.get(4..36) {
+                let account = H256::from(Address::from(H256::from(data)));
+                let accounts = PermissionManager::accounts(list, ext);
+                if let Some(pos) = accounts.iter().position(|a| *a == account) {
+                    // move the last account to the hole
+                    let count = accounts.len() as u64;
+                    ext.set_storage(permission_key(list, pos as u64 + 1), accounts[accounts.len() - 1])?;
+                    ext.set_storage(permission_key(list, count), H256::default())?;
+                    ext.set_storage(permission_key(list, 0), H256::from(count - 1))?;
+                }
+            }
+        }
+        Ok(GasLeft::Known(U256::from(0)))
     }
 }
 
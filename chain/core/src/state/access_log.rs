@@ -0,0 +1,70 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State accessed by a transaction, recorded for the execution audit.
+
+use rlp::{Encodable, RlpStream};
+use std::collections::BTreeMap;
+use util::{Address, H256, U256};
+
+/// Nonce and code hash of an account, `None` if it does not exist.
+pub type AccountSummary = Option<(U256, H256)>;
+
+/// State read and written by a transaction. The entries are ordered by the keys,
+/// so the same execution gives the same log on any node.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccessLog {
+    /// Accounts read, as they were first read.
+    pub accounts: BTreeMap<Address, AccountSummary>,
+    /// Storage read, with the values first read.
+    pub reads: BTreeMap<(Address, H256), H256>,
+    /// Storage written, with the values last written.
+    pub writes: BTreeMap<(Address, H256), H256>,
+    /// Accounts created, killed or whose nonce or code changed, as they are after the transaction.
+    pub changed: BTreeMap<Address, AccountSummary>,
+}
+
+fn append_accounts(s: &mut RlpStream, accounts: &BTreeMap<Address, AccountSummary>) {
+    s.begin_list(accounts.len());
+    for (address, summary) in accounts {
+        match *summary {
+            Some((ref nonce, ref code_hash)) => {
+                s.begin_list(3).append(address).append(nonce).append(code_hash);
+            }
+            None => {
+                s.begin_list(1).append(address);
+            }
+        }
+    }
+}
+
+fn append_storage(s: &mut RlpStream, storage: &BTreeMap<(Address, H256), H256>) {
+    s.begin_list(storage.len());
+    for (&(ref address, ref key), value) in storage {
+        s.begin_list(3).append(address).append(key).append(value);
+    }
+}
+
+impl Encodable for AccessLog {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(4);
+        append_accounts(s, &self.accounts);
+        append_storage(s, &self.reads);
+        append_storage(s, &self.writes);
+        append_accounts(s, &self.changed);
+    }
+}
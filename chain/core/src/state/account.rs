@@ -182,6 +182,18 @@ impl Account {
         Ok(value)
     }
 
+    /// Prove a storage key's existence or nonexistence in the account's storage trie.
+    /// Returns the nodes visited from the storage root and the value of the key.
+    pub fn prove_storage(&self, trie_factory: &TrieFactory, db: &HashDB, key: &H256) -> trie::Result<(Vec<Bytes>, H256)> {
+        let mut recorder = trie::Recorder::new();
+        let t = trie_factory.readonly(db, &self.storage_root)?;
+        let item: U256 = {
+            let query = (&mut recorder, ::rlp::decode);
+            t.get_with(key, query)?.unwrap_or_else(U256::zero)
+        };
+        Ok((recorder.drain().into_iter().map(|r| r.data).collect(), item.into()))
+    }
+
     /// Get cached storage value if any. Returns `None` if the
     /// key is not in the cache.
     pub fn cached_storage_at(&self, key: &H256) -> Option<H256> {
@@ -299,6 +311,11 @@ impl Account {
             }
     }
 
+    /// Determine whether the code has not been changed since it was committed.
+    pub fn code_is_clean(&self) -> bool {
+        self.code_filth == Filth::Clean
+    }
+
     /// Determine whether there are any un-`commit()`-ed storage-setting operations.
     pub fn storage_is_clean(&self) -> bool {
         self.storage_changes.is_empty()
@@ -327,6 +344,13 @@ impl Account {
         &self.storage_changes
     }
 
+    /// Drop the committed storage, returning its root to be kept until the storage is restored.
+    pub fn evict_storage(&mut self) -> H256 {
+        self.storage_changes.clear();
+        self.storage_cache = Self::empty_storage_cache();
+        ::std::mem::replace(&mut self.storage_root, HASH_NULL_RLP)
+    }
+
     /// Increment the nonce of the account by one.
     pub fn inc_nonce(&mut self) {
         self.nonce = self.nonce + U256::from(1u8);
@@ -421,6 +445,18 @@ impl Account {
         }
         self.storage_changes = other.storage_changes;
     }
+
+    /// Replace self with the committed data of the same account at a later state,
+    /// keeping the cached storage which is not changed. The storage cache of `other`
+    /// holds all the changed keys unless it is full, when ours is dropped instead.
+    pub fn update_cached(&mut self, other: Account) {
+        // an evicted storage changed all the keys
+        if other.storage_cache.borrow().len() >= STORAGE_CACHE_ITEMS || other.storage_root == HASH_NULL_RLP {
+            *self = other;
+        } else {
+            self.overwrite_with(other);
+        }
+    }
 }
 
 impl fmt::Debug for Account {
@@ -15,6 +15,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use state::Account;
 use util::*;
 
 /// State backend. See module docs for more details.
@@ -24,4 +25,20 @@ pub trait Backend: Send {
 
     /// Treat the backend as a writeable hashdb.
     fn as_hashdb_mut(&mut self) -> &mut HashDB;
+
+    /// Add an account of the state to be put in the shared cache,
+    /// `modified` if the account was changed by the state.
+    fn add_to_account_cache(&mut self, address: Address, account: Option<Account>, modified: bool);
+
+    /// Get basic copy of the account from the shared cache.
+    /// `None` if not cached, `Some(None)` if the account is known not to exist.
+    fn get_cached_account(&self, address: &Address) -> Option<Option<Account>>;
+
+    /// Apply `f` to the account in the shared cache, `None` if not cached.
+    fn get_cached<F, U>(&self, address: &Address, f: F) -> Option<U>
+    where
+        F: FnOnce(Option<&mut Account>) -> U;
+
+    /// Note that `delta` accounts of the state started or stopped using the shared code kept at `code_key`.
+    fn note_code_refs(&mut self, code_key: H256, delta: i64);
 }
@@ -20,25 +20,30 @@
 //! Unconfirmed sub-states are managed with `checkpoint`s which may be canonicalized
 //! or rolled back.
 
-use engines::NullEngine;
+use engines::Engine;
 use env_info::EnvInfo;
 use error::Error;
 use executive::{Executive, TransactOptions};
 use factory::Factories;
 use receipt::Receipt;
 use std::cell::{RefCell, RefMut};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::collections::hash_map::Entry;
 use std::fmt;
 use std::sync::Arc;
 use trace::FlatTrace;
+use types::account_diff::{AccountDiff, Diff};
+use types::basic_account::BasicAccount;
+use types::state_diff::StateDiff;
 use types::transaction::SignedTransaction;
 use util::*;
 use util::trie;
 
+pub mod access_log;
 pub mod account;
 pub mod backend;
 
+pub use self::access_log::{AccessLog, AccountSummary};
 pub use self::account::Account;
 use self::backend::*;
 use state_db::*;
@@ -62,7 +67,7 @@ enum AccountState {
     /// Account was loaded from disk and never modified in this state object.
     CleanFresh,
     /// Account was loaded from the global cache and never modified.
-    // CleanCached,
+    CleanCached,
     /// Account has been modified and is not committed to the trie yet.
     /// This is set if any of the account data is changed, including
     /// storage and code.
@@ -124,12 +129,12 @@ impl AccountEntry {
     }
 
     // Create a new account entry and mark it as clean and cached.
-    // fn new_clean_cached(account: Option<Account>) -> AccountEntry {
-    //     AccountEntry {
-    //         account: account,
-    //         state: AccountState::CleanCached,
-    //     }
-    // }
+    fn new_clean_cached(account: Option<Account>) -> AccountEntry {
+        AccountEntry {
+            account: account,
+            state: AccountState::CleanCached,
+        }
+    }
 
     // Replace data with another entry but preserve storage cache.
     fn overwrite_with(&mut self, other: AccountEntry) {
@@ -198,6 +203,10 @@ pub struct State<B: Backend> {
     checkpoints: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
     account_start_nonce: U256,
     factories: Factories,
+    // state accessed since `start_access_log`, for the execution audit
+    access_log: RefCell<Option<AccessLog>>,
+    // accounts whose storage was accessed since `track_storage_touches`, for the storage expiry
+    storage_touched: RefCell<Option<BTreeSet<Address>>>,
 }
 
 #[derive(Copy, Clone)]
@@ -237,6 +246,8 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            access_log: RefCell::new(None),
+            storage_touched: RefCell::new(None),
         }
     }
 
@@ -253,6 +264,8 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            access_log: RefCell::new(None),
+            storage_touched: RefCell::new(None),
         };
 
         Ok(state)
@@ -335,10 +348,25 @@ impl<B: Backend> State<B> {
     }
 
     /// Destroy the current object and return root and database.
-    pub fn drop(self) -> (H256, B) {
+    pub fn drop(mut self) -> (H256, B) {
+        self.propagate_to_global_cache();
         (self.root, self.db)
     }
 
+    /// Propagate the accounts loaded from the database or committed by this state
+    /// to the global cache, which takes them once the state is committed.
+    fn propagate_to_global_cache(&mut self) {
+        let addresses = self.cache.get_mut();
+        trace!("Propagating {} accounts to the global cache", addresses.len());
+        for (address, a) in addresses.drain() {
+            match a.state {
+                AccountState::Committed => self.db.add_to_account_cache(address, a.account, true),
+                AccountState::CleanFresh => self.db.add_to_account_cache(address, a.account, false),
+                AccountState::CleanCached | AccountState::Dirty => {}
+            }
+        }
+    }
+
     pub fn db(self) -> B {
         self.db
     }
@@ -348,14 +376,70 @@ impl<B: Backend> State<B> {
         &self.root
     }
 
+    /// Start recording the state read and written, until `take_access_log`.
+    pub fn start_access_log(&self) {
+        *self.access_log.borrow_mut() = Some(AccessLog::default());
+    }
+
+    /// Stop recording and return the state accessed, with the changed accounts as they are now.
+    pub fn take_access_log(&self) -> trie::Result<Option<AccessLog>> {
+        let mut log = match self.access_log.borrow_mut().take() {
+            Some(log) => log,
+            None => return Ok(None),
+        };
+        for (address, summary) in log.changed.iter_mut() {
+            *summary = self.ensure_cached(address, RequireCache::None, true, |a| a.map(|a| (*a.nonce(), a.code_hash())))?;
+        }
+        Ok(Some(log))
+    }
+
+    /// Start recording the accounts whose storage is read or written.
+    pub fn track_storage_touches(&self) {
+        *self.storage_touched.borrow_mut() = Some(BTreeSet::new());
+    }
+
+    /// The accounts whose storage was read or written since the last call, in order.
+    pub fn take_storage_touches(&self) -> BTreeSet<Address> {
+        self.storage_touched.borrow_mut().as_mut().map_or_else(BTreeSet::new, |touched| ::std::mem::replace(touched, BTreeSet::new()))
+    }
+
+    fn touch_storage(&self, address: &Address) {
+        if let Some(ref mut touched) = *self.storage_touched.borrow_mut() {
+            touched.insert(*address);
+        }
+    }
+
+    fn log_access<F>(&self, f: F)
+    where
+        F: FnOnce(&mut AccessLog),
+    {
+        if let Some(ref mut log) = *self.access_log.borrow_mut() {
+            f(log);
+        }
+    }
+
+    fn log_account_read(&self, address: &Address, account: Option<&Account>) {
+        self.log_access(|log| {
+                            log.accounts.entry(*address).or_insert_with(|| account.map(|a| (*a.nonce(), a.code_hash())));
+                        });
+    }
+
+    fn log_account_changed(&self, address: &Address) {
+        self.log_access(|log| {
+                            log.changed.insert(*address, None);
+                        });
+    }
+
     /// Create a new contract at address `contract`. If there is already an account at the address
     /// it will have its code reset, ready for `init_code()`.
     pub fn new_contract(&mut self, contract: &Address, nonce_offset: U256) {
+        self.log_account_changed(contract);
         self.insert_cache(contract, AccountEntry::new_dirty(Some(Account::new_contract(self.account_start_nonce + nonce_offset))));
     }
 
     /// Remove an existing account.
     pub fn kill_account(&mut self, account: &Address) {
+        self.log_account_changed(account);
         self.insert_cache(account, AccountEntry::new_dirty(None));
     }
 
@@ -382,9 +466,17 @@ impl<B: Backend> State<B> {
         self.ensure_cached(a, RequireCache::None, true, |a| a.as_ref().and_then(|account| account.storage_root().cloned()))
     }
 
-    // TODO: Add global cache.
-    /// Mutate storage of account `address` so that it is `value` for `key`.
+    /// Get the value of storage `key` of account `address`.
     pub fn storage_at(&self, address: &Address, key: &H256) -> trie::Result<H256> {
+        self.touch_storage(address);
+        let value = self.load_storage_at(address, key)?;
+        self.log_access(|log| {
+                            log.reads.entry((*address, *key)).or_insert(value);
+                        });
+        Ok(value)
+    }
+
+    fn load_storage_at(&self, address: &Address, key: &H256) -> trie::Result<H256> {
         // Storage key search and update works like this:
         // 1. If there's an entry for the account in the local cache check for the key and return it if found.
         // 2. If there's an entry for the account in the global cache check for the key or load it into that account.
@@ -418,6 +510,18 @@ impl<B: Backend> State<B> {
             }
         }
 
+        // check the global cache and cache the storage key there if found
+        let trie_res = self.db.get_cached(address, |acc| match acc {
+            None => Ok(H256::new()),
+            Some(a) => {
+                let account_db = self.factories.accountdb.readonly(self.db.as_hashdb(), a.address_hash(address));
+                a.storage_at(&self.factories.trie, account_db.as_hashdb(), key)
+            }
+        });
+        if let Some(res) = trie_res {
+            return res;
+        }
+
         // TODO: add account bloom. check if the account could exist before any requests to trie
 
         // account is not found in the global cache, get from the DB and insert into local
@@ -431,6 +535,33 @@ impl<B: Backend> State<B> {
         r
     }
 
+    /// Prove an account's existence or nonexistence in the state trie.
+    /// Returns the nodes visited from the state root and the account, which is
+    /// the empty account if it does not exist.
+    pub fn prove_account(&self, address: &Address) -> trie::Result<(Vec<Bytes>, BasicAccount)> {
+        let mut recorder = trie::Recorder::new();
+        let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
+        let maybe_account: Option<BasicAccount> = {
+            let query = (&mut recorder, ::rlp::decode);
+            db.get_with(address, query)?
+        };
+        let account = maybe_account.unwrap_or_else(|| BasicAccount { nonce: self.account_start_nonce, storage_root: HASH_NULL_RLP, code_hash: HASH_EMPTY });
+        Ok((recorder.drain().into_iter().map(|r| r.data).collect(), account))
+    }
+
+    /// Prove a storage key's existence or nonexistence in the storage trie of the account.
+    /// Returns the nodes visited from the storage root of the account and the value of the key,
+    /// the proof is empty if the account does not exist.
+    pub fn prove_storage(&self, address: &Address, key: &H256) -> trie::Result<(Vec<Bytes>, H256)> {
+        let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
+        let account = match db.get_with(address, Account::from_rlp)? {
+            Some(account) => account,
+            None => return Ok((Vec::new(), H256::new())),
+        };
+        let account_db = self.factories.accountdb.readonly(self.db.as_hashdb(), account.address_hash(address));
+        account.prove_storage(&self.factories.trie, account_db.as_hashdb(), key)
+    }
+
     /// Get accounts' code.
     pub fn code(&self, a: &Address) -> trie::Result<Option<Arc<Bytes>>> {
         self.ensure_cached(a, RequireCache::Code, true, |a| a.as_ref().map_or(None, |a| a.code().clone()))
@@ -448,21 +579,33 @@ impl<B: Backend> State<B> {
 
     /// Increment the nonce of account `a` by 1.
     pub fn inc_nonce(&mut self, a: &Address) -> trie::Result<()> {
+        self.log_account_changed(a);
         self.require(a, false).map(|mut x| x.inc_nonce())
     }
 
     /// Mutate storage of account `a` so that it is `value` for `key`.
     pub fn set_storage(&mut self, a: &Address, key: H256, value: H256) -> trie::Result<()> {
-        if self.storage_at(a, &key)? != value {
+        self.touch_storage(a);
+        self.log_access(|log| {
+                            log.writes.insert((*a, key), value);
+                        });
+        if self.load_storage_at(a, &key)? != value {
             self.require(a, false)?.set_storage(key, value)
         }
 
         Ok(())
     }
 
+    /// Drop the committed storage of the account, returning its root so that it can be restored.
+    pub fn evict_storage(&mut self, a: &Address) -> trie::Result<H256> {
+        self.log_account_changed(a);
+        Ok(self.require(a, false)?.evict_storage())
+    }
+
     /// Initialise the code of account `a` so that it is `code`.
     /// NOTE: Account should have been created with `new_contract`.
     pub fn init_code(&mut self, a: &Address, code: Bytes) -> trie::Result<()> {
+        self.log_account_changed(a);
         self.require_or_from(a, true, || Account::new_contract(self.account_start_nonce), |_| {})?
             .init_code(code);
         Ok(())
@@ -470,6 +613,7 @@ impl<B: Backend> State<B> {
 
     /// Reset the code of account `a` so that it is `code`.
     pub fn reset_code(&mut self, a: &Address, code: Bytes) -> trie::Result<()> {
+        self.log_account_changed(a);
         self.require_or_from(a, true, || Account::new_contract(self.account_start_nonce), |_| {})?
             .reset_code(code);
         Ok(())
@@ -477,9 +621,8 @@ impl<B: Backend> State<B> {
 
     /// Execute a given transaction.
     /// This will change the state accordingly.
-    pub fn apply(&mut self, env_info: &EnvInfo, t: &SignedTransaction, tracing: bool) -> ApplyResult {
+    pub fn apply(&mut self, env_info: &EnvInfo, engine: &Engine, t: &SignedTransaction, tracing: bool) -> ApplyResult {
         //		let old = self.to_pod();
-        let engine = &NullEngine::default();
         let options = TransactOptions {
             tracing: tracing,
             vm_tracing: false,
@@ -501,21 +644,43 @@ impl<B: Backend> State<B> {
     #[cfg_attr(feature = "dev", allow(match_ref_pats))]
     #[cfg_attr(feature = "dev", allow(needless_borrow))]
     fn commit_into(factories: &Factories, db: &mut B, root: &mut H256, accounts: &mut HashMap<Address, AccountEntry>) -> Result<(), Error> {
+        // accounts whose code may no more be used, killed or with a new code
+        let mut code_changed = HashSet::new();
         // first, commit the sub trees.
         for (address, ref mut a) in accounts.iter_mut().filter(|&(_, ref a)| a.is_dirty()) {
-            if let Some(ref mut account) = a.account {
-                let addr_hash = account.address_hash(address);
-                {
-                    let mut account_db = factories.accountdb.create(db.as_hashdb_mut(), addr_hash);
-                    account.commit_storage(&factories.trie, account_db.as_hashdb_mut())?;
-
-                    account.commit_code(account_db.as_hashdb_mut());
+            match a.account {
+                Some(ref mut account) => {
+                    let addr_hash = account.address_hash(address);
+                    {
+                        let mut account_db = factories.accountdb.create(db.as_hashdb_mut(), addr_hash);
+                        account.commit_storage(&factories.trie, account_db.as_hashdb_mut())?;
+                    }
+                    if !account.code_is_clean() {
+                        code_changed.insert(*address);
+                        if account.code_hash() != HASH_EMPTY {
+                            db.note_code_refs(factories.accountdb.code_key(&account.code_hash()), 1);
+                        }
+                        let mut code_db = factories.accountdb.create_code(db.as_hashdb_mut());
+                        account.commit_code(code_db.as_hashdb_mut());
+                    }
+                }
+                None => {
+                    code_changed.insert(*address);
                 }
             }
         }
 
+        let mut old_codes = Vec::new();
         {
             let mut trie = factories.trie.from_existing(db.as_hashdb_mut(), root)?;
+            for address in &code_changed {
+                if let Some(value) = trie.get(address)? {
+                    let code_hash = Account::from_rlp(&value).code_hash();
+                    if code_hash != HASH_EMPTY {
+                        old_codes.push((address.crypt_hash(), code_hash));
+                    }
+                }
+            }
             for (address, ref mut a) in accounts.iter_mut().filter(|&(_, ref a)| a.is_dirty()) {
                 a.state = AccountState::Committed;
                 match a.account {
@@ -528,6 +693,11 @@ impl<B: Backend> State<B> {
                 }
             }
         }
+        for (addr_hash, code_hash) in old_codes {
+            if !factories.accountdb.has_own_code(db.as_hashdb(), addr_hash, &code_hash) {
+                db.note_code_refs(factories.accountdb.code_key(&code_hash), -1);
+            }
+        }
 
         Ok(())
     }
@@ -538,6 +708,72 @@ impl<B: Backend> State<B> {
         Self::commit_into(&self.factories, &mut self.db, &mut self.root, &mut *self.cache.borrow_mut())
     }
 
+    /// Diff of the accounts changed since the last commit against the committed state,
+    /// so it is taken before `commit`. The storage of a killed account is not listed.
+    pub fn diff(&self) -> trie::Result<StateDiff> {
+        let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
+        let mut raw = BTreeMap::new();
+        for (address, entry) in self.cache.borrow().iter().filter(|&(_, ref a)| a.is_dirty()) {
+            let mut pre = db.get_with(address, Account::from_rlp)?;
+            let post = entry.account.as_ref();
+            let account_db = self.factories.accountdb.readonly(self.db.as_hashdb(), address.crypt_hash());
+
+            let nonce = match (pre.as_ref(), post) {
+                (None, Some(b)) => Diff::Born(*b.nonce()),
+                (Some(a), None) => Diff::Died(*a.nonce()),
+                (Some(a), Some(b)) => Diff::new(*a.nonce(), *b.nonce()),
+                (None, None) => Diff::Same,
+            };
+
+            let pre_hash = pre.as_ref().map_or(HASH_EMPTY, |a| a.code_hash());
+            let post_hash = post.map_or(HASH_EMPTY, |b| b.code_hash());
+            let code = if pre_hash == post_hash {
+                Diff::Same
+            } else {
+                let code_db = self.factories.accountdb.readonly_code(self.db.as_hashdb(), address.crypt_hash());
+                let pre_code = pre.as_mut().and_then(|a| a.cache_code(code_db.as_hashdb())).map_or_else(Vec::new, |c| (*c).clone());
+                let post_code = post.and_then(|b| b.code()).map_or_else(Vec::new, |c| (*c).clone());
+                if pre_code.is_empty() {
+                    Diff::Born(post_code)
+                } else if post_code.is_empty() {
+                    Diff::Died(pre_code)
+                } else {
+                    Diff::Changed(pre_code, post_code)
+                }
+            };
+
+            let mut storage = BTreeMap::new();
+            for (key, value) in post.map_or_else(HashMap::new, |b| b.storage_changes().clone()) {
+                let pre_value = match pre {
+                    Some(ref a) => a.storage_at(&self.factories.trie, account_db.as_hashdb(), &key)?,
+                    None => H256::new(),
+                };
+                if pre_value == value {
+                    continue;
+                }
+                let diff = if pre_value.is_zero() {
+                    Diff::Born(value)
+                } else if value.is_zero() {
+                    Diff::Died(pre_value)
+                } else {
+                    Diff::Changed(pre_value, value)
+                };
+                storage.insert(key, diff);
+            }
+
+            if !nonce.is_same() || !code.is_same() || !storage.is_empty() {
+                raw.insert(*address,
+                           AccountDiff {
+                               balance: Diff::Same,
+                               nonce: nonce,
+                               code: code,
+                               storage: storage,
+                           });
+            }
+        }
+        Ok(StateDiff { raw: raw })
+    }
+
     /// Clear state cache
     pub fn clear(&mut self) {
         self.cache.borrow_mut().clear();
@@ -591,16 +827,34 @@ impl<B: Backend> State<B> {
         // check local cache first
         if let Some(ref mut maybe_acc) = self.cache.borrow_mut().get_mut(a) {
             if let Some(ref mut account) = maybe_acc.account {
-                let accountdb = self.factories.accountdb.readonly(self.db.as_hashdb(), account.address_hash(a));
+                let accountdb = self.factories.accountdb.readonly_code(self.db.as_hashdb(), account.address_hash(a));
                 Self::update_account_cache(require,
                                            account,
                                            /* &self.db, */
                                            accountdb.as_hashdb());
+                self.log_account_read(a, Some(account));
                 return Ok(f(Some(account)));
             }
+            self.log_account_read(a, None);
             return Ok(f(None));
         }
-        // TODO: check global cache
+
+        // check the global cache
+        let result = self.db.get_cached(a, |acc| match acc {
+            Some(account) => {
+                let accountdb = self.factories.accountdb.readonly_code(self.db.as_hashdb(), account.address_hash(a));
+                Self::update_account_cache(require, account, accountdb.as_hashdb());
+                self.log_account_read(a, Some(&*account));
+                f(Some(&*account))
+            }
+            None => {
+                self.log_account_read(a, None);
+                f(None)
+            }
+        });
+        if let Some(r) = result {
+            return Ok(r);
+        }
 
         // first check if it is not in database for sure
 
@@ -608,12 +862,13 @@ impl<B: Backend> State<B> {
         let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
         let mut maybe_acc = db.get_with(a, Account::from_rlp)?;
         if let Some(ref mut account) = maybe_acc.as_mut() {
-            let accountdb = self.factories.accountdb.readonly(self.db.as_hashdb(), account.address_hash(a));
+            let accountdb = self.factories.accountdb.readonly_code(self.db.as_hashdb(), account.address_hash(a));
             Self::update_account_cache(require,
                                        account,
                                        /* &self.db, */
                                        accountdb.as_hashdb());
         }
+        self.log_account_read(a, maybe_acc.as_ref());
         let r = f(maybe_acc.as_ref());
         self.insert_cache(a, AccountEntry::new_clean(maybe_acc));
         Ok(r)
@@ -633,9 +888,14 @@ impl<B: Backend> State<B> {
     {
         let contains_key = self.cache.borrow().contains_key(a);
         if !contains_key {
-            let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
-            let maybe_acc = AccountEntry::new_clean(db.get_with(a, Account::from_rlp)?);
-            self.insert_cache(a, maybe_acc);
+            match self.db.get_cached_account(a) {
+                Some(acc) => self.insert_cache(a, AccountEntry::new_clean_cached(acc)),
+                None => {
+                    let db = self.factories.trie.readonly(self.db.as_hashdb(), &self.root)?;
+                    let maybe_acc = AccountEntry::new_clean(db.get_with(a, Account::from_rlp)?);
+                    self.insert_cache(a, maybe_acc);
+                }
+            }
         }
         self.note_cache(a);
 
@@ -654,7 +914,7 @@ impl<B: Backend> State<B> {
                 Some(ref mut account) => {
                     if require_code {
                         let addr_hash = account.address_hash(a);
-                        let accountdb = self.factories.accountdb.readonly(self.db.as_hashdb(), addr_hash);
+                        let accountdb = self.factories.accountdb.readonly_code(self.db.as_hashdb(), addr_hash);
                         Self::update_account_cache(RequireCache::Code,
                                                    account,
                                                    /* &self.db, */
@@ -695,10 +955,24 @@ impl Clone for State<StateDB> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: self.account_start_nonce.clone(),
             factories: self.factories.clone(),
+            access_log: RefCell::new(None),
+            storage_touched: RefCell::new(None),
         }
     }
 }
 
+/// Check a proof returned by `prove_account` or `prove_storage` against the trie root,
+/// returning the value of the key, or `None` if the proof shows the key is absent.
+/// An incomplete proof fails with the missing node.
+pub fn check_proof(proof: &[Bytes], root: &H256, key: &[u8]) -> trie::Result<Option<DBValue>> {
+    let mut db = MemoryDB::new();
+    for node in proof {
+        db.insert(&node[..]);
+    }
+    let t = TrieDB::new(&db, root)?;
+    t.get(key)
+}
+
 #[cfg(test)]
 mod tests {
     extern crate libproto;
@@ -709,6 +983,7 @@ mod tests {
 
     use self::libproto::blockchain;
     use super::*;
+    use engines::NullEngine;
     use env_info::EnvInfo;
     use rustc_hex::FromHex;
     use std::sync::Arc;
@@ -716,6 +991,67 @@ mod tests {
     use util::{H256, H512, Address};
     use util::hashable::HASH_NAME;
 
+    #[test]
+    fn diff_changed_accounts() {
+        let a = Address::from(0x1234);
+        let b = Address::from(0x5678);
+        let mut state = get_temp_state();
+        state.inc_nonce(&a).unwrap();
+        state.set_storage(&a, H256::from(1), H256::from(1)).unwrap();
+        state.set_storage(&a, H256::from(2), H256::from(2)).unwrap();
+        state.commit().unwrap();
+        assert!(state.diff().unwrap().is_empty());
+
+        state.inc_nonce(&a).unwrap();
+        state.set_storage(&a, H256::from(1), H256::new()).unwrap();
+        state.set_storage(&a, H256::from(2), H256::from(3)).unwrap();
+        state.set_storage(&a, H256::from(4), H256::from(4)).unwrap();
+        state.init_code(&b, vec![0x60, 0x00]).unwrap();
+        let diff = state.diff().unwrap();
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[&a].nonce, Diff::Changed(U256::from(1), U256::from(2)));
+        assert!(diff[&a].code.is_same());
+        assert_eq!(diff[&a].storage.len(), 3);
+        assert_eq!(diff[&a].storage[&H256::from(1)], Diff::Died(H256::from(1)));
+        assert_eq!(diff[&a].storage[&H256::from(2)], Diff::Changed(H256::from(2), H256::from(3)));
+        assert_eq!(diff[&a].storage[&H256::from(4)], Diff::Born(H256::from(4)));
+        assert_eq!(diff[&b].nonce, Diff::Born(U256::from(0)));
+        assert_eq!(diff[&b].code, Diff::Born(vec![0x60, 0x00]));
+    }
+
+    #[test]
+    fn prove_account_and_storage() {
+        let a = Address::from(0x1234);
+        let b = Address::from(0x5678);
+        let mut state = get_temp_state();
+        state.inc_nonce(&a).unwrap();
+        state.set_storage(&a, H256::from(1), H256::from(42)).unwrap();
+        state.inc_nonce(&b).unwrap();
+        state.commit().unwrap();
+        let root = *state.root();
+
+        let (proof, account) = state.prove_account(&a).unwrap();
+        assert_eq!(account.nonce, U256::from(1));
+        let value = check_proof(&proof, &root, &a).unwrap().unwrap();
+        assert_eq!(::rlp::decode::<BasicAccount>(&value), account);
+
+        let (proof, value) = state.prove_storage(&a, &H256::from(1)).unwrap();
+        assert_eq!(value, H256::from(42));
+        let stored = check_proof(&proof, &account.storage_root, &H256::from(1)).unwrap().unwrap();
+        assert_eq!(::rlp::decode::<U256>(&stored), U256::from(42));
+
+        let (proof, value) = state.prove_storage(&a, &H256::from(2)).unwrap();
+        assert_eq!(value, H256::new());
+        assert!(check_proof(&proof, &account.storage_root, &H256::from(2)).unwrap().is_none());
+
+        let missing = Address::from(0x9abc);
+        let (proof, account) = state.prove_account(&missing).unwrap();
+        assert_eq!(account.storage_root, HASH_NULL_RLP);
+        assert!(check_proof(&proof, &root, &missing).unwrap().is_none());
+        assert!(check_proof(&[], &root, &a).is_err());
+        assert!(state.prove_storage(&missing, &H256::from(1)).unwrap().0.is_empty());
+    }
+
     #[test]
     fn should_apply_create_transaction() {
         /*
@@ -765,9 +1101,10 @@ mod tests {
         // 5)
         let mut state = get_temp_state();
         let info = EnvInfo::default();
+        let engine = NullEngine::default();
         //info.gas_limit = U256::from(100_000);
         let contract_address = ::executive::contract_address(&signed.sender(), &U256::from(0));
-        let result = state.apply(&info, &signed, true).unwrap();
+        let result = state.apply(&info, &engine, &signed, true).unwrap();
         println!("{:?}", state.code(&contract_address).unwrap().unwrap());
         println!("{:?}", result.trace);
         assert_eq!(state.code(&contract_address).unwrap().unwrap(),
@@ -1673,6 +2010,102 @@ mod tests {
         assert_eq!(state.code(&a).unwrap(), Some(Arc::new([1u8, 2, 3].to_vec())));
     }
 
+    #[test]
+    fn code_shared_by_accounts() {
+        let (a, b) = (Address::from(1), Address::from(2));
+        let code = vec![1u8, 2, 3];
+        let code_key = Factories::default().accountdb.code_key(&code.crypt_hash());
+        let refs = |db: &StateDB| db.journal_db().backing().get(::db::COL_STATE, &code_refs_key(&code_key)).unwrap().map(|v| ::rlp::decode::<u64>(&v));
+        let journal = |state: State<StateDB>, era: u64| {
+            let (root, mut db) = state.drop();
+            let mut batch = db.journal_db().backing().transaction();
+            db.journal_under(&mut batch, era, &root).unwrap();
+            db.journal_db().backing().write(batch).unwrap();
+            (root, db)
+        };
+
+        let mut state = get_temp_state();
+        state.new_contract(&a, U256::zero());
+        state.init_code(&a, code.clone()).unwrap();
+        state.new_contract(&b, U256::zero());
+        state.init_code(&b, code.clone()).unwrap();
+        state.commit().unwrap();
+        let (root, db) = journal(state, 1);
+        assert_eq!(refs(&db), Some(2));
+        assert!(db.journal_db().backing().get(::db::COL_STATE, &code_key).unwrap().is_some());
+
+        let mut state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+        assert_eq!(state.code(&a).unwrap(), Some(Arc::new(code.clone())));
+        state.kill_account(&a);
+        state.commit().unwrap();
+        let (root, db) = journal(state, 2);
+        assert_eq!(refs(&db), Some(1));
+
+        let mut state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+        assert_eq!(state.code(&b).unwrap(), Some(Arc::new(code.clone())));
+        state.kill_account(&b);
+        state.commit().unwrap();
+        let (_, db) = journal(state, 3);
+        assert_eq!(refs(&db), None);
+    }
+
+    #[test]
+    fn code_refs_counted_when_canonical() {
+        let (a, b) = (Address::from(1), Address::from(2));
+        let code = vec![1u8, 2, 3];
+        let code_key = Factories::default().accountdb.code_key(&code.crypt_hash());
+        let refs = |db: &StateDB| db.journal_db().backing().get(::db::COL_STATE, &code_refs_key(&code_key)).unwrap().map(|v| ::rlp::decode::<u64>(&v));
+        let journal = |state: State<StateDB>, era: u64| {
+            let (root, mut db) = state.drop();
+            let mut batch = db.journal_db().backing().transaction();
+            db.journal_under(&mut batch, era, &root).unwrap();
+            db.journal_db().backing().write(batch).unwrap();
+            (root, db)
+        };
+        let canonical = |db: &mut StateDB, era: u64, root: &H256| {
+            let mut batch = db.journal_db().backing().transaction();
+            db.mark_canonical(&mut batch, era, root).unwrap();
+            db.journal_db().backing().write(batch).unwrap();
+        };
+        let journal_db = ::journaldb::new(Arc::new(::util::kvdb::in_memory(8)), ::journaldb::Algorithm::OverlayRecent, ::db::COL_STATE);
+
+        let mut state = State::new(StateDB::new(journal_db, 1024), 0.into(), Default::default());
+        state.new_contract(&a, U256::zero());
+        state.init_code(&a, code.clone()).unwrap();
+        state.new_contract(&b, U256::zero());
+        state.init_code(&b, code.clone()).unwrap();
+        state.commit().unwrap();
+        let (root, mut db) = journal(state, 1);
+        assert_eq!(refs(&db), None);
+        canonical(&mut db, 1, &root);
+        assert_eq!(refs(&db), Some(2));
+
+        // two states of the era, the one killing both accounts is replaced
+        let mut state = State::from_existing(db.boxed_clone(), root, U256::from(0u8), Default::default()).unwrap();
+        state.kill_account(&a);
+        state.kill_account(&b);
+        state.commit().unwrap();
+        let _ = journal(state, 2);
+        let mut state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+        state.kill_account(&a);
+        state.commit().unwrap();
+        let (root, mut db) = journal(state, 2);
+        assert_eq!(refs(&db), Some(2));
+        canonical(&mut db, 2, &root);
+        assert_eq!(refs(&db), Some(1));
+        assert!(db.journal_db().backing().get(::db::COL_STATE, &code_key).unwrap().is_some());
+
+        let mut state = State::from_existing(db, root, U256::from(0u8), Default::default()).unwrap();
+        assert_eq!(state.code(&b).unwrap(), Some(Arc::new(code.clone())));
+        state.kill_account(&b);
+        state.commit().unwrap();
+        let (root, mut db) = journal(state, 3);
+        canonical(&mut db, 3, &root);
+        assert_eq!(refs(&db), None);
+        assert!(db.journal_db().backing().get(::db::COL_STATE, &code_key).unwrap().is_none());
+        assert!(db.journal_db().backing().iter_from_prefix(::db::COL_STATE, b"code_journal").take_while(|&(ref key, _)| key.starts_with(b"code_journal")).next().is_none());
+    }
+
     #[test]
     fn storage_at_from_database() {
         let a = Address::zero();
@@ -1687,6 +2120,43 @@ mod tests {
         assert_eq!(s.storage_at(&a, &H256::from(1u64)).unwrap(), H256::from(69u64));
     }
 
+    #[test]
+    fn account_cache_follows_committed_state() {
+        let a = Address::zero();
+        let (root1, mut db) = {
+            let mut state = get_temp_state();
+            state.set_storage(&a, H256::from(1u64), H256::from(69u64)).unwrap();
+            state.commit().unwrap();
+            state.drop()
+        };
+        db.sync_cache(&root1);
+        assert!(db.boxed_clone_canon(&root1).get_cached_account(&a).is_some());
+        // not used by a state at an unknown root
+        assert!(db.boxed_clone().get_cached_account(&a).is_none());
+
+        let root2 = {
+            let mut state = State::from_existing(db.boxed_clone_canon(&root1), root1, U256::from(0u8), Default::default()).unwrap();
+            assert_eq!(state.storage_at(&a, &H256::from(1u64)).unwrap(), H256::from(69u64));
+            state.checkpoint();
+            state.set_storage(&a, H256::from(1u64), H256::from(70u64)).unwrap();
+            state.revert_to_checkpoint();
+            state.set_storage(&a, H256::from(2u64), H256::from(5u64)).unwrap();
+            state.commit().unwrap();
+            let (root2, mut state_db) = state.drop();
+            state_db.sync_cache(&root2);
+            root2
+        };
+
+        // the reverted change is not cached, the committed one is
+        let state = State::from_existing(db.boxed_clone_canon(&root2), root2, U256::from(0u8), Default::default()).unwrap();
+        assert_eq!(state.storage_at(&a, &H256::from(1u64)).unwrap(), H256::from(69u64));
+        assert_eq!(state.storage_at(&a, &H256::from(2u64)).unwrap(), H256::from(5u64));
+
+        // and the states at the older roots do not use it
+        let state = State::from_existing(db.boxed_clone_canon(&root1), root1, U256::from(0u8), Default::default()).unwrap();
+        assert_eq!(state.storage_at(&a, &H256::from(2u64)).unwrap(), H256::new());
+    }
+
     #[test]
     fn get_from_database() {
         let a = Address::zero();
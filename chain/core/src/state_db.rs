@@ -15,33 +15,209 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use db::COL_STATE;
+use lru_cache::LruCache;
+use rlp::{self, RlpStream, UntrustedRlp};
+use state::Account;
 use state::backend::*;
-use util::{JournalDB, DBTransaction, H256, UtilError, HashDB};
+use std::collections::HashMap;
+use std::sync::Arc;
+use util::{JournalDB, DBTransaction, H256, UtilError, HashDB, Address, Mutex};
+
+const CODE_REFS_PREFIX: &'static [u8] = b"code_refs";
+const CODE_JOURNAL_PREFIX: &'static [u8] = b"code_journal";
+
+/// Key of the number of accounts using the shared code kept at `code_key`.
+pub fn code_refs_key(code_key: &H256) -> Vec<u8> {
+    let mut key = CODE_REFS_PREFIX.to_vec();
+    key.extend_from_slice(code_key);
+    key
+}
+
+/// Prefix of the changes of the code references journaled in the era.
+fn code_journal_prefix(era: u64) -> Vec<u8> {
+    let mut key = CODE_JOURNAL_PREFIX.to_vec();
+    for i in (0..8).rev() {
+        key.push((era >> (i * 8)) as u8);
+    }
+    key
+}
+
+/// Key of the changes of the code references made by the state `id` journaled in the era.
+fn code_journal_key(era: u64, id: &H256) -> Vec<u8> {
+    let mut key = code_journal_prefix(era);
+    key.extend_from_slice(id);
+    key
+}
+
+/// Accounts of the latest committed state, shared by the states opened at it,
+/// so the accounts and storage used by consecutive blocks skip the trie lookups.
+struct AccountCache {
+    /// `None` for an account known not to exist. The storage read or written
+    /// through an account is kept in its own storage cache.
+    accounts: LruCache<Address, Option<Account>>,
+    /// State root the cached accounts are taken at.
+    root: H256,
+}
+
+/// An account loaded or committed by a state, waiting for `sync_cache`.
+struct CacheQueueItem {
+    address: Address,
+    account: Option<Account>,
+    /// Modified by the state, so it overwrites the cached account.
+    modified: bool,
+}
 
 pub struct StateDB {
     /// Backing database.
     db: Box<JournalDB>,
+    /// Shared account cache.
+    account_cache: Arc<Mutex<AccountCache>>,
+    /// Accounts of the local state to be put in the shared cache,
+    /// in a mutex only to keep the db `Sync`, which accounts are not.
+    local_cache: Mutex<Vec<CacheQueueItem>>,
+    /// State root the db is opened at, the shared cache is only read when it is the same.
+    parent_root: Option<H256>,
+    /// Changes of the number of accounts using each shared code, written with the state.
+    code_refs: HashMap<H256, i64>,
 }
 
 impl StateDB {
-    pub fn new(db: Box<JournalDB>) -> StateDB {
-        StateDB { db: db }
+    /// Create a state db keeping at most `cache_size` accounts of the latest state in memory.
+    pub fn new(db: Box<JournalDB>, cache_size: usize) -> StateDB {
+        StateDB {
+            db: db,
+            account_cache: Arc::new(Mutex::new(AccountCache {
+                                                   accounts: LruCache::new(cache_size),
+                                                   root: H256::default(),
+                                               })),
+            local_cache: Mutex::new(Vec::new()),
+            parent_root: None,
+            code_refs: HashMap::new(),
+        }
     }
 
-    /// Clone the database.
+    /// Clone the database, without the shared cache.
     pub fn boxed_clone(&self) -> StateDB {
-        StateDB { db: self.db.boxed_clone() }
+        StateDB {
+            db: self.db.boxed_clone(),
+            account_cache: self.account_cache.clone(),
+            local_cache: Mutex::new(Vec::new()),
+            parent_root: None,
+            code_refs: HashMap::new(),
+        }
+    }
+
+    /// Clone the database for the state at `root`, using the shared cache if it is taken at the same root.
+    pub fn boxed_clone_canon(&self, root: &H256) -> StateDB {
+        StateDB {
+            db: self.db.boxed_clone(),
+            account_cache: self.account_cache.clone(),
+            local_cache: Mutex::new(Vec::new()),
+            parent_root: Some(*root),
+            code_refs: HashMap::new(),
+        }
     }
 
     /// Journal all recent operations under the given era and ID.
+    /// The changes of the code references are journaled with them when the states are pruned,
+    /// and counted once the state is canonical, so those of the states replaced are dropped.
     pub fn journal_under(&mut self, batch: &mut DBTransaction, now: u64, id: &H256) -> Result<u32, UtilError> {
+        let changes: Vec<(H256, i64)> = self.code_refs.drain().filter(|&(_, delta)| delta != 0).collect();
+        if self.db.is_pruned() {
+            if !changes.is_empty() {
+                let mut s = RlpStream::new_list(changes.len());
+                for &(ref code_key, delta) in &changes {
+                    s.begin_list(3).append(code_key).append(&(delta.abs() as u64)).append(&(delta < 0));
+                }
+                batch.put(COL_STATE, &code_journal_key(now, id), &s.out());
+            }
+        } else {
+            // nothing is pruned, the code stays even when no account uses it
+            self.count_code_refs(batch, &changes, false)?;
+        }
         self.db.journal_under(batch, now, id)
     }
 
+    /// Write the number of accounts using the shared codes changed by a state,
+    /// removing the codes no more used if `prune`.
+    fn count_code_refs(&self, batch: &mut DBTransaction, changes: &[(H256, i64)], prune: bool) -> Result<(), UtilError> {
+        for &(ref code_key, delta) in changes {
+            let key = code_refs_key(code_key);
+            let refs = match self.db.backing().get(COL_STATE, &key)? {
+                Some(value) => UntrustedRlp::new(&value).as_val::<u64>()?,
+                None => 0,
+            };
+            let refs = refs as i64 + delta;
+            if refs > 0 {
+                batch.put(COL_STATE, &key, &rlp::encode(&(refs as u64)));
+            } else {
+                batch.delete(COL_STATE, &key);
+                if prune {
+                    batch.delete(COL_STATE, code_key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark the state of the given era and ID canonical, pruning the replaced nodes.
+    /// The code references journaled by it are counted, and a code no more used is removed.
+    /// A later state using the code again has it inserted in its journal, which restores it.
+    pub fn mark_canonical(&mut self, batch: &mut DBTransaction, era: u64, id: &H256) -> Result<u32, UtilError> {
+        // the code inserted by the canonical state is written first, so it can be removed after
+        let ops = self.db.mark_canonical(batch, era, id)?;
+        let prefix = code_journal_prefix(era);
+        let journaled: Vec<(Box<[u8]>, Box<[u8]>)> = self.db.backing().iter_from_prefix(COL_STATE, &prefix).take_while(|&(ref key, _)| key.starts_with(&prefix)).collect();
+        for (key, value) in journaled {
+            batch.delete(COL_STATE, &key);
+            if &key[prefix.len()..] != &id[..] {
+                continue;
+            }
+            let mut changes = Vec::new();
+            for change in UntrustedRlp::new(&value).iter() {
+                let code_key: H256 = change.val_at(0)?;
+                let delta = change.val_at::<u64>(1)? as i64;
+                let removed: bool = change.val_at(2)?;
+                changes.push((code_key, if removed { -delta } else { delta }));
+            }
+            self.count_code_refs(batch, &changes, true)?;
+        }
+        Ok(ops)
+    }
+
     /// Returns underlying `JournalDB`.
     pub fn journal_db(&self) -> &JournalDB {
         &*self.db
     }
+
+    /// Move the accounts of the local state, which has been committed at `root`, into the shared cache.
+    /// The accounts not modified by the state are the same at its parent, so they are kept
+    /// even if the shared cache has to be dropped, which happens when it was not taken at the parent.
+    pub fn sync_cache(&mut self, root: &H256) {
+        let mut cache = self.account_cache.lock();
+        if self.parent_root != Some(cache.root) {
+            cache.accounts.clear();
+        }
+        for item in self.local_cache.get_mut().drain(..) {
+            if !item.modified && cache.accounts.contains_key(&item.address) {
+                continue;
+            }
+            let account = match (cache.accounts.get_mut(&item.address), item.account) {
+                (Some(&mut Some(ref mut existing)), Some(account)) => {
+                    existing.update_cached(account);
+                    continue;
+                }
+                (_, account) => account,
+            };
+            cache.accounts.insert(item.address, account);
+        }
+        cache.root = *root;
+    }
+
+    fn is_cache_valid(&self, cache: &AccountCache) -> bool {
+        self.parent_root == Some(cache.root)
+    }
 }
 
 impl Backend for StateDB {
@@ -52,4 +228,35 @@ impl Backend for StateDB {
     fn as_hashdb_mut(&mut self) -> &mut HashDB {
         self.db.as_hashdb_mut()
     }
+
+    fn add_to_account_cache(&mut self, address: Address, account: Option<Account>, modified: bool) {
+        self.local_cache.get_mut().push(CacheQueueItem {
+                                  address: address,
+                                  account: account,
+                                  modified: modified,
+                              });
+    }
+
+    fn get_cached_account(&self, address: &Address) -> Option<Option<Account>> {
+        let mut cache = self.account_cache.lock();
+        if !self.is_cache_valid(&cache) {
+            return None;
+        }
+        cache.accounts.get_mut(address).map(|a| a.as_ref().map(Account::clone_basic))
+    }
+
+    fn get_cached<F, U>(&self, address: &Address, f: F) -> Option<U>
+    where
+        F: FnOnce(Option<&mut Account>) -> U,
+    {
+        let mut cache = self.account_cache.lock();
+        if !self.is_cache_valid(&cache) {
+            return None;
+        }
+        cache.accounts.get_mut(address).map(|a| f(a.as_mut()))
+    }
+
+    fn note_code_refs(&mut self, code_key: H256, delta: i64) {
+        *self.code_refs.entry(code_key).or_insert(0) += delta;
+    }
 }
@@ -33,5 +33,5 @@ fn new_db() -> Arc<KeyValueDB> {
 pub fn get_temp_state_db() -> StateDB {
     let db = new_db();
     let journal_db = journaldb::new(db, journaldb::Algorithm::Archive, ::db::COL_STATE);
-    StateDB::new(journal_db)
+    StateDB::new(journal_db, 1024)
 }
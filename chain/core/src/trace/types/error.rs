@@ -35,6 +35,16 @@ pub enum Error {
     StackUnderflow,
     /// When execution would exceed defined Stack Limit
     OutOfStack,
+    /// When a state changing instruction is executed in the context of `STATICCALL`
+    MutableCallInStaticContext,
+    /// When `RETURNDATACOPY` reads past the end of the return data
+    OutOfBounds,
+    /// When the execution ended with `REVERT`
+    Reverted,
+    /// When the storage of the called contract has expired
+    StorageEvicted,
+    /// When an account other than the admins changes the parameters of the chain
+    Unauthorized,
     /// Returned on evm internal error. Should never be ignored during development.
     /// Likely to cause consensus issues.
     Internal,
@@ -48,6 +58,11 @@ impl<'a> From<&'a EvmError> for Error {
             EvmError::BadInstruction { .. } => Error::BadInstruction,
             EvmError::StackUnderflow { .. } => Error::StackUnderflow,
             EvmError::OutOfStack { .. } => Error::OutOfStack,
+            EvmError::MutableCallInStaticContext => Error::MutableCallInStaticContext,
+            EvmError::OutOfBounds => Error::OutOfBounds,
+            EvmError::Reverted => Error::Reverted,
+            EvmError::StorageEvicted => Error::StorageEvicted,
+            EvmError::Unauthorized => Error::Unauthorized,
             EvmError::Internal(_) => Error::Internal,
         }
     }
@@ -68,6 +83,11 @@ impl fmt::Display for Error {
             BadInstruction => "Bad instruction",
             StackUnderflow => "Stack underflow",
             OutOfStack => "Out of stack",
+            MutableCallInStaticContext => "Mutable call in static context",
+            OutOfBounds => "Out of bounds",
+            Reverted => "Reverted",
+            StorageEvicted => "Storage evicted",
+            Unauthorized => "Unauthorized",
             Internal => "Internal error",
         };
         message.fmt(f)
@@ -84,6 +104,11 @@ impl Encodable for Error {
             StackUnderflow => 3,
             OutOfStack => 4,
             Internal => 5,
+            MutableCallInStaticContext => 6,
+            OutOfBounds => 7,
+            Reverted => 8,
+            StorageEvicted => 9,
+            Unauthorized => 10,
         };
 
         s.append_internal(&value);
@@ -101,6 +126,11 @@ impl Decodable for Error {
             3 => Ok(StackUnderflow),
             4 => Ok(OutOfStack),
             5 => Ok(Internal),
+            6 => Ok(MutableCallInStaticContext),
+            7 => Ok(OutOfBounds),
+            8 => Ok(Reverted),
+            9 => Ok(StorageEvicted),
+            10 => Ok(Unauthorized),
             _ => Err(DecoderError::Custom("Invalid error type")),
         }
     }
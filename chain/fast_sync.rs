@@ -0,0 +1,367 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fast sync of a fresh node: the state and the recent blocks are restored from
+//! the snapshot of a peer, then the blocks after it are synced one by one.
+//!
+//! The manifest of the snapshot is trusted once the block after its block is
+//! committed by the validators, the commit proof being in the block after that.
+//! The chunks are then checked against the manifest by their hashes, and the
+//! rebuilt state against the state root of the block.
+
+use byteorder::{BigEndian, ByteOrder};
+use core::libchain::Config;
+use core::libchain::block::Block;
+use core::libchain::chain::Chain;
+use core::libchain::snapshot::{self, ManifestData, Restoration};
+use libproto::*;
+use libproto::blockchain::Block as ProtoBlock;
+use protobuf::Message;
+use serde_json;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use util::{H256, Hashable, Mutex};
+use util::journaldb::Algorithm;
+
+/// Peers ahead by fewer blocks are synced block by block.
+const FAST_SYNC_THRESHOLD: u64 = 1000;
+/// Chunks requested at the same time.
+const CHUNKS_IN_FLIGHT: usize = 4;
+/// Seconds to wait for the answer of the peer.
+const REQUEST_TIMEOUT: u64 = 30;
+/// Timeouts or bad snapshots tolerated before syncing block by block.
+const MAX_RETRIES: usize = 3;
+
+enum Phase {
+    Idle,
+    /// Waiting for the manifest of the peer.
+    Manifest,
+    /// Waiting for the two blocks after the manifest block, which prove it.
+    Proof {
+        manifest: ManifestData,
+        next: Option<Block>,
+        commit: Option<Block>,
+    },
+    /// Downloading the chunks, `pending` ones are not requested yet.
+    Chunks {
+        restoration: Restoration,
+        pending: Vec<H256>,
+        requested: HashSet<H256>,
+    },
+    /// Restored, or given up.
+    Done,
+}
+
+struct State {
+    phase: Phase,
+    peer: u32,
+    since: Instant,
+    retries: usize,
+}
+
+pub struct FastSync {
+    chain: Arc<Chain>,
+    enabled: bool,
+    algorithm: Algorithm,
+    snapshot_dir: PathBuf,
+    snapshot_interval: u64,
+    // height of the snapshot served to the peers
+    snapshot_height: AtomicUsize,
+    state: Mutex<State>,
+}
+
+impl FastSync {
+    pub fn new(chain: Arc<Chain>, config: &Config, snapshot_dir: PathBuf) -> Self {
+        let snapshot_height = snapshot::read_manifest(&snapshot_dir).map(|manifest| manifest.height).unwrap_or(0);
+        FastSync {
+            chain: chain,
+            enabled: config.fast_sync,
+            algorithm: config.algorithm(),
+            snapshot_dir: snapshot_dir,
+            snapshot_interval: config.snapshot_interval,
+            snapshot_height: AtomicUsize::new(snapshot_height as usize),
+            state: Mutex::new(State {
+                                  phase: Phase::Idle,
+                                  peer: 0,
+                                  since: Instant::now(),
+                                  retries: 0,
+                              }),
+        }
+    }
+
+    /// Take a snapshot every `snapshot_interval` blocks, replacing the last one.
+    /// A pruning node has to read the state before the pruning history passes it.
+    pub fn snapshot(&self) {
+        let height = self.chain.get_current_height();
+        let last = self.snapshot_height.load(Ordering::SeqCst) as u64;
+        if self.snapshot_interval == 0 || height < last + self.snapshot_interval {
+            return;
+        }
+        let tmp_dir = self.snapshot_dir.with_extension("tmp");
+        let _ = fs::remove_dir_all(&tmp_dir);
+        let result = snapshot::take_snapshot(&self.chain, &tmp_dir).and_then(|manifest| {
+            let _ = fs::remove_dir_all(&self.snapshot_dir);
+            fs::rename(&tmp_dir, &self.snapshot_dir)?;
+            Ok(manifest)
+        });
+        match result {
+            Ok(manifest) => {
+                info!("snapshot at height {} is served", manifest.height);
+                self.snapshot_height.store(manifest.height as usize, Ordering::SeqCst);
+            }
+            Err(e) => warn!("failed to take snapshot: {}", e),
+        }
+    }
+
+    /// Start the fast sync from a peer far ahead. Returns whether a snapshot is
+    /// being restored, then the blocks are not to be synced one by one.
+    pub fn on_status(&self, origin: u32, height: u64, ctx_pub: &Sender<(String, Vec<u8>)>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        match state.phase {
+            Phase::Done => false,
+            Phase::Idle => {
+                if self.chain.get_current_height() > 0 || height < FAST_SYNC_THRESHOLD {
+                    return false;
+                }
+                info!("fast sync from node-{} at height {}", origin, height);
+                state.peer = origin;
+                state.since = Instant::now();
+                state.phase = Phase::Manifest;
+                send(ctx_pub, origin, topics::SNAPSHOT_REQ, Vec::new());
+                true
+            }
+            _ => {
+                if state.since.elapsed() > Duration::from_secs(REQUEST_TIMEOUT) {
+                    warn!("fast sync from node-{} timed out", state.peer);
+                    self.retry(state, ctx_pub);
+                }
+                true
+            }
+        }
+    }
+
+    /// Take the blocks proving the manifest, returns whether the block is consumed.
+    pub fn on_block(&self, block: &ProtoBlock, ctx_pub: &Sender<(String, Vec<u8>)>) -> bool {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let manifest = match state.phase {
+            Phase::Proof { ref manifest, ref mut next, ref mut commit } => {
+                let height = block.get_header().get_height();
+                if height == manifest.height + 1 {
+                    *next = Some(Block::from(block.clone()));
+                } else if height == manifest.height + 2 {
+                    *commit = Some(Block::from(block.clone()));
+                } else {
+                    return false;
+                }
+                match (next.as_ref(), commit.as_ref()) {
+                    (Some(next), Some(commit)) => {
+                        let authorities = self.chain.authorities_at(manifest.height + 1);
+                        if !snapshot::verify_manifest(manifest, next, commit, authorities) {
+                            None
+                        } else {
+                            Some(manifest.clone())
+                        }
+                    }
+                    _ => return true,
+                }
+            }
+            _ => return false,
+        };
+
+        match manifest {
+            Some(manifest) => {
+                info!("snapshot at height {} is proven, {} state chunks and {} block chunks", manifest.height, manifest.state_hashes.len(), manifest.block_hashes.len());
+                let pending = manifest.state_hashes.iter().chain(manifest.block_hashes.iter()).rev().cloned().collect();
+                match Restoration::new(self.chain.db.clone(), self.algorithm, manifest) {
+                    Ok(restoration) => {
+                        state.since = Instant::now();
+                        state.phase = Phase::Chunks {
+                            restoration: restoration,
+                            pending: pending,
+                            requested: HashSet::new(),
+                        };
+                        self.request_chunks(state, ctx_pub);
+                    }
+                    Err(e) => {
+                        warn!("fast sync is not possible: {}", e);
+                        state.phase = Phase::Done;
+                    }
+                }
+            }
+            None => {
+                warn!("snapshot of node-{} is not proven", state.peer);
+                self.retry(state, ctx_pub);
+            }
+        }
+        true
+    }
+
+    /// Serve the snapshot to the peers and take the answers of them, returns
+    /// whether the message is one of the fast sync.
+    pub fn on_message(&self, cmd_id: u32, origin: u32, content: &[u8], ctx_pub: &Sender<(String, Vec<u8>)>) -> bool {
+        if cmd_id == libproto::cmd_id(submodules::CHAIN, topics::SNAPSHOT_REQ) {
+            let mut manifest = Vec::new();
+            if let Ok(mut file) = File::open(self.snapshot_dir.join(snapshot::MANIFEST_FILE)) {
+                if file.read_to_end(&mut manifest).is_err() {
+                    manifest.clear();
+                }
+            }
+            send(ctx_pub, origin, topics::SNAPSHOT_MANIFEST, manifest);
+        } else if cmd_id == libproto::cmd_id(submodules::CHAIN, topics::SNAPSHOT_CHUNK_REQ) {
+            if content.len() == 32 {
+                let chunk = snapshot::read_chunk(&self.snapshot_dir, &H256::from_slice(content)).unwrap_or_default();
+                send(ctx_pub, origin, topics::SNAPSHOT_CHUNK, chunk);
+            }
+        } else if cmd_id == libproto::cmd_id(submodules::CHAIN, topics::SNAPSHOT_MANIFEST) {
+            self.on_manifest(origin, content, ctx_pub);
+        } else if cmd_id == libproto::cmd_id(submodules::CHAIN, topics::SNAPSHOT_CHUNK) {
+            self.on_chunk(origin, content, ctx_pub);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn on_manifest(&self, origin: u32, content: &[u8], ctx_pub: &Sender<(String, Vec<u8>)>) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        match state.phase {
+            Phase::Manifest if state.peer == origin => {}
+            _ => return,
+        }
+        match serde_json::from_slice::<ManifestData>(content) {
+            Ok(manifest) => {
+                info!("snapshot of node-{} at height {}", origin, manifest.height);
+                for height in manifest.height + 1..manifest.height + 3 {
+                    let mut wtr = vec![0; 8];
+                    BigEndian::write_u64(&mut wtr, height);
+                    send(ctx_pub, origin, topics::SYNC_BLK, wtr);
+                }
+                state.since = Instant::now();
+                state.phase = Phase::Proof {
+                    manifest: manifest,
+                    next: None,
+                    commit: None,
+                };
+            }
+            Err(_) => {
+                warn!("node-{} has no snapshot", origin);
+                self.retry(state, ctx_pub);
+            }
+        }
+    }
+
+    fn on_chunk(&self, origin: u32, content: &[u8], ctx_pub: &Sender<(String, Vec<u8>)>) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        if state.peer != origin {
+            return;
+        }
+        let hash = content.crypt_hash();
+        let result = match state.phase {
+            Phase::Chunks { ref mut restoration, ref mut requested, .. } => {
+                if !requested.remove(&hash) {
+                    return;
+                }
+                if restoration.manifest().state_hashes.contains(&hash) {
+                    restoration.feed_state(&hash, content)
+                } else {
+                    restoration.feed_blocks(&hash, content)
+                }
+            }
+            _ => return,
+        };
+        if let Err(e) = result {
+            warn!("invalid snapshot chunk from node-{}: {}, sync the blocks one by one", origin, e);
+            state.phase = Phase::Done;
+            return;
+        }
+        state.since = Instant::now();
+        self.request_chunks(state, ctx_pub);
+    }
+
+    /// Keep `CHUNKS_IN_FLIGHT` chunks requested, finalizing the restoration after all.
+    fn request_chunks(&self, state: &mut State, ctx_pub: &Sender<(String, Vec<u8>)>) {
+        let done = match state.phase {
+            Phase::Chunks { ref mut pending, ref mut requested, .. } => {
+                while requested.len() < CHUNKS_IN_FLIGHT {
+                    match pending.pop() {
+                        Some(hash) => {
+                            send(ctx_pub, state.peer, topics::SNAPSHOT_CHUNK_REQ, hash.to_vec());
+                            requested.insert(hash);
+                        }
+                        None => break,
+                    }
+                }
+                requested.is_empty()
+            }
+            _ => false,
+        };
+        if !done {
+            return;
+        }
+        if let Phase::Chunks { restoration, .. } = mem::replace(&mut state.phase, Phase::Done) {
+            match restoration.finalize() {
+                Ok(()) => {
+                    let status = self.chain.reload();
+                    info!("fast sync done at height {}", status.get_height());
+                    let msg = factory::create_msg(submodules::CHAIN, topics::NEW_STATUS, communication::MsgType::STATUS, status.write_to_bytes().unwrap());
+                    ctx_pub.send(("chain.status".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                }
+                Err(e) => warn!("failed to restore snapshot: {}, sync the blocks one by one", e),
+            }
+        }
+    }
+
+    /// Ask the peer again for the requested chunks, or start over from another peer.
+    fn retry(&self, state: &mut State, ctx_pub: &Sender<(String, Vec<u8>)>) {
+        state.retries += 1;
+        if state.retries > MAX_RETRIES {
+            warn!("fast sync given up, sync the blocks one by one");
+            state.phase = Phase::Done;
+            return;
+        }
+        state.since = Instant::now();
+        let requested: Vec<H256> = match state.phase {
+            Phase::Chunks { ref requested, .. } => requested.iter().cloned().collect(),
+            _ => Vec::new(),
+        };
+        if requested.is_empty() {
+            state.phase = Phase::Idle;
+        }
+        for hash in requested {
+            send(ctx_pub, state.peer, topics::SNAPSHOT_CHUNK_REQ, hash.to_vec());
+        }
+    }
+}
+
+fn send(ctx_pub: &Sender<(String, Vec<u8>)>, origin: u32, topic: u16, content: Vec<u8>) {
+    let msg = factory::create_msg_ex(submodules::CHAIN, topic, communication::MsgType::MSG, communication::OperateType::SINGLE, origin, content);
+    ctx_pub.send(("chain.sync".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+}
@@ -18,14 +18,21 @@
 #![allow(unused_variables)]
 
 pub use byteorder::{BigEndian, ByteOrder};
+use core::executed::{CallError, Executed};
 use core::filters::eth_filter::EthFilter;
 use core::libchain::call_request::CallRequest;
+use fast_sync::FastSync;
 pub use core::libchain::chain::*;
+use jsonrpc_types::bytes::Bytes as RpcBytes;
 use jsonrpc_types::rpctypes;
-use jsonrpc_types::rpctypes::{Filter as RpcFilter, Log as RpcLog, Receipt as RpcReceipt, CountAndCode, BlockNumber, BlockParamsByNumber, BlockParamsByHash, RpcBlock};
+use jsonrpc_types::response::{StorageValue, TransactionTrace};
+use jsonrpc_types::rpctypes::{Filter as RpcFilter, Log as RpcLog, Receipt as RpcReceipt, CountAndCode, AccountTransactions, StorageHistory, SimulateTransaction, BlockNumber, BlockParamsByNumber, BlockParamsByHash, RpcBlock, StateDiff as RpcStateDiff};
 use libproto;
 pub use libproto::*;
+use libproto::shutdown::Shutdown;
+use pubsub::retry::Retry;
 use protobuf::Message;
+use protobuf::core::parse_from_bytes;
 pub use libproto::request::Request_oneof_req as Request;
 use serde_json;
 use std::sync::Arc;
@@ -33,26 +40,92 @@ use std::sync::atomic::Ordering;
 use std::sync::mpsc::{Sender, Receiver};
 use std::vec::Vec;
 use threadpool::*;
+use types::call_analytics::CallAnalytics;
 use types::filter::Filter;
 use types::ids::BlockId;
+use types::transaction::SignedTransaction;
 use util::Address;
 use util::H256;
+use util::U256;
 // pub const CHAIN_PUB: u32 = 3;
+// Transactions returned at most for an account at a time.
+const ACCOUNT_TRANSACTIONS_LIMIT: usize = 1000;
+// Blocks returned at most for the history of a storage key at a time.
+const STORAGE_HISTORY_LIMIT: usize = 1000;
 
-pub fn chain_pool(pool: &ThreadPool, tx: &Sender<(u32, u32, u32, MsgClass)>, id: u32, msg: Vec<u8>) {
+// Transactions executed again by the debug methods only diff the state.
+const DEBUG_ANALYTICS: CallAnalytics = CallAnalytics {
+    transaction_tracing: false,
+    vm_tracing: false,
+    state_diffing: true,
+};
+
+/// A message of the bus parsed, with its key and its bytes to retry it.
+pub type Parsed = (String, Vec<u8>, u32, u32, u32, MsgClass);
+
+pub fn chain_pool(pool: &ThreadPool, tx: &Sender<Parsed>, key: String, id: u32, msg: Vec<u8>) {
     let tx = tx.clone();
     pool.execute(move || {
-                     let (cmd_id, origin, content) = parse_msg(msg.as_slice());
-                     tx.send((id, cmd_id, origin, content)).unwrap();
+                     let (cmd_id, origin, content) = match parse_msg(msg.as_slice()) {
+                         Ok(parsed) => parsed,
+                         Err(reason) => {
+                             warn!("drop msg of {}: {}", key, reason);
+                             return;
+                         }
+                     };
+                     tx.send((key, msg, id, cmd_id, origin, content)).unwrap();
                  });
 }
 
-// TODO: RPC Errors
-pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>, ctx_pub: Sender<(String, Vec<u8>)>) {
-    let (id, cmd_id, origin, content_ext) = rx.recv().unwrap();
+pub fn chain_result(chain: Arc<Chain>, fast_sync: &FastSync, shutdown: &Shutdown, retry: &Retry, rx: &Receiver<Parsed>, ctx_pub: Sender<(String, Vec<u8>)>) {
+    let (key, msg, id, cmd_id, origin, content_ext) = rx.recv().unwrap();
     trace!("chain_result call {:?} {:?}", id, cmd_id);
+    if shutdown.handle_ack(cmd_id) {
+        return;
+    }
+    // a shutdown waits for the block in import
+    let _work = shutdown.working();
+    // the content is parsed again for a retry
+    let mut content_ext = Some(content_ext);
+    retry.handle(&key, &msg, |msg| {
+        let content = match content_ext.take() {
+            Some(content) => content,
+            None => parse_msg(msg)?.2,
+        };
+        handle_msg(&chain, fast_sync, id, cmd_id, origin, content, &ctx_pub)
+    });
+}
+
+// Publish the message under the key, failing while the publisher is gone.
+fn publish(ctx_pub: &Sender<(String, Vec<u8>)>, key: &str, msg: &communication::Message) -> Result<(), String> {
+    let body = msg.write_to_bytes().map_err(|e| format!("failed to encode the message for {}: {}", key, e))?;
+    ctx_pub.send((key.to_string(), body)).map_err(|_| format!("failed to publish under {}", key))
+}
+
+// Answer a debug execution with its outcome, or the reason it could not run.
+fn set_transaction_trace(response: &mut request::Response, result: Result<Executed, CallError>) {
+    match result {
+        Ok(executed) => {
+            let trace = TransactionTrace {
+                gas_used: executed.gas_used,
+                cumulative_gas_used: executed.cumulative_gas_used,
+                output: RpcBytes::from(executed.output),
+                exception: executed.exception.map(|err| err.to_string()),
+                contracts_created: executed.contracts_created,
+                state_diff: executed.state_diff.map(RpcStateDiff::from),
+            };
+            response.set_transaction_trace(serde_json::to_string(&trace).unwrap());
+        }
+        Err(err) => response.set_error_msg(err.to_string()),
+    }
+}
+
+// TODO: RPC Errors
+fn handle_msg(chain: &Arc<Chain>, fast_sync: &FastSync, id: u32, cmd_id: u32, origin: u32, content_ext: MsgClass, ctx_pub: &Sender<(String, Vec<u8>)>) -> Result<(), String> {
     match content_ext {
         MsgClass::REQUEST(mut req) => {
+            // the auth of consensus is answered apart from jsonrpc
+            let rpc_key = if id == submodules::CONSENSUS { "chain.auth" } else { "chain.rpc" };
             let mut response = request::Response::new();
             response.set_request_id(req.take_request_id());
             match req.req.clone().unwrap() {
@@ -62,7 +135,7 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     let height = chain.get_current_height();
                     response.set_block_number(height);
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::block_by_hash(rpc) => {
@@ -78,11 +151,14 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                                 .map_err(|_| response.set_none(true));
                         }
                         None => {
-                            response.set_none(true);
+                            match chain.block_pruned_error(BlockId::Hash(H256::from(hash.as_slice()))) {
+                                Some(err) => response.set_error_msg(err),
+                                None => response.set_none(true),
+                            }
                         }
                     }
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
 
                 }
 
@@ -90,7 +166,8 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     let block_height: BlockParamsByNumber = serde_json::from_str(&block_height).expect("Invalid param");
 
                     let include_txs = block_height.include_txs;
-                    match chain.block(block_height.block_id.into()) {
+                    let block_id: BlockId = block_height.block_id.into();
+                    match chain.block(block_id.clone()) {
                         Some(block) => {
                             let rpc_block = RpcBlock::new(block.hash().to_vec(), include_txs, block.protobuf().write_to_bytes().unwrap());
                             //TODO，发生错误了，应该加错原因给rpc,通知客户
@@ -99,11 +176,14 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                                 .map_err(|_| response.set_none(true));
                         }
                         None => {
-                            response.set_none(true);
+                            match chain.block_pruned_error(block_id) {
+                                Some(err) => response.set_error_msg(err),
+                                None => response.set_none(true),
+                            }
                         }
                     }
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
                 Request::transaction(hash) => {
                     match chain.full_transaction(H256::from_slice(&hash)) {
@@ -111,11 +191,14 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                             response.set_ts(ts);
                         }
                         None => {
-                            response.set_none(true);
+                            match chain.transaction_pruned_error() {
+                                Some(err) => response.set_error_msg(err),
+                                None => response.set_none(true),
+                            }
                         }
                     }
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
                 Request::transaction_receipt(hash) => {
                     let tx_hash = H256::from_slice(&hash);
@@ -124,33 +207,46 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                         let rpc_receipt: RpcReceipt = receipt.into();
                         let serialized = serde_json::to_string(&rpc_receipt).unwrap();
                         response.set_receipt(serialized);
+                    } else if let Some(err) = chain.transaction_pruned_error() {
+                        response.set_error_msg(err);
                     } else {
                         response.set_none(true);
                     }
 
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::call(call) => {
                     trace!("Chainvm Call {:?}", call);
                     let block_id: BlockNumber = serde_json::from_str(&(call.height)).expect("Invalid param");
+                    let block_id: BlockId = block_id.into();
                     let call_request = CallRequest::from(call);
-                    let result = chain.cita_call(call_request, block_id.into());
-                    response.set_call_result(result.unwrap_or_default());
+                    match chain.state_pruned_error(block_id.clone()) {
+                        Some(err) => response.set_error_msg(err),
+                        None => {
+                            let result = chain.cita_call(call_request, block_id);
+                            response.set_call_result(result.unwrap_or_default());
+                        }
+                    }
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::filter(encoded) => {
                     trace!("filter: {:?}", encoded);
                     let rpc_filter: RpcFilter = serde_json::from_str(&encoded).expect("Invalid filter");
                     let filter: Filter = rpc_filter.into();
-                    let logs = chain.get_logs(filter);
-                    let rpc_logs: Vec<RpcLog> = logs.into_iter().map(|x| x.into()).collect();
-                    response.set_logs(serde_json::to_string(&rpc_logs).unwrap());
+                    match chain.block_pruned_error(filter.from_block.clone()) {
+                        Some(err) => response.set_error_msg(err),
+                        None => {
+                            let logs = chain.get_logs(filter);
+                            let rpc_logs: Vec<RpcLog> = logs.into_iter().map(|x| x.into()).collect();
+                            response.set_logs(serde_json::to_string(&rpc_logs).unwrap());
+                        }
+                    }
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::transaction_count(tx_count) => {
@@ -158,16 +254,22 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     //TODO 或许有错误返回给用户更好
                     let tx_count: CountAndCode = serde_json::from_str(&tx_count).expect("Invalid param");
                     let address = Address::from_slice(tx_count.address.as_ref());
-                    match chain.nonce(&address, tx_count.block_id.into()) {
-                        Some(nonce) => {
-                            response.set_transaction_count(u64::from(nonce));
-                        }
+                    let block_id: BlockId = tx_count.block_id.into();
+                    match chain.state_pruned_error(block_id.clone()) {
+                        Some(err) => response.set_error_msg(err),
                         None => {
-                            response.set_none(true);
+                            match chain.nonce(&address, block_id) {
+                                Some(nonce) => {
+                                    response.set_transaction_count(u64::from(nonce));
+                                }
+                                None => {
+                                    response.set_none(true);
+                                }
+                            }
                         }
                     };
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
 
                 }
 
@@ -176,23 +278,22 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     let code_content: CountAndCode = serde_json::from_str(&code_content).expect("Invalid param");
 
                     let address = Address::from_slice(code_content.address.as_ref());
-                    match chain.code_at(&address, code_content.block_id.into()) {
-                        Some(code) => {
-                            match code {
-                                Some(code) => {
+                    let block_id: BlockId = code_content.block_id.into();
+                    match chain.state_pruned_error(block_id.clone()) {
+                        Some(err) => response.set_error_msg(err),
+                        None => {
+                            match chain.code_at(&address, block_id) {
+                                Some(Some(code)) => {
                                     response.set_code(code);
                                 }
-                                None => {
+                                _ => {
                                     response.set_none(true);
                                 }
                             }
                         }
-                        None => {
-                            response.set_none(true);
-                        }
                     };
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
 
                 }
 
@@ -202,14 +303,14 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     trace!("new_filter {:?}", new_filter);
                     response.set_filter_id(chain.new_filter(new_filter) as u64);
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::new_block_filter(_) => {
                     let block_filter = chain.new_block_filter();
                     response.set_filter_id(block_filter as u64);
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::uninstall_filter(filter_id) => {
@@ -218,7 +319,7 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     let b = chain.uninstall_filter(index);
                     response.set_uninstall_filter(b);
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::filter_changes(filter_id) => {
@@ -228,7 +329,7 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     trace!("Log is: {:?}", log);
                     response.set_filter_changes(serde_json::to_vec(&log).unwrap());
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 Request::filter_logs(filter_id) => {
@@ -238,7 +339,105 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     trace!("Log is: {:?}", log);
                     response.set_filter_logs(serde_json::to_vec(&log).unwrap());
                     let msg: communication::Message = response.into();
-                    ctx_pub.send(("chain.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::state_diff(height) => {
+                    trace!("state_diff request from jsonrpc {:?}", height);
+                    let block_id: BlockNumber = serde_json::from_str(&height).expect("Invalid param");
+                    let block_id: BlockId = block_id.into();
+                    match chain.state_diff(block_id.clone()) {
+                        Some(diff) => {
+                            let rpc_diff: RpcStateDiff = diff.into();
+                            response.set_state_diff(serde_json::to_string(&rpc_diff).unwrap());
+                        }
+                        None => {
+                            match chain.state_diff_error(block_id) {
+                                Some(err) => response.set_error_msg(err),
+                                None => response.set_none(true),
+                            }
+                        }
+                    }
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::transactions_by_account(param) => {
+                    trace!("transactions_by_account request from jsonrpc {:?}", param);
+                    let param: AccountTransactions = serde_json::from_str(&param).expect("Invalid param");
+                    let address = Address::from_slice(param.address.as_ref());
+                    let from = chain.block_number(param.from_block.into());
+                    let to = chain.block_number(param.to_block.into());
+                    match (from, to) {
+                        (Some(from), Some(to)) => {
+                            let hashes = chain.account_transactions(&address, from, to, ACCOUNT_TRANSACTIONS_LIMIT);
+                            response.set_transactions_by_account(serde_json::to_string(&hashes).unwrap());
+                        }
+                        _ => response.set_none(true),
+                    }
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::storage_history(param) => {
+                    trace!("storage_history request from jsonrpc {:?}", param);
+                    let param: StorageHistory = serde_json::from_str(&param).expect("Invalid param");
+                    let address = Address::from_slice(param.address.as_ref());
+                    let key = H256::from_slice(param.key.as_ref());
+                    let from_id: BlockId = param.from_block.into();
+                    match chain.state_pruned_error(from_id.clone()) {
+                        Some(err) => response.set_error_msg(err),
+                        None => {
+                            match (chain.block_number(from_id), chain.block_number(param.to_block.into())) {
+                                (Some(from), Some(to)) => {
+                                    let values: Vec<StorageValue> = chain
+                                        .storage_history(&address, &key, from, to, STORAGE_HISTORY_LIMIT)
+                                        .into_iter()
+                                        .map(|(number, value)| StorageValue { block_number: U256::from(number), value: value })
+                                        .collect();
+                                    response.set_storage_history(serde_json::to_string(&values).unwrap());
+                                }
+                                _ => response.set_none(true),
+                            }
+                        }
+                    }
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::light_client_proof(height) => {
+                    trace!("light_client_proof request from jsonrpc {:?}", height);
+                    match chain.light_client_proof(height) {
+                        Some(proof) => response.set_light_client_proof(proof),
+                        None => response.set_none(true),
+                    }
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::replay_transaction(hash) => {
+                    trace!("replay_transaction request from jsonrpc {:?}", hash);
+                    let result = chain.replay_transaction(H256::from_slice(&hash), DEBUG_ANALYTICS);
+                    set_transaction_trace(&mut response, result);
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
+                }
+
+                Request::simulate_transaction(param) => {
+                    trace!("simulate_transaction request from jsonrpc {:?}", param);
+                    let param: SimulateTransaction = serde_json::from_str(&param).expect("Invalid param");
+                    let transaction = parse_from_bytes::<blockchain::SignedTransaction>(&param.transaction)
+                        .map_err(|err| err.to_string())
+                        .and_then(|proto| SignedTransaction::new(&proto).map_err(|err| format!("{:?}", err)));
+                    match transaction {
+                        Ok(transaction) => {
+                            let result = chain.simulate_transaction(&transaction, param.block_id.into(), DEBUG_ANALYTICS);
+                            set_transaction_trace(&mut response, result);
+                        }
+                        Err(err) => response.set_error_msg(format!("invalid transaction: {}", err)),
+                    }
+                    let msg: communication::Message = response.into();
+                    publish(ctx_pub, rpc_key, &msg)?;
                 }
 
                 _ => {}
@@ -248,6 +447,9 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
         MsgClass::HEADER(header) => {}
         MsgClass::BODY(body) => {}
         MsgClass::BLOCK(block) => {
+            if fast_sync.on_block(&block, &ctx_pub) {
+                return Ok(());
+            }
             let mut guard = chain.block_map.write();
 
             let current_height = chain.get_current_height();
@@ -281,6 +483,9 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                 chain.max_height.store(status_height as usize, Ordering::SeqCst);
                 trace!("recieved status update max_height: {:?}", status_height);
             }
+            if fast_sync.on_status(origin, status_height, &ctx_pub) {
+                return Ok(());
+            }
             let known_max_height = chain.get_max_height();
             let current_height = chain.get_current_height();
             let target_height = ::std::cmp::min(current_height + 100, known_max_height);
@@ -293,7 +498,7 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                     BigEndian::write_u64(&mut wtr, start_height);
                     let msg = factory::create_msg_ex(submodules::CHAIN, topics::SYNC_BLK, communication::MsgType::MSG, communication::OperateType::SINGLE, origin, wtr);
                     trace!("origin {:?}, chain.sync: OperateType {:?}", origin, communication::OperateType::SINGLE);
-                    ctx_pub.send(("chain.sync".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, "chain.sync", &msg)?;
                     start_height += 1;
                     diff -= 1;
                 }
@@ -308,11 +513,14 @@ pub fn chain_result(chain: Arc<Chain>, rx: &Receiver<(u32, u32, u32, MsgClass)>,
                 if let Some(block) = chain.block(BlockId::Number(BigEndian::read_u64(&content))) {
                     let msg = factory::create_msg_ex(submodules::CHAIN, topics::NEW_BLK, communication::MsgType::BLOCK, communication::OperateType::SINGLE, origin, block.protobuf().write_to_bytes().unwrap());
                     trace!("origin {:?}, chain.blk: OperateType {:?}", origin, communication::OperateType::SINGLE);
-                    ctx_pub.send(("chain.blk".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                    publish(ctx_pub, "chain.blk", &msg)?;
                 }
+            } else if fast_sync.on_message(cmd_id, origin, &content, &ctx_pub) {
+                trace!("fast sync msg from node-{:?}", origin);
             } else {
                 warn!("other content.");
             }
         }
     }
+    Ok(())
 }
@@ -33,26 +33,40 @@ extern crate byteorder;
 extern crate serde_json;
 extern crate protobuf;
 
+mod fast_sync;
 mod forward;
 mod synchronizer;
 
 use clap::App;
-use core::db;
 use core::libchain;
 use core::libchain::{submodules, key_to_id};
-use core::libchain::Genesis;
+use core::libchain::{Config, Genesis};
+use core::libchain::audit;
+use core::libchain::export;
+use core::libchain::rollback;
+use core::libchain::snapshot;
+use fast_sync::FastSync;
 use forward::*;
+use libproto::handshake::start_handshake;
+use libproto::heartbeat::start_heartbeat;
+use libproto::reload::on_hangup;
+use libproto::shutdown::Shutdown;
 use log::LogLevelFilter;
 use protobuf::Message;
-use pubsub::start_pubsub;
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use pubsub::retry::Retry;
 use std::env;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::process;
 use std::sync::Arc;
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, sync_channel};
 use std::thread;
 use std::time;
 use std::time::Duration;
 use synchronizer::Synchronizer;
-use util::kvdb::{Database, DatabaseConfig};
 
 pub const DATA_PATH: &'static str = "DATA_PATH";
 
@@ -68,6 +82,14 @@ fn main() {
         .author("Cryptape")
         .about("CITA Block Chain Node powered by Rust")
         .args_from_usage("-c, --config=[FILE] 'Sets a custom config file'")
+        .args_from_usage("-p, --pruning=[FILE] 'Sets the config file of state pruning, fast sync, snapshots, state diffs, ancient blocks and the database'")
+        .args_from_usage("--snapshot=[DIR] 'Takes a snapshot of the current block into the directory and exits'")
+        .args_from_usage("--restore=[DIR] 'Restores the empty node from the snapshot in the directory'")
+        .args_from_usage("--rollback-to=[HEIGHT] 'Rolls the chain back to the height, clears the consensus WAL and exits'")
+        .args_from_usage("--export-blocks=[FILE] 'Exports the blocks into the file and exits'")
+        .args_from_usage("--import-blocks=[FILE] 'Imports the blocks of the file and exits'")
+        .args_from_usage("--export-audit=[FILE] 'Exports the execution audit of the blocks into the file and exits'")
+        .args_from_usage("--compare-audit=[FILE] 'Compares the execution audit of the blocks with the one exported by another node and exits'")
         .get_matches();
 
     let mut config_path = "config";
@@ -76,49 +98,148 @@ fn main() {
         config_path = c;
     }
 
+    let mut chain_config = match matches.value_of("pruning") {
+        Some(path) => Config::init(path),
+        None => Config::default(),
+    };
+    info!("{:?} node, state pruning {}, history {}", chain_config.mode(), chain_config.algorithm(), chain_config.pruning_history);
+    info!("fast sync {}, snapshot interval {}", chain_config.fast_sync, chain_config.snapshot_interval);
+
     let (tx, rx) = channel();
     let pool = threadpool::ThreadPool::new(10);
-    let (ctx_sub, crx_sub) = channel();
+    let (ctx_sub, crx_sub) = sync_channel(QUEUE_SIZE);
     let (ctx_pub, crx_pub) = channel();
-    start_pubsub("chain", vec!["net.blk", "net.status", "net.sync", "consensus.blk", "jsonrpc.request"], ctx_sub, crx_pub);
+    start_pubsub("chain", vec!["net.blk", "net.status", "net.sync", "consensus.blk", "consensus.request", "jsonrpc.request", "consensus.shutdown_ack"], ctx_sub, crx_pub);
     thread::spawn(move || loop {
                       let (key, msg) = crx_sub.recv().unwrap();
-                      forward::chain_pool(&pool, &tx, key_to_id(&key), msg);
+                      let id = key_to_id(&key);
+                      forward::chain_pool(&pool, &tx, key, id, msg);
                   });
-    let nosql_path = env::var(DATA_PATH).expect(format!("{} must be set", DATA_PATH).as_str()) + "/nosql";
-    let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
-    let db = Database::open(&config, &nosql_path).unwrap();
+    let data_path = env::var(DATA_PATH).expect(format!("{} must be set", DATA_PATH).as_str());
+    if chain_config.ancient_path.is_none() {
+        chain_config.ancient_path = Some(data_path.clone() + "/ancient");
+    }
+    let nosql_path = data_path.clone() + "/nosql";
+    info!("database {}, compaction {}", chain_config.database.backend, chain_config.database.compaction_profile);
+    let db = chain_config.database.open(&nosql_path).unwrap();
+    if let Some(height) = matches.value_of("rollback-to") {
+        let height = height.parse().expect("Invalid rollback height.");
+        match rollback::rollback(db.clone(), &chain_config, height) {
+            Ok(hash) => {
+                rollback::clear_consensus_state(Path::new(&data_path)).expect("Failed to clear consensus state.");
+                info!("chain rolled back to {} {:?}", height, hash);
+            }
+            Err(e) => error!("Failed to roll back: {}", e),
+        }
+        return;
+    }
+    let mut restored = None;
+    if let Some(dir) = matches.value_of("restore") {
+        match snapshot::restore(db.clone(), chain_config.algorithm(), Path::new(dir)) {
+            Ok(manifest) => restored = Some(manifest.height),
+            Err(e) => panic!("Failed to restore snapshot: {}", e),
+        }
+    }
     let genesis = Genesis::init(config_path);
     let (sync_tx, sync_rx) = channel();
-    let (chain, st) = libchain::chain::Chain::init_chain(Arc::new(db), genesis, sync_tx);
+    let (chain, st) = libchain::chain::Chain::init_chain(db, genesis, &chain_config, sync_tx);
+    if let Some(height) = restored {
+        chain.rebuild_blooms(height.saturating_sub(snapshot::SNAPSHOT_BLOCKS), height);
+    }
+    if let Some(dir) = matches.value_of("snapshot") {
+        if let Err(e) = snapshot::take_snapshot(&chain, Path::new(dir)) {
+            error!("Failed to take snapshot: {}", e);
+        }
+        return;
+    }
+    if let Some(path) = matches.value_of("export-blocks") {
+        let mut file = io::BufWriter::new(fs::File::create(path).expect("Failed to create export file."));
+        match export::export_blocks(&chain, 0..chain.get_current_height() + 1, &mut file) {
+            Ok(count) => info!("exported {} blocks into {}", count, path),
+            Err(e) => error!("Failed to export blocks: {}", e),
+        }
+        return;
+    }
+    if let Some(path) = matches.value_of("import-blocks") {
+        let mut file = io::BufReader::new(fs::File::open(path).expect("Failed to open import file."));
+        if let Err(e) = export::import_blocks(&chain, &mut file) {
+            error!("Failed to import blocks: {}", e);
+        }
+        return;
+    }
+
+    if let Some(path) = matches.value_of("export-audit") {
+        let mut file = io::BufWriter::new(fs::File::create(path).expect("Failed to create audit file."));
+        match audit::export_audits(&chain, 0..chain.get_current_height() + 1, &mut file) {
+            Ok(count) => info!("exported the audits of {} blocks into {}", count, path),
+            Err(e) => error!("Failed to export audits: {}", e),
+        }
+        return;
+    }
+
+    if let Some(path) = matches.value_of("compare-audit") {
+        let file = io::BufReader::new(fs::File::open(path).expect("Failed to open audit file."));
+        match audit::compare_audits(&chain, file) {
+            Ok(Some(mismatch)) => error!("execution differs at {}", mismatch),
+            Ok(None) => info!("no difference in the audits"),
+            Err(e) => error!("Failed to compare audits: {}", e),
+        }
+        return;
+    }
     let msg = factory::create_msg(submodules::CHAIN, topics::NEW_STATUS, communication::MsgType::STATUS, st.write_to_bytes().unwrap());
 
     info!("init status {:?}, {:?}", st.get_height(), st.get_hash());
     ctx_pub.send(("chain.status".to_string(), msg.write_to_bytes().unwrap())).unwrap();
     let synchronizer = Synchronizer::new(chain.clone());
+    let fast_sync = Arc::new(FastSync::new(chain.clone(), &chain_config, Path::new(&data_path).join("snapshot")));
+    let shutdown = Shutdown::new(submodules::CHAIN, "chain");
+    start_handshake("chain", &[]);
+    start_heartbeat(submodules::CHAIN, "chain", shutdown.clone(), ctx_pub.clone());
+    // the config of the chain is structural, only the filters of the logs are reloaded
+    on_hangup(|| {});
+    // a panic may leave the chain updated half way, so the process dies for the supervisor to restart it
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+                                 default_hook(info);
+                                 process::exit(1);
+                             }));
     let chain1 = chain.clone();
+    let fast_sync1 = fast_sync.clone();
+    let shutdown1 = shutdown.clone();
+    let retry = Retry::new("chain");
     let ctx_pub1 = ctx_pub.clone();
     thread::spawn(move || loop {
                       let chain = chain1.clone();
-                      forward::chain_result(chain, &rx, ctx_pub1.clone());
+                      forward::chain_result(chain, &fast_sync1, &shutdown1, &retry, &rx, ctx_pub1.clone());
                   });
 
+    let ctx_pub1 = ctx_pub.clone();
     thread::spawn(move || loop {
                       let notify = sync_rx.recv_timeout(Duration::new(8, 0));
                       if notify.is_ok() {
-                          synchronizer.sync(ctx_pub.clone());
+                          synchronizer.sync(ctx_pub1.clone());
                       } else {
-                          synchronizer.sync_status(ctx_pub.clone());
+                          synchronizer.sync_status(ctx_pub1.clone());
                       }
                   });
-    //garbage collect
-    let mut i: u32 = 0;
-    loop {
-        thread::sleep(time::Duration::from_millis(10000));
-        if i > 100 {
-            chain.collect_garbage();
-            i = 0;
+    //garbage collect and take snapshots
+    let chain1 = chain.clone();
+    let shutdown1 = shutdown.clone();
+    thread::spawn(move || {
+        let mut i: u32 = 0;
+        loop {
+            thread::sleep(time::Duration::from_millis(10000));
+            let _work = shutdown1.working();
+            fast_sync.snapshot();
+            chain1.freeze_ancient();
+            if i > 100 {
+                chain1.collect_garbage();
+                i = 0;
+            }
+            i += 1;
         }
-        i += 1;
-    }
+    });
+
+    // the blocks committed by the consensus before it stopped are imported first
+    shutdown.run(&ctx_pub, &[submodules::CONSENSUS], || chain.flush());
 }
@@ -22,6 +22,7 @@
 use byteorder::{BigEndian, ByteOrder};
 use core::libchain::block::Block;
 use core::libchain::chain::Chain;
+use core::native;
 use libproto;
 use libproto::*;
 use libproto::blockchain::Status;
@@ -80,6 +81,75 @@ impl Synchronizer {
 
         let msg = factory::create_msg(submodules::CHAIN, topics::NEW_STATUS, communication::MsgType::STATUS, status.write_to_bytes().unwrap());
         ctx_pub.send(("chain.status".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+        self.pub_sys_config(ctx_pub);
+    }
+
+    /// Send the system config on chain to consensus, 8 bytes for each of the block interval,
+    /// the block quota limit, zero if not set, and the quota a transaction is packaged by.
+    pub fn pub_sys_config(&self, ctx_pub: Sender<(String, Vec<u8>)>) {
+        let mut content = vec![0u8; 24];
+        BigEndian::write_u64(&mut content[..8], self.chain.block_interval().unwrap_or(0));
+        BigEndian::write_u64(&mut content[8..16], self.chain.block_quota_limit().unwrap_or(0));
+        BigEndian::write_u64(&mut content[16..], self.chain.quota_per_transaction());
+        let msg = factory::create_msg(submodules::CHAIN, topics::SYS_CONFIG, communication::MsgType::MSG, content);
+        ctx_pub.send(("chain.sysconfig".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+        self.pub_node_list(ctx_pub.clone());
+        self.pub_key_rotations(ctx_pub.clone());
+        self.pub_commit_rounds(ctx_pub.clone());
+        self.pub_permissions(ctx_pub);
+    }
+
+    /// Send the rounds the last blocks were committed at to consensus, 16 bytes for each
+    /// block, the height and the round in 8 bytes each.
+    fn pub_commit_rounds(&self, ctx_pub: Sender<(String, Vec<u8>)>) {
+        let content = self.chain.commit_rounds().iter().fold(Vec::new(), |mut content, &(height, round)| {
+            let mut buf = [0u8; 16];
+            BigEndian::write_u64(&mut buf[..8], height);
+            BigEndian::write_u64(&mut buf[8..], round);
+            content.extend_from_slice(&buf);
+            content
+        });
+        let msg = factory::create_msg(submodules::CHAIN, topics::COMMIT_ROUNDS, communication::MsgType::MSG, content);
+        ctx_pub.send(("chain.sysconfig".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Send the key rotations recorded on chain to consensus, 48 bytes for each of them,
+    /// the height it takes effect from in 8 bytes, then the old and the new key.
+    fn pub_key_rotations(&self, ctx_pub: Sender<(String, Vec<u8>)>) {
+        let content = self.chain.key_rotations().iter().fold(Vec::new(), |mut content, &(height, ref old, ref new)| {
+            let mut buf = [0u8; 8];
+            BigEndian::write_u64(&mut buf, height);
+            content.extend_from_slice(&buf);
+            content.extend_from_slice(&old.0);
+            content.extend_from_slice(&new.0);
+            content
+        });
+        let msg = factory::create_msg(submodules::CHAIN, topics::KEY_ROTATION, communication::MsgType::MSG, content);
+        ctx_pub.send(("chain.sysconfig".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Send the accounts permitted on chain to auth, the number of the senders in
+    /// 4 bytes, then the senders and the creators, 20 bytes for each address.
+    fn pub_permissions(&self, ctx_pub: Sender<(String, Vec<u8>)>) {
+        let senders = self.chain.permitted_accounts(native::SEND_PERMISSION);
+        let creators = self.chain.permitted_accounts(native::CREATE_PERMISSION);
+        let mut content = vec![0u8; 4];
+        BigEndian::write_u32(&mut content, senders.len() as u32);
+        for account in senders.iter().chain(creators.iter()) {
+            content.extend_from_slice(&account.0);
+        }
+        let msg = factory::create_msg(submodules::CHAIN, topics::PERMISSIONS, communication::MsgType::MSG, content);
+        ctx_pub.send(("chain.permissions".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Send the nodes approved on chain to network, 20 bytes for each address.
+    fn pub_node_list(&self, ctx_pub: Sender<(String, Vec<u8>)>) {
+        let content = self.chain.node_list().iter().fold(Vec::new(), |mut content, node| {
+            content.extend_from_slice(&node.0);
+            content
+        });
+        let msg = factory::create_msg(submodules::CHAIN, topics::NODE_LIST, communication::MsgType::MSG, content);
+        ctx_pub.send(("chain.nodes".to_string(), msg.write_to_bytes().unwrap())).unwrap();
     }
 
     fn add_block(&self, ctx_pub: Sender<(String, Vec<u8>)>, blk: Block) {
@@ -88,6 +158,7 @@ impl Synchronizer {
             let msg = factory::create_msg(submodules::CHAIN, topics::NEW_STATUS, communication::MsgType::STATUS, st.write_to_bytes().unwrap());
             info!("chain after sync current height {:?}  known height{:?}", self.chain.get_current_height(), self.chain.get_max_height());
             ctx_pub.send(("chain.status".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+            self.pub_sys_config(ctx_pub);
         }
     }
 }
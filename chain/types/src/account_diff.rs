@@ -16,6 +16,7 @@
 
 //! Diff between two accounts.
 
+use rlp::*;
 use std::cmp::*;
 use std::collections::BTreeMap;
 use std::fmt;
@@ -73,6 +74,43 @@ where
     }
 }
 
+impl<T> Encodable for Diff<T>
+where
+    T: Eq + Encodable,
+{
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match *self {
+            Diff::Same => {
+                s.begin_list(1).append(&0u8);
+            }
+            Diff::Born(ref x) => {
+                s.begin_list(2).append(&1u8).append(x);
+            }
+            Diff::Changed(ref pre, ref post) => {
+                s.begin_list(3).append(&2u8).append(pre).append(post);
+            }
+            Diff::Died(ref x) => {
+                s.begin_list(2).append(&3u8).append(x);
+            }
+        }
+    }
+}
+
+impl<T> Decodable for Diff<T>
+where
+    T: Eq + Decodable,
+{
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        match rlp.val_at::<u8>(0)? {
+            0 => Ok(Diff::Same),
+            1 => Ok(Diff::Born(rlp.val_at(1)?)),
+            2 => Ok(Diff::Changed(rlp.val_at(1)?, rlp.val_at(2)?)),
+            3 => Ok(Diff::Died(rlp.val_at(1)?)),
+            _ => Err(DecoderError::Custom("Unknown diff.")),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "ipc", binary)]
 /// Account diff.
@@ -111,6 +149,34 @@ impl fmt::Display for Existance {
     }
 }
 
+impl Encodable for AccountDiff {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(4);
+        s.append(&self.balance);
+        s.append(&self.nonce);
+        s.append(&self.code);
+        s.begin_list(self.storage.len());
+        for (key, value) in &self.storage {
+            s.begin_list(2).append(key).append(value);
+        }
+    }
+}
+
+impl Decodable for AccountDiff {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let mut storage = BTreeMap::new();
+        for item in rlp.at(3)?.iter() {
+            storage.insert(item.val_at(0)?, item.val_at(1)?);
+        }
+        Ok(AccountDiff {
+               balance: rlp.val_at(0)?,
+               nonce: rlp.val_at(1)?,
+               code: rlp.val_at(2)?,
+               storage: storage,
+           })
+    }
+}
+
 impl AccountDiff {
     /// Get `Existance` projection.
     pub fn existance(&self) -> Existance {
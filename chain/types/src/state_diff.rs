@@ -17,6 +17,7 @@
 //! State diff module.
 
 use account_diff::*;
+use rlp::*;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::*;
@@ -38,6 +39,25 @@ impl StateDiff {
     }
 }
 
+impl Encodable for StateDiff {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(self.raw.len());
+        for (address, diff) in &self.raw {
+            s.begin_list(2).append(address).append(diff);
+        }
+    }
+}
+
+impl Decodable for StateDiff {
+    fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+        let mut raw = BTreeMap::new();
+        for item in rlp.iter() {
+            raw.insert(item.val_at(0)?, item.val_at(1)?);
+        }
+        Ok(StateDiff { raw: raw })
+    }
+}
+
 impl fmt::Display for StateDiff {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for (add, acc) in &self.raw {
@@ -54,3 +74,36 @@ impl Deref for StateDiff {
         &self.raw
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlp;
+    use util::{U256, H256};
+
+    #[test]
+    fn encode_decode() {
+        let mut storage = BTreeMap::new();
+        storage.insert(H256::from(1), Diff::Born(H256::from(2)));
+        storage.insert(H256::from(3), Diff::Changed(H256::from(4), H256::from(5)));
+        storage.insert(H256::from(6), Diff::Died(H256::from(7)));
+        let mut raw = BTreeMap::new();
+        raw.insert(Address::from(1),
+                   AccountDiff {
+                       balance: Diff::Same,
+                       nonce: Diff::Born(U256::from(1)),
+                       code: Diff::Born(vec![0x60, 0x00]),
+                       storage: storage,
+                   });
+        raw.insert(Address::from(2),
+                   AccountDiff {
+                       balance: Diff::Same,
+                       nonce: Diff::Changed(U256::from(1), U256::from(2)),
+                       code: Diff::Same,
+                       storage: BTreeMap::new(),
+                   });
+        let diff = StateDiff { raw: raw };
+        let decoded: StateDiff = rlp::decode(&rlp::encode(&diff));
+        assert_eq!(decoded, diff);
+    }
+}
@@ -123,6 +123,12 @@ pub struct Transaction {
     pub data: Bytes,
     /// valid before this block number
     pub block_limit: BlockNumber,
+    /// Id of the chain the transaction was signed for.
+    pub chain_id: u32,
+    /// Version of the transaction, 0 if it is not bound to a chain.
+    pub version: u32,
+    /// Price offered for the quota, ranking the transactions of a sender with the same nonce in the pool.
+    pub quota_price: u64,
 }
 
 impl HeapSizeOf for Transaction {
@@ -133,9 +139,12 @@ impl HeapSizeOf for Transaction {
 
 impl Decodable for Transaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
-        if d.item_count()? != 7 {
-            return Err(DecoderError::RlpIncorrectListLen);
-        }
+        let (chain_id, version, quota_price) = match d.item_count()? {
+            7 => (0, 0, 0),
+            9 => (d.val_at(7)?, d.val_at(8)?, 0),
+            10 => (d.val_at(7)?, d.val_at(8)?, d.val_at(9)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
         Ok(Transaction {
                nonce: d.val_at(0)?,
                gas_price: d.val_at(1)?,
@@ -144,6 +153,9 @@ impl Decodable for Transaction {
                value: d.val_at(4)?,
                data: d.val_at(5)?,
                block_limit: d.val_at(6)?,
+               chain_id: chain_id,
+               version: version,
+               quota_price: quota_price,
            })
     }
 }
@@ -177,6 +189,9 @@ impl Transaction {
                value: U256::default(),
                data: plain_transaction.get_data().into(),
                block_limit: plain_transaction.get_valid_until_block(),
+               chain_id: plain_transaction.get_chain_id(),
+               version: plain_transaction.get_version(),
+               quota_price: plain_transaction.get_quota_price(),
            })
 
     }
@@ -198,16 +213,20 @@ impl Transaction {
                 signature: signature,
                 hash: 0.into(),
                 crypto_type: CryptoType::default(),
+                fee_payer_signature: None,
             },
             sender: from,
             public: Public::default(),
+            fee_payer: None,
         }
     }
 
     /// Append object with a without signature into RLP stream
     pub fn rlp_append_unsigned_transaction(&self, s: &mut RlpStream) {
         let store_addr: Address = STORE_ADDRESS.into();
-        s.begin_list(7);
+        let priced = self.quota_price != 0;
+        let bound = priced || self.chain_id != 0 || self.version != 0;
+        s.begin_list(if priced { 10 } else if bound { 9 } else { 7 });
         s.append(&self.nonce);
         s.append(&self.gas_price);
         s.append(&self.gas);
@@ -219,6 +238,13 @@ impl Transaction {
         s.append(&self.value);
         s.append(&self.data);
         s.append(&self.block_limit);
+        if bound {
+            s.append(&self.chain_id);
+            s.append(&self.version);
+        }
+        if priced {
+            s.append(&self.quota_price);
+        }
     }
 
     /// get the protobuf transaction
@@ -226,6 +252,9 @@ impl Transaction {
         let mut pt = ProtoTransaction::new();
         pt.set_nonce(self.nonce.to_hex());
         pt.set_valid_until_block(self.block_limit);
+        pt.set_chain_id(self.chain_id);
+        pt.set_version(self.version);
+        pt.set_quota_price(self.quota_price);
         pt.set_data(self.data.clone());
         match self.action {
             Action::Create => pt.clear_to(),
@@ -247,6 +276,8 @@ pub struct UnverifiedTransaction {
     crypto_type: CryptoType,
     /// Hash of the transaction
     hash: H256,
+    /// The signature of the sponsor paying the quota, if any
+    fee_payer_signature: Option<Signature>,
 }
 
 impl Deref for UnverifiedTransaction {
@@ -265,14 +296,17 @@ impl DerefMut for UnverifiedTransaction {
 
 impl Decodable for UnverifiedTransaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
-        if d.item_count()? != 4 {
-            return Err(DecoderError::RlpIncorrectListLen);
-        }
+        let fee_payer_signature = match d.item_count()? {
+            4 => None,
+            5 => Some(d.val_at(4)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
         Ok(UnverifiedTransaction {
                unsigned: d.val_at(0)?,
                signature: d.val_at(1)?,
                crypto_type: d.val_at(2)?,
                hash: d.val_at(3)?,
+               fee_payer_signature: fee_payer_signature,
            })
     }
 }
@@ -290,21 +324,31 @@ impl UnverifiedTransaction {
             return Err(Error::InvalidSignature);
         }
 
+        let fee_payer_signature = match utx.get_fee_payer_signature().len() {
+            0 => None,
+            SIGNATURE_BYTES_LEN => Some(Signature::from(H768::from(utx.get_fee_payer_signature()))),
+            _ => return Err(Error::InvalidSignature),
+        };
+
         Ok(UnverifiedTransaction {
                unsigned: Transaction::new(utx.get_transaction())?,
                signature: Signature::from(H768::from(utx.get_signature())),
                crypto_type: CryptoType::from(utx.get_crypto()),
                hash: hash,
+               fee_payer_signature: fee_payer_signature,
            })
     }
 
     /// Append object with a signature into RLP stream
     fn rlp_append_sealed_transaction(&self, s: &mut RlpStream) {
-        s.begin_list(4);
+        s.begin_list(if self.fee_payer_signature.is_some() { 5 } else { 4 });
         s.append(&self.unsigned);
         s.append(&self.signature);
         s.append(&self.crypto_type);
         s.append(&self.hash);
+        if let Some(ref signature) = self.fee_payer_signature {
+            s.append(signature);
+        }
     }
 
     ///	Reference to unsigned part of this transaction.
@@ -323,6 +367,9 @@ impl UnverifiedTransaction {
 
         untx.set_transaction(tx);
         untx.set_signature(self.signature.to_vec());
+        if let Some(ref signature) = self.fee_payer_signature {
+            untx.set_fee_payer_signature(signature.to_vec());
+        }
 
         match self.crypto_type {
             CryptoType::SECP => untx.set_crypto(ProtoCrypto::SECP),
@@ -338,13 +385,20 @@ pub struct SignedTransaction {
     transaction: UnverifiedTransaction,
     sender: Address,
     public: Public,
+    /// The sponsor paying the quota and its public key
+    fee_payer: Option<(Address, Public)>,
 }
 
 impl Decodable for SignedTransaction {
     fn decode(d: &UntrustedRlp) -> Result<Self, DecoderError> {
-        if d.item_count()? != 5 {
-            return Err(DecoderError::RlpIncorrectListLen);
-        }
+        let (fee_payer_signature, fee_payer) = match d.item_count()? {
+            5 => (None, None),
+            7 => {
+                let public: H256 = d.val_at(6)?;
+                (Some(d.val_at(5)?), Some((pubkey_to_address(&public), public)))
+            }
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
 
         let public: H256 = d.val_at(4)?;
 
@@ -354,22 +408,32 @@ impl Decodable for SignedTransaction {
                                 signature: d.val_at(1)?,
                                 crypto_type: d.val_at(2)?,
                                 hash: d.val_at(3)?,
+                                fee_payer_signature: fee_payer_signature,
                             },
                sender: pubkey_to_address(&public),
                public: public,
+               fee_payer: fee_payer,
            })
     }
 }
 
 impl Encodable for SignedTransaction {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(5);
+        let fee_payer = match (&self.fee_payer_signature, &self.fee_payer) {
+            (&Some(ref signature), &Some((_, ref public))) => Some((signature, public)),
+            _ => None,
+        };
+        s.begin_list(if fee_payer.is_some() { 7 } else { 5 });
         s.append(&self.unsigned);
         s.append(&self.signature);
         s.append(&self.crypto_type);
         s.append(&self.hash);
         //TODO: remove it
         s.append(&self.public);
+        if let Some((signature, public)) = fee_payer {
+            s.append(signature);
+            s.append(public);
+        }
     }
 }
 
@@ -406,10 +470,22 @@ impl SignedTransaction {
         let tx_hash = H256::from(stx.get_tx_hash());
         let public = H256::from_slice(stx.get_signer());
         let sender = pubkey_to_address(&public);
+        let transaction = UnverifiedTransaction::new(stx.get_transaction_with_sig(), tx_hash)?;
+
+        let fee_payer = match (transaction.fee_payer_signature.is_some(), stx.get_fee_payer().len()) {
+            (false, 0) => None,
+            (true, PUBKEY_BYTES_LEN) => {
+                let public = H256::from_slice(stx.get_fee_payer());
+                Some((pubkey_to_address(&public), public))
+            }
+            _ => return Err(Error::InvalidPubKey),
+        };
+
         Ok(SignedTransaction {
-               transaction: UnverifiedTransaction::new(stx.get_transaction_with_sig(), tx_hash)?,
+               transaction: transaction,
                sender: sender,
                public: public,
+               fee_payer: fee_payer,
            })
     }
 
@@ -418,6 +494,17 @@ impl SignedTransaction {
         &self.sender
     }
 
+    /// Returns the sponsor paying the quota of the transaction, if any.
+    pub fn fee_payer(&self) -> Option<&Address> {
+        self.fee_payer.as_ref().map(|&(ref address, _)| address)
+    }
+
+    /// Returns the account the quota is charged to,
+    /// the sponsor if the transaction has one, otherwise the sender.
+    pub fn quota_payer(&self) -> &Address {
+        self.fee_payer().unwrap_or(&self.sender)
+    }
+
     /// Returns a public key of the sender.
     pub fn public_key(&self) -> &Public {
         &self.public
@@ -430,6 +517,9 @@ impl SignedTransaction {
         stx.set_transaction_with_sig(utx);
         stx.set_tx_hash(self.hash().to_vec());
         stx.set_signer(self.public.to_vec());
+        if let Some((_, ref public)) = self.fee_payer {
+            stx.set_fee_payer(public.to_vec());
+        }
         stx
     }
 }
@@ -462,4 +552,50 @@ mod tests {
 
         assert_eq!(stx_rlp, stx_encoded);
     }
+
+    #[test]
+    fn test_fee_payer() {
+        let sender = ed25519::KeyPair::gen_keypair();
+        let sponsor = ed25519::KeyPair::gen_keypair();
+        let mut utx = ProtoUnverifiedTransaction::new();
+        utx.set_transaction(Transaction::default().proto_transaction());
+        let mut proto = ProtoSignedTransaction::new();
+        proto.set_transaction_with_sig(utx);
+        proto.sign(*sender.privkey());
+        proto.sign_fee_payer(*sponsor.privkey());
+
+        let stx = SignedTransaction::new(&proto).unwrap();
+        assert_eq!(stx.sender(), &pubkey_to_address(sender.pubkey()));
+        assert_eq!(stx.fee_payer(), Some(&pubkey_to_address(sponsor.pubkey())));
+        assert_eq!(stx.quota_payer(), &pubkey_to_address(sponsor.pubkey()));
+        assert_eq!(stx.protobuf(), proto);
+
+        let stx_rlp = rlp::encode(&stx);
+        let decoded: SignedTransaction = rlp::decode(&stx_rlp);
+        assert_eq!(decoded, stx);
+    }
+
+    #[test]
+    fn test_chain_id() {
+        let keypair = ed25519::KeyPair::gen_keypair();
+        let mut tx = Transaction::default();
+        tx.chain_id = 7;
+        tx.version = 1;
+        tx.quota_price = 3;
+        let mut utx = ProtoUnverifiedTransaction::new();
+        utx.set_transaction(tx.proto_transaction());
+        let mut proto = ProtoSignedTransaction::new();
+        proto.set_transaction_with_sig(utx);
+        proto.sign(*keypair.privkey());
+
+        let stx = SignedTransaction::new(&proto).unwrap();
+        assert_eq!(stx.chain_id, 7);
+        assert_eq!(stx.version, 1);
+        assert_eq!(stx.quota_price, 3);
+        assert_eq!(stx.protobuf(), proto);
+
+        let stx_rlp = rlp::encode(&stx);
+        let decoded: SignedTransaction = rlp::decode(&stx_rlp);
+        assert_eq!(decoded, stx);
+    }
 }
@@ -51,7 +51,9 @@ pub struct AuthorityRoundParams {
 
 pub struct AuthorityRound {
     params: AuthorityRoundParams,
-    position: u64,
+    authorities: RwLock<Vec<Address>>,
+    // height the validators replacing the current ones seal from
+    pending_authorities: RwLock<Option<(usize, Vec<Address>)>>,
     tx_pool: Arc<RwLock<Pool>>,
     height: AtomicUsize,
     pre_hash: RwLock<Option<H256>>,
@@ -63,11 +65,14 @@ pub struct AuthorityRound {
 impl AuthorityRound {
     /// Create a new instance of POA engine
     pub fn new(params: AuthorityRoundParams, ready: Sender<usize>) -> Result<Arc<Self>, EngineError> {
-        let position = params.authorities.iter().position(|&a| a == params.signer.address.clone().into()).unwrap() as u64;
+        if params.authorities.is_empty() {
+            return Err(EngineError::EmptyAuthorities);
+        }
 
         let engine = Arc::new(AuthorityRound {
+                                  authorities: RwLock::new(params.authorities.clone()),
+                                  pending_authorities: RwLock::new(None),
                                   params: params,
-                                  position: position,
                                   tx_pool: Arc::new(RwLock::new(Pool::new(10000, 3000))),
                                   height: AtomicUsize::new(INIT_HEIGHT),
                                   pre_hash: RwLock::new(None),
@@ -78,14 +83,23 @@ impl AuthorityRound {
         Ok(engine)
     }
 
+    /// Replace the validators by the pending ones due at the height.
+    fn apply_authorities(&self, height: usize) {
+        let mut pending = self.pending_authorities.write();
+        if pending.as_ref().map_or(false, |&(from, _)| from <= height) {
+            *self.authorities.write() = pending.take().unwrap().1;
+        }
+    }
+
     pub fn update_height(&self) {
         // self.height.fetch_add(1, Ordering::SeqCst);
         self.step.fetch_add(1, Ordering::SeqCst);
     }
 
     pub fn is_sealer(&self, nonce: u64) -> bool {
-        let authority = nonce % self.params.authority_n;
-        authority == self.position
+        let authorities = self.authorities.read();
+        let authority = nonce % authorities.len() as u64;
+        authorities[authority as usize] == self.params.signer.address
     }
 
     pub fn generate_proof(&self, body: &mut BlockBody, step: u64) -> Proof {
@@ -146,6 +160,19 @@ impl From<engine_json::AuthorityRoundParams> for AuthorityRoundParams {
     }
 }
 
+/// Solo engine is the authority round with the signer as the only authority.
+impl From<engine_json::SoloParams> for AuthorityRoundParams {
+    fn from(p: engine_json::SoloParams) -> Self {
+        let signer = Signer::from(p.signer);
+        AuthorityRoundParams {
+            duration: Duration::from_millis(p.duration.into()),
+            authority_n: 1,
+            authorities: vec![signer.address],
+            signer: signer,
+        }
+    }
+}
+
 impl Engine for AuthorityRound {
     fn name(&self) -> &str {
         "AuthorityRound"
@@ -160,7 +187,7 @@ impl Engine for AuthorityRound {
         let proof = AuthorityRoundProof::from(block.get_header().get_proof().clone());
         let signature = Signature::from(proof.signature);
         let author = block.get_body().recover_address_with_signature(&signature).unwrap();
-        if !self.params.authorities.contains(&author) {
+        if !self.authorities.read().contains(&author) {
             trace!("verify_block author {:?}", author.to_hex());
             return Err(EngineError::NotAuthorized(author))?;
         }
@@ -186,6 +213,7 @@ impl Engine for AuthorityRound {
         }
         if new_height >= height {
             self.height.store(new_height, Ordering::SeqCst);
+            self.apply_authorities(new_height);
             let pre_hash = H256::from_slice(&status.hash);
             {
                 trace!("new_status hash is {:?}", pre_hash);
@@ -269,12 +297,31 @@ impl Engine for AuthorityRound {
     fn handle_proposal(&self, _message: Vec<u8>, tx_pub: Sender<(String, Vec<u8>)>) -> Result<(), EngineError> {
         unimplemented!()
     }
+
+    fn authorities(&self) -> Vec<Address> {
+        self.authorities.read().clone()
+    }
+
+    fn set_authorities(&self, authorities: Vec<Address>) -> Result<(), EngineError> {
+        if authorities.is_empty() {
+            return Err(EngineError::EmptyAuthorities);
+        }
+        // the signer is removed by the other validators, not by itself
+        if !authorities.contains(&self.params.signer.address) {
+            return Err(EngineError::NotAuthorized(self.params.signer.address));
+        }
+        let from = self.height.load(Ordering::SeqCst) + 1;
+        *self.pending_authorities.write() = Some((from, authorities));
+        Ok(())
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::super::Spec;
+    use libproto::blockchain::Status;
+    use util::{Address, H256};
 
     #[test]
     fn has_valid_metadata() {
@@ -284,4 +331,23 @@ mod tests {
         assert!(!engine.name().is_empty());
         assert!(engine.version().major >= 1);
     }
+
+    #[test]
+    fn solo_manages_authorities() {
+        let test_spec = ::std::env::current_dir().unwrap().join("../res/solo.json");
+        let engine = Spec::new_test_round(test_spec.to_str().unwrap()).engine;
+        let authorities = engine.authorities();
+        assert_eq!(authorities.len(), 1);
+        assert!(engine.set_authorities(Vec::new()).is_err());
+        assert!(engine.set_authorities(vec![Address::default()]).is_err());
+        assert!(engine.set_authorities(vec![authorities[0], Address::default()]).is_ok());
+        // the block being sealed keeps the current validators
+        assert_eq!(engine.authorities().len(), 1);
+
+        let mut status = Status::new();
+        status.set_height(1);
+        status.set_hash(H256::default().to_vec());
+        engine.receive_new_status(status);
+        assert_eq!(engine.authorities().len(), 2);
+    }
 }
@@ -24,7 +24,13 @@ use threadpool::*;
 pub fn receive(pool: &ThreadPool, tx: &Sender<(u32, u32, u32, MsgClass)>, id: u32, msg: Vec<u8>) {
     let tx = tx.clone();
     pool.execute(move || {
-                     let (cmd_id, origin, content) = parse_msg(msg.as_slice());
+                     let (cmd_id, origin, content) = match parse_msg(msg.as_slice()) {
+                         Ok(parsed) => parsed,
+                         Err(reason) => {
+                             warn!("drop msg: {}", reason);
+                             return;
+                         }
+                     };
                      tx.send((id, cmd_id, origin, content)).unwrap();
                  });
 }
@@ -46,6 +46,7 @@ impl Spec {
             EngineJson::AuthorityRound(authority_round) => {
                 AuthorityRound::new(From::from(authority_round.params), tx).expect("Failed to start AuthorityRound consensus engine.")
             }
+            EngineJson::Solo(solo) => AuthorityRound::new(From::from(solo.params), tx).expect("Failed to start Solo consensus engine."),
             _ => {
                 panic!("Failed to start AuthorityRound consensus engine.");
             }
@@ -44,8 +44,8 @@ use core::handler;
 use cpuprofiler::PROFILER;
 use libproto::*;
 use log::LogLevelFilter;
-use pubsub::start_pubsub;
-use std::sync::mpsc::channel;
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use std::sync::mpsc::{channel, sync_channel};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -96,7 +96,7 @@ fn main() {
 
     let threadpool = threadpool::ThreadPool::new(2);
     let (tx, rx) = channel();
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
     start_pubsub("consensus", vec!["net.tx", "jsonrpc.new_tx", "net.msg", "chain.status"], tx_sub, rx_pub);
     thread::spawn(move || loop {
@@ -32,6 +32,8 @@ pub enum EngineError {
     UnexpectedMessage,
     VoteMsgDelay(usize),
     VoteMsgForth(usize),
+    /// The validator set can not be empty.
+    EmptyAuthorities,
 }
 
 impl fmt::Display for EngineError {
@@ -48,6 +50,7 @@ impl fmt::Display for EngineError {
             UnexpectedMessage => "This Engine should not be fed messages.".into(),
             VoteMsgDelay(height) => format!("The vote message is delayed and missed the current height:{}", height),
             VoteMsgForth(height) => format!("The vote message is fulture height :{}", height),
+            EmptyAuthorities => "The authorities can not be empty.".into(),
         };
         f.write_fmt(format_args!("Engine error ({})", msg))
     }
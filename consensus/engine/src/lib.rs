@@ -31,7 +31,7 @@ pub use instrument::*;
 use libproto::blockchain::{Block, SignedTransaction, Status};
 use std::sync::mpsc::Sender;
 use std::time::Duration;
-use util::H256;
+use util::{H256, Address};
 use util::SemanticVersion;
 
 pub trait Engine: Sync + Send {
@@ -60,6 +60,13 @@ pub trait Engine: Sync + Send {
     fn handle_message(&self, _message: Vec<u8>, tx_pub: Sender<(String, Vec<u8>)>) -> Result<(), EngineError>;
 
     fn handle_proposal(&self, _message: Vec<u8>, tx_pub: Sender<(String, Vec<u8>)>) -> Result<(), EngineError>;
+
+    /// Validators allowed to seal blocks.
+    fn authorities(&self) -> Vec<Address>;
+
+    /// Replace the validators from the block after the one being sealed,
+    /// which must still include the signer of this node.
+    fn set_authorities(&self, authorities: Vec<Address>) -> Result<(), EngineError>;
 }
 
 #[test]
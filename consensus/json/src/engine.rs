@@ -18,6 +18,7 @@
 //! Engine deserialization.
 
 use super::AuthorityRound;
+use super::Solo;
 use super::Tendermint;
 
 
@@ -26,6 +27,7 @@ use super::Tendermint;
 pub enum Engine {
     AuthorityRound(AuthorityRound),
     Tendermint(Tendermint),
+    Solo(Solo),
 }
 
 #[cfg(test)]
@@ -69,4 +71,18 @@ mod tests {
 
         let _deserialized: Engine = serde_json::from_str(s).unwrap();
     }
+
+    #[test]
+    fn solo_engine_deserialization() {
+        let s = r#"{
+            "Solo": {
+                "params": {
+                    "duration": 3,
+                    "signer": "a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65"
+                }
+            }
+        }"#;
+
+        let _deserialized: Engine = serde_json::from_str(s).unwrap();
+    }
 }
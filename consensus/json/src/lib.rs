@@ -25,6 +25,7 @@ extern crate util;
 mod engine;
 mod authority_round;
 mod tendermint;
+mod solo;
 mod spec;
 
 
@@ -32,6 +33,7 @@ pub use self::authority_round::*;
 pub use self::engine::*;
 pub use self::spec::*;
 pub use self::tendermint::*;
+pub use self::solo::*;
 
 
 #[test]
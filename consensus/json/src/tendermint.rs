@@ -42,6 +42,61 @@ pub struct TendermintParams {
     /// Commit step timeout in milliseconds.
     #[serde(rename = "timeoutCommit")]
     pub timeout_commit: Option<u64>,
+    /// Do not produce blocks while the tx pool is empty.
+    pub skip_empty_block: Option<bool>,
+    /// Longest time in milliseconds without a block when skipping empty blocks.
+    pub max_idle_duration: Option<u64>,
+    /// Follow the consensus without proposing or voting.
+    pub observer: Option<bool>,
+    /// New consensus key replacing `signer` once the node manager rotates it on chain.
+    pub next_signer: Option<PrivKey>,
+    /// Scale the timeouts by the observed rounds.
+    pub adaptive_timeout: Option<bool>,
+    /// Bounds of the timeout scale in percent.
+    pub min_timeout_scale: Option<u32>,
+    pub max_timeout_scale: Option<u32>,
+    /// Skip a proposer after missing its slot this many times in a row.
+    pub proposer_miss_limit: Option<u64>,
+    /// Number of heights a failing proposer is skipped.
+    pub proposer_skip_heights: Option<u64>,
+    /// Target interval between blocks in milliseconds, overridden by the system config on chain.
+    pub block_interval: Option<u64>,
+    /// Id of the chain the transactions must be signed for, the same as in the genesis of the chain.
+    pub chain_id: Option<u32>,
+    /// Percent a transaction must raise the quota price by to replace the pending one with the same nonce.
+    pub tx_price_bump: Option<u64>,
+    /// Most pending transactions of a sender in the tx pool, no limit if absent.
+    pub tx_account_limit: Option<u64>,
+    /// Queue up to the limit more transactions of a sender over the limit instead of rejecting them.
+    pub tx_account_queue: Option<bool>,
+    /// Most transactions held by the tx pool, those with the lowest quota price are evicted over it.
+    pub tx_pool_capacity: Option<u64>,
+    /// Most bytes of the transactions held by the tx pool.
+    pub tx_pool_max_bytes: Option<u64>,
+    /// Seconds a transaction is held by the tx pool at most.
+    pub tx_ttl: Option<u64>,
+    /// Senders whose transactions are refused.
+    pub tx_blacklist: Option<Vec<Address>>,
+    /// Only the transactions of these senders are accepted if present.
+    pub tx_allowlist: Option<Vec<Address>>,
+    /// Order of packaging the transactions, "fifo" by default or "quota_price".
+    pub tx_pool_strategy: Option<String>,
+    /// Keep the hashes of all the committed transactions on disk to refuse them, true by default.
+    pub tx_history: Option<bool>,
+    /// Blocks the hashes of the committed transactions are kept for, all of them by default.
+    pub tx_history_blocks: Option<u64>,
+    /// Ask the chain for the nonces of the senders and refuse the transactions with a nonce used.
+    pub tx_pre_check: Option<bool>,
+    /// Journal the events of the transactions in the pool, true by default.
+    pub tx_audit: Option<bool>,
+    /// Bytes of the journal before it is rotated.
+    pub tx_audit_max_bytes: Option<u64>,
+    /// Raw bytes of a transaction and bytes of its data accepted, zero means no bound.
+    pub tx_max_bytes: Option<u64>,
+    pub tx_max_data_bytes: Option<u64>,
+    /// Transactions a peer or a jsonrpc client may submit per second, and at most at once.
+    pub tx_rate_limit: Option<u64>,
+    pub tx_rate_burst: Option<u64>,
 }
 
 /// Authority engine deserialization.
@@ -86,4 +141,43 @@ mod tests {
 
         let _deserialize: Tendermint = serde_json::from_str(s).unwrap();
     }
+
+    #[test]
+    fn tendermint_skip_empty_block_deserialization() {
+        let s = r#"{
+            "authorities" : ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"],
+            "duration": 3,
+            "signer": "a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65",
+            "block_tx_limit": 1000,
+            "tx_filter_size": 5000,
+            "tx_pool_size": 50000,
+            "is_test": true,
+            "skip_empty_block": true,
+            "max_idle_duration": 60000,
+            "observer": true
+        }"#;
+
+        let deserialize: TendermintParams = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialize.skip_empty_block, Some(true));
+        assert_eq!(deserialize.max_idle_duration, Some(60000));
+        assert_eq!(deserialize.observer, Some(true));
+    }
+
+    #[test]
+    fn tendermint_sender_lists_deserialization() {
+        let s = r#"{
+            "authorities" : ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"],
+            "duration": 3,
+            "signer": "a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65",
+            "block_tx_limit": 1000,
+            "tx_filter_size": 5000,
+            "tx_pool_size": 50000,
+            "is_test": true,
+            "tx_blacklist": ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"]
+        }"#;
+
+        let deserialize: TendermintParams = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialize.tx_blacklist.map(|list| list.len()), Some(1));
+        assert_eq!(deserialize.tx_allowlist, None);
+    }
 }
@@ -20,16 +20,19 @@ extern crate util;
 extern crate bincode;
 extern crate cita_ed25519 as ed25519;
 extern crate rustc_serialize;
+extern crate protobuf;
 #[macro_use]
 extern crate serde_derive;
 
 mod authority_round_proof;
 mod tendermint_proof;
+mod light_client_proof;
 
 
 pub use authority_round_proof::AuthorityRoundProof;
+pub use light_client_proof::{LightClientProof, LIGHT_CLIENT_PROOF_VERSION, authorities_hash};
 use libproto::blockchain::{Proof, ProofType};
-pub use tendermint_proof::TendermintProof;
+pub use tendermint_proof::{Step, TendermintProof};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
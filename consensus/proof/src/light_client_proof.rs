@@ -0,0 +1,162 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bincode::{serialize, deserialize, Infinite};
+use libproto::blockchain::BlockHeader;
+use protobuf::Message;
+use protobuf::core::parse_from_bytes;
+use tendermint_proof::TendermintProof;
+use util::{H256, Address};
+use util::Hashable;
+
+pub const LIGHT_CLIENT_PROOF_VERSION: u8 = 1;
+
+/// Hash of the validator set, the order of the authorities matters.
+pub fn authorities_hash(authorities: &[Address]) -> H256 {
+    let mut bytes = Vec::with_capacity(authorities.len() * 20);
+    for authority in authorities {
+        bytes.extend_from_slice(&authority.0);
+    }
+    bytes.crypt_hash()
+}
+
+/// Compact proof of a block for light clients.
+///
+/// `header` is the protobuf header proposed in consensus, so its hash is the hash
+/// signed by the validators. `proof` is the commit proof of the block, which
+/// is carried by the header of the next block.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct LightClientProof {
+    pub version: u8,
+    pub header: Vec<u8>,
+    pub authorities_hash: H256,
+    pub proof: TendermintProof,
+}
+
+impl LightClientProof {
+    pub fn new(header: &BlockHeader, authorities: &[Address], proof: TendermintProof) -> Self {
+        LightClientProof {
+            version: LIGHT_CLIENT_PROOF_VERSION,
+            header: header.write_to_bytes().unwrap(),
+            authorities_hash: authorities_hash(authorities),
+            proof: proof,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        serialize(self, Infinite).unwrap()
+    }
+
+    /// Decode the proof, the unknown versions are rejected.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes.first() {
+            Some(&LIGHT_CLIENT_PROOF_VERSION) => deserialize(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn header(&self) -> Option<BlockHeader> {
+        parse_from_bytes::<BlockHeader>(&self.header).ok()
+    }
+
+    /// Verify the header is committed by the authorities.
+    pub fn verify(&self, authorities: &[Address]) -> bool {
+        if self.version != LIGHT_CLIENT_PROOF_VERSION || self.authorities_hash != authorities_hash(authorities) {
+            return false;
+        }
+        match self.header() {
+            Some(header) => {
+                let height = header.get_height() as usize;
+                header.crypt_hash() == self.proof.proposal && height == self.proof.height && self.proof.check(height, authorities)
+            }
+            None => false,
+        }
+    }
+
+    /// Verify the proof follows `prev`, which has been verified by the light client.
+    /// The header of a block carries the commit proof of its parent.
+    pub fn verify_next(&self, prev: &LightClientProof, authorities: &[Address]) -> bool {
+        if !self.verify(authorities) || self.proof.height != prev.proof.height + 1 {
+            return false;
+        }
+        match self.header() {
+            Some(header) => {
+                let parent_proof = TendermintProof::from(header.get_proof().clone());
+                parent_proof.proposal == prev.proof.proposal && parent_proof.height == prev.proof.height
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::{serialize, Infinite};
+    use ed25519::{KeyPair, sign};
+    use libproto::blockchain::BlockHeader;
+    use std::collections::HashMap;
+    use tendermint_proof::{Step, TendermintProof};
+    use util::{H256, H768, Address};
+    use util::Hashable;
+
+    fn commit(keypairs: &[KeyPair], header: &BlockHeader, round: usize) -> TendermintProof {
+        let height = header.get_height() as usize;
+        let proposal = header.crypt_hash();
+        let mut commits = HashMap::new();
+        for keypair in keypairs {
+            let sender = keypair.address();
+            let msg = serialize(&(height, round, Step::Precommit, sender, Some(proposal)), Infinite).unwrap();
+            let signature: H768 = sign(keypair.privkey(), &msg.crypt_hash()).unwrap().into();
+            commits.insert(sender, signature);
+        }
+        TendermintProof::new(height, round, proposal, commits)
+    }
+
+    fn header(height: u64, parent: Option<TendermintProof>) -> BlockHeader {
+        let mut header = BlockHeader::new();
+        header.set_height(height);
+        header.set_prevhash(H256::random().to_vec());
+        if let Some(proof) = parent {
+            header.set_proof(proof.into());
+        }
+        header
+    }
+
+    #[test]
+    fn light_client_proof_verify() {
+        let keypairs: Vec<KeyPair> = (0..4).map(|_| KeyPair::gen_keypair()).collect();
+        let authorities: Vec<Address> = keypairs.iter().map(|k| k.address()).collect();
+
+        let h1 = header(1, None);
+        let p1 = LightClientProof::new(&h1, &authorities, commit(&keypairs, &h1, 0));
+        let decoded = LightClientProof::decode(&p1.encode()).unwrap();
+        assert_eq!(decoded, p1);
+        assert!(p1.verify(&authorities));
+        assert!(!p1.verify(&authorities[1..]));
+
+        let h2 = header(2, Some(p1.proof.clone()));
+        let p2 = LightClientProof::new(&h2, &authorities, commit(&keypairs, &h2, 1));
+        assert!(p2.verify_next(&p1, &authorities));
+
+        let forged = header(2, None);
+        let mut p3 = LightClientProof::new(&forged, &authorities, p2.proof.clone());
+        assert!(!p3.verify(&authorities));
+        p3.version = 2;
+        assert!(LightClientProof::decode(&p3.encode()).is_none());
+    }
+}
@@ -47,9 +47,9 @@ mod dispatch;
 
 use docopt::Docopt;
 use libproto::{parse_msg, MsgClass, key_to_id};
-use pubsub::start_pubsub;
+use pubsub::{start_pubsub, QUEUE_SIZE};
 use raft_server::*;
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::mpsc::{channel, sync_channel, Receiver};
 use log::LogLevelFilter;
 use std::thread;
 
@@ -87,13 +87,19 @@ fn main() {
     cita_log::format(LogLevelFilter::Info);
     let args: Args = Docopt::new(USAGE).and_then(|d| d.decode()).unwrap_or_else(|e| e.exit());
     info!("CITA:raft");
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
     let (tx, rx) = channel();
     start_pubsub("consensus_cmd", vec!["chain.status", "consensus.default"], tx_sub, rx_pub);
     thread::spawn(move || loop {
         let (key, body) = rx_sub.recv().unwrap();
-        let (cmd_id, _, content) = parse_msg(body.as_slice());
+        let (cmd_id, _, content) = match parse_msg(body.as_slice()) {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                warn!("drop msg of {}: {}", key, reason);
+                continue;
+            }
+        };
         tx.send((key_to_id(&key), cmd_id, content)).unwrap();
     });
 
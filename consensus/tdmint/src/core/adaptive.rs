@@ -0,0 +1,161 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use core::params::TendermintTimer;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const WINDOW_SIZE: usize = 10;
+const SCALE_UP: u32 = 125;
+const SCALE_DOWN: u32 = 90;
+/// Every step waits at least this many round trips of the network.
+const RTT_FACTOR: u32 = 3;
+
+/// Scales the propose, prevote and precommit timeouts by the recent rounds.
+/// The timeouts grow when blocks need more than one round, and shrink when
+/// the rounds finish far before the timeouts for a whole window. They are
+/// never below a few round trips of the network measured by the pings.
+pub struct AdaptiveTimeout {
+    base: TendermintTimer,
+    // percent of the base timeouts
+    scale: u32,
+    min_scale: u32,
+    max_scale: u32,
+    history: VecDeque<(usize, Duration)>,
+    rtt: Duration,
+}
+
+impl AdaptiveTimeout {
+    pub fn new(base: TendermintTimer, min_scale: u32, max_scale: u32) -> Self {
+        AdaptiveTimeout {
+            base: base,
+            scale: 100,
+            min_scale: min_scale,
+            max_scale: max_scale,
+            history: VecDeque::with_capacity(WINDOW_SIZE),
+            rtt: Duration::from_millis(0),
+        }
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn timer(&self) -> TendermintTimer {
+        let floor = self.rtt * RTT_FACTOR;
+        let scaled = |d: Duration| ::std::cmp::max(d * self.scale / 100, floor);
+        TendermintTimer {
+            propose: scaled(self.base.propose),
+            prevote: scaled(self.base.prevote),
+            precommit: scaled(self.base.precommit),
+            commit: self.base.commit,
+        }
+    }
+
+    /// Round trip time of the network to two thirds of the peers.
+    /// Returns the new timer if the timeouts have changed.
+    pub fn set_rtt(&mut self, rtt: Duration) -> Option<TendermintTimer> {
+        let old = self.timer();
+        self.rtt = rtt;
+        let timer = self.timer();
+        if timer.propose != old.propose || timer.prevote != old.prevote || timer.precommit != old.precommit {
+            info!("network rtt {:?}, timeouts {:?}", rtt, timer);
+            Some(timer)
+        } else {
+            None
+        }
+    }
+
+    /// Record a committed block, `latency` is the time the last round took.
+    /// Returns the new timer if the timeouts have changed.
+    pub fn observe(&mut self, rounds: usize, latency: Duration) -> Option<TendermintTimer> {
+        if self.history.len() == WINDOW_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back((rounds, latency));
+
+        let old = self.scale;
+        if rounds > 1 {
+            self.scale = ::std::cmp::min(self.max_scale, self.scale * SCALE_UP / 100);
+        } else if self.history.len() == WINDOW_SIZE {
+            let timer = self.timer();
+            let bound = timer.propose + timer.prevote + timer.precommit;
+            if self.history.iter().all(|&(r, l)| r == 1 && l * 2 < bound) {
+                self.scale = ::std::cmp::max(self.min_scale, self.scale * SCALE_DOWN / 100);
+                self.history.clear();
+            }
+        }
+
+        if old != self.scale {
+            info!("adjust timeout scale from {}% to {}%", old, self.scale);
+            Some(self.timer())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptiveTimeout;
+    use core::params::TendermintTimer;
+    use std::time::Duration;
+
+    #[test]
+    fn scale_up_on_extra_rounds() {
+        let mut adaptive = AdaptiveTimeout::new(TendermintTimer::default(), 50, 200);
+        let timer = adaptive.observe(2, Duration::from_millis(100)).unwrap();
+        assert_eq!(adaptive.scale(), 125);
+        assert_eq!(timer.propose, Duration::from_millis(1750));
+        assert_eq!(timer.commit, TendermintTimer::default().commit);
+
+        for _ in 0..5 {
+            adaptive.observe(3, Duration::from_millis(100));
+        }
+        assert_eq!(adaptive.scale(), 200);
+    }
+
+    #[test]
+    fn scale_down_on_fast_rounds() {
+        let mut adaptive = AdaptiveTimeout::new(TendermintTimer::default(), 80, 200);
+        for _ in 0..9 {
+            assert!(adaptive.observe(1, Duration::from_millis(100)).is_none());
+        }
+        assert!(adaptive.observe(1, Duration::from_millis(100)).is_some());
+        assert_eq!(adaptive.scale(), 90);
+        for _ in 0..10 {
+            adaptive.observe(1, Duration::from_millis(100));
+        }
+        assert_eq!(adaptive.scale(), 81);
+        for _ in 0..10 {
+            adaptive.observe(1, Duration::from_millis(100));
+        }
+        assert_eq!(adaptive.scale(), 80);
+    }
+
+    #[test]
+    fn rtt_floor() {
+        let mut adaptive = AdaptiveTimeout::new(TendermintTimer::default(), 50, 200);
+        let base = TendermintTimer::default();
+        assert!(adaptive.set_rtt(Duration::from_millis(10)).is_none());
+        let timer = adaptive.set_rtt(base.prevote).unwrap();
+        assert_eq!(timer.prevote, base.prevote * 3);
+        assert_eq!(timer.commit, base.commit);
+        let timer = adaptive.set_rtt(Duration::from_millis(0)).unwrap();
+        assert_eq!(timer.prevote, base.prevote);
+    }
+}
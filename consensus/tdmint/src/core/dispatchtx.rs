@@ -15,39 +15,125 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-extern crate threadpool;
-
-use core::txhandler::TxHandler;
+use cita_log;
+use core::metrics::{AuthCounter, AUTH_METRICS_INTERVAL};
+use core::params::TendermintParams;
+use core::precheck::NonceCheck;
+use core::permission::Permissions;
+use core::ratelimit::RateLimit;
+use core::senderfilter::{sender_of, SenderFilter};
+use core::txhandler::{TransType, TxHandler, TxLimits, PIPELINE_QUEUE};
+use core::txaudit::{AuditEntry, TxAudit};
+use core::txhistory::TxHistory;
 use core::txwal::Txwal;
-use libproto::{submodules, topics, factory, communication};
+use libproto::{submodules, topics, factory, communication, parse_msg, MsgClass};
+use libproto::request::Response;
 use libproto::blockchain::{TxResponse, SignedTransaction};
+use libproto::shutdown::Shutdown;
 use protobuf::Message;
-use pubsub::start_pubsub;
-use std::sync::{RwLock, Arc};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use rustc_serialize::hex::ToHex;
+use serde_json;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock, Arc};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender};
 use std::thread;
-use tx_pool::Pool;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+use util::U256;
+use tx_pool::{Enqueued, Pool, PoolLimits, PoolStats};
+
+/// Journal of the events of the transactions under the DATA_PATH.
+pub const TX_AUDIT_PATH: &'static str = "/txaudit.log";
+
+// Verified transactions added to the pool at a time.
+const VERIFIED_BATCH_SIZE: usize = 1000;
 
 pub struct Dispatchtx {
     tx_pool: Arc<RwLock<Pool>>,
     wal: Txwal,
     filter_wal: Txwal,
+    history: Option<TxHistory>,
     data_from_pool: AtomicBool,
     pool_limit: usize,
+    chain_id: u32,
+    sender_filter: SenderFilter,
+    auth_counter: Mutex<AuthCounter>,
+    nonce_check: Option<NonceCheck>,
+    chain_responses: Mutex<Sender<Response>>,
+    audit: Option<TxAudit>,
+    tx_limits: TxLimits,
+    tx_rate_limit: RateLimit,
+    permissions: RwLock<Permissions>,
+    // height of the next block after the last commit, and that the pool is swept for
+    next_height: AtomicUsize,
+    swept_height: AtomicUsize,
+}
+
+// The reason replied for a transaction refused by the pool, None if accepted.
+fn rejection(result: &Enqueued) -> Option<&'static str> {
+    match *result {
+        Enqueued::New | Enqueued::Replaced(_) | Enqueued::Queued | Enqueued::Future | Enqueued::Bundled => None,
+        Enqueued::Duplicate => Some("DUP"),
+        Enqueued::Underpriced => Some("UNDERPRICED"),
+        Enqueued::AccountLimit => Some("ACCOUNT_LIMIT"),
+        Enqueued::PoolFull => Some("POOL_FULL"),
+        Enqueued::Expired => Some("EXPIRED"),
+        Enqueued::BadBundle => Some("BAD_BUNDLE"),
+    }
+}
+
+// How a transaction accepted by the pool is held.
+fn admission(result: &Enqueued) -> &'static str {
+    match *result {
+        Enqueued::Replaced(_) => "replacing",
+        Enqueued::Queued => "queued",
+        Enqueued::Future => "future",
+        Enqueued::Bundled => "bundled",
+        _ => "pending",
+    }
 }
 
 #[allow(unused_assignments)]
 #[allow(unused)]
 impl Dispatchtx {
-    pub fn new(capacity: usize, package_limit: usize, limit: usize) -> Self {
+    pub fn new(params: &TendermintParams) -> Self {
+        let mut pool = Pool::new_with_strategy(params.tx_filter_size, params.block_tx_limit, params.tx_pool_strategy);
+        pool.set_price_bump(params.tx_price_bump);
+        pool.set_account_limit(params.tx_account_limit, params.tx_account_overflow);
+        pool.set_limits(PoolLimits {
+                            max_txs: params.tx_pool_capacity,
+                            max_bytes: params.tx_pool_max_bytes,
+                            ttl: params.tx_ttl,
+                        });
 
+        let (responses_tx, responses_rx) = channel();
         let mut dispatch = Dispatchtx {
-            tx_pool: Arc::new(RwLock::new(Pool::new(capacity, package_limit))),
+            tx_pool: Arc::new(RwLock::new(pool)),
             wal: Txwal::new("/txwal"),
             filter_wal: Txwal::new("/filterwal"),
+            history: if params.tx_history { Some(TxHistory::new("/txhistory", params.tx_history_blocks)) } else { None },
             data_from_pool: AtomicBool::new(false),
-            pool_limit: limit,
+            pool_limit: params.tx_pool_size,
+            chain_id: params.chain_id,
+            sender_filter: SenderFilter::new(params.tx_blacklist.clone(), params.tx_allowlist.clone()),
+            auth_counter: Mutex::new(AuthCounter::new()),
+            nonce_check: if params.tx_pre_check { Some(NonceCheck::new(params.tx_filter_size, responses_rx)) } else { None },
+            chain_responses: Mutex::new(responses_tx),
+            next_height: AtomicUsize::new(0),
+            swept_height: AtomicUsize::new(0),
+            permissions: RwLock::new(Permissions::default()),
+            tx_rate_limit: RateLimit {
+                rate: params.tx_rate_limit,
+                burst: params.tx_rate_burst,
+            },
+            tx_limits: TxLimits {
+                max_bytes: params.tx_max_bytes,
+                max_data_bytes: params.tx_max_data_bytes,
+            },
+            audit: if params.tx_audit { Some(Dispatchtx::open_audit(params.tx_audit_max_bytes)) } else { None },
         };
 
         let num = dispatch.read_tx_from_wal();
@@ -58,18 +144,55 @@ impl Dispatchtx {
     }
 
 
-    pub fn add_tx_to_pool(&self, tx: &SignedTransaction) -> bool {
+    fn open_audit(max_bytes: u64) -> TxAudit {
+        let path = env::var("DATA_PATH").expect(format!("{} must be set", "DATA_PATH").as_str()) + TX_AUDIT_PATH;
+        TxAudit::open(&path, max_bytes).expect("Failed to open the tx audit journal.")
+    }
+
+    fn audit(&self, entries: Vec<AuditEntry>) {
+        if let Some(ref audit) = self.audit {
+            audit.record(entries);
+        }
+    }
+
+    pub fn add_tx_to_pool(&self, tx: &SignedTransaction) -> Enqueued {
+        self.add_txs_to_pool(&[tx.clone()]).remove(0)
+    }
+
+    /// Add the transactions under one lock of the pool, and journal them in one write.
+    pub fn add_txs_to_pool(&self, txs: &[SignedTransaction]) -> Vec<Enqueued> {
         //交易放入pool，
         //放入pool完成后，持久化
         let mut tx_pool = self.tx_pool.write().unwrap();
-        let trans = tx.clone();
-        let success = tx_pool.enqueue(trans);
-        if success {
-            self.wal.write(&tx);
-        } else {
-            self.filter_wal.write(&tx);
+        let mut results = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let hash = tx.crypt_hash();
+            // committed out of the window of the filter
+            let committed = !tx_pool.seen(&hash) && self.history.as_ref().map_or(false, |history| history.contains(&hash));
+            results.push(if committed { Enqueued::Duplicate } else { tx_pool.add(tx.clone()) });
+        }
+        let (added, filtered): (Vec<_>, Vec<_>) = txs.iter().zip(results.iter()).partition(|&(_, result)| result.is_ok());
+        self.wal.write_batch(added.into_iter().map(|(tx, _)| tx));
+        self.filter_wal.write_batch(filtered.into_iter().map(|(tx, _)| tx));
+
+        let mut wal = self.wal.clone();
+        let mut entries = Vec::new();
+        for (tx, result) in txs.iter().zip(results.iter()) {
+            entries.push(match rejection(result) {
+                             Some(reason) => AuditEntry::new(tx.get_tx_hash(), "rejected").reason(reason),
+                             None => AuditEntry::new(tx.get_tx_hash(), "accepted").reason(admission(result)),
+                         });
+            if let Enqueued::Replaced(ref old) = *result {
+                wal.delete(old);
+                entries.push(AuditEntry::new(old.get_tx_hash(), "replaced").reason(&format!("by 0x{}", tx.get_tx_hash().to_hex())));
+            }
+        }
+        for evicted in tx_pool.take_evicted() {
+            wal.delete(&evicted);
+            entries.push(AuditEntry::new(evicted.get_tx_hash(), "evicted").reason("POOL_FULL"));
         }
-        success
+        self.audit(entries);
+        results
     }
 
     pub fn get_txs_from_pool(&self, height: u64) -> Vec<SignedTransaction> {
@@ -77,8 +200,17 @@ impl Dispatchtx {
             self.data_from_pool.store(false, Ordering::SeqCst);
             Vec::new()
         } else {
-            let mut tx_pool = self.tx_pool.write().unwrap();
-            let txs = tx_pool.package(height);
+            let (txs, expired) = self.tx_pool.write().unwrap().package_and_expire(height);
+            let mut entries: Vec<_> = txs.iter().map(|tx| AuditEntry::new(tx.get_tx_hash(), "packaged").height(height)).collect();
+            entries.extend(expired.iter().map(|tx| AuditEntry::new(tx.get_tx_hash(), "expired").height(height)));
+            self.audit(entries);
+            if !expired.is_empty() {
+                // keep the journal to the pending transactions
+                let mut wal = self.wal.clone();
+                thread::spawn(move || for tx in expired {
+                                  wal.delete(&tx);
+                              });
+            }
             txs
         }
     }
@@ -93,11 +225,43 @@ impl Dispatchtx {
         if tx_pool.len() > self.pool_limit { true } else { false }
     }
 
-    pub fn del_txs_from_pool(&self, txs: Vec<SignedTransaction>) {
+    /// Package no more transactions than fit the block quota limit on chain.
+    pub fn set_quota_limit(&self, quota_limit: Option<u64>, quota_per_tx: u64) {
+        self.tx_pool.write().unwrap().set_quota_limit(quota_limit, quota_per_tx);
+    }
+
+    pub fn tx_pool_len(&self) -> usize {
+        self.tx_pool.read().unwrap().len()
+    }
+
+    pub fn tx_pool_stats(&self) -> PoolStats {
+        self.tx_pool.read().unwrap().stats()
+    }
+
+    /// Number of the transactions refused as committed before, found out of the window of the filter.
+    pub fn tx_history_hits(&self) -> u64 {
+        self.history.as_ref().map_or(0, |history| history.hits())
+    }
+
+    pub fn del_txs_from_pool(&self, height: u64, txs: Vec<SignedTransaction>) {
         //收到删除通知，从pool中删除vec中的交易
         {
             self.tx_pool.write().unwrap().update(&txs);
         }
+        self.audit(txs.iter().map(|tx| AuditEntry::new(tx.get_tx_hash(), "committed").height(height)).collect());
+        // the transactions expired at the next height are swept by the auth thread
+        self.next_height.store(height as usize + 1, Ordering::SeqCst);
+        if let Some(ref history) = self.history {
+            history.insert(height, &txs);
+        }
+        if let Some(ref check) = self.nonce_check {
+            for tx in &txs {
+                let nonce = U256::from_str(tx.get_transaction_with_sig().get_transaction().get_nonce());
+                if let (Some(sender), Ok(nonce)) = (sender_of(tx), nonce) {
+                    check.committed(&sender, nonce);
+                }
+            }
+        }
         //改成多线程删除数据
         let mut wal = self.wal.clone();
         thread::spawn(move || for tx in txs {
@@ -105,69 +269,215 @@ impl Dispatchtx {
                       });
     }
 
-    fn receive_new_transaction(&self, tx: &mut SignedTransaction, tx_pub: Sender<(String, Vec<u8>)>, from_broadcast: bool, recover: bool) {
-        if from_broadcast {
-            if recover {
-                let _ = self.add_tx_to_pool(tx);
+    fn receive_new_transactions(&self, txs: Vec<TransType>, tx_pub: Sender<(String, Vec<u8>)>) {
+        let busy = self.tx_flow_control();
+        let mut counter = self.auth_counter.lock().unwrap();
+        counter.verified(txs.len());
+        let mut accepted = Vec::new();
+        // whether the sender waits for a response
+        let mut respond = Vec::new();
+        let mut refused = Vec::new();
+        // the trace ids of the requests, logged along with the responses
+        let mut traces = HashMap::new();
+        for (id, verdict, tx, trace) in txs {
+            let from_broadcast = id == submodules::NET;
+            // signed for another chain, or in a version not known
+            let chain_ok = tx.get_transaction_with_sig().get_transaction().check_chain(self.chain_id);
+            let sender_ok = self.sender_filter.allows_tx(&tx);
+            let refused = self.permissions.read().unwrap().refuses(&tx);
+            let result = if let Some(reason) = verdict.reason() {
+                reason
+            } else if !chain_ok {
+                "BAD CHAIN ID"
+            } else if !sender_ok {
+                "BLACKLISTED"
+            } else if let Some(reason) = refused {
+                reason
+            } else if busy && !from_broadcast {
+                "BUSY"
+            } else {
+                counter.received(&tx);
+                traces.insert(tx.get_tx_hash().to_vec(), trace);
+                accepted.push(tx);
+                respond.push(!from_broadcast);
+                continue;
+            };
+            counter.rejected(result);
+            refused.push(AuditEntry::new(tx.get_tx_hash(), "rejected").reason(result));
+            if from_broadcast {
+                continue;
             }
-        } else {
             let mut content = TxResponse::new();
             content.set_hash(tx.tx_hash.clone());
-            if !recover {
-                content.set_result(String::from("BAD SIG").into_bytes());
-            } else {
-                if self.tx_flow_control() {
-                    content.set_result(String::from("BUSY").into_bytes());
-                } else {
-                    let success = self.add_tx_to_pool(tx);
-                    if success {
-                        //info!("receive_new_transaction {:?}", hash);
-                        content.set_result(String::from("4:OK").into_bytes());
-                        let msg = factory::create_msg(submodules::CONSENSUS, topics::NEW_TX, communication::MsgType::TX, tx.write_to_bytes().unwrap());
-                        tx_pub.send(("consensus.tx".to_string(), msg.write_to_bytes().unwrap())).unwrap();
-
-                    } else {
-                        content.set_result(String::from("4:DUP").into_bytes());
-                    }
+            content.set_result(String::from(result).into_bytes());
+            cita_log::set_trace_id(&trace);
+            Dispatchtx::respond(&tx_pub, content);
+        }
+        self.audit(refused);
+
+        if let Some(ref check) = self.nonce_check {
+            // only those sent to this node wait for the chain
+            let senders: Vec<_> = accepted.iter().zip(respond.iter()).filter(|&(_, respond)| *respond).filter_map(|(tx, _)| sender_of(tx)).collect();
+            check.query(&senders, &tx_pub);
+            let (fresh, stale): (Vec<_>, Vec<_>) = accepted.into_iter().zip(respond).partition(|&(ref tx, respond)| !respond || !Dispatchtx::is_stale(check, tx));
+            self.audit(stale.iter().map(|&(ref tx, _)| AuditEntry::new(tx.get_tx_hash(), "rejected").reason("STALE_NONCE")).collect());
+            for (tx, _) in stale {
+                counter.rejected("STALE_NONCE");
+                let mut content = TxResponse::new();
+                content.set_hash(tx.tx_hash.clone());
+                content.set_result(String::from("4:STALE_NONCE").into_bytes());
+                cita_log::set_trace_id(traces.get(tx.get_tx_hash()).map_or(&[][..], |trace| &trace[..]));
+                Dispatchtx::respond(&tx_pub, content);
+            }
+            let (txs, responds) = fresh.into_iter().unzip();
+            accepted = txs;
+            respond = responds;
+        }
+
+        let results = self.add_txs_to_pool(&accepted);
+        for ((tx, result), respond) in accepted.iter().zip(results).zip(respond) {
+            let rejected = rejection(&result);
+            if let Some(reason) = rejected {
+                counter.rejected(reason);
+            }
+            if !respond {
+                continue;
+            }
+            cita_log::set_trace_id(traces.get(tx.get_tx_hash()).map_or(&[][..], |trace| &trace[..]));
+            let mut content = TxResponse::new();
+            content.set_hash(tx.tx_hash.clone());
+            match rejected {
+                None => {
+                    //info!("receive_new_transaction {:?}", hash);
+                    content.set_result(String::from("4:OK").into_bytes());
+                    let msg = factory::create_msg(submodules::CONSENSUS, topics::NEW_TX, communication::MsgType::TX, tx.write_to_bytes().unwrap());
+                    tx_pub.send(("consensus.tx".to_string(), msg.write_to_bytes().unwrap())).unwrap();
                 }
+                Some(reason) => content.set_result(format!("4:{}", reason).into_bytes()),
             }
-            let msg = factory::create_msg(submodules::CONSENSUS, topics::TX_RESPONSE, communication::MsgType::TX_RESPONSE, content.write_to_bytes().unwrap());
-            //trace!("response new tx {:?}", tx);
-            tx_pub.send(("consensus.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+            Dispatchtx::respond(&tx_pub, content);
         }
+        cita_log::set_trace_id(&[]);
+    }
+
+    // Whether the nonce of the transaction is used by a committed one of the sender.
+    fn is_stale(check: &NonceCheck, tx: &SignedTransaction) -> bool {
+        let nonce = U256::from_str(tx.get_transaction_with_sig().get_transaction().get_nonce());
+        match (sender_of(tx), nonce) {
+            (Some(sender), Ok(nonce)) => check.is_stale(&sender, &nonce),
+            _ => false,
+        }
+    }
+
+    /// Hand a response of the chain over to the check of the nonces waiting for it.
+    pub fn receive_chain_response(&self, body: &[u8]) {
+        if let Ok((_, _, MsgClass::RESPONSE(response))) = parse_msg(body) {
+            if NonceCheck::is_answer(&response) {
+                let _ = self.chain_responses.lock().unwrap().send(response);
+            }
+        }
+    }
+
+    /// Take the accounts permitted on chain as of the last block.
+    pub fn receive_permissions(&self, body: &[u8]) {
+        if let Ok((_, _, MsgClass::MSG(content))) = parse_msg(body) {
+            match Permissions::decode(&content) {
+                Some(permissions) => *self.permissions.write().unwrap() = permissions,
+                None => warn!("bad permissions from the chain"),
+            }
+        }
+    }
+
+    fn respond(tx_pub: &Sender<(String, Vec<u8>)>, content: TxResponse) {
+        let msg = factory::create_msg(submodules::CONSENSUS, topics::TX_RESPONSE, communication::MsgType::TX_RESPONSE, content.write_to_bytes().unwrap());
+        //trace!("response new tx {:?}", tx);
+        tx_pub.send(("consensus.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
     }
 
     pub fn read_tx_from_wal(&mut self) -> u64 {
         let mut tx_pool = self.tx_pool.write().unwrap();
-        self.wal.read(&mut tx_pool)
+        let num = self.wal.read(&mut tx_pool);
+        let mut wal = self.wal.clone();
+        let mut entries = Vec::new();
+        for evicted in tx_pool.take_evicted() {
+            wal.delete(&evicted);
+            entries.push(AuditEntry::new(evicted.get_tx_hash(), "evicted").reason("POOL_FULL"));
+        }
+        self.audit(entries);
+        num
     }
 
-    pub fn process(&self, rx: &Receiver<(u32, bool, SignedTransaction)>, tx_pub: Sender<(String, Vec<u8>)>) {
-        let res = rx.recv().unwrap();
-        let (id, recover, mut tx) = res;
-        let from_broadcast = id == submodules::NET;
-        if from_broadcast {
-            self.receive_new_transaction(&mut tx, tx_pub, from_broadcast, recover);
-        } else {
-            self.receive_new_transaction(&mut tx, tx_pub, from_broadcast, recover);
+    /// Take the transactions verified by the workers, as many as there are up to a batch,
+    /// and publish the statistics of the interval when it is over.
+    pub fn process(&self, rx: &Receiver<TransType>, shutdown: &Shutdown, tx_pub: Sender<(String, Vec<u8>)>) {
+        if let Ok(tx) = rx.recv_timeout(Duration::from_secs(AUTH_METRICS_INTERVAL)) {
+            // a shutdown waits for the batch to be journaled
+            let _work = shutdown.working();
+            let mut txs = vec![tx];
+            while txs.len() < VERIFIED_BATCH_SIZE {
+                match rx.try_recv() {
+                    Ok(tx) => txs.push(tx),
+                    Err(_) => break,
+                }
+            }
+            self.receive_new_transactions(txs, tx_pub.clone());
+        }
+        self.sweep_expired();
+        self.pub_auth_metrics(&tx_pub);
+    }
+
+    /// Drop the transactions no longer valid at the next height, once a height.
+    fn sweep_expired(&self) {
+        let height = self.next_height.load(Ordering::SeqCst);
+        if self.swept_height.swap(height, Ordering::SeqCst) >= height {
+            return;
+        }
+        let height = height as u64;
+        let expired = self.tx_pool.write().unwrap().expire(height);
+        if expired.is_empty() {
+            return;
+        }
+        info!("{} transactions expired at height {}", expired.len(), height);
+        self.audit(expired.iter().map(|tx| AuditEntry::new(tx.get_tx_hash(), "expired").height(height)).collect());
+        let mut wal = self.wal.clone();
+        for tx in &expired {
+            wal.delete(tx);
+        }
+    }
+
+    fn pub_auth_metrics(&self, tx_pub: &Sender<(String, Vec<u8>)>) {
+        let mut counter = self.auth_counter.lock().unwrap();
+        if counter.elapsed() < Duration::from_secs(AUTH_METRICS_INTERVAL) {
+            return;
+        }
+        let metrics = counter.report(self.tx_pool_stats());
+        info!("auth metrics {:?}", metrics);
+        if let Ok(content) = serde_json::to_vec(&metrics) {
+            tx_pub.send(("consensus.auth_metrics".to_string(), content)).unwrap();
         }
     }
 }
 
-pub fn sub_new_tx(dispatch: Arc<Dispatchtx>, num_thds: usize) {
+pub fn sub_new_tx(dispatch: Arc<Dispatchtx>, num_thds: usize, shutdown: Shutdown) {
     let _ = thread::Builder::new().name("consensus_new_tx".to_string()).spawn(move || {
-        let (tx, rx) = channel();
-        let threadpool = threadpool::ThreadPool::with_name("consensus_recv_tx_pool".to_string(), num_thds);
-        let mut handler = TxHandler::new(threadpool, tx);
-        let (tx_sub, rx_sub) = channel();
+        let (tx, rx) = sync_channel(PIPELINE_QUEUE);
+        let mut handler = TxHandler::new(num_thds, tx, dispatch.tx_limits, dispatch.tx_rate_limit);
+        let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
         let (tx_pub, rx_pub) = channel();
-        start_pubsub("consensus_tx", vec!["net.tx", "jsonrpc.new_tx"], tx_sub, rx_pub);
+        start_pubsub("consensus_tx", vec!["net.tx", "jsonrpc.new_tx", "chain.auth", "chain.permissions"], tx_sub, rx_pub);
+        let sub_dispatch = dispatch.clone();
         thread::spawn(move || loop {
                           let (key, body) = rx_sub.recv().unwrap();
-                          handler.handle(key, body);
+                          if key == "chain.auth" {
+                              sub_dispatch.receive_chain_response(&body);
+                          } else if key == "chain.permissions" {
+                              sub_dispatch.receive_permissions(&body);
+                          } else {
+                              handler.handle(key, body);
+                          }
                       });
         loop {
-            dispatch.process(&rx, tx_pub.clone());
+            dispatch.process(&rx, &shutdown, tx_pub.clone());
         }
     });
 }
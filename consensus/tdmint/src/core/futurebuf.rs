@@ -0,0 +1,131 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use core::tendermint::Step;
+use std::collections::{BTreeSet, HashMap};
+use util::Address;
+
+/// Heights after the current one to buffer messages for.
+pub const MAX_FUTURE_HEIGHTS: usize = 3;
+/// Rounds after the current one to buffer messages for.
+pub const MAX_FUTURE_ROUNDS: usize = 8;
+/// Future messages buffered for each sender.
+pub const SENDER_QUOTA: usize = 32;
+
+type Key = (usize, usize, Step);
+
+/// Bounds the votes buffered for future heights and rounds.
+///
+/// Only messages inside the window are accepted, and each sender can keep at
+/// most `SENDER_QUOTA` of them. When the quota is full a nearer message replaces
+/// the farthest one, so early messages of the next rounds are never lost to
+/// far-future ones.
+#[derive(Debug, Default)]
+pub struct FutureQuota {
+    buffered: HashMap<Address, BTreeSet<Key>>,
+}
+
+/// Whether the message is near enough to the current height and round to be kept.
+pub fn in_window(height: usize, round: usize, cur_height: usize, cur_round: usize) -> bool {
+    if height == cur_height {
+        round <= cur_round + MAX_FUTURE_ROUNDS
+    } else {
+        height > cur_height && height <= cur_height + MAX_FUTURE_HEIGHTS && round <= MAX_FUTURE_ROUNDS
+    }
+}
+
+impl FutureQuota {
+    pub fn new() -> Self {
+        FutureQuota { buffered: HashMap::new() }
+    }
+
+    /// Admit a message of the sender. Returns `Err(())` if it must be dropped,
+    /// otherwise the message evicted to make room for it.
+    pub fn admit(&mut self, sender: Address, key: Key, cur_height: usize, cur_round: usize) -> Result<Option<Key>, ()> {
+        let (height, round, _) = key;
+        if !in_window(height, round, cur_height, cur_round) {
+            return Err(());
+        }
+        if height == cur_height && round <= cur_round {
+            return Ok(None);
+        }
+
+        let keys = self.buffered.entry(sender).or_insert_with(BTreeSet::new);
+        // forget the messages which are not in the future any more
+        let expired: Vec<Key> = keys.iter().cloned().filter(|&(h, r, _)| h < cur_height || (h == cur_height && r <= cur_round)).collect();
+        for k in expired {
+            keys.remove(&k);
+        }
+        if keys.contains(&key) {
+            return Ok(None);
+        }
+
+        let mut evicted = None;
+        if keys.len() >= SENDER_QUOTA {
+            let farthest = *keys.iter().next_back().unwrap();
+            if key >= farthest {
+                return Err(());
+            }
+            keys.remove(&farthest);
+            evicted = Some(farthest);
+        }
+        keys.insert(key);
+        Ok(evicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::tendermint::Step;
+    use util::Address;
+
+    #[test]
+    fn reject_out_of_window() {
+        let mut quota = FutureQuota::new();
+        let sender = Address::from(1);
+        assert!(quota.admit(sender, (10, 0, Step::Prevote), 10, 0).is_ok());
+        assert!(quota.admit(sender, (10, MAX_FUTURE_ROUNDS + 1, Step::Prevote), 10, 0).is_err());
+        assert!(quota.admit(sender, (10 + MAX_FUTURE_HEIGHTS, 0, Step::Prevote), 10, 0).is_ok());
+        assert!(quota.admit(sender, (11 + MAX_FUTURE_HEIGHTS, 0, Step::Prevote), 10, 0).is_err());
+    }
+
+    #[test]
+    fn evict_farthest_when_full() {
+        let mut quota = FutureQuota::new();
+        let sender = Address::from(1);
+        let mut n = 0;
+        'outer: for h in 11..14 {
+            for r in 0..MAX_FUTURE_ROUNDS + 1 {
+                for step in vec![Step::Prevote, Step::Precommit] {
+                    if n == SENDER_QUOTA {
+                        break 'outer;
+                    }
+                    assert_eq!(quota.admit(sender, (h, r, step), 10, 0), Ok(None));
+                    n += 1;
+                }
+            }
+        }
+        // far messages are dropped, near ones replace the farthest
+        assert!(quota.admit(sender, (13, 8, Step::Precommit), 10, 0).is_err());
+        assert_eq!(quota.admit(sender, (10, 1, Step::Prevote), 10, 0), Ok(Some((12, 6, Step::Precommit))));
+        // other senders have their own quota
+        assert_eq!(quota.admit(Address::from(2), (13, 0, Step::Prevote), 10, 0), Ok(None));
+        // buffered messages become current
+        assert_eq!(quota.admit(sender, (13, 0, Step::Prevote), 11, 0), Ok(None));
+    }
+}
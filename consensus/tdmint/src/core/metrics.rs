@@ -0,0 +1,291 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use core::senderfilter::sender_of;
+use core::tendermint::Step;
+use libproto::blockchain::SignedTransaction;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tx_pool::PoolStats;
+use util::Address;
+
+/// Interval in seconds the statistics of the incoming transactions are published at.
+pub const AUTH_METRICS_INTERVAL: u64 = 10;
+/// Senders of the most transactions reported in the statistics.
+pub const TOP_SENDERS: usize = 10;
+
+const TIME_BUCKETS: [u64; 10] = [10, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000];
+const ROUND_BUCKETS: [u64; 5] = [1, 2, 3, 5, 10];
+
+fn as_millis(d: Duration) -> u64 {
+    d.as_secs() * 1000 + (d.subsec_nanos() / 1_000_000) as u64
+}
+
+/// Cumulative histogram, the last bucket counts the values above all bounds.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bounds: Vec<u64>,
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[u64]) -> Self {
+        Histogram {
+            bounds: bounds.to_vec(),
+            buckets: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0,
+        }
+    }
+
+    pub fn observe(&mut self, value: u64) {
+        let idx = self.bounds.iter().position(|b| value <= *b).unwrap_or(self.bounds.len());
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Transactions held by the tx pool, and those it has dropped.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct TxPoolMetrics {
+    pub pending: usize,
+    pub queued: usize,
+    pub future: usize,
+    pub bytes: usize,
+    pub evicted: u64,
+    pub expired: u64,
+    pub filter_hits: u64,
+    pub filter_misses: u64,
+    /// Transactions refused as committed before, out of the window of the filter.
+    pub history_hits: u64,
+}
+
+impl From<PoolStats> for TxPoolMetrics {
+    fn from(stats: PoolStats) -> Self {
+        TxPoolMetrics {
+            pending: stats.pending,
+            queued: stats.queued,
+            future: stats.future,
+            bytes: stats.bytes,
+            evicted: stats.evicted,
+            expired: stats.expired,
+            filter_hits: stats.filter_hits,
+            filter_misses: stats.filter_misses,
+            history_hits: 0,
+        }
+    }
+}
+
+/// Transactions of a sender in the interval.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SenderCount {
+    pub address: Address,
+    pub txs: u64,
+}
+
+/// Statistics of the incoming transactions in an interval, published for jsonrpc and the monitoring.
+#[derive(Serialize, Debug, Clone)]
+pub struct AuthMetrics {
+    pub tx_pool: TxPoolMetrics,
+    /// Transactions verified by the workers, and the rate per second.
+    pub verified: u64,
+    pub verify_rate: f64,
+    /// Transactions refused by the reason replied.
+    pub rejected: HashMap<String, u64>,
+    pub top_senders: Vec<SenderCount>,
+}
+
+/// Counts the incoming transactions until the statistics of the interval are reported.
+#[derive(Debug)]
+pub struct AuthCounter {
+    verified: u64,
+    rejected: HashMap<String, u64>,
+    senders: HashMap<Address, u64>,
+    since: Instant,
+}
+
+impl AuthCounter {
+    pub fn new() -> Self {
+        AuthCounter {
+            verified: 0,
+            rejected: HashMap::new(),
+            senders: HashMap::new(),
+            since: Instant::now(),
+        }
+    }
+
+    pub fn verified(&mut self, n: usize) {
+        self.verified += n as u64;
+    }
+
+    pub fn rejected(&mut self, reason: &str) {
+        *self.rejected.entry(reason.to_string()).or_insert(0) += 1;
+    }
+
+    /// Count a transaction passing the checks to its sender.
+    pub fn received(&mut self, tx: &SignedTransaction) {
+        if let Some(sender) = sender_of(tx) {
+            *self.senders.entry(sender).or_insert(0) += 1;
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now() - self.since
+    }
+
+    /// The statistics since the last report, the counting starts over.
+    pub fn report(&mut self, stats: PoolStats) -> AuthMetrics {
+        let elapsed = self.elapsed();
+        let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        let mut top_senders: Vec<_> = self.senders.drain().map(|(address, txs)| SenderCount { address: address, txs: txs }).collect();
+        top_senders.sort_by(|a, b| b.txs.cmp(&a.txs));
+        top_senders.truncate(TOP_SENDERS);
+        let metrics = AuthMetrics {
+            tx_pool: stats.into(),
+            verified: self.verified,
+            verify_rate: if secs > 0.0 { self.verified as f64 / secs } else { 0.0 },
+            rejected: ::std::mem::replace(&mut self.rejected, HashMap::new()),
+            top_senders: top_senders,
+        };
+        self.verified = 0;
+        self.since = Instant::now();
+        metrics
+    }
+}
+
+/// Counters of the consensus engine, reported to the operators periodically.
+#[derive(Serialize, Debug, Clone)]
+pub struct ConsensusMetrics {
+    pub height: usize,
+    pub blocks: u64,
+    pub proposer_misses: u64,
+    pub rounds_per_block: Histogram,
+    pub step_duration: HashMap<String, Histogram>,
+    pub vote_latency: HashMap<String, Histogram>,
+    pub tx_pool: TxPoolMetrics,
+    #[serde(skip_serializing)]
+    step: Step,
+    #[serde(skip_serializing)]
+    step_start: Instant,
+    #[serde(skip_serializing)]
+    round_start: Instant,
+}
+
+impl ConsensusMetrics {
+    pub fn new() -> Self {
+        ConsensusMetrics {
+            height: 0,
+            blocks: 0,
+            proposer_misses: 0,
+            rounds_per_block: Histogram::new(&ROUND_BUCKETS),
+            step_duration: HashMap::new(),
+            vote_latency: HashMap::new(),
+            tx_pool: TxPoolMetrics::default(),
+            step: Step::Propose,
+            step_start: Instant::now(),
+            round_start: Instant::now(),
+        }
+    }
+
+    /// Record the time spent in the previous step.
+    pub fn enter_step(&mut self, step: Step) {
+        if step == self.step {
+            return;
+        }
+        let now = Instant::now();
+        let elapsed = as_millis(now - self.step_start);
+        self.step_duration.entry(format!("{:?}", self.step)).or_insert_with(|| Histogram::new(&TIME_BUCKETS)).observe(elapsed);
+        self.step = step;
+        self.step_start = now;
+    }
+
+    pub fn round_start(&mut self) {
+        self.round_start = Instant::now();
+    }
+
+    pub fn round_elapsed(&self) -> Duration {
+        Instant::now() - self.round_start
+    }
+
+    /// Record the arrival of a vote of the current round.
+    pub fn vote_received(&mut self, step: Step) {
+        let elapsed = as_millis(Instant::now() - self.round_start);
+        self.vote_latency.entry(format!("{:?}", step)).or_insert_with(|| Histogram::new(&TIME_BUCKETS)).observe(elapsed);
+    }
+
+    pub fn proposer_missed(&mut self) {
+        self.proposer_misses += 1;
+    }
+
+    pub fn block_committed(&mut self, height: usize, round: usize) {
+        self.height = height;
+        self.blocks += 1;
+        self.rounds_per_block.observe((round + 1) as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthCounter, ConsensusMetrics, Histogram};
+    use core::tendermint::Step;
+
+    #[test]
+    fn histogram_observe() {
+        let mut h = Histogram::new(&[1, 2, 5]);
+        h.observe(1);
+        h.observe(3);
+        h.observe(9);
+        assert_eq!(h.buckets, vec![1, 0, 1, 1]);
+        assert_eq!(h.count, 3);
+        assert_eq!(h.sum, 13);
+    }
+
+    #[test]
+    fn metrics_record() {
+        let mut m = ConsensusMetrics::new();
+        m.enter_step(Step::ProposeWait);
+        m.enter_step(Step::ProposeWait);
+        m.enter_step(Step::Prevote);
+        m.vote_received(Step::Prevote);
+        m.proposer_missed();
+        m.block_committed(3, 1);
+        assert_eq!(m.step_duration["Propose"].count, 1);
+        assert_eq!(m.step_duration["ProposeWait"].count, 1);
+        assert_eq!(m.vote_latency["Prevote"].count, 1);
+        assert_eq!(m.proposer_misses, 1);
+        assert_eq!(m.rounds_per_block.buckets[1], 1);
+        assert_eq!(m.blocks, 1);
+    }
+
+    #[test]
+    fn auth_report() {
+        let mut c = AuthCounter::new();
+        c.verified(3);
+        c.rejected("BAD SIG");
+        c.rejected("DUP");
+        c.rejected("BAD SIG");
+        let m = c.report(Default::default());
+        assert_eq!(m.verified, 3);
+        assert_eq!(m.rejected["BAD SIG"], 2);
+        assert!(m.top_senders.is_empty());
+        // counted over in the next interval
+        assert_eq!(c.report(Default::default()).verified, 0);
+    }
+}
@@ -24,6 +24,19 @@ pub mod spec;
 pub mod dispatchtx;
 pub mod txwal;
 pub mod txhandler;
+pub mod verifier;
+pub mod metrics;
+pub mod rotation;
+pub mod adaptive;
+pub mod proposer;
+pub mod signguard;
+pub mod futurebuf;
+pub mod senderfilter;
+pub mod txhistory;
+pub mod precheck;
+pub mod txaudit;
+pub mod ratelimit;
+pub mod permission;
 
 pub use self::params::*;
 pub use self::spec::*;
@@ -15,12 +15,20 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use core::txaudit::DEFAULT_AUDIT_MAX_BYTES;
+use core::txhandler::{DEFAULT_MAX_DATA_BYTES, DEFAULT_MAX_TX_BYTES};
 use ed25519::Signer;
 use engine_json;
 use std::time::Duration;
+use tx_pool::{AccountOverflow, Strategy, DEFAULT_PRICE_BUMP};
 use util::Address;
 
-#[derive(Debug, Clone)]
+const DEFAULT_MAX_IDLE_DURATION: u64 = 60_000;
+const DEFAULT_MIN_TIMEOUT_SCALE: u32 = 50;
+const DEFAULT_MAX_TIMEOUT_SCALE: u32 = 400;
+const DEFAULT_PROPOSER_SKIP_HEIGHTS: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TendermintTimer {
     pub propose: Duration,
     pub prevote: Duration,
@@ -51,12 +59,67 @@ pub struct TendermintParams {
     pub block_tx_limit: usize,
     pub tx_filter_size: usize,
     pub tx_pool_size: usize,
+    /// Wait for transactions instead of proposing empty blocks
+    pub skip_empty_block: bool,
+    /// Upper bound of the interval between two blocks when skipping empty blocks
+    pub max_idle_duration: Duration,
+    /// Verify and commit blocks of the others, but never sign
+    pub observer: bool,
+    /// Key replacing the signer once the rotation recorded on chain takes effect
+    pub next_signer: Option<Signer>,
+    /// Adjust the timeouts within the scale bounds in percent
+    pub adaptive_timeout: bool,
+    pub min_timeout_scale: u32,
+    pub max_timeout_scale: u32,
+    /// Skip the proposers missing their slots in a row, zero means never skip
+    pub proposer_miss_limit: usize,
+    pub proposer_skip_heights: usize,
+    /// Do not start a new height before the interval since the last commit
+    pub block_interval: Duration,
+    /// Accept only the transactions signed for the chain
+    pub chain_id: u32,
+    /// Percent of quota price raise a replacement of a pending transaction requires
+    pub tx_price_bump: u64,
+    /// Pending transactions of a sender in the tx pool, zero means no limit
+    pub tx_account_limit: usize,
+    pub tx_account_overflow: AccountOverflow,
+    /// Bounds of the tx pool, zero means no bound
+    pub tx_pool_capacity: usize,
+    pub tx_pool_max_bytes: usize,
+    pub tx_ttl: Option<Duration>,
+    /// Senders refused by the tx pool, and the only ones accepted if an allowlist is given
+    pub tx_blacklist: Vec<Address>,
+    pub tx_allowlist: Option<Vec<Address>>,
+    pub tx_pool_strategy: Strategy,
+    /// Refuse the transactions committed before, beyond the recent ones of the filter
+    pub tx_history: bool,
+    /// Blocks the hashes of the committed transactions are kept for, zero keeps all of them
+    pub tx_history_blocks: u64,
+    /// Refuse the transactions with a nonce used, by the nonces the chain answers
+    pub tx_pre_check: bool,
+    /// Journal the events of the transactions in the pool, rotated over the bytes
+    pub tx_audit: bool,
+    pub tx_audit_max_bytes: u64,
+    /// Raw bytes of a transaction and bytes of its data accepted, zero means no bound
+    pub tx_max_bytes: usize,
+    pub tx_max_data_bytes: usize,
+    /// Transactions a source may submit per second, and at most at once, zero means no limit
+    pub tx_rate_limit: u64,
+    pub tx_rate_burst: u64,
 }
 
 fn to_duration(s: u64) -> Duration {
     Duration::from_millis(s)
 }
 
+fn to_strategy(name: Option<String>) -> Strategy {
+    match name.as_ref().map(String::as_str) {
+        None | Some("fifo") => Strategy::FIFO,
+        Some("quota_price") => Strategy::QUOTA_PRICE,
+        Some(name) => panic!("unknown tx pool strategy {}", name),
+    }
+}
+
 impl From<engine_json::TendermintParams> for TendermintParams {
     fn from(p: engine_json::TendermintParams) -> Self {
         let dt = TendermintTimer::default();
@@ -69,6 +132,35 @@ impl From<engine_json::TendermintParams> for TendermintParams {
             block_tx_limit: p.block_tx_limit as usize,
             tx_filter_size: p.tx_filter_size as usize,
             tx_pool_size: p.tx_pool_size as usize,
+            skip_empty_block: p.skip_empty_block.unwrap_or(false),
+            max_idle_duration: to_duration(p.max_idle_duration.unwrap_or(DEFAULT_MAX_IDLE_DURATION)),
+            observer: p.observer.unwrap_or(false),
+            next_signer: p.next_signer.map(Signer::from),
+            adaptive_timeout: p.adaptive_timeout.unwrap_or(false),
+            min_timeout_scale: p.min_timeout_scale.unwrap_or(DEFAULT_MIN_TIMEOUT_SCALE),
+            max_timeout_scale: p.max_timeout_scale.unwrap_or(DEFAULT_MAX_TIMEOUT_SCALE),
+            proposer_miss_limit: p.proposer_miss_limit.unwrap_or(0) as usize,
+            proposer_skip_heights: p.proposer_skip_heights.unwrap_or(DEFAULT_PROPOSER_SKIP_HEIGHTS) as usize,
+            block_interval: to_duration(p.block_interval.unwrap_or(0)),
+            chain_id: p.chain_id.unwrap_or(0),
+            tx_price_bump: p.tx_price_bump.unwrap_or(DEFAULT_PRICE_BUMP),
+            tx_account_limit: p.tx_account_limit.unwrap_or(0) as usize,
+            tx_account_overflow: if p.tx_account_queue.unwrap_or(false) { AccountOverflow::Queue } else { AccountOverflow::Reject },
+            tx_pool_capacity: p.tx_pool_capacity.unwrap_or(0) as usize,
+            tx_pool_max_bytes: p.tx_pool_max_bytes.unwrap_or(0) as usize,
+            tx_ttl: p.tx_ttl.map(Duration::from_secs),
+            tx_blacklist: p.tx_blacklist.unwrap_or_default().into_iter().map(Into::into).collect(),
+            tx_allowlist: p.tx_allowlist.map(|list| list.into_iter().map(Into::into).collect()),
+            tx_pool_strategy: to_strategy(p.tx_pool_strategy),
+            tx_history: p.tx_history.unwrap_or(true),
+            tx_history_blocks: p.tx_history_blocks.unwrap_or(0),
+            tx_pre_check: p.tx_pre_check.unwrap_or(false),
+            tx_audit: p.tx_audit.unwrap_or(true),
+            tx_audit_max_bytes: p.tx_audit_max_bytes.unwrap_or(DEFAULT_AUDIT_MAX_BYTES),
+            tx_max_bytes: p.tx_max_bytes.map_or(DEFAULT_MAX_TX_BYTES, |n| n as usize),
+            tx_max_data_bytes: p.tx_max_data_bytes.map_or(DEFAULT_MAX_DATA_BYTES, |n| n as usize),
+            tx_rate_limit: p.tx_rate_limit.unwrap_or(0),
+            tx_rate_burst: p.tx_rate_burst.unwrap_or(0),
             timer: TendermintTimer {
                 propose: p.timeout_propose.map_or(dt.propose, to_duration),
                 prevote: p.timeout_prevote.map_or(dt.prevote, to_duration),
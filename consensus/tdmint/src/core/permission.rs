@@ -0,0 +1,93 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use core::senderfilter::sender_of;
+use libproto::blockchain::SignedTransaction;
+use std::collections::HashSet;
+use util::Address;
+
+const ADDRESS_LEN: usize = 20;
+
+/// Accounts permitted on chain to send transactions and to deploy contracts, as the
+/// chain sends them for each block, so that auth refuses the transactions the chain
+/// would not apply. Anyone is permitted by an empty list.
+#[derive(Debug, Default, PartialEq)]
+pub struct Permissions {
+    senders: HashSet<Address>,
+    creators: HashSet<Address>,
+}
+
+impl Permissions {
+    /// The number of the senders in 4 bytes, then the senders and the creators, 20 bytes for each address.
+    pub fn decode(content: &[u8]) -> Option<Self> {
+        if content.len() < 4 || (content.len() - 4) % ADDRESS_LEN != 0 {
+            return None;
+        }
+        let senders = content[..4].iter().fold(0usize, |acc, b| (acc << 8) + *b as usize);
+        let accounts: Vec<Address> = content[4..].chunks(ADDRESS_LEN).map(Address::from_slice).collect();
+        if senders > accounts.len() {
+            return None;
+        }
+        Some(Permissions {
+                 senders: accounts[..senders].iter().cloned().collect(),
+                 creators: accounts[senders..].iter().cloned().collect(),
+             })
+    }
+
+    /// The reason the transaction is refused for, none if it is permitted.
+    pub fn refuses(&self, tx: &SignedTransaction) -> Option<&'static str> {
+        if self.senders.is_empty() && self.creators.is_empty() {
+            return None;
+        }
+        // transactions without a valid signer are left to the signature check
+        let sender = match sender_of(tx) {
+            Some(sender) => sender,
+            None => return None,
+        };
+        let creates = tx.get_transaction_with_sig().get_transaction().get_to().is_empty();
+        if !self.senders.is_empty() && !self.senders.contains(&sender) {
+            Some("NO_SEND_PERMISSION")
+        } else if creates && !self.creators.is_empty() && !self.creators.contains(&sender) {
+            Some("NO_CREATE_PERMISSION")
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let mut content = vec![0, 0, 0, 1];
+        content.extend_from_slice(&Address::from(1));
+        content.extend_from_slice(&Address::from(2));
+        content.extend_from_slice(&Address::from(3));
+        let permissions = Permissions::decode(&content).unwrap();
+        assert!(permissions.senders.contains(&Address::from(1)));
+        assert_eq!(permissions.creators.len(), 2);
+
+        assert_eq!(Permissions::decode(&[0, 0, 0, 0]), Some(Permissions::default()));
+        assert_eq!(Permissions::decode(&[0, 0, 0, 2, 1]), None);
+        assert_eq!(Permissions::decode(&content[..24]).map(|p| p.senders.len()), Some(1));
+        let mut over = vec![0, 0, 0, 2];
+        over.extend_from_slice(&Address::from(1));
+        assert_eq!(Permissions::decode(&over), None);
+    }
+}
@@ -0,0 +1,153 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use jsonrpc_types::rpctypes::{BlockNumber, CountAndCode};
+use libproto::{submodules, topics, factory, communication};
+use libproto::request::{Request, Response};
+use lru_cache::LruCache;
+use protobuf::Message;
+use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, Instant};
+use util::{Address, U256};
+
+/// Milliseconds to wait for the chain to answer the nonces of a batch.
+pub const PRE_CHECK_TIMEOUT: u64 = 300;
+/// Prefix of the ids of the requests sent by the check.
+const REQUEST_PREFIX: &'static [u8] = b"auth.";
+
+/// Nonces of the accounts queried from the chain, to refuse the transactions
+/// with a nonce already used before they enter the pool.
+///
+/// The nonces are kept for the recent senders and moved by the committed
+/// transactions. A sender the chain does not answer in time is not checked.
+pub struct NonceCheck {
+    nonces: Mutex<LruCache<Address, U256>>,
+    responses: Mutex<Receiver<Response>>,
+    seq: AtomicUsize,
+}
+
+impl NonceCheck {
+    pub fn new(capacity: usize, responses: Receiver<Response>) -> Self {
+        NonceCheck {
+            nonces: Mutex::new(LruCache::new(capacity)),
+            responses: Mutex::new(responses),
+            seq: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether the response answers a request of the check.
+    pub fn is_answer(response: &Response) -> bool {
+        response.get_request_id().starts_with(REQUEST_PREFIX)
+    }
+
+    fn request_id(&self) -> Vec<u8> {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut id = REQUEST_PREFIX.to_vec();
+        id.extend_from_slice(seq.to_string().as_bytes());
+        id
+    }
+
+    /// Query the chain for the nonces of the senders not known, waiting for the answers until the timeout.
+    pub fn query(&self, senders: &[Address], tx_pub: &Sender<(String, Vec<u8>)>) {
+        let unknown: HashSet<Address> = {
+            let mut nonces = self.nonces.lock().unwrap();
+            senders.iter().filter(|sender| !nonces.contains_key(sender)).cloned().collect()
+        };
+        if unknown.is_empty() {
+            return;
+        }
+        let mut pending = HashMap::new();
+        for sender in unknown {
+            let id = self.request_id();
+            let mut req = Request::new();
+            req.set_request_id(id.clone());
+            req.set_transaction_count(serde_json::to_string(&CountAndCode::new(sender.to_vec(), BlockNumber::default())).unwrap());
+            let msg = factory::create_msg(submodules::CONSENSUS, topics::REQUEST, communication::MsgType::REQUEST, req.write_to_bytes().unwrap());
+            tx_pub.send(("consensus.request".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+            pending.insert(id, sender);
+        }
+
+        let responses = self.responses.lock().unwrap();
+        let deadline = Instant::now() + Duration::from_millis(PRE_CHECK_TIMEOUT);
+        while !pending.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let response = match responses.recv_timeout(deadline - now) {
+                Ok(response) => response,
+                Err(_) => break,
+            };
+            if let Some(sender) = pending.remove(response.get_request_id()) {
+                if response.has_transaction_count() {
+                    self.nonces.lock().unwrap().insert(sender, U256::from(response.get_transaction_count()));
+                }
+            }
+        }
+        if !pending.is_empty() {
+            warn!("nonces of {} senders not answered by the chain in time", pending.len());
+        }
+    }
+
+    /// Whether the nonce is used by a committed transaction of the sender, the senders not known pass.
+    pub fn is_stale(&self, sender: &Address, nonce: &U256) -> bool {
+        self.nonces.lock().unwrap().get_mut(sender).map_or(false, |next| *nonce < *next)
+    }
+
+    /// Move the nonce of a known sender past a committed transaction.
+    pub fn committed(&self, sender: &Address, nonce: U256) {
+        if let Some(next) = self.nonces.lock().unwrap().get_mut(sender) {
+            if *next <= nonce {
+                *next = nonce + U256::one();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn nonce_answered() {
+        let (tx, rx) = channel();
+        let (tx_pub, rx_pub) = channel();
+        let check = NonceCheck::new(10, rx);
+        let sender = Address::from(1);
+
+        let mut response = Response::new();
+        response.set_request_id(b"auth.1".to_vec());
+        response.set_transaction_count(2);
+        assert!(NonceCheck::is_answer(&response));
+        tx.send(response).unwrap();
+        check.query(&[sender, sender], &tx_pub);
+        // asked once for the sender
+        assert!(rx_pub.try_recv().is_ok());
+        assert!(rx_pub.try_recv().is_err());
+
+        assert!(check.is_stale(&sender, &U256::from(1)));
+        assert!(!check.is_stale(&sender, &U256::from(2)));
+        check.committed(&sender, U256::from(2));
+        assert!(check.is_stale(&sender, &U256::from(2)));
+        assert!(!check.is_stale(&Address::from(2), &U256::zero()));
+    }
+}
@@ -0,0 +1,143 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use util::Address;
+
+/// Tracks the proposers missing their slots, and skips them in the rotation
+/// after `miss_limit` misses in a row.
+///
+/// The misses are counted from the rounds of the last committed blocks, which
+/// chain reads from the proofs in the blocks, so the nodes replaying the same
+/// blocks agree on the skipped proposers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposerTracker {
+    miss_limit: usize,
+    skip_heights: usize,
+    misses: HashMap<Address, usize>,
+    // proposer -> height the proposer joins the rotation again
+    skipped: HashMap<Address, usize>,
+}
+
+impl ProposerTracker {
+    pub fn new(miss_limit: usize, skip_heights: usize) -> Self {
+        ProposerTracker {
+            miss_limit: miss_limit,
+            skip_heights: skip_heights,
+            misses: HashMap::new(),
+            skipped: HashMap::new(),
+        }
+    }
+
+    /// The proposer of the round, the skipped ones are left out.
+    pub fn proposer<'a>(&self, authorities: &'a [Address], height: usize, round: usize) -> &'a Address {
+        let nonce = height + round;
+        let candidates: Vec<&Address> = authorities.iter().filter(|a| !self.is_skipped(a, height)).collect();
+        if candidates.is_empty() {
+            &authorities[nonce % authorities.len()]
+        } else {
+            candidates[nonce % candidates.len()]
+        }
+    }
+
+    pub fn is_skipped(&self, address: &Address, height: usize) -> bool {
+        self.skipped.get(address).map_or(false, |until| height < *until)
+    }
+
+    /// Update the misses by the block committed at the round.
+    /// Returns the proposers skipped from now on.
+    pub fn commit(&mut self, authorities: &[Address], height: usize, round: usize) -> Vec<Address> {
+        if self.miss_limit == 0 {
+            return Vec::new();
+        }
+        self.skipped.retain(|_, until| height < *until);
+
+        let proposers: Vec<Address> = (0..round + 1).map(|r| *self.proposer(authorities, height, r)).collect();
+        let mut skipped = Vec::new();
+        for (r, proposer) in proposers.into_iter().enumerate() {
+            if r == round {
+                self.misses.remove(&proposer);
+                continue;
+            }
+            let misses = {
+                let count = self.misses.entry(proposer).or_insert(0);
+                *count += 1;
+                *count
+            };
+            if misses >= self.miss_limit && !self.skipped.contains_key(&proposer) {
+                self.misses.remove(&proposer);
+                self.skipped.insert(proposer, height + 1 + self.skip_heights);
+                skipped.push(proposer);
+            }
+        }
+        skipped
+    }
+
+    /// Decode the commit rounds sent by chain, 16 bytes for each block, the height
+    /// and the round it was committed at in 8 bytes each.
+    pub fn decode_rounds(msg: &[u8]) -> Vec<(usize, usize)> {
+        let read = |bytes: &[u8]| bytes.iter().fold(0u64, |acc, b| (acc << 8) + *b as u64) as usize;
+        msg.chunks(16).filter(|chunk| chunk.len() == 16).map(|chunk| (read(&chunk[..8]), read(&chunk[8..]))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProposerTracker;
+    use util::Address;
+
+    #[test]
+    fn skip_failing_proposer() {
+        let authorities: Vec<Address> = (0..4).map(|i| Address::from(i as u64)).collect();
+        let mut tracker = ProposerTracker::new(2, 10);
+
+        // proposer of height 1 round 0 is authorities[1]
+        assert!(tracker.commit(&authorities, 1, 1).is_empty());
+        // proposer of height 5 round 0 is authorities[1] again
+        assert_eq!(tracker.commit(&authorities, 5, 1), vec![authorities[1]]);
+
+        assert!(tracker.is_skipped(&authorities[1], 6));
+        assert_eq!(*tracker.proposer(&authorities, 6, 0), authorities[0]);
+        assert!(!tracker.is_skipped(&authorities[1], 16));
+        assert_eq!(*tracker.proposer(&authorities, 16, 0), authorities[0]);
+    }
+
+    #[test]
+    fn committed_proposer_resets_misses() {
+        let authorities: Vec<Address> = (0..4).map(|i| Address::from(i as u64)).collect();
+        let mut tracker = ProposerTracker::new(2, 10);
+        assert!(tracker.commit(&authorities, 1, 1).is_empty());
+        assert!(tracker.commit(&authorities, 5, 0).is_empty());
+        assert!(tracker.commit(&authorities, 9, 1).is_empty());
+    }
+
+    #[test]
+    fn decode_rounds() {
+        let mut msg = vec![0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 1];
+        msg.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(ProposerTracker::decode_rounds(&msg), vec![(5, 1), (6, 0)]);
+        assert_eq!(ProposerTracker::decode_rounds(&msg[..20]), vec![(5, 1)]);
+    }
+
+    #[test]
+    fn disabled_tracker() {
+        let authorities: Vec<Address> = (0..4).map(|i| Address::from(i as u64)).collect();
+        let mut tracker = ProposerTracker::new(0, 10);
+        assert!(tracker.commit(&authorities, 1, 3).is_empty());
+        assert_eq!(*tracker.proposer(&authorities, 1, 0), authorities[1]);
+    }
+}
@@ -0,0 +1,125 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Sources tracked at most, the idle ones are forgotten over it.
+const MAX_SOURCES: usize = 10000;
+
+/// Transactions a source may submit per second, and at most at once, zero rate means no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RateLimit {
+    pub rate: u64,
+    pub burst: u64,
+}
+
+/// A source of transactions, the submodule they come from and their origin in it,
+/// the peer for the network and the client for the jsonrpc.
+pub type Source = (u32, u32);
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    throttled: bool,
+}
+
+fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1_000_000_000f64
+}
+
+/// Token buckets of the sources, to throttle the floods of transactions before
+/// their signatures are recovered.
+pub struct SourceLimiter {
+    limit: RateLimit,
+    buckets: HashMap<Source, Bucket>,
+}
+
+impl SourceLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        SourceLimiter {
+            limit: limit,
+            buckets: HashMap::new(),
+        }
+    }
+
+    fn burst(&self) -> f64 {
+        cmp::max(self.limit.burst, self.limit.rate) as f64
+    }
+
+    /// Whether a transaction of the source is allowed now, taking a token if so.
+    pub fn allow(&mut self, source: Source, now: Instant) -> bool {
+        if self.limit.rate == 0 {
+            return true;
+        }
+        if self.buckets.len() >= MAX_SOURCES && !self.buckets.contains_key(&source) {
+            self.prune(now);
+        }
+        let burst = self.burst();
+        let rate = self.limit.rate as f64;
+        let bucket = self.buckets.entry(source).or_insert(Bucket {
+                                                              tokens: burst,
+                                                              last: now,
+                                                              throttled: false,
+                                                          });
+        if now > bucket.last {
+            bucket.tokens = (bucket.tokens + secs(now - bucket.last) * rate).min(burst);
+            bucket.last = now;
+        }
+        if bucket.tokens >= 1f64 {
+            bucket.tokens -= 1f64;
+            bucket.throttled = false;
+            true
+        } else {
+            if !bucket.throttled {
+                warn!("throttling the transactions from {:?}", source);
+                bucket.throttled = true;
+            }
+            false
+        }
+    }
+
+    // Forget the sources whose buckets are full again.
+    fn prune(&mut self, now: Instant) {
+        let burst = self.burst();
+        let rate = self.limit.rate as f64;
+        self.buckets.retain(|_, bucket| now <= bucket.last || bucket.tokens + secs(now - bucket.last) * rate < burst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle() {
+        let mut limiter = SourceLimiter::new(RateLimit { rate: 2, burst: 3 });
+        let now = Instant::now();
+        let peer = (1, 7);
+        assert!((0..3).all(|_| limiter.allow(peer, now)));
+        assert!(!limiter.allow(peer, now));
+        // other sources are not held back
+        assert!(limiter.allow((1, 8), now));
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.allow(peer, later));
+        assert!(!limiter.allow(peer, later));
+
+        let mut unlimited = SourceLimiter::new(RateLimit::default());
+        assert!((0..100).all(|_| unlimited.allow(peer, now)));
+    }
+}
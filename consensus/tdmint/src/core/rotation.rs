@@ -0,0 +1,164 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use util::Address;
+
+// rotations older than this are not needed to check proofs any more
+const HISTORY_SIZE: usize = 16;
+
+/// Validator replacing its consensus key from `height`, recorded by the node manager on chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRotation {
+    pub height: usize,
+    pub old: Address,
+    pub new: Address,
+}
+
+impl KeyRotation {
+    /// Decode the rotations sent by chain, 48 bytes for each of them, the height
+    /// in 8 bytes, then the old and the new key.
+    pub fn decode_list(msg: &[u8]) -> Vec<KeyRotation> {
+        msg.chunks(48)
+           .filter(|chunk| chunk.len() == 48)
+           .map(|chunk| {
+                    KeyRotation {
+                        height: chunk[..8].iter().fold(0u64, |acc, b| (acc << 8) + *b as u64) as usize,
+                        old: Address::from_slice(&chunk[8..28]),
+                        new: Address::from_slice(&chunk[28..48]),
+                    }
+                })
+           .collect()
+    }
+}
+
+/// Authorities of the genesis with the rotations recorded on chain applied.
+pub struct Authorities {
+    genesis: Vec<Address>,
+    rotations: Vec<KeyRotation>,
+    current: Vec<Address>,
+    // height a rotation took effect -> authorities before it
+    history: BTreeMap<usize, Vec<Address>>,
+    pending: BTreeMap<usize, Vec<KeyRotation>>,
+}
+
+impl Authorities {
+    pub fn new(authorities: Vec<Address>) -> Self {
+        Authorities {
+            genesis: authorities.clone(),
+            rotations: Vec::new(),
+            current: authorities,
+            history: BTreeMap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub fn current(&self) -> &Vec<Address> {
+        &self.current
+    }
+
+    /// Authorities which signed the blocks of the height.
+    pub fn at(&self, height: usize) -> &Vec<Address> {
+        match self.history.range(height + 1..).next() {
+            Some((_, authorities)) => authorities,
+            None => &self.current,
+        }
+    }
+
+    /// Replace the rotations by those on chain and replay them from the genesis,
+    /// returns false if they have not changed.
+    pub fn set_rotations(&mut self, rotations: Vec<KeyRotation>) -> bool {
+        if rotations == self.rotations {
+            return false;
+        }
+        self.current = self.genesis.clone();
+        self.history.clear();
+        self.pending.clear();
+        for rotation in &rotations {
+            self.pending.entry(rotation.height).or_insert_with(Vec::new).push(rotation.clone());
+        }
+        self.rotations = rotations;
+        true
+    }
+
+    /// Apply the rotations taking effect up to the height, returns the applied ones.
+    pub fn apply(&mut self, height: usize) -> Vec<KeyRotation> {
+        let heights: Vec<usize> = self.pending.range(..height + 1).map(|(h, _)| *h).collect();
+        let mut applied = Vec::new();
+        for h in heights {
+            let before = self.current.clone();
+            for rotation in self.pending.remove(&h).unwrap() {
+                if let Some(pos) = self.current.iter().position(|a| *a == rotation.old) {
+                    if !self.current.contains(&rotation.new) {
+                        // keep the position so the proposer order does not change
+                        self.current[pos] = rotation.new;
+                        applied.push(rotation);
+                    }
+                }
+            }
+            if before != self.current {
+                self.history.insert(h, before);
+            }
+        }
+        while self.history.len() > HISTORY_SIZE {
+            let first = *self.history.keys().next().unwrap();
+            self.history.remove(&first);
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Authorities, KeyRotation};
+    use util::Address;
+
+    #[test]
+    fn rotation_decode_list() {
+        let mut msg = vec![0, 0, 0, 0, 0, 0, 0, 10];
+        msg.extend_from_slice(&Address::from(1).0);
+        msg.extend_from_slice(&Address::from(2).0);
+        let rotation = KeyRotation {
+            height: 10,
+            old: Address::from(1),
+            new: Address::from(2),
+        };
+        assert_eq!(KeyRotation::decode_list(&msg), vec![rotation]);
+        assert!(KeyRotation::decode_list(&msg[..47]).is_empty());
+    }
+
+    #[test]
+    fn rotation_apply() {
+        let (a, b, c, d) = (Address::from(1), Address::from(2), Address::from(3), Address::from(4));
+        let mut authorities = Authorities::new(vec![a, b]);
+        let rotations = vec![KeyRotation { height: 5, old: b, new: c }, KeyRotation { height: 5, old: c, new: a }, KeyRotation { height: 8, old: c, new: d }];
+        assert!(authorities.set_rotations(rotations.clone()));
+        assert!(!authorities.set_rotations(rotations.clone()));
+
+        assert!(authorities.apply(4).is_empty());
+        assert_eq!(authorities.apply(5).len(), 1);
+        assert_eq!(authorities.current(), &vec![a, c]);
+        assert_eq!(authorities.at(4), &vec![a, b]);
+        assert_eq!(authorities.at(5), &vec![a, c]);
+
+        // replayed from the genesis when the rotations on chain change
+        assert!(authorities.set_rotations(rotations[..1].to_vec()));
+        assert_eq!(authorities.current(), &vec![a, b]);
+        assert_eq!(authorities.apply(9).len(), 1);
+        assert_eq!(authorities.current(), &vec![a, c]);
+    }
+}
@@ -0,0 +1,84 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use ed25519::{pubkey_to_address, PubKey, PUBKEY_BYTES_LEN};
+use libproto::blockchain::SignedTransaction;
+use std::collections::HashSet;
+use util::Address;
+
+/// Address of the signer of the transaction, none without a valid signer.
+pub fn sender_of(tx: &SignedTransaction) -> Option<Address> {
+    let signer = tx.get_signer();
+    if signer.len() != PUBKEY_BYTES_LEN {
+        return None;
+    }
+    Some(pubkey_to_address(&PubKey::from_slice(signer)))
+}
+
+/// Decides by the sender whether a transaction may enter the tx pool.
+///
+/// A sender in the blacklist is always refused. With an allowlist, only the
+/// senders in it are accepted.
+#[derive(Debug, Default)]
+pub struct SenderFilter {
+    blacklist: HashSet<Address>,
+    allowlist: Option<HashSet<Address>>,
+}
+
+impl SenderFilter {
+    pub fn new(blacklist: Vec<Address>, allowlist: Option<Vec<Address>>) -> Self {
+        SenderFilter {
+            blacklist: blacklist.into_iter().collect(),
+            allowlist: allowlist.map(|list| list.into_iter().collect()),
+        }
+    }
+
+    pub fn allows(&self, sender: &Address) -> bool {
+        !self.blacklist.contains(sender) && self.allowlist.as_ref().map_or(true, |list| list.contains(sender))
+    }
+
+    /// Whether the signer of the transaction is allowed, transactions without a valid signer are left to the signature check.
+    pub fn allows_tx(&self, tx: &SignedTransaction) -> bool {
+        if self.blacklist.is_empty() && self.allowlist.is_none() {
+            return true;
+        }
+        sender_of(tx).map_or(true, |sender| self.allows(&sender))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blacklist() {
+        let bad = Address::from(1);
+        let filter = SenderFilter::new(vec![bad], None);
+        assert!(!filter.allows(&bad));
+        assert!(filter.allows(&Address::from(2)));
+    }
+
+    #[test]
+    fn allowlist() {
+        let good = Address::from(1);
+        let filter = SenderFilter::new(vec![good], Some(vec![good, Address::from(2)]));
+        // the blacklist wins
+        assert!(!filter.allows(&good));
+        assert!(filter.allows(&Address::from(2)));
+        assert!(!filter.allows(&Address::from(3)));
+    }
+}
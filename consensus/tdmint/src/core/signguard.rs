@@ -0,0 +1,122 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bincode::{serialize, deserialize, Infinite};
+use core::tendermint::Step;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use util::H256;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct SignState {
+    height: usize,
+    // (round, step) -> signed hash of the height
+    signed: HashMap<(usize, Step), Option<H256>>,
+}
+
+/// Remembers what the validator has signed, and refuses to sign a different
+/// message for the same (height, round, step). The state is synced to disk
+/// before the signature is released, so it survives crashes.
+pub struct SignGuard {
+    path: String,
+    state: SignState,
+}
+
+impl SignGuard {
+    pub fn new(path: String) -> Self {
+        let mut state = SignState::default();
+        if let Ok(mut file) = File::open(&path) {
+            let mut content = Vec::new();
+            if file.read_to_end(&mut content).is_ok() {
+                if let Ok(decoded) = deserialize(&content[..]) {
+                    state = decoded;
+                }
+            }
+        }
+        SignGuard {
+            path: path,
+            state: state,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.state.height
+    }
+
+    /// Check if the message can be signed, and record it when it is signed first time.
+    pub fn check(&mut self, height: usize, round: usize, step: Step, hash: Option<H256>) -> bool {
+        if height < self.state.height {
+            warn!("refuse to sign height {} lower than signed height {}", height, self.state.height);
+            return false;
+        }
+        if height > self.state.height {
+            self.state.height = height;
+            self.state.signed.clear();
+        }
+        if let Some(signed) = self.state.signed.get(&(round, step)) {
+            if *signed != hash {
+                warn!("refuse to sign {:?} at height {} round {} step {:?}, signed {:?}", hash, height, round, step, signed);
+            }
+            return *signed == hash;
+        }
+        self.state.signed.insert((round, step), hash);
+        self.store();
+        true
+    }
+
+    fn store(&self) {
+        let tmp = self.path.clone() + ".tmp";
+        {
+            let mut file = File::create(&tmp).unwrap();
+            file.write_all(&serialize(&self.state, Infinite).unwrap()).unwrap();
+            file.sync_all().unwrap();
+        }
+        fs::rename(&tmp, &self.path).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignGuard;
+    use core::tendermint::Step;
+    use std::env;
+    use std::fs;
+    use util::H256;
+
+    #[test]
+    fn refuse_double_sign() {
+        let path = env::temp_dir().join("tdmint_sign_guard_test").to_str().unwrap().to_string();
+        let _ = fs::remove_file(&path);
+        let hash = Some(H256::from(1));
+        {
+            let mut guard = SignGuard::new(path.clone());
+            assert!(guard.check(2, 0, Step::Prevote, hash));
+            assert!(guard.check(2, 0, Step::Prevote, hash));
+            assert!(!guard.check(2, 0, Step::Prevote, Some(H256::default())));
+            assert!(guard.check(2, 0, Step::Precommit, Some(H256::default())));
+        }
+
+        // the state is kept after restarting
+        let mut guard = SignGuard::new(path.clone());
+        assert_eq!(guard.height(), 2);
+        assert!(!guard.check(2, 0, Step::Prevote, None));
+        assert!(!guard.check(1, 3, Step::Prevote, hash));
+        assert!(guard.check(3, 0, Step::Prevote, None));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -53,6 +53,12 @@ impl Spec {
         }
     }
 
+    /// Load the spec again from the path, to reload the settings which may change at runtime.
+    pub fn reload(path: &str) -> Result<Self, String> {
+        let config_file = File::open(path).map_err(|err| format!("can not read {}: {}", path, err))?;
+        Spec::load(BufReader::new(config_file))
+    }
+
     pub fn new_test_tendermint(path: &str) -> Self {
         let config_file = File::open(path).unwrap();
         let fconfig = BufReader::new(config_file);
@@ -16,8 +16,15 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use bincode::{serialize, deserialize, Infinite};
+use core::adaptive::AdaptiveTimeout;
 use core::dispatchtx::Dispatchtx;
-use core::params::TendermintParams;
+use core::futurebuf::{self, FutureQuota};
+use core::metrics::ConsensusMetrics;
+use core::params::{TendermintParams, TendermintTimer};
+use core::proposer::ProposerTracker;
+use core::rotation::{Authorities, KeyRotation};
+use core::signguard::SignGuard;
+use core::verifier::ProposalVerifier;
 use core::voteset::{VoteCollector, ProposalCollector, VoteSet, Proposal, VoteMessage};
 
 use core::votetime::{WaitTimer, TimeoutInfo};
@@ -26,17 +33,19 @@ use core::wal::Wal;
 use ed25519::{Signature, sign, recover, pubkey_to_address};
 use engine::{EngineError, Mismatch, unix_now, AsMillis};
 use libproto;
-use libproto::{communication, submodules, topics, MsgClass};
+use libproto::{communication, request, submodules, topics, MsgClass};
 use libproto::blockchain::{Block, SignedTransaction, Status};
+use libproto::shutdown::Shutdown;
 
 //use tx_pool::Pool;
 use proof::TendermintProof;
 use protobuf::{Message, RepeatedField};
 use protobuf::core::parse_from_bytes;
+use serde_json;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::mpsc::{Sender, Receiver, RecvError};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use util::{H256, H768};
 use util::Address;
 use util::Hashable;
@@ -52,16 +61,23 @@ const LOG_TYPE_COMMITS: u8 = 5;
 
 const ID_CONSENSUS_MSG: u32 = (submodules::CONSENSUS << 16) + topics::CONSENSUS_MSG as u32;
 const ID_NEW_PROPOSAL: u32 = (submodules::CONSENSUS << 16) + topics::NEW_PROPOSAL as u32;
+const ID_COMMIT_PROOF: u32 = (submodules::CONSENSUS << 16) + topics::COMMIT_PROOF as u32;
+const ID_KEY_ROTATION: u32 = (submodules::CHAIN << 16) + topics::KEY_ROTATION as u32;
+const ID_COMMIT_ROUNDS: u32 = (submodules::CHAIN << 16) + topics::COMMIT_ROUNDS as u32;
+const ID_SYS_CONFIG: u32 = (submodules::CHAIN << 16) + topics::SYS_CONFIG as u32;
+const ID_NET_RTT: u32 = (submodules::NET << 16) + topics::NET_RTT as u32;
 //const ID_NEW_STATUS: u32 = (submodules::CHAIN << 16) + topics::NEW_STATUS as u32;
 
 const TIMEOUT_RETRANSE_MULTIPLE: u32 = 5;
 const TIMEOUT_LOW_ROUND_MESSAGE_MULTIPLE: u32 = 10;
 const DATA_PATH: &'static str = "DATA_PATH";
+// blocks between the metrics logged
+const METRICS_REPORT_INTERVAL: u64 = 10;
 
 pub type TransType = (u32, u32, MsgClass);
 pub type PubType = (String, Vec<u8>);
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Clone, Copy, Hash)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Hash)]
 pub enum Step {
     Propose,
     Prevote,
@@ -103,6 +119,7 @@ pub struct TenderMint {
     timer_notity: Receiver<TimeoutInfo>,
 
     params: TendermintParams,
+    authorities: Authorities,
     height: usize,
     round: usize,
     step: Step,
@@ -122,17 +139,40 @@ pub struct TenderMint {
     // to be used for chain syncing
     //sync_ok :bool,
     dispatch: Arc<Dispatchtx>,
+    verifier: ProposalVerifier,
+    metrics: ConsensusMetrics,
+    adaptive: Option<AdaptiveTimeout>,
+    proposers: ProposerTracker,
+    sign_guard: SignGuard,
+    future_quota: FutureQuota,
 
     htime: Instant,
+    last_commit_time: Instant,
+    // height and time of the last commit proof sent to the lagging nodes
+    proof_pub: Option<(usize, Instant)>,
+    shutdown: Shutdown,
+    // timeouts of the config reloaded
+    timer_reload: Receiver<TendermintTimer>,
 }
 
 impl TenderMint {
-    pub fn new(s: Sender<PubType>, r: Receiver<TransType>, ts: Sender<TimeoutInfo>, rs: Receiver<TimeoutInfo>, params: TendermintParams, dispatch: Arc<Dispatchtx>) -> TenderMint {
+    pub fn new(s: Sender<PubType>, r: Receiver<TransType>, ts: Sender<TimeoutInfo>, rs: Receiver<TimeoutInfo>, params: TendermintParams, dispatch: Arc<Dispatchtx>, verifier: ProposalVerifier, shutdown: Shutdown, timer_reload: Receiver<TendermintTimer>) -> TenderMint {
         let proof = TendermintProof::default();
         if params.is_test {
             trace!("Run for test!");
         }
+        if params.observer {
+            info!("Run as observer, never propose or vote!");
+        }
+        let adaptive = if params.adaptive_timeout {
+            Some(AdaptiveTimeout::new(params.timer.clone(), params.min_timeout_scale, params.max_timeout_scale))
+        } else {
+            None
+        };
+        let proposers = ProposerTracker::new(params.proposer_miss_limit, params.proposer_skip_heights);
+        let authorities = Authorities::new(params.authorities.clone());
         let logpath = ::std::env::var(DATA_PATH).expect(format!("{} must be set", DATA_PATH).as_str()) + "/wal";
+        let sign_state_path = ::std::env::var(DATA_PATH).unwrap() + "/sign_state.bin";
 
         trace!("tx pool size {}", params.tx_pool_size);
         TenderMint {
@@ -143,6 +183,7 @@ impl TenderMint {
 
             //tx_pool:Pool::new(params.tx_filter_size,params.block_tx_limit),
             params: params,
+            authorities: authorities,
             height: 0,
             round: INIT_ROUND,
             step: Step::Propose,
@@ -160,16 +201,22 @@ impl TenderMint {
             //To be used later
             //sync_ok : true,
             dispatch: dispatch,
+            verifier: verifier,
+            metrics: ConsensusMetrics::new(),
+            adaptive: adaptive,
+            proposers: proposers,
+            sign_guard: SignGuard::new(sign_state_path),
+            future_quota: FutureQuota::new(),
             htime: Instant::now(),
+            last_commit_time: Instant::now(),
+            proof_pub: None,
+            shutdown: shutdown,
+            timer_reload: timer_reload,
         }
     }
 
     fn is_round_proposer(&self, height: usize, round: usize, address: &Address) -> Result<(), EngineError> {
-        let ref p = self.params;
-        let proposer_nonce = height + round;
-        let proposer = p.authorities
-                        .get(proposer_nonce % p.authority_n)
-                        .expect("There are authority_n authorities; taking number modulo authority_n gives number in authority_n range; qed");
+        let proposer = self.proposers.proposer(&self.params.authorities, height, round);
         if proposer == address {
             Ok(())
         } else {
@@ -192,16 +239,22 @@ impl TenderMint {
         let mut msg = communication::Message::new();
         msg.set_cmd_id(libproto::cmd_id(submodules::CONSENSUS, topics::NEW_BLK));
         msg.set_field_type(communication::MsgType::BLOCK);
+        msg.set_version(libproto::MESSAGE_VERSION);
         msg.set_content(block.write_to_bytes().unwrap());
+        libproto::compress_msg(&mut msg);
         self.pub_sender.send(("consensus.blk".to_string(), msg.write_to_bytes().unwrap())).unwrap();
     }
 
-    pub fn pub_proposal(&self, proposal: &Proposal) -> Vec<u8> {
+    pub fn pub_proposal(&mut self, proposal: &Proposal) -> Option<Vec<u8>> {
         let mut msg = communication::Message::new();
         msg.set_cmd_id(libproto::cmd_id(submodules::CONSENSUS, topics::NEW_PROPOSAL));
         msg.set_field_type(communication::MsgType::MSG);
 
         let message = serialize(&(self.height, self.round, proposal), Infinite).unwrap();
+        let (height, round) = (self.height, self.round);
+        if !self.sign_guard.check(height, round, Step::Propose, Some(message.crypt_hash())) {
+            return None;
+        }
         let ref author = self.params.signer;
         let signature = sign(&author.privkey(), &message.crypt_hash().into()).unwrap();
         trace!("pub_proposal height {}, round {}, hash {}, signature {} ", self.height, self.round, message.crypt_hash(), signature);
@@ -209,7 +262,7 @@ impl TenderMint {
         let bmsg = serialize(&(message, sig), Infinite).unwrap();
         msg.set_content(bmsg.clone());
         self.pub_sender.send(("consensus.msg".to_string(), msg.write_to_bytes().unwrap())).unwrap();
-        bmsg
+        Some(bmsg)
     }
 
     fn pre_proc_prevote(&mut self) {
@@ -438,7 +491,20 @@ impl TenderMint {
 
         info!("proc_commit after self height {},round {} in height {} round {} ", nowheight, self.round, height, round);
         if nowheight < height + 1 {
+            self.last_commit_time = Instant::now();
+            self.metrics.block_committed(height, round);
+            if let Some(ref mut adaptive) = self.adaptive {
+                if let Some(timer) = adaptive.observe(round + 1, self.metrics.round_elapsed()) {
+                    self.params.timer = timer;
+                }
+            }
+            if self.metrics.blocks % METRICS_REPORT_INTERVAL == 0 {
+                self.refresh_pool_metrics();
+                info!("consensus metrics {:?}", self.metrics);
+            }
             self.change_state_step(height + 1, INIT_ROUND, Step::Propose, true);
+            self.apply_key_rotations();
+            self.pub_proposers();
             if let Some(hash) = self.pre_hash {
                 let buf = hash.to_vec();
                 let _ = self.wal_log.save(LOG_TYPE_PREV_HASH, &buf);
@@ -520,7 +586,7 @@ impl TenderMint {
                     //update tx pool
                     let txs = self.locked_block.as_ref().unwrap().get_body().get_transactions();
                     //self.tx_pool.update(txs);
-                    self.dispatch.del_txs_from_pool(txs.to_vec());
+                    self.dispatch.del_txs_from_pool(height as u64, txs.to_vec());
                 }
                 return true;
             }
@@ -538,7 +604,151 @@ impl TenderMint {
         self.pub_sender.send(("consensus.msg".to_string(), msg.write_to_bytes().unwrap())).unwrap();
     }
 
+    fn refresh_pool_metrics(&mut self) {
+        self.metrics.tx_pool = self.dispatch.tx_pool_stats().into();
+        self.metrics.tx_pool.history_hits = self.dispatch.tx_history_hits();
+    }
+
+    /// Answer the consensus_metrics request of jsonrpc with the metrics up to now.
+    fn reply_metrics(&mut self, request_id: Vec<u8>) {
+        self.refresh_pool_metrics();
+        let mut response = request::Response::new();
+        response.set_request_id(request_id);
+        match serde_json::to_string(&self.metrics) {
+            Ok(metrics) => response.set_consensus_metrics(metrics),
+            Err(err) => response.set_error_msg(err.to_string()),
+        }
+        let msg: communication::Message = response.into();
+        self.pub_sender.send(("consensus.rpc".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Send the latest commit proof, so the lagging nodes can jump to our height.
+    fn pub_commit_proof(&mut self) {
+        if self.proof.is_default() || self.proof.height + 1 != self.height {
+            return;
+        }
+        let now = Instant::now();
+        if let Some((h, t)) = self.proof_pub {
+            if h == self.height && now - t < self.params.timer.commit {
+                return;
+            }
+        }
+        self.proof_pub = Some((self.height, now));
+
+        info!("pub commit proof height {} for lagging nodes", self.proof.height);
+        let mut msg = communication::Message::new();
+        msg.set_cmd_id(libproto::cmd_id(submodules::CONSENSUS, topics::COMMIT_PROOF));
+        msg.set_field_type(communication::MsgType::MSG);
+        msg.set_content(serialize(&self.proof, Infinite).unwrap());
+        self.pub_sender.send(("consensus.msg".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Tell the network the authorities signing from current height.
+    fn pub_authorities(&mut self) {
+        let content = serde_json::to_vec(&(self.height as u64, self.authorities.current())).unwrap();
+        let mut message = communication::Message::new();
+        message.set_cmd_id(libproto::cmd_id(submodules::CONSENSUS, topics::AUTHORITIES));
+        message.set_field_type(communication::MsgType::MSG);
+        message.set_content(content);
+        self.pub_sender.send(("consensus.authorities".to_string(), message.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Tell the network the next proposers, the new transactions can be sent to them only.
+    fn pub_proposers(&self) {
+        let authorities = &self.params.authorities;
+        if authorities.is_empty() {
+            return;
+        }
+        let mut proposers: Vec<Address> = Vec::new();
+        for &(height, round) in &[(self.height, 0), (self.height, 1), (self.height + 1, 0)] {
+            let proposer = self.proposers.proposer(authorities, height, round).clone();
+            if !proposers.contains(&proposer) {
+                proposers.push(proposer);
+            }
+        }
+        let content = serde_json::to_vec(&(self.height as u64, proposers)).unwrap();
+        let mut message = communication::Message::new();
+        message.set_cmd_id(libproto::cmd_id(submodules::CONSENSUS, topics::PROPOSERS));
+        message.set_field_type(communication::MsgType::MSG);
+        message.set_content(content);
+        self.pub_sender.send(("consensus.proposers".to_string(), message.write_to_bytes().unwrap())).unwrap();
+    }
+
+    /// Apply the key rotations taking effect at current height.
+    fn apply_key_rotations(&mut self) {
+        for rotation in self.authorities.apply(self.height) {
+            info!("apply key rotation {:?}", rotation);
+        }
+        if self.params.authorities == *self.authorities.current() {
+            return;
+        }
+        self.params.authorities = self.authorities.current().clone();
+        self.params.authority_n = self.params.authorities.len();
+        self.pub_authorities();
+        // sign with the new key once it replaces ours
+        if self.params.next_signer.as_ref().map_or(false, |s| self.params.authorities.contains(&s.address)) {
+            self.params.signer = self.params.next_signer.take().unwrap();
+        }
+    }
+
+    /// Replay the rounds of the last blocks committed on chain, so the nodes following the
+    /// chain, by consensus, sync or commit proof alike, skip the same proposers.
+    fn handle_commit_rounds(&mut self, msg: Vec<u8>) {
+        if self.params.proposer_miss_limit == 0 {
+            return;
+        }
+        let mut proposers = ProposerTracker::new(self.params.proposer_miss_limit, self.params.proposer_skip_heights);
+        for (height, round) in ProposerTracker::decode_rounds(&msg) {
+            for proposer in proposers.commit(self.authorities.at(height), height, round) {
+                if !self.proposers.is_skipped(&proposer, height + 1) {
+                    warn!("skip proposer {:?} after height {} for missing slots", proposer, height);
+                }
+            }
+        }
+        if proposers != self.proposers {
+            self.proposers = proposers;
+            self.pub_proposers();
+        }
+    }
+
+    /// Take the key rotations recorded on chain, every node applies them at the same heights.
+    fn handle_key_rotations(&mut self, msg: Vec<u8>) {
+        if self.authorities.set_rotations(KeyRotation::decode_list(&msg)) {
+            self.apply_key_rotations();
+        }
+    }
+
+    /// Fast forward to the height after the proof, without waiting for the block syncing.
+    fn handle_commit_proof(&mut self, msg: Vec<u8>) -> bool {
+        let proof: TendermintProof = match deserialize(&msg[..]) {
+            Ok(proof) => proof,
+            Err(_) => return false,
+        };
+        if proof.is_default() || proof.height < self.height {
+            return false;
+        }
+        if !proof.check(proof.height, self.authorities.at(proof.height)) {
+            warn!("receive invalid commit proof height {}", proof.height);
+            return false;
+        }
+
+        info!("catch up from height {} to height {} by commit proof", self.height, proof.height + 1);
+        self.change_state_step(proof.height + 1, INIT_ROUND, Step::Propose, true);
+        self.apply_key_rotations();
+        self.pre_hash = Some(proof.proposal);
+        let buf = proof.proposal.to_vec();
+        let _ = self.wal_log.save(LOG_TYPE_PREV_HASH, &buf);
+        self.proof = proof;
+        self.save_wal_proof();
+        self.clean_saved_info();
+        self.clean_filtr_info();
+        true
+    }
+
     fn pub_and_broadcast_message(&mut self, height: usize, round: usize, step: Step, hash: Option<H256>) {
+        if self.params.observer || !self.sign_guard.check(height, round, step, hash) {
+            return;
+        }
         let ref author = self.params.signer;
         let msg = serialize(&(height, round, step, author.address.clone(), hash.clone()), Infinite).unwrap();
         let signature = sign(&author.privkey(), &msg.crypt_hash().into()).unwrap();
@@ -569,6 +779,7 @@ impl TenderMint {
         self.height = height;
         self.round = round;
         self.step = s;
+        self.metrics.enter_step(s);
 
         if newflag {
             let _ = self.wal_log.set_height(height);
@@ -602,6 +813,9 @@ impl TenderMint {
                 trace!("handle_message  parse over sender:{:?}  h:{} r:{} s:{:?} vs self {} {} {:?}", sender, h, r, step, self.height, self.round, self.step);
 
                 if h < self.height {
+                    if self.is_authority(&sender) {
+                        self.pub_commit_proof();
+                    }
                     return Err(EngineError::UnexpectedMessage);
                 }
 
@@ -644,6 +858,17 @@ impl TenderMint {
                     process up */
                     if h > self.height || (h == self.height && r >= self.round) {
                         //if h == self.height && r >= self.round {
+                        match self.future_quota.admit(sender, (h, r, step), self.height, self.round) {
+                            Ok(Some((eh, er, es))) => {
+                                trace!("handle_message evict future vote h {} r {} s {:?} of {:?}", eh, er, es, sender);
+                                self.votes.remove(eh, er, es, &sender);
+                            }
+                            Ok(None) => {}
+                            Err(_) => {
+                                trace!("handle_message drop future vote h {} r {} s {:?} of {:?}", h, r, step, sender);
+                                return Err(EngineError::UnexpectedMessage);
+                            }
+                        }
                         info!("handle_message get vote: height {:?}, round {:?}, step {:?}, sender {:?}, hash {:?}, signature {}", h, r, step, sender, hash, signature);
                         let ret = self.votes.add(h,
                                                  r,
@@ -697,7 +922,7 @@ impl TenderMint {
                 let block_proof = block.get_header().get_proof();
                 let proof = TendermintProof::from(block_proof.clone());
                 info!(" proof is {:?}  {} {}", proof, height, round);
-                if !proof.check(height - 1, &self.params.authorities) {
+                if !proof.check(height - 1, self.authorities.at(height - 1)) {
                     return false;
                 }
                 if self.proof.height != height - 1 {
@@ -727,6 +952,10 @@ impl TenderMint {
                 // else use proposal block，self.lock_round is none
                 let block = parse_from_bytes::<Block>(&proposal.block).unwrap();
                 let block_hash = block.crypt_hash();
+                if !self.verifier.check(block_hash, &block) {
+                    warn!("proc proposal verify transactions error: height {}, round {}", height, round);
+                    return false;
+                }
                 self.proposal = Some(block_hash.into());
                 info!("save the proposal's hash: height {:?}, round {}, proposal {:?}", self.height, self.round, self.proposal.unwrap());
                 self.locked_block = Some(block);
@@ -747,7 +976,7 @@ impl TenderMint {
 
             if let Ok(pubkey) = recover(&signature, &message.crypt_hash().into()) {
                 let decoded = deserialize(&message[..]).unwrap();
-                let (height, round, proposal) = decoded;
+                let (height, round, proposal): (usize, usize, Proposal) = decoded;
                 trace!("handle_proposal height {:?}, round {:?} sender {:?}", height, round, pubkey_to_address(&pubkey));
 
                 let ret = self.is_round_proposer(height, round, &pubkey_to_address(&pubkey));
@@ -755,11 +984,15 @@ impl TenderMint {
                     return Err(ret.err().unwrap());
                 }
 
-                if (height == self.height && round >= self.round) || height == self.height + 1 {
+                if ((height == self.height && round >= self.round) || height == self.height + 1) && futurebuf::in_window(height, round, self.height, self.round) {
                     if wal_flag && height == self.height {
                         self.wal_log.save(LOG_TYPE_PROPOSE, &msg).unwrap();
                     }
                     info!("add proposal height {} round {}!", height, round);
+                    // verify transactions in background while other messages are processed
+                    if let Ok(block) = parse_from_bytes::<Block>(&proposal.block) {
+                        self.verifier.verify(block.crypt_hash(), &block);
+                    }
                     self.proposals.add(height, round, proposal);
 
                     if height > self.height {
@@ -786,10 +1019,15 @@ impl TenderMint {
     }
 
     pub fn new_proposal(&mut self) {
+        // never make a second proposal in the same round, send the old one again
+        if let Some(proposal) = self.proposals.get_proposal(self.height, self.round) {
+            info!("proposal again: height {}, round {}", self.height, self.round);
+            let _ = self.pub_proposal(&proposal);
+            return;
+        }
         if let Some(lock_round) = self.lock_round {
-            let ref lock_blk = self.locked_block;
-            let ref lock_vote = self.locked_vote;
-            let lock_blk = lock_blk.clone().unwrap();
+            let lock_blk = self.locked_block.clone().unwrap();
+            let lock_vote = self.locked_vote.clone();
             {
                 let lock_blk_hash = H256::from(lock_blk.crypt_hash());
                 info!("proposal lock block: height {:?}, block hash {:?}", self.height, lock_blk_hash);
@@ -799,10 +1037,13 @@ impl TenderMint {
             let proposal = Proposal {
                 block: blk,
                 lock_round: Some(lock_round),
-                lock_votes: lock_vote.clone(),
+                lock_votes: lock_vote,
             };
             trace!("pub proposal");
-            let bmsg = self.pub_proposal(&proposal);
+            let bmsg = match self.pub_proposal(&proposal) {
+                Some(bmsg) => bmsg,
+                None => return,
+            };
             self.wal_log.save(LOG_TYPE_PROPOSE, &bmsg).unwrap();
             trace!("proposor vote locked block: height {}, round {}", self.height, self.round);
             self.proposals.add(self.height, self.round, proposal);
@@ -845,6 +1086,7 @@ impl TenderMint {
 
         let bh = block.crypt_hash();
         info!("proposal new block: height {:?}, block hash {:?}", self.height, bh);
+        self.verifier.set_verified(bh);
         let pro_hash = Some(bh);
         {
             self.proposal = pro_hash.map(|x| x.into());
@@ -857,7 +1099,14 @@ impl TenderMint {
             lock_votes: None,
         };
         trace!("pub proposal in not locked");
-        let bmsg = self.pub_proposal(&proposal);
+        let bmsg = match self.pub_proposal(&proposal) {
+            Some(bmsg) => bmsg,
+            None => {
+                self.proposal = None;
+                self.locked_block = None;
+                return;
+            }
+        };
         self.wal_log.save(LOG_TYPE_PROPOSE, &bmsg).unwrap();
         trace!("proposor vote myslef in not locked");
         self.proposals.add(self.height, self.round, proposal);
@@ -869,7 +1118,14 @@ impl TenderMint {
             return;
         }
 
-        if tminfo.step == Step::ProposeWait {
+        if tminfo.step == Step::Propose {
+            if tminfo.height == self.height && tminfo.round == self.round && tminfo.step == self.step {
+                self.new_round_start(tminfo.height, tminfo.round);
+            }
+        } else if tminfo.step == Step::ProposeWait {
+            if self.proposals.get_proposal(tminfo.height, tminfo.round).is_none() {
+                self.metrics.proposer_missed();
+            }
             let pres = self.proc_proposal(tminfo.height, tminfo.round);
             if !pres {
                 trace!("timeout_process proc_proposal res false height {} round {}", tminfo.height, tminfo.round);
@@ -938,6 +1194,9 @@ impl TenderMint {
                         let res = self.handle_message(msg, true);
 
                         if let Ok((h, r, s)) = res {
+                            if h == self.height && r == self.round {
+                                self.metrics.vote_received(s);
+                            }
                             if s == Step::Prevote {
                                 self.proc_prevote(h, r);
                             } else {
@@ -953,6 +1212,10 @@ impl TenderMint {
                         let res = self.handle_proposal(msg, false);
                         if let Ok((h, r)) = res {
                             trace!("handle_proposal {:?}", (h, r));
+                            if h == self.height && r == self.round && self.step == Step::Propose && self.params.skip_empty_block {
+                                // a proposal arrives while waiting for txs, join the round now
+                                self.new_round_start(h, r);
+                            }
                             if h == self.height && r == self.round && self.step < Step::PrevoteWait {
                                 let pres = self.proc_proposal(h, r);
                                 if !pres {
@@ -962,6 +1225,25 @@ impl TenderMint {
                         }
                     }
                 }
+                ID_COMMIT_PROOF => {
+                    if let MsgClass::MSG(msg) = content_ext {
+                        if self.handle_commit_proof(msg) {
+                            self.redo_work();
+                        }
+                    }
+                }
+                ID_NET_RTT => {
+                    if let MsgClass::MSG(msg) = content_ext {
+                        if msg.len() == 8 {
+                            let rtt = msg.iter().fold(0u64, |acc, b| (acc << 8) + *b as u64);
+                            if let Some(ref mut adaptive) = self.adaptive {
+                                if let Some(timer) = adaptive.set_rtt(Duration::from_millis(rtt)) {
+                                    self.params.timer = timer;
+                                }
+                            }
+                        }
+                    }
+                }
                 _ => {}
             }
         } else {
@@ -970,6 +1252,27 @@ impl TenderMint {
                     trace!("get new local status {:?}", status.height);
                     self.receive_new_status(status);
                 }
+                MsgClass::MSG(msg) => {
+                    if cmd_id == ID_COMMIT_ROUNDS {
+                        self.handle_commit_rounds(msg);
+                    } else if cmd_id == ID_KEY_ROTATION {
+                        self.handle_key_rotations(msg);
+                    } else if cmd_id == ID_SYS_CONFIG && msg.len() == 24 {
+                        let read = |bytes: &[u8]| bytes.iter().fold(0u64, |acc, b| (acc << 8) + *b as u64);
+                        let interval = read(&msg[..8]);
+                        if interval != 0 && self.params.block_interval != Duration::from_millis(interval) {
+                            info!("block interval set to {} ms on chain", interval);
+                            self.params.block_interval = Duration::from_millis(interval);
+                        }
+                        let quota_limit = read(&msg[8..16]);
+                        self.dispatch.set_quota_limit(if quota_limit == 0 { None } else { Some(quota_limit) }, read(&msg[16..]));
+                    }
+                }
+                MsgClass::REQUEST(mut req) => {
+                    if req.has_consensus_metrics() {
+                        self.reply_metrics(req.take_request_id());
+                    }
+                }
                 _ => {}
             }
         }
@@ -1025,15 +1328,53 @@ impl TenderMint {
                              });
     }
 
+    fn is_idle(&mut self, height: usize, round: usize) -> bool {
+        if !self.params.skip_empty_block || self.lock_round.is_some() {
+            return false;
+        }
+        if self.proposals.get_proposal(height, round).is_some() {
+            return false;
+        }
+        // the max idle duration keeps the block timestamp advancing
+        self.dispatch.tx_pool_len() == 0 && Instant::now() - self.last_commit_time < self.params.max_idle_duration
+    }
+
     fn new_round_start(&mut self, height: usize, round: usize) {
         if round == INIT_ROUND {
+            let elapsed = Instant::now() - self.last_commit_time;
+            if elapsed < self.params.block_interval {
+                trace!("wait for the block interval height {}", height);
+                self.step = Step::Propose;
+                WaitTimer::set_timer(self.timer_seter.clone(),
+                                     TimeoutInfo {
+                                         timeval: self.params.block_interval - elapsed,
+                                         height: height,
+                                         round: round,
+                                         step: Step::Propose,
+                                     });
+                return;
+            }
+            if self.is_idle(height, round) {
+                trace!("tx pool is empty, skip empty block height {}", height);
+                self.step = Step::Propose;
+                WaitTimer::set_timer(self.timer_seter.clone(),
+                                     TimeoutInfo {
+                                         timeval: self.params.timer.commit,
+                                         height: height,
+                                         round: round,
+                                         step: Step::Propose,
+                                     });
+                return;
+            }
             self.htime = Instant::now();
         }
+        self.metrics.round_start();
 
-        if self.is_round_proposer(height, round, &self.params.signer.address).is_ok() {
+        if !self.params.observer && self.is_round_proposer(height, round, &self.params.signer.address).is_ok() {
             self.new_proposal();
         }
         self.step = Step::ProposeWait;
+        self.metrics.enter_step(Step::ProposeWait);
         WaitTimer::set_timer(self.timer_seter.clone(),
                              TimeoutInfo {
                                  timeval: self.params.timer.propose * ((round + 1) as u32),
@@ -1096,6 +1437,14 @@ impl TenderMint {
         }
     }
 
+    // The adaptive timeouts start again from the timeouts reloaded.
+    fn reload_timer(&mut self, timer: TendermintTimer) {
+        if let Some(ref mut adaptive) = self.adaptive {
+            *adaptive = AdaptiveTimeout::new(timer.clone(), self.params.min_timeout_scale, self.params.max_timeout_scale);
+        }
+        self.params.timer = timer;
+    }
+
     pub fn start(&mut self) {
         let vec_buf = self.wal_log.load();
         for (mtype, vec_out) in vec_buf {
@@ -1154,6 +1503,11 @@ impl TenderMint {
                 }
             }
 
+            let shutdown = self.shutdown.clone();
+            let _work = shutdown.working();
+            while let Ok(timer) = self.timer_reload.try_recv() {
+                self.reload_timer(timer);
+            }
             if let Ok(oktm) = gtm {
                 //trace!("in select !height {},round {},step {:?}",oktm.height,oktm.round,oktm.step);
                 self.timeout_process(oktm);
@@ -1162,6 +1516,15 @@ impl TenderMint {
             if let Ok(tinfo) = ginfo {
                 self.process(tinfo);
             }
+
+            // neither propose nor vote any more
+            if shutdown.requested() {
+                if let Err(e) = self.wal_log.sync() {
+                    warn!("failed to sync the wal: {}", e);
+                }
+                info!("consensus stopped at height {} round {}", self.height, self.round);
+                return;
+            }
         }
     }
 }
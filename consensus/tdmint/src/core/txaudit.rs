@@ -0,0 +1,173 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rustc_serialize::hex::ToHex;
+use serde_json;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bytes of the journal before it is rotated, by default.
+pub const DEFAULT_AUDIT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// An event of a transaction in the pool, one line of json in the journal.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Milliseconds since the epoch.
+    pub time: u64,
+    pub hash: String,
+    /// accepted, rejected, replaced, evicted, expired, packaged or committed.
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub height: Option<u64>,
+}
+
+impl AuditEntry {
+    pub fn new(hash: &[u8], event: &str) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        AuditEntry {
+            time: now.as_secs() * 1000 + (now.subsec_nanos() / 1_000_000) as u64,
+            hash: format!("0x{}", hash.to_hex()),
+            event: event.to_string(),
+            reason: None,
+            height: None,
+        }
+    }
+
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    pub fn height(mut self, height: u64) -> Self {
+        self.height = Some(height);
+        self
+    }
+}
+
+/// Append-only journal of the events of the transactions in the pool, so the
+/// operators can tell what became of a transaction. The journal is rotated
+/// to one previous file over the size.
+pub struct TxAudit {
+    path: PathBuf,
+    max_bytes: u64,
+    file: Mutex<(BufWriter<File>, u64)>,
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+fn open_append(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let len = file.metadata()?.len();
+    Ok((BufWriter::new(file), len))
+}
+
+impl TxAudit {
+    pub fn open<P: AsRef<Path>>(path: P, max_bytes: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        Ok(TxAudit {
+               path: path,
+               max_bytes: max_bytes,
+               file: Mutex::new(file),
+           })
+    }
+
+    pub fn record(&self, entries: Vec<AuditEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        let mut file = self.file.lock().unwrap();
+        for entry in entries {
+            let mut line = serde_json::to_vec(&entry).unwrap();
+            line.push(b'\n');
+            if file.0.write_all(&line).is_err() {
+                warn!("failed to write the tx audit journal");
+                return;
+            }
+            file.1 += line.len() as u64;
+        }
+        let _ = file.0.flush();
+        if file.1 > self.max_bytes {
+            let _ = fs::rename(&self.path, rotated(&self.path));
+            match open_append(&self.path) {
+                Ok(new) => *file = new,
+                Err(e) => warn!("failed to rotate the tx audit journal: {}", e),
+            }
+        }
+    }
+
+    /// The events of the transaction in the journal at the path, the oldest first.
+    pub fn query<P: AsRef<Path>>(path: P, hash: &str) -> Vec<AuditEntry> {
+        let path = path.as_ref();
+        let hash = format!("0x{}", hash.trim_left_matches("0x").to_lowercase());
+        let mut entries = Vec::new();
+        for path in &[rotated(path), path.to_path_buf()] {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+            for line in BufReader::new(file).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                // skip the lines of other transactions before parsing
+                if !line.contains(&hash) {
+                    continue;
+                }
+                if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) {
+                    if entry.hash == hash {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn record_and_query() {
+        let path = env::temp_dir().join(format!("txaudit-{}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos()));
+        let audit = TxAudit::open(&path, 200).unwrap();
+        audit.record(vec![AuditEntry::new(&[1, 2], "accepted").reason("New"), AuditEntry::new(&[3], "rejected").reason("DUP")]);
+        // rotated over the size
+        audit.record(vec![AuditEntry::new(&[1, 2], "committed").height(7)]);
+
+        let entries = TxAudit::query(&path, "0x0102");
+        assert_eq!(entries.iter().map(|e| e.event.as_str()).collect::<Vec<_>>(), vec!["accepted", "committed"]);
+        assert_eq!(entries[1].height, Some(7));
+        assert_eq!(TxAudit::query(&path, "03")[0].reason, Some("DUP".to_string()));
+
+        let _ = fs::remove_file(rotated(&path));
+        let _ = fs::remove_file(&path);
+    }
+}
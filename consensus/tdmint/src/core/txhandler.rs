@@ -15,44 +15,217 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use core::ratelimit::{RateLimit, SourceLimiter};
 use libproto::blockchain::SignedTransaction;
 use libproto::communication::{Message, MsgType};
-use libproto::key_to_id;
+use libproto::{decompress_msg, key_to_id, upgrade_msg};
 use protobuf::core::parse_from_bytes;
-use std::sync::mpsc::Sender;
-use threadpool::ThreadPool;
-use util::snappy;
+use std::cmp;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+use std::time::Instant;
 
-pub type TransType = (u32, bool, SignedTransaction);
+/// Raw bytes of a transaction accepted by default.
+pub const DEFAULT_MAX_TX_BYTES: usize = 1024 * 1024;
+/// Bytes of the data of a transaction accepted by default.
+pub const DEFAULT_MAX_DATA_BYTES: usize = 512 * 1024;
 
-pub struct TxHandler {
-    pool: ThreadPool,
-    tx: Sender<TransType>,
+/// Outcome of the checks of a transaction by the workers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Ok,
+    BadSig,
+    TooLarge,
+    DataTooLarge,
+    RateLimited,
 }
 
-impl TxHandler {
-    pub fn new(pool: ThreadPool, tx: Sender<TransType>) -> Self {
-        TxHandler { pool: pool, tx: tx }
+impl Verdict {
+    /// The reason replied for a transaction refused, None if it passed.
+    pub fn reason(&self) -> Option<&'static str> {
+        match *self {
+            Verdict::Ok => None,
+            Verdict::BadSig => Some("BAD SIG"),
+            Verdict::TooLarge => Some("TX_TOO_LARGE"),
+            Verdict::DataTooLarge => Some("DATA_TOO_LARGE"),
+            Verdict::RateLimited => Some("RATE_LIMITED"),
+        }
+    }
+}
+
+/// The source, the verdict of the checks, the transaction and the trace id of its message.
+pub type TransType = (u32, Verdict, SignedTransaction, Vec<u8>);
+
+/// Bounds on the size of a transaction, zero means no bound. The transactions
+/// over them are refused before their signatures are recovered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TxLimits {
+    pub max_bytes: usize,
+    pub max_data_bytes: usize,
+}
+
+impl TxLimits {
+    pub fn check(&self, raw_len: usize, tx: &SignedTransaction) -> Verdict {
+        let data_len = tx.get_transaction_with_sig().get_transaction().get_data().len();
+        if self.max_bytes > 0 && raw_len > self.max_bytes {
+            Verdict::TooLarge
+        } else if self.max_data_bytes > 0 && data_len > self.max_data_bytes {
+            Verdict::DataTooLarge
+        } else {
+            Verdict::Ok
+        }
     }
+}
+
+/// Messages waiting between two stages of the pipeline, a full stage holds back those before it.
+pub const PIPELINE_QUEUE: usize = 10000;
 
-    pub fn receive(pool: &ThreadPool, tx: &Sender<TransType>, id: u32, msg: Vec<u8>) {
+// Start the workers of a stage, taking the inputs in turn and sending on the outputs.
+fn spawn_stage<I, O, F>(name: &str, workers: usize, rx: Receiver<I>, tx: SyncSender<O>, f: F)
+    where I: Send + 'static,
+          O: Send + 'static,
+          F: Fn(I) -> Option<O> + Send + Sync + 'static
+{
+    let rx = Arc::new(Mutex::new(rx));
+    let f = Arc::new(f);
+    for i in 0..workers {
+        let rx = rx.clone();
         let tx = tx.clone();
-        pool.execute(move || {
-            let mut msg = parse_from_bytes::<Message>(msg.as_ref()).unwrap();
-            let content_msg = msg.take_content();
-            let content_msg = snappy::cita_decompress(content_msg);
-            match msg.get_field_type() {
-                MsgType::TX => {
-                    let mut trans: SignedTransaction = parse_from_bytes::<SignedTransaction>(&content_msg).unwrap();
-                    let ret = trans.recover();
-                    tx.send((id, ret, trans)).unwrap();
-                }
-                _ => info!("recv msg type[{:?}] error", msg.get_field_type()),
+        let f = f.clone();
+        let work = move || loop {
+            let input = match rx.lock().unwrap().recv() {
+                Ok(input) => input,
+                Err(_) => break,
             };
-        });
+            if let Some(output) = f(input) {
+                if tx.send(output).is_err() {
+                    break;
+                }
+            }
+        };
+        let _ = thread::Builder::new().name(format!("{}_{}", name, i)).spawn(work);
+    }
+}
+
+// Decode the transaction of the message and check its size and the rate of its source.
+fn decode(limits: &TxLimits, limiter: &Mutex<SourceLimiter>, id: u32, msg: Vec<u8>) -> Option<TransType> {
+    let mut msg = match parse_from_bytes::<Message>(msg.as_ref()) {
+        Ok(msg) => msg,
+        Err(e) => {
+            warn!("bad tx message: {:?}", e);
+            return None;
+        }
+    };
+    if let Err(e) = upgrade_msg(&mut msg).and_then(|_| decompress_msg(&mut msg)) {
+        warn!("bad tx message: {}", e);
+        return None;
+    }
+    let content_msg = msg.take_content();
+    match msg.get_field_type() {
+        MsgType::TX => {
+            match parse_from_bytes::<SignedTransaction>(&content_msg) {
+                Ok(trans) => {
+                    let mut verdict = limits.check(content_msg.len(), &trans);
+                    if verdict == Verdict::Ok && !limiter.lock().unwrap().allow((id, msg.get_origin()), Instant::now()) {
+                        verdict = Verdict::RateLimited;
+                    }
+                    Some((id, verdict, trans, msg.take_trace_id()))
+                }
+                Err(e) => {
+                    warn!("bad transaction: {:?}", e);
+                    None
+                }
+            }
+        }
+        _ => {
+            info!("recv msg type[{:?}] error", msg.get_field_type());
+            None
+        }
+    }
+}
+
+fn recover((id, verdict, mut trans, trace): TransType) -> Option<TransType> {
+    if verdict == Verdict::Ok && !trans.recover() {
+        return Some((id, Verdict::BadSig, trans, trace));
+    }
+    Some((id, verdict, trans, trace))
+}
+
+/// Pipeline of the new transactions, the messages are decoded and the signatures
+/// recovered by their own workers, with bounded queues between the stages, and the
+/// transactions are sent to be added to the pool.
+pub struct TxHandler {
+    raw: SyncSender<(u32, Vec<u8>)>,
+}
+
+impl TxHandler {
+    pub fn new(num_thds: usize, tx: SyncSender<TransType>, limits: TxLimits, rate_limit: RateLimit) -> Self {
+        let (raw_tx, raw_rx) = sync_channel(PIPELINE_QUEUE);
+        let (decoded_tx, decoded_rx) = sync_channel(PIPELINE_QUEUE);
+        // decoding is much cheaper than the recovery of a signature
+        let decoders = cmp::max(1, num_thds / 4);
+        let limiter = Mutex::new(SourceLimiter::new(rate_limit));
+        spawn_stage("consensus_tx_decode", decoders, raw_rx, decoded_tx, move |(id, msg)| decode(&limits, &limiter, id, msg));
+        spawn_stage("consensus_tx_recover", cmp::max(1, num_thds), decoded_rx, tx, recover);
+        TxHandler { raw: raw_tx }
     }
+
     pub fn handle(&mut self, key: String, body: Vec<u8>) {
         //trace!("************ handle delivery id {:?} {:?} ",deliver.routing_key,deliver.delivery_tag);
-        TxHandler::receive(&self.pool, &self.tx, key_to_id(&key), body);
+        self.raw.send((key_to_id(&key), body)).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libproto::{factory, submodules, topics};
+    use protobuf::Message as ProtoMessage;
+
+    #[test]
+    fn limits() {
+        let limits = TxLimits {
+            max_bytes: 100,
+            max_data_bytes: 10,
+        };
+        let mut tx = SignedTransaction::new();
+        assert_eq!(limits.check(100, &tx), Verdict::Ok);
+        assert_eq!(limits.check(101, &tx), Verdict::TooLarge);
+        tx.mut_transaction_with_sig().mut_transaction().set_data(vec![0; 11]);
+        assert_eq!(limits.check(50, &tx), Verdict::DataTooLarge);
+        assert_eq!(limits.check(101, &tx).reason(), Some("TX_TOO_LARGE"));
+
+        let unbounded = TxLimits {
+            max_bytes: 0,
+            max_data_bytes: 0,
+        };
+        assert_eq!(unbounded.check(1 << 30, &tx), Verdict::Ok);
+    }
+
+    #[test]
+    fn pipeline() {
+        let (tx, rx) = sync_channel(1);
+        let limits = TxLimits {
+            max_bytes: 0,
+            max_data_bytes: 10,
+        };
+        let mut handler = TxHandler::new(2, tx, limits, RateLimit { rate: 1, burst: 1 });
+        let mut trans = SignedTransaction::new();
+        let msg = factory::create_msg(submodules::JSON_RPC, topics::NEW_TX, MsgType::TX, trans.write_to_bytes().unwrap());
+        handler.handle("jsonrpc.new_tx".to_string(), msg.write_to_bytes().unwrap());
+        // not signed
+        assert_eq!(rx.recv().unwrap().1, Verdict::BadSig);
+
+        trans.mut_transaction_with_sig().mut_transaction().set_data(vec![0; 11]);
+        let msg = factory::create_msg(submodules::NET, topics::NEW_TX, MsgType::TX, trans.write_to_bytes().unwrap());
+        handler.handle("net.tx".to_string(), msg.write_to_bytes().unwrap());
+        let (id, verdict, _, _) = rx.recv().unwrap();
+        assert_eq!((id, verdict), (submodules::NET, Verdict::DataTooLarge));
+
+        // the jsonrpc client has sent one already
+        let msg = factory::create_msg(submodules::JSON_RPC, topics::NEW_TX, MsgType::TX, SignedTransaction::new().write_to_bytes().unwrap());
+        handler.handle("jsonrpc.new_tx".to_string(), msg.write_to_bytes().unwrap());
+        assert_eq!(rx.recv().unwrap().1, Verdict::RateLimited);
     }
 }
@@ -0,0 +1,243 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bloom_journal::Bloom;
+use chain_core::db;
+use libproto::blockchain::SignedTransaction;
+use std::cmp;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use util::{H256, DBTransaction};
+use util::kvdb::{DatabaseConfig, Database, KeyValueDB};
+
+/// Hashes the bloom is sized for at least.
+pub const BLOOM_ITEMS: usize = 1_000_000;
+const BLOOM_FP_RATE: f64 = 0.001;
+/// Blocks between two checks whether the bloom is rebuilt.
+pub const REBUILD_INTERVAL: u64 = 10_000;
+// ratio of the bits set over which the bloom is rebuilt larger
+const MAX_SATURATION: f64 = 0.5;
+
+// the hashes to the heights they are committed at, and the parts of the bloom to their indexes
+const COL_HASHES: Option<u32> = None;
+const COL_BLOOM: Option<u32> = Some(0);
+// number of the parts and of the hash functions of the bloom
+const BLOOM_META_KEY: &'static [u8] = b"meta";
+
+fn to_bytes(n: u64) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (n >> (56 - 8 * i)) as u8;
+    }
+    bytes
+}
+
+fn from_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().take(8).fold(0, |n, &byte| (n << 8) | byte as u64)
+}
+
+fn load_bloom(db: &KeyValueDB) -> Option<Bloom> {
+    let meta = match db.get(COL_BLOOM, BLOOM_META_KEY) {
+        Ok(Some(meta)) if meta.len() == 16 => meta,
+        _ => return None,
+    };
+    let mut parts = vec![0u64; from_bytes(&meta[..8]) as usize];
+    for (key, value) in db.iter(COL_BLOOM) {
+        if key.len() != 8 {
+            continue;
+        }
+        match parts.get_mut(from_bytes(&key) as usize) {
+            Some(part) => *part = from_bytes(&value),
+            None => return None,
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(Bloom::from_parts(&parts, from_bytes(&meta[8..]) as u32))
+}
+
+// A bloom of the hashes committed after the retention before the height, zero keeps all of them,
+// and the batch deleting the others and replacing the bloom on the disk.
+fn build_bloom(db: &KeyValueDB, retention: u64, height: u64) -> (Bloom, DBTransaction) {
+    let mut batch = db.transaction();
+    let mut kept = Vec::new();
+    for (key, value) in db.iter(COL_HASHES) {
+        if retention == 0 || from_bytes(&value) + retention > height {
+            kept.push(H256::from_slice(&key));
+        } else {
+            batch.delete(COL_HASHES, &key);
+        }
+    }
+    let mut bloom = Bloom::new_for_fp_rate(cmp::max(BLOOM_ITEMS, kept.len() * 2), BLOOM_FP_RATE);
+    for hash in &kept {
+        bloom.set(hash);
+    }
+    for (key, _) in db.iter(COL_BLOOM) {
+        batch.delete(COL_BLOOM, &key);
+    }
+    let journal = bloom.drain_journal();
+    let mut meta = to_bytes(bloom.number_of_bits() / 64).to_vec();
+    meta.extend_from_slice(&to_bytes(journal.hash_functions as u64));
+    batch.put(COL_BLOOM, BLOOM_META_KEY, &meta);
+    for (index, part) in journal.entries {
+        batch.put(COL_BLOOM, &to_bytes(index as u64), &to_bytes(part));
+    }
+    (bloom, batch)
+}
+
+/// Hashes of the transactions committed, kept on disk so that a transaction
+/// is refused as a duplicate after it leaves the window of the recent hashes,
+/// and after a restart.
+///
+/// A bloom of the hashes, journaled to the disk along with them, spares the
+/// lookups of the new transactions. It is rebuilt from the hashes kept when
+/// they are trimmed to the retention, or larger when it is saturated.
+#[derive(Clone)]
+pub struct TxHistory {
+    db: Arc<KeyValueDB>,
+    bloom: Arc<Mutex<Bloom>>,
+    // blocks the hashes are kept for, zero keeps all of them
+    retention: u64,
+    rebuilding: Arc<AtomicBool>,
+    hits: Arc<AtomicUsize>,
+}
+
+impl TxHistory {
+    pub fn new(path: &str, retention: u64) -> Self {
+        let nosql_path = env::var("DATA_PATH").expect(format!("{} must be set", "DATA_PATH").as_str()) + path;
+        let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
+        let db = Database::open(&config, &nosql_path).unwrap();
+        TxHistory::with_db(Arc::new(db), retention)
+    }
+
+    pub fn with_db(db: Arc<KeyValueDB>, retention: u64) -> Self {
+        let bloom = match load_bloom(&*db) {
+            Some(bloom) => bloom,
+            None => {
+                info!("building the bloom of the tx history");
+                let (bloom, batch) = build_bloom(&*db, 0, 0);
+                let _ = db.write(batch);
+                bloom
+            }
+        };
+        TxHistory {
+            db: db,
+            bloom: Arc::new(Mutex::new(bloom)),
+            retention: retention,
+            rebuilding: Arc::new(AtomicBool::new(false)),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Whether the transaction of the hash is committed.
+    pub fn contains(&self, hash: &H256) -> bool {
+        if !self.bloom.lock().unwrap().check(hash) {
+            return false;
+        }
+        let found = self.db.get(COL_HASHES, hash).ok().map_or(false, |value| value.is_some());
+        if found {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    pub fn insert(&self, height: u64, txs: &[SignedTransaction]) {
+        let mut batch = self.db.transaction();
+        {
+            // written under the lock, so a rebuild sees the hashes the bloom has
+            let mut bloom = self.bloom.lock().unwrap();
+            for tx in txs {
+                let hash = H256::from_slice(tx.get_tx_hash());
+                batch.put(COL_HASHES, &hash, &to_bytes(height));
+                bloom.set(&hash);
+            }
+            for (index, part) in bloom.drain_journal().entries {
+                batch.put(COL_BLOOM, &to_bytes(index as u64), &to_bytes(part));
+            }
+            let _ = self.db.write(batch);
+        }
+        if height % REBUILD_INTERVAL == 0 {
+            self.rebuild_in_background(height);
+        }
+    }
+
+    fn rebuild_in_background(&self, height: u64) {
+        if self.retention == 0 && self.bloom.lock().unwrap().saturation() < MAX_SATURATION {
+            return;
+        }
+        if self.rebuilding.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let history = self.clone();
+        thread::spawn(move || {
+                          history.rebuild(height);
+                          history.rebuilding.store(false, Ordering::SeqCst);
+                      });
+    }
+
+    /// Drop the hashes out of the retention before the height and rebuild the bloom of the others.
+    /// The new transactions wait for it to be checked.
+    pub fn rebuild(&self, height: u64) {
+        let mut bloom = self.bloom.lock().unwrap();
+        let (rebuilt, batch) = build_bloom(&*self.db, self.retention, height);
+        if self.db.write(batch).is_ok() {
+            *bloom = rebuilt;
+            info!("rebuilt the bloom of the tx history at height {}", height);
+        }
+    }
+
+    /// Number of the transactions found committed.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::kvdb::in_memory;
+
+    fn committed(hash: u64) -> SignedTransaction {
+        let mut tx = SignedTransaction::new();
+        tx.set_tx_hash(H256::from(hash).to_vec());
+        tx
+    }
+
+    #[test]
+    fn bloom_on_disk() {
+        let db: Arc<KeyValueDB> = Arc::new(in_memory(1));
+        let history = TxHistory::with_db(db.clone(), 10);
+        history.insert(1, &[committed(1)]);
+        history.insert(5, &[committed(2)]);
+        assert!(history.contains(&H256::from(1)));
+        assert!(!history.contains(&H256::from(3)));
+
+        // loaded after a restart
+        let history = TxHistory::with_db(db.clone(), 10);
+        assert!(history.contains(&H256::from(2)));
+        assert_eq!(history.hits(), 1);
+
+        // trimmed to the retention
+        history.rebuild(12);
+        assert!(!history.contains(&H256::from(1)));
+        assert!(history.contains(&H256::from(2)));
+        assert!(!TxHistory::with_db(db, 10).contains(&H256::from(1)));
+    }
+}
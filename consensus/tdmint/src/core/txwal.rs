@@ -18,14 +18,41 @@
 use chain_core::db;
 use libproto::blockchain::SignedTransaction;
 use protobuf::core::{Message, parse_from_bytes};
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tx_pool::Pool;
 use util::kvdb::{DatabaseConfig, Database, KeyValueDB};
 
+/// Prefix of the key keeping the arrival order of a transaction, next to the
+/// transaction kept under its hash.
+const ORDER_PREFIX: u8 = b'o';
+
+/// Journal of the pending transactions, reloaded into the pool on startup
+/// in the order they arrived.
 #[derive(Clone)]
 pub struct Txwal {
     db: Arc<KeyValueDB>,
+    order: Arc<AtomicUsize>,
+}
+
+fn order_key(hash: &[u8]) -> Vec<u8> {
+    let mut key = vec![ORDER_PREFIX];
+    key.extend_from_slice(hash);
+    key
+}
+
+fn encode_order(order: u64) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    for i in 0..8 {
+        bytes[i] = (order >> (56 - 8 * i)) as u8;
+    }
+    bytes
+}
+
+fn decode_order(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |order, b| order << 8 | *b as u64)
 }
 
 impl Txwal {
@@ -34,14 +61,25 @@ impl Txwal {
         let nosql_path = env::var("DATA_PATH").expect(format!("{} must be set", "DATA_PATH").as_str()) + path;
         let config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
         let db = Database::open(&config, &nosql_path).unwrap();
-        Txwal { db: Arc::new(db) }
+        Txwal {
+            db: Arc::new(db),
+            order: Arc::new(AtomicUsize::new(0)),
+        }
     }
 
     pub fn write(&self, tx: &SignedTransaction) {
-        let tx = tx.clone();
+        self.write_batch(Some(tx));
+    }
+
+    /// Journal the transactions in one write.
+    pub fn write_batch<'a, I: IntoIterator<Item = &'a SignedTransaction>>(&self, txs: I) {
         let mut batch = self.db.transaction();
-        let block_binary = tx.write_to_bytes().unwrap();
-        batch.put_vec(None, tx.get_tx_hash(), block_binary);
+        for tx in txs {
+            let block_binary = tx.write_to_bytes().unwrap();
+            let order = self.order.fetch_add(1, Ordering::SeqCst) as u64;
+            batch.put_vec(None, tx.get_tx_hash(), block_binary);
+            batch.put(None, &order_key(tx.get_tx_hash()), &encode_order(order));
+        }
         let _ = self.db.write(batch);
     }
 
@@ -49,15 +87,28 @@ impl Txwal {
         let tx = tx.clone();
         let mut batch = self.db.transaction();
         batch.delete(None, tx.get_tx_hash());
+        batch.delete(None, &order_key(tx.get_tx_hash()));
         let _ = self.db.write(batch);
     }
 
+    /// Enqueue the journaled transactions into the pool in the order they arrived,
+    /// those journaled without an order first.
     pub fn read(&self, pool: &mut Pool) -> u64 {
-        let mut num: u64 = 0;
-        let mut ite = self.db.iter(None);
-        while let Some(item) = ite.next() {
-            let tx = parse_from_bytes::<SignedTransaction>(item.1.as_ref()).unwrap();
-            num = num + 1;
+        let mut txs = Vec::new();
+        let mut orders = HashMap::new();
+        for (key, value) in self.db.iter(None) {
+            if key.len() == 33 && key[0] == ORDER_PREFIX {
+                orders.insert(key[1..].to_vec(), decode_order(&value));
+            } else {
+                txs.push(parse_from_bytes::<SignedTransaction>(value.as_ref()).unwrap());
+            }
+        }
+        txs.sort_by_key(|tx| orders.get(tx.get_tx_hash()).cloned().unwrap_or(0));
+        let next = orders.values().max().map_or(0, |order| order + 1);
+        self.order.store(next as usize, Ordering::SeqCst);
+
+        let num = txs.len() as u64;
+        for tx in txs {
             pool.enqueue(tx);
         }
         info!("read tx num [{}] from pool.", num);
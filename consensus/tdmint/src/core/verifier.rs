@@ -0,0 +1,167 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use libproto::blockchain::{Block, SignedTransaction};
+use lru_cache::LruCache;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver};
+use threadpool::ThreadPool;
+use util::H256;
+
+const MIN_CHUNK_SIZE: usize = 50;
+const RESULT_CACHE_SIZE: usize = 16;
+
+/// Verifies the transactions of proposal blocks on a worker pool.
+/// Verification starts when the proposal arrives, so that it runs while
+/// the node keeps gossiping, and the result is collected before prevote.
+pub struct ProposalVerifier {
+    pool: ThreadPool,
+    thread_num: usize,
+    pending: HashMap<H256, (usize, Receiver<bool>)>,
+    results: LruCache<H256, bool>,
+}
+
+impl ProposalVerifier {
+    pub fn new(thread_num: usize) -> Self {
+        ProposalVerifier {
+            pool: ThreadPool::with_name("consensus_verify_proposal".to_string(), thread_num),
+            thread_num: thread_num,
+            pending: HashMap::new(),
+            results: LruCache::new(RESULT_CACHE_SIZE),
+        }
+    }
+
+    /// Start verifying the block in background.
+    pub fn verify(&mut self, hash: H256, block: &Block) {
+        if self.results.contains_key(&hash) || self.pending.contains_key(&hash) {
+            return;
+        }
+        let txs = block.get_body().get_transactions();
+        if txs.is_empty() {
+            self.results.insert(hash, true);
+            return;
+        }
+
+        let mut chunk_size = (txs.len() + self.thread_num - 1) / self.thread_num;
+        if chunk_size < MIN_CHUNK_SIZE {
+            chunk_size = MIN_CHUNK_SIZE;
+        }
+        let (tx, rx) = channel();
+        let mut jobs = 0;
+        for chunk in txs.chunks(chunk_size) {
+            let chunk = chunk.to_vec();
+            let tx = tx.clone();
+            jobs += 1;
+            self.pool.execute(move || {
+                let ret = chunk.iter().all(|t| verify_transaction(t));
+                let _ = tx.send(ret);
+            });
+        }
+        self.pending.insert(hash, (jobs, rx));
+    }
+
+    /// Mark the block as verified, used for the blocks built by ourselves.
+    pub fn set_verified(&mut self, hash: H256) {
+        self.pending.remove(&hash);
+        self.results.insert(hash, true);
+    }
+
+    /// Get the result of the block, waiting for the unfinished jobs.
+    pub fn check(&mut self, hash: H256, block: &Block) -> bool {
+        if let Some(ret) = self.results.get_mut(&hash) {
+            return *ret;
+        }
+        if !self.pending.contains_key(&hash) {
+            self.verify(hash, block);
+        }
+
+        let mut ret = true;
+        if let Some((jobs, rx)) = self.pending.remove(&hash) {
+            for _ in 0..jobs {
+                match rx.recv() {
+                    Ok(true) => {}
+                    _ => {
+                        ret = false;
+                        break;
+                    }
+                }
+            }
+        }
+        trace!("proposal {:?} verify result {}", hash, ret);
+        self.results.insert(hash, ret);
+        ret
+    }
+}
+
+fn verify_transaction(tx: &SignedTransaction) -> bool {
+    let mut recovered = tx.clone();
+    if !recovered.recover() {
+        return false;
+    }
+    recovered.get_tx_hash() == tx.get_tx_hash() && recovered.get_signer() == tx.get_signer() && recovered.get_fee_payer() == tx.get_fee_payer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProposalVerifier;
+    use ed25519::KeyPair;
+    use libproto::blockchain::{Block, SignedTransaction, Transaction, UnverifiedTransaction};
+    use protobuf::RepeatedField;
+
+    fn generate_tx(data: Vec<u8>) -> SignedTransaction {
+        let keypair = KeyPair::gen_keypair();
+        let mut tx = Transaction::new();
+        tx.set_data(data);
+        tx.set_to("1234567".to_string());
+        tx.set_nonce("0".to_string());
+        tx.set_valid_until_block(99999);
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(keypair.privkey().clone());
+        signed_tx
+    }
+
+    fn generate_block(txs: Vec<SignedTransaction>) -> Block {
+        let mut block = Block::new();
+        block.mut_body().set_transactions(RepeatedField::from_vec(txs));
+        block
+    }
+
+    #[test]
+    fn verify_valid_block() {
+        let mut verifier = ProposalVerifier::new(2);
+        let txs = (0..120).map(|i| generate_tx(vec![i as u8])).collect();
+        let block = generate_block(txs);
+        let hash = block.crypt_hash();
+        verifier.verify(hash, &block);
+        assert!(verifier.check(hash, &block));
+    }
+
+    #[test]
+    fn verify_invalid_block() {
+        let mut verifier = ProposalVerifier::new(2);
+        let mut txs: Vec<SignedTransaction> = (0..120).map(|i| generate_tx(vec![i as u8])).collect();
+        txs[100].set_signer(vec![0; 32]);
+        let block = generate_block(txs);
+        let hash = block.crypt_hash();
+        assert!(!verifier.check(hash, &block));
+    }
+}
@@ -54,6 +54,12 @@ impl VoteCollector {
             return None;
         }
     }
+
+    pub fn remove(&mut self, height: usize, round: usize, step: Step, sender: &Address) {
+        if let Some(round_votes) = self.votes.get_mut(&height) {
+            round_votes.remove(round, step, sender);
+        }
+    }
 }
 
 //round -> step collector
@@ -85,6 +91,12 @@ impl RoundCollector {
             return None;
         }
     }
+
+    pub fn remove(&mut self, round: usize, step: Step, sender: &Address) {
+        if let Some(step_votes) = self.round_votes.get_mut(&round) {
+            step_votes.remove(step, sender);
+        }
+    }
 }
 
 //step -> voteset
@@ -116,6 +128,12 @@ impl StepCollector {
             return None;
         }
     }
+
+    pub fn remove(&mut self, step: Step, sender: &Address) {
+        if let Some(vote_set) = self.step_votes.get_mut(&step) {
+            vote_set.remove(sender);
+        }
+    }
 }
 
 //1. sender's votemessage 2. proposal'hash count
@@ -154,6 +172,19 @@ impl VoteSet {
         }
     }
 
+    pub fn remove(&mut self, sender: &Address) {
+        if let Some(vote) = self.votes_by_sender.remove(sender) {
+            self.count -= 1;
+            let hash = vote.proposal.unwrap_or_default();
+            let left = self.votes_by_proposal.get(&hash).map_or(0, |c| *c - 1);
+            if left == 0 {
+                self.votes_by_proposal.remove(&hash);
+            } else {
+                self.votes_by_proposal.insert(hash, left);
+            }
+        }
+    }
+
     pub fn check(&self, h: usize, r: usize, step: Step, authorities: &[Address]) -> Result<Option<H256>, &str> {
         let mut votes_by_proposal = HashMap::new();
         for (sender, vote) in &self.votes_by_sender {
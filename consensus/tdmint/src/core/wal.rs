@@ -103,6 +103,11 @@ impl Wal {
         Ok(hlen)
     }
 
+    /// Sync the log to the disk, before a shutdown.
+    pub fn sync(&self) -> io::Result<()> {
+        self.fs.sync_all()
+    }
+
     pub fn load(&mut self) -> Vec<(u8, Vec<u8>)> {
         let mut vec_buf: Vec<u8> = Vec::new();
         let mut vec_out: Vec<(u8, Vec<u8>)> = Vec::new();
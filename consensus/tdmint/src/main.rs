@@ -19,6 +19,7 @@
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
+extern crate serde_json;
 extern crate libproto;
 extern crate util;
 extern crate threadpool;
@@ -42,20 +43,29 @@ extern crate dotenv;
 extern crate core as chain_core;
 extern crate cita_log;
 extern crate cpuprofiler;
+extern crate jsonrpc_types;
+extern crate ethcore_bloom_journal as bloom_journal;
 
+use cita_log::changelog;
 use clap::App;
 use log::LogLevelFilter;
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, sync_channel};
 use std::thread;
 
 mod core;
-use core::dispatchtx::{Dispatchtx, sub_new_tx};
+use core::dispatchtx::{Dispatchtx, sub_new_tx, TX_AUDIT_PATH};
 use core::spec::Spec;
 use core::tendermint::TenderMint;
+use core::txaudit::TxAudit;
+use core::verifier::ProposalVerifier;
 use core::votetime::WaitTimer;
 use cpuprofiler::PROFILER;
-use libproto::{parse_msg, key_to_id};
-use pubsub::start_pubsub;
+use libproto::{parse_msg, key_to_id, submodules};
+use libproto::handshake::start_handshake;
+use libproto::heartbeat::start_heartbeat;
+use libproto::reload::on_hangup;
+use libproto::shutdown::Shutdown;
+use pubsub::{start_pubsub, QUEUE_SIZE};
 use std::sync::Arc;
 
 const THREAD_POOL_NUM: usize = 10;
@@ -87,16 +97,27 @@ fn main() {
         .about("CITA Block Chain Node powered by Rust")
         .args_from_usage("-c, --config=[FILE] 'Sets a custom config file'")
         .args_from_usage("-n, --tx_pool_thread_num=[10] 'Transaction pool thread count'")
+        .args_from_usage("--verify_thread_num=[4] 'Proposal verification thread count'")
         .args_from_usage("--prof-start=[0] 'Specify the start time of profiling, zero means no profiling'")
         .args_from_usage("--prof-duration=[0] 'Specify the duration for profiling, zero means no profiling'")
+        .args_from_usage("--audit=[HASH] 'Print the events of the transaction in the tx pool journal and exit'")
         .get_matches();
 
+    if let Some(hash) = matches.value_of("audit") {
+        let path = ::std::env::var("DATA_PATH").expect("DATA_PATH must be set") + TX_AUDIT_PATH;
+        for entry in TxAudit::query(path, hash) {
+            println!("{}", serde_json::to_string(&entry).unwrap());
+        }
+        return;
+    }
+
     let mut config_path = "config";
     if let Some(c) = matches.value_of("config") {
         trace!("Value for config: {}", c);
         config_path = c;
     }
     let tx_pool_thread_num = matches.value_of("tx_pool_thread_num").unwrap_or("10").parse::<usize>().unwrap();
+    let verify_thread_num = matches.value_of("verify_thread_num").unwrap_or("4").parse::<usize>().unwrap();
     let flag_prof_start = matches.value_of("prof-start").unwrap_or("0").parse::<u64>().unwrap();
     let flag_prof_duration = matches.value_of("prof-duration").unwrap_or("0").parse::<u64>().unwrap();
 
@@ -104,42 +125,71 @@ fn main() {
     // timer module
     let (main2timer, timer4main) = channel();
     let (timer2main, main4timer) = channel();
-    let timethd = thread::spawn(move || {
-                                    let wt = WaitTimer::new(timer2main, timer4main);
-                                    wt.start();
-                                });
+    thread::spawn(move || {
+                      let wt = WaitTimer::new(timer2main, timer4main);
+                      wt.start();
+                  });
 
     //mq pubsub module
     let threadpool = threadpool::ThreadPool::new(THREAD_POOL_NUM);
     let (mq2main, main4mq) = channel();
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
-    start_pubsub("consensus", vec!["net.msg", "chain.status"], tx_sub, rx_pub);
+    start_pubsub("consensus", vec!["net.msg", "chain.status", "chain.sysconfig", "network.rtt", "jsonrpc.consensus"], tx_sub, rx_pub);
     thread::spawn(move || loop {
                       let (key, body) = rx_sub.recv().unwrap();
                       let tx = mq2main.clone();
                       let pool = threadpool.clone();
                       pool.execute(move || {
-                                       let (cmd_id, _, content) = parse_msg(body.as_slice());
+                                       let (cmd_id, _, content) = match parse_msg(body.as_slice()) {
+                                           Ok(parsed) => parsed,
+                                           Err(reason) => {
+                                               warn!("drop msg of {}: {}", key, reason);
+                                               return;
+                                           }
+                                       };
                                        tx.send((key_to_id(&key), cmd_id, content)).unwrap();
                                    });
                   });
 
     //main tendermint loop module
     let spec = Spec::new_test_tendermint(config_path);
-    let dispatch = Arc::new(Dispatchtx::new(spec.params.tx_filter_size, spec.params.block_tx_limit, spec.params.tx_pool_size));
-    sub_new_tx(dispatch.clone(), tx_pool_thread_num);
+    let shutdown = Shutdown::new(submodules::CONSENSUS, "consensus");
+    let dispatch = Arc::new(Dispatchtx::new(&spec.params));
+    sub_new_tx(dispatch.clone(), tx_pool_thread_num, shutdown.clone());
+    let verifier = ProposalVerifier::new(verify_thread_num);
     info!("main loop start **** ");
-    let mainthd = thread::spawn(move || {
-                                    let mut engine = TenderMint::new(tx_pub, main4mq, main2timer, main4timer, spec.params, dispatch);
-                                    engine.start();
-                                });
+    start_handshake("consensus", &[]);
+    start_heartbeat(submodules::CONSENSUS, "consensus", shutdown.clone(), tx_pub.clone());
+    let ack_pub = tx_pub.clone();
+    let engine_shutdown = shutdown.clone();
+    let (reload_tx, reload_rx) = channel();
+    let mut timer = spec.params.timer.clone();
+    let reload_path = config_path.to_string();
+    on_hangup(move || match Spec::reload(&reload_path) {
+                  Ok(spec) => {
+                      let new = spec.params.timer;
+                      changelog("consensus", "timeout_propose", &timer.propose, &new.propose);
+                      changelog("consensus", "timeout_prevote", &timer.prevote, &new.prevote);
+                      changelog("consensus", "timeout_precommit", &timer.precommit, &new.precommit);
+                      changelog("consensus", "timeout_commit", &timer.commit, &new.commit);
+                      if new != timer {
+                          timer = new.clone();
+                          let _ = reload_tx.send(new);
+                      }
+                  }
+                  Err(err) => warn!("ignore invalid consensus config: {}", err),
+              });
+    thread::spawn(move || {
+                      let mut engine = TenderMint::new(tx_pub, main4mq, main2timer, main4timer, spec.params, dispatch, verifier, engine_shutdown, reload_rx);
+                      engine.start();
+                  });
 
     /*let mut log = Wal::new("./yubo").unwrap();
     log.save("abcdefgh".to_string().into_bytes()).unwrap();
     log.save("1234567890".to_string().into_bytes()).unwrap();
     log.load();*/
 
-    mainthd.join().unwrap();
-    timethd.join().unwrap();
+    // the blocks are published and the transactions journaled as they are done
+    shutdown.run(&ack_pub, &[], || {});
 }
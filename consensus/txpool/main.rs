@@ -40,10 +40,10 @@ mod cmd;
 use candidate_pool::*;
 use libproto::{key_to_id, parse_msg};
 use log::LogLevelFilter;
-use pubsub::start_pubsub;
+use pubsub::{start_pubsub, QUEUE_SIZE};
 use std::thread;
 
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, sync_channel};
 use threadpool::ThreadPool;
 
 const THREAD_POOL_NUMBER: usize = 2;
@@ -54,7 +54,7 @@ fn main() {
     ::std::env::set_var("RUST_BACKTRACE", "1");
     cita_log::format(LogLevelFilter::Info);
     info!("CITA:txpool");
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
     let (tx, rx) = channel();
     let keys = vec!["net.*", "consensus_cmd.default", "consensus.blk", "chain.status", "jsonrpc.new_tx"];
@@ -64,7 +64,13 @@ fn main() {
         let sender = tx.clone();
         let (key, body) = rx_sub.recv().unwrap();
         pool.execute(move || {
-            let (cmd_id, origin, content) = parse_msg(&body);
+            let (cmd_id, origin, content) = match parse_msg(&body) {
+                Ok(parsed) => parsed,
+                Err(reason) => {
+                    warn!("drop msg of {}: {}", key, reason);
+                    return;
+                }
+            };
             sender.send((key_to_id(&key), cmd_id, origin, content)).unwrap();
         });
     });
@@ -19,11 +19,20 @@
 use jsonrpc_types::error::Error;
 use jsonrpc_types::request::RpcRequest;
 use serde_json;
+use std::net::IpAddr;
 use std::result;
 
 
 pub type RpcResult<T> = result::Result<T, Error>;
 
+/// Origin of the transactions of a client of the address, by which auth limits their rate.
+pub fn ip_origin(ip: IpAddr) -> u32 {
+    match ip {
+        IpAddr::V4(ip) => u32::from(ip),
+        IpAddr::V6(ip) => ip.segments().chunks(2).fold(0, |origin, pair| origin ^ ((pair[0] as u32) << 16 | pair[1] as u32)),
+    }
+}
+
 pub trait BaseHandler {
     fn select_topic(method: &String) -> String {
         let topic = if method.starts_with("cita_send") {
@@ -32,6 +41,8 @@ pub trait BaseHandler {
                         "jsonrpc.request"
                     } else if method.starts_with("net_") {
                         "jsonrpc.net"
+                    } else if method.starts_with("consensus_") {
+                        "jsonrpc.consensus"
                     } else {
                         "jsonrpc"
                     }
@@ -77,6 +88,7 @@ mod test {
         assert_eq!(Handler::select_topic(&"cita_send".to_string()), "jsonrpc.new_tx".to_string());
         assert_eq!(Handler::select_topic(&"cita".to_string()), "jsonrpc.request".to_string());
         assert_eq!(Handler::select_topic(&"eth".to_string()), "jsonrpc.request".to_string());
+        assert_eq!(Handler::select_topic(&"consensus_metrics".to_string()), "jsonrpc.consensus".to_string());
         assert_eq!(Handler::select_topic(&"123".to_string()), "jsonrpc".to_string());
     }
 
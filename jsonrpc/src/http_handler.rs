@@ -17,7 +17,8 @@
 
 #![allow(deprecated,unused_assignments, unused_must_use)]
 
-use base_hanlder::{BaseHandler, RpcResult};
+use base_hanlder::{ip_origin, BaseHandler, RpcResult};
+use cita_log;
 use hyper::Post;
 use hyper::server::{Handler, Request, Response};
 use hyper::uri::RequestUri::AbsolutePath;
@@ -36,6 +37,7 @@ use std::hash::Hash;
 use std::io::Read;
 use std::result;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::Sender;
 use std::thread;
 use std::time::Duration;
@@ -47,8 +49,10 @@ pub struct RpcHandler {
     pub tx: Arc<Mutex<Sender<(String, Vec<u8>)>>>,
     pub responses: Arc<RwLock<HashMap<Vec<u8>, request::Response>>>,
     pub tx_responses: Arc<RwLock<HashMap<H256, blockchain::TxResponse>>>,
-    pub sleep_duration: usize,
-    pub timeout_count: usize,
+    /// Milliseconds between the checks for a response, and the checks before the timeout,
+    /// set again when the config is reloaded.
+    pub sleep_duration: Arc<AtomicUsize>,
+    pub timeout_count: Arc<AtomicUsize>,
     pub method_handler: method::MethodHandler,
 }
 
@@ -75,17 +79,22 @@ impl RpcHandler {
     }
 
 
-    pub fn deal_req(&self, post_data: String) -> Result<RpcSuccess, RpcFailure> {
+    pub fn deal_req(&self, post_data: String, origin: u32) -> Result<RpcSuccess, RpcFailure> {
         match RpcHandler::into_json(post_data) {
             Err(err) => Err(RpcFailure::from(err)),
             Ok(rpc) => {
+                // the request enters the system
+                cita_log::set_trace_id(&cita_log::new_trace_id());
+                debug!("trace {} from {}", rpc.method, origin);
                 let req_id = rpc.id.clone();
                 let jsonrpc_version = rpc.jsonrpc.clone();
                 let topic = RpcHandler::select_topic(&rpc.method);
                 match self.method_handler.from_req(rpc)? {
                     method::RpcReqType::TX(tx) => {
                         let hash = tx.crypt_hash();
-                        self.send_mq(topic, tx.into(), self.tx_responses.clone(), hash)
+                        let mut msg: communication::Message = tx.into();
+                        msg.set_origin(origin);
+                        self.send_mq(topic, msg, self.tx_responses.clone(), hash)
                             .map_err(|err_data| RpcFailure::from_options(req_id.clone(), jsonrpc_version.clone(), err_data))
                             .map(|data| {
                                      RpcSuccess {
@@ -98,12 +107,13 @@ impl RpcHandler {
                     method::RpcReqType::REQ(req) => {
                         let key = req.request_id.clone();
                         self.send_mq(topic, req.into(), self.responses.clone(), key)
+                            .and_then(|data| cita_response::into_result(data.result.expect("chain response error")))
                             .map_err(|err_data| RpcFailure::from_options(req_id.clone(), jsonrpc_version.clone(), err_data))
-                            .map(|data| {
+                            .map(|result| {
                                      RpcSuccess {
                                          jsonrpc: jsonrpc_version,
                                          id: req_id,
-                                         result: cita_response::ResponseBody::from(data.result.expect("chain response error")), //TODO
+                                         result: result,
                                      }
                                  })
                     }
@@ -125,11 +135,11 @@ impl RpcHandler {
         let mut timeout_count = 0;
         loop {
             timeout_count = timeout_count + 1;
-            if timeout_count > self.timeout_count {
+            if timeout_count > self.timeout_count.load(Ordering::Relaxed) {
                 //TODO
                 return Err(Error::server_error(-32099, "system time out,please resend"));
             }
-            thread::sleep(Duration::new(0, (self.sleep_duration * 1000000) as u32));
+            thread::sleep(Duration::new(0, (self.sleep_duration.load(Ordering::Relaxed) * 1000000) as u32));
             if responses.read().contains_key(&key) {
                 let mut responses = responses.write();
                 if let Some(res) = responses.remove(&key) {
@@ -148,11 +158,12 @@ impl RpcHandler {
 impl Handler for RpcHandler {
     fn handle(&self, req: Request, res: Response) {
         //TODO 不允许在这里做业务处理。
+        let origin = ip_origin(req.remote_addr.ip());
         let data = match self.pase_url(req) {
             Err(err) => serde_json::to_string(&RpcFailure::from(err)),
             Ok(body) => {
                 trace!("Request data {:?}", body);
-                match self.deal_req(body) {
+                match self.deal_req(body, origin) {
                     Ok(ret) => serde_json::to_string(&ret),
                     Err(err) => serde_json::to_string(&err),
                 }
@@ -53,6 +53,7 @@ pub mod ws_handler;
 pub mod config;
 
 use base_hanlder::TransferType;
+use cita_log::changelog;
 use clap::App;
 use config::ProfileConfig;
 use cpuprofiler::PROFILER;
@@ -60,12 +61,18 @@ use dotenv::dotenv;
 use http_handler::RpcHandler;
 use hyper::server::Server;
 use jsonrpc_types::method;
+use libproto::submodules;
+use libproto::handshake::start_handshake;
+use libproto::heartbeat::start_heartbeat;
+use libproto::reload::on_hangup;
+use libproto::shutdown::Shutdown;
 use log::LogLevelFilter;
 use parking_lot::{RwLock, Mutex};
-use pubsub::start_pubsub;
+use pubsub::{start_pubsub, QUEUE_SIZE};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::mpsc::channel;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel};
 use std::thread;
 use std::time::Duration;
 use ws_handler::WsFactory;
@@ -121,13 +128,37 @@ fn main() {
     start_profile(&config.profile_config);
 
     // init pubsub
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
     start_pubsub("jsonrpc", vec!["*.rpc"], tx_sub, rx_pub);
+    let shutdown = Shutdown::new(submodules::JSON_RPC, "jsonrpc");
+    start_handshake("jsonrpc", &[]);
+    start_heartbeat(submodules::JSON_RPC, "jsonrpc", shutdown.clone(), tx_pub.clone());
+    let ack_pub = tx_pub.clone();
 
     //mq
     let mut new_subscriber = mq_hanlder::MqHandler::new();
 
+    // the timeouts of the http requests may change on SIGHUP
+    let sleep_duration = Arc::new(AtomicUsize::new(config.http_config.sleep_duration));
+    let timeout_count = Arc::new(AtomicUsize::new(config.http_config.timeout_count));
+    let mut current = config.http_config.clone();
+    let (reload_sleep, reload_timeout) = (sleep_duration.clone(), timeout_count.clone());
+    let reload_path = config_path.to_string();
+    on_hangup(move || match config::read_user_from_file(&reload_path) {
+                  Ok(config) => {
+                      let new = config.http_config;
+                      if changelog("jsonrpc", "sleep_duration", &current.sleep_duration, &new.sleep_duration) {
+                          reload_sleep.store(new.sleep_duration, Ordering::Relaxed);
+                      }
+                      if changelog("jsonrpc", "timeout_count", &current.timeout_count, &new.timeout_count) {
+                          reload_timeout.store(new.timeout_count, Ordering::Relaxed);
+                      }
+                      current = new;
+                  }
+                  Err(err) => warn!("ignore invalid jsonrpc config: {}", err),
+              });
+
     //http
     if config.http_config.enable {
         new_subscriber.set_http_or_ws(TransferType::HTTP, 0);
@@ -145,8 +176,8 @@ fn main() {
                                                                   responses: http_responses,
                                                                   tx: arc_tx,
                                                                   tx_responses: http_tx_responses,
-                                                                  sleep_duration: http_config.sleep_duration,
-                                                                  timeout_count: http_config.timeout_count,
+                                                                  sleep_duration: sleep_duration,
+                                                                  timeout_count: timeout_count,
                                                                   method_handler: method::MethodHandler,
                                                               },
                                                               http_config.thread_number);
@@ -172,8 +203,11 @@ fn main() {
         });
     }
 
-    loop {
-        let (key, msg) = rx_sub.recv().unwrap();
-        new_subscriber.handle(key, msg);
-    }
+    thread::spawn(move || loop {
+                      let (key, msg) = rx_sub.recv().unwrap();
+                      new_subscriber.handle(key, msg);
+                  });
+
+    // the requests in flight are answered by the time the other modules stop
+    shutdown.run(&ack_pub, &[], || {});
 }
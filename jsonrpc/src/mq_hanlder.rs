@@ -17,7 +17,7 @@
 
 #![allow(deprecated,unused_assignments, unused_must_use)]
 use base_hanlder::TransferType;
-use jsonrpc_types::response::{RpcSuccess, ResponseBody};
+use jsonrpc_types::response::{self as cita_response, RpcSuccess, RpcFailure, ResponseBody};
 use libproto::{submodules, topics, parse_msg, cmd_id, display_cmd, MsgClass, blockchain, request};
 use num_cpus;
 use parking_lot::{RwLock, Mutex};
@@ -78,7 +78,13 @@ impl MqHandler {
     }
 
     pub fn handle(&mut self, key: String, body: Vec<u8>) {
-        let (id, _, content_ext) = parse_msg(body.as_slice());
+        let (id, _, content_ext) = match parse_msg(body.as_slice()) {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                warn!("drop msg of {}: {}", key, reason);
+                return;
+            }
+        };
         trace!("routint_key {:?},get msg cmid {:?}", key, display_cmd(id));
         //TODO match
 
@@ -98,12 +104,17 @@ impl MqHandler {
                             let pair = ws_responses.lock().remove(&content.request_id);
                             drop(ws_responses);
                             if let Some(pair) = pair {
-                                let rpc_success = RpcSuccess {
-                                    jsonrpc: pair.0.jsonrpc.clone(),
-                                    id: pair.0.id.clone(),
-                                    result: ResponseBody::from(content.result.expect("chain response error")), //TODO
+                                let data = match cita_response::into_result(content.result.expect("chain response error")) {
+                                    Ok(result) => {
+                                        let rpc_success = RpcSuccess {
+                                            jsonrpc: pair.0.jsonrpc.clone(),
+                                            id: pair.0.id.clone(),
+                                            result: result,
+                                        };
+                                        serde_json::to_string(&rpc_success).unwrap()
+                                    }
+                                    Err(err) => serde_json::to_string(&RpcFailure::from_options(pair.0.id.clone(), pair.0.jsonrpc.clone(), err)).unwrap(),
                                 };
-                                let data = serde_json::to_string(&rpc_success).unwrap();
                                 pair.1.send(data);
                             }
                         })
@@ -17,6 +17,7 @@
 
 #![allow(deprecated,unused_assignments, unused_must_use)]
 use base_hanlder::BaseHandler;
+use cita_log;
 use jsonrpc_types::Id;
 use jsonrpc_types::method;
 use jsonrpc_types::request::Version;
@@ -91,6 +92,9 @@ impl Handler for WsHandler {
             let err = match WsHandler::into_json(msg.into_text().unwrap()) {
                 Err(err) => Err(err),
                 Ok(rpc) => {
+                    // the request enters the system
+                    cita_log::set_trace_id(&cita_log::new_trace_id());
+                    debug!("trace {} from ws {}", rpc.method, _self.sender.token().0);
                     let req_id = rpc.id.clone();
                     let jsonrpc_version = rpc.jsonrpc.clone();
                     let topic = WsHandler::select_topic(&rpc.method);
@@ -103,7 +107,9 @@ impl Handler for WsHandler {
                         match req_type {
                             method::RpcReqType::TX(tx_req) => {
                                 let hash = tx_req.crypt_hash();
-                                let data: communication::Message = tx_req.into();
+                                let mut data: communication::Message = tx_req.into();
+                                // limited by the connection
+                                data.set_origin(_self.sender.token().0 as u32);
                                 let _ = _self.tx.send((topic, data.write_to_bytes().unwrap()));
                                 _self.tx_responses.lock().insert(hash, (req_info, _self.sender.clone()));
                             }
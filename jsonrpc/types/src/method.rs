@@ -20,7 +20,7 @@ use super::{Params, Error, RpcRequest};
 use libproto::blockchain;
 use libproto::request as reqlib;
 use protobuf::core::parse_from_bytes;
-use rpctypes::{BlockNumber, CallRequest, Filter, CountAndCode, BlockParamsByHash, BlockParamsByNumber};
+use rpctypes::{BlockNumber, CallRequest, Filter, CountAndCode, AccountTransactions, StorageHistory, SimulateTransaction, BlockParamsByHash, BlockParamsByNumber};
 use rustc_serialize::hex::FromHex;
 use serde_json;
 use std::str::FromStr;
@@ -42,6 +42,16 @@ pub mod method {
     pub const CITA_GET_TRANSACTION: &'static str = "cita_getTransaction";
     pub const CITA_SEND_TRANSACTION: &'static str = "cita_sendTransaction";
     pub const NET_PEER_COUNT: &'static str = "net_peerCount";
+    /// Peers banned for misbehaving and the seconds left of their bans.
+    pub const NET_PEER_BANS: &'static str = "net_peerBans";
+    /// Lift the ban of the peer and reconnect it at once.
+    /// Parameters
+    /// 1. QUANTITY - id_card of the peer.
+    /// Connection, score, round trip time and traffic of the peers.
+    pub const NET_PEERS_INFO: &'static str = "net_peersInfo";
+    pub const NET_ENABLE_PEER: &'static str = "net_enablePeer";
+    /// Rounds, steps, missed proposers and vote latencies of the consensus, with its pool of transactions.
+    pub const CONSENSUS_METRICS: &'static str = "consensus_metrics";
     /// Executes a new message call immediately without creating a transaction on the block chain.
     /// Parameters
     /// 1. Object - The transaction call object
@@ -54,6 +64,41 @@ pub mod method {
     pub const ETH_CALL: &'static str = "eth_call";
     pub const ETH_GET_LOGS: &'static str = "eth_getLogs";
     pub const ETH_GET_TRANSACTION_RECEIPT: &'static str = "eth_getTransactionReceipt";
+    /// Accounts and storage changed by a block, if the node records them.
+    /// Parameters
+    /// 1. QUANTITY|TAG - integer block height, or the string "latest" or "earliest".
+    pub const CITA_GET_STATE_DIFF: &'static str = "cita_getStateDiff";
+    /// Hashes of the transactions sent or received by an account, at most 1000 of them.
+    /// Parameters
+    /// 1. DATA, 20 Bytes - address of the account.
+    /// 2. QUANTITY|TAG - integer block height to search from, or the string "latest" or "earliest".
+    /// 3. QUANTITY|TAG - integer block height to search to, or the string "latest" or "earliest".
+    pub const CITA_GET_TRANSACTIONS_BY_ACCOUNT: &'static str = "cita_getTransactionsByAccount";
+    /// Value of a storage key of an account at each block of a range, at most 1000 of them.
+    /// The states of the range must be kept by the node, as on an archive node.
+    /// Parameters
+    /// 1. DATA, 20 Bytes - address of the account.
+    /// 2. DATA, 32 Bytes - storage key.
+    /// 3. QUANTITY|TAG - integer block height to start from, or the string "latest" or "earliest".
+    /// 4. QUANTITY|TAG - integer block height to end at, or the string "latest" or "earliest".
+    pub const CITA_GET_STORAGE_HISTORY: &'static str = "cita_getStorageHistory";
+    /// Proof of a block for the light clients: the header, the hash of the validators and their
+    /// commit signatures, in the versioned encoding of `LightClientProof`. The commit of a block
+    /// is carried by the next one, so the latest block has no proof yet.
+    /// Parameters
+    /// 1. QUANTITY - integer block height.
+    pub const CITA_GET_LIGHT_CLIENT_PROOF: &'static str = "cita_getLightClientProof";
+    /// Execute again a transaction of the chain with the state diff, on the state left by the
+    /// transactions before it in its block, which the node must still keep.
+    /// Parameters
+    /// 1. DATA, 32 Bytes - hash of the transaction.
+    pub const DEBUG_REPLAY_TRANSACTION: &'static str = "debug_replayTransaction";
+    /// Execute a signed transaction with the state diff as if it were the first one of a block,
+    /// without checking its nonce nor sending it.
+    /// Parameters
+    /// 1. DATA - the signed transaction, encoded as for cita_sendTransaction.
+    /// 2. QUANTITY|TAG - integer block height, or the string "latest" or "earliest".
+    pub const DEBUG_SIMULATE_TRANSACTION: &'static str = "debug_simulateTransaction";
 
     /// filter
     pub const ETH_NEW_FILTER: &'static str = "eth_newFilter";
@@ -91,6 +136,22 @@ impl MethodHandler {
                 let peer = self.peer_count(rpc)?;
                 Ok(RpcReqType::REQ(peer))
             }
+            method::NET_PEER_BANS => {
+                let bans = self.peer_bans(rpc)?;
+                Ok(RpcReqType::REQ(bans))
+            }
+            method::NET_PEERS_INFO => {
+                let peers = self.peers_info(rpc)?;
+                Ok(RpcReqType::REQ(peers))
+            }
+            method::CONSENSUS_METRICS => {
+                let metrics = self.consensus_metrics(rpc)?;
+                Ok(RpcReqType::REQ(metrics))
+            }
+            method::NET_ENABLE_PEER => {
+                let enable = self.enable_peer(rpc)?;
+                Ok(RpcReqType::REQ(enable))
+            }
             method::CITA_GET_BLOCK_BY_HASH => {
                 let block = self.get_block_by_hash(rpc)?;
                 Ok(RpcReqType::REQ(block))
@@ -123,6 +184,30 @@ impl MethodHandler {
                 let gc = self.get_code(rpc)?;
                 Ok(RpcReqType::REQ(gc))
             }
+            method::CITA_GET_STATE_DIFF => {
+                let diff = self.get_state_diff(rpc)?;
+                Ok(RpcReqType::REQ(diff))
+            }
+            method::CITA_GET_TRANSACTIONS_BY_ACCOUNT => {
+                let txs = self.get_transactions_by_account(rpc)?;
+                Ok(RpcReqType::REQ(txs))
+            }
+            method::CITA_GET_STORAGE_HISTORY => {
+                let history = self.get_storage_history(rpc)?;
+                Ok(RpcReqType::REQ(history))
+            }
+            method::CITA_GET_LIGHT_CLIENT_PROOF => {
+                let proof = self.get_light_client_proof(rpc)?;
+                Ok(RpcReqType::REQ(proof))
+            }
+            method::DEBUG_REPLAY_TRANSACTION => {
+                let replay = self.replay_transaction(rpc)?;
+                Ok(RpcReqType::REQ(replay))
+            }
+            method::DEBUG_SIMULATE_TRANSACTION => {
+                let simulation = self.simulate_transaction(rpc)?;
+                Ok(RpcReqType::REQ(simulation))
+            }
             method::CITA_SEND_TRANSACTION => {
                 let tx = self.send_transaction(rpc)?;
                 Ok(RpcReqType::TX(tx))
@@ -175,6 +260,35 @@ impl MethodHandler {
         Ok(request)
     }
 
+    pub fn peer_bans(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        drop(req_rpc);
+        let mut request = self.create_request();
+        request.set_peer_bans(true);
+        Ok(request)
+    }
+
+    pub fn peers_info(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        drop(req_rpc);
+        let mut request = self.create_request();
+        request.set_peers_info(true);
+        Ok(request)
+    }
+
+    pub fn consensus_metrics(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        drop(req_rpc);
+        let mut request = self.create_request();
+        request.set_consensus_metrics(true);
+        Ok(request)
+    }
+
+    pub fn enable_peer(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let params: (String,) = req_rpc.params.parse()?;
+        let id_card = U256::from_str(clean_0x(&params.0)).map_err(|_| Error::invalid_params("param error"))?;
+        request.set_enable_peer(id_card.into());
+        Ok(request)
+    }
+
     pub fn block_number(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
         drop(req_rpc);
         let mut request = self.create_request();
@@ -294,6 +408,70 @@ impl MethodHandler {
         Ok(request)
     }
 
+    pub fn get_state_diff(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let params: (BlockNumber,) = req_rpc.params.parse()?;
+        serde_json::to_string(&params.0)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|height| {
+                     request.set_state_diff(height);
+                     request
+                 })
+    }
+
+    pub fn get_transactions_by_account(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let (address, from, to): (H160, BlockNumber, BlockNumber) = req_rpc.params.parse()?;
+        let param = AccountTransactions::new(address.to_vec(), from, to);
+        serde_json::to_string(&param)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|param| {
+                     request.set_transactions_by_account(param);
+                     request
+                 })
+    }
+
+    pub fn get_storage_history(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let (address, key, from, to): (H160, H256, BlockNumber, BlockNumber) = req_rpc.params.parse()?;
+        let param = StorageHistory::new(address.to_vec(), key.to_vec(), from, to);
+        serde_json::to_string(&param)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|param| {
+                     request.set_storage_history(param);
+                     request
+                 })
+    }
+
+    pub fn get_light_client_proof(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let params: (String,) = req_rpc.params.parse()?;
+        let height = U256::from_str(clean_0x(&params.0)).map_err(|_| Error::invalid_params("param error"))?;
+        request.set_light_client_proof(height.into());
+        Ok(request)
+    }
+
+    pub fn replay_transaction(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let params: (H256,) = req_rpc.params.parse()?;
+        request.set_replay_transaction(params.0.to_vec());
+        Ok(request)
+    }
+
+    pub fn simulate_transaction(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
+        let mut request = self.create_request();
+        let (data, block_id): (String, BlockNumber) = req_rpc.params.parse()?;
+        let content = clean_0x(&data).from_hex().map_err(|_| Error::parse_error())?;
+        parse_from_bytes::<blockchain::SignedTransaction>(&content[..]).map_err(|_| Error::parse_error())?;
+        let param = SimulateTransaction::new(content, block_id);
+        serde_json::to_string(&param)
+            .map_err(|err| Error::invalid_params(err.to_string()))
+            .map(|param| {
+                     request.set_simulate_transaction(param);
+                     request
+                 })
+    }
+
     pub fn new_filter(&self, req_rpc: RpcRequest) -> Result<reqlib::Request, Error> {
         let mut request = self.create_request();
         let params: (Filter,) = req_rpc.params.parse()?;
@@ -418,6 +596,48 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_light_client_proof() {
+        let rpc: RpcRequest = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"cita_getLightClientProof","params":["0x10"],"id":1}"#).unwrap();
+        match MethodHandler.from_req(rpc) {
+            Ok(RpcReqType::REQ(request)) => assert_eq!(request.get_light_client_proof(), 16),
+            _ => panic!("not a request"),
+        }
+
+        let rpc: RpcRequest = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"cita_getLightClientProof","params":["latest"],"id":1}"#).unwrap();
+        assert!(MethodHandler.from_req(rpc).is_err());
+    }
+
+    #[test]
+    fn test_replay_transaction() {
+        let hash = "0x00000000000000000000000000000000000000000000000000000000000000ff";
+        let rpc: RpcRequest = serde_json::from_str(&format!(r#"{{"jsonrpc":"2.0","method":"debug_replayTransaction","params":["{}"],"id":1}}"#, hash)).unwrap();
+        match MethodHandler.from_req(rpc) {
+            Ok(RpcReqType::REQ(request)) => assert_eq!(request.get_replay_transaction(), &H256::from(0xff).to_vec()[..]),
+            _ => panic!("not a request"),
+        }
+
+        let rpc: RpcRequest = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"debug_replayTransaction","params":["0xzz"],"id":1}"#).unwrap();
+        assert!(MethodHandler.from_req(rpc).is_err());
+    }
+
+    #[test]
+    fn test_simulate_transaction() {
+        let tx = "0x0a540a0f0a03313233120130189f8d0622010112416a9b0881216be79608d319d9036b09f157949e1e4d54a479b314cea0a52b20ee6fa0aac4486e23f5ba6cae35bb6a44090828f826c0866d704e926cb8904af885011220406b9490d3357ca7bfa8af64de45a2dd4a626b071388296bb5ca76817fc1484b1a40841de155ac4aacc27e9068cefbae5108a0210ebeaed06a293cf5e26cd806e03f50d7887fd3321745b4f6b81e4d45822d72da2adf0a9f3a870619af88fdf17db3";
+        let rpc: RpcRequest = serde_json::from_str(&format!(r#"{{"jsonrpc":"2.0","method":"debug_simulateTransaction","params":["{}","latest"],"id":1}}"#, tx)).unwrap();
+        match MethodHandler.from_req(rpc) {
+            Ok(RpcReqType::REQ(request)) => {
+                let param: SimulateTransaction = serde_json::from_str(request.get_simulate_transaction()).unwrap();
+                assert_eq!(param.transaction, clean_0x(tx).from_hex().unwrap());
+                assert_eq!(param.block_id, BlockNumber::default());
+            }
+            _ => panic!("not a request"),
+        }
+
+        let rpc: RpcRequest = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"debug_simulateTransaction","params":["0xzz","latest"],"id":1}"#).unwrap();
+        assert!(MethodHandler.from_req(rpc).is_err());
+    }
+
     #[test]
     fn test_rpc_into_err2() {
         let rpc = RpcRequest {
@@ -22,12 +22,14 @@ use error::Error;
 use libproto::blockchain::TxResponse as ProtoTxResponse;
 use libproto::request as reqlib;
 use request::Version;
-use rpctypes::{Receipt, Log, RpcTransaction, Block, RpcBlock};
+use rpctypes::{Receipt, Log, RpcTransaction, Block, RpcBlock, StateDiff};
 use serde_json;
 use std::string::String;
 use std::vec::Vec;
-use util::{H256, U256};
+use util::{H160, H256, U256};
 
+/// Code of the errors reported by chain.
+pub const CHAIN_ERROR_CODE: i64 = 102;
 
 //TODO respone contain error
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -36,6 +38,50 @@ pub struct TxResponse {
     pub status: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PeerBan {
+    pub id_card: u32,
+    /// Seconds until the ban is lifted.
+    pub remaining: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PeerInfo {
+    pub id_card: u32,
+    pub addr: String,
+    pub connected: bool,
+    pub banned: bool,
+    pub score: i32,
+    /// Smoothed round trip time in milliseconds, null before the first pong.
+    pub rtt: Option<u64>,
+    pub height: Option<u64>,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct StorageValue {
+    #[serde(rename = "blockNumber")]
+    pub block_number: U256,
+    pub value: H256,
+}
+
+/// Outcome of a transaction executed again by the debug methods, with the accounts it changed.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct TransactionTrace {
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    #[serde(rename = "cumulativeGasUsed")]
+    pub cumulative_gas_used: U256,
+    pub output: Bytes,
+    /// Error of the execution, null if it succeeded.
+    pub exception: Option<String>,
+    #[serde(rename = "contractsCreated")]
+    pub contracts_created: Vec<H160>,
+    #[serde(rename = "stateDiff")]
+    pub state_diff: Option<StateDiff>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum ResponseBody {
@@ -55,6 +101,15 @@ pub enum ResponseBody {
     UninstallFliter(bool),
     FilterChanges(Bytes),
     FilterLog(Bytes),
+    PeerBans(Vec<PeerBan>),
+    PeersInfo(Vec<PeerInfo>),
+    EnablePeer(bool),
+    StateDiff(StateDiff),
+    TransactionHashes(Vec<H256>),
+    StorageHistory(Vec<StorageValue>),
+    LightClientProof(Bytes),
+    TransactionTrace(TransactionTrace),
+    ConsensusMetrics(serde_json::Value),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -111,10 +166,56 @@ impl From<ResponseResult> for ResponseBody {
             ResponseResult::uninstall_filter(x) => ResponseBody::UninstallFliter(x),
             ResponseResult::filter_changes(x) => ResponseBody::FilterChanges(Bytes::from(x)),
             ResponseResult::filter_logs(x) => ResponseBody::FilterLog(Bytes::from(x)),
+            ResponseResult::peer_bans(serialized) => {
+                serde_json::from_str::<Vec<PeerBan>>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |bans| ResponseBody::PeerBans(bans))
+            }
+            ResponseResult::enable_peer(x) => ResponseBody::EnablePeer(x),
+            ResponseResult::peers_info(serialized) => {
+                serde_json::from_str::<Vec<PeerInfo>>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |peers| ResponseBody::PeersInfo(peers))
+            }
+            ResponseResult::state_diff(serialized) => {
+                serde_json::from_str::<StateDiff>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |diff| ResponseBody::StateDiff(diff))
+            }
+            ResponseResult::transactions_by_account(serialized) => {
+                serde_json::from_str::<Vec<H256>>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |hashes| ResponseBody::TransactionHashes(hashes))
+            }
+            ResponseResult::storage_history(serialized) => {
+                serde_json::from_str::<Vec<StorageValue>>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |values| ResponseBody::StorageHistory(values))
+            }
+            ResponseResult::light_client_proof(x) => ResponseBody::LightClientProof(Bytes::from(x)),
+            ResponseResult::transaction_trace(serialized) => {
+                serde_json::from_str::<TransactionTrace>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |trace| ResponseBody::TransactionTrace(trace))
+            }
+            ResponseResult::consensus_metrics(serialized) => {
+                serde_json::from_str::<serde_json::Value>(&serialized)
+                    .ok()
+                    .map_or(ResponseBody::Null, |metrics| ResponseBody::ConsensusMetrics(metrics))
+            }
+            ResponseResult::error_msg(_) => ResponseBody::Null,
         }
     }
 }
 
+/// Errors of chain, such as the data pruned by the node, become failures.
+pub fn into_result(res: ResponseResult) -> Result<ResponseBody, Error> {
+    match res {
+        ResponseResult::error_msg(msg) => Err(Error::server_error(CHAIN_ERROR_CODE, &msg)),
+        res => Ok(ResponseBody::from(res)),
+    }
+}
+
 impl From<Error> for RpcFailure {
     fn from(err: Error) -> Self {
         RpcFailure {
@@ -155,6 +256,14 @@ mod tests {
         assert_eq!(rpc_body, r#"{"jsonrpc":"2.0","id":2,"result":null}"#);
     }
 
+    #[test]
+    fn test_chain_error() {
+        let err = into_result(ResponseResult::error_msg("state of block 1 has been pruned".to_string())).unwrap_err();
+        assert_eq!(err.code.code(), CHAIN_ERROR_CODE);
+        assert_eq!(err.message, "state of block 1 has been pruned");
+        assert_eq!(into_result(ResponseResult::none(true)).unwrap(), ResponseBody::Null);
+    }
+
     #[test]
     fn test_rpc_deserialize2() {
         let rpc = RpcSuccess {
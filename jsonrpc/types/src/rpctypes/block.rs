@@ -17,6 +17,7 @@
 
 use super::{BlockTransaction, FullTransaction, TransactionHash};
 use super::RpcBlock;
+use bytes::Bytes;
 use libproto::blockchain::Block as ProtoBlock;
 use libproto::blockchain::BlockHeader as ProtoBlockHeader;
 use proof::CitaProof;
@@ -44,6 +45,8 @@ pub struct BlockHeader {
     #[serde(rename = "gasUsed")]
     pub gas_used: U256,
     pub proof: CitaProof,
+    /// Encoded extra fields of the headers from version 1.
+    pub extra: Bytes,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -65,6 +68,7 @@ impl From<ProtoBlockHeader> for BlockHeader {
             receipts_root: H256::from(proto_header.get_receipts_root()),
             gas_used: U256::from(proto_header.get_gas_used()),
             proof: proto_header.clone().take_proof().into(),
+            extra: Bytes::from(proto_header.get_extra().to_vec()),
         }
     }
 }
@@ -43,6 +43,60 @@ impl Default for CountAndCode {
 }
 
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AccountTransactions {
+    pub address: ::std::vec::Vec<u8>,
+    pub from_block: BlockNumber,
+    pub to_block: BlockNumber,
+}
+
+impl AccountTransactions {
+    pub fn new(address: Vec<u8>, from_block: BlockNumber, to_block: BlockNumber) -> AccountTransactions {
+        AccountTransactions {
+            address: address,
+            from_block: from_block,
+            to_block: to_block,
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StorageHistory {
+    pub address: ::std::vec::Vec<u8>,
+    pub key: ::std::vec::Vec<u8>,
+    pub from_block: BlockNumber,
+    pub to_block: BlockNumber,
+}
+
+impl StorageHistory {
+    pub fn new(address: Vec<u8>, key: Vec<u8>, from_block: BlockNumber, to_block: BlockNumber) -> StorageHistory {
+        StorageHistory {
+            address: address,
+            key: key,
+            from_block: from_block,
+            to_block: to_block,
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct SimulateTransaction {
+    pub transaction: ::std::vec::Vec<u8>,
+    pub block_id: BlockNumber,
+}
+
+impl SimulateTransaction {
+    pub fn new(transaction: Vec<u8>, block_id: BlockNumber) -> SimulateTransaction {
+        SimulateTransaction {
+            transaction: transaction,
+            block_id: block_id,
+        }
+    }
+}
+
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct BlockParamsByHash {
     pub hash: ::std::vec::Vec<u8>,
@@ -29,6 +29,7 @@ pub mod transaction;
 pub mod block;
 pub mod middle_modle;
 pub mod index;
+pub mod state_diff;
 
 pub use self::block::*;
 pub use self::block_number::*;
@@ -38,4 +39,5 @@ pub use self::index::Index;
 pub use self::log::*;
 pub use self::middle_modle::*;
 pub use self::receipt::*;
+pub use self::state_diff::StateDiff;
 pub use self::transaction::*;
@@ -0,0 +1,114 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use std::collections::BTreeMap;
+use types::account_diff::{AccountDiff as EthAccountDiff, Diff as EthDiff};
+use types::state_diff::StateDiff as EthStateDiff;
+use util::{H160, H256, U256};
+
+/// Change of a value, "=" if it stays the same.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum Diff<T> {
+    #[serde(rename = "=")]
+    Same,
+    #[serde(rename = "+")]
+    Born(T),
+    #[serde(rename = "*")]
+    Changed(ChangedType<T>),
+    #[serde(rename = "-")]
+    Died(T),
+}
+
+/// Values before and after a change.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ChangedType<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T, U> From<EthDiff<T>> for Diff<U>
+where
+    T: Eq,
+    U: From<T>,
+{
+    fn from(c: EthDiff<T>) -> Self {
+        match c {
+            EthDiff::Same => Diff::Same,
+            EthDiff::Born(t) => Diff::Born(t.into()),
+            EthDiff::Changed(from, to) => {
+                Diff::Changed(ChangedType {
+                                  from: from.into(),
+                                  to: to.into(),
+                              })
+            }
+            EthDiff::Died(t) => Diff::Died(t.into()),
+        }
+    }
+}
+
+/// Changes of an account.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct AccountDiff {
+    pub nonce: Diff<U256>,
+    pub code: Diff<Bytes>,
+    pub storage: BTreeMap<H256, Diff<H256>>,
+}
+
+impl From<EthAccountDiff> for AccountDiff {
+    fn from(c: EthAccountDiff) -> Self {
+        AccountDiff {
+            nonce: c.nonce.into(),
+            code: c.code.into(),
+            storage: c.storage.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        }
+    }
+}
+
+/// Accounts changed by a block.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct StateDiff(pub BTreeMap<H160, AccountDiff>);
+
+impl From<EthStateDiff> for StateDiff {
+    fn from(c: EthStateDiff) -> Self {
+        StateDiff(c.raw.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn serialize_state_diff() {
+        let mut storage = BTreeMap::new();
+        storage.insert(H256::from(1), Diff::Changed(ChangedType { from: H256::from(2), to: H256::from(3) }));
+        let mut accounts = BTreeMap::new();
+        accounts.insert(H160::from(1),
+                        AccountDiff {
+                            nonce: Diff::Same,
+                            code: Diff::Born(Bytes::from(vec![0x60, 0x00])),
+                            storage: storage,
+                        });
+        let diff = StateDiff(accounts);
+        let serialized = serde_json::to_string(&diff).unwrap();
+        assert!(serialized.contains(r#""nonce":"=""#));
+        assert!(serialized.contains(r#""code":{"+":"0x6000"}"#));
+        assert_eq!(serde_json::from_str::<StateDiff>(&serialized).unwrap(), diff);
+    }
+}
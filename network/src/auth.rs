@@ -0,0 +1,322 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handshake of the connections, it tells the protocol version, the chain
+//! and the capabilities of the sender, with a random challenge for the receiver.
+//!
+//! +- id_card -+- target -+- timestamp -+- version -+- chain_id -+- genesis -+- capabilities -+- challenge -+
+//! |  4 bytes  |  4 bytes |   8 bytes   |  2 bytes  |  4 bytes   |  32 bytes |    4 bytes     |   8 bytes   |
+//! +-----------+----------+-------------+-----------+------------+-----------+----------------+-------------+
+//!
+//! The receiver answers with a proof of its address, the signature of the
+//! challenge together with the id cards of both ends, so only the nodes
+//! approved by the node manager contract can send messages to us. A proof
+//! is good for the id card it is signed for and the connection of the
+//! challenge only, it can not be replayed on another one.
+//!
+//! +- id_card -+- signature -+
+//! |  4 bytes  |   96 bytes  |
+//! +-----------+-------------+
+//!
+//! The top byte of the capabilities is the newest version of the messages the
+//! sender decodes, the messages to it are written down to that version.
+//!
+//! The handshakes of version 1 end after the timestamp with a byte of flags,
+//! those of version 2 have no challenge but an optional signature.
+//! Peers of another chain or an unsupported version are refused with the
+//! reason, instead of failing later on the messages they can not decode.
+
+use byteorder::{BigEndian, ByteOrder};
+use ed25519::{PrivKey, Signature, sign, recover, pubkey_to_address, SIGNATURE_BYTES_LEN};
+use libproto::{cmd_id, submodules, topics, communication};
+use protobuf::Message;
+use std::time::UNIX_EPOCH;
+use util::{Address, H256, Hashable};
+
+pub const PROTOCOL_VERSION: u16 = 3;
+/// Oldest version we can talk to, version 1 has no multiplexed streams
+/// and version 2 proves the address by a signature which can be replayed.
+pub const MIN_PROTOCOL_VERSION: u16 = 3;
+
+/// The sender accepts snappy compressed frames.
+pub const CAP_SNAPPY: u32 = 1;
+/// The sender reassembles chunked messages.
+pub const CAP_CHUNK: u32 = 1 << 1;
+/// The sender answers pings.
+pub const CAP_PING: u32 = 1 << 2;
+/// Capabilities a peer must have to be connected.
+pub const REQUIRED_CAPS: u32 = CAP_CHUNK | CAP_PING;
+const MESSAGE_VERSION_SHIFT: u32 = 24;
+
+/// Capabilities telling the newest version of the messages decoded.
+pub fn message_version_cap(version: u32) -> u32 {
+    version << MESSAGE_VERSION_SHIFT
+}
+
+/// Newest version of the messages the sender of the capabilities decodes, 0 for the old nodes.
+pub fn message_version(capabilities: u32) -> u32 {
+    capabilities >> MESSAGE_VERSION_SHIFT
+}
+
+/// Handshakes older than this are refused, so they can not be replayed later.
+const HANDSHAKE_TIMEOUT: u64 = 60;
+const HANDSHAKE_BODY_LEN: usize = 66;
+const V2_BODY_LEN: usize = 58;
+const LEGACY_BODY_LEN: usize = 17;
+const PROOF_LEN: usize = 4 + SIGNATURE_BYTES_LEN;
+const LEGACY_FLAG_SNAPPY: u8 = 1;
+
+fn unix_secs() -> u64 {
+    UNIX_EPOCH.elapsed().unwrap().as_secs()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Handshake {
+    pub id_card: u32,
+    pub target: u32,
+    pub version: u16,
+    pub chain_id: u32,
+    /// Hash of the genesis, zero if not configured.
+    pub genesis: H256,
+    pub capabilities: u32,
+    /// Signed by the target in the proof of its address, zero for the older versions.
+    pub challenge: u64,
+}
+
+impl Handshake {
+    /// Message sent to the target.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut content = vec![0u8; HANDSHAKE_BODY_LEN];
+        BigEndian::write_u32(&mut content[0..4], self.id_card);
+        BigEndian::write_u32(&mut content[4..8], self.target);
+        BigEndian::write_u64(&mut content[8..16], unix_secs());
+        BigEndian::write_u16(&mut content[16..18], self.version);
+        BigEndian::write_u32(&mut content[18..22], self.chain_id);
+        content[22..54].copy_from_slice(&self.genesis);
+        BigEndian::write_u32(&mut content[54..58], self.capabilities);
+        BigEndian::write_u64(&mut content[58..66], self.challenge);
+
+        let mut msg = communication::Message::new();
+        msg.set_cmd_id(cmd_id(submodules::NET, topics::HANDSHAKE));
+        msg.set_field_type(communication::MsgType::MSG);
+        msg.set_origin(self.id_card);
+        msg.set_content(content);
+        msg.write_to_bytes().unwrap()
+    }
+
+    /// Check the handshake sent to us. The signatures of the older versions are not
+    /// checked, they are refused by their versions anyway.
+    pub fn decode(content: &[u8], id_card: u32) -> Result<Handshake, String> {
+        let body = if content.len() == HANDSHAKE_BODY_LEN {
+            content
+        } else if content.len() == V2_BODY_LEN || content.len() == V2_BODY_LEN + SIGNATURE_BYTES_LEN {
+            &content[..V2_BODY_LEN]
+        } else if content.len() == LEGACY_BODY_LEN || content.len() == LEGACY_BODY_LEN + SIGNATURE_BYTES_LEN {
+            &content[..LEGACY_BODY_LEN]
+        } else {
+            return Err(format!("invalid handshake length {}", content.len()));
+        };
+        let handshake = if body.len() == LEGACY_BODY_LEN {
+            Handshake {
+                id_card: BigEndian::read_u32(&body[0..4]),
+                target: BigEndian::read_u32(&body[4..8]),
+                version: 1,
+                chain_id: 0,
+                genesis: H256::zero(),
+                capabilities: if body[16] & LEGACY_FLAG_SNAPPY != 0 { CAP_SNAPPY } else { 0 },
+                challenge: 0,
+            }
+        } else {
+            Handshake {
+                id_card: BigEndian::read_u32(&body[0..4]),
+                target: BigEndian::read_u32(&body[4..8]),
+                version: BigEndian::read_u16(&body[16..18]),
+                chain_id: BigEndian::read_u32(&body[18..22]),
+                genesis: H256::from_slice(&body[22..54]),
+                capabilities: BigEndian::read_u32(&body[54..58]),
+                challenge: if body.len() == HANDSHAKE_BODY_LEN { BigEndian::read_u64(&body[58..66]) } else { 0 },
+            }
+        };
+        if handshake.target != id_card {
+            return Err(format!("handshake is sent to {}", handshake.target));
+        }
+        let timestamp = BigEndian::read_u64(&body[8..16]);
+        let now = unix_secs();
+        if timestamp + HANDSHAKE_TIMEOUT < now || timestamp > now + HANDSHAKE_TIMEOUT {
+            return Err(format!("handshake timestamp {} is off by more than {} seconds", timestamp, HANDSHAKE_TIMEOUT));
+        }
+        Ok(handshake)
+    }
+
+    /// Whether the peer sending the handshake can talk to us, `local` is our handshake.
+    pub fn check(&self, local: &Handshake) -> Result<(), String> {
+        if self.version < MIN_PROTOCOL_VERSION {
+            return Err(format!("protocol version {} is not supported, {} at least", self.version, MIN_PROTOCOL_VERSION));
+        }
+        if self.chain_id != local.chain_id {
+            return Err(format!("chain id {} differs from {}", self.chain_id, local.chain_id));
+        }
+        if !self.genesis.is_zero() && !local.genesis.is_zero() && self.genesis != local.genesis {
+            return Err(format!("genesis {:?} differs from {:?}", self.genesis, local.genesis));
+        }
+        let missing = REQUIRED_CAPS & !self.capabilities;
+        if missing != 0 {
+            return Err(format!("missing capabilities {:#x}", missing));
+        }
+        Ok(())
+    }
+}
+
+// What the proof signs, the challenge bound to the id cards of the sender and the target.
+fn proof_hash(id_card: u32, target: u32, challenge: u64) -> H256 {
+    let mut content = vec![0u8; 16];
+    BigEndian::write_u32(&mut content[0..4], id_card);
+    BigEndian::write_u32(&mut content[4..8], target);
+    BigEndian::write_u64(&mut content[8..16], challenge);
+    content.crypt_hash()
+}
+
+/// Proof of our address to the target, the answer to the challenge of its handshake.
+pub fn proof_msg(privkey: &PrivKey, id_card: u32, target: u32, challenge: u64) -> Vec<u8> {
+    let signature = sign(privkey, &proof_hash(id_card, target, challenge)).unwrap();
+    let mut content = vec![0u8; 4];
+    BigEndian::write_u32(&mut content, id_card);
+    content.extend_from_slice(&signature.0);
+
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::HANDSHAKE_PROOF));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_origin(id_card);
+    msg.set_content(content);
+    msg.write_to_bytes().unwrap()
+}
+
+/// Check the proof sent to us, `id_card` being ours and `challenge` the one of our
+/// handshake to the sender. Returns the id card the proof is signed for with the address.
+pub fn verify_proof(content: &[u8], id_card: u32, challenge: u64) -> Result<(u32, Address), String> {
+    if content.len() != PROOF_LEN {
+        return Err(format!("invalid proof length {}", content.len()));
+    }
+    let sender = BigEndian::read_u32(&content[0..4]);
+    let mut signature = [0u8; SIGNATURE_BYTES_LEN];
+    signature.copy_from_slice(&content[4..]);
+    match recover(&Signature(signature), &proof_hash(sender, id_card, challenge)) {
+        Ok(pubkey) => Ok((sender, pubkey_to_address(&pubkey))),
+        Err(_) => Err("invalid proof signature".to_string()),
+    }
+}
+
+/// Tell the peer why it is refused before closing the connection.
+pub fn reject_msg(id_card: u32, reason: &str) -> Vec<u8> {
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::HANDSHAKE_REJECT));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_origin(id_card);
+    msg.set_content(reason.as_bytes().to_vec());
+    msg.write_to_bytes().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Handshake, CAP_SNAPPY, CAP_CHUNK, CAP_PING, PROTOCOL_VERSION, message_version, message_version_cap, proof_msg, verify_proof};
+    use byteorder::{BigEndian, ByteOrder};
+    use ed25519::KeyPair;
+    use libproto::communication;
+    use protobuf::core::parse_from_bytes;
+    use std::time::UNIX_EPOCH;
+    use util::H256;
+
+    fn handshake() -> Handshake {
+        Handshake {
+            id_card: 1,
+            target: 2,
+            version: PROTOCOL_VERSION,
+            chain_id: 7,
+            genesis: H256::from(9),
+            capabilities: CAP_SNAPPY | CAP_CHUNK | CAP_PING,
+            challenge: 0x1234,
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        let handshake = handshake();
+        let msg = parse_from_bytes::<communication::Message>(&handshake.encode()).unwrap();
+        assert_eq!(Handshake::decode(msg.get_content(), 2), Ok(handshake));
+        // sent to another node
+        assert!(Handshake::decode(msg.get_content(), 3).is_err());
+        assert!(Handshake::decode(&msg.get_content()[..60], 2).is_err());
+    }
+
+    #[test]
+    fn proof() {
+        let keypair = KeyPair::gen_keypair();
+        let msg = parse_from_bytes::<communication::Message>(&proof_msg(keypair.privkey(), 1, 2, 0x1234)).unwrap();
+        assert_eq!(verify_proof(msg.get_content(), 2, 0x1234), Ok((1, keypair.address())));
+        // another challenge, or sent to another node
+        assert!(verify_proof(msg.get_content(), 2, 0x1235).is_err());
+        assert!(verify_proof(msg.get_content(), 3, 0x1234).is_err());
+
+        // claiming another id card
+        let mut content = msg.get_content().to_vec();
+        content[3] = 5;
+        assert!(verify_proof(&content, 2, 0x1234).is_err());
+        assert!(verify_proof(&content[..50], 2, 0x1234).is_err());
+    }
+
+    #[test]
+    fn versions() {
+        let capabilities = CAP_SNAPPY | CAP_CHUNK | message_version_cap(3);
+        assert_eq!(message_version(capabilities), 3);
+        assert_eq!(capabilities & CAP_CHUNK, CAP_CHUNK);
+        assert_eq!(message_version(CAP_SNAPPY | CAP_CHUNK | CAP_PING), 0);
+    }
+
+    #[test]
+    fn legacy() {
+        let mut content = vec![0u8; 17];
+        BigEndian::write_u32(&mut content[0..4], 1);
+        BigEndian::write_u32(&mut content[4..8], 2);
+        BigEndian::write_u64(&mut content[8..16], UNIX_EPOCH.elapsed().unwrap().as_secs());
+        content[16] = 1;
+        let legacy = Handshake::decode(&content, 2).unwrap();
+        assert_eq!(legacy.version, 1);
+        assert_eq!(legacy.capabilities, CAP_SNAPPY);
+        assert!(legacy.check(&handshake()).unwrap_err().contains("protocol version 1"));
+    }
+
+    #[test]
+    fn compatibility() {
+        let local = handshake();
+        assert_eq!(local.check(&local), Ok(()));
+
+        let mut peer = local;
+        peer.chain_id = 8;
+        assert!(peer.check(&local).is_err());
+
+        let mut peer = local;
+        peer.genesis = H256::from(10);
+        assert!(peer.check(&local).is_err());
+        // genesis not configured
+        peer.genesis = H256::zero();
+        assert_eq!(peer.check(&local), Ok(()));
+
+        let mut peer = local;
+        peer.capabilities = CAP_SNAPPY | CAP_CHUNK;
+        assert!(peer.check(&local).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rand::{thread_rng, Rng};
+use std::cmp;
+use std::time::Duration;
+
+/// Exponential backoff of reconnecting a peer. The delay doubles on every
+/// failure up to `max`, and is randomized into [delay / 2, delay] so the
+/// nodes do not retry a recovered peer at the same time.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min: u64,
+    max: u64,
+    max_retries: Option<u32>,
+    failures: u32,
+}
+
+impl Backoff {
+    /// Delays are in milliseconds, retry forever if `max_retries` is None.
+    pub fn new(min: u64, max: u64, max_retries: Option<u32>) -> Self {
+        Backoff {
+            min: cmp::max(min, 1),
+            max: cmp::max(max, min),
+            max_retries: max_retries,
+            failures: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Record a failure, returns the delay of the next attempt, None if giving up.
+    pub fn fail(&mut self) -> Option<Duration> {
+        self.failures += 1;
+        if let Some(max_retries) = self.max_retries {
+            if self.failures > max_retries {
+                return None;
+            }
+        }
+        let shift = cmp::min(self.failures - 1, 32);
+        let delay = cmp::min(self.min.saturating_mul(1 << shift), self.max);
+        let jitter = thread_rng().gen_range(0, delay / 2 + 1);
+        Some(Duration::from_millis(delay - delay / 2 + jitter))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Backoff;
+    use std::time::Duration;
+
+    #[test]
+    fn exponential() {
+        let mut backoff = Backoff::new(1000, 8000, None);
+        let bounds = [(500, 1000), (1000, 2000), (2000, 4000), (4000, 8000), (4000, 8000)];
+        for &(low, high) in bounds.iter() {
+            let delay = backoff.fail().unwrap();
+            assert!(delay >= Duration::from_millis(low) && delay <= Duration::from_millis(high));
+        }
+        for _ in 0..100 {
+            assert!(backoff.fail().unwrap() <= Duration::from_millis(8000));
+        }
+        backoff.reset();
+        assert!(backoff.fail().unwrap() <= Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn max_retries() {
+        let mut backoff = Backoff::new(1000, 8000, Some(2));
+        assert!(backoff.fail().is_some());
+        assert!(backoff.fail().is_some());
+        assert_eq!(backoff.fail(), None);
+        backoff.reset();
+        assert!(backoff.fail().is_some());
+    }
+}
@@ -0,0 +1,299 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chunked transfer of the large payloads, such as snapshots and big blocks.
+//! The payload is split into chunks carrying their own checksums, and the
+//! receiver asks the sender for the missing chunks if the transfer stalls,
+//! e.g. after a reconnection, instead of starting over.
+//!
+//! +- transfer -+- index -+- total -+- checksum -+- data -+
+//! |  8 bytes   | 4 bytes | 4 bytes |  32 bytes  |        |
+//! +------------+---------+---------+------------+--------+
+//!
+//! The transfer id is the prefix of the hash of the whole payload, so the
+//! reassembled payload is checked too.
+
+use byteorder::{BigEndian, ByteOrder};
+use libproto::{cmd_id, submodules, topics, communication};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use util::{H256, Hashable};
+
+/// Payloads larger than this are sent in chunks.
+pub const CHUNK_THRESHOLD: usize = 4 * 1024 * 1024;
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+/// At most 1GB in a transfer.
+const MAX_CHUNKS: u32 = 1024;
+const MAX_TRANSFERS_PER_PEER: usize = 4;
+const HEADER_LEN: usize = 48;
+/// Ask for the missing chunks if nothing is received for this long.
+const STALL_TIMEOUT: u64 = 10;
+/// Drop the transfer after asking so many times.
+const MAX_RESUMES: u32 = 5;
+/// Transfers kept by the sender to answer the resume requests.
+const OUTBOX_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub transfer: u64,
+    pub index: u32,
+    pub total: u32,
+    pub data: Vec<u8>,
+}
+
+fn transfer_id(payload: &[u8]) -> u64 {
+    BigEndian::read_u64(&payload.crypt_hash().0[0..8])
+}
+
+/// Split the payload into chunks.
+pub fn split(payload: &[u8]) -> Vec<Chunk> {
+    let transfer = transfer_id(payload);
+    let total = ((payload.len() + CHUNK_SIZE - 1) / CHUNK_SIZE) as u32;
+    payload.chunks(CHUNK_SIZE)
+           .enumerate()
+           .map(|(index, data)| {
+                    Chunk {
+                        transfer: transfer,
+                        index: index as u32,
+                        total: total,
+                        data: data.to_vec(),
+                    }
+                })
+           .collect()
+}
+
+impl Chunk {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut content = vec![0u8; HEADER_LEN];
+        BigEndian::write_u64(&mut content[0..8], self.transfer);
+        BigEndian::write_u32(&mut content[8..12], self.index);
+        BigEndian::write_u32(&mut content[12..16], self.total);
+        content[16..48].copy_from_slice(&self.data.crypt_hash().0);
+        content.extend_from_slice(&self.data);
+        content
+    }
+
+    /// Parse the chunk, None if it is malformed or the checksum mismatches.
+    pub fn decode(content: &[u8]) -> Option<Chunk> {
+        if content.len() <= HEADER_LEN {
+            return None;
+        }
+        let chunk = Chunk {
+            transfer: BigEndian::read_u64(&content[0..8]),
+            index: BigEndian::read_u32(&content[8..12]),
+            total: BigEndian::read_u32(&content[12..16]),
+            data: content[HEADER_LEN..].to_vec(),
+        };
+        if chunk.total == 0 || chunk.total > MAX_CHUNKS || chunk.index >= chunk.total || chunk.data.len() > CHUNK_SIZE || chunk.data.crypt_hash() != H256::from(&content[16..48]) {
+            return None;
+        }
+        Some(chunk)
+    }
+
+    /// Message of the chunk sent to the peers as the original message was.
+    pub fn to_msg(&self, operate: communication::OperateType, origin: u32) -> communication::Message {
+        let mut msg = communication::Message::new();
+        msg.set_cmd_id(cmd_id(submodules::NET, topics::CHUNK));
+        msg.set_field_type(communication::MsgType::MSG);
+        msg.set_operate(operate);
+        msg.set_origin(origin);
+        msg.set_content(self.encode());
+        msg
+    }
+}
+
+/// Message asking the peer to send the chunks again.
+pub fn resume_msg(peer: u32, transfer: u64, missing: &[u32]) -> communication::Message {
+    let mut content = vec![0u8; 8 + 4 * missing.len()];
+    BigEndian::write_u64(&mut content[0..8], transfer);
+    for (i, index) in missing.iter().enumerate() {
+        BigEndian::write_u32(&mut content[8 + 4 * i..12 + 4 * i], *index);
+    }
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::CHUNK_RESUME));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_operate(communication::OperateType::SINGLE);
+    msg.set_origin(peer);
+    msg.set_content(content);
+    msg
+}
+
+pub fn decode_resume(content: &[u8]) -> Option<(u64, Vec<u32>)> {
+    if content.len() < 8 || (content.len() - 8) % 4 != 0 {
+        return None;
+    }
+    let missing = content[8..].chunks(4).map(BigEndian::read_u32).collect();
+    Some((BigEndian::read_u64(&content[0..8]), missing))
+}
+
+struct Transfer {
+    chunks: Vec<Option<Vec<u8>>>,
+    received: u32,
+    last: Instant,
+    resumes: u32,
+}
+
+/// Incomplete transfers of the peers. They are kept over the reconnections,
+/// so only the missing chunks need to be sent again.
+pub struct Reassembler {
+    transfers: HashMap<(u32, u64), Transfer>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Reassembler { transfers: HashMap::new() }
+    }
+
+    /// Add the chunk from the peer, returns the payload once all the chunks are received.
+    pub fn add(&mut self, peer: u32, chunk: Chunk) -> Option<Vec<u8>> {
+        let key = (peer, chunk.transfer);
+        if !self.transfers.contains_key(&key) {
+            if self.transfers.keys().filter(|k| k.0 == peer).count() >= MAX_TRANSFERS_PER_PEER {
+                warn!("too many transfers from peer {}, drop chunk", peer);
+                return None;
+            }
+            self.transfers.insert(key,
+                                  Transfer {
+                                      chunks: vec![None; chunk.total as usize],
+                                      received: 0,
+                                      last: Instant::now(),
+                                      resumes: 0,
+                                  });
+        }
+
+        let complete = {
+            let transfer = self.transfers.get_mut(&key).unwrap();
+            if transfer.chunks.len() != chunk.total as usize {
+                return None;
+            }
+            transfer.last = Instant::now();
+            if transfer.chunks[chunk.index as usize].is_none() {
+                transfer.chunks[chunk.index as usize] = Some(chunk.data);
+                transfer.received += 1;
+            }
+            transfer.received as usize == transfer.chunks.len()
+        };
+        if !complete {
+            return None;
+        }
+
+        let transfer = self.transfers.remove(&key).unwrap();
+        let mut payload = Vec::new();
+        for data in transfer.chunks.into_iter() {
+            payload.extend(data.unwrap());
+        }
+        if transfer_id(&payload) != key.1 {
+            warn!("transfer {} from peer {} is corrupted", key.1, peer);
+            return None;
+        }
+        Some(payload)
+    }
+
+    /// Stalled transfers and their missing chunks, the ones asked too many times are dropped.
+    pub fn stalled(&mut self) -> Vec<(u32, u64, Vec<u32>)> {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(STALL_TIMEOUT);
+        self.transfers.retain(|&(peer, id), transfer| {
+                                  let keep = transfer.resumes < MAX_RESUMES || now.duration_since(transfer.last) < timeout;
+                                  if !keep {
+                                      warn!("give up transfer {} from peer {}", id, peer);
+                                  }
+                                  keep
+                              });
+        let mut stalled = Vec::new();
+        for (&(peer, id), transfer) in self.transfers.iter_mut() {
+            if now.duration_since(transfer.last) >= timeout {
+                transfer.resumes += 1;
+                transfer.last = now;
+                let missing = transfer.chunks.iter().enumerate().filter(|&(_, c)| c.is_none()).map(|(i, _)| i as u32).collect();
+                stalled.push((peer, id, missing));
+            }
+        }
+        stalled
+    }
+}
+
+/// Chunks of the latest transfers we sent.
+pub struct Outbox {
+    transfers: VecDeque<Vec<Chunk>>,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Outbox { transfers: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, chunks: Vec<Chunk>) {
+        if self.transfers.len() >= OUTBOX_LEN {
+            self.transfers.pop_front();
+        }
+        self.transfers.push_back(chunks);
+    }
+
+    pub fn get(&self, transfer: u64, indexes: &[u32]) -> Vec<Chunk> {
+        match self.transfers.iter().find(|chunks| chunks.first().map_or(false, |c| c.transfer == transfer)) {
+            Some(chunks) => indexes.iter().filter_map(|i| chunks.get(*i as usize)).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{split, Chunk, Reassembler, Outbox, CHUNK_SIZE, resume_msg, decode_resume};
+
+    fn payload() -> Vec<u8> {
+        (0..CHUNK_SIZE * 2 + 100).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn encode_decode() {
+        let chunks = split(&payload());
+        assert_eq!(chunks.len(), 3);
+        let content = chunks[1].encode();
+        assert_eq!(Chunk::decode(&content), Some(chunks[1].clone()));
+        let mut corrupted = content.clone();
+        corrupted[60] ^= 1;
+        assert_eq!(Chunk::decode(&corrupted), None);
+
+        let msg = resume_msg(1, chunks[0].transfer, &[0, 2]);
+        assert_eq!(decode_resume(msg.get_content()), Some((chunks[0].transfer, vec![0, 2])));
+    }
+
+    #[test]
+    fn reassemble() {
+        let payload = payload();
+        let chunks = split(&payload);
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.add(1, chunks[2].clone()), None);
+        assert_eq!(reassembler.add(1, chunks[0].clone()), None);
+        // duplicated chunk
+        assert_eq!(reassembler.add(1, chunks[0].clone()), None);
+        // the same transfer from another peer is separated
+        assert_eq!(reassembler.add(2, chunks[1].clone()), None);
+        assert_eq!(reassembler.add(1, chunks[1].clone()), Some(payload));
+    }
+
+    #[test]
+    fn resume() {
+        let chunks = split(&payload());
+        let mut outbox = Outbox::new();
+        outbox.push(chunks.clone());
+        assert_eq!(outbox.get(chunks[0].transfer, &[2, 7]), vec![chunks[2].clone()]);
+        assert!(outbox.get(chunks[0].transfer + 1, &[0]).is_empty());
+    }
+}
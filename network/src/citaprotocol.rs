@@ -15,97 +15,122 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-//! A multiplexed cita protocol
+//! Framing of the multiplexed cita protocol.
+//!
+//! Frames begin with an 8 byte header in network order, the magic, the kind
+//! of the frame and the length of the payload. The kind tells the protocol
+//! stream of the frame and whether the payload is compressed by snappy,
+//! 0xEF and 0xEC are the plain and snappy frames of the old nodes.
+//!
+//! +- magic -+- kind -+- length -+------- frame payload --------+
+//! |         |        |          |                              |
+//! | \xDEADBE| 1 byte | 4 bytes  | This is the frame payload    |
+//! |         |        |          |                              |
+//! +---------+--------+----------+------------------------------+
+//!
+//! kind of the new frames: 0xB0 | stream << 1 | snappy
 
 use byteorder::{BigEndian, ByteOrder};
 use bytes::BytesMut;
+use mux::Stream;
 use std::io;
-use tokio_io::{AsyncRead, AsyncWrite};
-use tokio_io::codec::{Framed, Encoder, Decoder};
-use tokio_proto::pipeline::ServerProto;
+use util::snappy;
 
 pub type CitaRequest = Vec<u8>;
-pub type CitaResponse = Vec<u8>;
 
-/// Our multiplexed line-based codec
-pub struct CitaCodec;
-
-/// Protocol definition
-pub struct CitaProto;
-
-/// Implementation of the multiplexed line-based protocol.
-///
-/// Frames begin with a 4 byte header, consisting of the numeric request ID
-/// encoded in network order, followed by the frame payload encoded as a UTF-8
-/// string and terminated with a '\n' character:
-///
-/// # An example frame:
-///
-/// +-- request id --+------- frame payload --------+
-/// |                |                              |
-/// | \xDEADBEEF+len | This is the frame payload    |
-/// |                |                              |
-/// +----------------+------------------------------+
-///
-impl Decoder for CitaCodec {
-    type Item = CitaRequest;
-    type Error = io::Error;
-
-    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
-        let buf_len = buf.len();
-        if buf_len < 8 {
-            return Ok(None);
-        }
+const HEADER_LEN: usize = 8;
+const MAGIC: u64 = 0xDEADBE;
+const LEGACY_PLAIN: u8 = 0xEF;
+const LEGACY_SNAPPY: u8 = 0xEC;
+const STREAM_KIND: u8 = 0xB0;
+const STREAM_KIND_MASK: u8 = 0xF8;
+const SNAPPY_BIT: u8 = 1;
+/// Payloads smaller than this are not worth compressing.
+const COMPRESS_THRESHOLD: usize = 1024;
+
+/// Frame of the payload on the stream, compressed by snappy if it is large and the peer accepts it.
+pub fn encode_frame(msg: &[u8], stream: Stream, snappy: bool) -> Vec<u8> {
+    let compressed;
+    let mut kind = STREAM_KIND | ((stream as u8) << 1);
+    let payload = if snappy && msg.len() >= COMPRESS_THRESHOLD {
+        compressed = snappy::compress(msg);
+        kind |= SNAPPY_BIT;
+        &compressed[..]
+    } else {
+        msg
+    };
+    let mut buf = vec![0; HEADER_LEN];
+    BigEndian::write_u64(&mut buf, MAGIC << 40 | (kind as u64) << 32 | payload.len() as u64);
+    buf.extend_from_slice(payload);
+    buf
+}
 
-        // check flag and msglen
-        let request_id = BigEndian::read_u64(buf.as_ref());
-        if request_id & 0xffffffff00000000 != 0xDEADBEEF00000000 {
-            return Ok(None);
-        }
-        let msg_len = request_id & 0x00000000ffffffff;
-        if (msg_len + 8) > buf_len as u64 {
-            return Ok(None);
+/// Take a frame from the buffer, None if it is not complete yet.
+/// Frames of the old nodes have no stream, they are taken as sync frames.
+pub fn decode_frame(buf: &mut BytesMut) -> io::Result<Option<(Stream, CitaRequest)>> {
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let header = BigEndian::read_u64(buf.as_ref());
+    if header >> 40 != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid frame magic"));
+    }
+    let kind = (header >> 32) as u8;
+    let (stream, compressed) = match kind {
+        LEGACY_PLAIN => (Stream::Sync, false),
+        LEGACY_SNAPPY => (Stream::Sync, true),
+        _ if kind & STREAM_KIND_MASK == STREAM_KIND => {
+            match Stream::from_id((kind >> 1) & 0x3) {
+                Some(stream) => (stream, kind & SNAPPY_BIT != 0),
+                None => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid frame stream")),
+            }
         }
-        // ok skip the flag
-        buf.split_to(8);
-        // get msg
-        let msg = buf.split_to(msg_len as usize);
-        let mut payload = Vec::new();
-        payload.extend(msg.as_ref());
-
-        trace!("decode msg {:?} {:?}", request_id, payload);
-
-        Ok(Some(payload))
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid frame kind")),
+    };
+    let msg_len = (header & 0xffffffff) as usize;
+    if HEADER_LEN + msg_len > buf.len() {
+        return Ok(None);
     }
+    buf.split_to(HEADER_LEN);
+    let msg = buf.split_to(msg_len);
+    let payload = if compressed {
+        snappy::decompress(msg.as_ref()).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid snappy frame"))?
+    } else {
+        msg.to_vec()
+    };
+
+    trace!("decode {:?} msg {:?}", stream, payload);
+    Ok(Some((stream, payload)))
 }
 
-impl Encoder for CitaCodec {
-    type Item = CitaResponse;
-    type Error = io::Error;
-
-    fn encode(&mut self, msg: Self::Item, buf: &mut BytesMut) -> io::Result<()> {
-        let request_id = 0xDEADBEEF00000000 + msg.len();
-        trace!("encode msg {:?} {:?}", request_id, msg);
-
-        let mut encoded_request_id = [0; 8];
-        BigEndian::write_u64(&mut encoded_request_id, request_id as u64);
-
-        buf.extend(&encoded_request_id);
-        buf.extend(&msg);
-
-        Ok(())
+#[cfg(test)]
+mod test {
+    use super::{encode_frame, decode_frame};
+    use bytes::BytesMut;
+    use mux::Stream;
+
+    #[test]
+    fn snappy_frame() {
+        let msg = vec![7u8; 4096];
+        let plain = encode_frame(&msg, Stream::Sync, false);
+        let compressed = encode_frame(&msg, Stream::Consensus, true);
+        assert!(compressed.len() < plain.len());
+
+        let mut buf = BytesMut::from(plain);
+        buf.extend(&compressed);
+        assert_eq!(decode_frame(&mut buf).unwrap(), Some((Stream::Sync, msg.clone())));
+        assert_eq!(decode_frame(&mut buf).unwrap(), Some((Stream::Consensus, msg)));
+        assert!(buf.is_empty());
     }
-}
-
-impl<T: AsyncRead + AsyncWrite + 'static> ServerProto<T> for CitaProto {
-    type Request = CitaRequest;
-    type Response = CitaResponse;
 
-    /// `Framed<T, CitaCodec>` is the return value of `io.framed(CitaCodec)`
-    type Transport = Framed<T, CitaCodec>;
-    type BindTransport = Result<Self::Transport, io::Error>;
+    #[test]
+    fn legacy_frame() {
+        let mut buf = BytesMut::from(vec![0xDE, 0xAD, 0xBE, 0xEF, 0, 0, 0, 3, 1, 2]);
+        assert_eq!(decode_frame(&mut buf).unwrap(), None);
+        buf.extend(&[3]);
+        assert_eq!(decode_frame(&mut buf).unwrap(), Some((Stream::Sync, vec![1, 2, 3])));
 
-    fn bind_transport(&self, io: T) -> Self::BindTransport {
-        Ok(io.framed(CitaCodec))
+        let mut buf = BytesMut::from(vec![0xDE, 0xAD, 0xBE, 0xB9, 0, 0, 0, 0]);
+        assert!(decode_frame(&mut buf).is_err());
     }
 }
@@ -20,18 +20,66 @@ extern crate toml;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use util::{H256, Hashable};
 
 #[derive(Debug, RustcDecodable)]
 pub struct NetConfig {
     pub id_card: Option<u32>,
+    /// Address announced to the other nodes by peer discovery,
+    /// an IPv4 or IPv6 address, or a hostname.
+    pub ip: Option<String>,
     pub port: Option<u64>,
+    /// Address the server listens on, "0.0.0.0" by default, or "::" if `ip` is an IPv6 address.
+    pub listen_ip: Option<String>,
+    /// Peers found by discovery we connect at most, the peers in the config are always connected.
+    pub max_outbound: Option<u64>,
+    /// Old name of max_outbound.
     pub max_peer: Option<u64>,
+    /// Connections accepted from the nodes not known to us at most, unlimited if not set.
+    pub max_inbound: Option<u64>,
+    /// Inbound slots reserved for the validators, default 0.
+    pub reserved_slots: Option<u64>,
+    /// Key proving our address to the peers in handshake.
+    pub privkey: Option<String>,
+    /// Peers of other chains are refused in handshake, default 0.
+    pub chain_id: Option<u32>,
+    /// Path of the genesis file, peers with another genesis are refused in handshake.
+    pub genesis: Option<String>,
+    /// Compress the large messages by snappy, default true.
+    pub compress: Option<bool>,
+    /// Seconds a misbehaving peer stays banned, default 600.
+    pub ban_time: Option<u64>,
+    /// Bandwidth limits in bytes per second, unlimited if not set.
+    pub upload_limit: Option<u64>,
+    pub download_limit: Option<u64>,
+    pub peer_upload_limit: Option<u64>,
+    pub peer_download_limit: Option<u64>,
+    /// Delays of reconnecting a peer in milliseconds, 1000 and 60000 by default.
+    pub reconnect_min: Option<u64>,
+    pub reconnect_max: Option<u64>,
+    /// Give up a peer after the failures until it is enabled by admin, retry forever if not set.
+    pub reconnect_retries: Option<u32>,
+    /// Seconds between the network status published on the bus, default 10.
+    pub status_interval: Option<u64>,
+    /// Seconds between the pings to the peers, default 5.
+    pub ping_interval: Option<u64>,
+    /// Run the network module alone to relay the messages between the peers, default false.
+    pub relay: Option<bool>,
+    /// Seconds between resolving the hostnames of the peers again, default 60.
+    pub resolve_interval: Option<u64>,
+    /// Send the new transactions to the next proposers and a few other peers instead of all, default false.
+    pub tx_forward: Option<bool>,
+    /// Peers other than the proposers a new transaction is sent to, default 2.
+    pub tx_gossip: Option<u64>,
+    /// Bootstrap peers, the others are found by peer discovery.
     pub peers: Option<Vec<PeerConfig>>,
 }
 
-#[derive(Debug, RustcDecodable)]
+#[derive(Debug, PartialEq, RustcDecodable)]
 pub struct PeerConfig {
     pub id_card: Option<u32>,
+    /// IPv4 or IPv6 address, or a hostname.
     pub ip: Option<String>,
     pub port: Option<u64>,
 }
@@ -45,6 +93,70 @@ impl NetConfig {
         toml::decode_str(&content).unwrap()
     }
 
+    /// Load and validate the config, used when reloading it at runtime.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut content = String::new();
+        File::open(path).and_then(|f| BufReader::new(f).read_to_string(&mut content)).map_err(|err| format!("can not read {}: {}", path, err))?;
+        let config: NetConfig = toml::decode_str(&content).ok_or(format!("can not parse {}", path))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id_card.is_none() {
+            return Err("id_card is missing".to_string());
+        }
+        let port = self.port.ok_or("port is missing".to_string())?;
+        if let Some(ref ip) = self.ip {
+            resolve(ip, port)?;
+        }
+        if let (Some(reserved), Some(max_inbound)) = (self.reserved_slots, self.max_inbound) {
+            if reserved > max_inbound {
+                return Err(format!("reserved_slots {} is more than max_inbound {}", reserved, max_inbound));
+            }
+        }
+        for &(name, limit) in &[("upload_limit", self.upload_limit), ("download_limit", self.download_limit), ("peer_upload_limit", self.peer_upload_limit), ("peer_download_limit", self.peer_download_limit)] {
+            if limit == Some(0) {
+                return Err(format!("{} is 0, leave it out for unlimited", name));
+            }
+        }
+        if let Some(ref genesis) = self.genesis {
+            genesis_hash(genesis)?;
+        }
+        if let Some(ref ip) = self.listen_ip {
+            ip.parse::<IpAddr>().map_err(|_| format!("invalid listen_ip {}", ip))?;
+        }
+        if let Some(ref peers) = self.peers {
+            for peer in peers.iter() {
+                if peer.id_card.is_none() {
+                    return Err(format!("id_card of peer {:?} is missing", peer));
+                }
+                match (peer.ip.as_ref(), peer.port) {
+                    (Some(ip), Some(port)) => {
+                        resolve(ip, port)?;
+                    }
+                    _ => return Err(format!("address of peer {:?} is missing", peer)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Address the server listens on.
+    pub fn listen_addr(&self) -> SocketAddr {
+        let port = self.port.unwrap() as u16;
+        let ip = match self.listen_ip {
+            Some(ref ip) => ip.parse::<IpAddr>().unwrap(),
+            None => {
+                match self.ip.as_ref().and_then(|ip| resolve(ip, port as u64).ok()) {
+                    Some(SocketAddr::V6(_)) => IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)),
+                    _ => IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+                }
+            }
+        };
+        SocketAddr::new(ip, port)
+    }
+
     pub fn test_config() -> Self {
         let toml = r#"
             id_card=0
@@ -60,11 +172,47 @@ impl NetConfig {
     }
 }
 
+/// Hash of the genesis file, it tells the chain of the node.
+pub fn genesis_hash(path: &str) -> Result<H256, String> {
+    let mut content = Vec::new();
+    File::open(path).and_then(|mut f| f.read_to_end(&mut content)).map_err(|err| format!("can not read genesis {}: {}", path, err))?;
+    Ok(content.crypt_hash())
+}
+
+/// Resolve the address of a node, the host may be an IPv4 or IPv6 address
+/// (with or without the brackets), or a hostname resolving to either family.
+pub fn resolve(host: &str, port: u64) -> Result<SocketAddr, String> {
+    resolve_all(host, port).map(|addrs| addrs[0])
+}
+
+/// All the addresses of the host, a hostname may have several A and AAAA records.
+pub fn resolve_all(host: &str, port: u64) -> Result<Vec<SocketAddr>, String> {
+    if port == 0 || port > 65535 {
+        return Err(format!("invalid port {}", port));
+    }
+    let host = host.trim_left_matches('[').trim_right_matches(']');
+    let mut addrs = Vec::new();
+    for addr in (host, port as u16).to_socket_addrs().map_err(|err| format!("can not resolve {}: {}", host, err))? {
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+    if addrs.is_empty() {
+        return Err(format!("no address for {}", host));
+    }
+    Ok(addrs)
+}
+
+/// Whether the host is a name to be resolved, not an IP address.
+pub fn is_hostname(host: &str) -> bool {
+    host.trim_left_matches('[').trim_right_matches(']').parse::<IpAddr>().is_err()
+}
+
 
 
 #[cfg(test)]
 mod test {
-    use super::NetConfig;
+    use super::{NetConfig, resolve, resolve_all, is_hostname};
     extern crate toml;
     #[test]
     fn basics() {
@@ -83,4 +231,52 @@ mod test {
         println!("{:?}", value);
         assert_eq!(value.port, Some(40000));
     }
+
+    #[test]
+    fn ipv6() {
+        let toml = r#"
+            id_card = 0
+            ip = "::1"
+            port = 40000
+            [[peers]]
+            id_card = 1
+            ip = "[::1]"
+            port = 40001
+            [[peers]]
+            id_card = 2
+            ip = "localhost"
+            port = 40002
+        "#;
+
+        let value: NetConfig = toml::decode_str(toml).unwrap();
+        assert_eq!(value.validate(), Ok(()));
+        assert_eq!(value.listen_addr(), "[::]:40000".parse().unwrap());
+        assert_eq!(resolve("[::1]", 40001), Ok("[::1]:40001".parse().unwrap()));
+        assert_eq!(resolve("127.0.0.1", 40001), Ok("127.0.0.1:40001".parse().unwrap()));
+        assert!(resolve("::1", 70000).is_err());
+        assert!(!resolve_all("localhost", 40002).unwrap().is_empty());
+        assert!(is_hostname("localhost"));
+        assert!(!is_hostname("[::1]"));
+        assert!(!is_hostname("127.0.0.1"));
+    }
+
+    #[test]
+    fn invalid() {
+        let toml = r#"
+            id_card = 0
+            port = 40000
+            listen_ip = "localhost"
+        "#;
+
+        let value: NetConfig = toml::decode_str(toml).unwrap();
+        assert!(value.validate().is_err());
+
+        let toml = r#"
+            id_card = 0
+            port = 40000
+            peer_upload_limit = 0
+        "#;
+        let value: NetConfig = toml::decode_str(toml).unwrap();
+        assert!(value.validate().is_err());
+    }
 }
@@ -15,109 +15,861 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use byteorder::{BigEndian, ByteOrder};
+use auth::{self, Handshake, PROTOCOL_VERSION, CAP_SNAPPY, CAP_CHUNK, CAP_PING, message_version, message_version_cap};
+use backoff::Backoff;
+use chunk::{self, Chunk, Reassembler, Outbox, CHUNK_THRESHOLD};
+use citaprotocol::encode_frame;
 use config;
-use libproto::communication;
-use parking_lot::RwLock;
+use dedup::{BoundedSet, Seen, item_hash};
+use ed25519::PrivKey;
+use lanes::SendQueue;
+use libproto::{communication, cmd_id, submodules, topics, downgrade_msg, MESSAGE_VERSION};
+use mux::{PeerLink, Stream, start_writer};
+use parking_lot::{Mutex, RwLock};
+use ping::Latency;
 use protobuf::Message;
-use std::convert::AsRef;
-use std::io::prelude::*;
+use rand;
+use score::{PeerScores, PeerKey, Misbehavior, DEFAULT_BAN_TIME, INITIAL_SCORE};
+use slots::{InboundSlot, SlotLimits, Slots};
+use status::PeerStats;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
 use std::net::SocketAddr;
 use std::net::TcpStream;
 use std::sync::Arc;
-use std::sync::mpsc::Receiver;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use throttle::Throttle;
+use txroute::{TxRoute, DEFAULT_TX_GOSSIP};
+use util::{Address, H256};
 
 const TIMEOUT: u64 = 15;
+const CHUNK_TIMER: u64 = 5;
+/// Seconds to wait for the peer with the smaller id_card to dial us before dialing it.
+const ADOPT_WAIT: u64 = 30;
+/// Messages remembered by the relay node, so they are passed on only once.
+const RELAY_CAPACITY: usize = 20000;
+
+pub type PeerStream = Arc<PeerLink>;
 
 pub struct Connection {
     pub id_card: u32,
-    pub peers_pair: Vec<(u32, SocketAddr, Arc<RwLock<Option<TcpStream>>>)>,
+    /// Address of this node announced to the others.
+    pub self_addr: Option<SocketAddr>,
+    slots: Mutex<Slots>,
+    pub privkey: Option<PrivKey>,
+    chain_id: u32,
+    genesis: H256,
+    // compress the large messages to the peers accepting it
+    compress: AtomicBool,
+    pub peers_pair: RwLock<Vec<(u32, SocketAddr, PeerStream)>>,
+    // nodes approved by the node manager contract, none until the chain tells them, empty if not enabled
+    authorized: RwLock<Option<Vec<Address>>>,
+    // challenges of our handshakes on the current connections of the peers
+    challenges: Arc<Mutex<HashMap<u32, u64>>>,
+    // addresses proved for the id cards, with the connections proving them
+    bound: Mutex<HashMap<u32, (Address, usize)>>,
+    // validators told by the consensus, they can take the reserved inbound slots
+    authorities: RwLock<Vec<Address>>,
+    // peers accepting snappy frames told by their handshakes
+    snappy_peers: RwLock<HashSet<u32>>,
+    // newest versions of the messages the peers decode, told by their handshakes
+    peer_versions: RwLock<HashMap<u32, u32>>,
+    // messages passed on by the relay node, None if not running as a relay
+    relayed: Option<Mutex<BoundedSet<H256>>>,
+    scores: Mutex<PeerScores>,
+    // keys the peers are scored for, told by their handshakes
+    peer_keys: RwLock<HashMap<u32, PeerKey>>,
+    // serial number of the connections, the key of those proving no address
+    connections: AtomicUsize,
+    // height of the local chain, blocks not above it are stale
+    chain_height: AtomicUsize,
+    upload: Arc<Mutex<Throttle>>,
+    download: Mutex<Throttle>,
+    backoff: RwLock<Backoff>,
+    // peers in the config, the others are found by discovery
+    config_peers: Mutex<Vec<(u32, SocketAddr)>>,
+    // peers of the config given by hostnames, resolved again periodically
+    peer_hosts: Mutex<Vec<(u32, String, u64)>>,
+    // wake up the connecting threads to retry at once
+    wakers: Mutex<HashMap<u32, Sender<()>>>,
+    seen: Mutex<Seen>,
+    chunks: Mutex<Reassembler>,
+    outbox: Mutex<Outbox>,
+    stats: Mutex<HashMap<u32, PeerStats>>,
+    latency: Mutex<Latency>,
+    // connections dialed by us, read by the server
+    dialed: Mutex<Sender<(u32, TcpStream)>>,
+    dialed_rx: Mutex<Option<Receiver<(u32, TcpStream)>>>,
+    // new transactions sent to the next proposers, None if sent to all
+    tx_route: Option<Mutex<TxRoute>>,
 }
 
 impl Connection {
     pub fn new(config: &config::NetConfig) -> Self {
         let id_card = config.id_card.unwrap();
-        let mut peers_pair = Vec::default();
-        match config.peers.as_ref() {
-            Some(peers) => {
-                for peer in peers.iter() {
-                    let id_card: u32 = peer.id_card.unwrap();
-                    let addr = format!("{}:{}", peer.ip.clone().unwrap(), peer.port.unwrap());
-                    let addr = addr.parse::<SocketAddr>().unwrap();
-                    peers_pair.push((id_card, addr, Arc::new(RwLock::new(None))));
+        let self_addr = config.ip.as_ref().map(|ip| config::resolve(ip, config.port.unwrap()).unwrap());
+        let privkey = config.privkey.as_ref().map(|k| k.parse::<PrivKey>().expect("Invalid privkey."));
+        let genesis = config.genesis.as_ref().map_or(H256::zero(), |path| config::genesis_hash(path).unwrap());
+        let config_peers = config_peers(config);
+        let peers_pair = config_peers.iter().map(|&(id_card, addr)| (id_card, addr, PeerLink::new(addr))).collect();
+        let (dialed, dialed_rx) = channel();
+
+        Connection {
+            id_card,
+            self_addr,
+            slots: Mutex::new(Slots::new(slot_limits(config))),
+            privkey,
+            chain_id: config.chain_id.unwrap_or(0),
+            genesis,
+            compress: AtomicBool::new(config.compress.unwrap_or(true)),
+            peers_pair: RwLock::new(peers_pair),
+            authorized: RwLock::new(None),
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+            bound: Mutex::new(HashMap::new()),
+            authorities: RwLock::new(Vec::new()),
+            snappy_peers: RwLock::new(HashSet::new()),
+            peer_versions: RwLock::new(HashMap::new()),
+            relayed: if config.relay.unwrap_or(false) { Some(Mutex::new(BoundedSet::new(RELAY_CAPACITY))) } else { None },
+            scores: Mutex::new(PeerScores::new(config.ban_time.unwrap_or(DEFAULT_BAN_TIME))),
+            peer_keys: RwLock::new(HashMap::new()),
+            connections: AtomicUsize::new(0),
+            chain_height: AtomicUsize::new(0),
+            upload: Arc::new(Mutex::new(Throttle::new(config.upload_limit, config.peer_upload_limit))),
+            download: Mutex::new(Throttle::new(config.download_limit, config.peer_download_limit)),
+            backoff: RwLock::new(backoff(config)),
+            config_peers: Mutex::new(config_peers),
+            peer_hosts: Mutex::new(peer_hosts(config)),
+            wakers: Mutex::new(HashMap::new()),
+            seen: Mutex::new(Seen::new()),
+            chunks: Mutex::new(Reassembler::new()),
+            outbox: Mutex::new(Outbox::new()),
+            stats: Mutex::new(HashMap::new()),
+            latency: Mutex::new(Latency::new()),
+            dialed: Mutex::new(dialed),
+            dialed_rx: Mutex::new(Some(dialed_rx)),
+            tx_route: if config.tx_forward.unwrap_or(false) { Some(Mutex::new(TxRoute::new(config.tx_gossip.map_or(DEFAULT_TX_GOSSIP, |n| n as usize)))) } else { None },
+        }
+    }
+
+    /// Apply the changed config without dropping the connections, the
+    /// id_card, address and key of the node can not be changed this way.
+    pub fn reload(&self, config: &config::NetConfig) {
+        if config.id_card != Some(self.id_card) {
+            warn!("changing id_card needs restarting network");
+        }
+        self.slots.lock().set_limits(slot_limits(config));
+        self.compress.store(config.compress.unwrap_or(true), Ordering::SeqCst);
+        self.scores.lock().set_ban_time(config.ban_time.unwrap_or(DEFAULT_BAN_TIME));
+        *self.upload.lock() = Throttle::new(config.upload_limit, config.peer_upload_limit);
+        *self.download.lock() = Throttle::new(config.download_limit, config.peer_download_limit);
+        *self.backoff.write() = backoff(config);
+
+        *self.peer_hosts.lock() = peer_hosts(config);
+        let new_peers = config_peers(config);
+        let old_peers = ::std::mem::replace(&mut *self.config_peers.lock(), new_peers.clone());
+        for &(id_card, addr) in old_peers.iter().filter(|p| !new_peers.contains(p)) {
+            self.remove_peer(id_card, addr);
+        }
+        for &(id_card, addr) in new_peers.iter().filter(|p| !old_peers.contains(p)) {
+            self.insert_peer(id_card, addr);
+        }
+    }
+
+    /// Resolve the hostnames of the peers again, so the nodes can change their IPs.
+    pub fn refresh_addrs(&self) {
+        let hosts = self.peer_hosts.lock().clone();
+        for (id_card, host, port) in hosts.into_iter() {
+            // resolve without holding the lock of peers
+            let addrs = match config::resolve_all(&host, port) {
+                Ok(addrs) => addrs,
+                Err(err) => {
+                    warn!("resolve peer {} failed: {}", id_card, err);
+                    continue;
+                }
+            };
+            let mut peers = self.peers_pair.write();
+            if let Some(peer) = peers.iter_mut().find(|peer| peer.0 == id_card) {
+                if !addrs.contains(&peer.1) {
+                    info!("address of peer {} changed from {:?} to {:?}", id_card, peer.1, addrs);
+                    peer.1 = addrs[0];
+                }
+                peer.2.set_addrs(addrs);
+            }
+        }
+    }
+
+    /// Handshake to the target peer, we always accept snappy frames and the messages of our version.
+    pub fn handshake(&self, target: u32) -> Handshake {
+        Handshake {
+            id_card: self.id_card,
+            target: target,
+            version: PROTOCOL_VERSION,
+            chain_id: self.chain_id,
+            genesis: self.genesis,
+            capabilities: CAP_SNAPPY | CAP_CHUNK | CAP_PING | message_version_cap(MESSAGE_VERSION),
+            challenge: 0,
+        }
+    }
+
+    /// Frame of our handshake to the peer with a new challenge, for a connection of a peer
+    /// we have no link to. The challenge is taken as the one of the peer.
+    pub fn challenge_frame(&self, target: u32) -> Vec<u8> {
+        let challenge = rand::random();
+        self.challenges.lock().insert(target, challenge);
+        handshake_frame(&self.handshake(target), challenge)
+    }
+
+    /// Forget the challenge of the peer we have no link to, once its connection is closed.
+    pub fn forget_challenge(&self, id_card: u32) {
+        if !self.is_known(id_card) {
+            self.challenges.lock().remove(&id_card);
+        }
+    }
+
+    /// Frame of the proof of our address to the peer, none if we have no key.
+    pub fn proof_frame(&self, target: u32, challenge: u64) -> Option<Vec<u8>> {
+        self.privkey.as_ref().map(|privkey| encode_frame(&auth::proof_msg(privkey, self.id_card, target, challenge), Stream::Auth, false))
+    }
+
+    /// Check the proof of the peer for the challenge of our handshake on its connection,
+    /// returns the id card it is signed for with the address.
+    pub fn verify_proof(&self, id_card: u32, content: &[u8]) -> Result<(u32, Address), String> {
+        let challenge = match self.challenges.lock().get(&id_card) {
+            Some(&challenge) => challenge,
+            None => return Err(format!("no handshake sent to {}", id_card)),
+        };
+        auth::verify_proof(content, self.id_card, challenge)
+    }
+
+    /// Bind the id card to the address proved for it, as long as a connection proving it lasts.
+    /// An id card is proved by one address only, and an address proves one id card only.
+    pub fn bind_peer(&self, id_card: u32, address: Address) -> Result<(), String> {
+        {
+            let mut bound = self.bound.lock();
+            for (&id, &(addr, _)) in bound.iter() {
+                if id == id_card && addr != address {
+                    return Err(format!("id card {} is proved by {:?}", id_card, addr));
+                }
+                if id != id_card && addr == address {
+                    return Err(format!("{:?} proved id card {}", address, id));
+                }
+            }
+            bound.entry(id_card).or_insert((address, 0)).1 += 1;
+        }
+        self.set_peer_address(id_card, address);
+        Ok(())
+    }
+
+    /// A connection proving the address of the peer is closed.
+    pub fn unbind_peer(&self, id_card: u32) {
+        let mut bound = self.bound.lock();
+        let unbound = match bound.get_mut(&id_card) {
+            Some(&mut (_, ref mut count)) => {
+                *count -= 1;
+                *count == 0
+            }
+            None => false,
+        };
+        if unbound {
+            bound.remove(&id_card);
+        }
+    }
+
+    /// Send the frames to the peer over its link, returns false if it is not connected.
+    pub fn reply(&self, id_card: u32, frames: &[u8]) -> bool {
+        match self.link(id_card) {
+            Some(ref link) if link.is_connected() => {
+                if !frames.is_empty() {
+                    link.outgoing.push(Stream::Auth, frames.to_vec());
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Refuse the peers of other chains or protocol versions.
+    pub fn check_handshake(&self, handshake: &Handshake) -> Result<(), String> {
+        handshake.check(&self.handshake(handshake.id_card))
+    }
+
+    pub fn set_peer_capabilities(&self, id_card: u32, capabilities: u32) {
+        if capabilities & CAP_SNAPPY != 0 {
+            self.snappy_peers.write().insert(id_card);
+        } else {
+            self.snappy_peers.write().remove(&id_card);
+        }
+        self.peer_versions.write().insert(id_card, message_version(capabilities));
+    }
+
+    /// Newest version of the messages the peer decodes, the oldest before its handshake.
+    fn message_version(&self, id_card: u32) -> u32 {
+        self.peer_versions.read().get(&id_card).cloned().unwrap_or(0)
+    }
+
+    fn is_snappy(&self, id_card: u32) -> bool {
+        self.compress.load(Ordering::SeqCst) && self.snappy_peers.read().contains(&id_card)
+    }
+
+    /// The nodes approved by the node manager contract, an empty list if it is not enabled.
+    pub fn set_authorized(&self, nodes: Vec<Address>) {
+        *self.authorized.write() = Some(nodes);
+    }
+
+    /// Whether the chain has told the approved nodes.
+    pub fn knows_authorized(&self) -> bool {
+        self.authorized.read().is_some()
+    }
+
+    /// Whether the peer with the address proved by its proof can send messages to us.
+    /// No one can until the chain tells the approved nodes.
+    pub fn is_authorized(&self, peer: Option<Address>) -> bool {
+        match *self.authorized.read() {
+            Some(ref nodes) => nodes.is_empty() || peer.map_or(false, |addr| nodes.contains(&addr)),
+            None => false,
+        }
+    }
+
+    pub fn set_authorities(&self, authorities: Vec<Address>) {
+        *self.authorities.write() = authorities;
+    }
+
+    /// Proposers of the next heights told by the consensus, the new transactions are sent to.
+    pub fn set_proposers(&self, proposers: Vec<Address>) {
+        if let Some(ref route) = self.tx_route {
+            route.lock().set_proposers(proposers);
+        }
+    }
+
+    pub fn set_peer_address(&self, id_card: u32, address: Address) {
+        if let Some(ref route) = self.tx_route {
+            route.lock().set_peer_address(id_card, address);
+        }
+    }
+
+    // Peers to send a new transaction of ours to, None for all.
+    fn tx_targets(&self) -> Option<HashSet<u32>> {
+        let route = match self.tx_route {
+            Some(ref route) => route,
+            None => return None,
+        };
+        let connected: Vec<u32> = self.peers_pair.read().iter().filter(|&&(_, _, ref link)| link.is_connected()).map(|&(id_card, _, _)| id_card).collect();
+        route.lock().targets(&connected)
+    }
+
+    /// Take an inbound slot for the accepted connection, returns false if all are taken.
+    pub fn accept_inbound(&self) -> bool {
+        self.slots.lock().accept()
+    }
+
+    /// Move the accepted connection to the slot of the peer once its handshake is received.
+    /// The peers we know do not take the inbound slots, their connections are used for both directions.
+    pub fn identify_inbound(&self, id_card: u32, address: Option<Address>) -> io::Result<Option<InboundSlot>> {
+        let known = self.is_known(id_card);
+        let validator = address.map_or(false, |addr| self.authorities.read().contains(&addr));
+        let mut slots = self.slots.lock();
+        if known {
+            slots.release(InboundSlot::Pending);
+            return Ok(None);
+        }
+        match slots.identify(validator) {
+            Some(slot) => Ok(Some(slot)),
+            None => Err(io::Error::new(io::ErrorKind::PermissionDenied, "no inbound slot")),
+        }
+    }
+
+    /// Move the observer to a validator slot once its proof tells an authority, returns the slot it takes.
+    pub fn promote_inbound(&self, slot: InboundSlot, address: Address) -> InboundSlot {
+        if slot == InboundSlot::Observer && self.authorities.read().contains(&address) {
+            return self.slots.lock().promote();
+        }
+        slot
+    }
+
+    pub fn release_inbound(&self, slot: InboundSlot) {
+        self.slots.lock().release(slot);
+    }
+
+    /// Key to score a new connection for, until its handshake proves an address.
+    pub fn connection_key(&self) -> PeerKey {
+        PeerKey::Connection(self.connections.fetch_add(1, Ordering::SeqCst) as u64)
+    }
+
+    /// The peer is scored for the key of its handshake from now on.
+    pub fn set_peer_key(&self, id_card: u32, key: PeerKey) {
+        self.peer_keys.write().insert(id_card, key);
+    }
+
+    fn peer_key(&self, id_card: u32) -> Option<PeerKey> {
+        self.peer_keys.read().get(&id_card).cloned()
+    }
+
+    /// Punish the peer, returns true if it gets banned.
+    pub fn report(&self, peer: PeerKey, misbehavior: Misbehavior) -> bool {
+        self.scores.lock().report(peer, misbehavior)
+    }
+
+    /// Punish the peer connected by the link of the id_card, if its handshake has been received.
+    pub fn report_peer(&self, id_card: u32, misbehavior: Misbehavior) -> bool {
+        self.peer_key(id_card).map_or(false, |peer| self.report(peer, misbehavior))
+    }
+
+    /// Count a message of the peer, returns false if it should be dropped.
+    pub fn received(&self, peer: PeerKey) -> bool {
+        self.scores.lock().received(peer)
+    }
+
+    pub fn is_banned(&self, peer: PeerKey) -> bool {
+        self.scores.lock().is_banned(peer)
+    }
+
+    pub fn is_peer_banned(&self, id_card: u32) -> bool {
+        self.peer_key(id_card).map_or(false, |peer| self.is_banned(peer))
+    }
+
+    /// Id cards of the banned peers with the seconds left of their bans.
+    pub fn banned(&self) -> Vec<(u32, u64)> {
+        let bans = self.scores.lock().banned();
+        let mut banned: Vec<(u32, u64)> = self.peer_keys.read().iter().filter_map(|(id_card, peer)| bans.iter().find(|&&(key, _)| key == *peer).map(|&(_, remaining)| (*id_card, remaining))).collect();
+        banned.sort();
+        banned
+    }
+
+    /// Lift the ban of the peer and reconnect it at once, returns false if the peer is unknown.
+    pub fn enable_peer(&self, id_card: u32) -> bool {
+        if let Some(peer) = self.peer_key(id_card) {
+            if self.scores.lock().unban(peer) {
+                info!("peer {} enabled", id_card);
+            }
+        }
+        self.wakers.lock().get(&id_card).map_or(false, |waker| waker.send(()).is_ok())
+    }
+
+    fn connect(&self, id_card: u32, link: PeerStream) {
+        let (tx, rx) = channel();
+        self.wakers.lock().insert(id_card, tx);
+        let upload = self.upload.clone();
+        start_writer(link.clone(), move |stream, bytes| wait_upload(&upload, id_card, bytes, stream <= Stream::Consensus));
+        let dialer = Dialer {
+            id_card: id_card,
+            // only one of the two peers dials, the other adopts the connection
+            dial_first: self.id_card < id_card,
+            handshake: self.handshake(id_card),
+            challenges: self.challenges.clone(),
+            dialed: self.dialed.lock().clone(),
+        };
+        connect_peer(dialer, link, self.backoff.read().clone(), rx);
+    }
+
+    /// Receiver of the connections dialed by us, taken by the server.
+    pub fn take_dialed(&self) -> Option<Receiver<(u32, TcpStream)>> {
+        self.dialed_rx.lock().take()
+    }
+
+    /// Use the connection accepted from the peer to send to it, if we have
+    /// no connection to it yet. Returns true if the connection is adopted.
+    pub fn adopt(&self, id_card: u32, stream: &TcpStream) -> bool {
+        let link = match self.link(id_card) {
+            Some(link) => link,
+            None => return false,
+        };
+        if link.is_connected() {
+            return false;
+        }
+        match stream.try_clone() {
+            Ok(stream) => {
+                let challenge = rand::random();
+                let adopted = link.attach(stream, &handshake_frame(&self.handshake(id_card), challenge));
+                if adopted {
+                    info!("adopt connection from {}", id_card);
+                    self.challenges.lock().insert(id_card, challenge);
                 }
+                adopted
             }
-            None => (),
+            Err(_) => false,
+        }
+    }
 
+    /// The connection of the peer is closed.
+    pub fn detach(&self, id_card: u32, stream: &TcpStream) {
+        if let Some(link) = self.link(id_card) {
+            link.detach(stream);
         }
+        self.latency.lock().remove(id_card);
+    }
+
+    /// Drop the connection of the peer, it is connected again later.
+    pub fn disconnect(&self, id_card: u32) {
+        if let Some(link) = self.link(id_card) {
+            link.disconnect();
+        }
+        self.latency.lock().remove(id_card);
+    }
+
+    fn link(&self, id_card: u32) -> Option<PeerStream> {
+        self.peers_pair.read().iter().find(|&&(id, _, _)| id == id_card).map(|&(_, _, ref link)| link.clone())
+    }
+
+    pub fn is_relay(&self) -> bool {
+        self.relayed.is_some()
+    }
+
+    /// Record the message to pass on, returns false if it was relayed before.
+    pub fn first_relayed(&self, hash: H256) -> bool {
+        self.relayed.as_ref().map_or(false, |relayed| relayed.lock().insert(hash))
+    }
+
+    /// Record the transaction or block, returns false if it was seen before.
+    pub fn first_seen(&self, hash: H256) -> bool {
+        self.seen.lock().first_seen(hash)
+    }
+
+    /// The peer has the item, it sent the item to us or we sent it to the peer.
+    pub fn set_peer_has(&self, id_card: u32, hash: H256) {
+        self.seen.lock().set_known(id_card, hash);
+    }
+
+    pub fn peer_has(&self, id_card: u32, hash: &H256) -> bool {
+        self.seen.lock().is_known(id_card, hash)
+    }
+
+    /// Add the chunk from the peer, returns the payload once the transfer completes.
+    pub fn add_chunk(&self, id_card: u32, chunk: Chunk) -> Option<Vec<u8>> {
+        self.chunks.lock().add(id_card, chunk)
+    }
+
+    /// Send the chunks asked by the peer again.
+    pub fn resend_chunks(&self, id_card: u32, transfer: u64, indexes: &[u32]) {
+        let chunks = self.outbox.lock().get(transfer, indexes);
+        info!("resend {} chunks of transfer {} to {}", chunks.len(), transfer, id_card);
+        for chunk in chunks.iter() {
+            broadcast(self, chunk.to_msg(communication::OperateType::SINGLE, id_card));
+        }
+    }
+
+    pub fn set_peer_height(&self, id_card: u32, height: u64) {
+        self.stats.lock().entry(id_card).or_insert_with(PeerStats::default).height = Some(height);
+    }
+
+    pub fn peer_stats(&self, id_card: u32) -> PeerStats {
+        self.stats.lock().get(&id_card).cloned().unwrap_or_default()
+    }
+
+    /// Start a ping to the peer, returns its nonce and the pings missed in a row before it.
+    pub fn ping(&self, id_card: u32) -> (u64, u32) {
+        self.latency.lock().ping(id_card)
+    }
+
+    pub fn pong(&self, id_card: u32, nonce: u64) -> Option<Duration> {
+        self.latency.lock().pong(id_card, nonce)
+    }
+
+    pub fn rtt(&self, id_card: u32) -> Option<Duration> {
+        self.latency.lock().rtt(id_card)
+    }
+
+    pub fn quorum_rtt(&self, peers: &[u32]) -> Option<Duration> {
+        self.latency.lock().quorum_rtt(peers)
+    }
+
+    pub fn score(&self, id_card: u32) -> i32 {
+        self.peer_key(id_card).map_or(INITIAL_SCORE, |peer| self.scores.lock().score(peer))
+    }
+
+    pub fn set_chain_height(&self, height: u64) {
+        self.chain_height.store(height as usize, Ordering::SeqCst);
+    }
+
+    pub fn chain_height(&self) -> u64 {
+        self.chain_height.load(Ordering::SeqCst) as u64
+    }
+
+    /// Count the bytes received from the peer, returns false if they are over the limits.
+    pub fn download(&self, id_card: u32, bytes: usize, priority: bool) -> bool {
+        self.stats.lock().entry(id_card).or_insert_with(PeerStats::default).received += bytes as u64;
+        let mut download = self.download.lock();
+        if priority {
+            download.charge(id_card, bytes);
+            return true;
+        }
+        download.acquire(id_card, bytes).is_none()
+    }
+
+    fn sent(&self, id_card: u32, bytes: usize) {
+        self.stats.lock().entry(id_card).or_insert_with(PeerStats::default).sent += bytes as u64;
+    }
+
+    pub fn is_known(&self, id_card: u32) -> bool {
+        id_card == self.id_card || self.peers_pair.read().iter().any(|&(id, _, _)| id == id_card)
+    }
+
+    /// Add a peer found by discovery and start connecting it.
+    pub fn add_peer(&self, id_card: u32, addr: SocketAddr) -> bool {
+        let mut peers = self.peers_pair.write();
+        if id_card == self.id_card || !self.slots.lock().can_dial(peers.len()) || peers.iter().any(|&(id, _, _)| id == id_card) {
+            return false;
+        }
+        info!("add peer {} {:?}", id_card, addr);
+        let stream = PeerLink::new(addr);
+        self.connect(id_card, stream.clone());
+        peers.push((id_card, addr, stream));
+        true
+    }
+
+    // peers of the config are added regardless of max_outbound, replacing the one with the same id_card
+    fn insert_peer(&self, id_card: u32, addr: SocketAddr) {
+        if id_card == self.id_card {
+            return;
+        }
+        self.remove_peer(id_card, addr);
+        info!("add peer {} {:?} of config", id_card, addr);
+        let stream = PeerLink::new(addr);
+        self.connect(id_card, stream.clone());
+        self.peers_pair.write().push((id_card, addr, stream));
+    }
+
+    /// Disconnect the peer and stop connecting it.
+    fn remove_peer(&self, id_card: u32, addr: SocketAddr) {
+        let removed: Vec<(u32, SocketAddr, PeerStream)> = {
+            let mut peers = self.peers_pair.write();
+            let (removed, kept): (Vec<_>, Vec<_>) = peers.drain(..).partition(|&(id, _, _)| id == id_card);
+            *peers = kept;
+            removed
+        };
+        for &(_, old_addr, ref stream) in removed.iter() {
+            info!("remove peer {} {:?}, new address {:?}", id_card, old_addr, addr);
+            // dropping the waker stops the connecting thread
+            self.wakers.lock().remove(&id_card);
+            stream.close();
+        }
+    }
 
-        Connection { id_card, peers_pair }
+    /// Peers with an established connection.
+    pub fn connected_peers(&self) -> Vec<(u32, SocketAddr)> {
+        self.peers_pair.read().iter().filter(|x| x.2.is_connected()).map(|&(id, addr, _)| (id, addr)).collect()
     }
 }
 
+fn slot_limits(config: &config::NetConfig) -> SlotLimits {
+    let limit = |n: Option<u64>| n.map_or(usize::max_value(), |n| n as usize);
+    SlotLimits {
+        max_inbound: limit(config.max_inbound),
+        // max_peer is the old name of max_outbound
+        max_outbound: limit(config.max_outbound.or(config.max_peer)),
+        reserved: config.reserved_slots.unwrap_or(0) as usize,
+    }
+}
+
+fn backoff(config: &config::NetConfig) -> Backoff {
+    Backoff::new(config.reconnect_min.unwrap_or(1000), config.reconnect_max.unwrap_or(60000), config.reconnect_retries)
+}
+
+fn peer_hosts(config: &config::NetConfig) -> Vec<(u32, String, u64)> {
+    config.peers.as_ref().map_or(Vec::new(), |peers| peers.iter().filter(|peer| config::is_hostname(peer.ip.as_ref().unwrap())).map(|peer| (peer.id_card.unwrap(), peer.ip.clone().unwrap(), peer.port.unwrap())).collect())
+}
+
+fn config_peers(config: &config::NetConfig) -> Vec<(u32, SocketAddr)> {
+    config.peers.as_ref().map_or(Vec::new(), |peers| peers.iter().map(|peer| (peer.id_card.unwrap(), config::resolve(peer.ip.as_ref().unwrap(), peer.port.unwrap()).unwrap())).collect())
+}
+
 pub fn do_connect(con: &Connection) {
-    for &(_, addr, ref stream) in &con.peers_pair {
-        let stream_lock = stream.clone();
-        thread::spawn(move || loop {
-                          {
-                              let stream_opt = &mut *stream_lock.as_ref().write();
-                              if stream_opt.is_none() {
-                                  trace!("connet {:?}", addr);
-                                  let stream = TcpStream::connect(addr).ok();
-                                  *stream_opt = stream;
-                              }
-
-                              let mut need_reconnect = false;
-                              if let Some(ref mut stream) = stream_opt.as_mut() {
-                                  trace!("handshake with {:?}!", addr);
-                                  let mut header = [0; 8];
-                                  BigEndian::write_u64(&mut header, 0xDEADBEEF00000000 as u64);
-                                  let res = stream.write(&header);
-                                  if res.is_err() {
-                                      warn!("handshake with {:?} error!", addr);
-                                      need_reconnect = true;
-                                  }
-                              }
-
-                              if need_reconnect {
-                                  *stream_opt = None;
-                              }
-                          }
+    for &(id_card, _, ref stream) in con.peers_pair.read().iter() {
+        con.connect(id_card, stream.clone());
+    }
+}
 
-                          let ten_sec = Duration::from_millis(TIMEOUT * 1000);
-                          thread::sleep(ten_sec);
-                          trace!("after sleep retry connect {:?}!", addr);
-                      });
+// Frame of our handshake with the challenge.
+fn handshake_frame(handshake: &Handshake, challenge: u64) -> Vec<u8> {
+    let mut handshake = *handshake;
+    handshake.challenge = challenge;
+    encode_frame(&handshake.encode(), Stream::Auth, false)
+}
+
+struct Dialer {
+    id_card: u32,
+    dial_first: bool,
+    // our handshake to the peer, with the challenge of the connection
+    handshake: Handshake,
+    challenges: Arc<Mutex<HashMap<u32, u64>>>,
+    dialed: Sender<(u32, TcpStream)>,
+}
+
+impl Dialer {
+    /// Frame of our handshake on the current connection, sent again to keep it alive.
+    fn handshake_frame(&self) -> Vec<u8> {
+        let challenge = self.challenges.lock().get(&self.id_card).cloned().unwrap_or(0);
+        handshake_frame(&self.handshake, challenge)
+    }
+
+    /// Dial the addresses of the peer in turn and use the first connection, returns false if all failed.
+    fn dial(&self, link: &PeerLink) -> bool {
+        for addr in link.addrs().into_iter() {
+            trace!("connet {} at {:?}", self.id_card, addr);
+            if let Ok(stream) = TcpStream::connect(addr) {
+                link.prefer(addr);
+                return self.use_stream(link, stream);
+            }
+        }
+        false
+    }
+
+    fn use_stream(&self, link: &PeerLink, stream: TcpStream) -> bool {
+        let reader = match stream.try_clone() {
+            Ok(reader) => reader,
+            Err(_) => return false,
+        };
+        // a new challenge for each connection, so a proof can not be replayed on another
+        let challenge = rand::random();
+        if !link.attach(stream, &handshake_frame(&self.handshake, challenge)) {
+            // the connection from the peer was adopted meanwhile
+            let _ = reader.shutdown(::std::net::Shutdown::Both);
+            return link.is_connected();
+        }
+        self.challenges.lock().insert(self.id_card, challenge);
+        let _ = self.dialed.send((self.id_card, reader));
+        true
     }
 }
 
+fn connect_peer(dialer: Dialer, link: PeerStream, mut backoff: Backoff, wake: Receiver<()>) {
+    thread::spawn(move || {
+        let mut idle_since = Instant::now();
+        loop {
+            let delay = if link.is_connected() {
+                // the handshake also keeps the connection alive
+                trace!("handshake with {}!", dialer.id_card);
+                link.outgoing.push(Stream::Auth, dialer.handshake_frame());
+                backoff.reset();
+                idle_since = Instant::now();
+                Some(Duration::from_secs(TIMEOUT))
+            } else if !dialer.dial_first && idle_since.elapsed() < Duration::from_secs(ADOPT_WAIT) {
+                // wait for the peer to dial us
+                Some(Duration::from_secs(TIMEOUT))
+            } else if dialer.dial(&link) {
+                backoff.reset();
+                Some(Duration::from_secs(TIMEOUT))
+            } else {
+                let delay = backoff.fail();
+                // only log the first failure to avoid spamming
+                if backoff.failures() == 1 {
+                    warn!("connect {} at {:?} failed, retrying with backoff", dialer.id_card, link.addrs());
+                }
+                delay
+            };
+
+            match delay {
+                Some(delay) => {
+                    match wake.recv_timeout(delay) {
+                        Ok(_) => backoff.reset(),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                None => {
+                    warn!("give up connecting {} after {} failures until enabled", dialer.id_card, backoff.failures() - 1);
+                    if wake.recv().is_err() {
+                        return;
+                    }
+                    backoff.reset();
+                }
+            }
+            trace!("retry connect {}!", dialer.id_card);
+        }
+    });
+}
+
 pub fn broadcast(con: &Connection, mut msg: communication::Message) {
     let origin = msg.get_origin();
     let operate = msg.get_operate();
     msg.set_origin(con.id_card);
+    let is_tx = msg.get_cmd_id() == cmd_id(submodules::CONSENSUS, topics::NEW_TX) && msg.get_field_type() == communication::MsgType::TX;
+    let targets = if is_tx { con.tx_targets() } else { None };
+    match targets {
+        Some(targets) => send(con, msg, |id_card| is_send(id_card, origin, operate) && targets.contains(&id_card)),
+        None => send(con, msg, |id_card| is_send(id_card, origin, operate)),
+    }
+}
+
+/// Pass the broadcast message of a peer on to the others, keeping its origin.
+pub fn forward(con: &Connection, from: u32, msg: communication::Message) {
+    let origin = msg.get_origin();
+    send(con, msg, |id_card| id_card != from && id_card != origin);
+}
+
+/// Wait until the bytes can be sent to the peer, run by the writer of the peer.
+/// The handshakes, pings and consensus messages are charged but never held back.
+fn wait_upload(upload: &Mutex<Throttle>, id_card: u32, bytes: usize, priority: bool) {
+    if priority {
+        upload.lock().charge(id_card, bytes);
+        return;
+    }
+    loop {
+        let delay = upload.lock().acquire(id_card, bytes);
+        match delay {
+            Some(delay) => thread::sleep(delay),
+            None => return,
+        }
+    }
+}
+
+fn send<F>(con: &Connection, msg: communication::Message, is_target: F)
+    where F: Fn(u32) -> bool
+{
+    let operate = msg.get_operate();
+    let stream = Stream::of(&msg);
+    let hash = item_hash(&msg);
+    if let Some(hash) = hash {
+        con.first_seen(hash);
+    }
 
     trace!("broadcast msg {:?} ", msg);
-    let msg = msg.write_to_bytes().unwrap();
-    let request_id = 0xDEADBEEF00000000 + msg.len();
-    let mut encoded_request_id = [0; 8];
-    BigEndian::write_u64(&mut encoded_request_id, request_id as u64);
-    let mut buf = Vec::new();
-    buf.extend(&encoded_request_id);
-    buf.extend(msg);
-    let send_msg = move |stream: &Arc<RwLock<Option<TcpStream>>>| {
-        let streams_lock = stream.clone();
-        let stream_opt = &mut (*streams_lock.as_ref().write());
-        if let Some(ref mut stream) = stream_opt.as_mut() {
-            let _ = stream.write(&buf);
-        }
-    };
+    // do not hold the lock of peers while waiting for the bandwidth
+    let targets: Vec<(u32, PeerStream)> = con.peers_pair.read().iter().filter(|&&(id_card, _, ref link)| link.is_connected() && is_target(id_card) && !con.is_peer_banned(id_card) && !hash.map_or(false, |hash| con.peer_has(id_card, &hash))).map(|&(id_card, _, ref link)| (id_card, link.clone())).collect();
+    // the peers of older versions get the message written down to theirs
+    let mut versions: BTreeMap<u32, Vec<(u32, PeerStream)>> = BTreeMap::new();
+    for (id_card, link) in targets {
+        versions.entry(cmp::min(con.message_version(id_card), msg.get_version())).or_insert_with(Vec::new).push((id_card, link));
+    }
     let mut peers = vec![];
-    for &(id_card, _, ref stream) in &con.peers_pair {
-        if is_send(id_card, origin, operate) {
+    for (version, targets) in versions {
+        let mut msg = msg.clone();
+        if let Err(reason) = downgrade_msg(&mut msg, version) {
+            warn!("can not send msg to peers of version {}: {}", version, reason);
+            continue;
+        }
+        let msg = msg.write_to_bytes().unwrap();
+        let payloads = if msg.len() > CHUNK_THRESHOLD {
+            let chunks = chunk::split(&msg);
+            info!("send {} bytes in {} chunks", msg.len(), chunks.len());
+            let payloads: Vec<Vec<u8>> = chunks.iter().map(|c| c.to_msg(operate, con.id_card).write_to_bytes().unwrap()).collect();
+            con.outbox.lock().push(chunks);
+            payloads
+        } else {
+            vec![msg]
+        };
+        let plain: Vec<Vec<u8>> = payloads.iter().map(|p| encode_frame(p, stream, false)).collect();
+        let mut compressed = None;
+        for &(id_card, ref link) in targets.iter() {
             peers.push(id_card);
-            send_msg(stream);
+            let frames = if con.is_snappy(id_card) {
+                if compressed.is_none() {
+                    compressed = Some(payloads.iter().map(|p| encode_frame(p, stream, true)).collect::<Vec<_>>());
+                }
+                compressed.as_ref().unwrap()
+            } else {
+                &plain
+            };
+            for buf in frames.iter() {
+                con.sent(id_card, buf.len());
+                link.outgoing.push(stream, buf.clone());
+            }
+            if let Some(hash) = hash {
+                con.set_peer_has(id_card, hash);
+            }
         }
     }
 
@@ -128,16 +880,28 @@ pub fn is_send(id_card: u32, origin: u32, operate: communication::OperateType) -
     operate == communication::OperateType::BROADCAST || (operate == communication::OperateType::SINGLE && id_card == origin) || (operate == communication::OperateType::SUBTRACT && origin != id_card)
 }
 
-pub fn start_client(con: Arc<Connection>, rx: Receiver<communication::Message>) {
+pub fn start_client(con: Arc<Connection>, queue: Arc<SendQueue<communication::Message>>) {
     thread::spawn(move || {
                       info!("start client!");
                       loop {
-                          let msg = rx.recv().unwrap();
+                          let msg = queue.pop();
                           broadcast(&con, msg);
                       }
                   });
 }
 
+/// Ask the peers for the missing chunks of the stalled transfers.
+pub fn start_chunk_timer(con: Arc<Connection>) {
+    thread::spawn(move || loop {
+                          thread::sleep(Duration::from_secs(CHUNK_TIMER));
+                          let stalled = con.chunks.lock().stalled();
+                          for (peer, transfer, missing) in stalled.into_iter() {
+                              info!("transfer {} from {} stalled, ask for {} chunks", transfer, peer, missing.len());
+                              broadcast(&con, chunk::resume_msg(peer, transfer, &missing));
+                          }
+                      });
+}
+
 #[cfg(test)]
 mod test {
     use super::is_send;
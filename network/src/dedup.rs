@@ -0,0 +1,144 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Recently seen transactions and blocks, and the ones each peer already
+//! has, so the gossip does not send or process the same item again.
+
+use libproto::communication::{Message, MsgType, OperateType};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use util::{H256, Hashable};
+
+const SEEN_CAPACITY: usize = 20000;
+const PEER_KNOWN_CAPACITY: usize = 4096;
+
+/// Set keeping the latest inserted items.
+pub struct BoundedSet<T> {
+    capacity: usize,
+    items: HashSet<T>,
+    order: VecDeque<T>,
+}
+
+impl<T: Hash + Eq + Clone> BoundedSet<T> {
+    pub fn new(capacity: usize) -> Self {
+        BoundedSet {
+            capacity: capacity,
+            items: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns false if the item is already in the set.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.items.contains(&item) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.items.remove(&oldest);
+            }
+        }
+        self.items.insert(item.clone());
+        self.order.push_back(item);
+        true
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
+}
+
+/// Hash of the gossiped transaction or block in the message. The blocks sent
+/// to a single peer answer its sync requests, they may be requested again.
+pub fn item_hash(msg: &Message) -> Option<H256> {
+    let field_type = msg.get_field_type();
+    if field_type == MsgType::TX || (field_type == MsgType::BLOCK && msg.get_operate() == OperateType::BROADCAST) {
+        Some(msg.get_content().crypt_hash())
+    } else {
+        None
+    }
+}
+
+pub struct Seen {
+    items: BoundedSet<H256>,
+    peers: HashMap<u32, BoundedSet<H256>>,
+}
+
+impl Seen {
+    pub fn new() -> Self {
+        Seen {
+            items: BoundedSet::new(SEEN_CAPACITY),
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Record the item, returns false if it was seen before.
+    pub fn first_seen(&mut self, hash: H256) -> bool {
+        self.items.insert(hash)
+    }
+
+    pub fn set_known(&mut self, id_card: u32, hash: H256) {
+        self.peers.entry(id_card).or_insert_with(|| BoundedSet::new(PEER_KNOWN_CAPACITY)).insert(hash);
+    }
+
+    pub fn is_known(&self, id_card: u32, hash: &H256) -> bool {
+        self.peers.get(&id_card).map_or(false, |known| known.contains(hash))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoundedSet, Seen, item_hash};
+    use libproto::communication::{Message, MsgType, OperateType};
+    use util::H256;
+
+    #[test]
+    fn bounded_set() {
+        let mut set = BoundedSet::new(2);
+        assert!(set.insert(1));
+        assert!(!set.insert(1));
+        assert!(set.insert(2));
+        assert!(set.insert(3));
+        assert!(!set.contains(&1));
+        assert!(set.contains(&2) && set.contains(&3));
+    }
+
+    #[test]
+    fn seen() {
+        let mut seen = Seen::new();
+        let hash = H256::from(1);
+        assert!(seen.first_seen(hash));
+        assert!(!seen.first_seen(hash));
+        seen.set_known(1, hash);
+        assert!(seen.is_known(1, &hash));
+        assert!(!seen.is_known(2, &hash));
+    }
+
+    #[test]
+    fn items() {
+        let mut msg = Message::new();
+        msg.set_content(vec![1, 2, 3]);
+        msg.set_field_type(MsgType::TX);
+        assert!(item_hash(&msg).is_some());
+        msg.set_field_type(MsgType::BLOCK);
+        assert!(item_hash(&msg).is_some());
+        msg.set_operate(OperateType::SINGLE);
+        assert_eq!(item_hash(&msg), None);
+        msg.set_field_type(MsgType::MSG);
+        assert_eq!(item_hash(&msg), None);
+    }
+}
@@ -0,0 +1,120 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Peer exchange, nodes only need a few bootstrap peers in the config
+//! and learn the others from the peers they are connected to. The bootstrap
+//! peers may be given by hostnames, which are resolved again periodically.
+
+use connection::{Connection, broadcast};
+use libproto::{cmd_id, submodules, topics, communication};
+use rustc_serialize::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const DISCOVERY_INTERVAL: u64 = 30;
+pub const DEFAULT_RESOLVE_INTERVAL: u64 = 60;
+
+#[derive(Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PeerInfo {
+    pub id_card: u32,
+    pub addr: String,
+}
+
+/// Message announcing ourself and the connected peers.
+pub fn peers_msg(con: &Connection) -> communication::Message {
+    let mut peers: Vec<PeerInfo> = con.connected_peers()
+                                      .into_iter()
+                                      .map(|(id_card, addr)| PeerInfo { id_card: id_card, addr: addr.to_string() })
+                                      .collect();
+    if let Some(addr) = con.self_addr {
+        peers.push(PeerInfo {
+                       id_card: con.id_card,
+                       addr: addr.to_string(),
+                   });
+    }
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::PEERS));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_operate(communication::OperateType::BROADCAST);
+    msg.set_content(json::encode(&peers).unwrap().into_bytes());
+    msg
+}
+
+/// Peers of the announcement not known yet.
+pub fn new_peers(con: &Connection, content: &[u8]) -> Vec<(u32, SocketAddr)> {
+    let peers: Vec<PeerInfo> = match ::std::str::from_utf8(content).ok().and_then(|s| json::decode(s).ok()) {
+        Some(peers) => peers,
+        None => return vec![],
+    };
+    peers.into_iter()
+         .filter(|p| !con.is_known(p.id_card))
+         .filter_map(|p| p.addr.parse::<SocketAddr>().ok().map(|addr| (p.id_card, addr)))
+         .collect()
+}
+
+pub fn handle_peers(con: &Connection, content: &[u8]) {
+    for (id_card, addr) in new_peers(con, content) {
+        if !con.add_peer(id_card, addr) {
+            break;
+        }
+    }
+}
+
+pub fn start_discovery(con: Arc<Connection>) {
+    thread::spawn(move || loop {
+                      thread::sleep(Duration::from_secs(DISCOVERY_INTERVAL));
+                      broadcast(&con, peers_msg(&con));
+                  });
+}
+
+/// Resolve the hostnames of the bootstrap peers every `interval` seconds.
+pub fn start_resolve(con: Arc<Connection>, interval: u64) {
+    thread::spawn(move || loop {
+                      con.refresh_addrs();
+                      thread::sleep(Duration::from_secs(interval));
+                  });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PeerInfo, new_peers};
+    use config::NetConfig;
+    use connection::Connection;
+    use rustc_serialize::json;
+
+    #[test]
+    fn find_new_peers() {
+        let con = Connection::new(&NetConfig::test_config());
+        let peers = vec![PeerInfo {
+                             id_card: 0,
+                             addr: "127.0.0.1:40000".to_string(),
+                         },
+                         PeerInfo {
+                             id_card: 1,
+                             addr: "127.0.0.1:40001".to_string(),
+                         },
+                         PeerInfo {
+                             id_card: 2,
+                             addr: "bad address".to_string(),
+                         }];
+        let content = json::encode(&peers).unwrap().into_bytes();
+        assert_eq!(new_peers(&con, &content), vec![(1, "127.0.0.1:40001".parse().unwrap())]);
+        assert!(new_peers(&con, b"not json").is_empty());
+    }
+}
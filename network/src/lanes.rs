@@ -0,0 +1,182 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Send queues of the messages to broadcast. Consensus messages have strict
+//! priority, the block sync and the transaction traffic share the rest of
+//! the bandwidth by weight, so heavy syncing can not delay the votes.
+
+use libproto::{cmd_id, submodules, topics, communication};
+use msghandle::is_consensus_msg;
+use parking_lot::{Condvar, Mutex};
+use std::collections::VecDeque;
+
+/// Sync messages sent for each transaction message when both are queued.
+const SYNC_WEIGHT: usize = 3;
+/// Messages kept in the sync or transaction lane, the oldest are dropped over it.
+const MAX_LANE_LEN: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lane {
+    Consensus,
+    Sync,
+    Tx,
+}
+
+impl Lane {
+    pub fn of(msg: &communication::Message) -> Lane {
+        if is_consensus_msg(msg) {
+            Lane::Consensus
+        } else if msg.get_cmd_id() == cmd_id(submodules::CONSENSUS, topics::NEW_TX) {
+            Lane::Tx
+        } else {
+            Lane::Sync
+        }
+    }
+}
+
+pub struct Lanes<T> {
+    consensus: VecDeque<T>,
+    sync: VecDeque<T>,
+    tx: VecDeque<T>,
+    // sync messages sent since the last transaction message
+    sync_sent: usize,
+}
+
+impl<T> Lanes<T> {
+    pub fn new() -> Self {
+        Lanes {
+            consensus: VecDeque::new(),
+            sync: VecDeque::new(),
+            tx: VecDeque::new(),
+            sync_sent: 0,
+        }
+    }
+
+    pub fn push(&mut self, lane: Lane, item: T) {
+        let queue = match lane {
+            Lane::Consensus => {
+                self.consensus.push_back(item);
+                return;
+            }
+            Lane::Sync => &mut self.sync,
+            Lane::Tx => &mut self.tx,
+        };
+        if queue.len() >= MAX_LANE_LEN {
+            warn!("{:?} lane is full, drop the oldest message", lane);
+            queue.pop_front();
+        }
+        queue.push_back(item);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if let Some(item) = self.consensus.pop_front() {
+            return Some(item);
+        }
+        if !self.sync.is_empty() && (self.tx.is_empty() || self.sync_sent < SYNC_WEIGHT) {
+            self.sync_sent += 1;
+            return self.sync.pop_front();
+        }
+        self.sync_sent = 0;
+        self.tx.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.consensus.len() + self.sync.len() + self.tx.len()
+    }
+}
+
+/// Lanes shared by the producers and the sending thread.
+pub struct SendQueue<T> {
+    lanes: Mutex<Lanes<T>>,
+    cond: Condvar,
+}
+
+impl<T> SendQueue<T> {
+    pub fn new() -> Self {
+        SendQueue {
+            lanes: Mutex::new(Lanes::new()),
+            cond: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, lane: Lane, item: T) {
+        self.lanes.lock().push(lane, item);
+        self.cond.notify_one();
+    }
+
+    /// Wait for the next message to send.
+    pub fn pop(&self) -> T {
+        let mut lanes = self.lanes.lock();
+        loop {
+            if let Some(item) = lanes.pop() {
+                return item;
+            }
+            self.cond.wait(&mut lanes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Lane, Lanes, SendQueue, SYNC_WEIGHT};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn consensus_first() {
+        let mut lanes = Lanes::new();
+        lanes.push(Lane::Sync, 1);
+        lanes.push(Lane::Tx, 2);
+        lanes.push(Lane::Consensus, 3);
+        assert_eq!(lanes.pop(), Some(3));
+        lanes.push(Lane::Consensus, 4);
+        assert_eq!(lanes.pop(), Some(4));
+        assert_eq!(lanes.len(), 2);
+    }
+
+    #[test]
+    fn weighted() {
+        let mut lanes = Lanes::new();
+        for i in 0..10 {
+            lanes.push(Lane::Sync, i);
+            lanes.push(Lane::Tx, 100 + i);
+        }
+        let order: Vec<i32> = (0..SYNC_WEIGHT + 1).map(|_| lanes.pop().unwrap()).collect();
+        assert_eq!(order, vec![0, 1, 2, 100]);
+        // the other lane is drained alone
+        let mut lanes = Lanes::new();
+        for i in 0..5 {
+            lanes.push(Lane::Tx, i);
+        }
+        let order: Vec<i32> = (0..5).map(|_| lanes.pop().unwrap()).collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+        assert_eq!(lanes.pop(), None);
+    }
+
+    #[test]
+    fn send_queue() {
+        let queue = Arc::new(SendQueue::new());
+        let producer = queue.clone();
+        let handle = thread::spawn(move || {
+                                       producer.push(Lane::Tx, 1);
+                                       producer.push(Lane::Consensus, 2);
+                                   });
+        handle.join().unwrap();
+        assert_eq!(queue.pop(), 2);
+        assert_eq!(queue.pop(), 1);
+    }
+}
@@ -19,11 +19,6 @@
 #[macro_use]
 extern crate log;
 extern crate clap;
-extern crate futures;
-extern crate tokio_io;
-extern crate tokio_core;
-extern crate tokio_proto;
-extern crate tokio_service;
 extern crate byteorder;
 extern crate rustc_serialize;
 extern crate parking_lot;
@@ -34,26 +29,52 @@ extern crate util;
 extern crate dotenv;
 extern crate cita_log;
 extern crate bytes;
+extern crate cita_ed25519 as ed25519;
+extern crate rand;
 
 pub mod config;
 pub mod server;
 pub mod connection;
 pub mod citaprotocol;
 pub mod msghandle;
+pub mod discovery;
+pub mod auth;
+pub mod score;
+pub mod throttle;
+pub mod backoff;
+pub mod lanes;
+pub mod dedup;
+pub mod chunk;
+pub mod status;
+pub mod reload;
+pub mod mux;
+pub mod ping;
+pub mod slots;
+pub mod txroute;
 
 
 use clap::{App, SubCommand};
 use config::NetConfig;
-use connection::{Connection, do_connect, start_client};
+use connection::{Connection, do_connect, start_client, start_chunk_timer};
+use discovery::{start_discovery, start_resolve, DEFAULT_RESOLVE_INTERVAL};
 use dotenv::dotenv;
+use lanes::{Lane, SendQueue};
+use libproto::submodules;
+use libproto::handshake::start_handshake;
+use libproto::heartbeat::start_heartbeat;
+use libproto::shutdown::Shutdown;
 use log::LogLevelFilter;
-use msghandle::{is_need_proc, handle_rpc};
-use pubsub::start_pubsub;
+use msghandle::{is_need_proc, handle_rpc, handle_node_list, handle_status, handle_authorities, handle_proposers};
+use ping::{start_ping, DEFAULT_PING_INTERVAL};
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use reload::start_reload;
 use server::MySender;
 use server::start_server;
+use status::{start_status, DEFAULT_STATUS_INTERVAL};
 use std::env;
 use std::sync::Arc;
-use std::sync::mpsc::channel;
+use std::thread;
+use std::sync::mpsc::{channel, sync_channel};
 
 fn main() {
     dotenv().ok();
@@ -84,33 +105,69 @@ fn main() {
     let is_test = matches.is_present("test");
 
     let config = if is_test { NetConfig::test_config() } else { NetConfig::new(config_path) };
+    if let Err(err) = config.validate() {
+        panic!("invalid network config: {}", err);
+    }
 
     // init pubsub
-    let (ctx_sub, crx_sub) = channel();
+    let (ctx_sub, crx_sub) = sync_channel(QUEUE_SIZE);
     let (ctx_pub, crx_pub) = channel();
 
-    start_pubsub("network", vec!["consensus.tx", "consensus.msg", "chain.status", "chain.blk", "chain.sync", "jsonrpc.net"], ctx_sub, crx_pub);
+    if config.relay.unwrap_or(false) {
+        info!("run as a relay node");
+        // there are no other modules on a relay node, drop what would be published to them,
+        // and nothing is subscribed, so the loop below just waits
+        thread::spawn(move || for _ in crx_pub.iter() {});
+    } else {
+        start_pubsub("network", vec!["consensus.tx", "consensus.msg", "chain.status", "chain.blk", "chain.sync", "chain.nodes", "jsonrpc.net", "consensus.authorities", "consensus.proposers"], ctx_sub, crx_pub);
+    }
 
     // start server
     // This brings up our server.
     // all server recv msg directly publish to mq
+    let con = Arc::new(Connection::new(&config));
+    if is_test || config.relay.unwrap_or(false) {
+        // no chain tells the approved nodes, let every peer in
+        con.set_authorized(Vec::new());
+    }
     let mysender = MySender::new(ctx_pub.clone());
-    start_server(&config, mysender);
+    start_server(&config, mysender, con.clone());
 
     // connect peers
-    let con = Connection::new(&config);
     do_connect(&con);
-    let (ctx, crx) = channel();
-    let con = Arc::new(con);
-    start_client(con.clone(), crx);
-
-    loop {
-        // msg from mq need proc before broadcast
-        let (key, body) = crx_sub.recv().unwrap();
-        trace!("handle delivery id {:?} payload {:?}", key, body);
-        if let (_, true, msg) = is_need_proc(body.as_ref()) {
-            ctx.send(msg).unwrap();
-        }
-        handle_rpc(&con, &ctx_pub, body.as_ref());
+    let queue = Arc::new(SendQueue::new());
+    start_client(con.clone(), queue.clone());
+    start_discovery(con.clone());
+    start_resolve(con.clone(), config.resolve_interval.unwrap_or(DEFAULT_RESOLVE_INTERVAL));
+    start_chunk_timer(con.clone());
+    start_status(con.clone(), ctx_pub.clone(), config.status_interval.unwrap_or(DEFAULT_STATUS_INTERVAL));
+    start_ping(con.clone(), ctx_pub.clone(), config.ping_interval.unwrap_or(DEFAULT_PING_INTERVAL));
+    if !is_test {
+        start_reload(con.clone(), config_path.to_string());
+    }
+
+    let shutdown = Shutdown::new(submodules::NET, "net");
+    // a relay node runs no other modules to check against
+    if !config.relay.unwrap_or(false) {
+        start_handshake("net", &[]);
     }
+    start_heartbeat(submodules::NET, "net", shutdown.clone(), ctx_pub.clone());
+    let shutdown1 = shutdown.clone();
+    let ctx_pub1 = ctx_pub.clone();
+    thread::spawn(move || loop {
+                      // msg from mq need proc before broadcast
+                      let (key, body) = crx_sub.recv().unwrap();
+                      let _work = shutdown1.working();
+                      trace!("handle delivery id {:?} payload {:?}", key, body);
+                      if let (_, true, msg) = is_need_proc(body.as_ref()) {
+                          queue.push(Lane::of(&msg), msg);
+                      }
+                      handle_rpc(&con, &ctx_pub1, body.as_ref());
+                      handle_node_list(&con, body.as_ref());
+                      handle_status(&con, body.as_ref());
+                      handle_authorities(&con, body.as_ref());
+                      handle_proposers(&con, body.as_ref());
+                  });
+
+    shutdown.run(&ctx_pub, &[], || {});
 }
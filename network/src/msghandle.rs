@@ -15,16 +15,28 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use auth::Handshake;
+use chunk::{Chunk, decode_resume};
 use citaprotocol::CitaRequest;
-use connection::Connection;
+use connection::{Connection, broadcast, forward};
+use dedup::item_hash;
+use discovery::handle_peers;
 use libproto::*;
+use libproto::blockchain::{Block, Status};
 use libproto::communication::*;
 use libproto::request::Request;
 use protobuf::Message;
+use ping::{decode_nonce, pong_msg};
 use protobuf::core::parse_from_bytes;
+use rustc_serialize::json::{self, Json};
+use score::{Misbehavior, PeerKey};
 use server::MySender;
+use status::net_status;
+use std::cell::Cell;
 use std::io;
+use std::str::FromStr;
 use std::sync::mpsc::Sender;
+use util::{Address, Hashable};
 
 pub fn handle_rpc(con: &Connection, tx_pub: &Sender<(String, Vec<u8>)>, payload: &[u8]) {
     if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
@@ -36,15 +48,105 @@ pub fn handle_rpc(con: &Connection, tx_pub: &Sender<(String, Vec<u8>)>, payload:
             let mut response = request::Response::new();
             response.set_request_id(ts.take_request_id());
             if ts.has_peercount() {
-                let peercount = con.peers_pair.iter().filter(|x| x.2.as_ref().read().is_some()).count();
+                let peercount = con.connected_peers().len();
                 response.set_peercount(peercount as u32);
                 let ms: communication::Message = response.into();
                 tx_pub.send(("chain.rpc".to_string(), ms.write_to_bytes().unwrap())).unwrap();
+            } else if ts.has_peer_bans() {
+                let bans: Vec<PeerBan> = con.banned().into_iter().map(|(id_card, remaining)| PeerBan { id_card: id_card, remaining: remaining }).collect();
+                response.set_peer_bans(json::encode(&bans).unwrap());
+                let ms: communication::Message = response.into();
+                tx_pub.send(("chain.rpc".to_string(), ms.write_to_bytes().unwrap())).unwrap();
+            } else if ts.has_peers_info() {
+                response.set_peers_info(json::encode(&net_status(con).peers).unwrap());
+                let ms: communication::Message = response.into();
+                tx_pub.send(("chain.rpc".to_string(), ms.write_to_bytes().unwrap())).unwrap();
+            } else if ts.has_enable_peer() {
+                let enabled = con.enable_peer(ts.get_enable_peer() as u32);
+                response.set_enable_peer(enabled);
+                let ms: communication::Message = response.into();
+                tx_pub.send(("chain.rpc".to_string(), ms.write_to_bytes().unwrap())).unwrap();
+            }
+        }
+    }
+}
+
+#[derive(Debug, RustcEncodable)]
+struct PeerBan {
+    id_card: u32,
+    remaining: u64,
+}
+
+/// Track the height of the local chain to find the stale blocks.
+pub fn handle_status(con: &Connection, payload: &[u8]) {
+    if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
+        if msg.get_cmd_id() == cmd_id(submodules::CHAIN, topics::NEW_STATUS) && msg.get_field_type() == MsgType::STATUS {
+            if let Ok(status) = parse_from_bytes::<Status>(msg.get_content()) {
+                con.set_chain_height(status.get_height());
+            }
+        }
+    }
+}
+
+/// Update the nodes approved by the node manager contract.
+pub fn handle_node_list(con: &Connection, payload: &[u8]) {
+    if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
+        if msg.get_cmd_id() == cmd_id(submodules::CHAIN, topics::NODE_LIST) && msg.get_field_type() == MsgType::MSG {
+            let nodes: Vec<Address> = msg.get_content().chunks(20).filter(|c| c.len() == 20).map(Address::from_slice).collect();
+            trace!("authorized nodes {:?}", nodes);
+            con.set_authorized(nodes);
+        }
+    }
+}
+
+/// Update the validators, they can take the reserved inbound slots.
+pub fn handle_authorities(con: &Connection, payload: &[u8]) {
+    if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
+        if msg.get_cmd_id() == cmd_id(submodules::CONSENSUS, topics::AUTHORITIES) && msg.get_field_type() == MsgType::MSG {
+            if let Some(authorities) = decode_authorities(msg.get_content()) {
+                trace!("authorities {:?}", authorities);
+                con.set_authorities(authorities);
+            }
+        }
+    }
+}
+
+/// Update the next proposers, the new transactions can be sent to them only.
+pub fn handle_proposers(con: &Connection, payload: &[u8]) {
+    if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
+        if msg.get_cmd_id() == cmd_id(submodules::CONSENSUS, topics::PROPOSERS) && msg.get_field_type() == MsgType::MSG {
+            if let Some(proposers) = decode_authorities(msg.get_content()) {
+                trace!("proposers {:?}", proposers);
+                con.set_proposers(proposers);
             }
         }
     }
 }
 
+// the content is the json of (height, [address])
+fn decode_authorities(content: &[u8]) -> Option<Vec<Address>> {
+    let json = match ::std::str::from_utf8(content).ok().and_then(|s| Json::from_str(s).ok()) {
+        Some(json) => json,
+        None => return None,
+    };
+    let addrs = match json.as_array().and_then(|pair| pair.get(1)).and_then(|addrs| addrs.as_array()) {
+        Some(addrs) => addrs,
+        None => return None,
+    };
+    addrs.iter().map(|addr| addr.as_string().and_then(|s| Address::from_str(s.trim_left_matches("0x")).ok())).collect()
+}
+
+/// Messages of the consensus, they are never held back by the bandwidth limits.
+pub fn is_consensus_msg(msg: &communication::Message) -> bool {
+    let cid = msg.get_cmd_id();
+    msg.get_field_type() == MsgType::MSG && (cid == cmd_id(submodules::CONSENSUS, topics::CONSENSUS_MSG) || cid == cmd_id(submodules::CONSENSUS, topics::NEW_PROPOSAL) || cid == cmd_id(submodules::CONSENSUS, topics::COMMIT_PROOF) || cid == cmd_id(submodules::NET, topics::HANDSHAKE) || cid == cmd_id(submodules::NET, topics::HANDSHAKE_PROOF) || cid == cmd_id(submodules::NET, topics::PING) || cid == cmd_id(submodules::NET, topics::PONG))
+}
+
+/// Messages of the fast sync, serving or downloading the snapshots.
+fn is_snapshot_msg(cid: u32) -> bool {
+    cid == cmd_id(submodules::CHAIN, topics::SNAPSHOT_REQ) || cid == cmd_id(submodules::CHAIN, topics::SNAPSHOT_MANIFEST) || cid == cmd_id(submodules::CHAIN, topics::SNAPSHOT_CHUNK_REQ) || cid == cmd_id(submodules::CHAIN, topics::SNAPSHOT_CHUNK)
+}
+
 pub fn is_need_proc(payload: &[u8]) -> (String, bool, communication::Message) {
     if let Ok(msg) = parse_from_bytes::<communication::Message>(payload) {
         let mut topic = String::default();
@@ -66,7 +168,10 @@ pub fn is_need_proc(payload: &[u8]) -> (String, bool, communication::Message) {
         } else if cid == cmd_id(submodules::CHAIN, topics::SYNC_BLK) && t == MsgType::MSG {
             info!("CHAIN sync blk");
             topic = "net.sync".to_string();
-        } else if (cid == cmd_id(submodules::CONSENSUS, topics::CONSENSUS_MSG) && t == MsgType::MSG) || (cid == cmd_id(submodules::CONSENSUS, topics::NEW_PROPOSAL) && t == MsgType::MSG) {
+        } else if is_snapshot_msg(cid) && t == MsgType::MSG {
+            trace!("CHAIN snapshot msg");
+            topic = "net.sync".to_string();
+        } else if (cid == cmd_id(submodules::CONSENSUS, topics::CONSENSUS_MSG) && t == MsgType::MSG) || (cid == cmd_id(submodules::CONSENSUS, topics::NEW_PROPOSAL) && t == MsgType::MSG) || (cid == cmd_id(submodules::CONSENSUS, topics::COMMIT_PROOF) && t == MsgType::MSG) {
             trace!("CONSENSUS pub msg");
             topic = "net.msg".to_string();
         } else {
@@ -77,11 +182,238 @@ pub fn is_need_proc(payload: &[u8]) -> (String, bool, communication::Message) {
     ("".to_string(), false, communication::Message::new())
 }
 
-pub fn net_msg_handler(payload: CitaRequest, mysender: &MySender) -> Result<Vec<u8>, io::Error> {
+// returning error closes the connection
+fn punish(con: &Connection, peer: PeerKey, misbehavior: Misbehavior) -> Result<Vec<u8>, io::Error> {
+    if con.report(peer, misbehavior) {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "peer banned"));
+    }
+    Ok(vec![])
+}
+
+/// Handle the message received from the connection, returns the frames to send back to
+/// the peer. Nothing but the handshake is taken before the handshake, and the messages sent
+/// before it close the connection without punishing anyone. The handshake is answered by
+/// the proof of our address, and the peer is scored for the address its proof proves.
+pub fn net_msg_handler(payload: CitaRequest, mysender: &MySender, con: &Connection, peer: &Cell<Option<Address>>, peer_id: &Cell<Option<u32>>, peer_key: &Cell<Option<PeerKey>>) -> Result<Vec<u8>, io::Error> {
     trace!("SERVER get msg: {:?}", payload);
+    let msg = match parse_from_bytes::<communication::Message>(payload.as_ref()) {
+        Ok(msg) => msg,
+        Err(_) => {
+            return match peer_key.get() {
+                       Some(key) => punish(con, key, Misbehavior::InvalidMessage),
+                       None => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid message before handshake")),
+                   };
+        }
+    };
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::HANDSHAKE) {
+        match Handshake::decode(msg.get_content(), con.id_card) {
+            Ok(handshake) => {
+                trace!("handshake {:?}", handshake);
+                if let Err(reason) = con.check_handshake(&handshake) {
+                    warn!("refuse incompatible peer {}: {}", handshake.id_card, reason);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, reason));
+                }
+                if let Some(id_card) = peer_id.get() {
+                    if id_card != handshake.id_card {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("handshake of {} on the connection of {}", handshake.id_card, id_card)));
+                    }
+                }
+                peer_id.set(Some(handshake.id_card));
+                con.set_peer_capabilities(handshake.id_card, handshake.capabilities);
+                // scored for the connection until the proof
+                let key = peer_key.get().unwrap_or_else(|| con.connection_key());
+                peer_key.set(Some(key));
+                con.set_peer_key(handshake.id_card, key);
+                if con.is_banned(key) {
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, "peer banned"));
+                }
+                return Ok(con.proof_frame(handshake.id_card, handshake.challenge).unwrap_or_default());
+            }
+            Err(reason) => {
+                warn!("invalid handshake from {}: {}", msg.get_origin(), reason);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, reason));
+            }
+        }
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::HANDSHAKE_PROOF) {
+        let id_card = match peer_id.get() {
+            Some(id_card) => id_card,
+            None => return Err(io::Error::new(io::ErrorKind::PermissionDenied, "proof before handshake")),
+        };
+        // a bad proof leaves the peer unproved, it may be meant for another connection of ours
+        match con.verify_proof(id_card, msg.get_content()) {
+            Ok((sender, _)) if sender != id_card => warn!("proof of {} on the connection of {}", sender, id_card),
+            Ok((_, address)) => {
+                match peer.get() {
+                    Some(proved) if proved == address => {}
+                    Some(proved) => warn!("proof of {:?} from {} proved as {:?}", address, id_card, proved),
+                    None => {
+                        match con.bind_peer(id_card, address) {
+                            Ok(()) => {
+                                trace!("peer {} proved {:?}", id_card, address);
+                                peer.set(Some(address));
+                                let key = PeerKey::Address(address);
+                                peer_key.set(Some(key));
+                                con.set_peer_key(id_card, key);
+                                if con.is_banned(key) {
+                                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, "peer banned"));
+                                }
+                            }
+                            Err(reason) => warn!("refuse the proof of {}: {}", id_card, reason),
+                        }
+                    }
+                }
+            }
+            Err(reason) => warn!("invalid proof from {}: {}", id_card, reason),
+        }
+        return Ok(vec![]);
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::HANDSHAKE_REJECT) {
+        let reason = String::from_utf8_lossy(msg.get_content()).into_owned();
+        warn!("refused by peer {}: {}", msg.get_origin(), reason);
+        return Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason));
+    }
+    let (id_card, key) = match (peer_id.get(), peer_key.get()) {
+        (Some(id_card), Some(key)) => (id_card, key),
+        _ => return Err(io::Error::new(io::ErrorKind::PermissionDenied, "message before handshake")),
+    };
+    if con.is_banned(key) {
+        warn!("drop banned peer {}", id_card);
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "peer banned"));
+    }
+    if !con.is_authorized(peer.get()) {
+        // the proof of the peer or the approved nodes may still be on the way
+        if peer.get().is_none() || !con.knows_authorized() {
+            trace!("drop msg from {} not authorized yet", id_card);
+            return Ok(vec![]);
+        }
+        warn!("drop unauthorized peer {:?}", peer.get());
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "unauthorized peer"));
+    }
+    if !con.received(key) {
+        // spamming, drop the message
+        if con.is_banned(key) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "peer banned"));
+        }
+        return Ok(vec![]);
+    }
+    if !con.download(id_card, payload.len(), is_consensus_msg(&msg)) {
+        trace!("drop msg from {} over the download limit", id_card);
+        return Ok(vec![]);
+    }
+    handle_msg(msg, payload, mysender, con, id_card, key)
+}
+
+/// Handle the message from the peer passing the checks.
+fn handle_msg(mut msg: communication::Message, mut payload: CitaRequest, mysender: &MySender, con: &Connection, id_card: u32, key: PeerKey) -> Result<Vec<u8>, io::Error> {
+    // the older messages are passed on upgraded, those which can not be are dropped
+    if msg.get_version() != MESSAGE_VERSION {
+        if let Err(reason) = upgrade_msg(&mut msg) {
+            warn!("drop msg from {}: {}", id_card, reason);
+            return Ok(vec![]);
+        }
+        payload = msg.write_to_bytes().unwrap();
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::PEERS) {
+        handle_peers(con, msg.get_content());
+        return Ok(vec![]);
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::CHUNK) {
+        return match Chunk::decode(msg.get_content()) {
+                   Some(chunk) => {
+                       match con.add_chunk(id_card, chunk) {
+                           // handle the reassembled message as it was received directly
+                           Some(payload) => {
+                               match parse_from_bytes::<communication::Message>(payload.as_ref()) {
+                                   Ok(msg) => handle_msg(msg, payload, mysender, con, id_card, key),
+                                   Err(_) => punish(con, key, Misbehavior::InvalidMessage),
+                               }
+                           }
+                           None => Ok(vec![]),
+                       }
+                   }
+                   None => punish(con, key, Misbehavior::InvalidMessage),
+               };
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::CHUNK_RESUME) {
+        return match decode_resume(msg.get_content()) {
+                   Some((transfer, missing)) => {
+                       con.resend_chunks(id_card, transfer, &missing);
+                       Ok(vec![])
+                   }
+                   None => punish(con, key, Misbehavior::InvalidMessage),
+               };
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::PING) {
+        return match decode_nonce(msg.get_content()) {
+                   Some(nonce) => {
+                       broadcast(con, pong_msg(id_card, nonce));
+                       Ok(vec![])
+                   }
+                   None => punish(con, key, Misbehavior::InvalidMessage),
+               };
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::NET, topics::PONG) {
+        return match decode_nonce(msg.get_content()) {
+                   Some(nonce) => {
+                       if let Some(rtt) = con.pong(id_card, nonce) {
+                           trace!("rtt of {} is {:?}", id_card, rtt);
+                       }
+                       Ok(vec![])
+                   }
+                   None => punish(con, key, Misbehavior::InvalidMessage),
+               };
+    }
+    if msg.get_cmd_id() == cmd_id(submodules::CHAIN, topics::NEW_STATUS) && msg.get_field_type() == MsgType::STATUS {
+        if let Ok(status) = parse_from_bytes::<Status>(msg.get_content()) {
+            con.set_peer_height(id_card, status.get_height());
+        }
+    }
+    if let Some(hash) = item_hash(&msg) {
+        con.set_peer_has(id_card, hash);
+        if !con.first_seen(hash) {
+            trace!("drop known item {:?} from {}", hash, id_card);
+            return Ok(vec![]);
+        }
+    }
+    if msg.get_field_type() == MsgType::BLOCK {
+        let content = match msg_content(&msg) {
+            Ok(content) => content,
+            Err(_) => return punish(con, key, Misbehavior::InvalidMessage),
+        };
+        match parse_from_bytes::<Block>(&content) {
+            Ok(block) => {
+                if block.get_header().get_height() < con.chain_height() {
+                    return punish(con, key, Misbehavior::StaleBlock);
+                }
+            }
+            Err(_) => return punish(con, key, Misbehavior::InvalidMessage),
+        }
+    }
+    if con.is_relay() {
+        // only the broadcast messages can be passed on, the others are for the relay itself
+        if msg.get_operate() == communication::OperateType::BROADCAST && con.first_relayed(payload.crypt_hash()) {
+            forward(con, id_card, msg);
+        }
+        return Ok(vec![]);
+    }
     if let (topic, true, msg) = is_need_proc(payload.as_ref()) {
         info!("recive msg from origin = {:?}", msg.get_origin());
         mysender.send((topic, payload))
     }
     Ok(vec![])
 }
+
+#[cfg(test)]
+mod test {
+    use super::decode_authorities;
+    use util::Address;
+
+    #[test]
+    fn authorities() {
+        let content = br#"[10,["0x0000000000000000000000000000000000000001","0x0000000000000000000000000000000000000002"]]"#;
+        assert_eq!(decode_authorities(content), Some(vec![Address::from(1u64), Address::from(2u64)]));
+        assert_eq!(decode_authorities(br#"[10,["0x01"]]"#), None);
+        assert_eq!(decode_authorities(b"invalid"), None);
+    }
+}
@@ -0,0 +1,309 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Protocol streams multiplexed on the single connection of a peer. Every
+//! stream has its own bounded send queue, so the backlog of one stream can
+//! not hold back the others, and the queues are drained by priority.
+
+use libproto::{cmd_id, submodules, topics, communication};
+use lanes::Lane;
+use parking_lot::{Condvar, Mutex, RwLock};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stream {
+    Auth = 0,
+    Consensus = 1,
+    Sync = 2,
+    Tx = 3,
+}
+
+pub const STREAMS: usize = 4;
+
+impl Stream {
+    pub fn from_id(id: u8) -> Option<Stream> {
+        match id {
+            0 => Some(Stream::Auth),
+            1 => Some(Stream::Consensus),
+            2 => Some(Stream::Sync),
+            3 => Some(Stream::Tx),
+            _ => None,
+        }
+    }
+
+    pub fn of(msg: &communication::Message) -> Stream {
+        let cid = msg.get_cmd_id();
+        // pings share the stream of the handshakes, so the rtt is not inflated by the queued traffic
+        if cid == cmd_id(submodules::NET, topics::HANDSHAKE) || cid == cmd_id(submodules::NET, topics::PING) || cid == cmd_id(submodules::NET, topics::PONG) {
+            return Stream::Auth;
+        }
+        match Lane::of(msg) {
+            Lane::Consensus => Stream::Consensus,
+            Lane::Sync => Stream::Sync,
+            Lane::Tx => Stream::Tx,
+        }
+    }
+
+    /// Bytes queued for the stream at most, the oldest frames are dropped over it.
+    fn capacity(&self) -> usize {
+        match *self {
+            Stream::Auth => 1024 * 1024,
+            Stream::Consensus => 16 * 1024 * 1024,
+            Stream::Sync => 32 * 1024 * 1024,
+            Stream::Tx => 8 * 1024 * 1024,
+        }
+    }
+}
+
+struct Queues {
+    frames: Vec<VecDeque<Vec<u8>>>,
+    bytes: Vec<usize>,
+    closed: bool,
+}
+
+/// Send queues of the streams to a peer.
+pub struct Outgoing {
+    queues: Mutex<Queues>,
+    cond: Condvar,
+}
+
+impl Outgoing {
+    pub fn new() -> Self {
+        Outgoing {
+            queues: Mutex::new(Queues {
+                                   frames: (0..STREAMS).map(|_| VecDeque::new()).collect(),
+                                   bytes: vec![0; STREAMS],
+                                   closed: false,
+                               }),
+            cond: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, stream: Stream, frame: Vec<u8>) {
+        let mut queues = self.queues.lock();
+        let i = stream as usize;
+        queues.bytes[i] += frame.len();
+        queues.frames[i].push_back(frame);
+        while queues.bytes[i] > stream.capacity() && queues.frames[i].len() > 1 {
+            warn!("{:?} stream is full, drop the oldest frame", stream);
+            let dropped = queues.frames[i].pop_front().unwrap();
+            queues.bytes[i] -= dropped.len();
+        }
+        self.cond.notify_one();
+    }
+
+    /// Wait for the next frame by the priority of the streams, None once closed.
+    pub fn pop(&self) -> Option<(Stream, Vec<u8>)> {
+        let mut queues = self.queues.lock();
+        loop {
+            if queues.closed {
+                return None;
+            }
+            for i in 0..STREAMS {
+                let frame = queues.frames[i].pop_front();
+                if let Some(frame) = frame {
+                    queues.bytes[i] -= frame.len();
+                    return Some((Stream::from_id(i as u8).unwrap(), frame));
+                }
+            }
+            self.cond.wait(&mut queues);
+        }
+    }
+
+    /// Drop the queued frames, used when the connection is lost.
+    pub fn clear(&self) {
+        let mut queues = self.queues.lock();
+        for i in 0..STREAMS {
+            queues.frames[i].clear();
+            queues.bytes[i] = 0;
+        }
+    }
+
+    pub fn close(&self) {
+        self.queues.lock().closed = true;
+        self.cond.notify_all();
+    }
+}
+
+/// The connection of a peer, dialed by us or accepted from the peer.
+pub struct PeerLink {
+    pub stream: RwLock<Option<TcpStream>>,
+    pub outgoing: Outgoing,
+    // addresses to dial the peer, the one connected last time first
+    addrs: RwLock<Vec<SocketAddr>>,
+}
+
+impl PeerLink {
+    pub fn new(addr: SocketAddr) -> Arc<Self> {
+        Arc::new(PeerLink {
+                     stream: RwLock::new(None),
+                     outgoing: Outgoing::new(),
+                     addrs: RwLock::new(vec![addr]),
+                 })
+    }
+
+    pub fn addrs(&self) -> Vec<SocketAddr> {
+        self.addrs.read().clone()
+    }
+
+    /// Replace the addresses by the newly resolved ones, keeping the preferred one first.
+    pub fn set_addrs(&self, mut addrs: Vec<SocketAddr>) {
+        let mut current = self.addrs.write();
+        if let Some(first) = current.first() {
+            if let Some(i) = addrs.iter().position(|addr| addr == first) {
+                addrs.swap(0, i);
+            }
+        }
+        *current = addrs;
+    }
+
+    /// Dial the address first next time.
+    pub fn prefer(&self, addr: SocketAddr) {
+        let mut addrs = self.addrs.write();
+        if let Some(i) = addrs.iter().position(|a| *a == addr) {
+            let addr = addrs.remove(i);
+            addrs.insert(0, addr);
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.stream.read().is_some()
+    }
+
+    /// Use the connection for the peer if it has none, the handshake is
+    /// written first so the peer knows who is on the other side.
+    pub fn attach(&self, mut stream: TcpStream, handshake: &[u8]) -> bool {
+        let mut current = self.stream.write();
+        if current.is_some() {
+            return false;
+        }
+        if stream.write_all(handshake).is_err() {
+            return false;
+        }
+        *current = Some(stream);
+        true
+    }
+
+    /// The connection is lost, stop using it if it is the current one.
+    pub fn detach(&self, stream: &TcpStream) {
+        let mut current = self.stream.write();
+        let same = current.as_ref().map_or(false, |s| is_same(s, stream));
+        if same {
+            *current = None;
+            self.outgoing.clear();
+        }
+    }
+
+    /// Shut down the connection, the peer will be connected again.
+    pub fn disconnect(&self) {
+        if let Some(stream) = self.stream.write().take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.outgoing.clear();
+    }
+
+    /// Shut down the connection and stop the writer.
+    pub fn close(&self) {
+        if let Some(stream) = self.stream.write().take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        self.outgoing.close();
+    }
+}
+
+fn is_same(a: &TcpStream, b: &TcpStream) -> bool {
+    match (a.local_addr(), a.peer_addr(), b.local_addr(), b.peer_addr()) {
+        (Ok(a_local), Ok(a_peer), Ok(b_local), Ok(b_peer)) => a_local == b_local && a_peer == b_peer,
+        _ => false,
+    }
+}
+
+/// Write the queued frames to the connection of the peer until the link is closed,
+/// frames queued while there is no connection are dropped. `throttle` waits for the
+/// bandwidth of each frame, on the thread of the peer so the others are not held back.
+pub fn start_writer<F>(link: Arc<PeerLink>, throttle: F)
+    where F: Fn(Stream, usize) + Send + 'static
+{
+    thread::spawn(move || while let Some((stream, frame)) = link.outgoing.pop() {
+                          throttle(stream, frame.len());
+                          let mut current = link.stream.write();
+                          let failed = match current.as_mut() {
+                              Some(s) => s.write_all(&frame).is_err(),
+                              None => false,
+                          };
+                          if failed {
+                              warn!("write {:?} stream failed, connection lost", stream);
+                              if let Some(s) = current.take() {
+                                  let _ = s.shutdown(Shutdown::Both);
+                              }
+                              link.outgoing.clear();
+                          }
+                      });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Outgoing, PeerLink, Stream};
+
+    #[test]
+    fn priority() {
+        let outgoing = Outgoing::new();
+        outgoing.push(Stream::Tx, vec![3]);
+        outgoing.push(Stream::Sync, vec![2]);
+        outgoing.push(Stream::Consensus, vec![1]);
+        outgoing.push(Stream::Auth, vec![0]);
+        for i in 0..4 {
+            assert_eq!(outgoing.pop(), Some((Stream::from_id(i).unwrap(), vec![i])));
+        }
+        outgoing.close();
+        assert_eq!(outgoing.pop(), None);
+    }
+
+    #[test]
+    fn independent_capacity() {
+        let outgoing = Outgoing::new();
+        let frame = vec![0u8; 5 * 1024 * 1024];
+        for _ in 0..3 {
+            outgoing.push(Stream::Tx, frame.clone());
+        }
+        outgoing.push(Stream::Consensus, vec![1]);
+        // the full tx stream does not affect consensus
+        assert_eq!(outgoing.pop(), Some((Stream::Consensus, vec![1])));
+        assert_eq!(outgoing.pop().map(|f| f.0), Some(Stream::Tx));
+        outgoing.clear();
+        outgoing.close();
+        assert_eq!(outgoing.pop(), None);
+    }
+
+    #[test]
+    fn addrs() {
+        let a = "127.0.0.1:4000".parse().unwrap();
+        let b = "127.0.0.2:4000".parse().unwrap();
+        let c = "[::1]:4000".parse().unwrap();
+        let link = PeerLink::new(a);
+        link.set_addrs(vec![b, c, a]);
+        assert_eq!(link.addrs(), vec![a, c, b]);
+        link.prefer(b);
+        assert_eq!(link.addrs(), vec![b, a, c]);
+        link.set_addrs(vec![c]);
+        assert_eq!(link.addrs(), vec![c]);
+    }
+}
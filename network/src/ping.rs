@@ -0,0 +1,230 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Liveness of the peers. Every connected peer is pinged periodically, the
+//! round trip time of the pongs is smoothed per peer, and the peers missing
+//! the pongs lose score and get disconnected at last.
+
+use byteorder::{BigEndian, ByteOrder};
+use connection::{Connection, broadcast};
+use libproto::{cmd_id, submodules, topics, communication};
+use protobuf::Message;
+use rand;
+use score::Misbehavior;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PING_INTERVAL: u64 = 5;
+/// Pings missed in a row before the connection is considered dead.
+const MAX_MISSED: u32 = 3;
+
+#[derive(Debug, Default)]
+struct PeerLatency {
+    // nonce and sending time of the ping waiting for the pong
+    pending: Option<(u64, Instant)>,
+    rtt: Option<Duration>,
+    missed: u32,
+}
+
+/// Round trip times of the peers.
+#[derive(Default)]
+pub struct Latency {
+    peers: HashMap<u32, PeerLatency>,
+}
+
+fn millis(d: Duration) -> u64 {
+    d.as_secs() * 1000 + (d.subsec_nanos() / 1_000_000) as u64
+}
+
+impl Latency {
+    pub fn new() -> Self {
+        Latency::default()
+    }
+
+    /// Start a ping to the peer, returns its nonce and the pings missed in a row before it.
+    pub fn ping(&mut self, id_card: u32) -> (u64, u32) {
+        let peer = self.peers.entry(id_card).or_insert_with(PeerLatency::default);
+        if peer.pending.is_some() {
+            peer.missed += 1;
+        }
+        let nonce = rand::random();
+        peer.pending = Some((nonce, Instant::now()));
+        (nonce, peer.missed)
+    }
+
+    /// Pong of the peer, returns the smoothed round trip time if it answers the pending ping.
+    pub fn pong(&mut self, id_card: u32, nonce: u64) -> Option<Duration> {
+        let peer = match self.peers.get_mut(&id_card) {
+            Some(peer) => peer,
+            None => return None,
+        };
+        let sent = match peer.pending {
+            Some((pending, sent)) if pending == nonce => sent,
+            _ => return None,
+        };
+        let sample = sent.elapsed();
+        peer.pending = None;
+        peer.missed = 0;
+        // smoothed like the rtt of tcp
+        let rtt = match peer.rtt {
+            Some(rtt) => (rtt * 7 + sample) / 8,
+            None => sample,
+        };
+        peer.rtt = Some(rtt);
+        Some(rtt)
+    }
+
+    pub fn rtt(&self, id_card: u32) -> Option<Duration> {
+        self.peers.get(&id_card).and_then(|peer| peer.rtt)
+    }
+
+    /// Forget the peer, used when its connection is dropped.
+    pub fn remove(&mut self, id_card: u32) {
+        self.peers.remove(&id_card);
+    }
+
+    /// Round trip time within which two thirds of the peers answer, what a
+    /// consensus round has to wait for at least.
+    pub fn quorum_rtt(&self, peers: &[u32]) -> Option<Duration> {
+        let mut rtts: Vec<Duration> = peers.iter().filter_map(|id_card| self.rtt(*id_card)).collect();
+        if rtts.is_empty() {
+            return None;
+        }
+        rtts.sort();
+        let index = (rtts.len() * 2 + 2) / 3 - 1;
+        Some(rtts[index])
+    }
+}
+
+fn net_msg(topic: u16, peer: u32, nonce: u64) -> communication::Message {
+    let mut content = vec![0u8; 8];
+    BigEndian::write_u64(&mut content, nonce);
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topic));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_operate(communication::OperateType::SINGLE);
+    msg.set_origin(peer);
+    msg.set_content(content);
+    msg
+}
+
+pub fn ping_msg(peer: u32, nonce: u64) -> communication::Message {
+    net_msg(topics::PING, peer, nonce)
+}
+
+pub fn pong_msg(peer: u32, nonce: u64) -> communication::Message {
+    net_msg(topics::PONG, peer, nonce)
+}
+
+pub fn decode_nonce(content: &[u8]) -> Option<u64> {
+    if content.len() != 8 {
+        return None;
+    }
+    Some(BigEndian::read_u64(content))
+}
+
+/// Round trip time for the consensus, published on "network.rtt" in milliseconds.
+pub fn rtt_msg(con: &Connection, rtt: Duration) -> communication::Message {
+    let mut content = vec![0u8; 8];
+    BigEndian::write_u64(&mut content, millis(rtt));
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::NET_RTT));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_origin(con.id_card);
+    msg.set_content(content);
+    msg
+}
+
+/// Ping the connected peers every `interval` seconds.
+pub fn start_ping(con: Arc<Connection>, tx_pub: Sender<(String, Vec<u8>)>, interval: u64) {
+    thread::spawn(move || loop {
+                          thread::sleep(Duration::from_secs(interval));
+                          let peers: Vec<u32> = con.connected_peers().into_iter().map(|(id_card, _)| id_card).collect();
+                          for &id_card in peers.iter() {
+                              let (nonce, missed) = con.ping(id_card);
+                              if missed > 0 {
+                                  con.report_peer(id_card, Misbehavior::Unresponsive);
+                              }
+                              if missed >= MAX_MISSED {
+                                  warn!("peer {} missed {} pings, disconnect it", id_card, missed);
+                                  con.disconnect(id_card);
+                                  continue;
+                              }
+                              broadcast(&con, ping_msg(id_card, nonce));
+                          }
+                          if let Some(rtt) = con.quorum_rtt(&peers) {
+                              let msg = rtt_msg(&con, rtt);
+                              tx_pub.send(("network.rtt".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                          }
+                      });
+}
+
+pub fn rtt_millis(rtt: Option<Duration>) -> Option<u64> {
+    rtt.map(millis)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Latency, decode_nonce, ping_msg};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn ping_pong() {
+        let mut latency = Latency::new();
+        let (nonce, missed) = latency.ping(1);
+        assert_eq!(missed, 0);
+        assert_eq!(latency.pong(1, nonce + 1), None);
+        thread::sleep(Duration::from_millis(10));
+        let rtt = latency.pong(1, nonce).unwrap();
+        assert!(rtt >= Duration::from_millis(10));
+        assert_eq!(latency.rtt(1), Some(rtt));
+        // answered only once
+        assert_eq!(latency.pong(1, nonce), None);
+
+        let msg = ping_msg(1, nonce);
+        assert_eq!(decode_nonce(msg.get_content()), Some(nonce));
+    }
+
+    #[test]
+    fn missed_pings() {
+        let mut latency = Latency::new();
+        latency.ping(1);
+        latency.ping(1);
+        let (nonce, missed) = latency.ping(1);
+        assert_eq!(missed, 2);
+        latency.pong(1, nonce);
+        assert_eq!(latency.ping(1).1, 0);
+    }
+
+    #[test]
+    fn quorum_rtt() {
+        let mut latency = Latency::new();
+        assert_eq!(latency.quorum_rtt(&[1, 2, 3]), None);
+        for id_card in 1..4 {
+            let (nonce, _) = latency.ping(id_card);
+            thread::sleep(Duration::from_millis(10 * id_card as u64));
+            latency.pong(id_card, nonce);
+        }
+        let rtt = latency.quorum_rtt(&[1, 2, 3]).unwrap();
+        assert_eq!(Some(rtt), latency.rtt(2));
+        assert_eq!(latency.quorum_rtt(&[3, 4]), latency.rtt(3));
+    }
+}
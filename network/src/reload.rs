@@ -0,0 +1,73 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Watch the config file and apply the changes at runtime, restarting the
+//! network would drop all the connections and delay the consensus. The
+//! config is also reloaded on SIGHUP.
+
+use cita_log::changelog;
+use config::NetConfig;
+use connection::Connection;
+use libproto::reload::on_hangup;
+use std::fs;
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const WATCH_INTERVAL: u64 = 5;
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+macro_rules! log_changes {
+    ($old:expr, $new:expr, $($field:ident),*) => {
+        $(changelog("network", stringify!($field), &$old.$field, &$new.$field);)*
+    }
+}
+
+pub fn start_reload(con: Arc<Connection>, path: String) {
+    let (hangup_tx, hangup_rx) = channel();
+    on_hangup(move || {
+                  let _ = hangup_tx.send(());
+              });
+    thread::spawn(move || {
+        let mut last = modified(&path);
+        let mut current = NetConfig::load(&path).ok();
+        loop {
+            let hangup = hangup_rx.recv_timeout(Duration::from_secs(WATCH_INTERVAL)).is_ok();
+            let now = modified(&path);
+            if !hangup && (now.is_none() || now == last) {
+                continue;
+            }
+            last = now;
+            match NetConfig::load(&path) {
+                Ok(config) => {
+                    info!("reload network config {}", path);
+                    if let Some(ref old) = current {
+                        // the settings applied by the connection
+                        log_changes!(old, config, max_outbound, max_peer, max_inbound, reserved_slots, compress, ban_time, upload_limit, download_limit, peer_upload_limit, peer_download_limit, reconnect_min, reconnect_max, reconnect_retries, peers);
+                    }
+                    con.reload(&config);
+                    current = Some(config);
+                }
+                Err(err) => warn!("ignore invalid network config: {}", err),
+            }
+        }
+    });
+}
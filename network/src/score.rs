@@ -0,0 +1,228 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Misbehavior tracking of the peers. Every peer starts with a full score,
+//! loses points for the bad messages and gets them back slowly by the good
+//! ones, it is banned for a while once the score runs out.
+//!
+//! The scores are kept for the address a peer proves in its handshake, or
+//! for its connection if it proves none, never for the id_card it claims, so
+//! no one gets banned for the messages sent in its name.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use util::Address;
+
+pub const INITIAL_SCORE: i32 = 100;
+const BAN_THRESHOLD: i32 = 0;
+/// Messages a peer may send in a second before it is considered spamming.
+const MAX_MSG_PER_SEC: u32 = 1000;
+pub const DEFAULT_BAN_TIME: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Misbehavior {
+    /// The payload can not be parsed.
+    InvalidMessage,
+    /// Bad handshake, or a message not allowed for the peer.
+    ProtocolViolation,
+    /// Block at or below the height we already have.
+    StaleBlock,
+    /// Too many messages in a short time.
+    Spam,
+    /// The ping is not answered in time.
+    Unresponsive,
+}
+
+impl Misbehavior {
+    fn penalty(&self) -> i32 {
+        match *self {
+            Misbehavior::InvalidMessage => 20,
+            Misbehavior::ProtocolViolation => 40,
+            Misbehavior::StaleBlock => 5,
+            Misbehavior::Spam => 30,
+            Misbehavior::Unresponsive => 10,
+        }
+    }
+}
+
+/// Whom the messages are scored for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PeerKey {
+    /// Address proved by the signature of the handshake.
+    Address(Address),
+    /// Serial number of a connection whose handshake proves no address.
+    Connection(u64),
+}
+
+pub struct PeerScores {
+    ban_time: Duration,
+    scores: HashMap<PeerKey, i32>,
+    // banned peers and when the bans are lifted
+    bans: HashMap<PeerKey, Instant>,
+    // start of the current rate window and messages received in it
+    rates: HashMap<PeerKey, (Instant, u32)>,
+}
+
+impl PeerScores {
+    pub fn new(ban_time: u64) -> Self {
+        PeerScores {
+            ban_time: Duration::from_secs(ban_time),
+            scores: HashMap::new(),
+            bans: HashMap::new(),
+            rates: HashMap::new(),
+        }
+    }
+
+    /// Takes effect on the next bans.
+    pub fn set_ban_time(&mut self, ban_time: u64) {
+        self.ban_time = Duration::from_secs(ban_time);
+    }
+
+    pub fn score(&self, peer: PeerKey) -> i32 {
+        *self.scores.get(&peer).unwrap_or(&INITIAL_SCORE)
+    }
+
+    /// Punish the peer, returns true if it gets banned.
+    pub fn report(&mut self, peer: PeerKey, misbehavior: Misbehavior) -> bool {
+        let score = self.score(peer) - misbehavior.penalty();
+        warn!("peer {:?} {:?}, score {}", peer, misbehavior, score);
+        if score > BAN_THRESHOLD {
+            self.scores.insert(peer, score);
+            return false;
+        }
+        warn!("ban peer {:?} for {:?}", peer, self.ban_time);
+        self.scores.remove(&peer);
+        self.rates.remove(&peer);
+        self.bans.insert(peer, Instant::now() + self.ban_time);
+        true
+    }
+
+    /// Count a good message of the peer, returns false if it is spamming.
+    pub fn received(&mut self, peer: PeerKey) -> bool {
+        let now = Instant::now();
+        let count = {
+            let rate = self.rates.entry(peer).or_insert((now, 0));
+            if now.duration_since(rate.0) >= Duration::from_secs(1) {
+                *rate = (now, 0);
+            }
+            rate.1 += 1;
+            rate.1
+        };
+        if count > MAX_MSG_PER_SEC {
+            // only report once in a window
+            if count == MAX_MSG_PER_SEC + 1 {
+                self.report(peer, Misbehavior::Spam);
+            }
+            return false;
+        }
+        let score = self.score(peer);
+        if score < INITIAL_SCORE {
+            self.scores.insert(peer, score + 1);
+        }
+        true
+    }
+
+    pub fn is_banned(&mut self, peer: PeerKey) -> bool {
+        let expired = match self.bans.get(&peer) {
+            Some(until) => *until <= Instant::now(),
+            None => return false,
+        };
+        if expired {
+            info!("ban of peer {:?} lifted", peer);
+            self.bans.remove(&peer);
+        }
+        !expired
+    }
+
+    /// Lift the ban of the peer, returns true if it was banned.
+    pub fn unban(&mut self, peer: PeerKey) -> bool {
+        self.scores.remove(&peer);
+        self.bans.remove(&peer).is_some()
+    }
+
+    /// Banned peers with the seconds left of their bans.
+    pub fn banned(&mut self) -> Vec<(PeerKey, u64)> {
+        let now = Instant::now();
+        self.bans.retain(|_, until| *until > now);
+        self.bans.iter().map(|(peer, until)| (*peer, until.duration_since(now).as_secs())).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PeerScores, PeerKey, Misbehavior, INITIAL_SCORE, MAX_MSG_PER_SEC};
+    use util::Address;
+
+    const A: PeerKey = PeerKey::Connection(1);
+    const B: PeerKey = PeerKey::Connection(2);
+
+    #[test]
+    fn ban_misbehaving_peer() {
+        let mut scores = PeerScores::new(600);
+        assert!(!scores.report(A, Misbehavior::ProtocolViolation));
+        assert!(!scores.report(A, Misbehavior::InvalidMessage));
+        assert!(!scores.is_banned(A));
+        // good messages recover the score
+        assert!(scores.received(A));
+        assert_eq!(scores.score(A), INITIAL_SCORE - 59);
+        assert!(!scores.report(A, Misbehavior::ProtocolViolation));
+        assert!(scores.report(A, Misbehavior::StaleBlock));
+        assert!(scores.is_banned(A));
+        assert!(!scores.is_banned(B));
+        let bans = scores.banned();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].0, A);
+        assert!(bans[0].1 > 590);
+        assert!(scores.unban(A));
+        assert!(!scores.is_banned(A));
+        assert_eq!(scores.score(A), INITIAL_SCORE);
+    }
+
+    #[test]
+    fn ban_expires() {
+        let mut scores = PeerScores::new(0);
+        assert!(!scores.report(A, Misbehavior::ProtocolViolation));
+        assert!(!scores.report(A, Misbehavior::ProtocolViolation));
+        assert!(scores.report(A, Misbehavior::ProtocolViolation));
+        assert!(!scores.is_banned(A));
+        assert!(scores.banned().is_empty());
+        assert_eq!(scores.score(A), INITIAL_SCORE);
+    }
+
+    #[test]
+    fn spam() {
+        let mut scores = PeerScores::new(600);
+        for _ in 0..MAX_MSG_PER_SEC {
+            assert!(scores.received(A));
+        }
+        assert!(!scores.received(A));
+        assert_eq!(scores.score(A), INITIAL_SCORE - 30);
+        // reported once in a window
+        assert!(!scores.received(A));
+        assert_eq!(scores.score(A), INITIAL_SCORE - 30);
+    }
+
+    #[test]
+    fn scored_by_address() {
+        let mut scores = PeerScores::new(600);
+        let peer = PeerKey::Address(Address::from(1));
+        assert!(!scores.report(peer, Misbehavior::ProtocolViolation));
+        assert_eq!(scores.score(peer), INITIAL_SCORE - 40);
+        // the connection with the same serial number is another peer
+        assert_eq!(scores.score(A), INITIAL_SCORE);
+    }
+}
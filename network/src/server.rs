@@ -15,18 +15,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use citaprotocol::{CitaProto, CitaRequest, CitaResponse};
-
+use bytes::BytesMut;
+use auth::reject_msg;
+use citaprotocol::{CitaRequest, decode_frame, encode_frame};
 use config::NetConfig;
-
-use futures::{BoxFuture, Future};
-use futures::future::result;
+use connection::Connection;
 use msghandle::net_msg_handler;
-use std::{io, thread};
-use std::net::SocketAddr;
+use mux::Stream;
+use score::PeerKey;
+use slots::InboundSlot;
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Arc;
 use std::sync::mpsc::Sender;
-use tokio_proto::TcpServer;
-use tokio_service::Service;
+use std::thread;
+use std::time::Duration;
+use util::Address;
+
+const READ_BUF_SIZE: usize = 64 * 1024;
+/// Seconds an accepted connection may hold its slot without a handshake.
+const HANDSHAKE_WAIT: u64 = 10;
 
 #[derive(Clone)]
 pub struct MySender {
@@ -45,27 +54,150 @@ impl MySender {
 
 unsafe impl Sync for MySender {}
 
-struct Server {
-    mysender: MySender,
+/// Read the frames of all streams from the connection until it is closed.
+/// `owner` is the peer of the connections dialed by us, the accepted
+/// connections are adopted by the peer once its handshake is received.
+fn serve(stream: TcpStream, owner: Option<u32>, mysender: MySender, con: Arc<Connection>) {
+    let mut reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(_) => return,
+    };
+    // address proved by the handshake of the peer
+    let peer: Cell<Option<Address>> = Cell::new(None);
+    // id card told by the handshake of the peer
+    let peer_id: Cell<Option<u32>> = Cell::new(owner);
+    // key the peer is scored for, told by its handshake
+    let peer_key: Cell<Option<PeerKey>> = Cell::new(None);
+    let mut attached = owner.is_some();
+    // our handshake is sent on the connection itself to the peers we have no link to
+    let mut greeted = false;
+    // frames to send back to the peer, after our handshake
+    let mut replies = Vec::new();
+    let mut slot = None;
+    if owner.is_none() {
+        if !con.accept_inbound() {
+            info!("refuse {:?}, no inbound slot", stream.peer_addr());
+            reject(&stream, &con, &io::Error::new(io::ErrorKind::PermissionDenied, "no inbound slot"));
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+        slot = Some(InboundSlot::Pending);
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(HANDSHAKE_WAIT)));
+    }
+    let mut buf = BytesMut::with_capacity(READ_BUF_SIZE);
+    let mut data = vec![0u8; READ_BUF_SIZE];
+    'read: loop {
+        match reader.read(&mut data) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&data[..n]),
+        }
+        loop {
+            match decode_frame(&mut buf) {
+                Ok(Some((_, payload))) => {
+                    match net_msg_handler(payload, &mysender, &con, &peer, &peer_id, &peer_key) {
+                        Ok(reply) => replies.extend(reply),
+                        Err(e) => {
+                            warn!("close connection of {:?}: {}", peer_id.get(), e);
+                            if !attached {
+                                reject(&stream, &con, &e);
+                            }
+                            break 'read;
+                        }
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("close connection of {:?}: {}", peer_id.get(), e);
+                    break 'read;
+                }
+            }
+        }
+        if let Some(id_card) = peer_id.get() {
+            match slot {
+                Some(InboundSlot::Pending) => {
+                    match con.identify_inbound(id_card, peer.get()) {
+                        Ok(identified) => {
+                            slot = identified;
+                            let _ = stream.set_read_timeout(None);
+                        }
+                        // a validator takes the reserved slots once its proof arrives
+                        Err(_) if peer.get().is_none() => {}
+                        Err(e) => {
+                            warn!("close connection of {}: {}", id_card, e);
+                            reject(&stream, &con, &e);
+                            break;
+                        }
+                    }
+                }
+                Some(InboundSlot::Observer) => {
+                    if let Some(address) = peer.get() {
+                        slot = Some(con.promote_inbound(InboundSlot::Observer, address));
+                    }
+                }
+                _ => {}
+            }
+            if !attached {
+                attached = con.adopt(id_card, &stream);
+            }
+            if !con.reply(id_card, &replies) {
+                if !greeted {
+                    greeted = true;
+                    let _ = (&stream).write_all(&con.challenge_frame(id_card));
+                }
+                let _ = (&stream).write_all(&replies);
+            }
+            replies.clear();
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+    if let Some(slot) = slot {
+        con.release_inbound(slot);
+    }
+    if let Some(id_card) = peer_id.get() {
+        if attached {
+            con.detach(id_card, &stream);
+        }
+        if peer.get().is_some() {
+            con.unbind_peer(id_card);
+        }
+        con.forget_challenge(id_card);
+    }
 }
 
-impl Service for Server {
-    type Request = CitaRequest;
-    type Response = CitaResponse;
-    type Error = io::Error;
-    type Future = BoxFuture<Self::Response, io::Error>;
-
-    fn call(&self, req: Self::Request) -> Self::Future {
-        result(net_msg_handler(req, &self.mysender)).boxed()
+// tell the peer why the connection is closed, unless it is refusing us
+fn reject(mut stream: &TcpStream, con: &Connection, err: &io::Error) {
+    if err.kind() != io::ErrorKind::ConnectionRefused {
+        let msg = reject_msg(con.id_card, &err.to_string());
+        let _ = stream.write_all(&encode_frame(&msg, Stream::Auth, false));
     }
 }
 
-pub fn start_server(config: &NetConfig, mysender: MySender) {
-    let addr = format!("0.0.0.0:{}", config.port.unwrap());
-    let addr = addr.parse::<SocketAddr>().unwrap();
+pub fn start_server(config: &NetConfig, mysender: MySender, con: Arc<Connection>) {
+    let addr = config.listen_addr();
+    let listener = TcpListener::bind(addr).expect("failed to bind the listen address");
+    info!("start server on {:?}!", addr);
+
+    // connections dialed by us are read the same way
+    let dialed = con.take_dialed().expect("server started twice");
+    {
+        let mysender = mysender.clone();
+        let con = con.clone();
+        thread::spawn(move || for (id_card, stream) in dialed.iter() {
+                          let mysender = mysender.clone();
+                          let con = con.clone();
+                          thread::spawn(move || serve(stream, Some(id_card), mysender, con));
+                      });
+    }
 
-    thread::spawn(move || {
-                      info!("start server on {:?}!", addr);
-                      TcpServer::new(CitaProto, addr).serve(move || Ok(Server { mysender: mysender.clone() }));
+    thread::spawn(move || for stream in listener.incoming() {
+                      match stream {
+                          Ok(stream) => {
+                              trace!("accept {:?}", stream.peer_addr());
+                              let mysender = mysender.clone();
+                              let con = con.clone();
+                              thread::spawn(move || serve(stream, None, mysender, con));
+                          }
+                          Err(e) => warn!("accept failed: {}", e),
+                      }
                   });
 }
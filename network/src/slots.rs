@@ -0,0 +1,151 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Connection slots of the node. The peers we connect and the connections
+//! accepted from the others are limited separately, and some inbound slots
+//! are reserved for the validators, so the observers connecting to a public
+//! node can not take all of them.
+
+/// Slot taken by an accepted connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InboundSlot {
+    /// Waiting for the handshake.
+    Pending,
+    Observer,
+    Validator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotLimits {
+    pub max_inbound: usize,
+    pub max_outbound: usize,
+    /// Inbound slots only the validators can take.
+    pub reserved: usize,
+}
+
+pub struct Slots {
+    limits: SlotLimits,
+    pending: usize,
+    observers: usize,
+    validators: usize,
+}
+
+impl Slots {
+    pub fn new(limits: SlotLimits) -> Self {
+        Slots {
+            limits: limits,
+            pending: 0,
+            observers: 0,
+            validators: 0,
+        }
+    }
+
+    /// Takes effect on the next connections, the current ones are kept.
+    pub fn set_limits(&mut self, limits: SlotLimits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> SlotLimits {
+        self.limits
+    }
+
+    /// Connections taking the inbound slots, as (pending, observers, validators).
+    pub fn inbound(&self) -> (usize, usize, usize) {
+        (self.pending, self.observers, self.validators)
+    }
+
+    /// Take a slot for the accepted connection until its handshake, returns false if all are taken.
+    pub fn accept(&mut self) -> bool {
+        if self.pending + self.observers + self.validators >= self.limits.max_inbound {
+            return false;
+        }
+        self.pending += 1;
+        true
+    }
+
+    /// The pending connection tells who it is, returns None if there is no slot for it,
+    /// the connection keeps its pending slot then.
+    pub fn identify(&mut self, validator: bool) -> Option<InboundSlot> {
+        if validator {
+            self.pending -= 1;
+            self.validators += 1;
+            return Some(InboundSlot::Validator);
+        }
+        if self.observers < self.limits.max_inbound.saturating_sub(self.limits.reserved) {
+            self.pending -= 1;
+            self.observers += 1;
+            return Some(InboundSlot::Observer);
+        }
+        None
+    }
+
+    /// The observer proved to be a validator.
+    pub fn promote(&mut self) -> InboundSlot {
+        self.observers -= 1;
+        self.validators += 1;
+        InboundSlot::Validator
+    }
+
+    pub fn release(&mut self, slot: InboundSlot) {
+        match slot {
+            InboundSlot::Pending => self.pending -= 1,
+            InboundSlot::Observer => self.observers -= 1,
+            InboundSlot::Validator => self.validators -= 1,
+        }
+    }
+
+    /// Whether one more peer can be connected when we have `outbound` peers.
+    pub fn can_dial(&self, outbound: usize) -> bool {
+        outbound < self.limits.max_outbound
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InboundSlot, SlotLimits, Slots};
+
+    #[test]
+    fn reserved_for_validators() {
+        let mut slots = Slots::new(SlotLimits {
+                                       max_inbound: 3,
+                                       max_outbound: 1,
+                                       reserved: 1,
+                                   });
+        assert!(slots.accept());
+        assert_eq!(slots.identify(false), Some(InboundSlot::Observer));
+        assert!(slots.accept());
+        assert_eq!(slots.identify(false), Some(InboundSlot::Observer));
+        // the last slot is kept for the validators
+        assert!(slots.accept());
+        assert_eq!(slots.identify(false), None);
+        assert_eq!(slots.inbound(), (1, 2, 0));
+        assert_eq!(slots.identify(true), Some(InboundSlot::Validator));
+        assert!(!slots.accept());
+        assert_eq!(slots.inbound(), (0, 2, 1));
+
+        slots.release(InboundSlot::Observer);
+        assert!(slots.accept());
+        slots.release(InboundSlot::Pending);
+        assert_eq!(slots.inbound(), (0, 1, 1));
+
+        assert_eq!(slots.promote(), InboundSlot::Validator);
+        assert_eq!(slots.inbound(), (0, 0, 2));
+
+        assert!(slots.can_dial(0));
+        assert!(!slots.can_dial(1));
+    }
+}
@@ -0,0 +1,134 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Status of the network published on the message bus periodically, so the
+//! other modules can expose it.
+
+use connection::Connection;
+use libproto::{cmd_id, submodules, topics, communication};
+use ping::rtt_millis;
+use protobuf::Message;
+use rustc_serialize::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_STATUS_INTERVAL: u64 = 10;
+
+/// Traffic and chain height of a peer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerStats {
+    pub height: Option<u64>,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct PeerStatus {
+    pub id_card: u32,
+    pub addr: String,
+    pub connected: bool,
+    pub banned: bool,
+    pub score: i32,
+    /// Smoothed round trip time in milliseconds.
+    pub rtt: Option<u64>,
+    pub height: Option<u64>,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, PartialEq, RustcEncodable, RustcDecodable)]
+pub struct NetStatus {
+    pub id_card: u32,
+    pub height: u64,
+    pub peers: Vec<PeerStatus>,
+}
+
+pub fn net_status(con: &Connection) -> NetStatus {
+    let connected = con.connected_peers();
+    let pairs: Vec<(u32, SocketAddr)> = con.peers_pair.read().iter().map(|&(id_card, addr, _)| (id_card, addr)).collect();
+    let peers = pairs.into_iter()
+                   .map(|(id_card, addr)| {
+                            let stats = con.peer_stats(id_card);
+                            PeerStatus {
+                                id_card: id_card,
+                                addr: addr.to_string(),
+                                connected: connected.iter().any(|&(id, _)| id == id_card),
+                                banned: con.is_peer_banned(id_card),
+                                score: con.score(id_card),
+                                rtt: rtt_millis(con.rtt(id_card)),
+                                height: stats.height,
+                                sent: stats.sent,
+                                received: stats.received,
+                            }
+                        })
+                   .collect();
+    NetStatus {
+        id_card: con.id_card,
+        height: con.chain_height(),
+        peers: peers,
+    }
+}
+
+pub fn status_msg(con: &Connection) -> communication::Message {
+    let mut msg = communication::Message::new();
+    msg.set_cmd_id(cmd_id(submodules::NET, topics::NET_STATUS));
+    msg.set_field_type(communication::MsgType::MSG);
+    msg.set_origin(con.id_card);
+    msg.set_content(json::encode(&net_status(con)).unwrap().into_bytes());
+    msg
+}
+
+/// Publish the status on "network.status" every `interval` seconds.
+pub fn start_status(con: Arc<Connection>, tx_pub: Sender<(String, Vec<u8>)>, interval: u64) {
+    thread::spawn(move || loop {
+                          thread::sleep(Duration::from_secs(interval));
+                          let msg = status_msg(&con);
+                          tx_pub.send(("network.status".to_string(), msg.write_to_bytes().unwrap())).unwrap();
+                      });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NetStatus, PeerStatus};
+    use rustc_serialize::json;
+
+    #[test]
+    fn encode_status() {
+        let status = NetStatus {
+            id_card: 0,
+            height: 10,
+            peers: vec![
+                PeerStatus {
+                    id_card: 1,
+                    addr: "127.0.0.1:4001".to_string(),
+                    connected: true,
+                    banned: false,
+                    score: 100,
+                    rtt: Some(12),
+                    height: Some(9),
+                    sent: 100,
+                    received: 200,
+                },
+            ],
+        };
+        let encoded = json::encode(&status).unwrap();
+        assert_eq!(json::decode::<NetStatus>(&encoded).unwrap(), status);
+    }
+}
@@ -0,0 +1,141 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bandwidth limits of the network, a global one for all the peers and one
+//! for each peer. Consensus messages are charged but never held back, so the
+//! other traffic can not delay the votes.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Token bucket refilled by `rate` bytes per second, holding at most one
+/// second of tokens. A message is let through while the bucket is not empty
+/// and may leave it in debt, so the messages larger than the bucket pass too.
+pub struct TokenBucket {
+    rate: u64,
+    tokens: i64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        TokenBucket {
+            rate: rate,
+            tokens: rate as i64,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last);
+        let ms = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1_000_000) as u64;
+        let add = self.rate.saturating_mul(ms) / 1000;
+        if add > 0 {
+            self.tokens = ::std::cmp::min(self.tokens + add as i64, self.rate as i64);
+            self.last = now;
+        }
+    }
+
+    /// Time to wait until the bucket is not empty.
+    pub fn delay(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens > 0 {
+            return None;
+        }
+        let ms = (-self.tokens as u64 + 1) * 1000 / self.rate + 1;
+        Some(Duration::from_millis(ms))
+    }
+
+    pub fn charge(&mut self, bytes: usize) {
+        self.tokens -= bytes as i64;
+    }
+}
+
+pub struct Throttle {
+    global: Option<TokenBucket>,
+    peer_rate: Option<u64>,
+    peers: HashMap<u32, TokenBucket>,
+}
+
+impl Throttle {
+    /// Rates are in bytes per second, None for unlimited.
+    pub fn new(global_rate: Option<u64>, peer_rate: Option<u64>) -> Self {
+        Throttle {
+            global: global_rate.map(TokenBucket::new),
+            peer_rate: peer_rate,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Take the bytes for the peer if allowed now, otherwise returns the time to wait.
+    pub fn acquire(&mut self, id_card: u32, bytes: usize) -> Option<Duration> {
+        let now = Instant::now();
+        let mut delay = self.global.as_mut().and_then(|bucket| bucket.delay(now));
+        if let Some(rate) = self.peer_rate {
+            let peer_delay = self.peers.entry(id_card).or_insert_with(|| TokenBucket::new(rate)).delay(now);
+            delay = ::std::cmp::max(delay, peer_delay);
+        }
+        if delay.is_none() {
+            self.charge(id_card, bytes);
+        }
+        delay
+    }
+
+    /// Take the bytes even if they are over the limits.
+    pub fn charge(&mut self, id_card: u32, bytes: usize) {
+        if let Some(bucket) = self.global.as_mut() {
+            bucket.charge(bytes);
+        }
+        if let Some(rate) = self.peer_rate {
+            self.peers.entry(id_card).or_insert_with(|| TokenBucket::new(rate)).charge(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Throttle;
+
+    #[test]
+    fn unlimited() {
+        let mut throttle = Throttle::new(None, None);
+        for _ in 0..100 {
+            assert_eq!(throttle.acquire(1, 1 << 20), None);
+        }
+    }
+
+    #[test]
+    fn per_peer_limit() {
+        let mut throttle = Throttle::new(None, Some(1000));
+        assert_eq!(throttle.acquire(1, 3000), None);
+        // peer 1 is in debt for two seconds
+        let delay = throttle.acquire(1, 10).unwrap();
+        assert!(delay.as_secs() >= 1 && delay.as_secs() <= 2);
+        assert_eq!(throttle.acquire(2, 10), None);
+    }
+
+    #[test]
+    fn global_limit() {
+        let mut throttle = Throttle::new(Some(1000), Some(100000));
+        assert_eq!(throttle.acquire(1, 600), None);
+        assert_eq!(throttle.acquire(2, 600), None);
+        assert!(throttle.acquire(3, 10).is_some());
+        // charged traffic is never held back but uses the budget
+        throttle.charge(3, 10);
+        assert!(throttle.acquire(4, 10).is_some());
+    }
+}
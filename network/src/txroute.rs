@@ -0,0 +1,90 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Routing of the new transactions. Instead of flooding every transaction
+//! to all the peers, it is sent to the next proposers told by the consensus,
+//! and gossiped to a few other peers so it is not lost if they miss their
+//! slots. Without a connected proposer it is sent to all the peers.
+
+use rand::{thread_rng, Rng};
+use std::collections::{HashMap, HashSet};
+use util::Address;
+
+/// Peers other than the proposers a new transaction is sent to, by default.
+pub const DEFAULT_TX_GOSSIP: usize = 2;
+
+pub struct TxRoute {
+    gossip: usize,
+    proposers: Vec<Address>,
+    // addresses of the peers proved in their handshakes
+    peers: HashMap<u32, Address>,
+}
+
+impl TxRoute {
+    pub fn new(gossip: usize) -> Self {
+        TxRoute {
+            gossip: gossip,
+            proposers: Vec::new(),
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn set_proposers(&mut self, proposers: Vec<Address>) {
+        self.proposers = proposers;
+    }
+
+    pub fn set_peer_address(&mut self, id_card: u32, address: Address) {
+        self.peers.insert(id_card, address);
+    }
+
+    /// Peers among the connected ones to send a new transaction to, all of them if none.
+    pub fn targets(&self, connected: &[u32]) -> Option<HashSet<u32>> {
+        let (mut proposers, mut others): (Vec<u32>, Vec<u32>) = connected.iter().cloned().partition(|id_card| self.peers.get(id_card).map_or(false, |address| self.proposers.contains(address)));
+        if proposers.is_empty() {
+            return None;
+        }
+        thread_rng().shuffle(&mut others);
+        others.truncate(self.gossip);
+        proposers.extend(others);
+        Some(proposers.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_proposers() {
+        let mut route = TxRoute::new(1);
+        let connected = vec![1, 2, 3, 4];
+        assert_eq!(route.targets(&connected), None);
+
+        route.set_proposers(vec![Address::from(10), Address::from(11)]);
+        route.set_peer_address(2, Address::from(10));
+        route.set_peer_address(3, Address::from(12));
+        // not connected
+        route.set_peer_address(5, Address::from(11));
+        let targets = route.targets(&connected).unwrap();
+        assert!(targets.contains(&2));
+        assert_eq!(targets.len(), 2);
+        assert!(!targets.contains(&5));
+
+        route.set_proposers(vec![Address::from(11)]);
+        assert_eq!(route.targets(&connected), None);
+    }
+}
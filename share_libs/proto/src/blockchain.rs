@@ -268,6 +268,7 @@ pub struct BlockHeader {
     pub receipts_root: ::std::vec::Vec<u8>,
     pub gas_used: u64,
     pub proof: ::protobuf::SingularPtrField<Proof>,
+    pub extra: ::std::vec::Vec<u8>,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -536,6 +537,40 @@ impl BlockHeader {
     fn mut_proof_for_reflect(&mut self) -> &mut ::protobuf::SingularPtrField<Proof> {
         &mut self.proof
     }
+
+    // bytes extra = 9;
+
+    pub fn clear_extra(&mut self) {
+        self.extra.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_extra(&mut self, v: ::std::vec::Vec<u8>) {
+        self.extra = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_extra(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.extra
+    }
+
+    // Take field
+    pub fn take_extra(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.extra, ::std::vec::Vec::new())
+    }
+
+    pub fn get_extra(&self) -> &[u8] {
+        &self.extra
+    }
+
+    fn get_extra_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.extra
+    }
+
+    fn mut_extra_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.extra
+    }
 }
 
 impl ::protobuf::Message for BlockHeader {
@@ -588,6 +623,9 @@ impl ::protobuf::Message for BlockHeader {
                 8 => {
                     ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.proof)?;
                 },
+                9 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.extra)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -625,6 +663,9 @@ impl ::protobuf::Message for BlockHeader {
             let len = v.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         }
+        if !self.extra.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(9, &self.extra);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -657,6 +698,9 @@ impl ::protobuf::Message for BlockHeader {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         }
+        if !self.extra.is_empty() {
+            os.write_bytes(9, &self.extra)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -741,6 +785,11 @@ impl ::protobuf::MessageStatic for BlockHeader {
                     BlockHeader::get_proof_for_reflect,
                     BlockHeader::mut_proof_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "extra",
+                    BlockHeader::get_extra_for_reflect,
+                    BlockHeader::mut_extra_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<BlockHeader>(
                     "BlockHeader",
                     fields,
@@ -761,6 +810,7 @@ impl ::protobuf::Clear for BlockHeader {
         self.clear_receipts_root();
         self.clear_gas_used();
         self.clear_proof();
+        self.clear_extra();
         self.unknown_fields.clear();
     }
 }
@@ -777,6 +827,131 @@ impl ::protobuf::reflect::ProtobufValue for BlockHeader {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct BlockHeaderExtra {
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+// see codegen.rs for the explanation why impl Sync explicitly
+unsafe impl ::std::marker::Sync for BlockHeaderExtra {}
+
+impl BlockHeaderExtra {
+    pub fn new() -> BlockHeaderExtra {
+        ::std::default::Default::default()
+    }
+
+    pub fn default_instance() -> &'static BlockHeaderExtra {
+        static mut instance: ::protobuf::lazy::Lazy<BlockHeaderExtra> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const BlockHeaderExtra,
+        };
+        unsafe {
+            instance.get(BlockHeaderExtra::new)
+        }
+    }
+}
+
+impl ::protobuf::Message for BlockHeaderExtra {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        ::protobuf::MessageStatic::descriptor_static(None::<Self>)
+    }
+}
+
+impl ::protobuf::MessageStatic for BlockHeaderExtra {
+    fn new() -> BlockHeaderExtra {
+        BlockHeaderExtra::new()
+    }
+
+    fn descriptor_static(_: ::std::option::Option<BlockHeaderExtra>) -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let fields = ::std::vec::Vec::new();
+                ::protobuf::reflect::MessageDescriptor::new::<BlockHeaderExtra>(
+                    "BlockHeaderExtra",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+}
+
+impl ::protobuf::Clear for BlockHeaderExtra {
+    fn clear(&mut self) {
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for BlockHeaderExtra {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for BlockHeaderExtra {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
 #[derive(PartialEq,Clone,Default)]
 pub struct Status {
     // message fields
@@ -1003,6 +1178,11 @@ pub struct Transaction {
     pub nonce: ::std::string::String,
     pub valid_until_block: u64,
     pub data: ::std::vec::Vec<u8>,
+    pub chain_id: u32,
+    pub version: u32,
+    pub quota_price: u64,
+    pub bundle: ::std::vec::Vec<u8>,
+    pub bundle_size: u32,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -1150,6 +1330,132 @@ impl Transaction {
     fn mut_data_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
         &mut self.data
     }
+
+    // uint32 chain_id = 5;
+
+    pub fn clear_chain_id(&mut self) {
+        self.chain_id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_chain_id(&mut self, v: u32) {
+        self.chain_id = v;
+    }
+
+    pub fn get_chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    fn get_chain_id_for_reflect(&self) -> &u32 {
+        &self.chain_id
+    }
+
+    fn mut_chain_id_for_reflect(&mut self) -> &mut u32 {
+        &mut self.chain_id
+    }
+
+    // uint32 version = 6;
+
+    pub fn clear_version(&mut self) {
+        self.version = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_version(&mut self, v: u32) {
+        self.version = v;
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    fn get_version_for_reflect(&self) -> &u32 {
+        &self.version
+    }
+
+    fn mut_version_for_reflect(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+
+    // uint64 quota_price = 7;
+
+    pub fn clear_quota_price(&mut self) {
+        self.quota_price = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_quota_price(&mut self, v: u64) {
+        self.quota_price = v;
+    }
+
+    pub fn get_quota_price(&self) -> u64 {
+        self.quota_price
+    }
+
+    fn get_quota_price_for_reflect(&self) -> &u64 {
+        &self.quota_price
+    }
+
+    fn mut_quota_price_for_reflect(&mut self) -> &mut u64 {
+        &mut self.quota_price
+    }
+
+    // bytes bundle = 8;
+
+    pub fn clear_bundle(&mut self) {
+        self.bundle.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bundle(&mut self, v: ::std::vec::Vec<u8>) {
+        self.bundle = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bundle(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.bundle
+    }
+
+    // Take field
+    pub fn take_bundle(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.bundle, ::std::vec::Vec::new())
+    }
+
+    pub fn get_bundle(&self) -> &[u8] {
+        &self.bundle
+    }
+
+    fn get_bundle_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.bundle
+    }
+
+    fn mut_bundle_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.bundle
+    }
+
+    // uint32 bundle_size = 9;
+
+    pub fn clear_bundle_size(&mut self) {
+        self.bundle_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bundle_size(&mut self, v: u32) {
+        self.bundle_size = v;
+    }
+
+    pub fn get_bundle_size(&self) -> u32 {
+        self.bundle_size
+    }
+
+    fn get_bundle_size_for_reflect(&self) -> &u32 {
+        &self.bundle_size
+    }
+
+    fn mut_bundle_size_for_reflect(&mut self) -> &mut u32 {
+        &mut self.bundle_size
+    }
 }
 
 impl ::protobuf::Message for Transaction {
@@ -1177,6 +1483,37 @@ impl ::protobuf::Message for Transaction {
                 4 => {
                     ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.data)?;
                 },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.chain_id = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.version = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.quota_price = tmp;
+                },
+                8 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.bundle)?;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.bundle_size = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1201,6 +1538,21 @@ impl ::protobuf::Message for Transaction {
         if !self.data.is_empty() {
             my_size += ::protobuf::rt::bytes_size(4, &self.data);
         }
+        if self.chain_id != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.chain_id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.version != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.version, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.quota_price != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.quota_price, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.bundle.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(8, &self.bundle);
+        }
+        if self.bundle_size != 0 {
+            my_size += ::protobuf::rt::value_size(9, self.bundle_size, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1219,6 +1571,21 @@ impl ::protobuf::Message for Transaction {
         if !self.data.is_empty() {
             os.write_bytes(4, &self.data)?;
         }
+        if self.chain_id != 0 {
+            os.write_uint32(5, self.chain_id)?;
+        }
+        if self.version != 0 {
+            os.write_uint32(6, self.version)?;
+        }
+        if self.quota_price != 0 {
+            os.write_uint64(7, self.quota_price)?;
+        }
+        if !self.bundle.is_empty() {
+            os.write_bytes(8, &self.bundle)?;
+        }
+        if self.bundle_size != 0 {
+            os.write_uint32(9, self.bundle_size)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1283,6 +1650,31 @@ impl ::protobuf::MessageStatic for Transaction {
                     Transaction::get_data_for_reflect,
                     Transaction::mut_data_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "chain_id",
+                    Transaction::get_chain_id_for_reflect,
+                    Transaction::mut_chain_id_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "version",
+                    Transaction::get_version_for_reflect,
+                    Transaction::mut_version_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                    "quota_price",
+                    Transaction::get_quota_price_for_reflect,
+                    Transaction::mut_quota_price_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "bundle",
+                    Transaction::get_bundle_for_reflect,
+                    Transaction::mut_bundle_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "bundle_size",
+                    Transaction::get_bundle_size_for_reflect,
+                    Transaction::mut_bundle_size_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Transaction>(
                     "Transaction",
                     fields,
@@ -1299,6 +1691,11 @@ impl ::protobuf::Clear for Transaction {
         self.clear_nonce();
         self.clear_valid_until_block();
         self.clear_data();
+        self.clear_chain_id();
+        self.clear_version();
+        self.clear_quota_price();
+        self.clear_bundle();
+        self.clear_bundle_size();
         self.unknown_fields.clear();
     }
 }
@@ -1321,6 +1718,7 @@ pub struct UnverifiedTransaction {
     pub transaction: ::protobuf::SingularPtrField<Transaction>,
     pub signature: ::std::vec::Vec<u8>,
     pub crypto: Crypto,
+    pub fee_payer_signature: ::std::vec::Vec<u8>,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -1441,6 +1839,40 @@ impl UnverifiedTransaction {
     fn mut_crypto_for_reflect(&mut self) -> &mut Crypto {
         &mut self.crypto
     }
+
+    // bytes fee_payer_signature = 4;
+
+    pub fn clear_fee_payer_signature(&mut self) {
+        self.fee_payer_signature.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_fee_payer_signature(&mut self, v: ::std::vec::Vec<u8>) {
+        self.fee_payer_signature = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_fee_payer_signature(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.fee_payer_signature
+    }
+
+    // Take field
+    pub fn take_fee_payer_signature(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.fee_payer_signature, ::std::vec::Vec::new())
+    }
+
+    pub fn get_fee_payer_signature(&self) -> &[u8] {
+        &self.fee_payer_signature
+    }
+
+    fn get_fee_payer_signature_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.fee_payer_signature
+    }
+
+    fn mut_fee_payer_signature_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.fee_payer_signature
+    }
 }
 
 impl ::protobuf::Message for UnverifiedTransaction {
@@ -1470,6 +1902,9 @@ impl ::protobuf::Message for UnverifiedTransaction {
                     let tmp = is.read_enum()?;
                     self.crypto = tmp;
                 },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.fee_payer_signature)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1492,6 +1927,9 @@ impl ::protobuf::Message for UnverifiedTransaction {
         if self.crypto != Crypto::SECP {
             my_size += ::protobuf::rt::enum_size(3, self.crypto);
         }
+        if !self.fee_payer_signature.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.fee_payer_signature);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1509,6 +1947,9 @@ impl ::protobuf::Message for UnverifiedTransaction {
         if self.crypto != Crypto::SECP {
             os.write_enum(3, self.crypto.value())?;
         }
+        if !self.fee_payer_signature.is_empty() {
+            os.write_bytes(4, &self.fee_payer_signature)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1568,6 +2009,11 @@ impl ::protobuf::MessageStatic for UnverifiedTransaction {
                     UnverifiedTransaction::get_crypto_for_reflect,
                     UnverifiedTransaction::mut_crypto_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "fee_payer_signature",
+                    UnverifiedTransaction::get_fee_payer_signature_for_reflect,
+                    UnverifiedTransaction::mut_fee_payer_signature_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<UnverifiedTransaction>(
                     "UnverifiedTransaction",
                     fields,
@@ -1583,6 +2029,7 @@ impl ::protobuf::Clear for UnverifiedTransaction {
         self.clear_transaction();
         self.clear_signature();
         self.clear_crypto();
+        self.clear_fee_payer_signature();
         self.unknown_fields.clear();
     }
 }
@@ -1605,6 +2052,7 @@ pub struct SignedTransaction {
     pub transaction_with_sig: ::protobuf::SingularPtrField<UnverifiedTransaction>,
     pub tx_hash: ::std::vec::Vec<u8>,
     pub signer: ::std::vec::Vec<u8>,
+    pub fee_payer: ::std::vec::Vec<u8>,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -1736,6 +2184,40 @@ impl SignedTransaction {
     fn mut_signer_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
         &mut self.signer
     }
+
+    // bytes fee_payer = 4;
+
+    pub fn clear_fee_payer(&mut self) {
+        self.fee_payer.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_fee_payer(&mut self, v: ::std::vec::Vec<u8>) {
+        self.fee_payer = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_fee_payer(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.fee_payer
+    }
+
+    // Take field
+    pub fn take_fee_payer(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.fee_payer, ::std::vec::Vec::new())
+    }
+
+    pub fn get_fee_payer(&self) -> &[u8] {
+        &self.fee_payer
+    }
+
+    fn get_fee_payer_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.fee_payer
+    }
+
+    fn mut_fee_payer_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.fee_payer
+    }
 }
 
 impl ::protobuf::Message for SignedTransaction {
@@ -1761,6 +2243,9 @@ impl ::protobuf::Message for SignedTransaction {
                 3 => {
                     ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.signer)?;
                 },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.fee_payer)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1783,6 +2268,9 @@ impl ::protobuf::Message for SignedTransaction {
         if !self.signer.is_empty() {
             my_size += ::protobuf::rt::bytes_size(3, &self.signer);
         }
+        if !self.fee_payer.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.fee_payer);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1800,6 +2288,9 @@ impl ::protobuf::Message for SignedTransaction {
         if !self.signer.is_empty() {
             os.write_bytes(3, &self.signer)?;
         }
+        if !self.fee_payer.is_empty() {
+            os.write_bytes(4, &self.fee_payer)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1859,6 +2350,11 @@ impl ::protobuf::MessageStatic for SignedTransaction {
                     SignedTransaction::get_signer_for_reflect,
                     SignedTransaction::mut_signer_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "fee_payer",
+                    SignedTransaction::get_fee_payer_for_reflect,
+                    SignedTransaction::mut_fee_payer_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<SignedTransaction>(
                     "SignedTransaction",
                     fields,
@@ -1874,6 +2370,7 @@ impl ::protobuf::Clear for SignedTransaction {
         self.clear_transaction_with_sig();
         self.clear_tx_hash();
         self.clear_signer();
+        self.clear_fee_payer();
         self.unknown_fields.clear();
     }
 }
@@ -2714,53 +3211,60 @@ impl ::protobuf::reflect::ProtobufValue for Crypto {
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x10blockchain.proto\"A\n\x05Proof\x12\x18\n\x07content\x18\x01\x20\
     \x01(\x0cR\x07content\x12\x1e\n\x04type\x18\x02\x20\x01(\x0e2\n.ProofTyp\
-    eR\x04type\"\x89\x02\n\x0bBlockHeader\x12\x1a\n\x08prevhash\x18\x01\x20\
+    eR\x04type\"\x9f\x02\n\x0bBlockHeader\x12\x1a\n\x08prevhash\x18\x01\x20\
     \x01(\x0cR\x08prevhash\x12\x1c\n\ttimestamp\x18\x02\x20\x01(\x04R\ttimes\
     tamp\x12\x16\n\x06height\x18\x03\x20\x01(\x04R\x06height\x12\x1d\n\nstat\
     e_root\x18\x04\x20\x01(\x0cR\tstateRoot\x12+\n\x11transactions_root\x18\
     \x05\x20\x01(\x0cR\x10transactionsRoot\x12#\n\rreceipts_root\x18\x06\x20\
     \x01(\x0cR\x0creceiptsRoot\x12\x19\n\x08gas_used\x18\x07\x20\x01(\x04R\
     \x07gasUsed\x12\x1c\n\x05proof\x18\x08\x20\x01(\x0b2\x06.ProofR\x05proof\
-    \"4\n\x06Status\x12\x12\n\x04hash\x18\x01\x20\x01(\x0cR\x04hash\x12\x16\
-    \n\x06height\x18\x02\x20\x01(\x04R\x06height\"s\n\x0bTransaction\x12\x0e\
-    \n\x02to\x18\x01\x20\x01(\tR\x02to\x12\x14\n\x05nonce\x18\x02\x20\x01(\t\
-    R\x05nonce\x12*\n\x11valid_until_block\x18\x03\x20\x01(\x04R\x0fvalidUnt\
-    ilBlock\x12\x12\n\x04data\x18\x04\x20\x01(\x0cR\x04data\"\x86\x01\n\x15U\
+    \x12\x14\n\x05extra\x18\t\x20\x01(\x0cR\x05extra\"\x12\n\x10BlockHeaderE\
+    xtra\"4\n\x06Status\x12\x12\n\x04hash\x18\x01\x20\x01(\x0cR\x04hash\x12\
+    \x16\n\x06height\x18\x02\x20\x01(\x04R\x06height\"\x82\x02\n\x0bTransact\
+    ion\x12\x0e\n\x02to\x18\x01\x20\x01(\tR\x02to\x12\x14\n\x05nonce\x18\x02\
+    \x20\x01(\tR\x05nonce\x12*\n\x11valid_until_block\x18\x03\x20\x01(\x04R\
+    \x0fvalidUntilBlock\x12\x12\n\x04data\x18\x04\x20\x01(\x0cR\x04data\x12\
+    \x19\n\x08chain_id\x18\x05\x20\x01(\rR\x07chainId\x12\x18\n\x07version\
+    \x18\x06\x20\x01(\rR\x07version\x12\x1f\n\x0bquota_price\x18\x07\x20\x01\
+    (\x04R\nquotaPrice\x12\x16\n\x06bundle\x18\x08\x20\x01(\x0cR\x06bundle\
+    \x12\x1f\n\x0bbundle_size\x18\t\x20\x01(\rR\nbundleSize\"\xb6\x01\n\x15U\
     nverifiedTransaction\x12.\n\x0btransaction\x18\x01\x20\x01(\x0b2\x0c.Tra\
     nsactionR\x0btransaction\x12\x1c\n\tsignature\x18\x02\x20\x01(\x0cR\tsig\
-    nature\x12\x1f\n\x06crypto\x18\x03\x20\x01(\x0e2\x07.CryptoR\x06crypto\"\
-    \x8e\x01\n\x11SignedTransaction\x12H\n\x14transaction_with_sig\x18\x01\
-    \x20\x01(\x0b2\x16.UnverifiedTransactionR\x12transactionWithSig\x12\x17\
-    \n\x07tx_hash\x18\x02\x20\x01(\x0cR\x06txHash\x12\x16\n\x06signer\x18\
-    \x03\x20\x01(\x0cR\x06signer\"8\n\nTxResponse\x12\x12\n\x04hash\x18\x01\
-    \x20\x01(\x0cR\x04hash\x12\x16\n\x06result\x18\x02\x20\x01(\x0cR\x06resu\
-    lt\"C\n\tBlockBody\x126\n\x0ctransactions\x18\x01\x20\x03(\x0b2\x12.Sign\
-    edTransactionR\x0ctransactions\"g\n\x05Block\x12\x18\n\x07version\x18\
-    \x01\x20\x01(\rR\x07version\x12$\n\x06header\x18\x02\x20\x01(\x0b2\x0c.B\
-    lockHeaderR\x06header\x12\x1e\n\x04body\x18\x03\x20\x01(\x0b2\n.BlockBod\
-    yR\x04body*9\n\tProofType\x12\x12\n\x0eAuthorityRound\x10\0\x12\x08\n\
-    \x04Raft\x10\x01\x12\x0e\n\nTendermint\x10\x02*\x1b\n\x06Crypto\x12\x08\
-    \n\x04SECP\x10\0\x12\x07\n\x03SM2\x10\x01J\xcc\x13\n\x06\x12\x04\0\0D\
-    \x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\n\n\x02\x05\0\x12\x04\x02\0\x06\
-    \x01\n\n\n\x03\x05\0\x01\x12\x03\x02\x05\x0e\n\x0b\n\x04\x05\0\x02\0\x12\
-    \x03\x03\x04\x17\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\x03\x04\x12\n\x0c\n\
-    \x05\x05\0\x02\0\x02\x12\x03\x03\x15\x16\n\x0b\n\x04\x05\0\x02\x01\x12\
-    \x03\x04\x04\r\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\x04\x04\x08\n\x0c\n\
-    \x05\x05\0\x02\x01\x02\x12\x03\x04\x0b\x0c\n\x0b\n\x04\x05\0\x02\x02\x12\
-    \x03\x05\x04\x13\n\x0c\n\x05\x05\0\x02\x02\x01\x12\x03\x05\x04\x0e\n\x0c\
-    \n\x05\x05\0\x02\x02\x02\x12\x03\x05\x11\x12\n\n\n\x02\x04\0\x12\x04\x08\
-    \0\x0b\x01\n\n\n\x03\x04\0\x01\x12\x03\x08\x08\r\n\x0b\n\x04\x04\0\x02\0\
-    \x12\x03\t\x04\x16\n\r\n\x05\x04\0\x02\0\x04\x12\x04\t\x04\x08\x0f\n\x0c\
-    \n\x05\x04\0\x02\0\x05\x12\x03\t\x04\t\n\x0c\n\x05\x04\0\x02\0\x01\x12\
-    \x03\t\n\x11\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\t\x14\x15\n\x0b\n\x04\
-    \x04\0\x02\x01\x12\x03\n\x04\x17\n\r\n\x05\x04\0\x02\x01\x04\x12\x04\n\
-    \x04\t\x16\n\x0c\n\x05\x04\0\x02\x01\x06\x12\x03\n\x04\r\n\x0c\n\x05\x04\
-    \0\x02\x01\x01\x12\x03\n\x0e\x12\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\n\
-    \x15\x16\n\n\n\x02\x04\x01\x12\x04\r\0\x16\x01\n\n\n\x03\x04\x01\x01\x12\
-    \x03\r\x08\x13\n\x0b\n\x04\x04\x01\x02\0\x12\x03\x0e\x04\x17\n\r\n\x05\
-    \x04\x01\x02\0\x04\x12\x04\x0e\x04\r\x15\n\x0c\n\x05\x04\x01\x02\0\x05\
-    \x12\x03\x0e\x04\t\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x03\x0e\n\x12\n\x0c\
-    \n\x05\x04\x01\x02\0\x03\x12\x03\x0e\x15\x16\n\x0b\n\x04\x04\x01\x02\x01\
+    nature\x12\x1f\n\x06crypto\x18\x03\x20\x01(\x0e2\x07.CryptoR\x06crypto\
+    \x12.\n\x13fee_payer_signature\x18\x04\x20\x01(\x0cR\x11feePayerSignatur\
+    e\"\xab\x01\n\x11SignedTransaction\x12H\n\x14transaction_with_sig\x18\
+    \x01\x20\x01(\x0b2\x16.UnverifiedTransactionR\x12transactionWithSig\x12\
+    \x17\n\x07tx_hash\x18\x02\x20\x01(\x0cR\x06txHash\x12\x16\n\x06signer\
+    \x18\x03\x20\x01(\x0cR\x06signer\x12\x1b\n\tfee_payer\x18\x04\x20\x01(\
+    \x0cR\x08feePayer\"8\n\nTxResponse\x12\x12\n\x04hash\x18\x01\x20\x01(\
+    \x0cR\x04hash\x12\x16\n\x06result\x18\x02\x20\x01(\x0cR\x06result\"C\n\t\
+    BlockBody\x126\n\x0ctransactions\x18\x01\x20\x03(\x0b2\x12.SignedTransac\
+    tionR\x0ctransactions\"g\n\x05Block\x12\x18\n\x07version\x18\x01\x20\x01\
+    (\rR\x07version\x12$\n\x06header\x18\x02\x20\x01(\x0b2\x0c.BlockHeaderR\
+    \x06header\x12\x1e\n\x04body\x18\x03\x20\x01(\x0b2\n.BlockBodyR\x04body*\
+    9\n\tProofType\x12\x12\n\x0eAuthorityRound\x10\0\x12\x08\n\x04Raft\x10\
+    \x01\x12\x0e\n\nTendermint\x10\x02*\x1b\n\x06Crypto\x12\x08\n\x04SECP\
+    \x10\0\x12\x07\n\x03SM2\x10\x01J\xcc\x13\n\x06\x12\x04\0\0D\x01\n\x08\n\
+    \x01\x0c\x12\x03\0\0\x12\n\n\n\x02\x05\0\x12\x04\x02\0\x06\x01\n\n\n\x03\
+    \x05\0\x01\x12\x03\x02\x05\x0e\n\x0b\n\x04\x05\0\x02\0\x12\x03\x03\x04\
+    \x17\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\x03\x04\x12\n\x0c\n\x05\x05\0\
+    \x02\0\x02\x12\x03\x03\x15\x16\n\x0b\n\x04\x05\0\x02\x01\x12\x03\x04\x04\
+    \r\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\x04\x04\x08\n\x0c\n\x05\x05\0\
+    \x02\x01\x02\x12\x03\x04\x0b\x0c\n\x0b\n\x04\x05\0\x02\x02\x12\x03\x05\
+    \x04\x13\n\x0c\n\x05\x05\0\x02\x02\x01\x12\x03\x05\x04\x0e\n\x0c\n\x05\
+    \x05\0\x02\x02\x02\x12\x03\x05\x11\x12\n\n\n\x02\x04\0\x12\x04\x08\0\x0b\
+    \x01\n\n\n\x03\x04\0\x01\x12\x03\x08\x08\r\n\x0b\n\x04\x04\0\x02\0\x12\
+    \x03\t\x04\x16\n\r\n\x05\x04\0\x02\0\x04\x12\x04\t\x04\x08\x0f\n\x0c\n\
+    \x05\x04\0\x02\0\x05\x12\x03\t\x04\t\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\
+    \t\n\x11\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\t\x14\x15\n\x0b\n\x04\x04\0\
+    \x02\x01\x12\x03\n\x04\x17\n\r\n\x05\x04\0\x02\x01\x04\x12\x04\n\x04\t\
+    \x16\n\x0c\n\x05\x04\0\x02\x01\x06\x12\x03\n\x04\r\n\x0c\n\x05\x04\0\x02\
+    \x01\x01\x12\x03\n\x0e\x12\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\n\x15\
+    \x16\n\n\n\x02\x04\x01\x12\x04\r\0\x16\x01\n\n\n\x03\x04\x01\x01\x12\x03\
+    \r\x08\x13\n\x0b\n\x04\x04\x01\x02\0\x12\x03\x0e\x04\x17\n\r\n\x05\x04\
+    \x01\x02\0\x04\x12\x04\x0e\x04\r\x15\n\x0c\n\x05\x04\x01\x02\0\x05\x12\
+    \x03\x0e\x04\t\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x03\x0e\n\x12\n\x0c\n\
+    \x05\x04\x01\x02\0\x03\x12\x03\x0e\x15\x16\n\x0b\n\x04\x04\x01\x02\x01\
     \x12\x03\x0f\x04\x19\n\r\n\x05\x04\x01\x02\x01\x04\x12\x04\x0f\x04\x0e\
     \x17\n\x0c\n\x05\x04\x01\x02\x01\x05\x12\x03\x0f\x04\n\n\x0c\n\x05\x04\
     \x01\x02\x01\x01\x12\x03\x0f\x0b\x14\n\x0c\n\x05\x04\x01\x02\x01\x03\x12\
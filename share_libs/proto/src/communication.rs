@@ -46,6 +46,9 @@ pub struct Message {
     pub origin: u32,
     pub operate: OperateType,
     pub content: ::std::vec::Vec<u8>,
+    pub version: u32,
+    pub trace_id: ::std::vec::Vec<u8>,
+    pub compression: u32,
     // special fields
     unknown_fields: ::protobuf::UnknownFields,
     cached_size: ::protobuf::CachedSize,
@@ -194,6 +197,86 @@ impl Message {
     fn mut_content_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
         &mut self.content
     }
+
+    // uint32 version = 6;
+
+    pub fn clear_version(&mut self) {
+        self.version = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_version(&mut self, v: u32) {
+        self.version = v;
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    fn get_version_for_reflect(&self) -> &u32 {
+        &self.version
+    }
+
+    fn mut_version_for_reflect(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+
+    // bytes trace_id = 7;
+
+    pub fn clear_trace_id(&mut self) {
+        self.trace_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_trace_id(&mut self, v: ::std::vec::Vec<u8>) {
+        self.trace_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_trace_id(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.trace_id
+    }
+
+    // Take field
+    pub fn take_trace_id(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.trace_id, ::std::vec::Vec::new())
+    }
+
+    pub fn get_trace_id(&self) -> &[u8] {
+        &self.trace_id
+    }
+
+    fn get_trace_id_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.trace_id
+    }
+
+    fn mut_trace_id_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.trace_id
+    }
+
+    // uint32 compression = 8;
+
+    pub fn clear_compression(&mut self) {
+        self.compression = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_compression(&mut self, v: u32) {
+        self.compression = v;
+    }
+
+    pub fn get_compression(&self) -> u32 {
+        self.compression
+    }
+
+    fn get_compression_for_reflect(&self) -> &u32 {
+        &self.compression
+    }
+
+    fn mut_compression_for_reflect(&mut self) -> &mut u32 {
+        &mut self.compression
+    }
 }
 
 impl ::protobuf::Message for Message {
@@ -236,6 +319,23 @@ impl ::protobuf::Message for Message {
                 5 => {
                     ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.content)?;
                 },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.version = tmp;
+                },
+                7 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.trace_id)?;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.compression = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -263,6 +363,15 @@ impl ::protobuf::Message for Message {
         if !self.content.is_empty() {
             my_size += ::protobuf::rt::bytes_size(5, &self.content);
         }
+        if self.version != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.version, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.trace_id.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(7, &self.trace_id);
+        }
+        if self.compression != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.compression, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -284,6 +393,15 @@ impl ::protobuf::Message for Message {
         if !self.content.is_empty() {
             os.write_bytes(5, &self.content)?;
         }
+        if self.version != 0 {
+            os.write_uint32(6, self.version)?;
+        }
+        if !self.trace_id.is_empty() {
+            os.write_bytes(7, &self.trace_id)?;
+        }
+        if self.compression != 0 {
+            os.write_uint32(8, self.compression)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -353,6 +471,21 @@ impl ::protobuf::MessageStatic for Message {
                     Message::get_content_for_reflect,
                     Message::mut_content_for_reflect,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "version",
+                    Message::get_version_for_reflect,
+                    Message::mut_version_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                    "trace_id",
+                    Message::get_trace_id_for_reflect,
+                    Message::mut_trace_id_for_reflect,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "compression",
+                    Message::get_compression_for_reflect,
+                    Message::mut_compression_for_reflect,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Message>(
                     "Message",
                     fields,
@@ -370,6 +503,9 @@ impl ::protobuf::Clear for Message {
         self.clear_origin();
         self.clear_operate();
         self.clear_content();
+        self.clear_version();
+        self.clear_trace_id();
+        self.clear_compression();
         self.unknown_fields.clear();
     }
 }
@@ -521,62 +657,64 @@ impl ::protobuf::reflect::ProtobufValue for OperateType {
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\x13communication.proto\"\x98\x01\n\x07Message\x12\x15\n\x06cmd_id\x18\
+    \n\x13communication.proto\"\xef\x01\n\x07Message\x12\x15\n\x06cmd_id\x18\
     \x01\x20\x01(\rR\x05cmdId\x12\x1c\n\x04type\x18\x02\x20\x01(\x0e2\x08.Ms\
     gTypeR\x04type\x12\x16\n\x06origin\x18\x03\x20\x01(\rR\x06origin\x12&\n\
     \x07operate\x18\x04\x20\x01(\x0e2\x0c.OperateTypeR\x07operate\x12\x18\n\
-    \x07content\x18\x05\x20\x01(\x0cR\x07content*s\n\x07MsgType\x12\x0b\n\
-    \x07REQUEST\x10\0\x12\n\n\x06HEADER\x10\x01\x12\x08\n\x04BODY\x10\x02\
-    \x12\t\n\x05BLOCK\x10\x03\x12\x06\n\x02TX\x10\x04\x12\n\n\x06STATUS\x10\
-    \x05\x12\x07\n\x03MSG\x10\x06\x12\x0c\n\x08RESPONSE\x10\x07\x12\x0f\n\
-    \x0bTX_RESPONSE\x10\x08*6\n\x0bOperateType\x12\r\n\tBROADCAST\x10\0\x12\
-    \n\n\x06SINGLE\x10\x01\x12\x0c\n\x08SUBTRACT\x10\x02J\xa4\x07\n\x06\x12\
-    \x04\0\0\x1a\x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\n\n\x02\x05\0\x12\x04\
-    \x02\0\x0c\x01\n\n\n\x03\x05\0\x01\x12\x03\x02\x05\x0c\n\x0b\n\x04\x05\0\
-    \x02\0\x12\x03\x03\x04\x10\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\x03\x04\
-    \x0b\n\x0c\n\x05\x05\0\x02\0\x02\x12\x03\x03\x0e\x0f\n\x0b\n\x04\x05\0\
-    \x02\x01\x12\x03\x04\x04\x0f\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\x04\
-    \x04\n\n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\x04\r\x0e\n\x0b\n\x04\x05\0\
-    \x02\x02\x12\x03\x05\x04\r\n\x0c\n\x05\x05\0\x02\x02\x01\x12\x03\x05\x04\
-    \x08\n\x0c\n\x05\x05\0\x02\x02\x02\x12\x03\x05\x0b\x0c\n\x0b\n\x04\x05\0\
-    \x02\x03\x12\x03\x06\x04\x0e\n\x0c\n\x05\x05\0\x02\x03\x01\x12\x03\x06\
-    \x04\t\n\x0c\n\x05\x05\0\x02\x03\x02\x12\x03\x06\x0c\r\n\x0b\n\x04\x05\0\
-    \x02\x04\x12\x03\x07\x04\x0b\n\x0c\n\x05\x05\0\x02\x04\x01\x12\x03\x07\
-    \x04\x06\n\x0c\n\x05\x05\0\x02\x04\x02\x12\x03\x07\t\n\n\x0b\n\x04\x05\0\
-    \x02\x05\x12\x03\x08\x04\x0f\n\x0c\n\x05\x05\0\x02\x05\x01\x12\x03\x08\
-    \x04\n\n\x0c\n\x05\x05\0\x02\x05\x02\x12\x03\x08\r\x0e\n\x0b\n\x04\x05\0\
-    \x02\x06\x12\x03\t\x04\x0c\n\x0c\n\x05\x05\0\x02\x06\x01\x12\x03\t\x04\
-    \x07\n\x0c\n\x05\x05\0\x02\x06\x02\x12\x03\t\n\x0b\n\x0b\n\x04\x05\0\x02\
-    \x07\x12\x03\n\x04\x11\n\x0c\n\x05\x05\0\x02\x07\x01\x12\x03\n\x04\x0c\n\
-    \x0c\n\x05\x05\0\x02\x07\x02\x12\x03\n\x0f\x10\n\x0b\n\x04\x05\0\x02\x08\
-    \x12\x03\x0b\x04\x14\n\x0c\n\x05\x05\0\x02\x08\x01\x12\x03\x0b\x04\x0f\n\
-    \x0c\n\x05\x05\0\x02\x08\x02\x12\x03\x0b\x12\x13\n\n\n\x02\x05\x01\x12\
-    \x04\x0e\0\x12\x01\n\n\n\x03\x05\x01\x01\x12\x03\x0e\x05\x10\n\x0b\n\x04\
-    \x05\x01\x02\0\x12\x03\x0f\x04\x12\n\x0c\n\x05\x05\x01\x02\0\x01\x12\x03\
-    \x0f\x04\r\n\x0c\n\x05\x05\x01\x02\0\x02\x12\x03\x0f\x10\x11\n\x0b\n\x04\
-    \x05\x01\x02\x01\x12\x03\x10\x04\x0f\n\x0c\n\x05\x05\x01\x02\x01\x01\x12\
-    \x03\x10\x04\n\n\x0c\n\x05\x05\x01\x02\x01\x02\x12\x03\x10\r\x0e\n\x0b\n\
-    \x04\x05\x01\x02\x02\x12\x03\x11\x04\x11\n\x0c\n\x05\x05\x01\x02\x02\x01\
-    \x12\x03\x11\x04\x0c\n\x0c\n\x05\x05\x01\x02\x02\x02\x12\x03\x11\x0f\x10\
-    \n\n\n\x02\x04\0\x12\x04\x14\0\x1a\x01\n\n\n\x03\x04\0\x01\x12\x03\x14\
-    \x08\x0f\n\x0b\n\x04\x04\0\x02\0\x12\x03\x15\x04\x16\n\r\n\x05\x04\0\x02\
-    \0\x04\x12\x04\x15\x04\x14\x11\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\x15\
-    \x04\n\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x15\x0b\x11\n\x0c\n\x05\x04\0\
-    \x02\0\x03\x12\x03\x15\x14\x15\n\x0b\n\x04\x04\0\x02\x01\x12\x03\x16\x04\
-    \x15\n\r\n\x05\x04\0\x02\x01\x04\x12\x04\x16\x04\x15\x16\n\x0c\n\x05\x04\
-    \0\x02\x01\x06\x12\x03\x16\x04\x0b\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\
-    \x16\x0c\x10\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x16\x13\x14\n\x0b\n\
-    \x04\x04\0\x02\x02\x12\x03\x17\x04\x16\n\r\n\x05\x04\0\x02\x02\x04\x12\
-    \x04\x17\x04\x16\x15\n\x0c\n\x05\x04\0\x02\x02\x05\x12\x03\x17\x04\n\n\
-    \x0c\n\x05\x04\0\x02\x02\x01\x12\x03\x17\x0b\x11\n\x0c\n\x05\x04\0\x02\
-    \x02\x03\x12\x03\x17\x14\x15\n\x0b\n\x04\x04\0\x02\x03\x12\x03\x18\x04\
-    \x1c\n\r\n\x05\x04\0\x02\x03\x04\x12\x04\x18\x04\x17\x16\n\x0c\n\x05\x04\
-    \0\x02\x03\x06\x12\x03\x18\x04\x0f\n\x0c\n\x05\x04\0\x02\x03\x01\x12\x03\
-    \x18\x10\x17\n\x0c\n\x05\x04\0\x02\x03\x03\x12\x03\x18\x1a\x1b\n\x0b\n\
-    \x04\x04\0\x02\x04\x12\x03\x19\x04\x16\n\r\n\x05\x04\0\x02\x04\x04\x12\
-    \x04\x19\x04\x18\x1c\n\x0c\n\x05\x04\0\x02\x04\x05\x12\x03\x19\x04\t\n\
-    \x0c\n\x05\x04\0\x02\x04\x01\x12\x03\x19\n\x11\n\x0c\n\x05\x04\0\x02\x04\
-    \x03\x12\x03\x19\x14\x15b\x06proto3\
+    \x07content\x18\x05\x20\x01(\x0cR\x07content\x12\x18\n\x07version\x18\
+    \x06\x20\x01(\rR\x07version\x12\x19\n\x08trace_id\x18\x07\x20\x01(\x0cR\
+    \x07traceId\x12\x20\n\x0bcompression\x18\x08\x20\x01(\rR\x0bcompression*\
+    s\n\x07MsgType\x12\x0b\n\x07REQUEST\x10\0\x12\n\n\x06HEADER\x10\x01\x12\
+    \x08\n\x04BODY\x10\x02\x12\t\n\x05BLOCK\x10\x03\x12\x06\n\x02TX\x10\x04\
+    \x12\n\n\x06STATUS\x10\x05\x12\x07\n\x03MSG\x10\x06\x12\x0c\n\x08RESPONS\
+    E\x10\x07\x12\x0f\n\x0bTX_RESPONSE\x10\x08*6\n\x0bOperateType\x12\r\n\tB\
+    ROADCAST\x10\0\x12\n\n\x06SINGLE\x10\x01\x12\x0c\n\x08SUBTRACT\x10\x02J\
+    \xa4\x07\n\x06\x12\x04\0\0\x1a\x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\n\n\
+    \x02\x05\0\x12\x04\x02\0\x0c\x01\n\n\n\x03\x05\0\x01\x12\x03\x02\x05\x0c\
+    \n\x0b\n\x04\x05\0\x02\0\x12\x03\x03\x04\x10\n\x0c\n\x05\x05\0\x02\0\x01\
+    \x12\x03\x03\x04\x0b\n\x0c\n\x05\x05\0\x02\0\x02\x12\x03\x03\x0e\x0f\n\
+    \x0b\n\x04\x05\0\x02\x01\x12\x03\x04\x04\x0f\n\x0c\n\x05\x05\0\x02\x01\
+    \x01\x12\x03\x04\x04\n\n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\x04\r\x0e\n\
+    \x0b\n\x04\x05\0\x02\x02\x12\x03\x05\x04\r\n\x0c\n\x05\x05\0\x02\x02\x01\
+    \x12\x03\x05\x04\x08\n\x0c\n\x05\x05\0\x02\x02\x02\x12\x03\x05\x0b\x0c\n\
+    \x0b\n\x04\x05\0\x02\x03\x12\x03\x06\x04\x0e\n\x0c\n\x05\x05\0\x02\x03\
+    \x01\x12\x03\x06\x04\t\n\x0c\n\x05\x05\0\x02\x03\x02\x12\x03\x06\x0c\r\n\
+    \x0b\n\x04\x05\0\x02\x04\x12\x03\x07\x04\x0b\n\x0c\n\x05\x05\0\x02\x04\
+    \x01\x12\x03\x07\x04\x06\n\x0c\n\x05\x05\0\x02\x04\x02\x12\x03\x07\t\n\n\
+    \x0b\n\x04\x05\0\x02\x05\x12\x03\x08\x04\x0f\n\x0c\n\x05\x05\0\x02\x05\
+    \x01\x12\x03\x08\x04\n\n\x0c\n\x05\x05\0\x02\x05\x02\x12\x03\x08\r\x0e\n\
+    \x0b\n\x04\x05\0\x02\x06\x12\x03\t\x04\x0c\n\x0c\n\x05\x05\0\x02\x06\x01\
+    \x12\x03\t\x04\x07\n\x0c\n\x05\x05\0\x02\x06\x02\x12\x03\t\n\x0b\n\x0b\n\
+    \x04\x05\0\x02\x07\x12\x03\n\x04\x11\n\x0c\n\x05\x05\0\x02\x07\x01\x12\
+    \x03\n\x04\x0c\n\x0c\n\x05\x05\0\x02\x07\x02\x12\x03\n\x0f\x10\n\x0b\n\
+    \x04\x05\0\x02\x08\x12\x03\x0b\x04\x14\n\x0c\n\x05\x05\0\x02\x08\x01\x12\
+    \x03\x0b\x04\x0f\n\x0c\n\x05\x05\0\x02\x08\x02\x12\x03\x0b\x12\x13\n\n\n\
+    \x02\x05\x01\x12\x04\x0e\0\x12\x01\n\n\n\x03\x05\x01\x01\x12\x03\x0e\x05\
+    \x10\n\x0b\n\x04\x05\x01\x02\0\x12\x03\x0f\x04\x12\n\x0c\n\x05\x05\x01\
+    \x02\0\x01\x12\x03\x0f\x04\r\n\x0c\n\x05\x05\x01\x02\0\x02\x12\x03\x0f\
+    \x10\x11\n\x0b\n\x04\x05\x01\x02\x01\x12\x03\x10\x04\x0f\n\x0c\n\x05\x05\
+    \x01\x02\x01\x01\x12\x03\x10\x04\n\n\x0c\n\x05\x05\x01\x02\x01\x02\x12\
+    \x03\x10\r\x0e\n\x0b\n\x04\x05\x01\x02\x02\x12\x03\x11\x04\x11\n\x0c\n\
+    \x05\x05\x01\x02\x02\x01\x12\x03\x11\x04\x0c\n\x0c\n\x05\x05\x01\x02\x02\
+    \x02\x12\x03\x11\x0f\x10\n\n\n\x02\x04\0\x12\x04\x14\0\x1a\x01\n\n\n\x03\
+    \x04\0\x01\x12\x03\x14\x08\x0f\n\x0b\n\x04\x04\0\x02\0\x12\x03\x15\x04\
+    \x16\n\r\n\x05\x04\0\x02\0\x04\x12\x04\x15\x04\x14\x11\n\x0c\n\x05\x04\0\
+    \x02\0\x05\x12\x03\x15\x04\n\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x15\x0b\
+    \x11\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\x15\x14\x15\n\x0b\n\x04\x04\0\
+    \x02\x01\x12\x03\x16\x04\x15\n\r\n\x05\x04\0\x02\x01\x04\x12\x04\x16\x04\
+    \x15\x16\n\x0c\n\x05\x04\0\x02\x01\x06\x12\x03\x16\x04\x0b\n\x0c\n\x05\
+    \x04\0\x02\x01\x01\x12\x03\x16\x0c\x10\n\x0c\n\x05\x04\0\x02\x01\x03\x12\
+    \x03\x16\x13\x14\n\x0b\n\x04\x04\0\x02\x02\x12\x03\x17\x04\x16\n\r\n\x05\
+    \x04\0\x02\x02\x04\x12\x04\x17\x04\x16\x15\n\x0c\n\x05\x04\0\x02\x02\x05\
+    \x12\x03\x17\x04\n\n\x0c\n\x05\x04\0\x02\x02\x01\x12\x03\x17\x0b\x11\n\
+    \x0c\n\x05\x04\0\x02\x02\x03\x12\x03\x17\x14\x15\n\x0b\n\x04\x04\0\x02\
+    \x03\x12\x03\x18\x04\x1c\n\r\n\x05\x04\0\x02\x03\x04\x12\x04\x18\x04\x17\
+    \x16\n\x0c\n\x05\x04\0\x02\x03\x06\x12\x03\x18\x04\x0f\n\x0c\n\x05\x04\0\
+    \x02\x03\x01\x12\x03\x18\x10\x17\n\x0c\n\x05\x04\0\x02\x03\x03\x12\x03\
+    \x18\x1a\x1b\n\x0b\n\x04\x04\0\x02\x04\x12\x03\x19\x04\x16\n\r\n\x05\x04\
+    \0\x02\x04\x04\x12\x04\x19\x04\x18\x1c\n\x0c\n\x05\x04\0\x02\x04\x05\x12\
+    \x03\x19\x04\t\n\x0c\n\x05\x04\0\x02\x04\x01\x12\x03\x19\n\x11\n\x0c\n\
+    \x05\x04\0\x02\x04\x03\x12\x03\x19\x14\x15b\x06proto3\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
@@ -0,0 +1,127 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handshake of the modules of a node at startup, so a module refuses to run
+//! beside one whose messages it can not decode, rather than failing on them
+//! later. Each module publishes its hello under `<module>.hello`, with the
+//! version of its messages and the versions it requires of the others, and
+//! the modules running answer with theirs. The hello is plain json, so it is
+//! read the same by all the versions.
+
+use super::MESSAGE_VERSION;
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use serde_json;
+use std::collections::HashMap;
+use std::process;
+use std::sync::mpsc::{channel, sync_channel};
+use std::thread;
+
+/// The versions of a module.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Hello {
+    pub module: String,
+    /// Version of the messages the module writes, and the newest it decodes.
+    pub version: u32,
+    /// Oldest version of the messages the module decodes.
+    pub oldest: u32,
+    /// Oldest versions of the other modules the module works with, by their names.
+    pub requires: HashMap<String, u32>,
+    /// Whether the modules running are asked to answer with their hellos.
+    pub request: bool,
+}
+
+impl Hello {
+    pub fn new(module: &str, requires: &[(&str, u32)]) -> Self {
+        Hello {
+            module: module.to_string(),
+            version: MESSAGE_VERSION,
+            oldest: 0,
+            requires: requires.iter().map(|&(name, version)| (name.to_string(), version)).collect(),
+            request: true,
+        }
+    }
+
+    /// Why the module can not run beside the other, none if it can.
+    pub fn mismatch(&self, other: &Hello) -> Option<String> {
+        if other.version > self.version {
+            return Some(format!("{} writes messages of version {}, newer than {} decoded by {}", other.module, other.version, self.version, self.module));
+        }
+        if other.version < self.oldest {
+            return Some(format!("{} writes messages of version {}, older than {} decoded by {}", other.module, other.version, self.oldest, self.module));
+        }
+        match self.requires.get(&other.module) {
+            Some(&required) if other.version < required => Some(format!("{} requires {} of version {} at least, found {}", self.module, other.module, required, other.version)),
+            _ => None,
+        }
+    }
+}
+
+/// Publish the hello of the module and exit on a mismatch with another, from a queue of its own.
+pub fn start_handshake(module: &str, requires: &[(&str, u32)]) {
+    let hello = Hello::new(module, requires);
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
+    let (tx_pub, rx_pub) = channel();
+    start_pubsub(&format!("{}_hello", module), vec!["*.hello"], tx_sub, rx_pub);
+    let key = format!("{}.hello", module);
+    tx_pub.send((key.clone(), serde_json::to_vec(&hello).unwrap())).unwrap();
+    info!("hello {:?}", hello);
+
+    thread::spawn(move || for (_, body) in rx_sub {
+                      let other: Hello = match serde_json::from_slice(&body) {
+                          Ok(other) => other,
+                          Err(e) => {
+                              warn!("bad hello: {}", e);
+                              continue;
+                          }
+                      };
+                      if other.module == hello.module {
+                          continue;
+                      }
+                      if let Some(reason) = hello.mismatch(&other) {
+                          error!("refuse to run: {}", reason);
+                          process::exit(1);
+                      }
+                      info!("{} of version {} is compatible", other.module, other.version);
+                      if other.request {
+                          let answer = Hello { request: false, ..hello.clone() };
+                          let _ = tx_pub.send((key.clone(), serde_json::to_vec(&answer).unwrap()));
+                      }
+                  });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatch() {
+        let chain = Hello::new("chain", &[("consensus", MESSAGE_VERSION)]);
+        let mut consensus = Hello::new("consensus", &[]);
+        assert_eq!(chain.mismatch(&consensus), None);
+
+        consensus.version = MESSAGE_VERSION + 1;
+        assert!(chain.mismatch(&consensus).unwrap().contains("newer"));
+        // an older one is decoded, unless required newer
+        consensus.version = MESSAGE_VERSION - 1;
+        assert!(chain.mismatch(&consensus).unwrap().contains("requires consensus"));
+        let jsonrpc = Hello::new("jsonrpc", &[]);
+        assert_eq!(jsonrpc.mismatch(&consensus), None);
+
+        let old = Hello { oldest: MESSAGE_VERSION, ..Hello::new("net", &[]) };
+        assert!(old.mismatch(&consensus).unwrap().contains("older"));
+    }
+}
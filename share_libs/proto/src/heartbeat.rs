@@ -0,0 +1,49 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Heartbeats of the modules on the bus under `<module>.heartbeat`, so the
+//! supervisor restarts a module which is stuck. A heartbeat is sent only
+//! when the module can take the lock of its work, so a module deadlocked in
+//! its work stops beating though its process is alive.
+
+use super::{factory, topics};
+use communication::MsgType;
+use protobuf::Message;
+use shutdown::Shutdown;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+/// Seconds between two heartbeats of a module.
+pub const HEARTBEAT_INTERVAL: u64 = 5;
+
+/// The heartbeat of the submodule publishing under the name, with the key to publish it under.
+pub fn heartbeat(submodule: u32, name: &str) -> (String, Vec<u8>) {
+    let msg = factory::create_msg(submodule, topics::HEARTBEAT, MsgType::MSG, Vec::new());
+    (format!("{}.heartbeat", name), msg.write_to_bytes().unwrap())
+}
+
+pub fn start_heartbeat(submodule: u32, name: &str, shutdown: Shutdown, tx_pub: Sender<(String, Vec<u8>)>) {
+    let name = name.to_string();
+    thread::spawn(move || loop {
+                      thread::sleep(Duration::from_secs(HEARTBEAT_INTERVAL));
+                      // not work the shutdown waits for, it only stops
+                      if shutdown.requested() || tx_pub.send(heartbeat(submodule, &name)).is_err() {
+                          break;
+                      }
+                  });
+}
@@ -19,14 +19,25 @@ extern crate protobuf;
 extern crate util;
 extern crate rustc_serialize;
 extern crate rlp;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
+extern crate pubsub;
 extern crate cita_ed25519 as ed25519;
+extern crate cita_log;
+extern crate libc;
+#[macro_use]
+extern crate log;
 
 pub mod blockchain;
 pub mod communication;
 pub mod request;
+pub mod handshake;
+pub mod heartbeat;
 pub mod into;
+pub mod reload;
+pub mod shutdown;
 
 use blockchain::*;
 use ed25519::{sign, PrivKey, recover, Signature, KeyPair, SIGNATURE_BYTES_LEN};
@@ -63,6 +74,30 @@ pub mod topics {
     pub const TX_RESPONSE: u16 = 7;
     pub const CONSENSUS_MSG: u16 = 8;
     pub const NEW_PROPOSAL: u16 = 9;
+    pub const COMMIT_PROOF: u16 = 10;
+    pub const KEY_ROTATION: u16 = 11;
+    pub const SYS_CONFIG: u16 = 12;
+    pub const AUTHORITIES: u16 = 13;
+    pub const PEERS: u16 = 14;
+    pub const NODE_LIST: u16 = 15;
+    pub const HANDSHAKE: u16 = 16;
+    pub const CHUNK: u16 = 17;
+    pub const CHUNK_RESUME: u16 = 18;
+    pub const NET_STATUS: u16 = 19;
+    pub const PING: u16 = 20;
+    pub const PONG: u16 = 21;
+    pub const NET_RTT: u16 = 22;
+    pub const HANDSHAKE_REJECT: u16 = 23;
+    pub const SNAPSHOT_REQ: u16 = 24;
+    pub const SNAPSHOT_MANIFEST: u16 = 25;
+    pub const SNAPSHOT_CHUNK_REQ: u16 = 26;
+    pub const SNAPSHOT_CHUNK: u16 = 27;
+    pub const PROPOSERS: u16 = 28;
+    pub const PERMISSIONS: u16 = 29;
+    pub const SHUTDOWN_ACK: u16 = 30;
+    pub const HEARTBEAT: u16 = 31;
+    pub const COMMIT_ROUNDS: u16 = 32;
+    pub const HANDSHAKE_PROOF: u16 = 33;
 }
 
 #[derive(Debug)]
@@ -90,6 +125,30 @@ pub fn topic_to_string(top: u16) -> &'static str {
         topics::TX_RESPONSE => "tx_response",
         topics::CONSENSUS_MSG => "consensus_msg",
         topics::NEW_PROPOSAL => "new_proposal",
+        topics::COMMIT_PROOF => "commit_proof",
+        topics::KEY_ROTATION => "key_rotation",
+        topics::SYS_CONFIG => "sys_config",
+        topics::AUTHORITIES => "authorities",
+        topics::PEERS => "peers",
+        topics::NODE_LIST => "node_list",
+        topics::HANDSHAKE => "handshake",
+        topics::CHUNK => "chunk",
+        topics::CHUNK_RESUME => "chunk_resume",
+        topics::NET_STATUS => "net_status",
+        topics::PING => "ping",
+        topics::PONG => "pong",
+        topics::NET_RTT => "net_rtt",
+        topics::HANDSHAKE_REJECT => "handshake_reject",
+        topics::SNAPSHOT_REQ => "snapshot_req",
+        topics::SNAPSHOT_MANIFEST => "snapshot_manifest",
+        topics::SNAPSHOT_CHUNK_REQ => "snapshot_chunk_req",
+        topics::SNAPSHOT_CHUNK => "snapshot_chunk",
+        topics::PROPOSERS => "proposers",
+        topics::PERMISSIONS => "permissions",
+        topics::SHUTDOWN_ACK => "shutdown_ack",
+        topics::HEARTBEAT => "heartbeat",
+        topics::COMMIT_ROUNDS => "commit_rounds",
+        topics::HANDSHAKE_PROOF => "handshake_proof",
         _ => "",
     }
 }
@@ -148,8 +207,11 @@ pub mod factory {
         msg.set_field_type(msg_type);
         msg.set_operate(communication::OperateType::BROADCAST);
         msg.set_origin(ZERO_ORIGIN);
-        //compress data
-        msg.set_content(snappy::cita_compresse(content));
+        msg.set_version(MESSAGE_VERSION);
+        // part of the trace the thread works for
+        msg.set_trace_id(cita_log::trace_id());
+        msg.set_content(content);
+        compress_msg(&mut msg);
         msg
     }
 
@@ -162,26 +224,123 @@ pub mod factory {
     }
 }
 
+/// Version of the messages between the modules and the nodes, the messages of the
+/// nodes before the version was added are of version 0. A node decodes the versions
+/// it can upgrade from, so the nodes of a consortium can be upgraded one by one.
+pub const MESSAGE_VERSION: u32 = 2;
+
+/// Codecs of the content of the messages, named by the header of a message.
+pub const COMPRESSION_NONE: u32 = 0;
+pub const COMPRESSION_SNAPPY: u32 = 1;
+/// Contents larger than this are compressed, the blocks and the states mostly.
+pub const COMPRESS_THRESHOLD: usize = 4 * 1024;
+
+/// Compress the content of the message if it is large, naming the codec in its header.
+pub fn compress_msg(msg: &mut communication::Message) {
+    if msg.get_compression() == COMPRESSION_NONE && msg.get_content().len() > COMPRESS_THRESHOLD {
+        let content = snappy::compress(msg.get_content());
+        msg.set_content(content);
+        msg.set_compression(COMPRESSION_SNAPPY);
+    }
+}
+
+/// Decompress the content of the message by the codec in its header.
+pub fn decompress_msg(msg: &mut communication::Message) -> Result<(), String> {
+    match msg.get_compression() {
+        COMPRESSION_NONE => Ok(()),
+        COMPRESSION_SNAPPY => {
+            let content = snappy::decompress(msg.get_content()).map_err(|e| e.to_string())?;
+            msg.set_content(content);
+            msg.set_compression(COMPRESSION_NONE);
+            Ok(())
+        }
+        codec => Err(format!("message compression {} is not supported", codec)),
+    }
+}
+
+/// The content of the message decompressed, leaving the message as it is.
+pub fn msg_content(msg: &communication::Message) -> Result<Vec<u8>, String> {
+    match msg.get_compression() {
+        COMPRESSION_NONE => Ok(msg.get_content().to_vec()),
+        COMPRESSION_SNAPPY => snappy::decompress(msg.get_content()).map_err(|e| e.to_string()),
+        codec => Err(format!("message compression {} is not supported", codec)),
+    }
+}
+
+// Change a message of the version into the next one.
+fn upgrade_step(msg: &mut communication::Message, version: u32) -> Result<(), String> {
+    match version {
+        // version 1 only adds the version
+        0 => Ok(()),
+        // version 2 names the codec, the contents valid for snappy were compressed before
+        1 => {
+            if snappy::validate_compressed_buffer(msg.get_content()) {
+                msg.set_compression(COMPRESSION_SNAPPY);
+            }
+            Ok(())
+        }
+        _ => Err(format!("message version {} is not supported", version)),
+    }
+}
+
+// Change a message of the next version into the version.
+fn downgrade_step(msg: &mut communication::Message, version: u32) -> Result<(), String> {
+    match version {
+        0 => Ok(()),
+        // the older nodes guess the codec by the content
+        1 => {
+            msg.clear_compression();
+            Ok(())
+        }
+        _ => Err(format!("message version {} is not supported", version)),
+    }
+}
+
+/// Bring a message of an older version to the current one.
+pub fn upgrade_msg(msg: &mut communication::Message) -> Result<(), String> {
+    let version = msg.get_version();
+    if version > MESSAGE_VERSION {
+        return Err(format!("message version {} is newer than {}", version, MESSAGE_VERSION));
+    }
+    for from in version..MESSAGE_VERSION {
+        upgrade_step(msg, from)?;
+    }
+    msg.set_version(MESSAGE_VERSION);
+    Ok(())
+}
+
+/// Write a message for a receiver decoding the versions up to the version.
+pub fn downgrade_msg(msg: &mut communication::Message, version: u32) -> Result<(), String> {
+    let mut current = msg.get_version();
+    while current > version {
+        current -= 1;
+        downgrade_step(msg, current)?;
+    }
+    msg.set_version(current);
+    Ok(())
+}
+
 type CmdId = u32;
 type Origin = u32;
 
-pub fn parse_msg(msg: &[u8]) -> (CmdId, Origin, MsgClass) {
-    let mut msg = parse_from_bytes::<communication::Message>(msg.as_ref()).unwrap();
+/// Decode the message, the thread works for its trace until the next one.
+/// The messages which can not be upgraded or decoded are errors, for the caller to drop.
+pub fn parse_msg(msg: &[u8]) -> Result<(CmdId, Origin, MsgClass), String> {
+    let mut msg = parse_from_bytes::<communication::Message>(msg.as_ref()).map_err(|e| format!("invalid message: {}", e))?;
+    upgrade_msg(&mut msg)?;
+    cita_log::set_trace_id(msg.get_trace_id());
+    decompress_msg(&mut msg)?;
     let content_msg = msg.take_content();
-    let content_msg = snappy::cita_decompress(content_msg);
+    let invalid = |e: protobuf::ProtobufError| format!("invalid content of {:?}: {}", msg.get_field_type(), e);
     let msg_class = match msg.get_field_type() {
-        MsgType::REQUEST => MsgClass::REQUEST(parse_from_bytes::<Request>(&content_msg).unwrap()),
-        MsgType::RESPONSE => {
-            MsgClass::RESPONSE(parse_from_bytes::<Response>(&content_msg).unwrap())
-        }
-        MsgType::TX_RESPONSE => {
-            MsgClass::TXRESPONSE(parse_from_bytes::<TxResponse>(&content_msg).unwrap())
-        }
-        MsgType::HEADER => MsgClass::HEADER(parse_from_bytes::<BlockHeader>(&content_msg).unwrap()),
-        MsgType::BODY => MsgClass::BODY(parse_from_bytes::<BlockBody>(&content_msg).unwrap()),
-        MsgType::BLOCK => MsgClass::BLOCK(parse_from_bytes::<Block>(&content_msg).unwrap()),
-        MsgType::TX => MsgClass::TX(parse_from_bytes::<SignedTransaction>(&content_msg).unwrap()),
-        MsgType::STATUS => MsgClass::STATUS(parse_from_bytes::<Status>(&content_msg).unwrap()),
+        MsgType::REQUEST => MsgClass::REQUEST(parse_from_bytes::<Request>(&content_msg).map_err(&invalid)?),
+        MsgType::RESPONSE => MsgClass::RESPONSE(parse_from_bytes::<Response>(&content_msg).map_err(&invalid)?),
+        MsgType::TX_RESPONSE => MsgClass::TXRESPONSE(parse_from_bytes::<TxResponse>(&content_msg).map_err(&invalid)?),
+        MsgType::HEADER => MsgClass::HEADER(parse_from_bytes::<BlockHeader>(&content_msg).map_err(&invalid)?),
+        MsgType::BODY => MsgClass::BODY(parse_from_bytes::<BlockBody>(&content_msg).map_err(&invalid)?),
+        MsgType::BLOCK => MsgClass::BLOCK(parse_from_bytes::<Block>(&content_msg).map_err(&invalid)?),
+        MsgType::TX => MsgClass::TX(parse_from_bytes::<SignedTransaction>(&content_msg).map_err(&invalid)?),
+        MsgType::STATUS => MsgClass::STATUS(parse_from_bytes::<Status>(&content_msg).map_err(&invalid)?),
         MsgType::MSG => {
             let mut content = Vec::new();
             content.extend_from_slice(&content_msg);
@@ -189,10 +348,23 @@ pub fn parse_msg(msg: &[u8]) -> (CmdId, Origin, MsgClass) {
         }
     };
 
-    (msg.get_cmd_id(), msg.get_origin(), msg_class)
+    Ok((msg.get_cmd_id(), msg.get_origin(), msg_class))
 }
 
 
+/// Version of the transactions signed together with the id of the chain they are sent to,
+/// the transactions of version 0 are not bound to a chain.
+pub const TRANSACTION_VERSION: u32 = 1;
+
+impl blockchain::Transaction {
+    /// Whether the transaction was signed for the chain, in a version known here.
+    /// The chain id is part of the signed bytes, so it cannot be changed to replay
+    /// the transaction on another chain.
+    pub fn check_chain(&self, chain_id: u32) -> bool {
+        self.get_version() <= TRANSACTION_VERSION && self.get_chain_id() == chain_id
+    }
+}
+
 impl blockchain::SignedTransaction {
     pub fn sign(&mut self, sk: PrivKey) {
         let keypair = KeyPair::from_privkey(sk).unwrap();
@@ -208,6 +380,48 @@ impl blockchain::SignedTransaction {
         self.set_tx_hash(bytes.crypt_hash().to_vec());
     }
 
+    /// Sign as the sponsor who pays the quota of the transaction,
+    /// after the sender has signed it.
+    pub fn sign_fee_payer(&mut self, sk: PrivKey) {
+        let keypair = KeyPair::from_privkey(sk).unwrap();
+        let pubkey = keypair.pubkey();
+
+        let hash = self.fee_payer_hash();
+        let signature = sign(&sk, &hash).unwrap();
+        self.mut_transaction_with_sig().set_fee_payer_signature(signature.to_vec());
+        self.set_fee_payer(pubkey.to_vec());
+        let bytes = self.get_transaction_with_sig().write_to_bytes().unwrap();
+        self.set_tx_hash(bytes.crypt_hash().to_vec());
+    }
+
+    /// The sponsor signs the transaction together with the signature of the sender,
+    /// so its signature can not be reused for another sender.
+    fn fee_payer_hash(&self) -> H256 {
+        let utx = self.get_transaction_with_sig();
+        let mut bytes = utx.get_transaction().write_to_bytes().unwrap();
+        bytes.extend_from_slice(utx.get_signature());
+        bytes.crypt_hash()
+    }
+
+    fn recover_fee_payer(&mut self) -> bool {
+        if self.get_transaction_with_sig().get_fee_payer_signature().is_empty() {
+            self.clear_fee_payer();
+            return true;
+        }
+        if self.get_transaction_with_sig().get_fee_payer_signature().len() != SIGNATURE_BYTES_LEN {
+            return false;
+        }
+        let hash = self.fee_payer_hash();
+        let signature: Signature = H768::from_slice(self.get_transaction_with_sig().get_fee_payer_signature()).into();
+        match recover(&signature, &hash) {
+            Ok(pubkey) => {
+                self.set_fee_payer(pubkey.to_vec());
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn recover(&mut self) -> bool {
         let mut ret = true;
         let bytes = self.get_transaction_with_sig().get_transaction().write_to_bytes().unwrap();
@@ -232,6 +446,9 @@ impl blockchain::SignedTransaction {
                 }
             }
         }
+        if ret {
+            ret = self.recover_fee_payer();
+        }
 
         let bytes = self.get_transaction_with_sig().write_to_bytes().unwrap();
         self.set_tx_hash(bytes.crypt_hash().to_vec());
@@ -300,6 +517,44 @@ mod tests {
         assert_eq!(cmd_id(submodules::CHAIN, topics::NEW_TX), 0x30006);
     }
 
+    #[test]
+    fn message_versions() {
+        let mut msg = factory::create_msg(submodules::CHAIN, topics::NEW_TX, MsgType::MSG, vec![1]);
+        assert_eq!(msg.get_version(), MESSAGE_VERSION);
+        downgrade_msg(&mut msg, 0).unwrap();
+        assert_eq!(msg.get_version(), 0);
+        upgrade_msg(&mut msg).unwrap();
+        assert_eq!(msg.get_version(), MESSAGE_VERSION);
+
+        msg.set_version(MESSAGE_VERSION + 1);
+        assert!(upgrade_msg(&mut msg).is_err());
+    }
+
+    #[test]
+    fn message_compression() {
+        let content = vec![7; COMPRESS_THRESHOLD + 1];
+        let mut msg = factory::create_msg(submodules::CHAIN, topics::NEW_BLK, MsgType::MSG, content.clone());
+        assert_eq!(msg.get_compression(), COMPRESSION_SNAPPY);
+        assert!(msg.get_content().len() < content.len());
+        assert_eq!(msg_content(&msg).unwrap(), content);
+
+        // the codec of the older versions is guessed
+        downgrade_msg(&mut msg, 1).unwrap();
+        assert_eq!(msg.get_compression(), COMPRESSION_NONE);
+        upgrade_msg(&mut msg).unwrap();
+        assert_eq!(msg.get_compression(), COMPRESSION_SNAPPY);
+        decompress_msg(&mut msg).unwrap();
+        assert_eq!(msg.get_content(), &content[..]);
+
+        let small = factory::create_msg(submodules::CHAIN, topics::NEW_TX, MsgType::MSG, vec![1]);
+        assert_eq!(small.get_compression(), COMPRESSION_NONE);
+        assert_eq!(parse_msg(&small.write_to_bytes().unwrap()).unwrap().0, small.get_cmd_id());
+        assert!(parse_msg(&[0xff, 0xff]).is_err());
+        let mut newer = small.clone();
+        newer.set_version(MESSAGE_VERSION + 1);
+        assert!(parse_msg(&newer.write_to_bytes().unwrap()).is_err());
+    }
+
     #[test]
     fn create_tx() {
         let keypair = KeyPair::gen_keypair();
@@ -321,4 +576,61 @@ mod tests {
 
         println!("{}", signed_tx.write_to_bytes().unwrap().to_hex())
     }
+
+    #[test]
+    fn recover_fee_payer() {
+        let sender = KeyPair::gen_keypair();
+        let sponsor = KeyPair::gen_keypair();
+
+        let mut tx = Transaction::new();
+        tx.set_data(vec![1]);
+        tx.set_nonce("0".to_string());
+        tx.set_to("123".to_string());
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(sender.privkey().clone());
+        signed_tx.sign_fee_payer(sponsor.privkey().clone());
+
+        let mut recovered = signed_tx.clone();
+        assert!(recovered.recover());
+        assert_eq!(recovered.get_signer(), &sender.pubkey().to_vec()[..]);
+        assert_eq!(recovered.get_fee_payer(), &sponsor.pubkey().to_vec()[..]);
+        assert_eq!(recovered.get_tx_hash(), signed_tx.get_tx_hash());
+
+        // the sponsor signature is bound to the signature of the sender
+        let mut forged = signed_tx.clone();
+        forged.sign(KeyPair::gen_keypair().privkey().clone());
+        assert!(!forged.recover());
+    }
+
+    #[test]
+    fn chain_id_is_signed() {
+        let keypair = KeyPair::gen_keypair();
+        let mut tx = Transaction::new();
+        tx.set_nonce("0".to_string());
+        tx.set_chain_id(1);
+        tx.set_version(TRANSACTION_VERSION);
+        assert!(tx.check_chain(1));
+        assert!(!tx.check_chain(2));
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(keypair.privkey().clone());
+
+        // moved to another chain, the signature recovers another signer
+        let mut replayed = signed_tx.clone();
+        replayed.mut_transaction_with_sig().mut_transaction().set_chain_id(2);
+        replayed.recover();
+        assert!(replayed.get_signer() != signed_tx.get_signer());
+
+        let mut unknown = Transaction::new();
+        unknown.set_version(TRANSACTION_VERSION + 1);
+        assert!(!unknown.check_chain(0));
+    }
 }
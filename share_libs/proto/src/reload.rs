@@ -0,0 +1,51 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reload of the configuration of a module on SIGHUP, without a restart. The
+//! filters of the logs are read again from the environment file of the node,
+//! and the module applies the settings of its config which may change at
+//! runtime. Each change applied is logged as an entry of the changelog.
+
+use cita_log;
+use libc;
+use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// milliseconds between two checks whether a reload is asked
+const POLL_INTERVAL: u64 = 200;
+
+static HANGUP: AtomicBool = ATOMIC_BOOL_INIT;
+
+extern "C" fn on_signal(_: libc::c_int) {
+    HANGUP.store(true, Ordering::SeqCst);
+}
+
+/// Reload the filters of the logs and call the reload of the module on every SIGHUP, from a thread of its own.
+pub fn on_hangup<F: FnMut() + Send + 'static>(mut reload: F) {
+    unsafe {
+        libc::signal(libc::SIGHUP, on_signal as libc::sighandler_t);
+    }
+    thread::spawn(move || loop {
+                      thread::sleep(Duration::from_millis(POLL_INTERVAL));
+                      if HANGUP.swap(false, Ordering::SeqCst) {
+                          info!("reload the config");
+                          cita_log::reload_filters();
+                          reload();
+                      }
+                  });
+}
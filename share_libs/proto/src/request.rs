@@ -396,6 +396,16 @@ pub enum Request_oneof_req {
     uninstall_filter(u64),
     filter_changes(u64),
     filter_logs(u64),
+    peer_bans(bool),
+    enable_peer(u64),
+    peers_info(bool),
+    state_diff(::std::string::String),
+    transactions_by_account(::std::string::String),
+    storage_history(::std::string::String),
+    light_client_proof(u64),
+    replay_transaction(::std::vec::Vec<u8>),
+    simulate_transaction(::std::string::String),
+    consensus_metrics(bool),
 }
 
 impl Request {
@@ -1062,6 +1072,376 @@ impl Request {
             _ => 0,
         }
     }
+
+    // bool peer_bans = 18;
+
+    pub fn clear_peer_bans(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_peer_bans(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::peer_bans(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_peer_bans(&mut self, v: bool) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::peer_bans(v))
+    }
+
+    pub fn get_peer_bans(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::peer_bans(v)) => v,
+            _ => false,
+        }
+    }
+
+    // uint64 enable_peer = 19;
+
+    pub fn clear_enable_peer(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_enable_peer(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::enable_peer(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_enable_peer(&mut self, v: u64) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::enable_peer(v))
+    }
+
+    pub fn get_enable_peer(&self) -> u64 {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::enable_peer(v)) => v,
+            _ => 0,
+        }
+    }
+
+    // bool peers_info = 20;
+
+    pub fn clear_peers_info(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_peers_info(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::peers_info(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_peers_info(&mut self, v: bool) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::peers_info(v))
+    }
+
+    pub fn get_peers_info(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::peers_info(v)) => v,
+            _ => false,
+        }
+    }
+
+    // string state_diff = 21;
+
+    pub fn clear_state_diff(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_state_diff(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::state_diff(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_state_diff(&mut self, v: ::std::string::String) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::state_diff(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_state_diff(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Request_oneof_req::state_diff(_)) = self.req {
+        } else {
+            self.req = ::std::option::Option::Some(Request_oneof_req::state_diff(::std::string::String::new()));
+        }
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::state_diff(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_state_diff(&mut self) -> ::std::string::String {
+        if self.has_state_diff() {
+            match self.req.take() {
+                ::std::option::Option::Some(Request_oneof_req::state_diff(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_state_diff(&self) -> &str {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::state_diff(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // string transactions_by_account = 22;
+
+    pub fn clear_transactions_by_account(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_transactions_by_account(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::transactions_by_account(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_transactions_by_account(&mut self, v: ::std::string::String) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::transactions_by_account(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_transactions_by_account(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Request_oneof_req::transactions_by_account(_)) = self.req {
+        } else {
+            self.req = ::std::option::Option::Some(Request_oneof_req::transactions_by_account(::std::string::String::new()));
+        }
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::transactions_by_account(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_transactions_by_account(&mut self) -> ::std::string::String {
+        if self.has_transactions_by_account() {
+            match self.req.take() {
+                ::std::option::Option::Some(Request_oneof_req::transactions_by_account(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_transactions_by_account(&self) -> &str {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::transactions_by_account(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // string storage_history = 23;
+
+    pub fn clear_storage_history(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_storage_history(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::storage_history(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_storage_history(&mut self, v: ::std::string::String) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::storage_history(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_storage_history(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Request_oneof_req::storage_history(_)) = self.req {
+        } else {
+            self.req = ::std::option::Option::Some(Request_oneof_req::storage_history(::std::string::String::new()));
+        }
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::storage_history(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_storage_history(&mut self) -> ::std::string::String {
+        if self.has_storage_history() {
+            match self.req.take() {
+                ::std::option::Option::Some(Request_oneof_req::storage_history(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_storage_history(&self) -> &str {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::storage_history(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // uint64 light_client_proof = 24;
+
+    pub fn clear_light_client_proof(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_light_client_proof(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::light_client_proof(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_light_client_proof(&mut self, v: u64) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::light_client_proof(v))
+    }
+
+    pub fn get_light_client_proof(&self) -> u64 {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::light_client_proof(v)) => v,
+            _ => 0,
+        }
+    }
+
+    // bytes replay_transaction = 25;
+
+    pub fn clear_replay_transaction(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_replay_transaction(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::replay_transaction(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_replay_transaction(&mut self, v: ::std::vec::Vec<u8>) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::replay_transaction(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_replay_transaction(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Request_oneof_req::replay_transaction(_)) = self.req {
+        } else {
+            self.req = ::std::option::Option::Some(Request_oneof_req::replay_transaction(::std::vec::Vec::new()));
+        }
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::replay_transaction(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_replay_transaction(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_replay_transaction() {
+            match self.req.take() {
+                ::std::option::Option::Some(Request_oneof_req::replay_transaction(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::vec::Vec::new()
+        }
+    }
+
+    pub fn get_replay_transaction(&self) -> &[u8] {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::replay_transaction(ref v)) => v,
+            _ => &[],
+        }
+    }
+
+    // string simulate_transaction = 26;
+
+    pub fn clear_simulate_transaction(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_simulate_transaction(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::simulate_transaction(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_simulate_transaction(&mut self, v: ::std::string::String) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::simulate_transaction(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_simulate_transaction(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Request_oneof_req::simulate_transaction(_)) = self.req {
+        } else {
+            self.req = ::std::option::Option::Some(Request_oneof_req::simulate_transaction(::std::string::String::new()));
+        }
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::simulate_transaction(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_simulate_transaction(&mut self) -> ::std::string::String {
+        if self.has_simulate_transaction() {
+            match self.req.take() {
+                ::std::option::Option::Some(Request_oneof_req::simulate_transaction(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_simulate_transaction(&self) -> &str {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::simulate_transaction(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // bool consensus_metrics = 27;
+
+    pub fn clear_consensus_metrics(&mut self) {
+        self.req = ::std::option::Option::None;
+    }
+
+    pub fn has_consensus_metrics(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::consensus_metrics(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_consensus_metrics(&mut self, v: bool) {
+        self.req = ::std::option::Option::Some(Request_oneof_req::consensus_metrics(v))
+    }
+
+    pub fn get_consensus_metrics(&self) -> bool {
+        match self.req {
+            ::std::option::Option::Some(Request_oneof_req::consensus_metrics(v)) => v,
+            _ => false,
+        }
+    }
 }
 
 impl ::protobuf::Message for Request {
@@ -1177,7 +1557,67 @@ impl ::protobuf::Message for Request {
                     }
                     self.req = ::std::option::Option::Some(Request_oneof_req::filter_logs(is.read_uint64()?));
                 },
-                _ => {
+                18 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::peer_bans(is.read_bool()?));
+                },
+                19 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::enable_peer(is.read_uint64()?));
+                },
+                20 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::peers_info(is.read_bool()?));
+                },
+                21 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::state_diff(is.read_string()?));
+                },
+                22 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::transactions_by_account(is.read_string()?));
+                },
+                23 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::storage_history(is.read_string()?));
+                },
+                24 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::light_client_proof(is.read_uint64()?));
+                },
+                25 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::replay_transaction(is.read_bytes()?));
+                },
+                26 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::simulate_transaction(is.read_string()?));
+                },
+                27 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.req = ::std::option::Option::Some(Request_oneof_req::consensus_metrics(is.read_bool()?));
+                },
+                _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
             };
@@ -1243,6 +1683,36 @@ impl ::protobuf::Message for Request {
                 &Request_oneof_req::filter_logs(v) => {
                     my_size += ::protobuf::rt::value_size(17, v, ::protobuf::wire_format::WireTypeVarint);
                 },
+                &Request_oneof_req::peer_bans(v) => {
+                    my_size += 3;
+                },
+                &Request_oneof_req::enable_peer(v) => {
+                    my_size += ::protobuf::rt::value_size(19, v, ::protobuf::wire_format::WireTypeVarint);
+                },
+                &Request_oneof_req::peers_info(v) => {
+                    my_size += 3;
+                },
+                &Request_oneof_req::state_diff(ref v) => {
+                    my_size += ::protobuf::rt::string_size(21, &v);
+                },
+                &Request_oneof_req::transactions_by_account(ref v) => {
+                    my_size += ::protobuf::rt::string_size(22, &v);
+                },
+                &Request_oneof_req::storage_history(ref v) => {
+                    my_size += ::protobuf::rt::string_size(23, &v);
+                },
+                &Request_oneof_req::light_client_proof(v) => {
+                    my_size += ::protobuf::rt::value_size(24, v, ::protobuf::wire_format::WireTypeVarint);
+                },
+                &Request_oneof_req::replay_transaction(ref v) => {
+                    my_size += ::protobuf::rt::bytes_size(25, &v);
+                },
+                &Request_oneof_req::simulate_transaction(ref v) => {
+                    my_size += ::protobuf::rt::string_size(26, &v);
+                },
+                &Request_oneof_req::consensus_metrics(v) => {
+                    my_size += 3;
+                },
             };
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
@@ -1306,6 +1776,36 @@ impl ::protobuf::Message for Request {
                 &Request_oneof_req::filter_logs(v) => {
                     os.write_uint64(17, v)?;
                 },
+                &Request_oneof_req::peer_bans(v) => {
+                    os.write_bool(18, v)?;
+                },
+                &Request_oneof_req::enable_peer(v) => {
+                    os.write_uint64(19, v)?;
+                },
+                &Request_oneof_req::peers_info(v) => {
+                    os.write_bool(20, v)?;
+                },
+                &Request_oneof_req::state_diff(ref v) => {
+                    os.write_string(21, v)?;
+                },
+                &Request_oneof_req::transactions_by_account(ref v) => {
+                    os.write_string(22, v)?;
+                },
+                &Request_oneof_req::storage_history(ref v) => {
+                    os.write_string(23, v)?;
+                },
+                &Request_oneof_req::light_client_proof(v) => {
+                    os.write_uint64(24, v)?;
+                },
+                &Request_oneof_req::replay_transaction(ref v) => {
+                    os.write_bytes(25, v)?;
+                },
+                &Request_oneof_req::simulate_transaction(ref v) => {
+                    os.write_string(26, v)?;
+                },
+                &Request_oneof_req::consensus_metrics(v) => {
+                    os.write_bool(27, v)?;
+                },
             };
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
@@ -1437,6 +1937,56 @@ impl ::protobuf::MessageStatic for Request {
                     Request::has_filter_logs,
                     Request::get_filter_logs,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bool_accessor::<_>(
+                    "peer_bans",
+                    Request::has_peer_bans,
+                    Request::get_peer_bans,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_u64_accessor::<_>(
+                    "enable_peer",
+                    Request::has_enable_peer,
+                    Request::get_enable_peer,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bool_accessor::<_>(
+                    "peers_info",
+                    Request::has_peers_info,
+                    Request::get_peers_info,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "state_diff",
+                    Request::has_state_diff,
+                    Request::get_state_diff,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "transactions_by_account",
+                    Request::has_transactions_by_account,
+                    Request::get_transactions_by_account,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "storage_history",
+                    Request::has_storage_history,
+                    Request::get_storage_history,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_u64_accessor::<_>(
+                    "light_client_proof",
+                    Request::has_light_client_proof,
+                    Request::get_light_client_proof,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bytes_accessor::<_>(
+                    "replay_transaction",
+                    Request::has_replay_transaction,
+                    Request::get_replay_transaction,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "simulate_transaction",
+                    Request::has_simulate_transaction,
+                    Request::get_simulate_transaction,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bool_accessor::<_>(
+                    "consensus_metrics",
+                    Request::has_consensus_metrics,
+                    Request::get_consensus_metrics,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Request>(
                     "Request",
                     fields,
@@ -1466,6 +2016,16 @@ impl ::protobuf::Clear for Request {
         self.clear_uninstall_filter();
         self.clear_filter_changes();
         self.clear_filter_logs();
+        self.clear_peer_bans();
+        self.clear_enable_peer();
+        self.clear_peers_info();
+        self.clear_state_diff();
+        self.clear_transactions_by_account();
+        self.clear_storage_history();
+        self.clear_light_client_proof();
+        self.clear_replay_transaction();
+        self.clear_simulate_transaction();
+        self.clear_consensus_metrics();
         self.unknown_fields.clear();
     }
 }
@@ -1807,346 +2367,799 @@ impl ::protobuf::reflect::ProtobufValue for FullTransaction {
     fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
         ::protobuf::reflect::ProtobufValueRef::Message(self)
     }
-}
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct Response {
+    // message fields
+    pub request_id: ::std::vec::Vec<u8>,
+    // message oneof groups
+    pub result: ::std::option::Option<Response_oneof_result>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+// see codegen.rs for the explanation why impl Sync explicitly
+unsafe impl ::std::marker::Sync for Response {}
+
+#[derive(Clone,PartialEq)]
+pub enum Response_oneof_result {
+    block_number(u64),
+    block(::std::string::String),
+    ts(FullTransaction),
+    none(bool),
+    peercount(u32),
+    call_result(::std::vec::Vec<u8>),
+    logs(::std::string::String),
+    receipt(::std::string::String),
+    transaction_count(u64),
+    code(::std::vec::Vec<u8>),
+    filter_id(u64),
+    uninstall_filter(bool),
+    filter_changes(::std::vec::Vec<u8>),
+    filter_logs(::std::vec::Vec<u8>),
+    peer_bans(::std::string::String),
+    enable_peer(bool),
+    peers_info(::std::string::String),
+    error_msg(::std::string::String),
+    state_diff(::std::string::String),
+    transactions_by_account(::std::string::String),
+    storage_history(::std::string::String),
+    light_client_proof(::std::vec::Vec<u8>),
+    transaction_trace(::std::string::String),
+    consensus_metrics(::std::string::String),
+}
+
+impl Response {
+    pub fn new() -> Response {
+        ::std::default::Default::default()
+    }
+
+    pub fn default_instance() -> &'static Response {
+        static mut instance: ::protobuf::lazy::Lazy<Response> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const Response,
+        };
+        unsafe {
+            instance.get(Response::new)
+        }
+    }
+
+    // bytes request_id = 1;
+
+    pub fn clear_request_id(&mut self) {
+        self.request_id.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_request_id(&mut self, v: ::std::vec::Vec<u8>) {
+        self.request_id = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_request_id(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.request_id
+    }
+
+    // Take field
+    pub fn take_request_id(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.request_id, ::std::vec::Vec::new())
+    }
+
+    pub fn get_request_id(&self) -> &[u8] {
+        &self.request_id
+    }
+
+    fn get_request_id_for_reflect(&self) -> &::std::vec::Vec<u8> {
+        &self.request_id
+    }
+
+    fn mut_request_id_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.request_id
+    }
+
+    // uint64 block_number = 2;
+
+    pub fn clear_block_number(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_block_number(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::block_number(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_block_number(&mut self, v: u64) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::block_number(v))
+    }
+
+    pub fn get_block_number(&self) -> u64 {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::block_number(v)) => v,
+            _ => 0,
+        }
+    }
+
+    // string block = 3;
+
+    pub fn clear_block(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_block(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::block(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_block(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::block(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_block(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::block(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::block(::std::string::String::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::block(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_block(&mut self) -> ::std::string::String {
+        if self.has_block() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::block(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_block(&self) -> &str {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::block(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // .FullTransaction ts = 4;
+
+    pub fn clear_ts(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_ts(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::ts(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_ts(&mut self, v: FullTransaction) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::ts(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_ts(&mut self) -> &mut FullTransaction {
+        if let ::std::option::Option::Some(Response_oneof_result::ts(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::ts(FullTransaction::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::ts(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_ts(&mut self) -> FullTransaction {
+        if self.has_ts() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::ts(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            FullTransaction::new()
+        }
+    }
+
+    pub fn get_ts(&self) -> &FullTransaction {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::ts(ref v)) => v,
+            _ => FullTransaction::default_instance(),
+        }
+    }
+
+    // bool none = 5;
+
+    pub fn clear_none(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_none(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::none(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_none(&mut self, v: bool) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::none(v))
+    }
+
+    pub fn get_none(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::none(v)) => v,
+            _ => false,
+        }
+    }
+
+    // uint32 peercount = 6;
+
+    pub fn clear_peercount(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_peercount(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::peercount(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_peercount(&mut self, v: u32) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::peercount(v))
+    }
+
+    pub fn get_peercount(&self) -> u32 {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::peercount(v)) => v,
+            _ => 0,
+        }
+    }
+
+    // bytes call_result = 7;
+
+    pub fn clear_call_result(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_call_result(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::call_result(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_call_result(&mut self, v: ::std::vec::Vec<u8>) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::call_result(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_call_result(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Response_oneof_result::call_result(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::call_result(::std::vec::Vec::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::call_result(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_call_result(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_call_result() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::call_result(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::vec::Vec::new()
+        }
+    }
+
+    pub fn get_call_result(&self) -> &[u8] {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::call_result(ref v)) => v,
+            _ => &[],
+        }
+    }
+
+    // string logs = 8;
+
+    pub fn clear_logs(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_logs(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::logs(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_logs(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::logs(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_logs(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::logs(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::logs(::std::string::String::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::logs(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_logs(&mut self) -> ::std::string::String {
+        if self.has_logs() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::logs(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    pub fn get_logs(&self) -> &str {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::logs(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // string receipt = 9;
+
+    pub fn clear_receipt(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_receipt(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::receipt(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_receipt(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::receipt(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_receipt(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::receipt(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::receipt(::std::string::String::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::receipt(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_receipt(&mut self) -> ::std::string::String {
+        if self.has_receipt() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::receipt(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
 
-#[derive(PartialEq,Clone,Default)]
-pub struct Response {
-    // message fields
-    pub request_id: ::std::vec::Vec<u8>,
-    // message oneof groups
-    pub result: ::std::option::Option<Response_oneof_result>,
-    // special fields
-    unknown_fields: ::protobuf::UnknownFields,
-    cached_size: ::protobuf::CachedSize,
-}
+    pub fn get_receipt(&self) -> &str {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::receipt(ref v)) => v,
+            _ => "",
+        }
+    }
 
-// see codegen.rs for the explanation why impl Sync explicitly
-unsafe impl ::std::marker::Sync for Response {}
+    // uint64 transaction_count = 10;
 
-#[derive(Clone,PartialEq)]
-pub enum Response_oneof_result {
-    block_number(u64),
-    block(::std::string::String),
-    ts(FullTransaction),
-    none(bool),
-    peercount(u32),
-    call_result(::std::vec::Vec<u8>),
-    logs(::std::string::String),
-    receipt(::std::string::String),
-    transaction_count(u64),
-    code(::std::vec::Vec<u8>),
-    filter_id(u64),
-    uninstall_filter(bool),
-    filter_changes(::std::vec::Vec<u8>),
-    filter_logs(::std::vec::Vec<u8>),
-}
+    pub fn clear_transaction_count(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
 
-impl Response {
-    pub fn new() -> Response {
-        ::std::default::Default::default()
+    pub fn has_transaction_count(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::transaction_count(..)) => true,
+            _ => false,
+        }
     }
 
-    pub fn default_instance() -> &'static Response {
-        static mut instance: ::protobuf::lazy::Lazy<Response> = ::protobuf::lazy::Lazy {
-            lock: ::protobuf::lazy::ONCE_INIT,
-            ptr: 0 as *const Response,
-        };
-        unsafe {
-            instance.get(Response::new)
+    // Param is passed by value, moved
+    pub fn set_transaction_count(&mut self, v: u64) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::transaction_count(v))
+    }
+
+    pub fn get_transaction_count(&self) -> u64 {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::transaction_count(v)) => v,
+            _ => 0,
         }
     }
 
-    // bytes request_id = 1;
+    // bytes code = 11;
 
-    pub fn clear_request_id(&mut self) {
-        self.request_id.clear();
+    pub fn clear_code(&mut self) {
+        self.result = ::std::option::Option::None;
+    }
+
+    pub fn has_code(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::code(..)) => true,
+            _ => false,
+        }
     }
 
     // Param is passed by value, moved
-    pub fn set_request_id(&mut self, v: ::std::vec::Vec<u8>) {
-        self.request_id = v;
+    pub fn set_code(&mut self, v: ::std::vec::Vec<u8>) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::code(v))
     }
 
     // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_request_id(&mut self) -> &mut ::std::vec::Vec<u8> {
-        &mut self.request_id
+    pub fn mut_code(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Response_oneof_result::code(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::code(::std::vec::Vec::new()));
+        }
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::code(ref mut v)) => v,
+            _ => panic!(),
+        }
     }
 
     // Take field
-    pub fn take_request_id(&mut self) -> ::std::vec::Vec<u8> {
-        ::std::mem::replace(&mut self.request_id, ::std::vec::Vec::new())
+    pub fn take_code(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_code() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::code(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::vec::Vec::new()
+        }
     }
 
-    pub fn get_request_id(&self) -> &[u8] {
-        &self.request_id
+    pub fn get_code(&self) -> &[u8] {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::code(ref v)) => v,
+            _ => &[],
+        }
     }
 
-    fn get_request_id_for_reflect(&self) -> &::std::vec::Vec<u8> {
-        &self.request_id
+    // uint64 filter_id = 12;
+
+    pub fn clear_filter_id(&mut self) {
+        self.result = ::std::option::Option::None;
     }
 
-    fn mut_request_id_for_reflect(&mut self) -> &mut ::std::vec::Vec<u8> {
-        &mut self.request_id
+    pub fn has_filter_id(&self) -> bool {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::filter_id(..)) => true,
+            _ => false,
+        }
     }
 
-    // uint64 block_number = 2;
+    // Param is passed by value, moved
+    pub fn set_filter_id(&mut self, v: u64) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::filter_id(v))
+    }
 
-    pub fn clear_block_number(&mut self) {
+    pub fn get_filter_id(&self) -> u64 {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::filter_id(v)) => v,
+            _ => 0,
+        }
+    }
+
+    // bool uninstall_filter = 13;
+
+    pub fn clear_uninstall_filter(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_block_number(&self) -> bool {
+    pub fn has_uninstall_filter(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::block_number(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::uninstall_filter(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_block_number(&mut self, v: u64) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::block_number(v))
+    pub fn set_uninstall_filter(&mut self, v: bool) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::uninstall_filter(v))
     }
 
-    pub fn get_block_number(&self) -> u64 {
+    pub fn get_uninstall_filter(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::block_number(v)) => v,
-            _ => 0,
+            ::std::option::Option::Some(Response_oneof_result::uninstall_filter(v)) => v,
+            _ => false,
         }
     }
 
-    // string block = 3;
+    // bytes filter_changes = 14;
 
-    pub fn clear_block(&mut self) {
+    pub fn clear_filter_changes(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_block(&self) -> bool {
+    pub fn has_filter_changes(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::block(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::filter_changes(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_block(&mut self, v: ::std::string::String) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::block(v))
+    pub fn set_filter_changes(&mut self, v: ::std::vec::Vec<u8>) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::filter_changes(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_block(&mut self) -> &mut ::std::string::String {
-        if let ::std::option::Option::Some(Response_oneof_result::block(_)) = self.result {
+    pub fn mut_filter_changes(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Response_oneof_result::filter_changes(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::block(::std::string::String::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::filter_changes(::std::vec::Vec::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::block(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::filter_changes(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_block(&mut self) -> ::std::string::String {
-        if self.has_block() {
+    pub fn take_filter_changes(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_filter_changes() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::block(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::filter_changes(v)) => v,
                 _ => panic!(),
             }
         } else {
-            ::std::string::String::new()
+            ::std::vec::Vec::new()
         }
     }
 
-    pub fn get_block(&self) -> &str {
+    pub fn get_filter_changes(&self) -> &[u8] {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::block(ref v)) => v,
-            _ => "",
+            ::std::option::Option::Some(Response_oneof_result::filter_changes(ref v)) => v,
+            _ => &[],
         }
     }
 
-    // .FullTransaction ts = 4;
+    // bytes filter_logs = 15;
 
-    pub fn clear_ts(&mut self) {
+    pub fn clear_filter_logs(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_ts(&self) -> bool {
+    pub fn has_filter_logs(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::ts(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::filter_logs(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_ts(&mut self, v: FullTransaction) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::ts(v))
+    pub fn set_filter_logs(&mut self, v: ::std::vec::Vec<u8>) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::filter_logs(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_ts(&mut self) -> &mut FullTransaction {
-        if let ::std::option::Option::Some(Response_oneof_result::ts(_)) = self.result {
+    pub fn mut_filter_logs(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Response_oneof_result::filter_logs(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::ts(FullTransaction::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::filter_logs(::std::vec::Vec::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::ts(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::filter_logs(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_ts(&mut self) -> FullTransaction {
-        if self.has_ts() {
+    pub fn take_filter_logs(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_filter_logs() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::ts(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::filter_logs(v)) => v,
                 _ => panic!(),
             }
         } else {
-            FullTransaction::new()
+            ::std::vec::Vec::new()
         }
     }
 
-    pub fn get_ts(&self) -> &FullTransaction {
+    pub fn get_filter_logs(&self) -> &[u8] {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::ts(ref v)) => v,
-            _ => FullTransaction::default_instance(),
+            ::std::option::Option::Some(Response_oneof_result::filter_logs(ref v)) => v,
+            _ => &[],
         }
     }
 
-    // bool none = 5;
+    // string peer_bans = 16;
 
-    pub fn clear_none(&mut self) {
+    pub fn clear_peer_bans(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_none(&self) -> bool {
+    pub fn has_peer_bans(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::none(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::peer_bans(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_none(&mut self, v: bool) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::none(v))
+    pub fn set_peer_bans(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::peer_bans(v))
     }
 
-    pub fn get_none(&self) -> bool {
+    // Mutable pointer to the field.
+    pub fn mut_peer_bans(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::peer_bans(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::peer_bans(::std::string::String::new()));
+        }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::none(v)) => v,
-            _ => false,
+            ::std::option::Option::Some(Response_oneof_result::peer_bans(ref mut v)) => v,
+            _ => panic!(),
         }
     }
 
-    // uint32 peercount = 6;
+    // Take field
+    pub fn take_peer_bans(&mut self) -> ::std::string::String {
+        if self.has_peer_bans() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::peer_bans(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
 
-    pub fn clear_peercount(&mut self) {
+    pub fn get_peer_bans(&self) -> &str {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::peer_bans(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // bool enable_peer = 17;
+
+    pub fn clear_enable_peer(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_peercount(&self) -> bool {
+    pub fn has_enable_peer(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::peercount(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::enable_peer(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_peercount(&mut self, v: u32) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::peercount(v))
+    pub fn set_enable_peer(&mut self, v: bool) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::enable_peer(v))
     }
 
-    pub fn get_peercount(&self) -> u32 {
+    pub fn get_enable_peer(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::peercount(v)) => v,
-            _ => 0,
+            ::std::option::Option::Some(Response_oneof_result::enable_peer(v)) => v,
+            _ => false,
         }
     }
 
-    // bytes call_result = 7;
+    // string peers_info = 18;
 
-    pub fn clear_call_result(&mut self) {
+    pub fn clear_peers_info(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_call_result(&self) -> bool {
+    pub fn has_peers_info(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::call_result(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::peers_info(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_call_result(&mut self, v: ::std::vec::Vec<u8>) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::call_result(v))
+    pub fn set_peers_info(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::peers_info(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_call_result(&mut self) -> &mut ::std::vec::Vec<u8> {
-        if let ::std::option::Option::Some(Response_oneof_result::call_result(_)) = self.result {
+    pub fn mut_peers_info(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::peers_info(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::call_result(::std::vec::Vec::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::peers_info(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::call_result(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::peers_info(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_call_result(&mut self) -> ::std::vec::Vec<u8> {
-        if self.has_call_result() {
+    pub fn take_peers_info(&mut self) -> ::std::string::String {
+        if self.has_peers_info() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::call_result(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::peers_info(v)) => v,
                 _ => panic!(),
             }
         } else {
-            ::std::vec::Vec::new()
+            ::std::string::String::new()
         }
     }
 
-    pub fn get_call_result(&self) -> &[u8] {
+    pub fn get_peers_info(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::call_result(ref v)) => v,
-            _ => &[],
+            ::std::option::Option::Some(Response_oneof_result::peers_info(ref v)) => v,
+            _ => "",
         }
     }
 
-    // string logs = 8;
+    // string error_msg = 19;
 
-    pub fn clear_logs(&mut self) {
+    pub fn clear_error_msg(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_logs(&self) -> bool {
+    pub fn has_error_msg(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::logs(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::error_msg(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_logs(&mut self, v: ::std::string::String) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::logs(v))
+    pub fn set_error_msg(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::error_msg(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_logs(&mut self) -> &mut ::std::string::String {
-        if let ::std::option::Option::Some(Response_oneof_result::logs(_)) = self.result {
+    pub fn mut_error_msg(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::error_msg(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::logs(::std::string::String::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::error_msg(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::logs(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::error_msg(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_logs(&mut self) -> ::std::string::String {
-        if self.has_logs() {
+    pub fn take_error_msg(&mut self) -> ::std::string::String {
+        if self.has_error_msg() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::logs(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::error_msg(v)) => v,
                 _ => panic!(),
             }
         } else {
@@ -2154,48 +3167,48 @@ impl Response {
         }
     }
 
-    pub fn get_logs(&self) -> &str {
+    pub fn get_error_msg(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::logs(ref v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::error_msg(ref v)) => v,
             _ => "",
         }
     }
 
-    // string receipt = 9;
+    // string state_diff = 20;
 
-    pub fn clear_receipt(&mut self) {
+    pub fn clear_state_diff(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_receipt(&self) -> bool {
+    pub fn has_state_diff(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::receipt(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::state_diff(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_receipt(&mut self, v: ::std::string::String) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::receipt(v))
+    pub fn set_state_diff(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::state_diff(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_receipt(&mut self) -> &mut ::std::string::String {
-        if let ::std::option::Option::Some(Response_oneof_result::receipt(_)) = self.result {
+    pub fn mut_state_diff(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::state_diff(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::receipt(::std::string::String::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::state_diff(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::receipt(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::state_diff(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_receipt(&mut self) -> ::std::string::String {
-        if self.has_receipt() {
+    pub fn take_state_diff(&mut self) -> ::std::string::String {
+        if self.has_state_diff() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::receipt(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::state_diff(v)) => v,
                 _ => panic!(),
             }
         } else {
@@ -2203,232 +3216,255 @@ impl Response {
         }
     }
 
-    pub fn get_receipt(&self) -> &str {
+    pub fn get_state_diff(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::receipt(ref v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::state_diff(ref v)) => v,
             _ => "",
         }
     }
 
-    // uint64 transaction_count = 10;
+    // string transactions_by_account = 21;
 
-    pub fn clear_transaction_count(&mut self) {
+    pub fn clear_transactions_by_account(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_transaction_count(&self) -> bool {
+    pub fn has_transactions_by_account(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::transaction_count(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::transactions_by_account(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_transaction_count(&mut self, v: u64) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::transaction_count(v))
+    pub fn set_transactions_by_account(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::transactions_by_account(v))
     }
 
-    pub fn get_transaction_count(&self) -> u64 {
+    // Mutable pointer to the field.
+    pub fn mut_transactions_by_account(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::transactions_by_account(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::transactions_by_account(::std::string::String::new()));
+        }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::transaction_count(v)) => v,
-            _ => 0,
+            ::std::option::Option::Some(Response_oneof_result::transactions_by_account(ref mut v)) => v,
+            _ => panic!(),
         }
     }
 
-    // bytes code = 11;
+    // Take field
+    pub fn take_transactions_by_account(&mut self) -> ::std::string::String {
+        if self.has_transactions_by_account() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::transactions_by_account(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
 
-    pub fn clear_code(&mut self) {
+    pub fn get_transactions_by_account(&self) -> &str {
+        match self.result {
+            ::std::option::Option::Some(Response_oneof_result::transactions_by_account(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    // string storage_history = 22;
+
+    pub fn clear_storage_history(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_code(&self) -> bool {
+    pub fn has_storage_history(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::code(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::storage_history(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_code(&mut self, v: ::std::vec::Vec<u8>) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::code(v))
+    pub fn set_storage_history(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::storage_history(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_code(&mut self) -> &mut ::std::vec::Vec<u8> {
-        if let ::std::option::Option::Some(Response_oneof_result::code(_)) = self.result {
+    pub fn mut_storage_history(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::storage_history(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::code(::std::vec::Vec::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::storage_history(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::code(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::storage_history(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_code(&mut self) -> ::std::vec::Vec<u8> {
-        if self.has_code() {
+    pub fn take_storage_history(&mut self) -> ::std::string::String {
+        if self.has_storage_history() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::code(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::storage_history(v)) => v,
                 _ => panic!(),
             }
         } else {
-            ::std::vec::Vec::new()
+            ::std::string::String::new()
         }
     }
 
-    pub fn get_code(&self) -> &[u8] {
+    pub fn get_storage_history(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::code(ref v)) => v,
-            _ => &[],
+            ::std::option::Option::Some(Response_oneof_result::storage_history(ref v)) => v,
+            _ => "",
         }
     }
 
-    // uint64 filter_id = 12;
+    // bytes light_client_proof = 23;
 
-    pub fn clear_filter_id(&mut self) {
+    pub fn clear_light_client_proof(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_filter_id(&self) -> bool {
+    pub fn has_light_client_proof(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_id(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::light_client_proof(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_filter_id(&mut self, v: u64) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::filter_id(v))
+    pub fn set_light_client_proof(&mut self, v: ::std::vec::Vec<u8>) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::light_client_proof(v))
     }
 
-    pub fn get_filter_id(&self) -> u64 {
-        match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_id(v)) => v,
-            _ => 0,
+    // Mutable pointer to the field.
+    pub fn mut_light_client_proof(&mut self) -> &mut ::std::vec::Vec<u8> {
+        if let ::std::option::Option::Some(Response_oneof_result::light_client_proof(_)) = self.result {
+        } else {
+            self.result = ::std::option::Option::Some(Response_oneof_result::light_client_proof(::std::vec::Vec::new()));
         }
-    }
-
-    // bool uninstall_filter = 13;
-
-    pub fn clear_uninstall_filter(&mut self) {
-        self.result = ::std::option::Option::None;
-    }
-
-    pub fn has_uninstall_filter(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::uninstall_filter(..)) => true,
-            _ => false,
+            ::std::option::Option::Some(Response_oneof_result::light_client_proof(ref mut v)) => v,
+            _ => panic!(),
         }
     }
 
-    // Param is passed by value, moved
-    pub fn set_uninstall_filter(&mut self, v: bool) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::uninstall_filter(v))
+    // Take field
+    pub fn take_light_client_proof(&mut self) -> ::std::vec::Vec<u8> {
+        if self.has_light_client_proof() {
+            match self.result.take() {
+                ::std::option::Option::Some(Response_oneof_result::light_client_proof(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::vec::Vec::new()
+        }
     }
 
-    pub fn get_uninstall_filter(&self) -> bool {
+    pub fn get_light_client_proof(&self) -> &[u8] {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::uninstall_filter(v)) => v,
-            _ => false,
+            ::std::option::Option::Some(Response_oneof_result::light_client_proof(ref v)) => v,
+            _ => &[],
         }
     }
 
-    // bytes filter_changes = 14;
+    // string transaction_trace = 24;
 
-    pub fn clear_filter_changes(&mut self) {
+    pub fn clear_transaction_trace(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_filter_changes(&self) -> bool {
+    pub fn has_transaction_trace(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_changes(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::transaction_trace(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_filter_changes(&mut self, v: ::std::vec::Vec<u8>) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::filter_changes(v))
+    pub fn set_transaction_trace(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::transaction_trace(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_filter_changes(&mut self) -> &mut ::std::vec::Vec<u8> {
-        if let ::std::option::Option::Some(Response_oneof_result::filter_changes(_)) = self.result {
+    pub fn mut_transaction_trace(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::transaction_trace(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::filter_changes(::std::vec::Vec::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::transaction_trace(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_changes(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::transaction_trace(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_filter_changes(&mut self) -> ::std::vec::Vec<u8> {
-        if self.has_filter_changes() {
+    pub fn take_transaction_trace(&mut self) -> ::std::string::String {
+        if self.has_transaction_trace() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::filter_changes(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::transaction_trace(v)) => v,
                 _ => panic!(),
             }
         } else {
-            ::std::vec::Vec::new()
+            ::std::string::String::new()
         }
     }
 
-    pub fn get_filter_changes(&self) -> &[u8] {
+    pub fn get_transaction_trace(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_changes(ref v)) => v,
-            _ => &[],
+            ::std::option::Option::Some(Response_oneof_result::transaction_trace(ref v)) => v,
+            _ => "",
         }
     }
 
-    // bytes filter_logs = 15;
+    // string consensus_metrics = 25;
 
-    pub fn clear_filter_logs(&mut self) {
+    pub fn clear_consensus_metrics(&mut self) {
         self.result = ::std::option::Option::None;
     }
 
-    pub fn has_filter_logs(&self) -> bool {
+    pub fn has_consensus_metrics(&self) -> bool {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_logs(..)) => true,
+            ::std::option::Option::Some(Response_oneof_result::consensus_metrics(..)) => true,
             _ => false,
         }
     }
 
     // Param is passed by value, moved
-    pub fn set_filter_logs(&mut self, v: ::std::vec::Vec<u8>) {
-        self.result = ::std::option::Option::Some(Response_oneof_result::filter_logs(v))
+    pub fn set_consensus_metrics(&mut self, v: ::std::string::String) {
+        self.result = ::std::option::Option::Some(Response_oneof_result::consensus_metrics(v))
     }
 
     // Mutable pointer to the field.
-    pub fn mut_filter_logs(&mut self) -> &mut ::std::vec::Vec<u8> {
-        if let ::std::option::Option::Some(Response_oneof_result::filter_logs(_)) = self.result {
+    pub fn mut_consensus_metrics(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(Response_oneof_result::consensus_metrics(_)) = self.result {
         } else {
-            self.result = ::std::option::Option::Some(Response_oneof_result::filter_logs(::std::vec::Vec::new()));
+            self.result = ::std::option::Option::Some(Response_oneof_result::consensus_metrics(::std::string::String::new()));
         }
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_logs(ref mut v)) => v,
+            ::std::option::Option::Some(Response_oneof_result::consensus_metrics(ref mut v)) => v,
             _ => panic!(),
         }
     }
 
     // Take field
-    pub fn take_filter_logs(&mut self) -> ::std::vec::Vec<u8> {
-        if self.has_filter_logs() {
+    pub fn take_consensus_metrics(&mut self) -> ::std::string::String {
+        if self.has_consensus_metrics() {
             match self.result.take() {
-                ::std::option::Option::Some(Response_oneof_result::filter_logs(v)) => v,
+                ::std::option::Option::Some(Response_oneof_result::consensus_metrics(v)) => v,
                 _ => panic!(),
             }
         } else {
-            ::std::vec::Vec::new()
+            ::std::string::String::new()
         }
     }
 
-    pub fn get_filter_logs(&self) -> &[u8] {
+    pub fn get_consensus_metrics(&self) -> &str {
         match self.result {
-            ::std::option::Option::Some(Response_oneof_result::filter_logs(ref v)) => v,
-            _ => &[],
+            ::std::option::Option::Some(Response_oneof_result::consensus_metrics(ref v)) => v,
+            _ => "",
         }
     }
 }
@@ -2534,6 +3570,66 @@ impl ::protobuf::Message for Response {
                     }
                     self.result = ::std::option::Option::Some(Response_oneof_result::filter_logs(is.read_bytes()?));
                 },
+                16 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::peer_bans(is.read_string()?));
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::enable_peer(is.read_bool()?));
+                },
+                18 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::peers_info(is.read_string()?));
+                },
+                19 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::error_msg(is.read_string()?));
+                },
+                20 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::state_diff(is.read_string()?));
+                },
+                21 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::transactions_by_account(is.read_string()?));
+                },
+                22 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::storage_history(is.read_string()?));
+                },
+                23 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::light_client_proof(is.read_bytes()?));
+                },
+                24 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::transaction_trace(is.read_string()?));
+                },
+                25 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeLengthDelimited {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    self.result = ::std::option::Option::Some(Response_oneof_result::consensus_metrics(is.read_string()?));
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -2594,6 +3690,36 @@ impl ::protobuf::Message for Response {
                 &Response_oneof_result::filter_logs(ref v) => {
                     my_size += ::protobuf::rt::bytes_size(15, &v);
                 },
+                &Response_oneof_result::peer_bans(ref v) => {
+                    my_size += ::protobuf::rt::string_size(16, &v);
+                },
+                &Response_oneof_result::enable_peer(v) => {
+                    my_size += 3;
+                },
+                &Response_oneof_result::peers_info(ref v) => {
+                    my_size += ::protobuf::rt::string_size(18, &v);
+                },
+                &Response_oneof_result::error_msg(ref v) => {
+                    my_size += ::protobuf::rt::string_size(19, &v);
+                },
+                &Response_oneof_result::state_diff(ref v) => {
+                    my_size += ::protobuf::rt::string_size(20, &v);
+                },
+                &Response_oneof_result::transactions_by_account(ref v) => {
+                    my_size += ::protobuf::rt::string_size(21, &v);
+                },
+                &Response_oneof_result::storage_history(ref v) => {
+                    my_size += ::protobuf::rt::string_size(22, &v);
+                },
+                &Response_oneof_result::light_client_proof(ref v) => {
+                    my_size += ::protobuf::rt::bytes_size(23, &v);
+                },
+                &Response_oneof_result::transaction_trace(ref v) => {
+                    my_size += ::protobuf::rt::string_size(24, &v);
+                },
+                &Response_oneof_result::consensus_metrics(ref v) => {
+                    my_size += ::protobuf::rt::string_size(25, &v);
+                },
             };
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
@@ -2651,6 +3777,36 @@ impl ::protobuf::Message for Response {
                 &Response_oneof_result::filter_logs(ref v) => {
                     os.write_bytes(15, v)?;
                 },
+                &Response_oneof_result::peer_bans(ref v) => {
+                    os.write_string(16, v)?;
+                },
+                &Response_oneof_result::enable_peer(v) => {
+                    os.write_bool(17, v)?;
+                },
+                &Response_oneof_result::peers_info(ref v) => {
+                    os.write_string(18, v)?;
+                },
+                &Response_oneof_result::error_msg(ref v) => {
+                    os.write_string(19, v)?;
+                },
+                &Response_oneof_result::state_diff(ref v) => {
+                    os.write_string(20, v)?;
+                },
+                &Response_oneof_result::transactions_by_account(ref v) => {
+                    os.write_string(21, v)?;
+                },
+                &Response_oneof_result::storage_history(ref v) => {
+                    os.write_string(22, v)?;
+                },
+                &Response_oneof_result::light_client_proof(ref v) => {
+                    os.write_bytes(23, v)?;
+                },
+                &Response_oneof_result::transaction_trace(ref v) => {
+                    os.write_string(24, v)?;
+                },
+                &Response_oneof_result::consensus_metrics(ref v) => {
+                    os.write_string(25, v)?;
+                },
             };
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
@@ -2772,6 +3928,56 @@ impl ::protobuf::MessageStatic for Response {
                     Response::has_filter_logs,
                     Response::get_filter_logs,
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "peer_bans",
+                    Response::has_peer_bans,
+                    Response::get_peer_bans,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bool_accessor::<_>(
+                    "enable_peer",
+                    Response::has_enable_peer,
+                    Response::get_enable_peer,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "peers_info",
+                    Response::has_peers_info,
+                    Response::get_peers_info,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "error_msg",
+                    Response::has_error_msg,
+                    Response::get_error_msg,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "state_diff",
+                    Response::has_state_diff,
+                    Response::get_state_diff,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "transactions_by_account",
+                    Response::has_transactions_by_account,
+                    Response::get_transactions_by_account,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "storage_history",
+                    Response::has_storage_history,
+                    Response::get_storage_history,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_bytes_accessor::<_>(
+                    "light_client_proof",
+                    Response::has_light_client_proof,
+                    Response::get_light_client_proof,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "transaction_trace",
+                    Response::has_transaction_trace,
+                    Response::get_transaction_trace,
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_string_accessor::<_>(
+                    "consensus_metrics",
+                    Response::has_consensus_metrics,
+                    Response::get_consensus_metrics,
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Response>(
                     "Response",
                     fields,
@@ -2799,6 +4005,16 @@ impl ::protobuf::Clear for Response {
         self.clear_uninstall_filter();
         self.clear_filter_changes();
         self.clear_filter_logs();
+        self.clear_peer_bans();
+        self.clear_enable_peer();
+        self.clear_peers_info();
+        self.clear_error_msg();
+        self.clear_state_diff();
+        self.clear_transactions_by_account();
+        self.clear_storage_history();
+        self.clear_light_client_proof();
+        self.clear_transaction_trace();
+        self.clear_consensus_metrics();
         self.unknown_fields.clear();
     }
 }
@@ -2874,7 +4090,7 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \n\rrequest.proto\x1a\x10blockchain.proto\"V\n\x04Call\x12\x12\n\x04from\
     \x18\x01\x20\x01(\x0cR\x04from\x12\x0e\n\x02to\x18\x02\x20\x01(\x0cR\x02\
     to\x12\x12\n\x04data\x18\x03\x20\x01(\x0cR\x04data\x12\x16\n\x06height\
-    \x18\x04\x20\x01(\tR\x06height\"\xf7\x04\n\x07Request\x12\x1d\n\nrequest\
+    \x18\x04\x20\x01(\tR\x06height\"\xa5\x08\n\x07Request\x12\x1d\n\nrequest\
     _id\x18\x01\x20\x01(\x0cR\trequestId\x12#\n\x0cblock_number\x18\x02\x20\
     \x01(\x08H\0R\x0bblockNumber\x12$\n\rblock_by_hash\x18\x03\x20\x01(\tH\0\
     R\x0bblockByHash\x12(\n\x0fblock_by_height\x18\x04\x20\x01(\tH\0R\rblock\
@@ -2889,153 +4105,172 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     \x18\x0e\x20\x01(\x08H\0R\x0enewBlockFilter\x12+\n\x10uninstall_filter\
     \x18\x0f\x20\x01(\x04H\0R\x0funinstallFilter\x12'\n\x0efilter_changes\
     \x18\x10\x20\x01(\x04H\0R\rfilterChanges\x12!\n\x0bfilter_logs\x18\x11\
-    \x20\x01(\x04H\0R\nfilterLogsB\x05\n\x03req\"\x9f\x01\n\x0fFullTransacti\
-    on\x124\n\x0btransaction\x18\x01\x20\x01(\x0b2\x12.SignedTransactionR\
-    \x0btransaction\x12!\n\x0cblock_number\x18\x02\x20\x01(\x04R\x0bblockNum\
-    ber\x12\x1d\n\nblock_hash\x18\x03\x20\x01(\x0cR\tblockHash\x12\x14\n\x05\
-    index\x18\x04\x20\x01(\rR\x05index\"\xfc\x03\n\x08Response\x12\x1d\n\nre\
-    quest_id\x18\x01\x20\x01(\x0cR\trequestId\x12#\n\x0cblock_number\x18\x02\
-    \x20\x01(\x04H\0R\x0bblockNumber\x12\x16\n\x05block\x18\x03\x20\x01(\tH\
-    \0R\x05block\x12\"\n\x02ts\x18\x04\x20\x01(\x0b2\x10.FullTransactionH\0R\
-    \x02ts\x12\x14\n\x04none\x18\x05\x20\x01(\x08H\0R\x04none\x12\x1e\n\tpee\
-    rcount\x18\x06\x20\x01(\rH\0R\tpeercount\x12!\n\x0bcall_result\x18\x07\
-    \x20\x01(\x0cH\0R\ncallResult\x12\x14\n\x04logs\x18\x08\x20\x01(\tH\0R\
-    \x04logs\x12\x1a\n\x07receipt\x18\t\x20\x01(\tH\0R\x07receipt\x12-\n\x11\
-    transaction_count\x18\n\x20\x01(\x04H\0R\x10transactionCount\x12\x14\n\
-    \x04code\x18\x0b\x20\x01(\x0cH\0R\x04code\x12\x1d\n\tfilter_id\x18\x0c\
-    \x20\x01(\x04H\0R\x08filterId\x12+\n\x10uninstall_filter\x18\r\x20\x01(\
-    \x08H\0R\x0funinstallFilter\x12'\n\x0efilter_changes\x18\x0e\x20\x01(\
-    \x0cH\0R\rfilterChanges\x12!\n\x0bfilter_logs\x18\x0f\x20\x01(\x0cH\0R\n\
-    filterLogsB\x08\n\x06result*$\n\x08BlockTag\x12\n\n\x06Latest\x10\0\x12\
-    \x0c\n\x08Earliest\x10\x01J\xcd\x14\n\x06\x12\x04\0\0?\x01\n\x08\n\x01\
-    \x0c\x12\x03\0\0\x12\n\t\n\x02\x03\0\x12\x03\x02\x07\x19\n\n\n\x02\x05\0\
-    \x12\x04\x04\0\x07\x01\n\n\n\x03\x05\0\x01\x12\x03\x04\x05\r\n\x0b\n\x04\
-    \x05\0\x02\0\x12\x03\x05\x04\x0f\n\x0c\n\x05\x05\0\x02\0\x01\x12\x03\x05\
-    \x04\n\n\x0c\n\x05\x05\0\x02\0\x02\x12\x03\x05\r\x0e\n\x0b\n\x04\x05\0\
-    \x02\x01\x12\x03\x06\x04\x11\n\x0c\n\x05\x05\0\x02\x01\x01\x12\x03\x06\
-    \x04\x0c\n\x0c\n\x05\x05\0\x02\x01\x02\x12\x03\x06\x0f\x10\n\n\n\x02\x04\
-    \0\x12\x04\t\0\x0e\x01\n\n\n\x03\x04\0\x01\x12\x03\t\x08\x0c\n\x0b\n\x04\
-    \x04\0\x02\0\x12\x03\n\x04\x13\n\r\n\x05\x04\0\x02\0\x04\x12\x04\n\x04\t\
-    \x0e\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\n\x04\t\n\x0c\n\x05\x04\0\x02\0\
-    \x01\x12\x03\n\n\x0e\n\x0c\n\x05\x04\0\x02\0\x03\x12\x03\n\x11\x12\n\x0b\
-    \n\x04\x04\0\x02\x01\x12\x03\x0b\x04\x11\n\r\n\x05\x04\0\x02\x01\x04\x12\
-    \x04\x0b\x04\n\x13\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\x0b\x04\t\n\x0c\
-    \n\x05\x04\0\x02\x01\x01\x12\x03\x0b\n\x0c\n\x0c\n\x05\x04\0\x02\x01\x03\
-    \x12\x03\x0b\x0f\x10\n\x0b\n\x04\x04\0\x02\x02\x12\x03\x0c\x04\x13\n\r\n\
-    \x05\x04\0\x02\x02\x04\x12\x04\x0c\x04\x0b\x11\n\x0c\n\x05\x04\0\x02\x02\
-    \x05\x12\x03\x0c\x04\t\n\x0c\n\x05\x04\0\x02\x02\x01\x12\x03\x0c\n\x0e\n\
-    \x0c\n\x05\x04\0\x02\x02\x03\x12\x03\x0c\x11\x12\n\x0b\n\x04\x04\0\x02\
-    \x03\x12\x03\r\x04\x16\n\r\n\x05\x04\0\x02\x03\x04\x12\x04\r\x04\x0c\x13\
-    \n\x0c\n\x05\x04\0\x02\x03\x05\x12\x03\r\x04\n\n\x0c\n\x05\x04\0\x02\x03\
-    \x01\x12\x03\r\x0b\x11\n\x0c\n\x05\x04\0\x02\x03\x03\x12\x03\r\x14\x15\n\
-    \n\n\x02\x04\x01\x12\x04\x10\0$\x01\n\n\n\x03\x04\x01\x01\x12\x03\x10\
-    \x08\x0f\n\x0b\n\x04\x04\x01\x02\0\x12\x03\x11\x04\x19\n\r\n\x05\x04\x01\
-    \x02\0\x04\x12\x04\x11\x04\x10\x11\n\x0c\n\x05\x04\x01\x02\0\x05\x12\x03\
-    \x11\x04\t\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x03\x11\n\x14\n\x0c\n\x05\
-    \x04\x01\x02\0\x03\x12\x03\x11\x17\x18\n\x0c\n\x04\x04\x01\x08\0\x12\x04\
-    \x12\x04#\x05\n\x0c\n\x05\x04\x01\x08\0\x01\x12\x03\x12\n\r\n\x0b\n\x04\
-    \x04\x01\x02\x01\x12\x03\x13\x08\x1e\n\x0c\n\x05\x04\x01\x02\x01\x05\x12\
-    \x03\x13\x08\x0c\n\x0c\n\x05\x04\x01\x02\x01\x01\x12\x03\x13\r\x19\n\x0c\
-    \n\x05\x04\x01\x02\x01\x03\x12\x03\x13\x1c\x1d\n\x0b\n\x04\x04\x01\x02\
-    \x02\x12\x03\x14\x08!\n\x0c\n\x05\x04\x01\x02\x02\x05\x12\x03\x14\x08\
-    \x0e\n\x0c\n\x05\x04\x01\x02\x02\x01\x12\x03\x14\x0f\x1c\n\x0c\n\x05\x04\
-    \x01\x02\x02\x03\x12\x03\x14\x1f\x20\n\x0b\n\x04\x04\x01\x02\x03\x12\x03\
-    \x15\x08#\n\x0c\n\x05\x04\x01\x02\x03\x05\x12\x03\x15\x08\x0e\n\x0c\n\
-    \x05\x04\x01\x02\x03\x01\x12\x03\x15\x0f\x1e\n\x0c\n\x05\x04\x01\x02\x03\
-    \x03\x12\x03\x15!\"\n\x0b\n\x04\x04\x01\x02\x04\x12\x03\x16\x08\x1e\n\
-    \x0c\n\x05\x04\x01\x02\x04\x05\x12\x03\x16\x08\r\n\x0c\n\x05\x04\x01\x02\
-    \x04\x01\x12\x03\x16\x0e\x19\n\x0c\n\x05\x04\x01\x02\x04\x03\x12\x03\x16\
-    \x1c\x1d\n\x0b\n\x04\x04\x01\x02\x05\x12\x03\x17\x08\x1a\n\x0c\n\x05\x04\
-    \x01\x02\x05\x05\x12\x03\x17\x08\x0e\n\x0c\n\x05\x04\x01\x02\x05\x01\x12\
-    \x03\x17\x0f\x15\n\x0c\n\x05\x04\x01\x02\x05\x03\x12\x03\x17\x18\x19\n\
-    \x0b\n\x04\x04\x01\x02\x06\x12\x03\x18\x08\x1b\n\x0c\n\x05\x04\x01\x02\
-    \x06\x05\x12\x03\x18\x08\x0c\n\x0c\n\x05\x04\x01\x02\x06\x01\x12\x03\x18\
-    \r\x16\n\x0c\n\x05\x04\x01\x02\x06\x03\x12\x03\x18\x19\x1a\n\x0b\n\x04\
-    \x04\x01\x02\x07\x12\x03\x19\x08\x16\n\x0c\n\x05\x04\x01\x02\x07\x06\x12\
-    \x03\x19\x08\x0c\n\x0c\n\x05\x04\x01\x02\x07\x01\x12\x03\x19\r\x11\n\x0c\
-    \n\x05\x04\x01\x02\x07\x03\x12\x03\x19\x14\x15\n\x0b\n\x04\x04\x01\x02\
-    \x08\x12\x03\x1a\x08\x1a\n\x0c\n\x05\x04\x01\x02\x08\x05\x12\x03\x1a\x08\
-    \x0e\n\x0c\n\x05\x04\x01\x02\x08\x01\x12\x03\x1a\x0f\x15\n\x0c\n\x05\x04\
-    \x01\x02\x08\x03\x12\x03\x1a\x18\x19\n\x0b\n\x04\x04\x01\x02\t\x12\x03\
-    \x1b\x08'\n\x0c\n\x05\x04\x01\x02\t\x05\x12\x03\x1b\x08\r\n\x0c\n\x05\
-    \x04\x01\x02\t\x01\x12\x03\x1b\x0e!\n\x0c\n\x05\x04\x01\x02\t\x03\x12\
-    \x03\x1b$&\n\x0b\n\x04\x04\x01\x02\n\x12\x03\x1c\x08&\n\x0c\n\x05\x04\
-    \x01\x02\n\x05\x12\x03\x1c\x08\x0e\n\x0c\n\x05\x04\x01\x02\n\x01\x12\x03\
-    \x1c\x0f\x20\n\x0c\n\x05\x04\x01\x02\n\x03\x12\x03\x1c#%\n\x0b\n\x04\x04\
-    \x01\x02\x0b\x12\x03\x1d\x08\x19\n\x0c\n\x05\x04\x01\x02\x0b\x05\x12\x03\
-    \x1d\x08\x0e\n\x0c\n\x05\x04\x01\x02\x0b\x01\x12\x03\x1d\x0f\x13\n\x0c\n\
-    \x05\x04\x01\x02\x0b\x03\x12\x03\x1d\x16\x18\n\x0b\n\x04\x04\x01\x02\x0c\
-    \x12\x03\x1e\x08\x1f\n\x0c\n\x05\x04\x01\x02\x0c\x05\x12\x03\x1e\x08\x0e\
-    \n\x0c\n\x05\x04\x01\x02\x0c\x01\x12\x03\x1e\x0f\x19\n\x0c\n\x05\x04\x01\
-    \x02\x0c\x03\x12\x03\x1e\x1c\x1e\n\x0b\n\x04\x04\x01\x02\r\x12\x03\x1f\
-    \x08#\n\x0c\n\x05\x04\x01\x02\r\x05\x12\x03\x1f\x08\x0c\n\x0c\n\x05\x04\
-    \x01\x02\r\x01\x12\x03\x1f\r\x1d\n\x0c\n\x05\x04\x01\x02\r\x03\x12\x03\
-    \x1f\x20\"\n\x0b\n\x04\x04\x01\x02\x0e\x12\x03\x20\x08%\n\x0c\n\x05\x04\
-    \x01\x02\x0e\x05\x12\x03\x20\x08\x0e\n\x0c\n\x05\x04\x01\x02\x0e\x01\x12\
-    \x03\x20\x0f\x1f\n\x0c\n\x05\x04\x01\x02\x0e\x03\x12\x03\x20\"$\n\x0b\n\
-    \x04\x04\x01\x02\x0f\x12\x03!\x08#\n\x0c\n\x05\x04\x01\x02\x0f\x05\x12\
-    \x03!\x08\x0e\n\x0c\n\x05\x04\x01\x02\x0f\x01\x12\x03!\x0f\x1d\n\x0c\n\
-    \x05\x04\x01\x02\x0f\x03\x12\x03!\x20\"\n\x0b\n\x04\x04\x01\x02\x10\x12\
-    \x03\"\x08\x20\n\x0c\n\x05\x04\x01\x02\x10\x05\x12\x03\"\x08\x0e\n\x0c\n\
-    \x05\x04\x01\x02\x10\x01\x12\x03\"\x0f\x1a\n\x0c\n\x05\x04\x01\x02\x10\
-    \x03\x12\x03\"\x1d\x1f\n\n\n\x02\x04\x02\x12\x04&\0+\x01\n\n\n\x03\x04\
-    \x02\x01\x12\x03&\x08\x17\n\x0b\n\x04\x04\x02\x02\0\x12\x03'\x04&\n\r\n\
-    \x05\x04\x02\x02\0\x04\x12\x04'\x04&\x19\n\x0c\n\x05\x04\x02\x02\0\x06\
-    \x12\x03'\x04\x15\n\x0c\n\x05\x04\x02\x02\0\x01\x12\x03'\x16!\n\x0c\n\
-    \x05\x04\x02\x02\0\x03\x12\x03'$%\n\x0b\n\x04\x04\x02\x02\x01\x12\x03(\
-    \x04\x1c\n\r\n\x05\x04\x02\x02\x01\x04\x12\x04(\x04'&\n\x0c\n\x05\x04\
-    \x02\x02\x01\x05\x12\x03(\x04\n\n\x0c\n\x05\x04\x02\x02\x01\x01\x12\x03(\
-    \x0b\x17\n\x0c\n\x05\x04\x02\x02\x01\x03\x12\x03(\x1a\x1b\n\x0b\n\x04\
-    \x04\x02\x02\x02\x12\x03)\x04\x19\n\r\n\x05\x04\x02\x02\x02\x04\x12\x04)\
-    \x04(\x1c\n\x0c\n\x05\x04\x02\x02\x02\x05\x12\x03)\x04\t\n\x0c\n\x05\x04\
-    \x02\x02\x02\x01\x12\x03)\n\x14\n\x0c\n\x05\x04\x02\x02\x02\x03\x12\x03)\
-    \x17\x18\n\x0b\n\x04\x04\x02\x02\x03\x12\x03*\x04\x15\n\r\n\x05\x04\x02\
-    \x02\x03\x04\x12\x04*\x04)\x19\n\x0c\n\x05\x04\x02\x02\x03\x05\x12\x03*\
-    \x04\n\n\x0c\n\x05\x04\x02\x02\x03\x01\x12\x03*\x0b\x10\n\x0c\n\x05\x04\
-    \x02\x02\x03\x03\x12\x03*\x13\x14\n\n\n\x02\x04\x03\x12\x04-\0?\x01\n\n\
-    \n\x03\x04\x03\x01\x12\x03-\x08\x10\n\x0b\n\x04\x04\x03\x02\0\x12\x03.\
-    \x04\x19\n\r\n\x05\x04\x03\x02\0\x04\x12\x04.\x04-\x12\n\x0c\n\x05\x04\
-    \x03\x02\0\x05\x12\x03.\x04\t\n\x0c\n\x05\x04\x03\x02\0\x01\x12\x03.\n\
-    \x14\n\x0c\n\x05\x04\x03\x02\0\x03\x12\x03.\x17\x18\n\x0c\n\x04\x04\x03\
-    \x08\0\x12\x04/\x04>\x05\n\x0c\n\x05\x04\x03\x08\0\x01\x12\x03/\n\x10\n\
-    \x0b\n\x04\x04\x03\x02\x01\x12\x030\x08\x20\n\x0c\n\x05\x04\x03\x02\x01\
-    \x05\x12\x030\x08\x0e\n\x0c\n\x05\x04\x03\x02\x01\x01\x12\x030\x0f\x1b\n\
-    \x0c\n\x05\x04\x03\x02\x01\x03\x12\x030\x1e\x1f\n\x0b\n\x04\x04\x03\x02\
-    \x02\x12\x031\x08\x19\n\x0c\n\x05\x04\x03\x02\x02\x05\x12\x031\x08\x0e\n\
-    \x0c\n\x05\x04\x03\x02\x02\x01\x12\x031\x0f\x14\n\x0c\n\x05\x04\x03\x02\
-    \x02\x03\x12\x031\x17\x18\n\x0b\n\x04\x04\x03\x02\x03\x12\x032\x08\x1f\n\
-    \x0c\n\x05\x04\x03\x02\x03\x06\x12\x032\x08\x17\n\x0c\n\x05\x04\x03\x02\
-    \x03\x01\x12\x032\x18\x1a\n\x0c\n\x05\x04\x03\x02\x03\x03\x12\x032\x1d\
-    \x1e\n\x0b\n\x04\x04\x03\x02\x04\x12\x033\x08\x16\n\x0c\n\x05\x04\x03\
-    \x02\x04\x05\x12\x033\x08\x0c\n\x0c\n\x05\x04\x03\x02\x04\x01\x12\x033\r\
-    \x11\n\x0c\n\x05\x04\x03\x02\x04\x03\x12\x033\x14\x15\n\x0b\n\x04\x04\
-    \x03\x02\x05\x12\x034\x08\x1d\n\x0c\n\x05\x04\x03\x02\x05\x05\x12\x034\
-    \x08\x0e\n\x0c\n\x05\x04\x03\x02\x05\x01\x12\x034\x0f\x18\n\x0c\n\x05\
-    \x04\x03\x02\x05\x03\x12\x034\x1b\x1c\n\x0b\n\x04\x04\x03\x02\x06\x12\
-    \x035\x08\x1e\n\x0c\n\x05\x04\x03\x02\x06\x05\x12\x035\x08\r\n\x0c\n\x05\
-    \x04\x03\x02\x06\x01\x12\x035\x0e\x19\n\x0c\n\x05\x04\x03\x02\x06\x03\
-    \x12\x035\x1c\x1d\n\x0b\n\x04\x04\x03\x02\x07\x12\x036\x08\x18\n\x0c\n\
-    \x05\x04\x03\x02\x07\x05\x12\x036\x08\x0e\n\x0c\n\x05\x04\x03\x02\x07\
-    \x01\x12\x036\x0f\x13\n\x0c\n\x05\x04\x03\x02\x07\x03\x12\x036\x16\x17\n\
-    \x0b\n\x04\x04\x03\x02\x08\x12\x037\x08\x1b\n\x0c\n\x05\x04\x03\x02\x08\
-    \x05\x12\x037\x08\x0e\n\x0c\n\x05\x04\x03\x02\x08\x01\x12\x037\x0f\x16\n\
-    \x0c\n\x05\x04\x03\x02\x08\x03\x12\x037\x19\x1a\n\x0b\n\x04\x04\x03\x02\
-    \t\x12\x038\x08&\n\x0c\n\x05\x04\x03\x02\t\x05\x12\x038\x08\x0e\n\x0c\n\
-    \x05\x04\x03\x02\t\x01\x12\x038\x0f\x20\n\x0c\n\x05\x04\x03\x02\t\x03\
-    \x12\x038#%\n\x0b\n\x04\x04\x03\x02\n\x12\x039\x08\x18\n\x0c\n\x05\x04\
-    \x03\x02\n\x05\x12\x039\x08\r\n\x0c\n\x05\x04\x03\x02\n\x01\x12\x039\x0e\
-    \x12\n\x0c\n\x05\x04\x03\x02\n\x03\x12\x039\x15\x17\n\x0b\n\x04\x04\x03\
-    \x02\x0b\x12\x03:\x08\x1e\n\x0c\n\x05\x04\x03\x02\x0b\x05\x12\x03:\x08\
-    \x0e\n\x0c\n\x05\x04\x03\x02\x0b\x01\x12\x03:\x0f\x18\n\x0c\n\x05\x04\
-    \x03\x02\x0b\x03\x12\x03:\x1b\x1d\n\x0b\n\x04\x04\x03\x02\x0c\x12\x03;\
-    \x08#\n\x0c\n\x05\x04\x03\x02\x0c\x05\x12\x03;\x08\x0c\n\x0c\n\x05\x04\
-    \x03\x02\x0c\x01\x12\x03;\r\x1d\n\x0c\n\x05\x04\x03\x02\x0c\x03\x12\x03;\
-    \x20\"\n\x0b\n\x04\x04\x03\x02\r\x12\x03<\x08\"\n\x0c\n\x05\x04\x03\x02\
-    \r\x05\x12\x03<\x08\r\n\x0c\n\x05\x04\x03\x02\r\x01\x12\x03<\x0e\x1c\n\
-    \x0c\n\x05\x04\x03\x02\r\x03\x12\x03<\x1f!\n\x0b\n\x04\x04\x03\x02\x0e\
-    \x12\x03=\x08\x1f\n\x0c\n\x05\x04\x03\x02\x0e\x05\x12\x03=\x08\r\n\x0c\n\
-    \x05\x04\x03\x02\x0e\x01\x12\x03=\x0e\x19\n\x0c\n\x05\x04\x03\x02\x0e\
-    \x03\x12\x03=\x1c\x1eb\x06proto3\
+    \x20\x01(\x04H\0R\nfilterLogs\x12\x1d\n\tpeer_bans\x18\x12\x20\x01(\x08H\
+    \0R\x08peerBans\x12!\n\x0benable_peer\x18\x13\x20\x01(\x04H\0R\nenablePe\
+    er\x12\x1f\n\npeers_info\x18\x14\x20\x01(\x08H\0R\tpeersInfo\x12\x1f\n\n\
+    state_diff\x18\x15\x20\x01(\tH\0R\tstateDiff\x128\n\x17transactions_by_a\
+    ccount\x18\x16\x20\x01(\tH\0R\x15transactionsByAccount\x12)\n\x0fstorage\
+    _history\x18\x17\x20\x01(\tH\0R\x0estorageHistory\x12.\n\x12light_client\
+    _proof\x18\x18\x20\x01(\x04H\0R\x10lightClientProof\x12/\n\x12replay_tra\
+    nsaction\x18\x19\x20\x01(\x0cH\0R\x11replayTransaction\x123\n\x14simulat\
+    e_transaction\x18\x1a\x20\x01(\tH\0R\x13simulateTransaction\x12-\n\x11co\
+    nsensus_metrics\x18\x1b\x20\x01(\x08H\0R\x10consensusMetricsB\x05\n\x03r\
+    eq\"\x9f\x01\n\x0fFullTransaction\x124\n\x0btransaction\x18\x01\x20\x01(\
+    \x0b2\x12.SignedTransactionR\x0btransaction\x12!\n\x0cblock_number\x18\
+    \x02\x20\x01(\x04R\x0bblockNumber\x12\x1d\n\nblock_hash\x18\x03\x20\x01(\
+    \x0cR\tblockHash\x12\x14\n\x05index\x18\x04\x20\x01(\rR\x05index\"\x92\
+    \x07\n\x08Response\x12\x1d\n\nrequest_id\x18\x01\x20\x01(\x0cR\trequestI\
+    d\x12#\n\x0cblock_number\x18\x02\x20\x01(\x04H\0R\x0bblockNumber\x12\x16\
+    \n\x05block\x18\x03\x20\x01(\tH\0R\x05block\x12\"\n\x02ts\x18\x04\x20\
+    \x01(\x0b2\x10.FullTransactionH\0R\x02ts\x12\x14\n\x04none\x18\x05\x20\
+    \x01(\x08H\0R\x04none\x12\x1e\n\tpeercount\x18\x06\x20\x01(\rH\0R\tpeerc\
+    ount\x12!\n\x0bcall_result\x18\x07\x20\x01(\x0cH\0R\ncallResult\x12\x14\
+    \n\x04logs\x18\x08\x20\x01(\tH\0R\x04logs\x12\x1a\n\x07receipt\x18\t\x20\
+    \x01(\tH\0R\x07receipt\x12-\n\x11transaction_count\x18\n\x20\x01(\x04H\0\
+    R\x10transactionCount\x12\x14\n\x04code\x18\x0b\x20\x01(\x0cH\0R\x04code\
+    \x12\x1d\n\tfilter_id\x18\x0c\x20\x01(\x04H\0R\x08filterId\x12+\n\x10uni\
+    nstall_filter\x18\r\x20\x01(\x08H\0R\x0funinstallFilter\x12'\n\x0efilter\
+    _changes\x18\x0e\x20\x01(\x0cH\0R\rfilterChanges\x12!\n\x0bfilter_logs\
+    \x18\x0f\x20\x01(\x0cH\0R\nfilterLogs\x12\x1d\n\tpeer_bans\x18\x10\x20\
+    \x01(\tH\0R\x08peerBans\x12!\n\x0benable_peer\x18\x11\x20\x01(\x08H\0R\n\
+    enablePeer\x12\x1f\n\npeers_info\x18\x12\x20\x01(\tH\0R\tpeersInfo\x12\
+    \x1d\n\terror_msg\x18\x13\x20\x01(\tH\0R\x08errorMsg\x12\x1f\n\nstate_di\
+    ff\x18\x14\x20\x01(\tH\0R\tstateDiff\x128\n\x17transactions_by_account\
+    \x18\x15\x20\x01(\tH\0R\x15transactionsByAccount\x12)\n\x0fstorage_histo\
+    ry\x18\x16\x20\x01(\tH\0R\x0estorageHistory\x12.\n\x12light_client_proof\
+    \x18\x17\x20\x01(\x0cH\0R\x10lightClientProof\x12-\n\x11transaction_trac\
+    e\x18\x18\x20\x01(\tH\0R\x10transactionTrace\x12-\n\x11consensus_metrics\
+    \x18\x19\x20\x01(\tH\0R\x10consensusMetricsB\x08\n\x06result*$\n\x08Bloc\
+    kTag\x12\n\n\x06Latest\x10\0\x12\x0c\n\x08Earliest\x10\x01J\xcd\x14\n\
+    \x06\x12\x04\0\0?\x01\n\x08\n\x01\x0c\x12\x03\0\0\x12\n\t\n\x02\x03\0\
+    \x12\x03\x02\x07\x19\n\n\n\x02\x05\0\x12\x04\x04\0\x07\x01\n\n\n\x03\x05\
+    \0\x01\x12\x03\x04\x05\r\n\x0b\n\x04\x05\0\x02\0\x12\x03\x05\x04\x0f\n\
+    \x0c\n\x05\x05\0\x02\0\x01\x12\x03\x05\x04\n\n\x0c\n\x05\x05\0\x02\0\x02\
+    \x12\x03\x05\r\x0e\n\x0b\n\x04\x05\0\x02\x01\x12\x03\x06\x04\x11\n\x0c\n\
+    \x05\x05\0\x02\x01\x01\x12\x03\x06\x04\x0c\n\x0c\n\x05\x05\0\x02\x01\x02\
+    \x12\x03\x06\x0f\x10\n\n\n\x02\x04\0\x12\x04\t\0\x0e\x01\n\n\n\x03\x04\0\
+    \x01\x12\x03\t\x08\x0c\n\x0b\n\x04\x04\0\x02\0\x12\x03\n\x04\x13\n\r\n\
+    \x05\x04\0\x02\0\x04\x12\x04\n\x04\t\x0e\n\x0c\n\x05\x04\0\x02\0\x05\x12\
+    \x03\n\x04\t\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\n\n\x0e\n\x0c\n\x05\x04\
+    \0\x02\0\x03\x12\x03\n\x11\x12\n\x0b\n\x04\x04\0\x02\x01\x12\x03\x0b\x04\
+    \x11\n\r\n\x05\x04\0\x02\x01\x04\x12\x04\x0b\x04\n\x13\n\x0c\n\x05\x04\0\
+    \x02\x01\x05\x12\x03\x0b\x04\t\n\x0c\n\x05\x04\0\x02\x01\x01\x12\x03\x0b\
+    \n\x0c\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x0b\x0f\x10\n\x0b\n\x04\x04\
+    \0\x02\x02\x12\x03\x0c\x04\x13\n\r\n\x05\x04\0\x02\x02\x04\x12\x04\x0c\
+    \x04\x0b\x11\n\x0c\n\x05\x04\0\x02\x02\x05\x12\x03\x0c\x04\t\n\x0c\n\x05\
+    \x04\0\x02\x02\x01\x12\x03\x0c\n\x0e\n\x0c\n\x05\x04\0\x02\x02\x03\x12\
+    \x03\x0c\x11\x12\n\x0b\n\x04\x04\0\x02\x03\x12\x03\r\x04\x16\n\r\n\x05\
+    \x04\0\x02\x03\x04\x12\x04\r\x04\x0c\x13\n\x0c\n\x05\x04\0\x02\x03\x05\
+    \x12\x03\r\x04\n\n\x0c\n\x05\x04\0\x02\x03\x01\x12\x03\r\x0b\x11\n\x0c\n\
+    \x05\x04\0\x02\x03\x03\x12\x03\r\x14\x15\n\n\n\x02\x04\x01\x12\x04\x10\0\
+    $\x01\n\n\n\x03\x04\x01\x01\x12\x03\x10\x08\x0f\n\x0b\n\x04\x04\x01\x02\
+    \0\x12\x03\x11\x04\x19\n\r\n\x05\x04\x01\x02\0\x04\x12\x04\x11\x04\x10\
+    \x11\n\x0c\n\x05\x04\x01\x02\0\x05\x12\x03\x11\x04\t\n\x0c\n\x05\x04\x01\
+    \x02\0\x01\x12\x03\x11\n\x14\n\x0c\n\x05\x04\x01\x02\0\x03\x12\x03\x11\
+    \x17\x18\n\x0c\n\x04\x04\x01\x08\0\x12\x04\x12\x04#\x05\n\x0c\n\x05\x04\
+    \x01\x08\0\x01\x12\x03\x12\n\r\n\x0b\n\x04\x04\x01\x02\x01\x12\x03\x13\
+    \x08\x1e\n\x0c\n\x05\x04\x01\x02\x01\x05\x12\x03\x13\x08\x0c\n\x0c\n\x05\
+    \x04\x01\x02\x01\x01\x12\x03\x13\r\x19\n\x0c\n\x05\x04\x01\x02\x01\x03\
+    \x12\x03\x13\x1c\x1d\n\x0b\n\x04\x04\x01\x02\x02\x12\x03\x14\x08!\n\x0c\
+    \n\x05\x04\x01\x02\x02\x05\x12\x03\x14\x08\x0e\n\x0c\n\x05\x04\x01\x02\
+    \x02\x01\x12\x03\x14\x0f\x1c\n\x0c\n\x05\x04\x01\x02\x02\x03\x12\x03\x14\
+    \x1f\x20\n\x0b\n\x04\x04\x01\x02\x03\x12\x03\x15\x08#\n\x0c\n\x05\x04\
+    \x01\x02\x03\x05\x12\x03\x15\x08\x0e\n\x0c\n\x05\x04\x01\x02\x03\x01\x12\
+    \x03\x15\x0f\x1e\n\x0c\n\x05\x04\x01\x02\x03\x03\x12\x03\x15!\"\n\x0b\n\
+    \x04\x04\x01\x02\x04\x12\x03\x16\x08\x1e\n\x0c\n\x05\x04\x01\x02\x04\x05\
+    \x12\x03\x16\x08\r\n\x0c\n\x05\x04\x01\x02\x04\x01\x12\x03\x16\x0e\x19\n\
+    \x0c\n\x05\x04\x01\x02\x04\x03\x12\x03\x16\x1c\x1d\n\x0b\n\x04\x04\x01\
+    \x02\x05\x12\x03\x17\x08\x1a\n\x0c\n\x05\x04\x01\x02\x05\x05\x12\x03\x17\
+    \x08\x0e\n\x0c\n\x05\x04\x01\x02\x05\x01\x12\x03\x17\x0f\x15\n\x0c\n\x05\
+    \x04\x01\x02\x05\x03\x12\x03\x17\x18\x19\n\x0b\n\x04\x04\x01\x02\x06\x12\
+    \x03\x18\x08\x1b\n\x0c\n\x05\x04\x01\x02\x06\x05\x12\x03\x18\x08\x0c\n\
+    \x0c\n\x05\x04\x01\x02\x06\x01\x12\x03\x18\r\x16\n\x0c\n\x05\x04\x01\x02\
+    \x06\x03\x12\x03\x18\x19\x1a\n\x0b\n\x04\x04\x01\x02\x07\x12\x03\x19\x08\
+    \x16\n\x0c\n\x05\x04\x01\x02\x07\x06\x12\x03\x19\x08\x0c\n\x0c\n\x05\x04\
+    \x01\x02\x07\x01\x12\x03\x19\r\x11\n\x0c\n\x05\x04\x01\x02\x07\x03\x12\
+    \x03\x19\x14\x15\n\x0b\n\x04\x04\x01\x02\x08\x12\x03\x1a\x08\x1a\n\x0c\n\
+    \x05\x04\x01\x02\x08\x05\x12\x03\x1a\x08\x0e\n\x0c\n\x05\x04\x01\x02\x08\
+    \x01\x12\x03\x1a\x0f\x15\n\x0c\n\x05\x04\x01\x02\x08\x03\x12\x03\x1a\x18\
+    \x19\n\x0b\n\x04\x04\x01\x02\t\x12\x03\x1b\x08'\n\x0c\n\x05\x04\x01\x02\
+    \t\x05\x12\x03\x1b\x08\r\n\x0c\n\x05\x04\x01\x02\t\x01\x12\x03\x1b\x0e!\
+    \n\x0c\n\x05\x04\x01\x02\t\x03\x12\x03\x1b$&\n\x0b\n\x04\x04\x01\x02\n\
+    \x12\x03\x1c\x08&\n\x0c\n\x05\x04\x01\x02\n\x05\x12\x03\x1c\x08\x0e\n\
+    \x0c\n\x05\x04\x01\x02\n\x01\x12\x03\x1c\x0f\x20\n\x0c\n\x05\x04\x01\x02\
+    \n\x03\x12\x03\x1c#%\n\x0b\n\x04\x04\x01\x02\x0b\x12\x03\x1d\x08\x19\n\
+    \x0c\n\x05\x04\x01\x02\x0b\x05\x12\x03\x1d\x08\x0e\n\x0c\n\x05\x04\x01\
+    \x02\x0b\x01\x12\x03\x1d\x0f\x13\n\x0c\n\x05\x04\x01\x02\x0b\x03\x12\x03\
+    \x1d\x16\x18\n\x0b\n\x04\x04\x01\x02\x0c\x12\x03\x1e\x08\x1f\n\x0c\n\x05\
+    \x04\x01\x02\x0c\x05\x12\x03\x1e\x08\x0e\n\x0c\n\x05\x04\x01\x02\x0c\x01\
+    \x12\x03\x1e\x0f\x19\n\x0c\n\x05\x04\x01\x02\x0c\x03\x12\x03\x1e\x1c\x1e\
+    \n\x0b\n\x04\x04\x01\x02\r\x12\x03\x1f\x08#\n\x0c\n\x05\x04\x01\x02\r\
+    \x05\x12\x03\x1f\x08\x0c\n\x0c\n\x05\x04\x01\x02\r\x01\x12\x03\x1f\r\x1d\
+    \n\x0c\n\x05\x04\x01\x02\r\x03\x12\x03\x1f\x20\"\n\x0b\n\x04\x04\x01\x02\
+    \x0e\x12\x03\x20\x08%\n\x0c\n\x05\x04\x01\x02\x0e\x05\x12\x03\x20\x08\
+    \x0e\n\x0c\n\x05\x04\x01\x02\x0e\x01\x12\x03\x20\x0f\x1f\n\x0c\n\x05\x04\
+    \x01\x02\x0e\x03\x12\x03\x20\"$\n\x0b\n\x04\x04\x01\x02\x0f\x12\x03!\x08\
+    #\n\x0c\n\x05\x04\x01\x02\x0f\x05\x12\x03!\x08\x0e\n\x0c\n\x05\x04\x01\
+    \x02\x0f\x01\x12\x03!\x0f\x1d\n\x0c\n\x05\x04\x01\x02\x0f\x03\x12\x03!\
+    \x20\"\n\x0b\n\x04\x04\x01\x02\x10\x12\x03\"\x08\x20\n\x0c\n\x05\x04\x01\
+    \x02\x10\x05\x12\x03\"\x08\x0e\n\x0c\n\x05\x04\x01\x02\x10\x01\x12\x03\"\
+    \x0f\x1a\n\x0c\n\x05\x04\x01\x02\x10\x03\x12\x03\"\x1d\x1f\n\n\n\x02\x04\
+    \x02\x12\x04&\0+\x01\n\n\n\x03\x04\x02\x01\x12\x03&\x08\x17\n\x0b\n\x04\
+    \x04\x02\x02\0\x12\x03'\x04&\n\r\n\x05\x04\x02\x02\0\x04\x12\x04'\x04&\
+    \x19\n\x0c\n\x05\x04\x02\x02\0\x06\x12\x03'\x04\x15\n\x0c\n\x05\x04\x02\
+    \x02\0\x01\x12\x03'\x16!\n\x0c\n\x05\x04\x02\x02\0\x03\x12\x03'$%\n\x0b\
+    \n\x04\x04\x02\x02\x01\x12\x03(\x04\x1c\n\r\n\x05\x04\x02\x02\x01\x04\
+    \x12\x04(\x04'&\n\x0c\n\x05\x04\x02\x02\x01\x05\x12\x03(\x04\n\n\x0c\n\
+    \x05\x04\x02\x02\x01\x01\x12\x03(\x0b\x17\n\x0c\n\x05\x04\x02\x02\x01\
+    \x03\x12\x03(\x1a\x1b\n\x0b\n\x04\x04\x02\x02\x02\x12\x03)\x04\x19\n\r\n\
+    \x05\x04\x02\x02\x02\x04\x12\x04)\x04(\x1c\n\x0c\n\x05\x04\x02\x02\x02\
+    \x05\x12\x03)\x04\t\n\x0c\n\x05\x04\x02\x02\x02\x01\x12\x03)\n\x14\n\x0c\
+    \n\x05\x04\x02\x02\x02\x03\x12\x03)\x17\x18\n\x0b\n\x04\x04\x02\x02\x03\
+    \x12\x03*\x04\x15\n\r\n\x05\x04\x02\x02\x03\x04\x12\x04*\x04)\x19\n\x0c\
+    \n\x05\x04\x02\x02\x03\x05\x12\x03*\x04\n\n\x0c\n\x05\x04\x02\x02\x03\
+    \x01\x12\x03*\x0b\x10\n\x0c\n\x05\x04\x02\x02\x03\x03\x12\x03*\x13\x14\n\
+    \n\n\x02\x04\x03\x12\x04-\0?\x01\n\n\n\x03\x04\x03\x01\x12\x03-\x08\x10\
+    \n\x0b\n\x04\x04\x03\x02\0\x12\x03.\x04\x19\n\r\n\x05\x04\x03\x02\0\x04\
+    \x12\x04.\x04-\x12\n\x0c\n\x05\x04\x03\x02\0\x05\x12\x03.\x04\t\n\x0c\n\
+    \x05\x04\x03\x02\0\x01\x12\x03.\n\x14\n\x0c\n\x05\x04\x03\x02\0\x03\x12\
+    \x03.\x17\x18\n\x0c\n\x04\x04\x03\x08\0\x12\x04/\x04>\x05\n\x0c\n\x05\
+    \x04\x03\x08\0\x01\x12\x03/\n\x10\n\x0b\n\x04\x04\x03\x02\x01\x12\x030\
+    \x08\x20\n\x0c\n\x05\x04\x03\x02\x01\x05\x12\x030\x08\x0e\n\x0c\n\x05\
+    \x04\x03\x02\x01\x01\x12\x030\x0f\x1b\n\x0c\n\x05\x04\x03\x02\x01\x03\
+    \x12\x030\x1e\x1f\n\x0b\n\x04\x04\x03\x02\x02\x12\x031\x08\x19\n\x0c\n\
+    \x05\x04\x03\x02\x02\x05\x12\x031\x08\x0e\n\x0c\n\x05\x04\x03\x02\x02\
+    \x01\x12\x031\x0f\x14\n\x0c\n\x05\x04\x03\x02\x02\x03\x12\x031\x17\x18\n\
+    \x0b\n\x04\x04\x03\x02\x03\x12\x032\x08\x1f\n\x0c\n\x05\x04\x03\x02\x03\
+    \x06\x12\x032\x08\x17\n\x0c\n\x05\x04\x03\x02\x03\x01\x12\x032\x18\x1a\n\
+    \x0c\n\x05\x04\x03\x02\x03\x03\x12\x032\x1d\x1e\n\x0b\n\x04\x04\x03\x02\
+    \x04\x12\x033\x08\x16\n\x0c\n\x05\x04\x03\x02\x04\x05\x12\x033\x08\x0c\n\
+    \x0c\n\x05\x04\x03\x02\x04\x01\x12\x033\r\x11\n\x0c\n\x05\x04\x03\x02\
+    \x04\x03\x12\x033\x14\x15\n\x0b\n\x04\x04\x03\x02\x05\x12\x034\x08\x1d\n\
+    \x0c\n\x05\x04\x03\x02\x05\x05\x12\x034\x08\x0e\n\x0c\n\x05\x04\x03\x02\
+    \x05\x01\x12\x034\x0f\x18\n\x0c\n\x05\x04\x03\x02\x05\x03\x12\x034\x1b\
+    \x1c\n\x0b\n\x04\x04\x03\x02\x06\x12\x035\x08\x1e\n\x0c\n\x05\x04\x03\
+    \x02\x06\x05\x12\x035\x08\r\n\x0c\n\x05\x04\x03\x02\x06\x01\x12\x035\x0e\
+    \x19\n\x0c\n\x05\x04\x03\x02\x06\x03\x12\x035\x1c\x1d\n\x0b\n\x04\x04\
+    \x03\x02\x07\x12\x036\x08\x18\n\x0c\n\x05\x04\x03\x02\x07\x05\x12\x036\
+    \x08\x0e\n\x0c\n\x05\x04\x03\x02\x07\x01\x12\x036\x0f\x13\n\x0c\n\x05\
+    \x04\x03\x02\x07\x03\x12\x036\x16\x17\n\x0b\n\x04\x04\x03\x02\x08\x12\
+    \x037\x08\x1b\n\x0c\n\x05\x04\x03\x02\x08\x05\x12\x037\x08\x0e\n\x0c\n\
+    \x05\x04\x03\x02\x08\x01\x12\x037\x0f\x16\n\x0c\n\x05\x04\x03\x02\x08\
+    \x03\x12\x037\x19\x1a\n\x0b\n\x04\x04\x03\x02\t\x12\x038\x08&\n\x0c\n\
+    \x05\x04\x03\x02\t\x05\x12\x038\x08\x0e\n\x0c\n\x05\x04\x03\x02\t\x01\
+    \x12\x038\x0f\x20\n\x0c\n\x05\x04\x03\x02\t\x03\x12\x038#%\n\x0b\n\x04\
+    \x04\x03\x02\n\x12\x039\x08\x18\n\x0c\n\x05\x04\x03\x02\n\x05\x12\x039\
+    \x08\r\n\x0c\n\x05\x04\x03\x02\n\x01\x12\x039\x0e\x12\n\x0c\n\x05\x04\
+    \x03\x02\n\x03\x12\x039\x15\x17\n\x0b\n\x04\x04\x03\x02\x0b\x12\x03:\x08\
+    \x1e\n\x0c\n\x05\x04\x03\x02\x0b\x05\x12\x03:\x08\x0e\n\x0c\n\x05\x04\
+    \x03\x02\x0b\x01\x12\x03:\x0f\x18\n\x0c\n\x05\x04\x03\x02\x0b\x03\x12\
+    \x03:\x1b\x1d\n\x0b\n\x04\x04\x03\x02\x0c\x12\x03;\x08#\n\x0c\n\x05\x04\
+    \x03\x02\x0c\x05\x12\x03;\x08\x0c\n\x0c\n\x05\x04\x03\x02\x0c\x01\x12\
+    \x03;\r\x1d\n\x0c\n\x05\x04\x03\x02\x0c\x03\x12\x03;\x20\"\n\x0b\n\x04\
+    \x04\x03\x02\r\x12\x03<\x08\"\n\x0c\n\x05\x04\x03\x02\r\x05\x12\x03<\x08\
+    \r\n\x0c\n\x05\x04\x03\x02\r\x01\x12\x03<\x0e\x1c\n\x0c\n\x05\x04\x03\
+    \x02\r\x03\x12\x03<\x1f!\n\x0b\n\x04\x04\x03\x02\x0e\x12\x03=\x08\x1f\n\
+    \x0c\n\x05\x04\x03\x02\x0e\x05\x12\x03=\x08\r\n\x0c\n\x05\x04\x03\x02\
+    \x0e\x01\x12\x03=\x0e\x19\n\x0c\n\x05\x04\x03\x02\x0e\x03\x12\x03=\x1c\
+    \x1eb\x06proto3\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
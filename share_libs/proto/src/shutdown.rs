@@ -0,0 +1,178 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Graceful shutdown of the modules. Asked by SIGTERM or SIGINT, a module
+//! stops taking new work, waits for the work in flight, flushes what it keeps
+//! on disk, acknowledges on the bus under `<module>.shutdown_ack` and exits.
+//! A module fed by another waits for its acknowledgement first, so the chain
+//! imports the blocks the consensus committed before it stopped.
+
+use super::{de_cmd_id, factory, topics};
+use communication::MsgType;
+use libc;
+use protobuf::Message;
+use std::collections::HashSet;
+use std::process;
+use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard};
+use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds a module waits for the acknowledgements and for its work in flight before it exits anyway.
+pub const SHUTDOWN_TIMEOUT: u64 = 10;
+// milliseconds between two checks whether the shutdown is asked
+const POLL_INTERVAL: u64 = 100;
+// milliseconds left to the publisher to send the acknowledgement
+const ACK_DELAY: u64 = 500;
+
+static REQUESTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+extern "C" fn on_signal(_: libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// The shutdown of a module, cloned into the threads doing its work.
+#[derive(Clone)]
+pub struct Shutdown {
+    submodule: u32,
+    // routing key of the acknowledgement
+    key: String,
+    // read by the work a shutdown must not cut, written by the shutdown
+    work: Arc<RwLock<()>>,
+    acks: Arc<(Mutex<HashSet<u32>>, Condvar)>,
+}
+
+impl Shutdown {
+    /// The shutdown of the submodule publishing under the name, asked by SIGTERM and SIGINT from now on.
+    pub fn new(submodule: u32, name: &str) -> Self {
+        unsafe {
+            libc::signal(libc::SIGTERM, on_signal as libc::sighandler_t);
+            libc::signal(libc::SIGINT, on_signal as libc::sighandler_t);
+        }
+        Shutdown {
+            submodule: submodule,
+            key: format!("{}.shutdown_ack", name),
+            work: Arc::new(RwLock::new(())),
+            acks: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
+        }
+    }
+
+    pub fn requested(&self) -> bool {
+        REQUESTED.load(Ordering::SeqCst)
+    }
+
+    /// Held while doing work the shutdown waits for, the threads of a module working
+    /// at once. Once the shutdown has begun, the work waits for the exit instead.
+    pub fn working(&self) -> RwLockReadGuard<()> {
+        self.work.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record the acknowledgement of another module, whether the message of the command is one.
+    pub fn handle_ack(&self, cmd: u32) -> bool {
+        let (submodule, topic) = de_cmd_id(cmd);
+        if topic != topics::SHUTDOWN_ACK {
+            return false;
+        }
+        let &(ref acks, ref cvar) = &*self.acks;
+        acks.lock().unwrap().insert(submodule);
+        cvar.notify_all();
+        true
+    }
+
+    /// Wait for the acknowledgements of the submodules until the timeout, whether all of them came.
+    pub fn wait_acks(&self, submodules: &[u32], timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let &(ref acks, ref cvar) = &*self.acks;
+        let mut acks = acks.lock().unwrap();
+        while !submodules.iter().all(|submodule| acks.contains(submodule)) {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            acks = cvar.wait_timeout(acks, deadline - now).unwrap().0;
+        }
+        true
+    }
+
+    /// The acknowledgement of the module, with the key to publish it under.
+    pub fn ack(&self) -> (String, Vec<u8>) {
+        let msg = factory::create_msg(self.submodule, topics::SHUTDOWN_ACK, MsgType::MSG, Vec::new());
+        (self.key.clone(), msg.write_to_bytes().unwrap())
+    }
+
+    /// Wait until the shutdown is asked, then for the acknowledgements of the submodules
+    /// and the work in flight, flush, acknowledge and exit the process.
+    pub fn run<F: FnOnce()>(&self, tx_pub: &Sender<(String, Vec<u8>)>, wait_for: &[u32], flush: F) {
+        while !self.requested() {
+            thread::sleep(Duration::from_millis(POLL_INTERVAL));
+        }
+        info!("shutting down");
+        let timeout = Duration::from_secs(SHUTDOWN_TIMEOUT);
+        if !self.wait_acks(wait_for, timeout) {
+            warn!("shutting down without the acknowledgements of {:?}", wait_for);
+        }
+        let deadline = Instant::now() + timeout;
+        // kept until the exit, so no new work starts
+        let mut work = None;
+        while work.is_none() {
+            match self.work.try_write() {
+                Ok(guard) => work = Some(guard),
+                Err(_) if Instant::now() >= deadline => {
+                    warn!("shutting down with work in flight");
+                    break;
+                }
+                Err(_) => thread::sleep(Duration::from_millis(POLL_INTERVAL)),
+            }
+        }
+        flush();
+        let _ = tx_pub.send(self.ack());
+        thread::sleep(Duration::from_millis(ACK_DELAY));
+        info!("shut down");
+        process::exit(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {cmd_id, submodules};
+
+    #[test]
+    fn acks() {
+        let shutdown = Shutdown::new(submodules::CHAIN, "chain");
+        assert!(!shutdown.handle_ack(cmd_id(submodules::CONSENSUS, topics::NEW_TX)));
+        assert!(!shutdown.wait_acks(&[submodules::CONSENSUS], Duration::from_millis(10)));
+
+        let waiting = shutdown.clone();
+        let waiter = thread::spawn(move || waiting.wait_acks(&[submodules::CONSENSUS], Duration::from_secs(5)));
+        assert!(shutdown.handle_ack(cmd_id(submodules::CONSENSUS, topics::SHUTDOWN_ACK)));
+        assert!(waiter.join().unwrap());
+
+        // the threads work at once, the shutdown waits for all of them
+        let first = shutdown.working();
+        let second = shutdown.working();
+        assert!(shutdown.work.try_write().is_err());
+        drop(first);
+        assert!(shutdown.work.try_write().is_err());
+        drop(second);
+        assert!(shutdown.work.try_write().is_ok());
+
+        let (key, _) = shutdown.ack();
+        assert_eq!(key, "chain.shutdown_ack");
+    }
+}
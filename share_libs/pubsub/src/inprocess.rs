@@ -0,0 +1,145 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A message bus within the process, so the modules may run as threads of
+//! one process without a broker, in development and tests.
+
+use super::MessageBus;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::thread;
+
+lazy_static! {
+    static ref GLOBAL: InProcessBus = InProcessBus::new();
+}
+
+/// Whether the routing key matches the key a queue subscribes to, as a topic of amqp.
+pub fn topic_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let key: Vec<&str> = key.split('.').collect();
+    words_match(&pattern, &key)
+}
+
+fn words_match(pattern: &[&str], key: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => key.is_empty(),
+        Some((&"#", rest)) => (0..key.len() + 1).any(|skip| words_match(rest, &key[skip..])),
+        Some((word, rest)) => {
+            match key.split_first() {
+                Some((first, key_rest)) => (*word == "*" || word == first) && words_match(rest, key_rest),
+                None => false,
+            }
+        }
+    }
+}
+
+struct Queue {
+    keys: Vec<String>,
+    tx: SyncSender<(String, Vec<u8>)>,
+}
+
+#[derive(Clone, Default)]
+pub struct InProcessBus {
+    queues: Arc<Mutex<HashMap<String, Queue>>>,
+}
+
+impl InProcessBus {
+    pub fn new() -> Self {
+        InProcessBus::default()
+    }
+
+    /// The bus shared by the modules of the process.
+    pub fn global() -> &'static InProcessBus {
+        &GLOBAL
+    }
+
+    /// Send the message to each queue subscribed to its key, once, waiting for room in the full ones.
+    pub fn publish(&self, key: &str, msg: Vec<u8>) {
+        // not sent under the lock, so a full queue holds up its publishers only
+        let subscribed: Vec<(String, SyncSender<(String, Vec<u8>)>)> = self.queues
+                                                                           .lock()
+                                                                           .unwrap()
+                                                                           .iter()
+                                                                           .filter(|&(_, queue)| queue.keys.iter().any(|pattern| topic_matches(pattern, key)))
+                                                                           .map(|(name, queue)| (name.clone(), queue.tx.clone()))
+                                                                           .collect();
+        let gone: Vec<String> = subscribed.into_iter().filter(|&(_, ref tx)| tx.send((key.to_string(), msg.clone())).is_err()).map(|(name, _)| name).collect();
+        if !gone.is_empty() {
+            let mut queues = self.queues.lock().unwrap();
+            for name in gone {
+                queues.remove(&name);
+            }
+        }
+    }
+}
+
+impl MessageBus for InProcessBus {
+    fn start(&self, name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+        {
+            // a queue started again subscribes to the keys of both, for the last sender
+            let mut queues = self.queues.lock().unwrap();
+            let queue = queues.entry(name.to_string()).or_insert_with(|| Queue { keys: Vec::new(), tx: tx.clone() });
+            queue.keys.extend(keys.into_iter().map(String::from));
+            queue.tx = tx;
+        }
+        let bus = self.clone();
+        let _ = thread::Builder::new().name("publisher".to_string()).spawn(move || for (key, msg) in rx {
+                                                                              bus.publish(&key, msg);
+                                                                          });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use QUEUE_SIZE;
+    use std::sync::mpsc::{channel, sync_channel};
+
+    #[test]
+    fn topics() {
+        assert!(topic_matches("chain.status", "chain.status"));
+        assert!(topic_matches("*.rpc", "chain.rpc"));
+        assert!(!topic_matches("*.rpc", "chain.sub.rpc"));
+        assert!(topic_matches("#", "chain.status"));
+        assert!(topic_matches("chain.#", "chain"));
+        assert!(!topic_matches("chain.*", "chain"));
+    }
+
+    #[test]
+    fn basics() {
+        let bus = InProcessBus::new();
+        let (ntx_sub, nrx_sub) = sync_channel(QUEUE_SIZE);
+        let (ntx_pub, nrx_pub) = channel();
+        bus.start("network", vec!["chain.newtx", "chain.newblk"], ntx_sub, nrx_pub);
+
+        let (ctx_sub, crx_sub) = sync_channel(QUEUE_SIZE);
+        let (ctx_pub, crx_pub) = channel();
+        bus.start("chain", vec!["network.*"], ctx_sub, crx_pub);
+
+        ntx_pub.send(("network.newtx".to_string(), vec![1])).unwrap();
+        ntx_pub.send(("network.newblk".to_string(), vec![2])).unwrap();
+        ctx_pub.send(("chain.newtx".to_string(), vec![3])).unwrap();
+        ctx_pub.send(("chain.other".to_string(), vec![4])).unwrap();
+        ctx_pub.send(("chain.newblk".to_string(), vec![5])).unwrap();
+
+        assert_eq!(crx_sub.recv().unwrap(), ("network.newtx".to_string(), vec![1]));
+        assert_eq!(crx_sub.recv().unwrap(), ("network.newblk".to_string(), vec![2]));
+        assert_eq!(nrx_sub.recv().unwrap(), ("chain.newtx".to_string(), vec![3]));
+        assert_eq!(nrx_sub.recv().unwrap(), ("chain.newblk".to_string(), vec![5]));
+    }
+}
@@ -17,34 +17,175 @@
 
 #[cfg(feature = "rabbitmq")]
 extern crate pubsub_rabbitmq;
+#[cfg(feature = "zeromq")]
+extern crate pubsub_zeromq;
+#[cfg(feature = "kafka")]
+extern crate pubsub_kafka;
 extern crate dotenv;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 
-use dotenv::dotenv;
+pub mod inprocess;
+pub mod metrics;
+pub mod retry;
 
+use dotenv::dotenv;
+pub use inprocess::{InProcessBus, topic_matches};
 #[cfg(feature = "rabbitmq")]
-use pubsub_rabbitmq::start_rabbitmq;
+use pubsub_rabbitmq::{queue_depth, start_rabbitmq};
+#[cfg(feature = "zeromq")]
+use pubsub_zeromq::start_zeromq;
+#[cfg(feature = "kafka")]
+use pubsub_kafka::start_kafka;
+use std::env;
 use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
+#[cfg(any(feature = "zeromq", feature = "kafka"))]
+use std::sync::mpsc::sync_channel;
+#[cfg(any(feature = "zeromq", feature = "kafka"))]
+use std::thread;
+
+/// Messages a module may leave in its queue, the queues the modules subscribe with are bounded by.
+/// The bus stops delivering to a module with its queue full.
+pub const QUEUE_SIZE: usize = 1024;
+
+/// Environment variable selecting the message bus, "rabbitmq" by default, "zeromq", "kafka" or "inprocess".
+pub const MESSAGE_BUS: &'static str = "MESSAGE_BUS";
+
+/// Carries the messages between the modules by their routing keys. The keys a
+/// queue subscribes to are matched as the topics of amqp, `*` standing for
+/// one word and `#` for any words. A bus over a broker delivers the messages of
+/// the consensus before those of the chain before the transactions.
+pub trait MessageBus: Send + Sync {
+    /// Subscribe the queue of the name to the keys, whose messages are sent to tx,
+    /// and publish the messages received from rx.
+    fn start(&self, name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>);
+
+    /// Messages the broker holds for the queue of the name, none without a broker.
+    fn queue_depth(&self, _name: &str) -> Option<u32> {
+        None
+    }
+}
+
+/// The bus through the exchange of a RabbitMQ broker at AMQP_URL.
+#[cfg(feature = "rabbitmq")]
+pub struct RabbitMqBus;
+
+#[cfg(feature = "rabbitmq")]
+impl MessageBus for RabbitMqBus {
+    fn start(&self, name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+        start_rabbitmq(name, keys, tx, rx);
+    }
+
+    fn queue_depth(&self, name: &str) -> Option<u32> {
+        queue_depth(name)
+    }
+}
+
+#[cfg(feature = "rabbitmq")]
+static RABBITMQ: RabbitMqBus = RabbitMqBus;
+
+/// The bus over ZeroMQ between the endpoints of the modules in ZMQ_ENDPOINTS, without a broker.
+#[cfg(feature = "zeromq")]
+pub struct ZeroMqBus;
 
+#[cfg(feature = "zeromq")]
+impl MessageBus for ZeroMqBus {
+    fn start(&self, name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+        // the sockets match the keys by their prefixes only
+        let tx_sub = filtered(&keys, tx);
+        start_zeromq(name, keys, tx_sub, rx);
+    }
+}
+
+#[cfg(feature = "zeromq")]
+static ZEROMQ: ZeroMqBus = ZeroMqBus;
+
+/// The bus over the topic KAFKA_TOPIC of the Kafka brokers at KAFKA_BROKERS, keeping the messages
+/// in a log which may be replayed.
+#[cfg(feature = "kafka")]
+pub struct KafkaBus;
+
+#[cfg(feature = "kafka")]
+impl MessageBus for KafkaBus {
+    fn start(&self, name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+        // each queue reads all the messages of the topic
+        let tx_sub = filtered(&keys, tx);
+        start_kafka(name, keys, tx_sub, rx);
+    }
+
+    fn queue_depth(&self, name: &str) -> Option<u32> {
+        pubsub_kafka::queue_depth(name)
+    }
+}
+
+#[cfg(feature = "kafka")]
+static KAFKA: KafkaBus = KafkaBus;
+
+/// Forward to tx the messages sent to the sender returned whose keys match those subscribed,
+/// for the buses which can't match the keys as amqp does.
+#[cfg(any(feature = "zeromq", feature = "kafka"))]
+fn filtered(keys: &[&str], tx: SyncSender<(String, Vec<u8>)>) -> SyncSender<(String, Vec<u8>)> {
+    let patterns: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
+    let _ = thread::Builder::new().name("filter".to_string()).spawn(move || for (key, msg) in rx_sub {
+                                                                       if patterns.iter().any(|pattern| topic_matches(pattern, &key)) && tx.send((key, msg)).is_err() {
+                                                                           break;
+                                                                       }
+                                                                   });
+    tx_sub
+}
 
 #[cfg(feature = "rabbitmq")]
-pub fn start_pubsub(name: &str, keys: Vec<&str>, tx: Sender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+fn default_bus() -> &'static MessageBus {
+    &RABBITMQ
+}
+
+#[cfg(not(feature = "rabbitmq"))]
+fn default_bus() -> &'static MessageBus {
+    InProcessBus::global()
+}
+
+/// The message bus selected by the environment.
+pub fn message_bus() -> &'static MessageBus {
+    match env::var(MESSAGE_BUS).ok().as_ref().map(String::as_str) {
+        None => default_bus(),
+        Some("inprocess") => InProcessBus::global(),
+        #[cfg(feature = "rabbitmq")]
+        Some("rabbitmq") => &RABBITMQ,
+        #[cfg(feature = "zeromq")]
+        Some("zeromq") => &ZEROMQ,
+        #[cfg(feature = "kafka")]
+        Some("kafka") => &KAFKA,
+        Some(name) => panic!("unknown message bus {}", name),
+    }
+}
+
+pub fn start_pubsub(name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
     dotenv().ok();
-    start_rabbitmq(name, keys, tx, rx);
+    let bus = message_bus();
+    let (tx, rx) = metrics::instrument(name, bus, tx, rx);
+    bus.start(name, keys, tx, rx);
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::sync::mpsc::channel;
+    use std::sync::mpsc::{channel, sync_channel};
 
     #[test]
     fn basics() {
-        let (ntx_sub, nrx_sub) = channel();
+        let (ntx_sub, nrx_sub) = sync_channel(QUEUE_SIZE);
         let (ntx_pub, nrx_pub) = channel();
         start_pubsub("network", vec!["chain.newtx", "chain.newblk"], ntx_sub, nrx_pub);
 
-        let (ctx_sub, crx_sub) = channel();
+        let (ctx_sub, crx_sub) = sync_channel(QUEUE_SIZE);
         let (ctx_pub, crx_pub) = channel();
         start_pubsub("chain", vec!["network.newtx", "network.newblk"], ctx_sub, crx_pub);
 
@@ -0,0 +1,213 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Metrics of the queues of the modules on the bus, so a saturated bus shows
+//! before the consensus misses its rounds. The messages published and
+//! consumed are counted by their keys, with the time the messages delivered
+//! waited for room in the queue of the module, and the depth of the queue in
+//! the broker. The metrics are logged and published under
+//! `<queue>.bus_metrics` periodically.
+
+use super::MessageBus;
+use serde_json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval in seconds the metrics of the bus are published at.
+pub const BUS_METRICS_INTERVAL: u64 = 10;
+
+const WAIT_BUCKETS: [u64; 9] = [1, 5, 10, 50, 100, 250, 500, 1000, 5000];
+
+fn as_millis(d: Duration) -> u64 {
+    d.as_secs() * 1000 + (d.subsec_nanos() / 1_000_000) as u64
+}
+
+/// Cumulative histogram, the last bucket counts the values above all bounds.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bounds: Vec<u64>,
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[u64]) -> Self {
+        Histogram {
+            bounds: bounds.to_vec(),
+            buckets: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0,
+        }
+    }
+
+    pub fn observe(&mut self, value: u64) {
+        let idx = self.bounds.iter().position(|b| value <= *b).unwrap_or(self.bounds.len());
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Messages of a key since the queue started.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TopicMetrics {
+    pub published: u64,
+    pub consumed: u64,
+    /// Milliseconds the messages delivered waited for room in the queue of the module,
+    /// growing as the module falls behind.
+    pub wait: Histogram,
+}
+
+impl TopicMetrics {
+    fn new() -> Self {
+        TopicMetrics {
+            published: 0,
+            consumed: 0,
+            wait: Histogram::new(&WAIT_BUCKETS),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BusMetrics {
+    pub queue: String,
+    pub topics: HashMap<String, TopicMetrics>,
+    /// Messages held for the queue by the broker, none without a broker.
+    pub queue_depth: Option<u32>,
+}
+
+impl BusMetrics {
+    pub fn new(queue: &str) -> Self {
+        BusMetrics {
+            queue: queue.to_string(),
+            topics: HashMap::new(),
+            queue_depth: None,
+        }
+    }
+
+    fn topic(&mut self, key: &str) -> &mut TopicMetrics {
+        self.topics.entry(key.to_string()).or_insert_with(TopicMetrics::new)
+    }
+
+    pub fn published(&mut self, key: &str) {
+        self.topic(key).published += 1;
+    }
+
+    pub fn consumed(&mut self, key: &str, wait: Duration) {
+        let topic = self.topic(key);
+        topic.consumed += 1;
+        topic.wait.observe(as_millis(wait));
+    }
+}
+
+/// Count the messages passing between the queue of the name on the bus and the module,
+/// returning the channels the bus takes in place of those of the module.
+pub fn instrument(name: &str, bus: &'static MessageBus, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) -> (SyncSender<(String, Vec<u8>)>, Receiver<(String, Vec<u8>)>) {
+    let metrics = Arc::new(Mutex::new(BusMetrics::new(name)));
+
+    // without a buffer of its own, so the queue of the module still bounds the messages delivered
+    let (tx_bus, rx_bus) = sync_channel(0);
+    let consumed = metrics.clone();
+    let _ = thread::Builder::new().name("consumed".to_string()).spawn(move || for (key, msg) in rx_bus {
+                                                                         let start = Instant::now();
+                                                                         let counted = key.clone();
+                                                                         if tx.send((key, msg)).is_err() {
+                                                                             break;
+                                                                         }
+                                                                         consumed.lock().unwrap().consumed(&counted, start.elapsed());
+                                                                     });
+
+    let (tx_pub, rx_pub) = channel();
+    let name = name.to_string();
+    let _ = thread::Builder::new().name("published".to_string()).spawn(move || {
+        let interval = Duration::from_secs(BUS_METRICS_INTERVAL);
+        let mut next_report = Instant::now() + interval;
+        loop {
+            let now = Instant::now();
+            let timeout = if next_report > now { next_report - now } else { Duration::from_secs(0) };
+            match rx.recv_timeout(timeout) {
+                Ok((key, msg)) => {
+                    metrics.lock().unwrap().published(&key);
+                    if tx_pub.send((key, msg)).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if Instant::now() >= next_report {
+                next_report = Instant::now() + interval;
+                let depth = bus.queue_depth(&name);
+                let report = {
+                    let mut metrics = metrics.lock().unwrap();
+                    metrics.queue_depth = depth;
+                    metrics.clone()
+                };
+                info!("bus metrics {:?}", report);
+                if let Ok(content) = serde_json::to_vec(&report) {
+                    if tx_pub.send((format!("{}.bus_metrics", name), content)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    (tx_bus, rx_pub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use InProcessBus;
+
+    #[test]
+    fn histogram_observe() {
+        let mut h = Histogram::new(&[1, 2, 5]);
+        h.observe(1);
+        h.observe(3);
+        h.observe(9);
+        assert_eq!(h.buckets, vec![1, 0, 1, 1]);
+        assert_eq!(h.count, 3);
+        assert_eq!(h.sum, 13);
+    }
+
+    #[test]
+    fn counts() {
+        let (tx_sub, rx_sub) = sync_channel(1);
+        let (tx_pub, rx_pub) = channel();
+        let (tx_bus, rx_bus) = instrument("test", InProcessBus::global(), tx_sub, rx_pub);
+
+        tx_pub.send(("test.out".to_string(), vec![1])).unwrap();
+        assert_eq!(rx_bus.recv().unwrap(), ("test.out".to_string(), vec![1]));
+        tx_bus.send(("other.in".to_string(), vec![2])).unwrap();
+        assert_eq!(rx_sub.recv().unwrap(), ("other.in".to_string(), vec![2]));
+
+        let mut metrics = BusMetrics::new("test");
+        metrics.published("test.out");
+        metrics.consumed("other.in", Duration::from_millis(7));
+        metrics.consumed("other.in", Duration::from_millis(700));
+        assert_eq!(metrics.topics["test.out"].published, 1);
+        assert_eq!(metrics.topics["other.in"].consumed, 2);
+        assert_eq!(metrics.topics["other.in"].wait.buckets[2], 1);
+        assert_eq!(metrics.topics["other.in"].wait.buckets[7], 1);
+    }
+}
@@ -0,0 +1,194 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Retries of the messages a module failed to handle, so a transient error
+//! does not drop a block or a transaction. A handler failing is called again
+//! after a backoff doubled on each attempt. A message failing all its attempts
+//! is written down as a dead letter of the queue, one json per line in
+//! `<queue>.jsonl` of the dead letters directory, whose messages the replay
+//! tool publishes again once the cause is fixed. A panic is never caught, as
+//! the state of the module may be broken half way: the module dies and the
+//! supervisor restarts it.
+
+use serde_json;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Attempts of handling a message before it is a dead letter, 3 by default.
+pub const BUS_RETRY_ATTEMPTS: &'static str = "BUS_RETRY_ATTEMPTS";
+/// Milliseconds before the first retry of a message, doubled on each retry, 100 by default.
+pub const BUS_RETRY_BACKOFF: &'static str = "BUS_RETRY_BACKOFF";
+/// Directory of the dead letters, `$DATA_PATH/dead_letters` by default.
+pub const DEAD_LETTER_PATH: &'static str = "DEAD_LETTER_PATH";
+const DEFAULT_ATTEMPTS: u32 = 3;
+const DEFAULT_BACKOFF: u64 = 100;
+
+/// A message failed by its queue.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    pub key: String,
+    pub msg: Vec<u8>,
+    pub error: String,
+}
+
+/// The directory of the dead letters selected by the environment.
+pub fn dead_letter_dir() -> PathBuf {
+    match env::var(DEAD_LETTER_PATH) {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => Path::new(&env::var("DATA_PATH").unwrap_or(".".to_string())).join("dead_letters"),
+    }
+}
+
+/// The file of the dead letters of the queue in the directory.
+pub fn dead_letter_file(dir: &Path, queue: &str) -> PathBuf {
+    dir.join(format!("{}.jsonl", queue))
+}
+
+/// The dead letters of the file, none if there is no file.
+pub fn read_dead_letters(path: &Path) -> io::Result<Vec<DeadLetter>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut letters = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        letters.push(serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+    }
+    Ok(letters)
+}
+
+/// Drop the dead letters of the file, once they are replayed.
+pub fn clear_dead_letters(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Retry {
+    attempts: u32,
+    backoff: Duration,
+    file: PathBuf,
+}
+
+impl Retry {
+    /// The retries of the queue of the name, as set by the environment.
+    pub fn new(queue: &str) -> Self {
+        let attempts = env::var(BUS_RETRY_ATTEMPTS).ok().and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_ATTEMPTS);
+        let backoff = env::var(BUS_RETRY_BACKOFF).ok().and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_BACKOFF);
+        Retry::with(queue, attempts, Duration::from_millis(backoff), &dead_letter_dir())
+    }
+
+    pub fn with(queue: &str, attempts: u32, backoff: Duration, dir: &Path) -> Self {
+        Retry {
+            attempts: ::std::cmp::max(attempts, 1),
+            backoff: backoff,
+            file: dead_letter_file(dir, queue),
+        }
+    }
+
+    /// Handle the message of the key, again on a failure until the attempts run out,
+    /// when it is written down as a dead letter.
+    /// Whether the message was handled.
+    pub fn handle<F>(&self, key: &str, msg: &[u8], mut handler: F) -> bool
+        where F: FnMut(&[u8]) -> Result<(), String>
+    {
+        let mut backoff = self.backoff;
+        let mut error = String::new();
+        for attempt in 1..self.attempts + 1 {
+            match handler(msg) {
+                Ok(()) => return true,
+                Err(e) => error = e,
+            }
+            if attempt < self.attempts {
+                warn!("failed to handle {} on attempt {}: {}, retry in {:?}", key, attempt, error, backoff);
+                thread::sleep(backoff);
+                backoff = backoff * 2;
+            }
+        }
+        error!("failed to handle {}: {}, dead letter in {:?}", key, error, self.file);
+        let letter = DeadLetter {
+            key: key.to_string(),
+            msg: msg.to_vec(),
+            error: error,
+        };
+        if let Err(e) = self.write(&letter) {
+            error!("failed to write the dead letter of {}: {}", key, e);
+        }
+        false
+    }
+
+    fn write(&self, letter: &DeadLetter) -> io::Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.file)?;
+        let line = serde_json::to_string(letter).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writeln!(file, "{}", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries() {
+        let dir = env::temp_dir().join("pubsub_dead_letters");
+        let _ = fs::remove_dir_all(&dir);
+        let retry = Retry::with("chain", 3, Duration::from_millis(1), &dir);
+        let file = dead_letter_file(&dir, "chain");
+
+        let mut calls = 0;
+        assert!(retry.handle("net.blk", &[1], |_| {
+            calls += 1;
+            if calls < 3 { Err("busy".to_string()) } else { Ok(()) }
+        }));
+        assert_eq!(calls, 3);
+        assert!(read_dead_letters(&file).unwrap().is_empty());
+
+        let mut calls = 0;
+        assert!(!retry.handle("consensus.blk", &[3], |_| {
+            calls += 1;
+            Err("bad".to_string())
+        }));
+        assert_eq!(calls, 3);
+        let letters = read_dead_letters(&file).unwrap();
+        assert_eq!(letters,
+                   vec![
+            DeadLetter {
+                key: "consensus.blk".to_string(),
+                msg: vec![3],
+                error: "bad".to_string(),
+            },
+        ]);
+
+        clear_dead_letters(&file).unwrap();
+        assert!(read_dead_letters(&file).unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
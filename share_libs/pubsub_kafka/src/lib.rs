@@ -15,8 +15,154 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Messages between the modules over Kafka, for the deployments running it,
+//! so the messages are kept in a durable log which may be replayed, to rebuild
+//! an indexer for one. All the messages go to one topic, KAFKA_TOPIC, keyed
+//! by their routing keys, so those of a key keep their order. Each queue reads
+//! the whole topic as a consumer group of its name, the keys it subscribes to
+//! are to be matched by the caller. Kafka has no priorities, the messages are
+//! delivered in the order they were published.
+
+extern crate kafka;
+
+use kafka::client::{FetchOffset, GroupOffsetStorage, KafkaClient};
+use kafka::consumer::Consumer;
+use kafka::producer::{Producer, Record, RequiredAcks};
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::Duration;
+
+/// The brokers, as `host:port` separated by commas.
+pub const KAFKA_BROKERS: &'static str = "KAFKA_BROKERS";
+/// The topic of the messages, "cita" by default.
+pub const KAFKA_TOPIC: &'static str = "KAFKA_TOPIC";
+/// Where a queue without an offset committed starts reading, "latest" by default, or "earliest"
+/// to replay the messages kept by the brokers.
+pub const KAFKA_OFFSET: &'static str = "KAFKA_OFFSET";
+const DEFAULT_TOPIC: &'static str = "cita";
+// seconds the brokers have to acknowledge a message published
+const ACK_TIMEOUT: u64 = 1;
+// seconds before polling the brokers again after a failure
+const POLL_RETRY_INTERVAL: u64 = 1;
+
+pub fn parse_brokers(s: &str) -> Vec<String> {
+    s.split(',').map(|host| host.trim().to_string()).filter(|host| !host.is_empty()).collect()
+}
+
+fn brokers() -> Vec<String> {
+    parse_brokers(&std::env::var(KAFKA_BROKERS).expect(format!("{} must be set", KAFKA_BROKERS).as_str()))
+}
+
+fn topic() -> String {
+    std::env::var(KAFKA_TOPIC).unwrap_or(DEFAULT_TOPIC.to_string())
+}
+
+/// The offset to start from, of the value of KAFKA_OFFSET.
+pub fn fallback_offset(value: Option<&str>) -> FetchOffset {
+    match value {
+        Some("earliest") => FetchOffset::Earliest,
+        _ => FetchOffset::Latest,
+    }
+}
+
+/// Messages of the topic the consumer group of the name has not committed yet, none if
+/// the brokers can't tell.
+pub fn queue_depth(name: &str) -> Option<u32> {
+    let hosts = match std::env::var(KAFKA_BROKERS) {
+        Ok(hosts) => parse_brokers(&hosts),
+        Err(_) => return None,
+    };
+    let topic = topic();
+    let mut client = KafkaClient::new(hosts);
+    client.set_group_offset_storage(GroupOffsetStorage::Kafka);
+    if client.load_metadata(&[&topic]).is_err() {
+        return None;
+    }
+    let latest = match client.fetch_topic_offsets(&topic, FetchOffset::Latest) {
+        Ok(offsets) => offsets,
+        Err(_) => return None,
+    };
+    let committed = match client.fetch_group_topic_offsets(name, &topic) {
+        Ok(offsets) => offsets,
+        Err(_) => return None,
+    };
+    let lag: i64 = latest.iter()
+                         .map(|latest| {
+                                  // a partition never committed is read from the latest
+                                  let committed = committed.iter().find(|c| c.partition == latest.partition).map_or(latest.offset, |c| c.offset);
+                                  if committed < 0 { 0 } else { latest.offset - committed }
+                              })
+                         .sum();
+    Some(lag as u32)
+}
+
+/// Forward the messages of the topic to tx, the keys are to be matched by the caller,
+/// and publish those from rx.
+pub fn start_kafka(name: &str, _keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+    let hosts = brokers();
+    let topic = topic();
+    let offset = fallback_offset(std::env::var(KAFKA_OFFSET).ok().as_ref().map(String::as_str));
+
+    let mut consumer = match Consumer::from_hosts(hosts.clone())
+              .with_topic(topic.clone())
+              .with_group(name.to_string())
+              .with_fallback_offset(offset)
+              .with_offset_storage(GroupOffsetStorage::Kafka)
+              .create() {
+        Ok(consumer) => consumer,
+        Err(error) => panic!("failed to consume {} from {:?} : {:?}", topic, hosts, error),
+    };
+    // thread recv msg from the brokers, committed once the module has them in its queue
+    let _ = thread::Builder::new().name("subscriber".to_string()).spawn(move || 'poll: loop {
+        let sets = match consumer.poll() {
+            Ok(sets) => sets,
+            Err(_) => {
+                thread::sleep(Duration::from_secs(POLL_RETRY_INTERVAL));
+                continue;
+            }
+        };
+        for set in sets.iter() {
+            for msg in set.messages() {
+                let key = String::from_utf8_lossy(msg.key).into_owned();
+                if tx.send((key, msg.value.to_vec())).is_err() {
+                    break 'poll;
+                }
+            }
+            let _ = consumer.consume_messageset(set);
+        }
+        let _ = consumer.commit_consumed();
+    });
+
+    let mut producer = match Producer::from_hosts(hosts.clone())
+              .with_ack_timeout(Duration::from_secs(ACK_TIMEOUT))
+              .with_required_acks(RequiredAcks::One)
+              .create() {
+        Ok(producer) => producer,
+        Err(error) => panic!("failed to publish to {:?} : {:?}", hosts, error),
+    };
+    // thread send msg to the brokers
+    let _ = thread::Builder::new().name("publisher".to_string()).spawn(move || for (routing_key, msg) in rx {
+                                                                          let _ = producer.send(&Record::from_key_value(&topic, routing_key.as_bytes(), msg));
+                                                                      });
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {}
+
+    #[test]
+    fn settings() {
+        assert_eq!(parse_brokers("kafka1:9092, kafka2:9092,"), vec!["kafka1:9092", "kafka2:9092"]);
+        let latest = |offset| match offset {
+            FetchOffset::Latest => true,
+            _ => false,
+        };
+        assert!(latest(fallback_offset(None)));
+        assert!(!latest(fallback_offset(Some("earliest"))));
+        assert!(latest(fallback_offset(Some("bad"))));
+    }
 }
@@ -16,17 +16,39 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 extern crate amqp;
-use amqp::{Basic, Session, Consumer, Channel, Table, protocol};
+use amqp::{Basic, Session, Consumer, Channel, Table, TableEntry, protocol};
+use std::str::FromStr;
 use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
 use std::thread;
 
+/// Priorities of the messages, the consensus first and the transactions last.
+pub const PRIORITY_TX: u8 = 0;
+pub const PRIORITY_CHAIN: u8 = 1;
+pub const PRIORITY_CONSENSUS: u8 = 2;
+
+// keys of the transactions and of the messages of auth
+const TX_KEYS: &'static [&'static str] = &["net.tx", "jsonrpc.new_tx", "consensus.tx", "chain.auth"];
+
+/// Priority of the messages of the routing key in the queues.
+pub fn priority(routing_key: &str) -> u8 {
+    if TX_KEYS.contains(&routing_key) {
+        PRIORITY_TX
+    } else if routing_key.starts_with("consensus.") || routing_key == "net.msg" {
+        PRIORITY_CONSENSUS
+    } else {
+        PRIORITY_CHAIN
+    }
+}
+
+/// Blocks while the queue of the module is full, and the messages delivered and not
+/// acknowledged are bounded by the prefetch, so the broker holds the rest.
 pub struct Handler {
-    tx: Sender<(String, Vec<u8>)>,
+    tx: SyncSender<(String, Vec<u8>)>,
 }
 
 impl Handler {
-    pub fn new(tx: Sender<(String, Vec<u8>)>) -> Self {
+    pub fn new(tx: SyncSender<(String, Vec<u8>)>) -> Self {
         Handler { tx: tx }
     }
 }
@@ -39,8 +61,37 @@ impl Consumer for Handler {
 }
 
 pub const AMQP_URL: &'static str = "AMQP_URL";
+/// Messages delivered to a module and not acknowledged at most, 10 by default.
+pub const AMQP_PREFETCH: &'static str = "AMQP_PREFETCH";
+/// Messages held in the queue of a module by the broker at most, over which the oldest
+/// are dropped, no bound by default.
+pub const AMQP_MAX_LENGTH: &'static str = "AMQP_MAX_LENGTH";
+const DEFAULT_PREFETCH: u16 = 10;
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+/// Messages held by the broker in the queue of the name, none if the broker can't tell.
+pub fn queue_depth(name: &str) -> Option<u32> {
+    let mut session = match std::env::var(AMQP_URL).ok().and_then(|url| Session::open_url(&url).ok()) {
+        Some(session) => session,
+        None => return None,
+    };
+    let mut channel = match session.open_channel(1) {
+        Ok(channel) => channel,
+        Err(_) => return None,
+    };
+    // passive, so the queue is not declared again with other arguments
+    let depth = channel.queue_declare(name, true, true, false, false, false, Table::new()).ok().map(|declared| declared.message_count);
+    let _ = channel.close(200, "Bye");
+    let _ = session.close(200, "Good Bye");
+    depth
+}
 
-pub fn start_rabbitmq(name: &str, keys: Vec<&str>, tx: Sender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+pub fn start_rabbitmq(name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+    let prefetch = env_or(AMQP_PREFETCH, DEFAULT_PREFETCH);
+    let max_length: i64 = env_or(AMQP_MAX_LENGTH, 0);
     let amqp_url = std::env::var(AMQP_URL).expect(format!("{} must be set", AMQP_URL).as_str());
     let mut session = match Session::open_url(&amqp_url) {
         Ok(session) => session,
@@ -48,11 +99,17 @@ pub fn start_rabbitmq(name: &str, keys: Vec<&str>, tx: Sender<(String, Vec<u8>)>
     };
 
     let mut channel = session.open_channel(1).ok().expect("Can't open channel");
-    let _ = channel.basic_prefetch(10);
+    let _ = channel.basic_prefetch(prefetch);
     channel.exchange_declare("cita", "topic", false, true, false, false, false, Table::new()).unwrap();
 
+    // a queue declared before with other arguments has to be deleted
+    let mut arguments = Table::new();
+    arguments.insert("x-max-priority".to_string(), TableEntry::ShortShortUint(PRIORITY_CONSENSUS));
+    if max_length > 0 {
+        arguments.insert("x-max-length".to_string(), TableEntry::LongLongInt(max_length));
+    }
     //queue: &str, passive: bool, durable: bool, exclusive: bool, auto_delete: bool, nowait: bool, arguments: Table
-    channel.queue_declare(name.clone(), false, true, false, false, false, Table::new()).unwrap();
+    channel.queue_declare(name.clone(), false, true, false, false, false, arguments).unwrap();
 
     for key in keys {
         channel.queue_bind(name.clone(), "cita", key, false, Table::new()).unwrap();
@@ -68,7 +125,7 @@ pub fn start_rabbitmq(name: &str, keys: Vec<&str>, tx: Sender<(String, Vec<u8>)>
                                                                         });
 
     let mut channel = session.open_channel(2).ok().expect("Can't open channel");
-    let _ = channel.basic_prefetch(10);
+    let _ = channel.basic_prefetch(prefetch);
     channel.exchange_declare("cita", "topic", false, true, false, false, false, Table::new()).unwrap();
 
     // thread send msg to mq
@@ -85,6 +142,7 @@ pub fn start_rabbitmq(name: &str, keys: Vec<&str>, tx: Sender<(String, Vec<u8>)>
                                           false,
                                           protocol::basic::BasicProperties {
                                               content_type: Some("text".to_string()),
+                                              priority: Some(priority(&routing_key)),
                                               ..Default::default()
                                           },
                                           msg);
@@ -15,8 +15,91 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Messages between the modules over ZeroMQ, without a broker. The queue of
+//! each module publishes on its own endpoint and subscribes to the endpoints
+//! of all the modules, the endpoints given by ZMQ_ENDPOINTS as
+//! `name=endpoint` pairs separated by commas.
+
+extern crate zmq;
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+
+pub const ZMQ_ENDPOINTS: &'static str = "ZMQ_ENDPOINTS";
+
+/// The endpoints of the queues by their names.
+pub fn parse_endpoints(s: &str) -> HashMap<String, String> {
+    s.split(',')
+     .filter_map(|pair| {
+                     let mut parts = pair.splitn(2, '=');
+                     match (parts.next(), parts.next()) {
+                         (Some(name), Some(endpoint)) => Some((name.trim().to_string(), endpoint.trim().to_string())),
+                         _ => None,
+                     }
+                 })
+     .collect()
+}
+
+/// The part of the key before its first wildcard, which the socket subscribes to.
+pub fn key_prefix(key: &str) -> &str {
+    match key.find(|c| c == '*' || c == '#') {
+        Some(index) => &key[..index],
+        None => key,
+    }
+}
+
+/// Forward the messages with a key prefixed by a subscribed one to tx, the keys
+/// with wildcards are to be matched by the caller, and publish those from rx.
+pub fn start_zeromq(name: &str, keys: Vec<&str>, tx: SyncSender<(String, Vec<u8>)>, rx: Receiver<(String, Vec<u8>)>) {
+    let endpoints = parse_endpoints(&std::env::var(ZMQ_ENDPOINTS).expect(format!("{} must be set", ZMQ_ENDPOINTS).as_str()));
+    let own = endpoints.get(name).cloned().expect(format!("no endpoint of {} in {}", name, ZMQ_ENDPOINTS).as_str());
+    let context = zmq::Context::new();
+
+    let subscriber = context.socket(zmq::SUB).expect("Can't create socket");
+    for endpoint in endpoints.values() {
+        subscriber.connect(endpoint).unwrap();
+    }
+    for key in keys {
+        subscriber.set_subscribe(key_prefix(key).as_bytes()).unwrap();
+    }
+    // thread recv msg from the other modules
+    let _ = thread::Builder::new().name("subscriber".to_string()).spawn(move || loop {
+        let parts = match subscriber.recv_multipart(0) {
+            Ok(parts) => parts,
+            Err(_) => break,
+        };
+        if parts.len() != 2 {
+            continue;
+        }
+        let key = String::from_utf8_lossy(&parts[0]).into_owned();
+        if tx.send((key, parts[1].clone())).is_err() {
+            break;
+        }
+    });
+
+    let publisher = context.socket(zmq::PUB).expect("Can't create socket");
+    if let Err(error) = publisher.bind(&own) {
+        panic!("failed to bind {} : {:?}", own, error);
+    }
+    // thread send msg to the other modules
+    let _ = thread::Builder::new().name("publisher".to_string()).spawn(move || for (routing_key, msg) in rx {
+                                                                          let _ = publisher.send_multipart(&[routing_key.as_bytes(), &msg[..]], 0);
+                                                                      });
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    fn it_works() {}
+    fn endpoints() {
+        let endpoints = parse_endpoints("chain=tcp://127.0.0.1:5001, network=ipc:///tmp/network,bad");
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints["network"], "ipc:///tmp/network");
+        assert_eq!(key_prefix("*.rpc"), "");
+        assert_eq!(key_prefix("chain.#"), "chain.");
+        assert_eq!(key_prefix("net.tx"), "net.tx");
+    }
 }
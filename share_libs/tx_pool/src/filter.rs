@@ -21,20 +21,49 @@ use util::H256;
 #[derive(Debug)]
 pub struct Filter {
     inner: LruCache<H256, u32>,
+    hits: u64,
+    misses: u64,
 }
 
 impl Filter {
     pub fn new(capacity: usize) -> Self {
-        Filter { inner: LruCache::new(capacity) }
+        Filter {
+            inner: LruCache::new(capacity),
+            hits: 0,
+            misses: 0,
+        }
     }
 
     pub fn check(&mut self, hash: H256) -> bool {
         let is_ok = !self.inner.contains_key(&hash);
         if is_ok {
             self.inner.insert(hash, 0);
+            self.misses += 1;
+        } else {
+            self.hits += 1;
         }
         is_ok
     }
+
+    /// Whether the hash is seen recently, without counting it as a check.
+    pub fn contains(&mut self, hash: &H256) -> bool {
+        self.inner.contains_key(hash)
+    }
+
+    /// Number of the checks finding the hash a duplicate.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of the checks finding the hash new.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Forget the hash, so that the transaction may be sent again.
+    pub fn forget(&mut self, hash: &H256) {
+        self.inner.remove(hash);
+    }
 }
 
 #[cfg(test)]
@@ -75,5 +104,6 @@ mod tests {
         assert_eq!(f.check(tx3.crypt_hash()), true);
         assert_eq!(f.check(tx4.crypt_hash()), true);
         assert_eq!(f.check(tx2.crypt_hash()), true);
+        assert_eq!((f.hits(), f.misses()), (1, 4));
     }
 }
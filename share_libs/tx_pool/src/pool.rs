@@ -17,16 +17,219 @@
 
 use filter::Filter;
 use libproto::blockchain::SignedTransaction;
-use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use lru_cache::LruCache;
+use std::cmp::{self, Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashSet, VecDeque};
 use std::collections::HashMap;
-use util::H256;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use util::{H256, U256};
 
+/// Percent a replacement must raise the quota price of the pending transaction by.
+pub const DEFAULT_PRICE_BUMP: u64 = 10;
+
+/// Transactions held at most for a sender with nonces ahead of its next nonce.
+pub const FUTURE_LIMIT: usize = 64;
+
+/// Transactions in a bundle at most.
+pub const MAX_BUNDLE_SIZE: usize = 16;
+// Bundles waiting for the rest of their transactions at most.
+const MAX_PARTIAL_BUNDLES: usize = 1024;
+// Bundles of a sender waiting for the rest of their transactions at most.
+const MAX_PARTIAL_BUNDLES_PER_SENDER: usize = 4;
+
+/// What to do with a transaction of a sender having the limit of pending transactions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccountOverflow {
+    Reject,
+    /// Queue up to the limit more, entering the pending ones as those of the sender leave.
+    Queue,
+}
+
+/// Bounds of the transactions held by the pool, zero means no bound.
+/// Over a bound, the transaction with the lowest quota price, the oldest of them, is evicted.
+/// The transactions of the bundles waiting for the rest count in the bounds, but are never evicted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PoolLimits {
+    pub max_txs: usize,
+    /// Approximate size of the transactions in bytes.
+    pub max_bytes: usize,
+    /// Transactions held longer are expired.
+    pub ttl: Option<Duration>,
+}
+
+/// Counters of the pool.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolStats {
+    pub pending: usize,
+    pub queued: usize,
+    pub future: usize,
+    pub bytes: usize,
+    pub evicted: u64,
+    pub expired: u64,
+    /// Checks of the recent hashes finding a duplicate, or not.
+    pub filter_hits: u64,
+    pub filter_misses: u64,
+}
+
+/// Order of the transactions packaged into a block.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Strategy {
     FIFO,
     PRIORITY,
     VIP,
+    /// The highest quota price first, the transactions of a sender in the order of their nonces.
+    QUOTA_PRICE,
+}
+
+/// Outcome of adding a transaction to the pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Enqueued {
+    New,
+    /// Replaced the pending transaction of the sender with the same nonce, which is returned.
+    Replaced(SignedTransaction),
+    Duplicate,
+    /// Same nonce as a pending transaction of the sender, without raising the quota price enough.
+    Underpriced,
+    /// Queued as the sender has the limit of pending transactions.
+    Queued,
+    /// Rejected as the sender has the limit of pending or future transactions.
+    AccountLimit,
+    /// Held until the gap to its nonce is filled by the transactions of the sender.
+    Future,
+    /// Rejected as the pool is full of transactions with a quota price not lower.
+    PoolFull,
+    /// Rejected as its valid_until_block is below the height of the next block.
+    Expired,
+    /// Held until the rest of its bundle arrives.
+    Bundled,
+    /// Rejected as its bundle is too large, or does not agree on the size or with consecutive nonces.
+    BadBundle,
+}
+
+impl Enqueued {
+    pub fn is_ok(&self) -> bool {
+        match *self {
+            Enqueued::New | Enqueued::Replaced(_) | Enqueued::Queued | Enqueued::Future | Enqueued::Bundled => true,
+            _ => false,
+        }
+    }
+}
+
+fn sender_nonce(tx: &SignedTransaction) -> (Vec<u8>, String) {
+    (tx.get_signer().to_vec(), tx.get_transaction_with_sig().get_transaction().get_nonce().to_string())
+}
+
+fn quota_price(tx: &SignedTransaction) -> u64 {
+    tx.get_transaction_with_sig().get_transaction().get_quota_price()
+}
+
+fn nonce_of(tx: &SignedTransaction) -> Option<U256> {
+    U256::from_str(tx.get_transaction_with_sig().get_transaction().get_nonce()).ok()
+}
+
+// approximate size of the transaction in memory
+fn tx_size(tx: &SignedTransaction) -> usize {
+    let utx = tx.get_transaction_with_sig();
+    let t = utx.get_transaction();
+    t.get_to().len() + t.get_nonce().len() + t.get_data().len() + utx.get_signature().len() + utx.get_fee_payer_signature().len() + tx.get_tx_hash().len() + tx.get_signer().len() + tx.get_fee_payer().len() + 32
+}
+
+// The sender and the id of the bundle of the transaction, if it is in one.
+fn bundle_of(tx: &SignedTransaction) -> Option<(Vec<u8>, Vec<u8>)> {
+    let bundle = tx.get_transaction_with_sig().get_transaction().get_bundle();
+    if bundle.is_empty() { None } else { Some((tx.get_signer().to_vec(), bundle.to_vec())) }
+}
+
+fn consecutive(txs: &[SignedTransaction]) -> bool {
+    txs.windows(2).all(|pair| match (nonce_of(&pair[0]), nonce_of(&pair[1])) {
+                           (Some(a), Some(b)) => a.overflowing_add(U256::one()) == (b, false),
+                           _ => false,
+                       })
+}
+
+// Put the transactions of a bundle together where the first of them is, and take
+// the transactions in the order up to the limit without splitting a bundle.
+fn group_bundles(txs: Vec<SignedTransaction>, limit: usize) -> Vec<SignedTransaction> {
+    let mut bundles: HashMap<(Vec<u8>, Vec<u8>), Vec<SignedTransaction>> = HashMap::new();
+    for tx in &txs {
+        if let Some(key) = bundle_of(tx) {
+            bundles.entry(key).or_insert_with(Vec::new).push(tx.clone());
+        }
+    }
+    let mut grouped = Vec::new();
+    for tx in txs {
+        let group = match bundle_of(&tx) {
+            Some(key) => {
+                match bundles.remove(&key) {
+                    Some(group) => group,
+                    None => continue,
+                }
+            }
+            None => vec![tx],
+        };
+        if grouped.len() + group.len() > limit {
+            break;
+        }
+        grouped.extend(group);
+    }
+    grouped
+}
+
+fn expired(tx: &SignedTransaction, height: u64) -> bool {
+    let valid_until_block = tx.get_transaction_with_sig().get_transaction().valid_until_block;
+    valid_until_block != 0 && valid_until_block < height
+}
+
+// Reorder the transactions in the arrival order by the quota price, taking the next
+// transaction of a sender only after those with lower nonces. Ties keep the arrival order.
+fn order_by_quota_price(txs: Vec<SignedTransaction>) -> Vec<SignedTransaction> {
+    let mut senders: HashMap<Vec<u8>, Vec<(usize, SignedTransaction)>> = HashMap::new();
+    for (arrival, tx) in txs.into_iter().enumerate() {
+        senders.entry(tx.get_signer().to_vec()).or_insert_with(Vec::new).push((arrival, tx));
+    }
+    let mut heads = BinaryHeap::new();
+    let mut rests = Vec::new();
+    for (_, mut txs) in senders {
+        // nonces not parsed stay in the arrival order after the others
+        txs.sort_by_key(|&(arrival, ref tx)| (nonce_of(tx).is_none(), nonce_of(tx).unwrap_or_default(), arrival));
+        let mut rest: VecDeque<_> = txs.into_iter().collect();
+        let (arrival, tx) = rest.pop_front().expect("a transaction of the sender");
+        heads.push((quota_price(&tx), Reverse(arrival), rests.len()));
+        rests.push((Some(tx), rest));
+    }
+    let mut ordered = Vec::new();
+    while let Some((_, _, sender)) = heads.pop() {
+        let (ref mut head, ref mut rest) = rests[sender];
+        ordered.push(head.take().expect("head of the sender"));
+        if let Some((arrival, tx)) = rest.pop_front() {
+            heads.push((quota_price(&tx), Reverse(arrival), sender));
+            *head = Some(tx);
+        }
+    }
+    ordered
+}
+
+// Transactions of a sender packaged together, all or none, in the order of their nonces.
+#[derive(Debug)]
+struct Bundle {
+    size: usize,
+    // when its first transaction arrived
+    arrival: Instant,
+    // arrived before the bundle is complete
+    partial: Vec<SignedTransaction>,
+    // added to the pool once complete, in the order of the nonces
+    members: Vec<H256>,
+}
+
+#[derive(Debug)]
+struct Held {
+    sender: Vec<u8>,
+    nonce: String,
+    price: u64,
+    seq: u64,
+    arrival: Instant,
+    size: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -62,36 +265,131 @@ impl Ord for TxOrder {
 #[derive(Debug)]
 pub struct Pool {
     package_limit: usize,
+    // transactions fitting the block quota limit, packaged no more than the package limit
+    quota_limit: Option<usize>,
     filter: Filter,
     order_set: BTreeSet<TxOrder>,
     txs: HashMap<H256, SignedTransaction>,
+    // pending transaction and its order by the sender and nonce
+    nonces: HashMap<(Vec<u8>, String), (H256, u64)>,
+    // number of pending transactions by the sender
+    senders: HashMap<Vec<u8>, usize>,
+    queued: HashMap<Vec<u8>, VecDeque<SignedTransaction>>,
+    // next nonce of the recent senders, after their pending and committed transactions
+    next_nonces: LruCache<Vec<u8>, U256>,
+    futures: HashMap<Vec<u8>, BTreeMap<U256, SignedTransaction>>,
+    // all the transactions held, pending, queued or future, and their eviction order
+    held: HashMap<H256, Held>,
+    by_price: BTreeSet<(u64, u64, H256)>,
+    seq: u64,
+    bytes: usize,
+    // transactions of the bundles waiting for the rest and their size
+    partial_txs: usize,
+    partial_bytes: usize,
+    evicted: Vec<SignedTransaction>,
+    evicted_count: u64,
+    expired_count: u64,
+    // height of the next block, the transactions valid until a lower one are refused
+    height: u64,
+    bundles: HashMap<(Vec<u8>, Vec<u8>), Bundle>,
     strategy: Strategy,
     order: u64,
+    price_bump: u64,
+    account_limit: usize,
+    account_overflow: AccountOverflow,
+    limits: PoolLimits,
 }
 
 impl Pool {
     pub fn new(capacity: usize, package_limit: usize) -> Self {
         Pool {
             package_limit: package_limit,
+            quota_limit: None,
             filter: Filter::new(capacity),
             order_set: BTreeSet::new(),
             txs: HashMap::new(),
+            nonces: HashMap::new(),
+            senders: HashMap::new(),
+            queued: HashMap::new(),
+            next_nonces: LruCache::new(capacity),
+            futures: HashMap::new(),
+            held: HashMap::new(),
+            by_price: BTreeSet::new(),
+            seq: 0,
+            bytes: 0,
+            partial_txs: 0,
+            partial_bytes: 0,
+            evicted: Vec::new(),
+            evicted_count: 0,
+            expired_count: 0,
+            height: 0,
+            bundles: HashMap::new(),
             strategy: Strategy::FIFO,
             order: 0,
+            price_bump: DEFAULT_PRICE_BUMP,
+            account_limit: 0,
+            account_overflow: AccountOverflow::Reject,
+            limits: PoolLimits::default(),
         }
     }
 
     pub fn new_with_strategy(capacity: usize, package_limit: usize, strategy: Strategy) -> Self {
         Pool {
             package_limit: package_limit,
+            quota_limit: None,
             filter: Filter::new(capacity),
             order_set: BTreeSet::new(),
             txs: HashMap::new(),
+            nonces: HashMap::new(),
+            senders: HashMap::new(),
+            queued: HashMap::new(),
+            next_nonces: LruCache::new(capacity),
+            futures: HashMap::new(),
+            held: HashMap::new(),
+            by_price: BTreeSet::new(),
+            seq: 0,
+            bytes: 0,
+            partial_txs: 0,
+            partial_bytes: 0,
+            evicted: Vec::new(),
+            evicted_count: 0,
+            expired_count: 0,
+            height: 0,
+            bundles: HashMap::new(),
             strategy: strategy,
             order: 0,
+            price_bump: DEFAULT_PRICE_BUMP,
+            account_limit: 0,
+            account_overflow: AccountOverflow::Reject,
+            limits: PoolLimits::default(),
         }
     }
 
+    /// Set the percent a replacement must raise the quota price by.
+    pub fn set_price_bump(&mut self, price_bump: u64) {
+        self.price_bump = price_bump;
+    }
+
+    /// Limit the pending transactions of a sender, zero means no limit.
+    pub fn set_account_limit(&mut self, limit: usize, overflow: AccountOverflow) {
+        self.account_limit = limit;
+        self.account_overflow = overflow;
+    }
+
+    pub fn set_limits(&mut self, limits: PoolLimits) {
+        self.limits = limits;
+    }
+
+    /// Package no more transactions than fit the quota limit of a block at the quota
+    /// of each, no limit if None.
+    pub fn set_quota_limit(&mut self, quota_limit: Option<u64>, quota_per_tx: u64) {
+        self.quota_limit = quota_limit.map(|limit| (limit / cmp::max(quota_per_tx, 1)) as usize);
+    }
+
+    fn package_limit(&self) -> usize {
+        self.quota_limit.map_or(self.package_limit, |limit| cmp::min(limit, self.package_limit))
+    }
+
     fn get_order(&mut self) -> u64 {
         let order = self.order;
         let (new_order, _) = order.overflowing_add(1);
@@ -110,41 +408,459 @@ impl Pool {
     }
 
     pub fn enqueue(&mut self, tx: SignedTransaction) -> bool {
+        self.add(tx).is_ok()
+    }
+
+    fn outbids(&self, old: &SignedTransaction, tx: &SignedTransaction) -> bool {
+        let (old_price, price) = (quota_price(old), quota_price(tx));
+        price > old_price && price.saturating_mul(100) >= old_price.saturating_mul(100 + self.price_bump)
+    }
+
+    /// Add the transaction, replacing the pending one of the sender with the same nonce
+    /// if it raises the quota price by the price bump. The replacement takes its place in the order.
+    /// A transaction with a nonce ahead of the next nonce of the sender is held until the gap is filled,
+    /// the next nonce is known for the senders seen recently only.
+    /// A transaction over the limits of the pool evicts those with a lower quota price.
+    /// The transactions of a bundle are held until all of them arrive, then added in the order of their nonces.
+    pub fn add(&mut self, tx: SignedTransaction) -> Enqueued {
+        let hash = H256::from_slice(tx.get_tx_hash());
+
+        if expired(&tx, self.height) {
+            return Enqueued::Expired;
+        }
+        if !self.filter.check(hash) {
+            return Enqueued::Duplicate;
+        }
+        if let Some(key) = bundle_of(&tx) {
+            return self.add_to_bundle(hash, key, tx);
+        }
+        self.add_one(hash, tx)
+    }
+
+    // Hold a transaction of a bundle until the bundle is complete, then add all of them.
+    fn add_to_bundle(&mut self, hash: H256, key: (Vec<u8>, Vec<u8>), tx: SignedTransaction) -> Enqueued {
+        let size = tx.get_transaction_with_sig().get_transaction().get_bundle_size() as usize;
+        if size < 2 || size > MAX_BUNDLE_SIZE {
+            self.filter.forget(&hash);
+            return Enqueued::BadBundle;
+        }
+        match self.bundles.get(&key) {
+            Some(bundle) => {
+                if bundle.size != size || !bundle.members.is_empty() {
+                    self.filter.forget(&hash);
+                    return Enqueued::BadBundle;
+                }
+            }
+            None => {
+                if self.bundles.values().filter(|bundle| bundle.members.is_empty()).count() >= MAX_PARTIAL_BUNDLES {
+                    self.filter.forget(&hash);
+                    return Enqueued::PoolFull;
+                }
+                if self.bundles.iter().filter(|&(other, bundle)| other.0 == key.0 && bundle.members.is_empty()).count() >= MAX_PARTIAL_BUNDLES_PER_SENDER {
+                    self.filter.forget(&hash);
+                    return Enqueued::AccountLimit;
+                }
+            }
+        }
+        if !self.make_room(&tx) {
+            self.filter.forget(&hash);
+            return Enqueued::PoolFull;
+        }
+        self.partial_txs += 1;
+        self.partial_bytes += tx_size(&tx);
+        let mut complete = {
+            let bundle = self.bundles.entry(key.clone()).or_insert_with(|| {
+                                                                            Bundle {
+                                                                                size: size,
+                                                                                arrival: Instant::now(),
+                                                                                partial: Vec::new(),
+                                                                                members: Vec::new(),
+                                                                            }
+                                                                        });
+            bundle.partial.push(tx);
+            if bundle.partial.len() < size {
+                return Enqueued::Bundled;
+            }
+            ::std::mem::replace(&mut bundle.partial, Vec::new())
+        };
+        self.uncount_partial(&complete);
+        complete.sort_by_key(|tx| nonce_of(tx).unwrap_or_default());
+        if !consecutive(&complete) {
+            self.bundles.remove(&key);
+            for tx in &complete {
+                self.filter.forget(&H256::from_slice(tx.get_tx_hash()));
+            }
+            return Enqueued::BadBundle;
+        }
+        let mut members = Vec::new();
+        for tx in complete {
+            let hash = H256::from_slice(tx.get_tx_hash());
+            match self.add_one(hash, tx) {
+                // only the journal of the pool keeps the one replaced
+                Enqueued::Replaced(old) => {
+                    self.evicted.push(old);
+                    members.push(hash);
+                }
+                result => {
+                    if result.is_ok() {
+                        members.push(hash);
+                    }
+                }
+            }
+        }
+        // never packaged short of a transaction, until the rest leave the pool
+        if members.is_empty() {
+            self.bundles.remove(&key);
+        } else if let Some(bundle) = self.bundles.get_mut(&key) {
+            bundle.members = members;
+        }
+        Enqueued::New
+    }
+
+    fn add_one(&mut self, hash: H256, tx: SignedTransaction) -> Enqueued {
+        if !self.make_room(&tx) {
+            // may be sent again when there is room
+            self.filter.forget(&hash);
+            return Enqueued::PoolFull;
+        }
+        let key = sender_nonce(&tx);
+        let seq = self.seq;
+        self.seq += 1;
+        let held = Held {
+            sender: key.0.clone(),
+            nonce: key.1.clone(),
+            price: quota_price(&tx),
+            seq: seq,
+            arrival: Instant::now(),
+            size: tx_size(&tx),
+        };
+        let result = self.place(hash, key, tx);
+        if result.is_ok() {
+            self.by_price.insert((held.price, held.seq, hash));
+            self.bytes += held.size;
+            self.held.insert(hash, held);
+        }
+        match result {
+            Enqueued::Replaced(ref old) => self.untrack(&H256::from_slice(old.get_tx_hash())),
+            Enqueued::AccountLimit => self.filter.forget(&hash),
+            _ => {}
+        }
+        result
+    }
+
+    fn over_limits(&self, size: usize) -> bool {
+        (self.limits.max_txs > 0 && self.held.len() + self.partial_txs + 1 > self.limits.max_txs) || (self.limits.max_bytes > 0 && self.bytes + self.partial_bytes + size > self.limits.max_bytes)
+    }
+
+    fn uncount_partial(&mut self, txs: &[SignedTransaction]) {
+        self.partial_txs -= txs.len();
+        self.partial_bytes -= txs.iter().map(tx_size).sum::<usize>();
+    }
+
+    // Drop the bundle waiting for the rest of its transactions, returns those arrived.
+    fn drop_partial(&mut self, key: &(Vec<u8>, Vec<u8>)) -> Vec<SignedTransaction> {
+        match self.bundles.remove(key) {
+            Some(bundle) => {
+                self.uncount_partial(&bundle.partial);
+                bundle.partial
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // Evict the transactions with a lower quota price until the transaction fits.
+    fn make_room(&mut self, tx: &SignedTransaction) -> bool {
+        let (price, size) = (quota_price(tx), tx_size(tx));
+        while self.over_limits(size) {
+            let lowest = self.by_price.iter().next().cloned();
+            match lowest {
+                Some((lowest_price, _, hash)) if lowest_price < price => {
+                    if let Some(tx) = self.find(&hash) {
+                        self.remove(&[tx.clone()]);
+                        self.evicted.push(tx);
+                        self.evicted_count += 1;
+                    } else {
+                        self.untrack(&hash);
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    // The held transaction of the hash, pending, queued or future.
+    fn find(&self, hash: &H256) -> Option<SignedTransaction> {
+        if let Some(tx) = self.txs.get(hash) {
+            return Some(tx.clone());
+        }
+        let held = match self.held.get(hash) {
+            Some(held) => held,
+            None => return None,
+        };
+        let queued = self.queued.get(&held.sender).and_then(|queue| queue.iter().find(|tx| tx.get_tx_hash() == &hash[..]).cloned());
+        queued.or_else(|| {
+            let nonce = U256::from_str(&held.nonce).ok();
+            self.futures.get(&held.sender).and_then(|futures| nonce.and_then(|nonce| futures.get(&nonce))).cloned()
+        })
+    }
+
+    fn untrack(&mut self, hash: &H256) {
+        if let Some(held) = self.held.remove(hash) {
+            self.by_price.remove(&(held.price, held.seq, *hash));
+            self.bytes -= held.size;
+        }
+    }
+
+    /// The transactions evicted for the room of those added since the last call.
+    pub fn take_evicted(&mut self) -> Vec<SignedTransaction> {
+        ::std::mem::replace(&mut self.evicted, Vec::new())
+    }
+
+    fn place(&mut self, hash: H256, key: (Vec<u8>, String), tx: SignedTransaction) -> Enqueued {
+        let pending = self.nonces.get(&key).cloned();
+        match pending {
+            Some((old_hash, order)) => {
+                if !self.outbids(&self.txs[&old_hash], &tx) {
+                    return Enqueued::Underpriced;
+                }
+                let old = self.txs.remove(&old_hash).expect("pending transaction of the nonce");
+                self.order_set.remove(&TxOrder::new(old_hash, order));
+                self.order_set.insert(TxOrder::new(hash, order));
+                self.nonces.insert(key, (hash, order));
+                self.txs.insert(hash, tx);
+                Enqueued::Replaced(old)
+            }
+            None => {
+                let nonce = nonce_of(&tx);
+                let ahead = match nonce {
+                    Some(nonce) => self.next_nonces.get_mut(&key.0).map_or(false, |next| nonce > *next),
+                    None => false,
+                };
+                match (nonce, ahead) {
+                    (Some(nonce), true) => self.hold(key.0, nonce, tx),
+                    _ => self.admit(hash, key, tx),
+                }
+            }
+        }
+    }
+
+    fn hold(&mut self, sender: Vec<u8>, nonce: U256, tx: SignedTransaction) -> Enqueued {
+        let old = match self.futures.get(&sender) {
+            Some(futures) if futures.contains_key(&nonce) => {
+                if !self.outbids(&futures[&nonce], &tx) {
+                    return Enqueued::Underpriced;
+                }
+                futures.get(&nonce).cloned()
+            }
+            Some(futures) if futures.len() >= FUTURE_LIMIT => return Enqueued::AccountLimit,
+            _ => None,
+        };
+        self.futures.entry(sender).or_insert_with(BTreeMap::new).insert(nonce, tx);
+        old.map_or(Enqueued::Future, Enqueued::Replaced)
+    }
+
+    // Add a transaction ready for its nonce, as pending or queued over the limit of the sender.
+    fn admit(&mut self, hash: H256, key: (Vec<u8>, String), tx: SignedTransaction) -> Enqueued {
+        let sender = key.0.clone();
+        let nonce = nonce_of(&tx);
+        if self.account_limit > 0 && self.senders.get(&sender).map_or(0, |n| *n) >= self.account_limit {
+            if self.account_overflow != AccountOverflow::Queue || self.queued.get(&sender).map_or(0, |queue| queue.len()) >= self.account_limit {
+                return Enqueued::AccountLimit;
+            }
+            self.queued.entry(sender.clone()).or_insert_with(VecDeque::new).push_back(tx);
+            if let Some(nonce) = nonce {
+                self.advance(&sender, nonce);
+            }
+            return Enqueued::Queued;
+        }
+        self.insert(hash, key, tx);
+        if let Some(nonce) = nonce {
+            self.advance(&sender, nonce);
+        }
+        Enqueued::New
+    }
+
+    // Move the next nonce of the sender past the nonce, and admit the futures the gap is filled for.
+    fn advance(&mut self, sender: &Vec<u8>, nonce: U256) {
+        let (next, _) = nonce.overflowing_add(U256::one());
+        let behind = self.next_nonces.get_mut(sender).map_or(true, |current| *current < next);
+        if behind {
+            self.next_nonces.insert(sender.clone(), next);
+        }
+        self.admit_futures(sender);
+    }
+
+    fn admit_futures(&mut self, sender: &Vec<u8>) {
+        let next = match self.next_nonces.get_mut(sender) {
+            Some(next) => *next,
+            None => return,
+        };
+        let ready = self.futures.get(sender).and_then(|futures| futures.keys().next().cloned()).map_or(false, |nonce| nonce <= next);
+        if !ready {
+            return;
+        }
+        let (nonce, tx) = {
+            let futures = self.futures.get_mut(sender).expect("futures of the sender");
+            let nonce = *futures.keys().next().expect("a future of the sender");
+            (nonce, futures.remove(&nonce).expect("a future of the sender"))
+        };
+        if self.futures.get(sender).map_or(false, |futures| futures.is_empty()) {
+            self.futures.remove(sender);
+        }
         let hash = H256::from_slice(tx.get_tx_hash());
+        let key = sender_nonce(&tx);
+        if self.nonces.contains_key(&key) {
+            // superseded by a pending one
+            self.untrack(&hash);
+            self.admit_futures(sender);
+        } else if self.admit(hash, key, tx.clone()) == Enqueued::AccountLimit {
+            // back until the sender is under the limit
+            self.futures.entry(sender.clone()).or_insert_with(BTreeMap::new).insert(nonce, tx);
+        }
+    }
+
+    fn insert(&mut self, hash: H256, key: (Vec<u8>, String), tx: SignedTransaction) {
+        let order = match self.strategy {
+            Strategy::FIFO | Strategy::QUOTA_PRICE => self.get_order(),
+            Strategy::PRIORITY => self.get_order_by_priority(&tx),
+            Strategy::VIP => self.get_order_by_vip(&tx),
+        };
+        let tx_order = TxOrder::new(hash, order);
+        self.order_set.insert(tx_order);
+        *self.senders.entry(key.0.clone()).or_insert(0) += 1;
+        self.nonces.insert(key, (hash, order));
+        self.txs.insert(hash, tx);
+    }
 
-        let is_ok = self.filter.check(hash);
-        if is_ok {
-            let order = match self.strategy {
-                Strategy::FIFO => self.get_order(),
-                Strategy::PRIORITY => self.get_order_by_priority(&tx),
-                Strategy::VIP => self.get_order_by_vip(&tx),
+    // Enter the queued transactions of the sender while it is under the limit.
+    fn promote(&mut self, sender: &Vec<u8>) {
+        while self.senders.get(sender).map_or(0, |n| *n) < self.account_limit {
+            let next = self.queued.get_mut(sender).and_then(|queue| queue.pop_front());
+            let tx = match next {
+                Some(tx) => tx,
+                None => break,
             };
-            let tx_order = TxOrder::new(hash, order);
-            self.order_set.insert(tx_order);
-            self.txs.insert(hash, tx);
+            let key = sender_nonce(&tx);
+            let hash = H256::from_slice(tx.get_tx_hash());
+            // dropped if it has the nonce of a pending one
+            if self.nonces.contains_key(&key) {
+                self.untrack(&hash);
+            } else {
+                self.insert(hash, key, tx);
+            }
+        }
+        if self.queued.get(sender).map_or(false, |queue| queue.is_empty()) {
+            self.queued.remove(sender);
         }
-        is_ok
     }
 
     fn update_order_set(&mut self, hash_list: &[H256]) {
         self.order_set = self.order_set.iter().cloned().filter(|order| !hash_list.contains(&order.hash)).collect();
     }
 
+    /// Remove the transactions committed, the next nonces of their senders move past them.
     pub fn update(&mut self, txs: &[SignedTransaction]) {
+        self.remove(txs);
+        for tx in txs {
+            if let Some(nonce) = nonce_of(tx) {
+                self.advance(&tx.get_signer().to_vec(), nonce);
+            }
+        }
+    }
+
+    // Remove the transactions, pending, queued or future.
+    fn remove(&mut self, txs: &[SignedTransaction]) {
         let mut hash_list = Vec::new();
+        let mut senders = HashSet::new();
         for tx in txs {
             let hash = tx.crypt_hash();
-            self.txs.remove(&hash);
+            let key = sender_nonce(tx);
+            self.untrack(&hash);
+            if self.txs.remove(&hash).is_some() {
+                if let Some(n) = self.senders.get_mut(&key.0) {
+                    *n -= 1;
+                }
+            } else {
+                if let Some(queue) = self.queued.get_mut(&key.0) {
+                    queue.retain(|queued| queued.get_tx_hash() != tx.get_tx_hash());
+                }
+                if let (Some(futures), Some(nonce)) = (self.futures.get_mut(&key.0), nonce_of(tx)) {
+                    if futures.get(&nonce).map_or(false, |future| future.get_tx_hash() == tx.get_tx_hash()) {
+                        futures.remove(&nonce);
+                    }
+                }
+                if self.futures.get(&key.0).map_or(false, |futures| futures.is_empty()) {
+                    self.futures.remove(&key.0);
+                }
+            }
+            if let Some(bundle_key) = bundle_of(tx) {
+                let done = match self.bundles.get_mut(&bundle_key) {
+                    Some(bundle) => {
+                        bundle.members.retain(|member| *member != hash);
+                        bundle.members.is_empty() && bundle.partial.is_empty()
+                    }
+                    None => false,
+                };
+                if done {
+                    self.bundles.remove(&bundle_key);
+                }
+            }
+            senders.insert(key.0.clone());
+            if self.nonces.get(&key).map_or(false, |&(pending, _)| pending == hash) {
+                self.nonces.remove(&key);
+            }
             hash_list.push(hash);
         }
         self.update_order_set(&hash_list);
+        for sender in senders {
+            if self.senders.get(&sender) == Some(&0) {
+                self.senders.remove(&sender);
+            }
+            if self.account_limit > 0 {
+                self.promote(&sender);
+            }
+            self.admit_futures(&sender);
+        }
     }
 
     pub fn package(&mut self, height: u64) -> Vec<SignedTransaction> {
+        self.package_and_expire(height).0
+    }
+
+    /// Package the transactions for the block of the height, and drop those
+    /// no longer valid at the height, which are returned with them.
+    pub fn package_and_expire(&mut self, height: u64) -> (Vec<SignedTransaction>, Vec<SignedTransaction>) {
+        self.height = cmp::max(self.height, height);
         let mut tx_list = Vec::new();
-        let mut invalid_tx_list = Vec::new();
-        let mut n = self.package_limit;
+        // held longer than the ttl
+        let stale: HashSet<H256> = match self.limits.ttl {
+            Some(ttl) => {
+                let now = Instant::now();
+                self.held.iter().filter(|&(_, held)| now - held.arrival > ttl).map(|(hash, _)| *hash).collect()
+            }
+            None => HashSet::new(),
+        };
+        // bundles waiting for the rest longer than the ttl
+        let stale_bundles: Vec<_> = match self.limits.ttl {
+            Some(ttl) => {
+                let now = Instant::now();
+                self.bundles.iter().filter(|&(_, bundle)| bundle.members.is_empty() && now - bundle.arrival > ttl).map(|(key, _)| key.clone()).collect()
+            }
+            None => Vec::new(),
+        };
+        let mut invalid_tx_list: Vec<SignedTransaction> = stale.iter().filter_map(|hash| self.find(hash)).collect();
+        invalid_tx_list.extend(self.futures
+                                   .values()
+                                   .flat_map(|futures| futures.values())
+                                   .filter(|tx| expired(tx, height) && !stale.contains(&H256::from_slice(tx.get_tx_hash())))
+                                   .cloned());
+        let limit = self.package_limit();
+        let mut n = limit;
+        // all the valid ones are ranked by the price before the limit is applied
+        let by_price = self.strategy == Strategy::QUOTA_PRICE;
 
+        let mut packed_bundles = HashSet::new();
         {
             let mut iter = self.order_set.iter();
             loop {
@@ -155,14 +871,39 @@ impl Pool {
                 let hash = order.unwrap().hash;
                 let tx = self.txs.get(&hash);
                 if let Some(tx) = tx {
-                    if tx.get_transaction_with_sig().get_transaction().valid_until_block == 0 || tx.get_transaction_with_sig().get_transaction().valid_until_block >= height {
-                        tx_list.push(tx.clone());
-                        n = n - 1;
-                        if n == 0 {
-                            break;
-                        }
-                    } else {
+                    if stale.contains(&hash) {
+                        continue;
+                    }
+                    if expired(tx, height) {
                         invalid_tx_list.push(tx.clone());
+                        continue;
+                    }
+                    match bundle_of(tx) {
+                        Some(key) => {
+                            // the whole bundle where its first transaction is met
+                            if !packed_bundles.insert(key.clone()) {
+                                continue;
+                            }
+                            let members = match self.bundle_members(&key) {
+                                Some(members) => members,
+                                None => continue,
+                            };
+                            if members.iter().any(|member| expired(member, height) || stale.contains(&H256::from_slice(member.get_tx_hash()))) {
+                                continue;
+                            }
+                            if !by_price && members.len() > n {
+                                break;
+                            }
+                            n = n.saturating_sub(members.len());
+                            tx_list.extend(members);
+                        }
+                        None => {
+                            tx_list.push(tx.clone());
+                            n = n.saturating_sub(1);
+                        }
+                    }
+                    if n == 0 && !by_price {
+                        break;
                     }
                 } else {
                     panic!("invalid tx order {:?}", order);
@@ -170,27 +911,92 @@ impl Pool {
             }
         }
 
-        self.update(&invalid_tx_list);
-        tx_list
+        if by_price {
+            tx_list = group_bundles(order_by_quota_price(tx_list), limit);
+        }
+
+        self.remove(&invalid_tx_list);
+        for key in stale_bundles {
+            invalid_tx_list.extend(self.drop_partial(&key));
+        }
+        self.expired_count += invalid_tx_list.len() as u64;
+        (tx_list, invalid_tx_list)
+    }
+
+    // The transactions of the complete bundle in the order of their nonces, none unless all of them are pending.
+    fn bundle_members(&self, key: &(Vec<u8>, Vec<u8>)) -> Option<Vec<SignedTransaction>> {
+        let bundle = match self.bundles.get(key) {
+            Some(bundle) if bundle.members.len() == bundle.size => bundle,
+            _ => return None,
+        };
+        let members: Vec<_> = bundle.members.iter().filter_map(|hash| self.txs.get(hash)).cloned().collect();
+        if members.len() == bundle.size { Some(members) } else { None }
+    }
+
+    /// Drop the transactions held, pending, queued or future, no longer valid at the
+    /// height of the next block, which are returned. Those added later are refused.
+    pub fn expire(&mut self, height: u64) -> Vec<SignedTransaction> {
+        self.height = cmp::max(self.height, height);
+        let hashes: Vec<H256> = self.held.keys().cloned().collect();
+        let mut expired_txs: Vec<_> = hashes.iter().filter_map(|hash| self.find(hash)).filter(|tx| expired(tx, height)).collect();
+        self.remove(&expired_txs);
+        // the bundles waiting for a transaction which expired
+        let partial: Vec<_> = self.bundles.iter().filter(|&(_, bundle)| bundle.partial.iter().any(|tx| expired(tx, height))).map(|(key, _)| key.clone()).collect();
+        for key in partial {
+            expired_txs.extend(self.drop_partial(&key));
+        }
+        self.expired_count += expired_txs.len() as u64;
+        expired_txs
     }
 
     pub fn len(&self) -> usize {
         self.txs.len()
     }
+
+    /// Whether the hash is among the recent ones filtered for duplicates.
+    pub fn seen(&mut self, hash: &H256) -> bool {
+        self.filter.contains(hash)
+    }
+
+    /// Number of the transactions queued over the limit of their senders.
+    pub fn queued_len(&self) -> usize {
+        self.queued.values().map(|queue| queue.len()).sum()
+    }
+
+    /// Number of the transactions held for the gaps to their nonces.
+    pub fn future_len(&self) -> usize {
+        self.futures.values().map(|futures| futures.len()).sum()
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            pending: self.len(),
+            queued: self.queued_len(),
+            future: self.future_len(),
+            bytes: self.bytes,
+            evicted: self.evicted_count,
+            expired: self.expired_count,
+            filter_hits: self.filter.hits(),
+            filter_misses: self.filter.misses(),
+        }
+    }
 }
 
 //FIXME
 #[cfg(test)]
 mod tests {
     use super::*;
+    use cita_ed25519::KeyPair;
+    use std::time::Duration;
     use util::H512;
     use libproto::blockchain::{SignedTransaction, UnverifiedTransaction, Transaction};
 
     pub fn generate_tx(data: Vec<u8>, valid_until_block: u64) -> SignedTransaction {
         let mut tx = Transaction::new();
+        // the same sender, a nonce for each data
+        tx.set_nonce(data[0].to_string());
         tx.set_data(data);
         tx.set_to("1234567".to_string());
-        tx.set_nonce("0".to_string());
         tx.set_valid_until_block(valid_until_block);
 
         let pv = H512::from_slice(&[20,17]);
@@ -227,4 +1033,261 @@ mod tests {
         assert_eq!(p.package(6), vec![]);
         assert_eq!(p.len(), 0);
     }
+
+    #[test]
+    fn package_and_expire() {
+        let mut p = Pool::new(10, 2);
+        let tx1 = generate_tx(vec![1], 5);
+        let tx2 = generate_tx(vec![2], 999);
+        p.enqueue(tx1.clone());
+        p.enqueue(tx2.clone());
+        assert_eq!(p.package_and_expire(6), (vec![tx2], vec![tx1]));
+        assert_eq!(p.len(), 1);
+    }
+
+    #[test]
+    fn package_quota_limit() {
+        let mut p = Pool::new(10, 3);
+        let txs: Vec<_> = (1..5).map(|i| generate_tx(vec![i], 999)).collect();
+        for tx in &txs {
+            p.enqueue(tx.clone());
+        }
+        // two fit the quota of the block
+        p.set_quota_limit(Some(50000), 20000);
+        assert_eq!(p.package(1), txs[..2].to_vec());
+        p.set_quota_limit(None, 20000);
+        assert_eq!(p.package(1), txs[..3].to_vec());
+    }
+
+    #[test]
+    fn expire() {
+        let mut p = Pool::new(10, 1);
+        let txs: Vec<_> = (1..4).map(|i| generate_tx(vec![i], 4 + i as u64)).collect();
+        for tx in &txs {
+            p.enqueue(tx.clone());
+        }
+        // beyond the package limit too
+        assert_eq!(p.expire(6), vec![txs[0].clone()]);
+        assert_eq!(p.len(), 2);
+        assert_eq!(p.stats().expired, 1);
+        assert_eq!(p.add(generate_tx(vec![6], 5)), Enqueued::Expired);
+        assert_eq!(p.add(generate_tx(vec![4], 0)), Enqueued::New);
+    }
+
+    fn generate_priced_tx(nonce: &str, data: Vec<u8>, quota_price: u64) -> SignedTransaction {
+        let mut tx = Transaction::new();
+        tx.set_data(data);
+        tx.set_to("1234567".to_string());
+        tx.set_nonce(nonce.to_string());
+        tx.set_quota_price(quota_price);
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(H512::from_slice(&[20,17]));
+        signed_tx
+    }
+
+    #[test]
+    fn replace_by_nonce() {
+        let mut p = Pool::new(10, 10);
+        let tx1 = generate_priced_tx("0", vec![1], 100);
+        let tx2 = generate_priced_tx("1", vec![2], 100);
+        assert_eq!(p.add(tx1.clone()), Enqueued::New);
+        assert_eq!(p.add(tx2.clone()), Enqueued::New);
+
+        // below the default bump of 10 percent
+        assert_eq!(p.add(generate_priced_tx("0", vec![3], 109)), Enqueued::Underpriced);
+        let tx3 = generate_priced_tx("0", vec![4], 110);
+        assert_eq!(p.add(tx3.clone()), Enqueued::Replaced(tx1));
+        assert_eq!(p.add(tx3.clone()), Enqueued::Duplicate);
+        assert_eq!(p.len(), 2);
+        assert_eq!(p.package(1), vec![tx3.clone(), tx2]);
+
+        p.update(&vec![tx3]);
+        assert_eq!(p.add(generate_priced_tx("0", vec![5], 0)), Enqueued::New);
+    }
+
+    #[test]
+    fn account_limit() {
+        let mut p = Pool::new(10, 10);
+        p.set_account_limit(2, AccountOverflow::Queue);
+        let txs: Vec<_> = (0..5).map(|i| generate_priced_tx(&i.to_string(), vec![i], 0)).collect();
+        assert_eq!(p.add(txs[0].clone()), Enqueued::New);
+        assert_eq!(p.add(txs[1].clone()), Enqueued::New);
+        assert_eq!(p.add(txs[2].clone()), Enqueued::Queued);
+        assert_eq!(p.add(txs[3].clone()), Enqueued::Queued);
+        assert_eq!(p.add(txs[4].clone()), Enqueued::AccountLimit);
+        assert_eq!((p.len(), p.queued_len()), (2, 2));
+
+        p.update(&vec![txs[0].clone()]);
+        assert_eq!((p.len(), p.queued_len()), (2, 1));
+        assert_eq!(p.package(1), vec![txs[1].clone(), txs[2].clone()]);
+
+        let mut p = Pool::new(10, 10);
+        p.set_account_limit(1, AccountOverflow::Reject);
+        assert_eq!(p.add(txs[0].clone()), Enqueued::New);
+        assert_eq!(p.add(txs[1].clone()), Enqueued::AccountLimit);
+        assert_eq!(p.queued_len(), 0);
+    }
+
+    fn generate_sender_tx(quota_price: u64) -> SignedTransaction {
+        let keypair = KeyPair::gen_keypair();
+        let mut tx = Transaction::new();
+        tx.set_to("1234567".to_string());
+        tx.set_nonce("0".to_string());
+        tx.set_quota_price(quota_price);
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(keypair.privkey().clone());
+        signed_tx
+    }
+
+    #[test]
+    fn evict_by_price() {
+        let mut p = Pool::new(10, 10);
+        p.set_limits(PoolLimits {
+                         max_txs: 2,
+                         max_bytes: 0,
+                         ttl: None,
+                     });
+        let txs: Vec<_> = [5, 3, 3, 4].iter().map(|price| generate_sender_tx(*price)).collect();
+        assert_eq!(p.add(txs[0].clone()), Enqueued::New);
+        assert_eq!(p.add(txs[1].clone()), Enqueued::New);
+        // the older one of the same price is kept
+        assert_eq!(p.add(txs[2].clone()), Enqueued::PoolFull);
+        assert_eq!(p.add(txs[3].clone()), Enqueued::New);
+        assert_eq!(p.take_evicted(), vec![txs[1].clone()]);
+        assert_eq!(p.package(1), vec![txs[0].clone(), txs[3].clone()]);
+        assert_eq!(p.stats().evicted, 1);
+
+        p.update(&vec![txs[0].clone()]);
+        assert_eq!(p.add(txs[2].clone()), Enqueued::New);
+    }
+
+    #[test]
+    fn ttl() {
+        let mut p = Pool::new(10, 10);
+        p.set_limits(PoolLimits {
+                         max_txs: 0,
+                         max_bytes: 0,
+                         ttl: Some(Duration::from_millis(0)),
+                     });
+        let tx = generate_sender_tx(0);
+        assert_eq!(p.add(tx.clone()), Enqueued::New);
+        ::std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(p.package_and_expire(1), (vec![], vec![tx]));
+        assert_eq!((p.len(), p.stats().expired), (0, 1));
+    }
+
+    #[test]
+    fn order_by_quota_price() {
+        let mut p = Pool::new_with_strategy(10, 3, Strategy::QUOTA_PRICE);
+        let cheap = generate_sender_tx(1);
+        let dear = generate_sender_tx(9);
+        // the dearer second one of the sender waits for the first
+        let first = generate_priced_tx("0", vec![1], 2);
+        let second = generate_priced_tx("1", vec![2], 10);
+        for tx in vec![cheap.clone(), second.clone(), first.clone(), dear.clone()] {
+            assert!(p.add(tx).is_ok());
+        }
+        assert_eq!(p.package(1), vec![dear, first, second]);
+        assert_eq!(p.len(), 4);
+    }
+
+    fn generate_bundle_tx(nonce: u8, bundle: &[u8], size: u32) -> SignedTransaction {
+        let mut tx = Transaction::new();
+        tx.set_data(vec![nonce]);
+        tx.set_to("1234567".to_string());
+        tx.set_nonce(nonce.to_string());
+        tx.set_bundle(bundle.to_vec());
+        tx.set_bundle_size(size);
+
+        let mut uv_tx = UnverifiedTransaction::new();
+        uv_tx.set_transaction(tx);
+
+        let mut signed_tx = SignedTransaction::new();
+        signed_tx.set_transaction_with_sig(uv_tx);
+        signed_tx.sign(H512::from_slice(&[20,17]));
+        signed_tx
+    }
+
+    #[test]
+    fn bundle() {
+        let mut p = Pool::new(10, 3);
+        let first = generate_priced_tx("0", vec![0], 0);
+        let bundle: Vec<_> = (1..4).map(|nonce| generate_bundle_tx(nonce, b"deploy", 3)).collect();
+        assert_eq!(p.add(first.clone()), Enqueued::New);
+        assert_eq!(p.add(bundle[2].clone()), Enqueued::Bundled);
+        assert_eq!(p.add(bundle[0].clone()), Enqueued::Bundled);
+        assert_eq!(p.package(1), vec![first.clone()]);
+
+        assert_eq!(p.add(bundle[1].clone()), Enqueued::New);
+        assert_eq!(p.len(), 4);
+        // not split over the limit
+        assert_eq!(p.package(1), vec![first.clone()]);
+        p.update(&[first]);
+        assert_eq!(p.package(1), bundle);
+
+        assert_eq!(p.add(generate_bundle_tx(4, b"alone", 1)), Enqueued::BadBundle);
+        assert_eq!(p.add(generate_bundle_tx(4, b"gap", 2)), Enqueued::Bundled);
+        assert_eq!(p.add(generate_bundle_tx(6, b"gap", 2)), Enqueued::BadBundle);
+        p.update(&bundle);
+        assert_eq!(p.len(), 0);
+    }
+
+    #[test]
+    fn partial_bundle_limits() {
+        let mut p = Pool::new(100, 10);
+        // a sender can not hold back too many incomplete bundles
+        for i in 0..MAX_PARTIAL_BUNDLES_PER_SENDER {
+            assert_eq!(p.add(generate_bundle_tx(1, &[i as u8], 2)), Enqueued::Bundled);
+        }
+        assert_eq!(p.add(generate_bundle_tx(1, b"more", 2)), Enqueued::AccountLimit);
+
+        // the incomplete bundles count in the pool limits, and expire after the ttl
+        let mut p = Pool::new(100, 10);
+        p.set_limits(PoolLimits {
+                         max_txs: 2,
+                         max_bytes: 0,
+                         ttl: Some(Duration::from_millis(0)),
+                     });
+        let partial = generate_bundle_tx(1, b"first", 3);
+        assert_eq!(p.add(partial.clone()), Enqueued::Bundled);
+        assert_eq!(p.add(generate_bundle_tx(2, b"second", 3)), Enqueued::Bundled);
+        assert_eq!(p.add(generate_sender_tx(10)), Enqueued::PoolFull);
+        ::std::thread::sleep(Duration::from_millis(2));
+        let (packaged, expired) = p.package_and_expire(1);
+        assert!(packaged.is_empty());
+        assert_eq!(expired.len(), 2);
+        assert!(expired.contains(&partial));
+        assert_eq!(p.stats().expired, 2);
+        assert!(p.add(generate_sender_tx(10)).is_ok());
+    }
+
+    #[test]
+    fn nonce_gap() {
+        let mut p = Pool::new(10, 10);
+        let txs: Vec<_> = (0..4).map(|i| generate_priced_tx(&i.to_string(), vec![i], 0)).collect();
+        assert_eq!(p.add(txs[0].clone()), Enqueued::New);
+        assert_eq!(p.add(txs[2].clone()), Enqueued::Future);
+        assert_eq!(p.add(txs[3].clone()), Enqueued::Future);
+        assert_eq!((p.len(), p.future_len()), (1, 2));
+        assert_eq!(p.add(txs[1].clone()), Enqueued::New);
+        assert_eq!((p.len(), p.future_len()), (4, 0));
+        assert_eq!(p.package(1), txs);
+
+        // committed through another node
+        let mut p = Pool::new(10, 10);
+        p.update(&txs[..2]);
+        assert_eq!(p.add(txs[3].clone()), Enqueued::Future);
+        assert_eq!(p.add(txs[2].clone()), Enqueued::New);
+        assert_eq!((p.len(), p.future_len()), (2, 0));
+    }
 }
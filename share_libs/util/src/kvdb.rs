@@ -14,32 +14,53 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Key-Value store abstraction with `RocksDB` backend.
+//! Key-Value store abstraction with `RocksDB` and `LMDB` backends.
 
 use {UtilError, Bytes};
 
 use elastic_array::*;
 use hashdb::DBValue;
-use parking_lot::{Mutex, MutexGuard, RwLock};
+#[cfg(feature = "lmdb")]
+use lmdb::{self, Cursor, DatabaseFlags, Environment, Transaction, WriteFlags};
+use parking_lot::RwLock;
+#[cfg(feature = "rocksdb")]
+use parking_lot::{Mutex, MutexGuard};
 
 
 #[cfg(target_os = "linux")]
 use regex::Regex;
 use rlp::{UntrustedRlp, RlpType, Compressible};
+#[cfg(feature = "rocksdb")]
 use rocksdb::{DB, Writable, WriteBatch, WriteOptions, IteratorMode, DBIterator, Options, DBCompactionStyle, BlockBasedOptions, Direction, Cache, Column, ReadOptions};
-use std::{mem, fs};
+use std::{fs, str};
 use std::collections::{HashMap, BTreeMap};
 #[cfg(target_os = "linux")]
 use std::fs::File;
+#[cfg(feature = "rocksdb")]
 use std::io::ErrorKind;
+#[cfg(feature = "rocksdb")]
 use std::marker::PhantomData;
+#[cfg(feature = "rocksdb")]
+use std::mem;
 use std::path::{PathBuf, Path};
 #[cfg(target_os = "linux")]
 use std::process::Command;
 
+#[cfg(feature = "rocksdb")]
 const DB_BACKGROUND_FLUSHES: i32 = 2;
+#[cfg(feature = "rocksdb")]
 const DB_BACKGROUND_COMPACTIONS: i32 = 2;
 const DB_WRITE_BUFFER_SIZE: usize = 2048 * 1000;
+const DB_MAX_TOTAL_WAL_SIZE: u64 = 64 * 1024 * 1024;
+// Address space reserved for the LMDB file, which only grows with the data.
+#[cfg(feature = "lmdb")]
+const LMDB_MAP_SIZE: usize = 1 << 40;
+// Pairs read by a cursor of the LMDB iterator at a time.
+#[cfg(feature = "lmdb")]
+const LMDB_ITER_BATCH: usize = 256;
+// MDB_SET_RANGE of the cursor ops in lmdb.h, not exported by the lmdb crate.
+#[cfg(feature = "lmdb")]
+const MDB_SET_RANGE: ::libc::c_uint = 17;
 
 /// Required length of prefixes.
 pub const PREFIX_LEN: usize = 12;
@@ -121,6 +142,7 @@ impl DBTransaction {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 enum KeyState {
     Insert(DBValue),
     InsertCompressed(DBValue),
@@ -169,6 +191,12 @@ pub trait KeyValueDB: Sync + Send {
     /// Flush all buffered data.
     fn flush(&self) -> Result<(), String>;
 
+    /// Flush the buffered data of all the columns but `col`, which stays buffered
+    /// until the next `flush`. A store without a buffer flushes everything.
+    fn flush_except(&self, _col: Option<u32>) -> Result<(), String> {
+        self.flush()
+    }
+
     /// Iterate over flushed data for a given column.
     fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
 
@@ -268,15 +296,229 @@ impl KeyValueDB for InMemory {
     }
 }
 
+/// Key-value database with `LMDB` backend, for the nodes which can not build `RocksDB`.
+///
+/// Transactions are committed as they are written, so nothing is buffered.
+/// Compaction and cache settings of the `DatabaseConfig` do not apply.
+#[cfg(feature = "lmdb")]
+pub struct LmdbDatabase {
+    env: Environment,
+    // the database of `None`, followed by the ones of the columns
+    dbs: Vec<lmdb::Database>,
+}
+
+#[cfg(feature = "lmdb")]
+impl LmdbDatabase {
+    /// Open database directory. Creates if it does not exist.
+    pub fn open(config: &DatabaseConfig, path: &str) -> Result<LmdbDatabase, String> {
+        let columns = config.columns.unwrap_or(0);
+        fs::create_dir_all(path).map_err(|e| e.to_string())?;
+        let env = Environment::new()
+            .set_max_dbs(columns + 1)
+            .set_map_size(LMDB_MAP_SIZE)
+            .open(Path::new(path))
+            .map_err(|e| e.to_string())?;
+        let mut dbs = Vec::with_capacity(columns as usize + 1);
+        // The unnamed database holds the names of the others, so `None` gets one of its own.
+        dbs.push(env.create_db(Some("default"), DatabaseFlags::empty()).map_err(|e| e.to_string())?);
+        for col in 0..columns {
+            dbs.push(env.create_db(Some(format!("col{}", col).as_str()), DatabaseFlags::empty()).map_err(|e| e.to_string())?);
+        }
+        Ok(LmdbDatabase { env: env, dbs: dbs })
+    }
+
+    fn db(&self, col: Option<u32>) -> Result<lmdb::Database, String> {
+        self.dbs.get(col.map_or(0, |c| c as usize + 1)).cloned().ok_or_else(|| format!("No such column family: {:?}", col))
+    }
+
+    // At most `limit` pairs with keys of the prefix, after the key if any. The pairs
+    // are copied out, since they only live as long as the read transaction.
+    fn pairs(&self, col: Option<u32>, prefix: &[u8], after: Option<&[u8]>, limit: usize) -> Result<Vec<(Box<[u8]>, Box<[u8]>)>, String> {
+        let db = self.db(col)?;
+        let txn = self.env.begin_ro_txn().map_err(|e| e.to_string())?;
+        let pairs = {
+            let mut cursor = txn.open_ro_cursor(db).map_err(|e| e.to_string())?;
+            let start = match after {
+                Some(key) => Some(key),
+                None if prefix.is_empty() => None,
+                None => Some(prefix),
+            };
+            // the iterators of the cursor panic when there is no key to start from, so it is looked up first
+            if let Some(key) = start {
+                match cursor.get(Some(key), None, MDB_SET_RANGE) {
+                    Ok(_) => {}
+                    Err(lmdb::Error::NotFound) => return Ok(Vec::new()),
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+            let iter = match start {
+                Some(key) => cursor.iter_from(key),
+                // the first move of a cursor not positioned is to the first pair, none in an empty column
+                None => cursor.iter(),
+            };
+            iter.skip_while(|&(k, _)| Some(k) == after)
+                .take_while(|&(k, _)| k.starts_with(prefix))
+                .take(limit)
+                .map(|(k, v)| (k.to_vec().into_boxed_slice(), v.to_vec().into_boxed_slice()))
+                .collect()
+        };
+        Ok(pairs)
+    }
+}
+
+/// Iterator over the pairs of a column of `LmdbDatabase` with keys of a prefix.
+/// The pairs are read lazily, a batch in a read transaction at a time, so a
+/// long iteration neither copies the column nor holds back the writers.
+#[cfg(feature = "lmdb")]
+pub struct LmdbIterator<'a> {
+    db: &'a LmdbDatabase,
+    col: Option<u32>,
+    prefix: Vec<u8>,
+    // the key of the last pair read, the next batch starts after it
+    last: Option<Vec<u8>>,
+    batch: ::std::vec::IntoIter<(Box<[u8]>, Box<[u8]>)>,
+    done: bool,
+}
+
+#[cfg(feature = "lmdb")]
+impl<'a> Iterator for LmdbIterator<'a> {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(pair) = self.batch.next() {
+            return Some(pair);
+        }
+        if self.done {
+            return None;
+        }
+        match self.db.pairs(self.col, &self.prefix, self.last.as_ref().map(|key| &key[..]), LMDB_ITER_BATCH) {
+            Ok(batch) => {
+                self.done = batch.len() < LMDB_ITER_BATCH;
+                self.last = batch.last().map(|&(ref key, _)| key.to_vec());
+                self.batch = batch.into_iter();
+                self.batch.next()
+            }
+            Err(e) => {
+                warn!("LMDB iteration failed: {}", e);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "lmdb")]
+impl KeyValueDB for LmdbDatabase {
+    fn get(&self, col: Option<u32>, key: &[u8]) -> Result<Option<DBValue>, String> {
+        let db = self.db(col)?;
+        let txn = self.env.begin_ro_txn().map_err(|e| e.to_string())?;
+        let value = match txn.get(db, &key) {
+            Ok(value) => Ok(Some(DBValue::from_slice(value))),
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        };
+        value
+    }
+
+    fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+        self.pairs(col, prefix, None, 1).ok().and_then(|pairs| pairs.into_iter().next()).map(|(_, v)| v)
+    }
+
+    fn write_buffered(&self, transaction: DBTransaction) {
+        self.write(transaction).expect("LMDB write failed");
+    }
+
+    fn write(&self, transaction: DBTransaction) -> Result<(), String> {
+        let mut txn = self.env.begin_rw_txn().map_err(|e| e.to_string())?;
+        for op in transaction.ops {
+            match op {
+                DBOp::Insert { col, key, value } => {
+                    txn.put(self.db(col)?, &&key[..], &&value[..], WriteFlags::empty()).map_err(|e| e.to_string())?;
+                }
+                DBOp::InsertCompressed { col, key, value } => {
+                    let compressed = UntrustedRlp::new(&value).compress(RlpType::Blocks);
+                    txn.put(self.db(col)?, &&key[..], &&compressed[..], WriteFlags::empty()).map_err(|e| e.to_string())?;
+                }
+                DBOp::Delete { col, key } => {
+                    match txn.del(self.db(col)?, &&key[..], None) {
+                        Ok(()) | Err(lmdb::Error::NotFound) => {}
+                        Err(e) => return Err(e.to_string()),
+                    }
+                }
+            }
+        }
+        txn.commit().map_err(|e| e.to_string())
+    }
+
+    fn flush(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        self.iter_from_prefix(col, &[])
+    }
+
+    /// Only the pairs with keys of the prefix.
+    fn iter_from_prefix<'a>(&'a self, col: Option<u32>, prefix: &'a [u8]) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        Box::new(LmdbIterator {
+                     db: self,
+                     col: col,
+                     prefix: prefix.to_vec(),
+                     last: None,
+                     batch: Vec::new().into_iter(),
+                     done: false,
+                 })
+    }
+
+    fn restore(&self, _new_db: &str) -> Result<(), UtilError> {
+        Err(UtilError::SimpleString("Attempted to restore LMDB database".into()))
+    }
+}
+
+/// Compaction style of the database
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CompactionStyle {
+    /// Leveled compaction, fewer and larger writes, suitable for HDD storage
+    Level,
+    /// Universal compaction, lower write amplification
+    Universal,
+}
+
+#[cfg(feature = "rocksdb")]
+impl CompactionStyle {
+    fn to_rocksdb(&self) -> DBCompactionStyle {
+        match *self {
+            CompactionStyle::Level => DBCompactionStyle::DBLevelCompaction,
+            CompactionStyle::Universal => DBCompactionStyle::DBUniversalCompaction,
+        }
+    }
+}
+
+impl str::FromStr for CompactionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "level" => Ok(CompactionStyle::Level),
+            "universal" => Ok(CompactionStyle::Universal),
+            _ => Err(format!("unknown compaction style {}", s)),
+        }
+    }
+}
+
 /// Compaction profile for the database settings
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct CompactionProfile {
+    /// compaction style
+    pub style: CompactionStyle,
     /// L0-L1 target file size
     pub initial_file_size: u64,
     /// L2-LN target file size multiplier
     pub file_size_multiplier: i32,
     /// rate limiter for background flushes and compactions, bytes/sec, if any
     pub write_rate_limit: Option<u64>,
+    /// size of the data blocks, larger ones take fewer seeks
+    pub block_size: usize,
 }
 
 impl Default for CompactionProfile {
@@ -347,18 +589,22 @@ impl CompactionProfile {
     /// Default profile suitable for SSD storage
     pub fn ssd() -> CompactionProfile {
         CompactionProfile {
+            style: CompactionStyle::Universal,
             initial_file_size: 32 * 1024 * 1024,
             file_size_multiplier: 2,
             write_rate_limit: None,
+            block_size: 4 * 1024,
         }
     }
 
     /// Slow HDD compaction profile
     pub fn hdd() -> CompactionProfile {
         CompactionProfile {
+            style: CompactionStyle::Level,
             initial_file_size: 192 * 1024 * 1024,
             file_size_multiplier: 1,
             write_rate_limit: Some(8 * 1024 * 1024),
+            block_size: 64 * 1024,
         }
     }
 }
@@ -376,6 +622,10 @@ pub struct DatabaseConfig {
     pub columns: Option<u32>,
     /// Should we keep WAL enabled?
     pub wal: bool,
+    /// Size of the WAL files at which the columns are flushed, in bytes.
+    pub max_total_wal_size: u64,
+    /// Size of the memtables of all the columns, in bytes.
+    pub write_buffer_size: usize,
 }
 
 impl DatabaseConfig {
@@ -401,6 +651,8 @@ impl Default for DatabaseConfig {
             compaction: CompactionProfile::default(),
             columns: None,
             wal: true,
+            max_total_wal_size: DB_MAX_TOTAL_WAL_SIZE,
+            write_buffer_size: DB_WRITE_BUFFER_SIZE,
         }
     }
 }
@@ -409,11 +661,13 @@ impl Default for DatabaseConfig {
 // The compromise of holding only a virtual borrow vs. holding a lock on the
 // inner DB (to prevent closing via restoration) may be re-evaluated in the future.
 //
+#[cfg(feature = "rocksdb")]
 pub struct DatabaseIterator<'a> {
     iter: DBIterator,
     _marker: PhantomData<&'a Database>,
 }
 
+#[cfg(feature = "rocksdb")]
 impl<'a> Iterator for DatabaseIterator<'a> {
     type Item = (Box<[u8]>, Box<[u8]>);
 
@@ -422,21 +676,23 @@ impl<'a> Iterator for DatabaseIterator<'a> {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 struct DBAndColumns {
     db: DB,
     cfs: Vec<Column>,
 }
 
 // get column family configuration from database config.
+#[cfg(feature = "rocksdb")]
 fn col_config(col: u32, config: &DatabaseConfig) -> Options {
     // default cache size for columns not specified.
     const DEFAULT_CACHE: usize = 2;
 
     let mut opts = Options::new();
-    opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
+    opts.set_compaction_style(config.compaction.style.to_rocksdb());
     opts.set_target_file_size_base(config.compaction.initial_file_size);
     opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
-    opts.set_db_write_buffer_size(DB_WRITE_BUFFER_SIZE);
+    opts.set_db_write_buffer_size(config.write_buffer_size);
 
     let col_opt = config.columns.map(|_| col);
 
@@ -445,6 +701,7 @@ fn col_config(col: u32, config: &DatabaseConfig) -> Options {
         let mut block_opts = BlockBasedOptions::new();
         // all goes to read cache.
         block_opts.set_cache(Cache::new(cache_size * 1024 * 1024));
+        block_opts.set_block_size(config.compaction.block_size);
         opts.set_block_based_table_factory(&block_opts);
     }
 
@@ -452,6 +709,7 @@ fn col_config(col: u32, config: &DatabaseConfig) -> Options {
 }
 
 /// Key-Value database.
+#[cfg(feature = "rocksdb")]
 pub struct Database {
     db: RwLock<Option<DBAndColumns>>,
     config: DatabaseConfig,
@@ -467,6 +725,7 @@ pub struct Database {
     flushing_lock: Mutex<bool>,
 }
 
+#[cfg(feature = "rocksdb")]
 impl Database {
     /// Open database with default settings.
     pub fn open_default(path: &str) -> Result<Database, String> {
@@ -479,18 +738,18 @@ impl Database {
         if let Some(rate_limit) = config.compaction.write_rate_limit {
             opts.set_parsed_options(&format!("rate_limiter_bytes_per_sec={}", rate_limit))?;
         }
-        opts.set_parsed_options(&format!("max_total_wal_size={}", 64 * 1024 * 1024))?;
+        opts.set_parsed_options(&format!("max_total_wal_size={}", config.max_total_wal_size))?;
         opts.set_parsed_options("verify_checksums_in_compaction=0")?;
         opts.set_max_open_files(config.max_open_files);
         opts.create_if_missing(true);
         opts.set_use_fsync(false);
-        opts.set_db_write_buffer_size(DB_WRITE_BUFFER_SIZE);
+        opts.set_db_write_buffer_size(config.write_buffer_size);
 
         opts.set_max_background_flushes(DB_BACKGROUND_FLUSHES);
         opts.set_max_background_compactions(DB_BACKGROUND_COMPACTIONS);
 
         // compaction settings
-        opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
+        opts.set_compaction_style(config.compaction.style.to_rocksdb());
         opts.set_target_file_size_base(config.compaction.initial_file_size);
         opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
 
@@ -596,12 +855,20 @@ impl Database {
         }
     }
 
-    /// Commit buffered changes to database. Must be called under `flush_lock`
-    fn write_flushing_with_lock(&self, _lock: &mut MutexGuard<bool>) -> Result<(), String> {
+    /// Commit buffered changes to database, but the ones of the `kept` overlay column.
+    /// Must be called under `flush_lock`
+    fn write_flushing_with_lock(&self, _lock: &mut MutexGuard<bool>, kept: Option<usize>) -> Result<(), String> {
         match *self.db.read() {
             Some(DBAndColumns { ref db, ref cfs }) => {
                 let batch = WriteBatch::new();
-                mem::swap(&mut *self.overlay.write(), &mut *self.flushing.write());
+                {
+                    let mut overlay = self.overlay.write();
+                    let mut flushing = self.flushing.write();
+                    mem::swap(&mut *overlay, &mut *flushing);
+                    if let Some(c) = kept {
+                        mem::swap(&mut overlay[c], &mut flushing[c]);
+                    }
+                }
                 {
                     for (c, column) in self.flushing.read().iter().enumerate() {
                         for (ref key, ref state) in column.iter() {
@@ -645,6 +912,15 @@ impl Database {
 
     /// Commit buffered changes to database.
     pub fn flush(&self) -> Result<(), String> {
+        self.flush_with(None)
+    }
+
+    /// Commit buffered changes to database, keeping the ones of the column buffered.
+    pub fn flush_except(&self, col: Option<u32>) -> Result<(), String> {
+        self.flush_with(Some(Self::to_overlay_column(col)))
+    }
+
+    fn flush_with(&self, kept: Option<usize>) -> Result<(), String> {
         let mut lock = self.flushing_lock.lock();
         // If RocksDB batch allocation fails the thread gets terminated and the lock is released.
         // The value inside the lock is used to detect that.
@@ -653,7 +929,7 @@ impl Database {
             return Err("Database write failure. Running low on memory perhaps?".to_owned());
         }
         *lock = true;
-        let result = self.write_flushing_with_lock(&mut lock);
+        let result = self.write_flushing_with_lock(&mut lock, kept);
         *lock = false;
         result
     }
@@ -841,6 +1117,7 @@ impl Database {
 
 // duplicate declaration of methods here to avoid trait import in certain existing cases
 // at time of addition.
+#[cfg(feature = "rocksdb")]
 impl KeyValueDB for Database {
     fn get(&self, col: Option<u32>, key: &[u8]) -> Result<Option<DBValue>, String> {
         Database::get(self, col, key)
@@ -855,13 +1132,19 @@ impl KeyValueDB for Database {
     }
 
     fn write(&self, transaction: DBTransaction) -> Result<(), String> {
-        Database::write(self, transaction)
+        // Goes out with the buffered changes, so it never lands before or under them.
+        Database::write_buffered(self, transaction);
+        Database::flush(self)
     }
 
     fn flush(&self) -> Result<(), String> {
         Database::flush(self)
     }
 
+    fn flush_except(&self, col: Option<u32>) -> Result<(), String> {
+        Database::flush_except(self, col)
+    }
+
     fn iter<'a>(&'a self, col: Option<u32>) -> Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
         let unboxed = Database::iter(self, col);
         Box::new(unboxed.into_iter().flat_map(|inner| inner))
@@ -877,6 +1160,7 @@ impl KeyValueDB for Database {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 impl Drop for Database {
     fn drop(&mut self) {
         // write all buffered changes if we can.
@@ -891,6 +1175,7 @@ mod tests {
     use hash::H256;
     use std::str::FromStr;
 
+    #[cfg(feature = "rocksdb")]
     fn test_db(config: &DatabaseConfig) {
         let path = RandomTempPath::create_dir();
         let db = Database::open(config, path.as_path().to_str().unwrap()).unwrap();
@@ -945,12 +1230,109 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rocksdb")]
+    fn flush_except() {
+        let path = RandomTempPath::create_dir();
+        let db = Database::open(&DatabaseConfig::with_columns(Some(2)), path.as_path().to_str().unwrap()).unwrap();
+        let key = H256::from_str("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+
+        let mut batch = db.transaction();
+        batch.put(Some(0), &key, b"cat");
+        batch.put(Some(1), &key, b"dog");
+        db.write_buffered(batch);
+        db.flush_except(Some(0)).unwrap();
+        // the iteration only sees the flushed data
+        assert_eq!(db.iter(Some(0)).into_iter().flat_map(|inner| inner).count(), 0);
+        assert_eq!(db.iter(Some(1)).into_iter().flat_map(|inner| inner).count(), 1);
+        assert_eq!(&*db.get(Some(0), &key).unwrap().unwrap(), b"cat");
+
+        let mut batch = db.transaction();
+        batch.put(Some(0), &key, b"horse");
+        db.write_buffered(batch);
+        db.flush().unwrap();
+        let contents: Vec<_> = db.iter(Some(0)).into_iter().flat_map(|inner| inner).collect();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&*contents[0].1, b"horse");
+    }
+
+    #[test]
+    #[cfg(feature = "rocksdb")]
     fn kvdb() {
         let path = RandomTempPath::create_dir();
         let _ = Database::open_default(path.as_path().to_str().unwrap()).unwrap();
         test_db(&DatabaseConfig::default());
     }
 
+    #[test]
+    #[cfg(feature = "rocksdb")]
+    fn kvdb_hdd() {
+        let config = DatabaseConfig {
+            compaction: CompactionProfile::hdd(),
+            max_total_wal_size: 16 * 1024 * 1024,
+            ..DatabaseConfig::default()
+        };
+        test_db(&config);
+        assert_eq!(CompactionStyle::from_str("level"), Ok(CompactionStyle::Level));
+        assert!(CompactionStyle::from_str("fifo").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "lmdb")]
+    fn lmdb() {
+        let path = RandomTempPath::create_dir();
+        let key1 = H256::from_str("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+        let key2 = H256::from_str("03c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+        {
+            let db = LmdbDatabase::open(&DatabaseConfig::with_columns(Some(2)), path.as_path().to_str().unwrap()).unwrap();
+            // nothing to iterate, from the start or from a prefix
+            assert_eq!(db.iter(Some(1)).count(), 0);
+            assert_eq!(db.iter_from_prefix(Some(1), &key1).count(), 0);
+
+            let mut batch = db.transaction();
+            batch.put(None, &key1, b"cat");
+            batch.put(Some(1), &key1, b"dog");
+            batch.put(Some(1), &key2, b"horse");
+            batch.delete(Some(0), &key1);
+            db.write(batch).unwrap();
+
+            assert_eq!(&*db.get(None, &key1).unwrap().unwrap(), b"cat");
+            assert_eq!(&*db.get(Some(1), &key1).unwrap().unwrap(), b"dog");
+            assert!(db.get(Some(0), &key1).unwrap().is_none());
+            assert!(db.get(Some(2), &key1).is_err());
+            assert_eq!(&*db.get_by_prefix(Some(1), &key2[..4]).unwrap(), b"horse");
+
+            let contents: Vec<_> = db.iter(Some(1)).collect();
+            assert_eq!(contents.len(), 2);
+            assert_eq!(&*contents[0].0, &*key1);
+            assert_eq!(&*contents[1].1, b"horse");
+            assert_eq!(db.iter_from_prefix(Some(1), &key2).count(), 1);
+            assert_eq!(db.iter_from_prefix(Some(1), &key1[..1]).count(), 1);
+            // a prefix after the last key
+            assert_eq!(db.iter_from_prefix(Some(1), &[0xff]).count(), 0);
+
+            // read in batches, up to the end of the prefix
+            let mut batch = db.transaction();
+            for i in 0..LMDB_ITER_BATCH as u32 * 2 + 1 {
+                let mut key = vec![7];
+                key.extend_from_slice(&[(i >> 8) as u8, i as u8]);
+                batch.put(None, &key, b"many");
+            }
+            batch.put(None, &[8], b"other");
+            db.write(batch).unwrap();
+            assert_eq!(db.iter_from_prefix(None, &[7]).count(), LMDB_ITER_BATCH * 2 + 1);
+            assert_eq!(db.iter(None).count(), LMDB_ITER_BATCH * 2 + 3);
+
+            let mut batch = db.transaction();
+            batch.delete(Some(1), &key1);
+            db.write(batch).unwrap();
+        }
+
+        // reopen
+        let db = LmdbDatabase::open(&DatabaseConfig::with_columns(Some(2)), path.as_path().to_str().unwrap()).unwrap();
+        assert!(db.get(Some(1), &key1).unwrap().is_none());
+        assert_eq!(&*db.get(None, &key1).unwrap().unwrap(), b"cat");
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn df_to_rotational() {
@@ -1074,6 +1456,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rocksdb")]
     fn add_columns() {
         let config = DatabaseConfig::default();
         let config_5 = DatabaseConfig::with_columns(Some(5));
@@ -1099,6 +1482,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rocksdb")]
     fn drop_columns() {
         let config = DatabaseConfig::default();
         let config_5 = DatabaseConfig::with_columns(Some(5));
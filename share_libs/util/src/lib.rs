@@ -17,7 +17,10 @@
 
 extern crate rustc_hex;
 extern crate rand;
+#[cfg(feature = "rocksdb")]
 extern crate rocksdb;
+#[cfg(feature = "lmdb")]
+extern crate lmdb;
 extern crate env_logger;
 extern crate elastic_array;
 extern crate time;
@@ -0,0 +1,91 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde_json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+const DEFAULT_HEARTBEAT_TIMEOUT: u64 = 30;
+const DEFAULT_BACKOFF_MIN: u64 = 1;
+const DEFAULT_BACKOFF_MAX: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct SupervisorConfig {
+    pub services: Vec<ServiceConfig>,
+    /// Seconds without a heartbeat before a service is restarted, default 30.
+    pub heartbeat_timeout: Option<u64>,
+    /// Seconds before restarting a crashed service the first time, doubled up to the max, default 1 and 60.
+    pub backoff_min: Option<u64>,
+    pub backoff_max: Option<u64>,
+    /// Address of the health endpoint, none if not set.
+    pub health_addr: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServiceConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Variables added to the environment of the supervisor.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// File the output of the service is appended to, inherited if not set.
+    pub log: Option<String>,
+    /// Name the service sends its heartbeats under on the bus, not watched if not set.
+    pub heartbeat: Option<String>,
+}
+
+impl SupervisorConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<Error>> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn heartbeat_timeout(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_timeout.unwrap_or(DEFAULT_HEARTBEAT_TIMEOUT))
+    }
+
+    pub fn backoff(&self) -> (Duration, Duration) {
+        (Duration::from_secs(self.backoff_min.unwrap_or(DEFAULT_BACKOFF_MIN)), Duration::from_secs(self.backoff_max.unwrap_or(DEFAULT_BACKOFF_MAX)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config: SupervisorConfig = serde_json::from_str(r#"{
+            "services": [
+                {"name": "chain", "command": "bin/chain", "args": ["-c", "genesis.json"], "heartbeat": "chain"},
+                {"name": "jsonrpc", "command": "bin/jsonrpc", "env": {"RUST_LOG": "jsonrpc=trace"}}
+            ],
+            "backoff_max": 30
+        }"#)
+                .unwrap();
+        assert_eq!(config.services[0].args, vec!["-c", "genesis.json"]);
+        assert_eq!(config.services[1].heartbeat, None);
+        assert_eq!(config.services[1].env["RUST_LOG"], "jsonrpc=trace");
+        assert_eq!(config.heartbeat_timeout(), Duration::from_secs(30));
+        assert_eq!(config.backoff(), (Duration::from_secs(1), Duration::from_secs(30)));
+    }
+}
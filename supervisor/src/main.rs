@@ -0,0 +1,174 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Supervisor of the modules of a node. It starts the modules, restarts
+//! those which crashed with a backoff, and those which missed their
+//! heartbeats on the bus, and serves the health of the node over http.
+//! On SIGTERM or SIGINT it asks the modules to shut down gracefully.
+
+#[macro_use]
+extern crate log;
+extern crate clap;
+extern crate libc;
+extern crate hyper;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate dotenv;
+extern crate pubsub;
+extern crate cita_log;
+
+mod config;
+mod service;
+
+use clap::App;
+use config::SupervisorConfig;
+use hyper::header::ContentType;
+use hyper::server::{Request, Response, Server};
+use hyper::status::StatusCode;
+use log::LogLevelFilter;
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use service::{Backoff, Service, ServiceStatus};
+use std::collections::HashMap;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
+use std::sync::mpsc::{channel, sync_channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// milliseconds between two checks of the modules
+const CHECK_INTERVAL: u64 = 1000;
+// seconds the modules have to shut down before they are killed
+const STOP_TIMEOUT: u64 = 30;
+
+static STOP: AtomicBool = ATOMIC_BOOL_INIT;
+
+extern "C" fn on_signal(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Serialize, Default)]
+struct Health {
+    healthy: bool,
+    services: Vec<ServiceStatus>,
+}
+
+fn serve_health(addr: &str, health: Arc<Mutex<Health>>) {
+    let server = match Server::http(addr) {
+        Ok(server) => server,
+        Err(e) => {
+            error!("failed to serve the health at {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("health at {}", addr);
+    thread::spawn(move || {
+        let _ = server.handle(move |_: Request, mut res: Response| {
+                                  let health = health.lock().unwrap();
+                                  if !health.healthy {
+                                      *res.status_mut() = StatusCode::ServiceUnavailable;
+                                  }
+                                  res.headers_mut().set(ContentType::json());
+                                  let _ = res.send(serde_json::to_string(&*health).unwrap().as_bytes());
+                              });
+    });
+}
+
+fn stop(services: &mut [Service]) -> ! {
+    info!("stopping the modules");
+    for service in services.iter() {
+        service.terminate();
+    }
+    let deadline = Instant::now() + Duration::from_secs(STOP_TIMEOUT);
+    loop {
+        let force = Instant::now() >= deadline;
+        let mut stopped = true;
+        for service in services.iter_mut() {
+            if !service.stopped(force) {
+                stopped = false;
+            }
+        }
+        if stopped {
+            break;
+        }
+        thread::sleep(Duration::from_millis(CHECK_INTERVAL));
+    }
+    info!("stopped");
+    process::exit(0);
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    cita_log::format(LogLevelFilter::Info);
+    info!("CITA:supervisor");
+
+    let matches = App::new("supervisor")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Supervisor of the CITA modules of a node")
+        .args_from_usage("-c, --config=[FILE] 'Sets a custom config file'")
+        .get_matches();
+    let config_path = matches.value_of("config").unwrap_or("supervisor.json");
+    let config = SupervisorConfig::load(config_path).expect("config error!");
+
+    unsafe {
+        libc::signal(libc::SIGTERM, on_signal as libc::sighandler_t);
+        libc::signal(libc::SIGINT, on_signal as libc::sighandler_t);
+    }
+
+    // the latest heartbeat of each name
+    let heartbeats = Arc::new(Mutex::new(HashMap::new()));
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
+    let (_tx_pub, rx_pub) = channel();
+    start_pubsub("supervisor", vec!["*.heartbeat"], tx_sub, rx_pub);
+    let beats = heartbeats.clone();
+    thread::spawn(move || loop {
+                      let (key, _): (String, Vec<u8>) = rx_sub.recv().unwrap();
+                      if let Some(name) = key.split('.').next() {
+                          beats.lock().unwrap().insert(name.to_string(), Instant::now());
+                      }
+                  });
+
+    let health = Arc::new(Mutex::new(Health::default()));
+    if let Some(ref addr) = config.health_addr {
+        serve_health(addr, health.clone());
+    }
+
+    let timeout = config.heartbeat_timeout();
+    let (backoff_min, backoff_max) = config.backoff();
+    let mut services: Vec<_> = config.services.iter().map(|service| Service::new(service.clone(), Backoff::new(backoff_min, backoff_max))).collect();
+    loop {
+        if STOP.load(Ordering::SeqCst) {
+            stop(&mut services);
+        }
+        let beats = heartbeats.lock().unwrap().clone();
+        let mut statuses = Vec::new();
+        for service in services.iter_mut() {
+            let heartbeat = service.config.heartbeat.as_ref().and_then(|name| beats.get(name)).cloned();
+            service.check(heartbeat, timeout);
+            statuses.push(service.status(heartbeat));
+        }
+        {
+            let mut health = health.lock().unwrap();
+            health.healthy = statuses.iter().all(|status| status.running);
+            health.services = statuses;
+        }
+        thread::sleep(Duration::from_millis(CHECK_INTERVAL));
+    }
+}
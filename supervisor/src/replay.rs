@@ -0,0 +1,90 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replay of the dead letters of a queue, the messages its module failed to
+//! handle. The messages are published again under their keys, so all the
+//! queues subscribing to a key get its message again, and the dead letters
+//! are dropped once published.
+
+#[macro_use]
+extern crate log;
+extern crate clap;
+extern crate dotenv;
+extern crate pubsub;
+extern crate cita_log;
+
+use clap::App;
+use log::LogLevelFilter;
+use pubsub::{start_pubsub, QUEUE_SIZE};
+use pubsub::retry::{clear_dead_letters, dead_letter_dir, dead_letter_file, read_dead_letters};
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc::{channel, sync_channel};
+use std::thread;
+use std::time::Duration;
+
+// seconds the bus has to publish the messages before the process exits
+const FLUSH_TIMEOUT: u64 = 2;
+
+fn main() {
+    dotenv::dotenv().ok();
+    cita_log::format(LogLevelFilter::Info);
+
+    let matches = App::new("replay")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Publish again the dead letters of a queue of the bus")
+        .args_from_usage("<queue> 'Sets the queue whose dead letters are replayed'
+                          -d, --dir=[DIR] 'Sets the directory of the dead letters'
+                          -l, --list 'Lists the dead letters without replaying them'")
+        .get_matches();
+    let queue = matches.value_of("queue").unwrap();
+    let dir = matches.value_of("dir").map_or_else(dead_letter_dir, PathBuf::from);
+    let file = dead_letter_file(&dir, queue);
+    let letters = match read_dead_letters(&file) {
+        Ok(letters) => letters,
+        Err(e) => {
+            error!("failed to read the dead letters in {:?}: {}", file, e);
+            process::exit(1);
+        }
+    };
+
+    if matches.is_present("list") {
+        for letter in &letters {
+            println!("{} {} bytes: {}", letter.key, letter.msg.len(), letter.error);
+        }
+        return;
+    }
+    if letters.is_empty() {
+        info!("no dead letters of {}", queue);
+        return;
+    }
+
+    let (tx_sub, _rx_sub) = sync_channel(QUEUE_SIZE);
+    let (tx_pub, rx_pub) = channel();
+    start_pubsub("replay", vec![], tx_sub, rx_pub);
+    let count = letters.len();
+    for letter in letters {
+        tx_pub.send((letter.key, letter.msg)).unwrap();
+    }
+    thread::sleep(Duration::from_secs(FLUSH_TIMEOUT));
+    if let Err(e) = clear_dead_letters(&file) {
+        error!("failed to drop the dead letters in {:?}: {}", file, e);
+        process::exit(1);
+    }
+    info!("replayed {} dead letters of {}", count, queue);
+}
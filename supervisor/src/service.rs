@@ -0,0 +1,230 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use config::ServiceConfig;
+use libc;
+use std::cmp;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Delays of restarting a crashed service, doubled on every crash up to the max,
+/// and back to the min once the service ran longer than the max.
+#[derive(Debug)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Backoff {
+            min: min,
+            max: max,
+            next: min,
+        }
+    }
+
+    /// The delay before the restart of a service crashed after running for the uptime.
+    pub fn delay(&mut self, uptime: Duration) -> Duration {
+        if uptime > self.max {
+            self.next = self.min;
+        }
+        let delay = self.next;
+        self.next = cmp::min(self.next * 2, self.max);
+        delay
+    }
+}
+
+/// Whether a service started at the time, last heard at the heartbeat, missed its heartbeats.
+/// A service not heard since it started is still starting, which may take long.
+pub fn is_stuck(started: Instant, heartbeat: Option<Instant>, now: Instant, timeout: Duration) -> bool {
+    match heartbeat {
+        Some(heartbeat) if heartbeat >= started => now.duration_since(heartbeat) > timeout,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    /// Seconds since the last heartbeat, none if not watched or not heard yet.
+    pub heartbeat_age: Option<u64>,
+}
+
+pub struct Service {
+    pub config: ServiceConfig,
+    child: Option<Child>,
+    started: Instant,
+    restart_at: Option<Instant>,
+    backoff: Backoff,
+    restarts: u32,
+}
+
+impl Service {
+    pub fn new(config: ServiceConfig, backoff: Backoff) -> Self {
+        Service {
+            config: config,
+            child: None,
+            started: Instant::now(),
+            restart_at: Some(Instant::now()),
+            backoff: backoff,
+            restarts: 0,
+        }
+    }
+
+    fn spawn(&mut self) -> io::Result<()> {
+        let mut command = Command::new(&self.config.command);
+        command.args(&self.config.args);
+        for (key, value) in &self.config.env {
+            command.env(key, value);
+        }
+        if let Some(ref log) = self.config.log {
+            let file = OpenOptions::new().create(true).append(true).open(log)?;
+            let err = file.try_clone()?;
+            unsafe {
+                command.stdout(Stdio::from_raw_fd(file.into_raw_fd()));
+                command.stderr(Stdio::from_raw_fd(err.into_raw_fd()));
+            }
+        }
+        self.child = Some(command.spawn()?);
+        self.started = Instant::now();
+        info!("started {} pid {}", self.config.name, self.pid().unwrap_or(0));
+        Ok(())
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.child.as_ref().map(|child| child.id())
+    }
+
+    fn schedule_restart(&mut self, now: Instant) {
+        self.child = None;
+        let delay = self.backoff.delay(now.duration_since(self.started));
+        warn!("restart {} in {:?}", self.config.name, delay);
+        self.restart_at = Some(now + delay);
+        self.restarts += 1;
+    }
+
+    /// Start the service when its restart is due, restart it when it exited or
+    /// missed its heartbeats.
+    pub fn check(&mut self, heartbeat: Option<Instant>, timeout: Duration) {
+        let now = Instant::now();
+        if let Some(at) = self.restart_at {
+            if now >= at {
+                self.restart_at = None;
+                if let Err(e) = self.spawn() {
+                    error!("failed to start {}: {}", self.config.name, e);
+                    self.schedule_restart(now);
+                }
+            }
+            return;
+        }
+        let exited = match self.child.as_mut().map(|child| child.try_wait()) {
+            Some(Ok(Some(status))) => {
+                warn!("{} exited with {}", self.config.name, status);
+                true
+            }
+            Some(Ok(None)) => false,
+            _ => true,
+        };
+        if exited {
+            self.schedule_restart(now);
+        } else if self.config.heartbeat.is_some() && is_stuck(self.started, heartbeat, now, timeout) {
+            warn!("{} missed its heartbeats, killing it", self.config.name);
+            if let Some(mut child) = self.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            self.schedule_restart(now);
+        }
+    }
+
+    /// Ask the service to shut down gracefully.
+    pub fn terminate(&self) {
+        if let Some(pid) = self.pid() {
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGTERM);
+            }
+        }
+    }
+
+    /// Whether the service exited after being asked to, killing it when forced.
+    pub fn stopped(&mut self, force: bool) -> bool {
+        let stopped = match self.child.as_mut() {
+            None => true,
+            Some(child) => {
+                if force {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    true
+                } else {
+                    child.try_wait().map(|status| status.is_some()).unwrap_or(true)
+                }
+            }
+        };
+        if stopped {
+            self.child = None;
+        }
+        stopped
+    }
+
+    pub fn status(&self, heartbeat: Option<Instant>) -> ServiceStatus {
+        ServiceStatus {
+            name: self.config.name.clone(),
+            running: self.child.is_some(),
+            pid: self.pid(),
+            restarts: self.restarts,
+            heartbeat_age: self.config.heartbeat.as_ref().and(heartbeat).map(|heartbeat| heartbeat.elapsed().as_secs()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let crashed = Duration::from_secs(0);
+        assert_eq!(backoff.delay(crashed), Duration::from_secs(1));
+        assert_eq!(backoff.delay(crashed), Duration::from_secs(2));
+        assert_eq!(backoff.delay(crashed), Duration::from_secs(4));
+        assert_eq!(backoff.delay(crashed), Duration::from_secs(5));
+        // ran long enough
+        assert_eq!(backoff.delay(Duration::from_secs(6)), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn stuck() {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(30);
+        // still starting
+        assert!(!is_stuck(started, None, started + Duration::from_secs(100), timeout));
+        let heard = started + Duration::from_secs(20);
+        assert!(!is_stuck(started, Some(heard), started + Duration::from_secs(31), timeout));
+        assert!(is_stuck(started, Some(heard), started + Duration::from_secs(51), timeout));
+        // heard before a restart
+        assert!(!is_stuck(heard + Duration::from_secs(1), Some(heard), started + Duration::from_secs(51), timeout));
+    }
+}
@@ -16,7 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use common_types::receipt::LocalizedReceipt;
-use core::libchain::Genesis;
+use core::libchain::{Config, Genesis};
 use core::libchain::chain::*;
 use std::sync::Arc;
 use std::sync::mpsc::Sender;
@@ -33,7 +33,7 @@ pub struct Callchain {
 impl Callchain {
     pub fn new(db: Arc<KeyValueDB>, genesis: Genesis, sync_sender: Sender<u64>) -> Self {
 
-        let (chain, st) = Chain::init_chain(db, genesis, sync_sender);
+        let (chain, st) = Chain::init_chain(db, genesis, &Config::default(), sync_sender);
         Callchain { chain: chain }
     }
 
@@ -17,9 +17,9 @@
 
 extern crate pubsub;
 
-use pubsub::start_pubsub;
+use pubsub::{start_pubsub, QUEUE_SIZE};
 use std::env;
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, sync_channel};
 use std::thread;
 use std::time::{Duration, SystemTime};
 
@@ -32,12 +32,12 @@ fn main() {
     let max = args[1].parse::<u64>().unwrap();
     let size = args[2].parse::<usize>().unwrap();
     println!{"test count {:?}, test size: {:?}", max, size};
-    let (tx_sub, rx_sub) = channel();
+    let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
     let (tx_pub, rx_pub) = channel();
     start_pubsub("latency_req", vec!["latency_res"], tx_sub, rx_pub);
 
     thread::spawn(move || {
-        let (tx_sub, rx_sub) = channel();
+        let (tx_sub, rx_sub) = sync_channel(QUEUE_SIZE);
         let (tx_pub, rx_pub) = channel();
         start_pubsub("latency_res", vec!["latency_req"], tx_sub, rx_pub);
         loop {
@@ -0,0 +1,373 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Load tests of running nodes over their jsonrpc: signed transactions are
+//! sent at a target rate, round robin to the nodes, by the keys of the
+//! senders generated for the run. The blocks are watched on the first node,
+//! giving the confirmation latency of each transaction, from being sent to
+//! its block being seen, and the transactions per second of each block.
+//! The report is printed, and written as csv or json.
+
+extern crate clap;
+extern crate hyper;
+extern crate protobuf;
+extern crate rustc_serialize;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate libproto;
+extern crate util;
+extern crate cita_ed25519 as ed25519;
+
+mod report;
+mod rpc;
+
+use clap::{App, ArgMatches};
+use ed25519::KeyPair;
+use libproto::TRANSACTION_VERSION;
+use libproto::blockchain::{SignedTransaction, Transaction, UnverifiedTransaction};
+use protobuf::Message;
+use report::{BlockStat, Report, TxStat};
+use rpc::Rpc;
+use rustc_serialize::hex::{FromHex, ToHex};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+use util::Address;
+
+/// Transactions to the store address only keep their data.
+const STORE_ADDRESS: &str = "ffffffffffffffffffffffffffffffffffffffff";
+
+struct Bench {
+    urls: Vec<String>,
+    rate: f64,
+    count: Option<u64>,
+    duration: Option<Duration>,
+    keys: Vec<KeyPair>,
+    threads: usize,
+    to: String,
+    data: Vec<u8>,
+    chain_id: u32,
+    valid_blocks: u64,
+    wait: Duration,
+}
+
+/// The state of the run shared by the senders and the watcher.
+struct Run {
+    start: Instant,
+    txs: Mutex<HashMap<String, TxStat>>,
+    blocks: Mutex<Vec<BlockStat>>,
+    stop: AtomicBool,
+}
+
+impl Run {
+    fn elapsed(&self) -> u64 {
+        let elapsed = self.start.elapsed();
+        elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000
+    }
+
+    /// Sent and not refused, yet to be confirmed.
+    fn pending(&self) -> usize {
+        self.txs.lock().unwrap().values().filter(|tx| tx.error.is_none() && tx.confirmed.is_none()).count()
+    }
+}
+
+fn clean_0x(s: &str) -> &str {
+    let s = s.trim();
+    if s.starts_with("0x") { &s[2..] } else { s }
+}
+
+fn parse<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match matches.value_of(name) {
+        Some(value) => value.parse().map(Some).map_err(|_| format!("invalid {} {}", name, value)),
+        None => Ok(None),
+    }
+}
+
+impl Bench {
+    fn new(matches: &ArgMatches) -> Result<Self, String> {
+        let urls: Vec<String> = matches.values_of("url").unwrap().flat_map(|urls| urls.split(',')).map(|url| url.trim().to_string()).filter(|url| !url.is_empty()).collect();
+        if urls.is_empty() {
+            return Err("no url of the nodes".to_string());
+        }
+        let rate: f64 = parse(matches, "rate")?.unwrap_or(100.0);
+        if !(rate > 0.0) {
+            return Err(format!("invalid rate {}", rate));
+        }
+        let count = parse(matches, "count")?;
+        let duration = parse(matches, "duration")?.map(Duration::from_secs);
+        let to = clean_0x(matches.value_of("to").unwrap_or(STORE_ADDRESS)).to_string();
+        Address::from_str(&to).map_err(|_| format!("invalid receiver {}", to))?;
+        let data = match matches.value_of("data") {
+            Some(data) => clean_0x(data).from_hex().map_err(|_| "invalid data".to_string())?,
+            None => Vec::new(),
+        };
+        let senders = parse(matches, "senders")?.unwrap_or(1usize);
+        let threads = parse(matches, "threads")?.unwrap_or(4usize);
+        Ok(Bench {
+               urls: urls,
+               rate: rate,
+               // ten seconds of transactions if neither is set
+               count: if count.is_none() && duration.is_none() { Some((rate * 10.0).ceil() as u64) } else { count },
+               duration: duration,
+               keys: (0..if senders == 0 { 1 } else { senders }).map(|_| KeyPair::gen_keypair()).collect(),
+               threads: if threads == 0 { 1 } else { threads },
+               to: to,
+               data: data,
+               chain_id: parse(matches, "chain-id")?.unwrap_or(0),
+               valid_blocks: parse(matches, "valid-blocks")?.unwrap_or(100),
+               wait: Duration::from_secs(parse(matches, "wait")?.unwrap_or(60)),
+           })
+    }
+
+    /// The signed transaction of the index and its hash. The senders take the indexes
+    /// in turn, so the nonces of each sender count up from 0 without gaps.
+    fn sign(&self, index: u64, valid_until_block: u64) -> (String, String) {
+        let senders = self.keys.len() as u64;
+        let mut tx = Transaction::new();
+        tx.set_to(self.to.clone());
+        tx.set_data(self.data.clone());
+        tx.set_nonce(format!("{:x}", index / senders));
+        tx.set_valid_until_block(valid_until_block);
+        tx.set_chain_id(self.chain_id);
+        tx.set_version(TRANSACTION_VERSION);
+        let mut utx = UnverifiedTransaction::new();
+        utx.set_transaction(tx);
+        let mut signed = SignedTransaction::new();
+        signed.set_transaction_with_sig(utx);
+        signed.sign(*self.keys[(index % senders) as usize].privkey());
+        (format!("0x{}", signed.get_tx_hash().to_hex()), format!("0x{}", signed.write_to_bytes().unwrap().to_hex()))
+    }
+
+    /// Hand the indexes of the transactions to the senders at the rate, until the count or the duration.
+    fn pace(&self, run: &Run, jobs: Sender<u64>) {
+        let mut index = 0;
+        loop {
+            if self.count.map_or(false, |count| index >= count) {
+                break;
+            }
+            let due = (index as f64 * 1000.0 / self.rate) as u64;
+            if self.duration.map_or(false, |duration| due >= duration.as_secs() * 1000) {
+                break;
+            }
+            let now = run.elapsed();
+            if due > now {
+                thread::sleep(Duration::from_millis(due - now));
+            }
+            if jobs.send(index).is_err() {
+                break;
+            }
+            index += 1;
+        }
+    }
+
+    fn send(&self, run: &Run, rpc: &Rpc, jobs: &Mutex<Receiver<u64>>, valid_until_block: u64) {
+        loop {
+            let index = match jobs.lock().unwrap().recv() {
+                Ok(index) => index,
+                Err(_) => break,
+            };
+            let (hash, raw) = self.sign(index, valid_until_block);
+            let node = index as usize % self.urls.len();
+            let sent = run.elapsed();
+            let error = rpc.send_transaction(&self.urls[node], &raw).err();
+            run.txs.lock().unwrap().insert(hash.clone(),
+                                           TxStat {
+                                               hash: hash,
+                                               node: node,
+                                               sent: sent,
+                                               error: error,
+                                               confirmed: None,
+                                               height: None,
+                                           });
+        }
+    }
+
+    /// Follow the blocks after the height on the first node until the run stops.
+    fn watch(&self, run: &Run, rpc: &Rpc, height: u64, mut parent_timestamp: u64) {
+        let url = &self.urls[0];
+        let mut next = height + 1;
+        while !run.stop.load(Ordering::SeqCst) {
+            let current = rpc.block_number(url).unwrap_or(0);
+            while next <= current {
+                let block = match rpc.block(url, next) {
+                    Ok(block) => block,
+                    Err(_) => break,
+                };
+                let seen = run.elapsed();
+                let mut ours = 0;
+                {
+                    let mut txs = run.txs.lock().unwrap();
+                    for hash in &block.tx_hashes {
+                        if let Some(tx) = txs.get_mut(&format!("0x{}", clean_0x(hash).to_lowercase())) {
+                            tx.confirmed = Some(seen);
+                            tx.height = Some(next);
+                            ours += 1;
+                        }
+                    }
+                }
+                run.blocks.lock().unwrap().push(BlockStat::new(next, block.timestamp, parent_timestamp, block.tx_hashes.len(), ours));
+                parent_timestamp = block.timestamp;
+                next += 1;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let bench = Arc::new(Bench::new(matches)?);
+    let rpc = Arc::new(Rpc::new());
+    let height = rpc.block_number(&bench.urls[0])?;
+    let parent = rpc.block(&bench.urls[0], height)?;
+    let valid_until_block = height + bench.valid_blocks;
+    println!("sending to {} nodes at {}/s from height {}", bench.urls.len(), bench.rate, height);
+
+    let run = Arc::new(Run {
+                           start: Instant::now(),
+                           txs: Mutex::new(HashMap::new()),
+                           blocks: Mutex::new(Vec::new()),
+                           stop: AtomicBool::new(false),
+                       });
+    let watcher = {
+        let (bench, run, rpc) = (bench.clone(), run.clone(), rpc.clone());
+        thread::spawn(move || bench.watch(&run, &rpc, height, parent.timestamp))
+    };
+    let (jobs_tx, jobs_rx) = channel();
+    let jobs = Arc::new(Mutex::new(jobs_rx));
+    let senders: Vec<_> = (0..bench.threads)
+        .map(|_| {
+                 let (bench, run, rpc, jobs) = (bench.clone(), run.clone(), rpc.clone(), jobs.clone());
+                 thread::spawn(move || bench.send(&run, &rpc, &jobs, valid_until_block))
+             })
+        .collect();
+    bench.pace(&run, jobs_tx);
+    for sender in senders {
+        let _ = sender.join();
+    }
+    let sending = run.elapsed();
+
+    let deadline = Instant::now() + bench.wait;
+    while run.pending() > 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+    run.stop.store(true, Ordering::SeqCst);
+    let _ = watcher.join();
+
+    let mut txs: Vec<TxStat> = run.txs.lock().unwrap().values().cloned().collect();
+    txs.sort_by_key(|tx| tx.sent);
+    let blocks = run.blocks.lock().unwrap().clone();
+    let report = Report::new(blocks, txs, sending, bench.rate);
+    report.print();
+    if let Some(path) = matches.value_of("csv") {
+        report::write_file(path, |file| report.write_blocks_csv(file))?;
+    }
+    if let Some(path) = matches.value_of("tx-csv") {
+        report::write_file(path, |file| report.write_txs_csv(file))?;
+    }
+    if let Some(path) = matches.value_of("json") {
+        report::write_file(path, |file| report.write_json(file))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let matches = App::new("cita-bench")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Sends signed transactions to the nodes at a rate, reporting their latency and the tps of the blocks")
+        .args_from_usage("-u, --url=<URL>... 'Sets the jsonrpc urls of the nodes, the blocks are watched on the first one'
+                          --rate=[TPS] 'Sets the transactions sent per second, 100 by default'
+                          --count=[N] 'Sets the transactions sent, ten seconds of them by default'
+                          --duration=[SECONDS] 'Sets the seconds to send for'
+                          --senders=[N] 'Sets the senders signing the transactions, 1 by default'
+                          --threads=[N] 'Sets the threads sending the transactions, 4 by default'
+                          --to=[ADDRESS] 'Sets the receiver, the store address by default'
+                          --data=[HEX] 'Sets the data of the transactions'
+                          --chain-id=[ID] 'Sets the id of the chain, 0 by default'
+                          --valid-blocks=[N] 'Sets the blocks the transactions are valid for, 100 by default'
+                          --wait=[SECONDS] 'Sets the seconds to wait for the confirmations after sending, 60 by default'
+                          --csv=[FILE] 'Writes the blocks as csv'
+                          --tx-csv=[FILE] 'Writes the transactions as csv'
+                          --json=[FILE] 'Writes the whole report as json'")
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        let _ = writeln!(io::stderr(), "{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign() {
+        let bench = Bench {
+            urls: vec!["http://127.0.0.1:1337".to_string()],
+            rate: 10.0,
+            count: Some(2),
+            duration: None,
+            keys: vec![KeyPair::gen_keypair(), KeyPair::gen_keypair()],
+            threads: 1,
+            to: STORE_ADDRESS.to_string(),
+            data: vec![1, 2, 3],
+            chain_id: 1,
+            valid_blocks: 100,
+            wait: Duration::from_secs(1),
+        };
+        let (hash, raw) = bench.sign(1, 100);
+        let signed: SignedTransaction = protobuf::parse_from_bytes(&clean_0x(&raw).from_hex().unwrap()).unwrap();
+        assert_eq!(hash, format!("0x{}", signed.get_tx_hash().to_hex()));
+        assert_eq!(signed.get_signer(), &bench.keys[1].pubkey()[..]);
+        assert_eq!(signed.get_transaction_with_sig().get_transaction().get_nonce(), "0");
+        assert!(bench.sign(0, 100).0 != hash);
+    }
+
+    #[test]
+    fn nonces_by_sender() {
+        let bench = Bench {
+            urls: vec!["http://127.0.0.1:1337".to_string()],
+            rate: 10.0,
+            count: Some(6),
+            duration: None,
+            keys: vec![KeyPair::gen_keypair(), KeyPair::gen_keypair()],
+            threads: 1,
+            to: STORE_ADDRESS.to_string(),
+            data: vec![1, 2, 3],
+            chain_id: 1,
+            valid_blocks: 100,
+            wait: Duration::from_secs(1),
+        };
+        let signed: Vec<SignedTransaction> = (0..6)
+            .map(|index| protobuf::parse_from_bytes(&clean_0x(&bench.sign(index, 100).1).from_hex().unwrap()).unwrap())
+            .collect();
+        for (index, signed) in signed.iter().enumerate() {
+            assert_eq!(signed.get_signer(), &bench.keys[index % 2].pubkey()[..]);
+            assert_eq!(signed.get_transaction_with_sig().get_transaction().get_nonce(), format!("{:x}", index / 2));
+        }
+    }
+}
@@ -0,0 +1,233 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reports of a run: the blocks with their transactions per second, the
+//! transactions sent with their confirmation latency, and their summary.
+
+use serde_json;
+use std::fs::File;
+use std::io::{self, Write};
+
+/// A transaction sent, milliseconds since the start of the run.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TxStat {
+    pub hash: String,
+    /// Index of the node the transaction was sent to.
+    pub node: usize,
+    pub sent: u64,
+    /// Refused by the node, with the reason.
+    pub error: Option<String>,
+    /// When the block packaging the transaction was seen, and its height.
+    pub confirmed: Option<u64>,
+    pub height: Option<u64>,
+}
+
+impl TxStat {
+    pub fn latency(&self) -> Option<u64> {
+        self.confirmed.map(|confirmed| confirmed.saturating_sub(self.sent))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BlockStat {
+    pub height: u64,
+    /// Timestamp of the block in milliseconds.
+    pub timestamp: u64,
+    /// Milliseconds since the block before.
+    pub interval: u64,
+    pub txs: usize,
+    /// Transactions of the run in the block.
+    pub ours: usize,
+    pub tps: f64,
+}
+
+impl BlockStat {
+    pub fn new(height: u64, timestamp: u64, parent_timestamp: u64, txs: usize, ours: usize) -> Self {
+        let interval = timestamp.saturating_sub(parent_timestamp);
+        BlockStat {
+            height: height,
+            timestamp: timestamp,
+            interval: interval,
+            txs: txs,
+            ours: ours,
+            tps: if interval == 0 { 0.0 } else { txs as f64 * 1000.0 / interval as f64 },
+        }
+    }
+}
+
+/// Confirmation latencies in milliseconds.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Latency {
+    pub min: u64,
+    pub mean: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl Latency {
+    pub fn new(mut latencies: Vec<u64>) -> Self {
+        if latencies.is_empty() {
+            return Latency::default();
+        }
+        latencies.sort();
+        // nearest rank
+        let percentile = |p: usize| latencies[(latencies.len() * p + 99) / 100 - 1];
+        Latency {
+            min: latencies[0],
+            mean: latencies.iter().sum::<u64>() / latencies.len() as u64,
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: latencies[latencies.len() - 1],
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub sent: usize,
+    pub refused: usize,
+    pub confirmed: usize,
+    /// Rate the transactions were sent at, and the rate asked for.
+    pub send_rate: f64,
+    pub target_rate: f64,
+    /// Transactions of the blocks per second, from the block before the first to the last.
+    pub tps: f64,
+    pub latency: Latency,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Report {
+    pub summary: Summary,
+    pub blocks: Vec<BlockStat>,
+    pub txs: Vec<TxStat>,
+}
+
+impl Report {
+    /// `sending` is the milliseconds the transactions were sent in.
+    pub fn new(blocks: Vec<BlockStat>, txs: Vec<TxStat>, sending: u64, target_rate: f64) -> Self {
+        let refused = txs.iter().filter(|tx| tx.error.is_some()).count();
+        let latencies: Vec<u64> = txs.iter().filter_map(|tx| tx.latency()).collect();
+        let interval: u64 = blocks.iter().map(|block| block.interval).sum();
+        let packaged: usize = blocks.iter().map(|block| block.txs).sum();
+        Report {
+            summary: Summary {
+                sent: txs.len(),
+                refused: refused,
+                confirmed: latencies.len(),
+                send_rate: if sending == 0 { 0.0 } else { txs.len() as f64 * 1000.0 / sending as f64 },
+                target_rate: target_rate,
+                tps: if interval == 0 { 0.0 } else { packaged as f64 * 1000.0 / interval as f64 },
+                latency: Latency::new(latencies),
+            },
+            blocks: blocks,
+            txs: txs,
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, out: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(out, self).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    /// A line for each block.
+    pub fn write_blocks_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "height,timestamp,interval_ms,txs,ours,tps")?;
+        for block in &self.blocks {
+            writeln!(out, "{},{},{},{},{},{:.2}", block.height, block.timestamp, block.interval, block.txs, block.ours, block.tps)?;
+        }
+        Ok(())
+    }
+
+    /// A line for each transaction, the fields of the unconfirmed ones left empty.
+    pub fn write_txs_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "hash,node,sent_ms,confirmed_ms,latency_ms,height,error")?;
+        let field = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+        for tx in &self.txs {
+            writeln!(out,
+                     "{},{},{},{},{},{},{}",
+                     tx.hash,
+                     tx.node,
+                     tx.sent,
+                     field(tx.confirmed),
+                     field(tx.latency()),
+                     field(tx.height),
+                     tx.error.as_ref().map(|e| e.replace(',', ";")).unwrap_or_default())?;
+        }
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        let summary = &self.summary;
+        let latency = &summary.latency;
+        println!("sent {} at {:.1}/s (target {:.1}/s), refused {}, confirmed {}",
+                 summary.sent,
+                 summary.send_rate,
+                 summary.target_rate,
+                 summary.refused,
+                 summary.confirmed);
+        println!("{} blocks, {:.1} tps", self.blocks.len(), summary.tps);
+        println!("latency ms: min {} mean {} p50 {} p95 {} p99 {} max {}", latency.min, latency.mean, latency.p50, latency.p95, latency.p99, latency.max);
+    }
+}
+
+/// Write the report into the file by the writer.
+pub fn write_file<F>(path: &str, write: F) -> Result<(), String>
+    where F: FnOnce(File) -> io::Result<()>
+{
+    File::create(path).and_then(write).map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(sent: u64, confirmed: Option<u64>) -> TxStat {
+        TxStat {
+            hash: "0x01".to_string(),
+            node: 0,
+            sent: sent,
+            error: None,
+            confirmed: confirmed,
+            height: confirmed.map(|_| 2),
+        }
+    }
+
+    #[test]
+    fn report() {
+        let latency = Latency::new((1..101).collect());
+        assert_eq!((latency.min, latency.mean, latency.p50, latency.p95, latency.p99, latency.max), (1, 50, 50, 95, 99, 100));
+        assert_eq!(Latency::new(vec![7]).p99, 7);
+
+        let blocks = vec![BlockStat::new(1, 3000, 0, 0, 0), BlockStat::new(2, 6000, 3000, 300, 2)];
+        assert_eq!(blocks[1].tps, 100.0);
+        let mut refused = tx(20, None);
+        refused.error = Some("dup, again".to_string());
+        let report = Report::new(blocks, vec![tx(0, Some(500)), tx(10, Some(1510)), refused], 30, 100.0);
+        assert_eq!(report.summary.confirmed, 2);
+        assert_eq!(report.summary.refused, 1);
+        assert_eq!(report.summary.tps, 50.0);
+        assert_eq!(report.summary.latency.max, 1500);
+
+        let mut csv = Vec::new();
+        report.write_txs_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(2), Some("0x01,0,10,1510,1500,2,"));
+        assert_eq!(csv.lines().nth(3), Some("0x01,0,20,,,,dup; again"));
+    }
+}
@@ -0,0 +1,114 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The calls of the jsonrpc of the nodes the run needs.
+
+use hyper::Client;
+use serde_json::{self, Value};
+use std::io::Read;
+use std::time::Duration;
+
+/// A block seen by the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockInfo {
+    pub height: u64,
+    pub timestamp: u64,
+    pub tx_hashes: Vec<String>,
+}
+
+pub struct Rpc {
+    client: Client,
+}
+
+fn parse_hex_u64(value: &Value) -> Result<u64, String> {
+    match *value {
+        Value::Number(ref n) => n.as_u64().ok_or(format!("invalid number {}", n)),
+        Value::String(ref s) => {
+            let hex = if s.starts_with("0x") { &s[2..] } else { s.as_str() };
+            u64::from_str_radix(hex, 16).map_err(|_| format!("invalid number {}", s))
+        }
+        ref other => Err(format!("invalid number {}", other)),
+    }
+}
+
+impl Rpc {
+    pub fn new() -> Self {
+        let mut client = Client::new();
+        client.set_read_timeout(Some(Duration::from_secs(10)));
+        client.set_write_timeout(Some(Duration::from_secs(10)));
+        Rpc { client: client }
+    }
+
+    /// The result of the call, or its error.
+    pub fn call(&self, url: &str, method: &str, params: Value) -> Result<Value, String> {
+        let body = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}).to_string();
+        let mut response = self.client.post(url).body(&body).send().map_err(|e| format!("{}: {}", url, e))?;
+        let mut content = String::new();
+        response.read_to_string(&mut content).map_err(|e| format!("{}: {}", url, e))?;
+        let value: Value = serde_json::from_str(&content).map_err(|_| format!("{}: invalid response {}", url, content))?;
+        match value {
+            Value::Object(mut map) => {
+                if let Some(error) = map.remove("error") {
+                    return Err(format!("{}: {}", method, error));
+                }
+                map.remove("result").ok_or(format!("{}: no result in {}", method, content))
+            }
+            _ => Err(format!("{}: invalid response {}", url, content)),
+        }
+    }
+
+    pub fn block_number(&self, url: &str) -> Result<u64, String> {
+        parse_hex_u64(&self.call(url, "cita_blockNumber", json!([]))?)
+    }
+
+    /// The block of the height, with the hashes of its transactions.
+    pub fn block(&self, url: &str, height: u64) -> Result<BlockInfo, String> {
+        let block = self.call(url, "cita_getBlockByNumber", json!([format!("0x{:x}", height), false]))?;
+        let timestamp = parse_hex_u64(&block["header"]["timestamp"])?;
+        let tx_hashes = match block["body"]["transactions"] {
+            Value::Array(ref txs) => txs.iter().filter_map(|tx| tx["hash"].as_str().map(|hash| hash.to_string())).collect(),
+            _ => Vec::new(),
+        };
+        Ok(BlockInfo {
+               height: height,
+               timestamp: timestamp,
+               tx_hashes: tx_hashes,
+           })
+    }
+
+    /// Send the hex of the signed transaction, an error if the node refuses it.
+    pub fn send_transaction(&self, url: &str, raw: &str) -> Result<(), String> {
+        let result = self.call(url, "cita_sendTransaction", json!([raw]))?;
+        match result["status"].as_str() {
+            Some(status) if status.ends_with("OK") => Ok(()),
+            Some(status) => Err(status.to_string()),
+            None => Err(format!("invalid response {}", result)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(parse_hex_u64(&json!("0x1f")), Ok(31));
+        assert_eq!(parse_hex_u64(&json!(1500000000000u64)), Ok(1500000000000));
+        assert!(parse_hex_u64(&json!(null)).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Configs of a new chain generated from the spec of the chain in one toml:
+//! the genesis with the system contracts initialized by the quota and the
+//! permissions of the spec, and for each validator a node directory with its
+//! private key, network, consensus and jsonrpc configs, the same as the
+//! admintool writes. The keys of the validators are kept when generated
+//! again, so with a timestamp set in the spec the same chain is generated.
+
+extern crate clap;
+extern crate core;
+extern crate rustc_serialize;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
+extern crate util;
+extern crate cita_ed25519 as ed25519;
+
+mod spec;
+
+use clap::App;
+use ed25519::{KeyPair, PrivKey};
+use rustc_serialize::hex::ToHex;
+use spec::ChainSpec;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use util::Address;
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), String> {
+    File::create(path).and_then(|mut file| file.write_all(content)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_privkey(hex: &str) -> Result<KeyPair, String> {
+    let hex = hex.trim();
+    let hex = if hex.starts_with("0x") { &hex[2..] } else { hex };
+    let privkey = PrivKey::from_str(hex).map_err(|_| "invalid private key".to_string())?;
+    KeyPair::from_privkey(privkey).map_err(|e| e.to_string())
+}
+
+/// The key of the validator set in the spec, kept in its directory or a new one.
+fn validator_key(privkey: &Option<String>, dir: &Path) -> Result<KeyPair, String> {
+    let path = dir.join("privkey");
+    if let Some(ref privkey) = *privkey {
+        return parse_privkey(privkey);
+    }
+    let mut content = String::new();
+    match File::open(&path).and_then(|mut file| file.read_to_string(&mut content)) {
+        Ok(_) => parse_privkey(&content).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(KeyPair::gen_keypair()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn network_config(spec: &ChainSpec, id: usize) -> String {
+    let validator = &spec.validators[id];
+    let mut config = format!("id_card = {}\nip = \"{}\"\nport = {}\nmax_peer = {}\ngenesis = \"genesis.json\"\n", id, validator.ip, validator.port, spec.validators.len() - 1);
+    if let Some(chain_id) = spec.chain_id {
+        config.push_str(&format!("chain_id = {}\n", chain_id));
+    }
+    for (peer, validator) in spec.validators.iter().enumerate().filter(|&(peer, _)| peer != id) {
+        config.push_str(&format!("[[peers]]\nid_card = {}\nip = \"{}\"\nport = {}\n", peer, validator.ip, validator.port));
+    }
+    config
+}
+
+fn consensus_config(spec: &ChainSpec, authorities: &[Address], key: &KeyPair) -> serde_json::Value {
+    let mut params = json!({
+        "authorities": authorities,
+        "duration": spec.duration.unwrap_or(3000),
+        "is_test": false,
+        "signer": key.privkey().to_hex(),
+        "block_tx_limit": spec.block_tx_limit.unwrap_or(300),
+        "tx_filter_size": spec.tx_filter_size.unwrap_or(100000),
+        "tx_pool_size": spec.tx_pool_size.unwrap_or(0)
+    });
+    if let Some(chain_id) = spec.chain_id {
+        params["chain_id"] = json!(chain_id);
+    }
+    if let Some(interval) = spec.block_interval {
+        params["block_interval"] = json!(interval);
+    }
+    let engine = match spec.consensus() {
+        "tendermint" => json!({ "Tendermint": { "params": params } }),
+        _ => json!({ "AuthorityRound": { "params": params } }),
+    };
+    json!({ "name": spec.consensus(), "engine": engine })
+}
+
+fn jsonrpc_config(spec: &ChainSpec, id: usize) -> serde_json::Value {
+    let (http, http_port, ws, ws_port) = match spec.jsonrpc {
+        Some(ref jsonrpc) => (jsonrpc.http.unwrap_or(true), jsonrpc.http_port.unwrap_or(1337), jsonrpc.ws.unwrap_or(false), jsonrpc.ws_port.unwrap_or(4337)),
+        None => (true, 1337, false, 4337),
+    };
+    // the nodes on the same host listen on the next ports
+    let ip = &spec.validators[id].ip;
+    let offset = spec.validators[..id].iter().filter(|validator| &validator.ip == ip).count() as u64;
+    let http_port = (http_port + offset).to_string();
+    let ws_port = (ws_port + offset).to_string();
+    json!({
+        "profile_config": { "enable": false, "flag_prof_start": 0, "flag_prof_duration": 0 },
+        "http_config": {
+            "enable": http, "thread_number": 200, "listen_ip": "0.0.0.0", "listen_port": http_port,
+            "sleep_duration": 1, "timeout_count": 3000
+        },
+        "ws_config": {
+            "enable": ws, "thread_number": 0, "listen_ip": "0.0.0.0", "listen_port": ws_port,
+            "max_connections": 500, "queue_size": 500,
+            "panic_on_new_connection": false, "panic_on_shutdown": false,
+            "fragments_capacity": 100, "fragments_grow": true,
+            "fragment_size": 65535, "in_buffer_capacity": 2048,
+            "in_buffer_grow": true, "out_buffer_capacity": 2048,
+            "out_buffer_grow": true, "panic_on_internal": true,
+            "panic_on_capacity": false, "panic_on_protocol": false,
+            "panic_on_encoding": false, "panic_on_queue": false,
+            "panic_on_io": false, "panic_on_timeout": false,
+            "shutdown_on_interrupt": true, "masking_strict": false,
+            "key_strict": false, "method_strict": false,
+            "encrypt_server": false, "tcp_nodelay": false
+        }
+    })
+}
+
+/// Write the genesis and the node directories of the spec into the directory.
+fn generate(spec: &ChainSpec, out: &Path) -> Result<(), String> {
+    let mut keys = Vec::new();
+    for (id, validator) in spec.validators.iter().enumerate() {
+        let dir = out.join(format!("node{}", id));
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        keys.push(validator_key(&validator.privkey, &dir)?);
+    }
+    let authorities: Vec<Address> = keys.iter().map(|key| key.address()).collect();
+    let timestamp = match spec.timestamp {
+        Some(timestamp) => timestamp,
+        None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+    };
+    let genesis = serde_json::to_vec_pretty(&spec.genesis(&authorities, timestamp)?).unwrap();
+    let list: String = authorities.iter().map(|address| format!("0x{}\n", address.to_hex())).collect();
+    write_file(&out.join("authorities"), list.as_bytes())?;
+    write_file(&out.join("genesis.json"), &genesis)?;
+
+    for (id, key) in keys.iter().enumerate() {
+        let dir = out.join(format!("node{}", id));
+        write_file(&dir.join("privkey"), key.privkey().to_hex().as_bytes())?;
+        write_file(&dir.join("genesis.json"), &genesis)?;
+        write_file(&dir.join("network.toml"), network_config(spec, id).as_bytes())?;
+        write_file(&dir.join("consensus.json"), &serde_json::to_vec_pretty(&consensus_config(spec, &authorities, key)).unwrap())?;
+        write_file(&dir.join("jsonrpc.json"), &serde_json::to_vec_pretty(&jsonrpc_config(spec, id)).unwrap())?;
+        println!("node{}: 0x{}", id, key.address().to_hex());
+    }
+    Ok(())
+}
+
+fn main() {
+    let matches = App::new("cita-chain-config")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Generates the genesis and the node configs of a chain from its spec")
+        .args_from_usage("<SPEC> 'Sets the toml spec of the chain'
+                          -o, --output=[DIR] 'Sets the directory of the configs, the current one by default'")
+        .get_matches();
+
+    let out = Path::new(matches.value_of("output").unwrap_or("."));
+    if let Err(e) = ChainSpec::load(matches.value_of("SPEC").unwrap()).and_then(|spec| generate(&spec, out)) {
+        let _ = writeln!(io::stderr(), "{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn generate_twice() {
+        let out = env::temp_dir().join("cita_chain_config");
+        let _ = fs::remove_dir_all(&out);
+        let spec = ChainSpec::parse("timestamp = 1500000000\n[[validators]]\nip = \"127.0.0.1\"\nport = 4000\n[[validators]]\nip = \"127.0.0.1\"\nport = 4001").unwrap();
+        let read = |path: &str| {
+            let mut content = String::new();
+            File::open(out.join(path)).unwrap().read_to_string(&mut content).unwrap();
+            content
+        };
+
+        generate(&spec, &out).unwrap();
+        let genesis = read("genesis.json");
+        assert_eq!(read("node1/genesis.json"), genesis);
+        assert!(read("node0/network.toml").contains("port = 4001"));
+        let jsonrpc: serde_json::Value = serde_json::from_str(&read("node1/jsonrpc.json")).unwrap();
+        assert_eq!(jsonrpc["http_config"]["listen_port"], "1338");
+
+        // the keys are kept, so the same genesis is generated again
+        generate(&spec, &out).unwrap();
+        assert_eq!(read("genesis.json"), genesis);
+        let _ = fs::remove_dir_all(&out);
+    }
+}
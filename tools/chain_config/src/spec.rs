@@ -0,0 +1,249 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The spec of a chain in toml, e.g.
+//!
+//! ```toml
+//! consensus = "tendermint"
+//! chain_id = 1
+//! timestamp = 1500000000
+//!
+//! [[validators]]
+//! ip = "127.0.0.1"
+//! port = 4000
+//!
+//! [quota]
+//! block_quota_limit = 1073741824
+//!
+//! [permissions]
+//! creators = ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"]
+//! admins = ["0x5b073e9233944b5e729e46d618f0d8edf3d9c34a"]
+//! ```
+
+use core::libchain::{GenesisBuilder, Spec};
+use rustc_serialize::hex::FromHex;
+use std::fs::File;
+use std::io::Read;
+use std::str::FromStr;
+use toml;
+use util::{Address, H256};
+
+pub const CONSENSUS: [&'static str; 3] = ["tendermint", "poa", "raft"];
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct ChainSpec {
+    /// Consensus of the nodes, "tendermint" by default, "poa" or "raft".
+    pub consensus: Option<String>,
+    pub chain_id: Option<u32>,
+    /// Seconds of the genesis block, the time of the generation if not set,
+    /// so the same genesis is generated again only if it is set.
+    pub timestamp: Option<u64>,
+    /// Block duration of the consensus in milliseconds, 3000 by default.
+    pub duration: Option<u64>,
+    /// Target block interval in milliseconds, kept by the system config contract.
+    pub block_interval: Option<u64>,
+    pub block_tx_limit: Option<u64>,
+    pub tx_filter_size: Option<u64>,
+    pub tx_pool_size: Option<u64>,
+    /// The nodes of the chain, all validators, in the order of their ids.
+    pub validators: Vec<ValidatorSpec>,
+    pub quota: Option<QuotaSpec>,
+    pub permissions: Option<PermissionSpec>,
+    pub jsonrpc: Option<JsonrpcSpec>,
+    /// Contracts deployed in the genesis.
+    pub contracts: Option<Vec<ContractSpec>>,
+}
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct ValidatorSpec {
+    /// IPv4 or IPv6 address, or a hostname.
+    pub ip: String,
+    pub port: u64,
+    /// Hex private key of the node, the key in the node directory or a new one if not set.
+    pub privkey: Option<String>,
+}
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct QuotaSpec {
+    pub block_quota_limit: Option<u64>,
+    pub account_quota_limit: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct PermissionSpec {
+    /// Accounts allowed to send transactions, anyone if not set.
+    pub senders: Option<Vec<String>>,
+    /// Accounts allowed to deploy contracts, anyone if not set.
+    pub creators: Option<Vec<String>>,
+    /// Accounts allowed to change the parameters of the chain, nobody if not set.
+    pub admins: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct JsonrpcSpec {
+    /// Serve http, true by default.
+    pub http: Option<bool>,
+    /// Port of the nodes, the next ports for the nodes on the same host, 1337 by default.
+    pub http_port: Option<u64>,
+    /// Serve websocket, false by default.
+    pub ws: Option<bool>,
+    /// Port of the nodes, the next ports for the nodes on the same host, 4337 by default.
+    pub ws_port: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, RustcDecodable)]
+pub struct ContractSpec {
+    pub address: String,
+    /// Hex runtime bytecode.
+    pub code: String,
+}
+
+fn strip(s: &str) -> &str {
+    if s.starts_with("0x") { &s[2..] } else { s }
+}
+
+pub fn parse_address(s: &str) -> Result<Address, String> {
+    Address::from_str(strip(s)).map_err(|_| format!("invalid address {}", s))
+}
+
+fn parse_addresses(list: &Option<Vec<String>>) -> Result<Vec<Address>, String> {
+    match *list {
+        Some(ref list) => list.iter().map(|s| parse_address(s)).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+impl ChainSpec {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut content = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut content)).map_err(|e| format!("can not read {}: {}", path, e))?;
+        ChainSpec::parse(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let spec: ChainSpec = toml::decode_str(content).ok_or("can not parse the spec".to_string())?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !CONSENSUS.contains(&self.consensus()) {
+            return Err(format!("unknown consensus {}", self.consensus()));
+        }
+        if self.validators.is_empty() {
+            return Err("no validators".to_string());
+        }
+        if let Some(ref permissions) = self.permissions {
+            parse_addresses(&permissions.senders)?;
+            parse_addresses(&permissions.creators)?;
+            parse_addresses(&permissions.admins)?;
+        }
+        for contract in self.contracts.iter().flat_map(|contracts| contracts.iter()) {
+            parse_address(&contract.address)?;
+            strip(&contract.code).from_hex().map_err(|_| format!("invalid code of {}", contract.address))?;
+        }
+        Ok(())
+    }
+
+    pub fn consensus(&self) -> &str {
+        self.consensus.as_ref().map(|s| s.as_str()).unwrap_or("tendermint")
+    }
+
+    /// The genesis of the validators of the addresses, which are also the nodes of the node manager.
+    pub fn genesis(&self, authorities: &[Address], timestamp: u64) -> Result<Spec, String> {
+        let mut builder = GenesisBuilder::new()
+            .prevhash(H256::zero())
+            .timestamp(timestamp)
+            .authorities(authorities.to_vec())
+            .nodes(authorities.to_vec())
+            .chain_id(self.chain_id.unwrap_or(0));
+        if let Some(interval) = self.block_interval {
+            builder = builder.block_interval(interval);
+        }
+        if let Some(ref quota) = self.quota {
+            if let Some(limit) = quota.block_quota_limit {
+                builder = builder.block_quota_limit(limit);
+            }
+            if let Some(limit) = quota.account_quota_limit {
+                builder = builder.account_quota_limit(limit);
+            }
+        }
+        if let Some(ref permissions) = self.permissions {
+            builder = builder.senders(parse_addresses(&permissions.senders)?)
+                             .creators(parse_addresses(&permissions.creators)?)
+                             .admins(parse_addresses(&permissions.admins)?);
+        }
+        for contract in self.contracts.iter().flat_map(|contracts| contracts.iter()) {
+            let code = strip(&contract.code).from_hex().map_err(|_| format!("invalid code of {}", contract.address))?;
+            builder = builder.contract(parse_address(&contract.address)?, code);
+        }
+        Ok(builder.build().spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genesis() {
+        let spec = ChainSpec::parse(r#"
+            chain_id = 7
+            timestamp = 1500000000
+
+            [[validators]]
+            ip = "127.0.0.1"
+            port = 4000
+
+            [[validators]]
+            ip = "::1"
+            port = 4001
+
+            [quota]
+            block_quota_limit = 1000
+
+            [permissions]
+            creators = ["0x0000000000000000000000000000000000000001"]
+            admins = ["0x0000000000000000000000000000000000000002"]
+
+            [[contracts]]
+            address = "0x0000000000000000000000000000000000000100"
+            code = "0x6000"
+        "#)
+                .unwrap();
+        assert_eq!(spec.consensus(), "tendermint");
+        assert_eq!(spec.validators[1].ip, "::1");
+
+        let authorities = vec![Address::from(10), Address::from(11)];
+        let genesis = spec.genesis(&authorities, spec.timestamp.unwrap()).unwrap();
+        let expected = GenesisBuilder::new()
+            .timestamp(1500000000)
+            .authorities(authorities.clone())
+            .nodes(authorities)
+            .chain_id(7)
+            .block_quota_limit(1000)
+            .creators(vec![Address::from(1)])
+            .admins(vec![Address::from(2)])
+            .contract(Address::from(0x100), vec![0x60, 0x00])
+            .build();
+        assert_eq!(genesis, expected.spec);
+
+        assert!(ChainSpec::parse("consensus = \"pow\"\n[[validators]]\nip = \"127.0.0.1\"\nport = 4000").is_err());
+        assert!(ChainSpec::parse("validators = []").is_err());
+        assert!(ChainSpec::parse("[[validators]]\nip = \"127.0.0.1\"\nport = 4000\n[permissions]\nsenders = [\"0x1\"]").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use ed25519;
+use rand::{OsRng, Rng};
+use secp256k1;
+use std::str::FromStr;
+use util::{Address, H256, H512};
+
+/// The signature schemes of the keys, ed25519 signs the transactions and the
+/// votes of the nodes, secp256k1 the accounts of the tools of ethereum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crypto {
+    Ed25519,
+    Secp256k1,
+}
+
+impl FromStr for Crypto {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ed25519" => Ok(Crypto::Ed25519),
+            "secp256k1" => Ok(Crypto::Secp256k1),
+            _ => Err(format!("unknown crypto {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    pub crypto: Crypto,
+    pub privkey: Vec<u8>,
+    pub pubkey: Vec<u8>,
+    pub address: Address,
+}
+
+impl Crypto {
+    /// Length of the private keys in bytes.
+    pub fn privkey_len(&self) -> usize {
+        match *self {
+            Crypto::Ed25519 => 64,
+            Crypto::Secp256k1 => 32,
+        }
+    }
+
+    pub fn generate(&self) -> Key {
+        match *self {
+            Crypto::Ed25519 => {
+                let keypair = ed25519::KeyPair::gen_keypair();
+                self.from_privkey(&keypair.privkey()[..]).unwrap()
+            }
+            Crypto::Secp256k1 => {
+                let mut rng = OsRng::new().expect("no random source");
+                // the few values out of the curve order are drawn again
+                loop {
+                    let mut privkey = [0u8; 32];
+                    rng.fill_bytes(&mut privkey);
+                    if let Ok(key) = self.from_privkey(&privkey) {
+                        return key;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn from_privkey(&self, privkey: &[u8]) -> Result<Key, String> {
+        if privkey.len() != self.privkey_len() {
+            return Err(format!("private key of {} bytes, {} expected", privkey.len(), self.privkey_len()));
+        }
+        let (pubkey, address) = match *self {
+            Crypto::Ed25519 => {
+                let keypair = ed25519::KeyPair::from_privkey(H512::from_slice(privkey)).map_err(|e| e.to_string())?;
+                (keypair.pubkey().to_vec(), keypair.address())
+            }
+            Crypto::Secp256k1 => {
+                let keypair = secp256k1::KeyPair::from_privkey(H256::from_slice(privkey)).map_err(|e| e.to_string())?;
+                (keypair.pubkey().to_vec(), keypair.address())
+            }
+        };
+        Ok(Key {
+               crypto: *self,
+               privkey: privkey.to_vec(),
+               pubkey: pubkey,
+               address: address,
+           })
+    }
+}
+
+impl Key {
+    /// Sign the hash of a message.
+    pub fn sign(&self, hash: &H256) -> Result<Vec<u8>, String> {
+        match self.crypto {
+            Crypto::Ed25519 => ed25519::sign(&H512::from_slice(&self.privkey), hash).map(|sig| sig.to_vec()).map_err(|e| e.to_string()),
+            Crypto::Secp256k1 => secp256k1::sign(&H256::from_slice(&self.privkey), hash).map(|sig| sig.to_vec()).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        for crypto in &[Crypto::Ed25519, Crypto::Secp256k1] {
+            let key = crypto.generate();
+            assert_eq!(key.privkey.len(), crypto.privkey_len());
+            assert_eq!(crypto.from_privkey(&key.privkey).unwrap(), key);
+            assert!(key.sign(&H256::from(1)).is_ok());
+            assert!(crypto.from_privkey(&[1, 2, 3]).is_err());
+        }
+        assert_eq!("secp256k1".parse(), Ok(Crypto::Secp256k1));
+        assert!("rsa".parse::<Crypto>().is_err());
+    }
+}
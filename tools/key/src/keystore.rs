@@ -0,0 +1,197 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keystores of the private keys encrypted by a password, in the layout of
+//! the version 3 keystores of ethereum: the key derived from the password
+//! by pbkdf2, the private key encrypted by aes-128-ctr, and the keccak of
+//! the derived key and the ciphertext as the mac checking the password.
+
+use crypto::{Crypto, Key};
+use rand::{OsRng, Rng};
+use rust_crypto::aes::{self, KeySize};
+use rust_crypto::digest::Digest;
+use rust_crypto::hmac::Hmac;
+use rust_crypto::pbkdf2::pbkdf2;
+use rust_crypto::sha2::Sha256;
+use rust_crypto::sha3::Sha3;
+use rust_crypto::symmetriccipher::SynchronousStreamCipher;
+use rustc_serialize::hex::{FromHex, ToHex};
+
+pub const KEYSTORE_VERSION: u32 = 3;
+// rounds of pbkdf2
+const ROUNDS: u32 = 262144;
+// rounds above it are taken as a broken keystore rather than spending minutes on them
+const MAX_ROUNDS: u32 = 16 * ROUNDS;
+const IV_LEN: usize = 16;
+const DKLEN: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KdfParams {
+    pub c: u32,
+    pub dklen: usize,
+    pub prf: String,
+    pub salt: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyCrypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keystore {
+    pub version: u32,
+    /// Signature scheme of the key, "ed25519" or "secp256k1".
+    pub scheme: String,
+    pub address: String,
+    pub crypto: KeyCrypto,
+}
+
+fn derive(password: &str, salt: &[u8], rounds: u32) -> Vec<u8> {
+    let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+    let mut derived = vec![0u8; DKLEN];
+    pbkdf2(&mut mac, salt, rounds, &mut derived);
+    derived
+}
+
+fn mac(derived: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha3::keccak256();
+    hasher.input(&derived[16..32]);
+    hasher.input(ciphertext);
+    let mut mac = vec![0u8; 32];
+    hasher.result(&mut mac);
+    mac
+}
+
+fn aes_ctr(key: &[u8], iv: &[u8], input: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; input.len()];
+    aes::ctr(KeySize::KeySize128, key, iv).process(input, &mut output);
+    output
+}
+
+fn scheme(crypto: Crypto) -> &'static str {
+    match crypto {
+        Crypto::Ed25519 => "ed25519",
+        Crypto::Secp256k1 => "secp256k1",
+    }
+}
+
+impl Keystore {
+    pub fn encrypt(key: &Key, password: &str) -> Self {
+        Keystore::encrypt_with(key, password, ROUNDS)
+    }
+
+    fn encrypt_with(key: &Key, password: &str, rounds: u32) -> Self {
+        let mut rng = OsRng::new().expect("no random source");
+        let mut salt = [0u8; 32];
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+        let derived = derive(password, &salt, rounds);
+        let ciphertext = aes_ctr(&derived[0..16], &iv, &key.privkey);
+        Keystore {
+            version: KEYSTORE_VERSION,
+            scheme: scheme(key.crypto).to_string(),
+            address: key.address.to_hex(),
+            crypto: KeyCrypto {
+                cipher: "aes-128-ctr".to_string(),
+                cipherparams: CipherParams { iv: iv.to_hex() },
+                ciphertext: ciphertext.to_hex(),
+                kdf: "pbkdf2".to_string(),
+                kdfparams: KdfParams {
+                    c: rounds,
+                    dklen: DKLEN,
+                    prf: "hmac-sha256".to_string(),
+                    salt: salt.to_hex(),
+                },
+                mac: mac(&derived, &ciphertext).to_hex(),
+            },
+        }
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<Key, String> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("keystore version {} is not supported", self.version));
+        }
+        let params = &self.crypto.kdfparams;
+        if self.crypto.cipher != "aes-128-ctr" || self.crypto.kdf != "pbkdf2" || params.prf != "hmac-sha256" || params.dklen != DKLEN {
+            return Err("keystore cipher is not supported".to_string());
+        }
+        if params.c == 0 || params.c > MAX_ROUNDS {
+            return Err(format!("keystore kdf rounds {} out of range", params.c));
+        }
+        let crypto: Crypto = self.scheme.parse()?;
+        let hex = |s: &str| s.from_hex().map_err(|e| e.to_string());
+        let salt = hex(&params.salt)?;
+        let iv = hex(&self.crypto.cipherparams.iv)?;
+        if iv.len() != IV_LEN {
+            return Err(format!("keystore iv of {} bytes, expected {}", iv.len(), IV_LEN));
+        }
+        let ciphertext = hex(&self.crypto.ciphertext)?;
+        let derived = derive(password, &salt, params.c);
+        if mac(&derived, &ciphertext).to_hex() != self.crypto.mac {
+            return Err("wrong password".to_string());
+        }
+        crypto.from_privkey(&aes_ctr(&derived[0..16], &iv, &ciphertext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let key = Crypto::Ed25519.generate();
+        let keystore = Keystore::encrypt_with(&key, "secret", 16);
+        assert_eq!(keystore.address, key.address.to_hex());
+        assert_eq!(keystore.decrypt("secret").unwrap(), key);
+        assert_eq!(keystore.decrypt("wrong"), Err("wrong password".to_string()));
+
+        let key = Crypto::Secp256k1.generate();
+        let keystore = Keystore::encrypt_with(&key, "", 16);
+        assert_eq!(keystore.decrypt("").unwrap(), key);
+    }
+
+    #[test]
+    fn reject_broken_params() {
+        let key = Crypto::Ed25519.generate();
+        let keystore = Keystore::encrypt_with(&key, "secret", 16);
+
+        let mut broken = keystore.clone();
+        broken.crypto.cipherparams.iv = "0011".to_string();
+        assert!(broken.decrypt("secret").is_err());
+
+        let mut broken = keystore.clone();
+        broken.crypto.kdfparams.c = 0;
+        assert!(broken.decrypt("secret").is_err());
+
+        let mut broken = keystore.clone();
+        broken.crypto.kdfparams.c = u32::max_value();
+        assert!(broken.decrypt("secret").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keys of the nodes and the accounts: generates the keypairs and derives
+//! their addresses, exports and imports the keystores encrypted by a
+//! password, and signs the messages. The private keys are kept in files as
+//! hex, as the nodes read them.
+
+extern crate clap;
+extern crate crypto as rust_crypto;
+extern crate rand;
+extern crate rustc_serialize;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate util;
+extern crate cita_ed25519 as ed25519;
+extern crate cita_secp256k1 as secp256k1;
+
+mod crypto;
+mod keystore;
+
+use clap::{App, ArgMatches, SubCommand};
+use crypto::{Crypto, Key};
+use keystore::Keystore;
+use rustc_serialize::hex::{FromHex, ToHex};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Read, Write};
+use std::process;
+use util::Hashable;
+
+fn read_privkey(path: &str, crypto: Crypto) -> Result<Key, String> {
+    let mut content = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut content)).map_err(|e| format!("{}: {}", path, e))?;
+    let content = content.trim();
+    let hex = if content.starts_with("0x") { &content[2..] } else { content };
+    let privkey = hex.from_hex().map_err(|e| format!("{}: {}", path, e))?;
+    crypto.from_privkey(&privkey)
+}
+
+fn write_file(path: &str, content: &str) -> Result<(), String> {
+    File::create(path).and_then(|mut file| file.write_all(content.as_bytes())).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// The password in the first line of the file, or of the standard input.
+fn read_password(matches: &ArgMatches) -> Result<String, String> {
+    let mut line = String::new();
+    match matches.value_of("password") {
+        Some(path) => {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            io::BufReader::new(file).read_line(&mut line).map_err(|e| e.to_string())?;
+        }
+        None => {
+            let _ = write!(io::stderr(), "password: ");
+            io::stdin().read_line(&mut line).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(line.trim_right_matches(|c| c == '\r' || c == '\n').to_string())
+}
+
+fn print_key(key: &Key, privkey: bool) {
+    if privkey {
+        println!("privkey: {}", key.privkey.to_hex());
+    }
+    println!("pubkey:  {}", key.pubkey.to_hex());
+    println!("address: 0x{}", key.address.to_hex());
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let crypto: Crypto = matches.value_of("crypto").unwrap_or("ed25519").parse()?;
+    match matches.subcommand() {
+        ("generate", Some(args)) => {
+            let key = crypto.generate();
+            if let Some(path) = args.value_of("output") {
+                write_file(path, &key.privkey.to_hex())?;
+            }
+            if let Some(path) = args.value_of("authorities") {
+                OpenOptions::new().create(true).append(true).open(path).and_then(|mut file| writeln!(file, "0x{}", key.address.to_hex())).map_err(|e| format!("{}: {}", path, e))?;
+            }
+            print_key(&key, !args.is_present("output"));
+        }
+        ("address", Some(args)) => {
+            let key = read_privkey(args.value_of("KEY").unwrap(), crypto)?;
+            print_key(&key, false);
+        }
+        ("export", Some(args)) => {
+            let key = read_privkey(args.value_of("KEY").unwrap(), crypto)?;
+            let keystore = Keystore::encrypt(&key, &read_password(args)?);
+            write_file(args.value_of("KEYSTORE").unwrap(), &serde_json::to_string_pretty(&keystore).unwrap())?;
+            println!("address: 0x{}", key.address.to_hex());
+        }
+        ("import", Some(args)) => {
+            let path = args.value_of("KEYSTORE").unwrap();
+            let keystore: Keystore = File::open(path).map_err(|e| e.to_string()).and_then(|file| serde_json::from_reader(file).map_err(|e| e.to_string())).map_err(|e| format!("{}: {}", path, e))?;
+            let key = keystore.decrypt(&read_password(args)?)?;
+            write_file(args.value_of("KEY").unwrap(), &key.privkey.to_hex())?;
+            println!("address: 0x{}", key.address.to_hex());
+        }
+        ("sign", Some(args)) => {
+            let key = read_privkey(args.value_of("KEY").unwrap(), crypto)?;
+            let message = args.value_of("MESSAGE").unwrap();
+            let bytes = if args.is_present("hex") {
+                message.trim_left_matches("0x").from_hex().map_err(|e| e.to_string())?
+            } else {
+                message.as_bytes().to_vec()
+            };
+            let hash = bytes.crypt_hash();
+            println!("hash:      0x{}", hash.to_hex());
+            println!("signature: 0x{}", key.sign(&hash)?.to_hex());
+        }
+        _ => return Err(matches.usage().to_string()),
+    }
+    Ok(())
+}
+
+fn main() {
+    let matches = App::new("cita-key")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Keys of the CITA nodes and accounts")
+        .args_from_usage("-c, --crypto=[CRYPTO] 'Sets the signature scheme, ed25519 by default or secp256k1'")
+        .subcommand(SubCommand::with_name("generate")
+                        .about("Generates a keypair")
+                        .args_from_usage("-o, --output=[FILE] 'Writes the private key into the file'
+                                          -a, --authorities=[FILE] 'Appends the address to the file of the authorities'"))
+        .subcommand(SubCommand::with_name("address")
+                        .about("Derives the public key and the address of a private key")
+                        .args_from_usage("<KEY> 'Sets the file of the private key'"))
+        .subcommand(SubCommand::with_name("export")
+                        .about("Exports a private key into a keystore encrypted by a password")
+                        .args_from_usage("<KEY> 'Sets the file of the private key'
+                                          <KEYSTORE> 'Sets the keystore file to write'
+                                          -p, --password=[FILE] 'Reads the password from the file instead of the input'"))
+        .subcommand(SubCommand::with_name("import")
+                        .about("Imports the private key of a keystore encrypted by a password")
+                        .args_from_usage("<KEYSTORE> 'Sets the keystore file to read'
+                                          <KEY> 'Sets the file of the private key to write'
+                                          -p, --password=[FILE] 'Reads the password from the file instead of the input'"))
+        .subcommand(SubCommand::with_name("sign")
+                        .about("Signs the hash of a message")
+                        .args_from_usage("<KEY> 'Sets the file of the private key'
+                                          <MESSAGE> 'Sets the message'
+                                          --hex 'Reads the message as hex'"))
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        let _ = writeln!(io::stderr(), "{}", e);
+        process::exit(1);
+    }
+}
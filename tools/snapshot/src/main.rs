@@ -0,0 +1,137 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshots of a node taken offline, with the node stopped: creates the
+//! snapshot of a height from the database of the node, verifies the chunks
+//! and the proof of a snapshot, and restores the database of an empty node
+//! from a snapshot. The node directory is `DATA_PATH`, as for the chain.
+
+extern crate clap;
+extern crate core;
+extern crate dotenv;
+extern crate log;
+extern crate cita_log;
+extern crate util;
+
+use clap::{App, ArgMatches, SubCommand};
+use core::libchain::{Config, Genesis};
+use core::libchain::chain::Chain;
+use core::libchain::snapshot::{self, ManifestData};
+use log::LogLevelFilter;
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+use util::KeyValueDB;
+
+/// The config of the chain and the database of the node.
+fn open(matches: &ArgMatches) -> Result<(Config, Arc<KeyValueDB>), String> {
+    let data_path = match matches.value_of("data") {
+        Some(path) => path.to_string(),
+        None => env::var("DATA_PATH").map_err(|_| "DATA_PATH must be set".to_string())?,
+    };
+    let mut config = match matches.value_of("pruning") {
+        Some(path) => Config::init(path),
+        None => Config::default(),
+    };
+    if config.ancient_path.is_none() {
+        config.ancient_path = Some(data_path.clone() + "/ancient");
+    }
+    let db = config.database.open(&(data_path + "/nosql"))?;
+    Ok((config, db))
+}
+
+fn init_chain(matches: &ArgMatches, config: &Config, db: Arc<KeyValueDB>) -> Arc<Chain> {
+    let genesis = Genesis::init(matches.value_of("config").unwrap_or("genesis.json"));
+    let (sync_tx, _) = channel();
+    Chain::init_chain(db, genesis, config, sync_tx).0
+}
+
+fn print_manifest(manifest: &ManifestData) {
+    println!("height:       {}", manifest.height);
+    println!("block hash:   {:?}", manifest.block_hash);
+    println!("state root:   {:?}", manifest.state_root);
+    println!("state chunks: {}", manifest.state_hashes.len());
+    println!("block chunks: {}", manifest.block_hashes.len());
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        ("create", Some(args)) => {
+            let (config, db) = open(args)?;
+            let chain = init_chain(args, &config, db);
+            let height = match args.value_of("height") {
+                Some(height) => height.parse().map_err(|_| format!("invalid height {}", height))?,
+                None => chain.get_current_height(),
+            };
+            let manifest = snapshot::take_snapshot_at(&chain, height, Path::new(args.value_of("DIR").unwrap())).map_err(|e| e.to_string())?;
+            print_manifest(&manifest);
+        }
+        ("verify", Some(args)) => {
+            let manifest = snapshot::verify_snapshot(Path::new(args.value_of("DIR").unwrap())).map_err(|e| e.to_string())?;
+            print_manifest(&manifest);
+            println!("verified");
+        }
+        ("restore", Some(args)) => {
+            let dir = Path::new(args.value_of("DIR").unwrap());
+            if !args.is_present("no-verify") {
+                snapshot::verify_snapshot(dir).map_err(|e| e.to_string())?;
+            }
+            let (config, db) = open(args)?;
+            let manifest = snapshot::restore(db.clone(), config.algorithm(), dir).map_err(|e| e.to_string())?;
+            // the chain picks up the restored blocks, then the blooms of their logs are rebuilt
+            let chain = init_chain(args, &config, db);
+            chain.rebuild_blooms(manifest.height.saturating_sub(snapshot::SNAPSHOT_BLOCKS), manifest.height);
+            print_manifest(&manifest);
+        }
+        _ => return Err(matches.usage().to_string()),
+    }
+    Ok(())
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    cita_log::format(LogLevelFilter::Info);
+    let node = "-d, --data=[DIR] 'Sets the node directory, DATA_PATH by default'
+                -c, --config=[FILE] 'Sets the genesis of the chain, genesis.json by default'
+                -p, --pruning=[FILE] 'Sets the config file of state pruning and the database'";
+    let matches = App::new("cita-snapshot")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Snapshots of a stopped CITA node")
+        .subcommand(SubCommand::with_name("create")
+                        .about("Takes a snapshot of the chain into the directory")
+                        .args_from_usage("<DIR> 'Sets the directory of the snapshot'
+                                          --height=[HEIGHT] 'Sets the height of the snapshot, the current one by default'")
+                        .args_from_usage(node))
+        .subcommand(SubCommand::with_name("verify")
+                        .about("Verifies the chunks and the proof of the snapshot in the directory")
+                        .args_from_usage("<DIR> 'Sets the directory of the snapshot'"))
+        .subcommand(SubCommand::with_name("restore")
+                        .about("Restores the empty node from the snapshot in the directory")
+                        .args_from_usage("<DIR> 'Sets the directory of the snapshot'
+                                          --no-verify 'Restores without verifying the snapshot in memory first'")
+                        .args_from_usage(node))
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        let _ = writeln!(io::stderr(), "{}", e);
+        process::exit(1);
+    }
+}
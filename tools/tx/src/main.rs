@@ -0,0 +1,197 @@
+// CITA
+// Copyright 2016-2017 Cryptape Technologies LLC.
+
+// This program is free software: you can redistribute it
+// and/or modify it under the terms of the GNU General Public
+// License as published by the Free Software Foundation,
+// either version 3 of the License, or (at your option) any
+// later version.
+
+// This program is distributed in the hope that it will be
+// useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR
+// PURPOSE. See the GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transactions built and signed offline, e.g. on an air-gapped machine.
+//! The signed transaction is printed as the hex taken by `cita_sendTransaction`.
+//! It is signed by a private key file, or by a command given the hash to sign,
+//! which prints the signature, e.g. the client of a hardware key.
+//!
+//! The transactions carry no quota of their own, the quota they use is bounded
+//! by the limits of the block and of the account kept by the quota manager,
+//! only their quota price is signed.
+
+extern crate clap;
+extern crate protobuf;
+extern crate rustc_serialize;
+extern crate libproto;
+extern crate util;
+extern crate cita_ed25519 as ed25519;
+
+use clap::{App, ArgMatches};
+use ed25519::{PrivKey, SIGNATURE_BYTES_LEN};
+use libproto::TRANSACTION_VERSION;
+use libproto::blockchain::{Crypto, SignedTransaction, Transaction, UnverifiedTransaction};
+use protobuf::Message;
+use rustc_serialize::hex::{FromHex, ToHex};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::{self, Command};
+use std::str::FromStr;
+use util::{Address, H256, Hashable, U256};
+
+fn clean_0x(s: &str) -> &str {
+    let s = s.trim();
+    if s.starts_with("0x") { &s[2..] } else { s }
+}
+
+fn parse<T: FromStr>(matches: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match matches.value_of(name) {
+        Some(value) => value.parse().map(Some).map_err(|_| format!("invalid {} {}", name, value)),
+        None => Ok(None),
+    }
+}
+
+/// The transaction of the arguments, one without a receiver deploys the contract of the data.
+fn build(matches: &ArgMatches) -> Result<Transaction, String> {
+    let mut tx = Transaction::new();
+    if let Some(to) = matches.value_of("to") {
+        let to = clean_0x(to);
+        Address::from_str(to).map_err(|_| format!("invalid receiver {}", to))?;
+        tx.set_to(to.to_string());
+    }
+    if let Some(data) = matches.value_of("data") {
+        tx.set_data(clean_0x(data).from_hex().map_err(|_| "invalid data".to_string())?);
+    }
+    let nonce = matches.value_of("nonce").unwrap();
+    U256::from_str(clean_0x(nonce)).map_err(|_| format!("invalid nonce {}", nonce))?;
+    tx.set_nonce(clean_0x(nonce).to_string());
+    tx.set_valid_until_block(parse(matches, "valid-until-block")?.unwrap());
+    tx.set_chain_id(parse(matches, "chain-id")?.unwrap_or(0));
+    tx.set_quota_price(parse(matches, "quota-price")?.unwrap_or(0));
+    tx.set_version(TRANSACTION_VERSION);
+    Ok(tx)
+}
+
+/// The hash of the transaction the sender signs.
+fn hash(tx: &Transaction) -> H256 {
+    tx.write_to_bytes().unwrap().crypt_hash()
+}
+
+fn sign_with_key(tx: Transaction, privkey: PrivKey) -> SignedTransaction {
+    let mut utx = UnverifiedTransaction::new();
+    utx.set_transaction(tx);
+    let mut signed = SignedTransaction::new();
+    signed.set_transaction_with_sig(utx);
+    signed.sign(privkey);
+    signed
+}
+
+/// The transaction signed by the signature made elsewhere, checked by recovering its signer.
+fn with_signature(tx: Transaction, signature: Vec<u8>) -> Result<SignedTransaction, String> {
+    if signature.len() != SIGNATURE_BYTES_LEN {
+        return Err(format!("signature of {} bytes, {} expected", signature.len(), SIGNATURE_BYTES_LEN));
+    }
+    let mut utx = UnverifiedTransaction::new();
+    utx.set_transaction(tx);
+    utx.set_signature(signature);
+    utx.set_crypto(Crypto::SECP);
+    let mut signed = SignedTransaction::new();
+    signed.set_transaction_with_sig(utx);
+    if !signed.recover() {
+        return Err("invalid signature".to_string());
+    }
+    Ok(signed)
+}
+
+/// Run the command with the hex hash as its last argument, for the hex signature it prints.
+fn sign_by_command(command: &str, hash: &H256) -> Result<Vec<u8>, String> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or("empty signer command".to_string())?;
+    let output = Command::new(program).args(&words.collect::<Vec<_>>()).arg(hash.to_hex()).output().map_err(|e| format!("{}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    clean_0x(&String::from_utf8_lossy(&output.stdout)).from_hex().map_err(|_| format!("{} printed no hex signature", program))
+}
+
+fn read_privkey(path: &str) -> Result<PrivKey, String> {
+    let mut content = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut content)).map_err(|e| format!("{}: {}", path, e))?;
+    PrivKey::from_str(clean_0x(&content)).map_err(|_| format!("{}: invalid private key", path))
+}
+
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let tx = build(matches)?;
+    let hash = hash(&tx);
+    let signed = if let Some(path) = matches.value_of("privkey") {
+        sign_with_key(tx, read_privkey(path)?)
+    } else if let Some(command) = matches.value_of("signer") {
+        let signature = sign_by_command(command, &hash)?;
+        with_signature(tx, signature)?
+    } else if let Some(signature) = matches.value_of("signature") {
+        with_signature(tx, clean_0x(signature).from_hex().map_err(|_| "invalid signature".to_string())?)?
+    } else {
+        // the hash is signed elsewhere, then given back by --signature
+        println!("0x{}", hash.to_hex());
+        return Ok(());
+    };
+    let _ = writeln!(io::stderr(), "tx hash: 0x{}", signed.get_tx_hash().to_hex());
+    println!("0x{}", signed.write_to_bytes().unwrap().to_hex());
+    Ok(())
+}
+
+fn main() {
+    let matches = App::new("cita-tx")
+        .version("0.1")
+        .author("Cryptape")
+        .about("Builds and signs a transaction offline, printing its hex for cita_sendTransaction")
+        .args_from_usage("--to=[ADDRESS] 'Sets the receiver, the contract of the data is deployed if not set'
+                          --data=[HEX] 'Sets the data, the call of the contract or its code'
+                          --nonce=<NONCE> 'Sets the hex nonce of the sender'
+                          --valid-until-block=<HEIGHT> 'Sets the last height the transaction may be packaged at'
+                          --chain-id=[ID] 'Sets the id of the chain, 0 by default'
+                          --quota-price=[PRICE] 'Sets the quota price, 0 by default'
+                          -k, --privkey=[FILE] 'Signs by the private key in the file'
+                          --signer=[COMMAND] 'Signs by the command given the hex hash, printing the hex signature'
+                          --signature=[HEX] 'Attaches the signature of the hash made elsewhere'")
+        .after_help("Without a key, a signer or a signature, the hash to sign is printed.")
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        let _ = writeln!(io::stderr(), "{}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519::{sign, KeyPair};
+
+    #[test]
+    fn sign_offline() {
+        let keypair = KeyPair::gen_keypair();
+        let mut tx = Transaction::new();
+        tx.set_to("0000000000000000000000000000000000000100".to_string());
+        tx.set_nonce("1".to_string());
+        tx.set_valid_until_block(100);
+        tx.set_chain_id(7);
+
+        let signed = sign_with_key(tx.clone(), *keypair.privkey());
+        let signature = sign(keypair.privkey(), &hash(&tx)).unwrap();
+        let attached = with_signature(tx.clone(), signature.to_vec()).unwrap();
+        assert_eq!(attached.get_tx_hash(), signed.get_tx_hash());
+        assert_eq!(attached.get_signer(), &keypair.pubkey()[..]);
+
+        // the signature of the transaction for another chain does not sign it
+        let mut other = tx.clone();
+        other.set_chain_id(8);
+        let replayed = sign(keypair.privkey(), &hash(&other)).unwrap();
+        assert!(with_signature(tx, replayed.to_vec()).is_err());
+        assert!(with_signature(Transaction::new(), vec![1, 2, 3]).is_err());
+    }
+}